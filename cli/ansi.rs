@@ -1,10 +1,16 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! Whether output is colored is decided here, for everything deno prints and
+//! for the runtime, see `Deno.noColor`.
+use crate::flags::ColorMode;
 use ansi_term::Color::Fixed;
+use ansi_term::Color::Green;
 use ansi_term::Color::Red;
 use ansi_term::Style;
+use atty;
 use regex::Regex;
 use std::env;
 use std::fmt;
+use std::sync::Mutex;
 
 lazy_static! {
   // STRIP_ANSI_RE and strip_ansi_codes are lifted from the "console" crate.
@@ -15,6 +21,7 @@ lazy_static! {
   static ref NO_COLOR: bool = {
     env::var_os("NO_COLOR").is_some()
   };
+  static ref COLOR_MODE: Mutex<ColorMode> = Mutex::new(ColorMode::Auto);
 }
 
 /// Helper function to strip ansi codes.
@@ -23,8 +30,25 @@ pub fn strip_ansi_codes(s: &str) -> std::borrow::Cow<str> {
   STRIP_ANSI_RE.replace_all(s, "")
 }
 
+/// Sets the mode of `--color`, before anything is printed.
+pub fn set_color_mode(mode: ColorMode) {
+  *COLOR_MODE.lock().unwrap() = mode;
+}
+
+/// Whether output to `stream` is colored. In auto mode, the default, it is
+/// when `stream` is a terminal and NO_COLOR is not set.
+pub fn use_color_on(stream: atty::Stream) -> bool {
+  match *COLOR_MODE.lock().unwrap() {
+    ColorMode::Always => true,
+    ColorMode::Never => false,
+    ColorMode::Auto => !(*NO_COLOR) && atty::is(stream),
+  }
+}
+
+/// Whether output to stderr, where errors, prompts and progress go, is
+/// colored. The helpers below style for stderr.
 pub fn use_color() -> bool {
-  !(*NO_COLOR)
+  use_color_on(atty::Stream::Stderr)
 }
 
 pub fn red_bold(s: String) -> impl fmt::Display {
@@ -61,6 +85,14 @@ pub fn cyan(s: String) -> impl fmt::Display {
   style.paint(s)
 }
 
+pub fn green_bold(s: String) -> impl fmt::Display {
+  let mut style = Style::new();
+  if use_color() {
+    style = style.bold().fg(Green);
+  }
+  style.paint(s)
+}
+
 pub fn bold(s: String) -> impl fmt::Display {
   let mut style = Style::new();
   if use_color() {
@@ -68,3 +100,12 @@ pub fn bold(s: String) -> impl fmt::Display {
   }
   style.paint(s)
 }
+
+/// Like `bold()`, for output to stdout.
+pub fn bold_stdout(s: String) -> impl fmt::Display {
+  let mut style = Style::new();
+  if use_color_on(atty::Stream::Stdout) {
+    style = style.bold();
+  }
+  style.paint(s)
+}
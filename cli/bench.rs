@@ -0,0 +1,109 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! `deno bench` finds the bench files and runs them in this process, as the
+//! imports of a main module which then runs the benchmarks they registered
+//! with `Deno.bench()`. Only the first run of a bench file compiles it, and
+//! the benchmarks don't measure the startup of a deno for each file.
+use crate::flags::DenoFlags;
+use crate::fmt;
+use crate::test_runner;
+use std::path::Path;
+use std::path::PathBuf;
+
+const EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx"];
+
+/// Whether `path` is a bench file, e.g. `url_bench.ts`.
+pub fn is_bench_file(path: &Path) -> bool {
+  let has_extension = path
+    .extension()
+    .map_or(false, |ext| EXTENSIONS.iter().any(|e| ext == *e));
+  has_extension
+    && path
+      .file_stem()
+      .map_or(false, |stem| stem.to_string_lossy().ends_with("_bench"))
+}
+
+/// Returns the bench files for the `paths` given on the command line. Files
+/// are taken as they are, directories are searched like by `deno fmt`.
+pub fn collect_files<F: FnMut(String)>(
+  paths: &[PathBuf],
+  warn: F,
+) -> Vec<PathBuf> {
+  fmt::collect_files_matching(paths, &[], is_bench_file, warn)
+}
+
+/// The source of the main module, a script which imports the `files`, which
+/// must be absolute, in order and then runs the benchmarks. It exits with 1
+/// if a benchmark failed.
+pub fn main_source(files: &[PathBuf], flags: &DenoFlags) -> String {
+  let options = json!({
+    "filter": flags.bench_filter,
+    "iterations": flags.bench_iterations,
+    "warmup": flags.bench_warmup,
+    "json": flags.bench_json,
+  });
+  let run = format!("Deno.runBenchmarks({})", options);
+  test_runner::runner_source(files, &run, false)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_is_bench_file() {
+    assert!(is_bench_file(Path::new("url_bench.ts")));
+    assert!(is_bench_file(Path::new("bench/parse_bench.jsx")));
+    assert!(!is_bench_file(Path::new("url.ts")));
+    assert!(!is_bench_file(Path::new("url_bench.json")));
+    assert!(!is_bench_file(Path::new("bench.ts")));
+    assert!(!is_bench_file(Path::new("url_bench")));
+  }
+
+  #[test]
+  fn test_collect_files() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("sub")).unwrap();
+    std::fs::create_dir_all(root.join("node_modules")).unwrap();
+    for file in &[
+      "a_bench.ts",
+      "a.ts",
+      "sub/b_bench.js",
+      "node_modules/c_bench.ts",
+    ] {
+      std::fs::write(root.join(file), "").unwrap();
+    }
+    let files = collect_files(&[root.to_path_buf()], |w| panic!("{}", w));
+    assert_eq!(
+      files,
+      vec![root.join("a_bench.ts"), root.join("sub/b_bench.js")]
+    );
+    // Files given are run whatever their name.
+    let files = collect_files(&[root.join("a.ts")], |w| panic!("{}", w));
+    assert_eq!(files, vec![root.join("a.ts")]);
+  }
+
+  #[test]
+  fn test_main_source() {
+    let flags = DenoFlags {
+      bench_filter: Some("^parse".to_string()),
+      bench_iterations: Some(5),
+      ..DenoFlags::default()
+    };
+    let file = if cfg!(windows) {
+      PathBuf::from("C:\\bench\\url_bench.ts")
+    } else {
+      PathBuf::from("/bench/url_bench.ts")
+    };
+    let source = main_source(&[file], &flags);
+    let url = if cfg!(windows) {
+      "file:///C:/bench/url_bench.ts"
+    } else {
+      "file:///bench/url_bench.ts"
+    };
+    assert!(source.starts_with(&format!("import \"{}\";\n", url)));
+    assert!(source.contains("\"filter\":\"^parse\""));
+    assert!(source.contains("\"iterations\":5"));
+    assert!(source.contains("\"warmup\":null"));
+  }
+}
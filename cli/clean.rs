@@ -0,0 +1,233 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! `deno clean` removes cached remote modules and compiled output from the
+//! DenoDir, either everything or only the modules under a URL prefix.
+use crate::deno_dir;
+use crate::deno_dir::DenoDir;
+use crate::fs as deno_fs;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+const HEADERS_SUFFIX: &str = ".headers.json";
+
+/// The files `clean()` removed, or would remove with `dry_run`.
+#[derive(Debug, Default)]
+pub struct Removed {
+  pub files: Vec<PathBuf>,
+  pub bytes: u64,
+}
+
+impl Removed {
+  fn add(&mut self, path: PathBuf, size: u64) {
+    self.files.push(path);
+    self.bytes += size;
+  }
+}
+
+/// Removes the `deps/` and `gen/` caches, or with `prefix` the cached modules
+/// whose URL starts with it, together with their headers and compiled output.
+/// Nothing outside the root of `dir` is removed: symlinks are removed
+/// themselves instead of being followed, and directories which resolve to
+/// somewhere else are an error.
+pub fn clean(
+  dir: &DenoDir,
+  prefix: Option<&str>,
+  dry_run: bool,
+) -> io::Result<Removed> {
+  let root = fs::canonicalize(&dir.root)?;
+  let mut deps = Tree::default();
+  deps.collect(&root, &dir.deps_http)?;
+  deps.collect(&root, &dir.deps_https)?;
+  let mut gen = Tree::default();
+  gen.collect(&root, &dir.gen)?;
+
+  let mut removed = Removed::default();
+  match prefix {
+    None => {
+      for (path, size) in deps.files.into_iter().chain(gen.files) {
+        removed.add(path, size);
+      }
+    }
+    Some(prefix) => {
+      for (path, size) in deps.files {
+        let filename = deno_fs::normalize_path(&path);
+        let is_headers = filename.ends_with(HEADERS_SUFFIX);
+        let module_filename = if is_headers {
+          &filename[..filename.len() - HEADERS_SUFFIX.len()]
+        } else {
+          &filename
+        };
+        if !dir.src_file_to_url(module_filename).starts_with(prefix) {
+          continue;
+        }
+        if !is_headers {
+          for compiled in compiled_files(dir, &filename)? {
+            let size = gen.files.iter().find(|f| f.0 == compiled).map(|f| f.1);
+            if let Some(size) = size {
+              removed.add(compiled, size);
+            }
+          }
+        }
+        removed.add(path, size);
+      }
+    }
+  }
+
+  if !dry_run {
+    for path in &removed.files {
+      fs::remove_file(path)?;
+    }
+    // Only directories which are empty now are removed, the top level ones
+    // are kept.
+    for path in deps.dirs.iter().chain(gen.dirs.iter()) {
+      fs::remove_dir(path).ok();
+    }
+  }
+  Ok(removed)
+}
+
+/// The bytes taken up by the `deps/` and `gen/` caches of `dir`.
+pub fn cache_size(dir: &DenoDir) -> io::Result<u64> {
+  let root = fs::canonicalize(&dir.root)?;
+  let mut tree = Tree::default();
+  tree.collect(&root, &dir.deps)?;
+  tree.collect(&root, &dir.gen)?;
+  Ok(tree.files.iter().map(|f| f.1).sum())
+}
+
+/// The compiled JS and source map of the cached module at `filename`, see
+/// DenoDir::cache_path().
+fn compiled_files(dir: &DenoDir, filename: &str) -> io::Result<Vec<PathBuf>> {
+  let mut source_code = fs::read(filename)?;
+  // Compiled output is keyed by the source without its shebang.
+  if source_code.starts_with(b"#!") {
+    source_code = deno_dir::filter_shebang(source_code);
+  }
+  let (js, map) = dir.cache_path(filename, &source_code);
+  Ok(vec![js, map])
+}
+
+/// The files and the directories below a cache directory, children before
+/// their parents.
+#[derive(Default)]
+struct Tree {
+  files: Vec<(PathBuf, u64)>,
+  dirs: Vec<PathBuf>,
+}
+
+impl Tree {
+  fn collect(&mut self, root: &Path, dir: &Path) -> io::Result<()> {
+    let canonical = fs::canonicalize(dir)?;
+    if !canonical.starts_with(root) {
+      return Err(io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!(
+          "Refusing to clean \"{}\", it is outside of \"{}\"",
+          canonical.display(),
+          root.display()
+        ),
+      ));
+    }
+    for entry in fs::read_dir(dir)? {
+      let entry = entry?;
+      let path = entry.path();
+      // Does not follow symlinks.
+      let metadata = fs::symlink_metadata(&path)?;
+      if metadata.is_dir() {
+        self.collect(root, &path)?;
+        self.dirs.push(path);
+      } else {
+        self.files.push((path, metadata.len()));
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  fn write(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+  }
+
+  #[test]
+  fn test_clean_prefix() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let dir = DenoDir::new(Some(temp_dir.path().to_path_buf()), false).unwrap();
+    let a = dir.deps_https.join("deno.land/std/a.ts");
+    let a_headers = dir.deps_https.join("deno.land/std/a.ts.headers.json");
+    let b = dir.deps_http.join("localhost_PORT4545/b.ts");
+    write(&a, "#!/usr/bin/env deno\nexport {};\n");
+    write(&a_headers, "{}");
+    write(&b, "export {};\n");
+    let (a_js, a_map) =
+      dir.cache_path(&deno_fs::normalize_path(&a), b"\nexport {};\n");
+    write(&a_js, "");
+    write(&a_map, "");
+
+    let removed = clean(&dir, Some("https://deno.land/std/"), true).unwrap();
+    assert_eq!(removed.files.len(), 4);
+    assert!(a.exists());
+
+    let removed = clean(&dir, Some("https://deno.land/std/"), false).unwrap();
+    assert_eq!(removed.files.len(), 4);
+    assert!(!a.exists() && !a_headers.exists() && !a_js.exists());
+    assert!(!dir.deps_https.join("deno.land").exists());
+    assert!(b.exists());
+
+    let removed = clean(&dir, Some("http://localhost:4545/b"), false).unwrap();
+    assert_eq!(removed.files, vec![b.clone()]);
+    assert_eq!(removed.bytes, 11);
+  }
+
+  #[test]
+  fn test_clean_all() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let dir = DenoDir::new(Some(temp_dir.path().join("deno")), false).unwrap();
+    write(&dir.deps_https.join("deno.land/a.ts"), "");
+    write(&dir.gen.join("0123.js"), "");
+
+    let removed = clean(&dir, None, false).unwrap();
+    assert_eq!(removed.files.len(), 2);
+    assert!(!dir.deps_https.join("deno.land").exists());
+    assert!(dir.deps_https.exists() && dir.gen.exists());
+  }
+
+  #[test]
+  fn test_cache_size() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let dir = DenoDir::new(Some(temp_dir.path().to_path_buf()), false).unwrap();
+    assert_eq!(cache_size(&dir).unwrap(), 0);
+    write(&dir.deps_https.join("deno.land/a.ts"), "export {};\n");
+    write(&dir.gen.join("0123.js"), "");
+    write(&dir.gen.join("0123.js.map"), "{}");
+    assert_eq!(cache_size(&dir).unwrap(), 13);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_clean_symlinks() {
+    use std::os::unix::fs::symlink;
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let outside = temp_dir.path().join("outside");
+    write(&outside.join("a.ts"), "");
+    let dir = DenoDir::new(Some(temp_dir.path().join("deno")), false).unwrap();
+
+    // Only the link is removed.
+    symlink(&outside, dir.deps_https.join("deno.land")).unwrap();
+    let removed = clean(&dir, None, false).unwrap();
+    assert_eq!(removed.files, vec![dir.deps_https.join("deno.land")]);
+    assert!(outside.join("a.ts").exists());
+
+    fs::remove_dir(&dir.gen).unwrap();
+    symlink(&outside, &dir.gen).unwrap();
+    let err = clean(&dir, None, false).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    assert!(outside.join("a.ts").exists());
+  }
+}
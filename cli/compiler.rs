@@ -1,4 +1,6 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+use crate::diagnostics;
+use crate::diagnostics::Diagnostic;
 use crate::js_errors;
 use crate::js_errors::JSErrorColor;
 use crate::msg;
@@ -8,6 +10,7 @@ use crate::resources::ResourceId;
 use crate::startup_data;
 use crate::state::*;
 use crate::tokio_util;
+use crate::wasm;
 use crate::worker::Worker;
 use deno::js_check;
 use deno::Buf;
@@ -17,27 +20,46 @@ use futures::sync::oneshot;
 use futures::Future;
 use futures::Stream;
 use serde_json;
+use num_cpus;
 use std::collections::HashMap;
 use std::str;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::sync::Mutex;
 use tokio::runtime::Runtime;
 
 type CmdId = u32;
 type ResponseSenderTable = HashMap<CmdId, oneshot::Sender<Buf>>;
+/// The `data` of a successful response of the compiler worker.
+type ResponseData = serde_json::Map<String, serde_json::Value>;
 
 lazy_static! {
   static ref C_NEXT_CMD_ID: AtomicUsize = AtomicUsize::new(1);
   // Map of response senders
   static ref C_RES_SENDER_TABLE: Mutex<ResponseSenderTable> = Mutex::new(ResponseSenderTable::new());
-  // Shared worker resources so we can spawn
-  static ref C_RID: Mutex<Option<ResourceId>> = Mutex::new(None);
+  // The compiler workers started so far, up to --compile-jobs of them
+  static ref C_WORKERS: Mutex<Vec<CompilerWorker>> = Mutex::new(Vec::new());
   // tokio runtime specifically for spawning logic that is dependent on
   // completetion of the compiler worker future
   static ref C_RUNTIME: Mutex<Runtime> = Mutex::new(Runtime::new().unwrap());
 }
 
+/// A compiler worker and the number of requests it has yet to answer.
+struct CompilerWorker {
+  rid: ResourceId,
+  pending: Arc<AtomicUsize>,
+}
+
+/// What keeps a module from compiling.
+#[derive(Debug)]
+pub enum CompileError {
+  /// The TypeScript diagnostics of the module.
+  Diagnostics(Vec<Diagnostic>),
+  /// A failure to load one of its imports.
+  Js(JSError),
+}
+
 // This corresponds to JS ModuleMetaData.
 // TODO Rename one or the other so they correspond.
 #[derive(Debug, Clone)]
@@ -51,6 +73,9 @@ pub struct ModuleMetaData {
   pub maybe_output_code: Option<Vec<u8>>,
   pub maybe_source_map_filename: Option<String>,
   pub maybe_source_map: Option<Vec<u8>>,
+  /// The URL of the declarations of a JavaScript module, given by the
+  /// X-TypeScript-Types header it was served with.
+  pub maybe_types: Option<String>,
 }
 
 impl ModuleMetaData {
@@ -65,6 +90,9 @@ impl ModuleMetaData {
         str::from_utf8(&self.source_code).unwrap()
       );
     }
+    if self.media_type == msg::MediaType::Wasm {
+      return wasm::js_source(&self.source_code);
+    }
     match self.maybe_output_code {
       None => str::from_utf8(&self.source_code).unwrap().to_string(),
       Some(ref output_code) => str::from_utf8(output_code).unwrap().to_string(),
@@ -87,98 +115,151 @@ fn parse_cmd_id(res_json: &str) -> CmdId {
   }
 }
 
-fn lazy_start(parent_state: ThreadSafeState) -> ResourceId {
-  let mut cell = C_RID.lock().unwrap();
-  cell
-    .get_or_insert_with(|| {
-      let child_state = ThreadSafeState::new(
-        parent_state.flags.clone(),
-        parent_state.argv.clone(),
-        op_selector_compiler,
-      );
-      let rid = child_state.resource.rid;
-      let resource = child_state.resource.clone();
+/// Picks the compiler worker for a request: an idle one, a new one while
+/// fewer than `--compile-jobs` run, or else the one with the fewest requests.
+fn lazy_start(parent_state: ThreadSafeState) -> (ResourceId, Arc<AtomicUsize>) {
+  let mut workers = C_WORKERS.lock().unwrap();
+  let jobs = parent_state.flags.compile_jobs.unwrap_or_else(num_cpus::get);
+  let is_idle = |w: &CompilerWorker| w.pending.load(Ordering::SeqCst) == 0;
+  if !workers.iter().any(is_idle) && workers.len() < jobs {
+    let rid = start_worker(parent_state);
+    workers.push(CompilerWorker {
+      rid,
+      pending: Arc::new(AtomicUsize::new(0)),
+    });
+  }
+  let worker = workers
+    .iter()
+    .min_by_key(|w| w.pending.load(Ordering::SeqCst))
+    .unwrap();
+  worker.pending.fetch_add(1, Ordering::SeqCst);
+  (worker.rid, worker.pending.clone())
+}
 
-      let mut worker = Worker::new(
-        "TS".to_string(),
-        startup_data::compiler_isolate_init(),
-        child_state,
-      );
+fn start_worker(parent_state: ThreadSafeState) -> ResourceId {
+  let child_state = ThreadSafeState::new(
+    parent_state.flags.clone(),
+    parent_state.argv.clone(),
+    op_selector_compiler,
+  );
+  let rid = child_state.resource.rid;
+  let resource = child_state.resource.clone();
 
-      js_check(worker.execute("denoMain()"));
-      js_check(worker.execute("workerMain()"));
-      js_check(worker.execute("compilerMain()"));
-
-      let mut runtime = C_RUNTIME.lock().unwrap();
-      runtime.spawn(lazy(move || {
-        tokio_util::abort_on_panic();
-
-        worker.then(move |result| -> Result<(), ()> {
-          // Close resource so the future created by
-          // handle_worker_message_stream exits
-          resource.close();
-          debug!("Compiler worker exited!");
-          if let Err(e) = result {
-            eprintln!("{}", JSErrorColor(&e).to_string());
-          }
-          std::process::exit(1);
-        })
-      }));
-      runtime.spawn(lazy(move || {
-        debug!("Start worker stream handler!");
-        let worker_stream = resources::get_message_stream_from_worker(rid);
-        worker_stream
-          .for_each(|msg: Buf| {
-            // All worker responses are handled here first before being sent via
-            // their respective sender. This system can be compared to the
-            // promise system used on the js side. This provides a way to
-            // resolve many futures via the same channel.
-            let res_json = std::str::from_utf8(&msg).unwrap();
-            debug!("Got message from worker: {}", res_json);
-            // Get the intended receiver's cmd_id from the message.
-            let cmd_id = parse_cmd_id(res_json);
-            let mut table = C_RES_SENDER_TABLE.lock().unwrap();
-            debug!("Cmd id for get message handler: {}", cmd_id);
-            // Get the corresponding response sender from the table and
-            // send a response.
-            let response_sender = table.remove(&(cmd_id as CmdId)).unwrap();
-            response_sender.send(msg).unwrap();
-            Ok(())
-          }).map_err(|_| ())
-      }));
-      rid
-    }).to_owned()
+  let mut worker = Worker::new(
+    "TS".to_string(),
+    startup_data::compiler_isolate_init(!parent_state.flags.no_snapshot),
+    child_state,
+  );
+
+  js_check(worker.execute("denoMain()"));
+  js_check(worker.execute("workerMain()"));
+  js_check(worker.execute("compilerMain()"));
+
+  let mut runtime = C_RUNTIME.lock().unwrap();
+  runtime.spawn(lazy(move || {
+    tokio_util::abort_on_panic();
+
+    worker.then(move |result| -> Result<(), ()> {
+      // Close resource so the future created by
+      // handle_worker_message_stream exits
+      resource.close();
+      debug!("Compiler worker exited!");
+      if let Err(e) = result {
+        eprintln!("{}", JSErrorColor(&e).to_string());
+      }
+      std::process::exit(1);
+    })
+  }));
+  runtime.spawn(lazy(move || {
+    debug!("Start worker stream handler!");
+    let worker_stream = resources::get_message_stream_from_worker(rid);
+    worker_stream
+      .for_each(|msg: Buf| {
+        // All worker responses are handled here first before being sent via
+        // their respective sender. This system can be compared to the
+        // promise system used on the js side. This provides a way to
+        // resolve many futures via the same channel.
+        let res_json = std::str::from_utf8(&msg).unwrap();
+        debug!("Got message from worker: {}", res_json);
+        // Get the intended receiver's cmd_id from the message.
+        let cmd_id = parse_cmd_id(res_json);
+        let mut table = C_RES_SENDER_TABLE.lock().unwrap();
+        debug!("Cmd id for get message handler: {}", cmd_id);
+        // Get the corresponding response sender from the table and
+        // send a response.
+        let response_sender = table.remove(&(cmd_id as CmdId)).unwrap();
+        response_sender.send(msg).unwrap();
+        Ok(())
+      }).map_err(|_| ())
+  }));
+  rid
 }
 
-fn req(specifier: &str, referrer: &str, cmd_id: u32) -> Buf {
+fn req(
+  specifier: &str,
+  referrer: &str,
+  compiler_options: &serde_json::Value,
+  no_check: bool,
+  cmd_id: u32,
+) -> Buf {
   json!({
     "specifier": specifier,
     "referrer": referrer,
+    "compilerOptions": compiler_options,
+    "noCheck": no_check,
     "cmdId": cmd_id,
   }).to_string()
   .into_boxed_str()
   .into_boxed_bytes()
 }
 
-pub fn compile_async(
-  parent_state: ThreadSafeState,
+fn bundle_req(
   specifier: &str,
   referrer: &str,
-  module_meta_data: &ModuleMetaData,
-) -> impl Future<Item = ModuleMetaData, Error = JSError> {
-  debug!(
-    "Running rust part of compile_sync. specifier: {}, referrer: {}",
-    &specifier, &referrer
-  );
-  let cmd_id = new_cmd_id();
+  compiler_options: &serde_json::Value,
+  inline_source_map: bool,
+  cmd_id: u32,
+) -> Buf {
+  json!({
+    "specifier": specifier,
+    "referrer": referrer,
+    "compilerOptions": compiler_options,
+    "cmdId": cmd_id,
+    "bundle": true,
+    "inlineSourceMap": inline_source_map,
+  }).to_string()
+  .into_boxed_str()
+  .into_boxed_bytes()
+}
 
-  let req_msg = req(&specifier, &referrer, cmd_id);
-  let module_meta_data_ = module_meta_data.clone();
+fn doc_req(
+  specifier: &str,
+  referrer: &str,
+  compiler_options: &serde_json::Value,
+  cmd_id: u32,
+) -> Buf {
+  json!({
+    "specifier": specifier,
+    "referrer": referrer,
+    "compilerOptions": compiler_options,
+    "cmdId": cmd_id,
+    "doc": true,
+  }).to_string()
+  .into_boxed_str()
+  .into_boxed_bytes()
+}
 
-  let compiler_rid = lazy_start(parent_state.clone());
+/// Sends a request to the compiler worker and resolves to the `data` of its
+/// response.
+fn request(
+  parent_state: ThreadSafeState,
+  cmd_id: CmdId,
+  req_msg: Buf,
+) -> impl Future<Item = ResponseData, Error = JSError> {
+  let (compiler_rid, pending) = lazy_start(parent_state.clone());
 
   let (local_sender, local_receiver) =
-    oneshot::channel::<Result<ModuleMetaData, Option<JSError>>>();
+    oneshot::channel::<Result<ResponseData, Option<JSError>>>();
 
   let (response_sender, response_receiver) = oneshot::channel::<Buf>();
 
@@ -197,6 +278,7 @@ pub fn compile_async(
           response_receiver.map_err(|_| None)
         }).and_then(move |res_msg| {
           debug!("Received message from worker");
+          pending.fetch_sub(1, Ordering::SeqCst);
           let res_json = std::str::from_utf8(res_msg.as_ref()).unwrap();
           let res = serde_json::from_str::<serde_json::Value>(res_json)
             .expect("Error decoding compiler response");
@@ -204,15 +286,7 @@ pub fn compile_async(
             "Error decoding compiler response: expected object field 'data'",
           );
           match res["success"].as_bool() {
-            Some(true) => Ok(ModuleMetaData {
-              maybe_output_code: res_data["outputCode"]
-                .as_str()
-                .map(|s| s.as_bytes().to_owned()),
-              maybe_source_map: res_data["sourceMap"]
-                .as_str()
-                .map(|s| s.as_bytes().to_owned()),
-              ..module_meta_data_
-            }),
+            Some(true) => Ok(res_data.clone()),
             Some(false) => {
               let js_error = JSError::from_json_value(
                 serde_json::Value::Object(res_data.clone()),
@@ -222,6 +296,7 @@ pub fn compile_async(
               Err(Some(js_errors::apply_source_map(
                 &js_error,
                 &parent_state.dir,
+                parent_state.flags.full_stack_traces,
               )))
             }
             _ => panic!(
@@ -248,12 +323,109 @@ pub fn compile_async(
     })
 }
 
+pub fn compile_async(
+  parent_state: ThreadSafeState,
+  specifier: &str,
+  referrer: &str,
+  module_meta_data: &ModuleMetaData,
+) -> impl Future<Item = ModuleMetaData, Error = CompileError> {
+  debug!(
+    "Running rust part of compile_sync. specifier: {}, referrer: {}",
+    &specifier, &referrer
+  );
+  let cmd_id = new_cmd_id();
+
+  let req_msg = req(
+    &specifier,
+    &referrer,
+    &parent_state.dir.compiler_options,
+    parent_state.dir.no_check,
+    cmd_id,
+  );
+  let module_meta_data_ = module_meta_data.clone();
+
+  request(parent_state, cmd_id, req_msg)
+    .map_err(CompileError::Js)
+    .and_then(move |res_data| {
+      if let Some(diagnostics) = res_data.get("diagnostics") {
+        return Err(CompileError::Diagnostics(diagnostics::from_json_value(
+          diagnostics,
+        )));
+      }
+      Ok(ModuleMetaData {
+        maybe_output_code: res_data["outputCode"]
+          .as_str()
+          .map(|s| s.as_bytes().to_owned()),
+        maybe_source_map: res_data["sourceMap"]
+          .as_str()
+          .map(|s| s.as_bytes().to_owned()),
+        ..module_meta_data_
+      })
+    })
+}
+
+/// Compiles the module graph of `specifier` into a single script, see
+/// `deno bundle`.
+pub fn bundle_async(
+  parent_state: ThreadSafeState,
+  specifier: &str,
+  referrer: &str,
+  inline_source_map: bool,
+) -> impl Future<Item = String, Error = CompileError> {
+  debug!("bundle specifier: {}, referrer: {}", &specifier, &referrer);
+  let cmd_id = new_cmd_id();
+  let req_msg = bundle_req(
+    &specifier,
+    &referrer,
+    &parent_state.dir.compiler_options,
+    inline_source_map,
+    cmd_id,
+  );
+  request(parent_state, cmd_id, req_msg)
+    .map_err(CompileError::Js)
+    .and_then(|res_data| {
+      if let Some(diagnostics) = res_data.get("diagnostics") {
+        return Err(CompileError::Diagnostics(diagnostics::from_json_value(
+          diagnostics,
+        )));
+      }
+      Ok(
+        res_data["bundle"]
+          .as_str()
+          .expect("Error decoding compiler response: expected field 'bundle'")
+          .to_string(),
+      )
+    })
+}
+
+/// Resolves to the documentation of what `specifier` exports, see
+/// `deno doc` and js/doc.ts.
+pub fn doc_async(
+  parent_state: ThreadSafeState,
+  specifier: &str,
+  referrer: &str,
+) -> impl Future<Item = serde_json::Value, Error = JSError> {
+  debug!("doc specifier: {}, referrer: {}", &specifier, &referrer);
+  let cmd_id = new_cmd_id();
+  let req_msg = doc_req(
+    &specifier,
+    &referrer,
+    &parent_state.dir.compiler_options,
+    cmd_id,
+  );
+  request(parent_state, cmd_id, req_msg).map(|mut res_data| {
+    res_data
+      .remove("nodes")
+      .expect("Error decoding compiler response: expected field 'nodes'")
+  })
+}
+
 pub fn compile_sync(
   parent_state: ThreadSafeState,
   specifier: &str,
   referrer: &str,
   module_meta_data: &ModuleMetaData,
-) -> Result<ModuleMetaData, JSError> {
+) -> Result<ModuleMetaData, CompileError> {
   tokio_util::block_on(compile_async(
     parent_state,
     specifier,
@@ -262,6 +434,28 @@ pub fn compile_sync(
   ))
 }
 
+pub fn bundle_sync(
+  parent_state: ThreadSafeState,
+  specifier: &str,
+  referrer: &str,
+  inline_source_map: bool,
+) -> Result<String, CompileError> {
+  tokio_util::block_on(bundle_async(
+    parent_state,
+    specifier,
+    referrer,
+    inline_source_map,
+  ))
+}
+
+pub fn doc_sync(
+  parent_state: ThreadSafeState,
+  specifier: &str,
+  referrer: &str,
+) -> Result<serde_json::Value, JSError> {
+  tokio_util::block_on(doc_async(parent_state, specifier, referrer))
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -285,6 +479,7 @@ mod tests {
         maybe_output_code: None,
         maybe_source_map_filename: None,
         maybe_source_map: None,
+        maybe_types: None,
       };
 
       out = compile_sync(ThreadSafeState::mock(), specifier, &referrer, &out)
@@ -302,7 +497,7 @@ mod tests {
   fn test_parse_cmd_id() {
     let cmd_id = new_cmd_id();
 
-    let msg = req("Hello", "World", cmd_id);
+    let msg = req("Hello", "World", &json!({}), false, cmd_id);
 
     let res_json = std::str::from_utf8(&msg).unwrap();
 
@@ -0,0 +1,381 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! `deno completions <shell>` prints the completion script clap generates
+//! for the command line of `create_cli_app()`, fixed up for what clap can't
+//! know: the script deno runs is told apart by a fake `<script>` subcommand,
+//! which can't be completed as it is, and is a path like the files of
+//! `deno fmt` and `deno info`.
+use crate::flags;
+use clap::Shell;
+use regex::Regex;
+
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish", "powershell"];
+
+/// The subcommands whose arguments are paths, by the names the bash and the
+/// PowerShell scripts give them.
+const BASH_PATH_COMMANDS: &[&str] =
+  &["deno", "deno__run", "deno__fmt", "deno__info"];
+const POWERSHELL_PATH_COMMANDS: &str = "'deno', 'deno;run', 'deno;fmt', \
+                                        'deno;info'";
+
+/// The completion script of `shell`, one of SHELLS.
+pub fn generate(shell: &str) -> String {
+  let mut script = Vec::new();
+  flags::create_cli_app().gen_completions_to(
+    "deno",
+    shell.parse::<Shell>().unwrap(),
+    &mut script,
+  );
+  let script = String::from_utf8(script).unwrap();
+  match shell {
+    "bash" => fix_bash(&script),
+    "zsh" => fix_zsh(&script),
+    "fish" => fix_fish(&script),
+    "powershell" => fix_powershell(&script),
+    _ => unreachable!(),
+  }
+}
+
+fn indent(line: &str) -> usize {
+  line.len() - line.trim_start().len()
+}
+
+/// Removes the blocks of lines which `is_start` starts, up to the first line
+/// after for which `is_end` holds, given the start line.
+fn remove_blocks<S, E>(script: &str, is_start: S, is_end: E) -> String
+where
+  S: Fn(&str) -> bool,
+  E: Fn(&str, &str) -> bool,
+{
+  let mut out = String::new();
+  let mut start: Option<&str> = None;
+  for line in script.lines() {
+    match start {
+      Some(start_line) => {
+        if is_end(start_line, line) {
+          start = None;
+        }
+      }
+      None if is_start(line) => start = Some(line),
+      None => {
+        out.push_str(line);
+        out.push('\n');
+      }
+    }
+  }
+  out
+}
+
+/// The case arms of `<script>` are syntax errors, its name is dropped from
+/// the words to complete, and paths are completed along with them.
+fn fix_bash(script: &str) -> String {
+  let script = remove_blocks(
+    script,
+    |line| line.contains("<script>") && line.trim_end().ends_with(')'),
+    |start, line| line.trim() == ";;" && indent(line) == indent(start) + 4,
+  );
+  let compgen =
+    Regex::new(r#"compgen -W "\$\{opts\}" -- "?\$\{cur\}"?"#).unwrap();
+  let label = Regex::new(r"^        ([\w-]+)\)$").unwrap();
+  let mut out = String::new();
+  let mut completes_paths = false;
+  for line in script.lines() {
+    if let Some(captures) = label.captures(line) {
+      completes_paths = BASH_PATH_COMMANDS.contains(&&captures[1]);
+    }
+    let line = line.replace(" <script>", "");
+    if completes_paths {
+      let files = r#"$0) $$(compgen -f -- "$${cur}""#;
+      out.push_str(&compgen.replace_all(&line, files));
+    } else {
+      out.push_str(&line);
+    }
+    out.push('\n');
+  }
+  out
+}
+
+/// The case arms and the functions of `<script>` are syntax errors, and the
+/// script is a path.
+fn fix_zsh(script: &str) -> String {
+  let script = remove_blocks(
+    script,
+    |line| line.trim() == "(<script>)",
+    |_, line| line.trim() == ";;",
+  );
+  let script = remove_blocks(
+    &script,
+    |line| line.contains("$+functions[") && line.contains("<script>"),
+    |_, line| line.trim() == "}",
+  );
+  let describe =
+    Regex::new(r#"^(\s*)_describe -t commands 'deno( run)? commands'"#)
+      .unwrap();
+  let mut out = String::new();
+  for line in script.lines().filter(|line| !line.contains("\"<script>:")) {
+    out.push_str(line);
+    out.push('\n');
+    if let Some(captures) = describe.captures(line) {
+      out.push_str(&format!("{}_files\n", &captures[1]));
+    }
+  }
+  out
+}
+
+/// Without `-f` the subcommands of deno and run don't keep fish from
+/// completing paths for the script.
+fn fix_fish(script: &str) -> String {
+  let mut out = String::new();
+  for line in script.lines().filter(|line| !line.contains("<script>")) {
+    if line.contains("__fish_use_subcommand")
+      || line.contains("__fish_seen_subcommand_from run\"")
+    {
+      out.push_str(&line.replace(" -f -a ", " -a "));
+    } else {
+      out.push_str(line);
+    }
+    out.push('\n');
+  }
+  out
+}
+
+/// Drops `<script>` and completes paths too where those are expected.
+fn fix_powershell(script: &str) -> String {
+  let script = remove_blocks(
+    script,
+    |line| {
+      let line = line.trim();
+      line.starts_with('\'') && line.contains("<script>") && line.ends_with('{')
+    },
+    |start, line| line.trim() == "}" && indent(line) == indent(start),
+  );
+  let mut out = String::new();
+  for line in script.lines().filter(|line| !line.contains("'<script>'")) {
+    if line.trim_start().starts_with("$completions.Where{") {
+      out.push_str(&format!(
+        "    if ($command -in {} -and -not $wordToComplete.StartsWith('-')) {{
+        $completions += Get-ChildItem -Path \"$wordToComplete*\" |
+            ForEach-Object {{
+                $path = Resolve-Path -Relative $_.FullName
+                [CompletionResult]::new($path, $_.Name, [CompletionResultType]::ProviderItem, $_.FullName)
+            }}
+    }}
+
+",
+        POWERSHELL_PATH_COMMANDS
+      ));
+    }
+    out.push_str(line);
+    out.push('\n');
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::BTreeSet;
+
+  /// The help of `args`, as --help prints it.
+  fn help(args: &[&str]) -> String {
+    let mut argv = vec!["deno"];
+    argv.extend(args);
+    argv.push("-h");
+    match flags::create_cli_app().get_matches_from_safe(argv) {
+      Err(err) => err.message,
+      Ok(_) => panic!("-h did not print the help of {:?}", args),
+    }
+  }
+
+  /// The long flags of deno and of all its subcommands.
+  fn long_flags() -> BTreeSet<String> {
+    let flag = Regex::new(r"^\s+(?:-\w, )?--([\w-]+)").unwrap();
+    let subcommand = Regex::new(r"^    ([a-z]\S*)\s").unwrap();
+    let root = help(&[]);
+    let mut helps = vec![root.clone()];
+    let subcommands = root
+      .split("SUBCOMMANDS:")
+      .nth(1)
+      .unwrap()
+      .lines()
+      .filter_map(|line| subcommand.captures(line))
+      .map(|captures| captures[1].to_string());
+    for name in subcommands {
+      helps.push(help(&[name.as_str()]));
+    }
+    let mut flags = BTreeSet::new();
+    for help in helps {
+      for line in help.lines() {
+        if let Some(captures) = flag.captures(line) {
+          flags.insert(captures[1].to_string());
+        }
+      }
+    }
+    flags
+  }
+
+  #[test]
+  fn test_generate() {
+    let flags = long_flags();
+    for name in &["allow-read", "reload", "fail-fast", "coverage", "json"] {
+      assert!(flags.contains(*name), "--{} is not found", name);
+    }
+    for shell in SHELLS {
+      let script = generate(shell);
+      assert!(!script.contains("<script>"), "{}", shell);
+      for flag in &flags {
+        let completion = if *shell == "fish" {
+          format!("-l {}", flag)
+        } else {
+          format!("--{}", flag)
+        };
+        assert!(script.contains(&completion), "{}: --{}", shell, flag);
+      }
+    }
+  }
+
+  #[test]
+  fn test_fix_bash() {
+    let script = "    for i in ${COMP_WORDS[@]}
+    do
+        case \"${i}\" in
+            <script>)
+                cmd+=\"__<script>\"
+                ;;
+            fmt)
+                cmd+=\"__fmt\"
+                ;;
+        esac
+    done
+
+    case \"${cmd}\" in
+        deno)
+            opts=\" -h --reload <script> fmt\"
+            COMPREPLY=( $(compgen -W \"${opts}\" -- \"${cur}\") )
+            ;;
+        deno__<script>)
+            opts=\" -h \"
+            case \"${prev}\" in
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            ;;
+        deno__types)
+            COMPREPLY=( $(compgen -W \"${opts}\" -- \"${cur}\") )
+            ;;
+    esac
+";
+    assert_eq!(
+      fix_bash(script),
+      "    for i in ${COMP_WORDS[@]}
+    do
+        case \"${i}\" in
+            fmt)
+                cmd+=\"__fmt\"
+                ;;
+        esac
+    done
+
+    case \"${cmd}\" in
+        deno)
+            opts=\" -h --reload fmt\"
+            COMPREPLY=( $(compgen -W \"${opts}\" -- \"${cur}\") $(compgen -f -- \"${cur}\") )
+            ;;
+        deno__types)
+            COMPREPLY=( $(compgen -W \"${opts}\" -- \"${cur}\") )
+            ;;
+    esac
+"
+    );
+  }
+
+  #[test]
+  fn test_fix_zsh() {
+    let script = "        case $line[1] in
+            (<script>)
+_arguments \"${_arguments_options[@]}\" \\
+'-h[Prints help information]' \\
+&& ret=0
+;;
+(fmt)
+&& ret=0
+;;
+        esac
+(( $+functions[_deno_commands] )) ||
+_deno_commands() {
+    local commands; commands=(
+        \"<script>:Script to run\" \\
+\"fmt:Format files\" \\
+    )
+    _describe -t commands 'deno commands' commands \"$@\"
+}
+(( $+functions[_deno__<script>_commands] )) ||
+_deno__<script>_commands() {
+    local commands; commands=(
+
+    )
+    _describe -t commands 'deno <script> commands' commands \"$@\"
+}
+";
+    assert_eq!(
+      fix_zsh(script),
+      "        case $line[1] in
+(fmt)
+&& ret=0
+;;
+        esac
+(( $+functions[_deno_commands] )) ||
+_deno_commands() {
+    local commands; commands=(
+\"fmt:Format files\" \\
+    )
+    _describe -t commands 'deno commands' commands \"$@\"
+    _files
+}
+"
+    );
+  }
+
+  #[test]
+  fn test_fix_fish() {
+    let script = "complete -c deno -n \"__fish_use_subcommand\" -l reload
+complete -c deno -n \"__fish_use_subcommand\" -f -a \"<script>\" -d 'Script to run'
+complete -c deno -n \"__fish_use_subcommand\" -f -a \"fmt\" -d 'Format files'
+complete -c deno -n \"__fish_seen_subcommand_from <script>\" -s h -l help
+complete -c deno -n \"__fish_seen_subcommand_from types\" -f -a \"x\"
+";
+    assert_eq!(
+      fix_fish(script),
+      "complete -c deno -n \"__fish_use_subcommand\" -l reload
+complete -c deno -n \"__fish_use_subcommand\" -a \"fmt\" -d 'Format files'
+complete -c deno -n \"__fish_seen_subcommand_from types\" -f -a \"x\"
+"
+    );
+  }
+
+  #[test]
+  fn test_fix_powershell() {
+    let script = "    $completions = @(switch ($command) {
+        'deno' {
+            [CompletionResult]::new('<script>', '<script>', [CompletionResultType]::ParameterValue, 'Script to run')
+            break
+        }
+        'deno;<script>' {
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Prints help information')
+            break
+        }
+    })
+
+    $completions.Where{ $_.CompletionText -like \"$wordToComplete*\" } |
+";
+    let fixed = fix_powershell(script);
+    assert!(!fixed.contains("<script>"));
+    assert!(fixed.contains("        'deno' {\n            break\n        }\n"));
+    assert!(fixed.contains(
+      "    if ($command -in 'deno', 'deno;run', 'deno;fmt', 'deno;info' -and"
+    ));
+    assert!(fixed.ends_with(
+      "$completions.Where{ $_.CompletionText -like \"$wordToComplete*\" } |\n"
+    ));
+  }
+}
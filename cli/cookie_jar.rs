@@ -0,0 +1,271 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! The cookies of the fetches with `credentials: "include"`, kept in memory
+//! for the rest of the run. A simplified RFC 6265: cookies are stored and sent
+//! by domain, path, secure and expiry, other attributes are ignored.
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+use time;
+use url::Url;
+
+lazy_static! {
+  pub static ref COOKIE_JAR: Mutex<CookieJar> =
+    Mutex::new(CookieJar::default());
+}
+
+#[derive(Debug, PartialEq)]
+struct Cookie {
+  name: String,
+  value: String,
+  domain: String,
+  /// Without a domain attribute a cookie is only sent to the host which set
+  /// it, not to its subdomains.
+  host_only: bool,
+  path: String,
+  secure: bool,
+  expires: Option<SystemTime>,
+}
+
+impl Cookie {
+  fn is_expired(&self, now: SystemTime) -> bool {
+    self.expires.map_or(false, |expires| expires <= now)
+  }
+
+  fn matches(&self, url: &Url, now: SystemTime) -> bool {
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    let domain_matches = if self.host_only {
+      host == self.domain
+    } else {
+      domain_match(&host, &self.domain)
+    };
+    domain_matches
+      && path_match(url.path(), &self.path)
+      && (!self.secure || url.scheme() == "https")
+      && !self.is_expired(now)
+  }
+}
+
+#[derive(Default)]
+pub struct CookieJar {
+  cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+  /// Keeps the cookie of the `Set-Cookie` header `set_cookie` of a response
+  /// from `url`, replacing the one with the same name, domain and path. An
+  /// already expired cookie removes it.
+  pub fn store(&mut self, url: &Url, set_cookie: &str) {
+    let now = SystemTime::now();
+    let cookie = match parse_set_cookie(url, set_cookie, now) {
+      Some(cookie) => cookie,
+      None => return,
+    };
+    self.cookies.retain(|c| {
+      !(c.name == cookie.name
+        && c.domain == cookie.domain
+        && c.path == cookie.path)
+    });
+    if !cookie.is_expired(now) {
+      self.cookies.push(cookie);
+    }
+  }
+
+  /// The value of the `Cookie` header of a request to `url`, the cookies with
+  /// longer paths first.
+  pub fn header(&self, url: &Url) -> Option<String> {
+    let now = SystemTime::now();
+    let mut cookies: Vec<&Cookie> = self
+      .cookies
+      .iter()
+      .filter(|c| c.matches(url, now))
+      .collect();
+    if cookies.is_empty() {
+      return None;
+    }
+    cookies.sort_by(|a, b| b.path.len().cmp(&a.path.len()));
+    let pairs: Vec<String> = cookies
+      .iter()
+      .map(|c| format!("{}={}", c.name, c.value))
+      .collect();
+    Some(pairs.join("; "))
+  }
+}
+
+/// Whether `host` is `domain` or one of its subdomains.
+fn domain_match(host: &str, domain: &str) -> bool {
+  host == domain
+    || (host.ends_with(domain)
+      && host[..host.len() - domain.len()].ends_with('.'))
+}
+
+/// Whether the cookie `path` applies to the request `path`, "/docs" applies
+/// to "/docs" and "/docs/api", not to "/docsets".
+fn path_match(request_path: &str, path: &str) -> bool {
+  request_path == path
+    || (request_path.starts_with(path)
+      && (path.ends_with('/') || request_path[path.len()..].starts_with('/')))
+}
+
+/// The directory of the path of `url`, where a cookie without a path
+/// attribute applies.
+fn default_path(url: &Url) -> String {
+  let path = url.path();
+  match path.rfind('/') {
+    Some(0) | None => "/".to_string(),
+    Some(i) => path[..i].to_string(),
+  }
+}
+
+/// Parses dates like "Wed, 21 Oct 2015 07:28:00 GMT".
+fn parse_http_date(s: &str) -> Option<SystemTime> {
+  let tm = time::strptime(s, "%a, %d %b %Y %H:%M:%S GMT").ok()?;
+  let secs = tm.to_timespec().sec;
+  if secs < 0 {
+    Some(UNIX_EPOCH)
+  } else {
+    Some(UNIX_EPOCH + Duration::from_secs(secs as u64))
+  }
+}
+
+/// None for a cookie which is invalid or for another domain.
+fn parse_set_cookie(
+  url: &Url,
+  set_cookie: &str,
+  now: SystemTime,
+) -> Option<Cookie> {
+  let host = url.host_str()?.to_lowercase();
+  let mut parts = set_cookie.split(';');
+  let pair = parts.next()?;
+  let eq = pair.find('=')?;
+  let name = pair[..eq].trim();
+  if name.is_empty() {
+    return None;
+  }
+  let mut cookie = Cookie {
+    name: name.to_string(),
+    value: pair[eq + 1..].trim().to_string(),
+    domain: host.clone(),
+    host_only: true,
+    path: default_path(url),
+    secure: false,
+    expires: None,
+  };
+  let mut max_age = None;
+  for attribute in parts {
+    let (key, value) = match attribute.find('=') {
+      Some(i) => (&attribute[..i], attribute[i + 1..].trim()),
+      None => (attribute, ""),
+    };
+    match key.trim().to_lowercase().as_str() {
+      "domain" if !value.is_empty() => {
+        let domain = value.trim_start_matches('.').to_lowercase();
+        if !domain_match(&host, &domain) {
+          return None;
+        }
+        cookie.domain = domain;
+        cookie.host_only = false;
+      }
+      "path" if value.starts_with('/') => cookie.path = value.to_string(),
+      "secure" => cookie.secure = true,
+      "expires" => {
+        if let Some(expires) = parse_http_date(value) {
+          cookie.expires = Some(expires);
+        }
+      }
+      "max-age" => {
+        if let Ok(secs) = value.parse::<i64>() {
+          max_age = Some(if secs <= 0 {
+            UNIX_EPOCH
+          } else {
+            now + Duration::from_secs(secs as u64)
+          });
+        }
+      }
+      _ => {}
+    }
+  }
+  // Max-Age wins over Expires.
+  if max_age.is_some() {
+    cookie.expires = max_age;
+  }
+  Some(cookie)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn url(s: &str) -> Url {
+    Url::parse(s).unwrap()
+  }
+
+  #[test]
+  fn store_and_send() {
+    let mut jar = CookieJar::default();
+    jar.store(&url("http://example.com/a/b"), "id=1");
+    jar.store(&url("http://example.com/"), "theme=dark; Path=/");
+    assert_eq!(
+      jar.header(&url("http://example.com/a/c")),
+      Some("id=1; theme=dark".to_string())
+    );
+    assert_eq!(
+      jar.header(&url("http://example.com/b")),
+      Some("theme=dark".to_string())
+    );
+    assert_eq!(jar.header(&url("http://other.com/a/c")), None);
+  }
+
+  #[test]
+  fn domain_attribute() {
+    let mut jar = CookieJar::default();
+    jar.store(&url("http://www.example.com/"), "a=1; Domain=.example.com");
+    jar.store(&url("http://www.example.com/"), "b=2");
+    jar.store(&url("http://www.example.com/"), "c=3; Domain=other.com");
+    assert_eq!(
+      jar.header(&url("http://api.example.com/")),
+      Some("a=1".to_string())
+    );
+    assert_eq!(
+      jar.header(&url("http://www.example.com/")),
+      Some("a=1; b=2".to_string())
+    );
+    assert_eq!(jar.header(&url("http://other.com/")), None);
+  }
+
+  #[test]
+  fn replace_and_expire() {
+    let mut jar = CookieJar::default();
+    let u = url("http://example.com/");
+    jar.store(&u, "a=1");
+    jar.store(&u, "a=2");
+    assert_eq!(jar.header(&u), Some("a=2".to_string()));
+    jar.store(&u, "a=; Max-Age=0");
+    assert_eq!(jar.header(&u), None);
+    jar.store(&u, "b=1; Expires=Thu, 01 Jan 1970 00:00:00 GMT");
+    assert_eq!(jar.header(&u), None);
+    jar.store(&u, "c=1; Expires=Fri, 01 Jan 2100 00:00:00 GMT");
+    assert_eq!(jar.header(&u), Some("c=1".to_string()));
+  }
+
+  #[test]
+  fn secure_only_over_https() {
+    let mut jar = CookieJar::default();
+    jar.store(&url("https://example.com/"), "s=1; Secure");
+    assert_eq!(jar.header(&url("http://example.com/")), None);
+    assert_eq!(
+      jar.header(&url("https://example.com/")),
+      Some("s=1".to_string())
+    );
+  }
+
+  #[test]
+  fn path_matching() {
+    assert!(path_match("/docs", "/docs"));
+    assert!(path_match("/docs/api", "/docs"));
+    assert!(path_match("/docs/api", "/docs/"));
+    assert!(!path_match("/docsets", "/docs"));
+    assert_eq!(default_path(&url("http://a.com/x/y")), "/x");
+    assert_eq!(default_path(&url("http://a.com/x")), "/");
+  }
+}
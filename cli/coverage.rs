@@ -0,0 +1,509 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! `deno test --coverage`: the precise coverage of V8, taken through the
+//! inspector once the tests ran. V8 reports the ranges of each script which
+//! ran, and how often, as offsets into the JavaScript it executed. They are
+//! mapped to the lines of the original files, through the source map of those
+//! which were compiled, and written as an lcov file, which e.g. Codecov reads.
+use crate::deno_dir;
+use crate::deno_dir::ModuleOrigin;
+use crate::flags::DenoFlags;
+use crate::fmt::Glob;
+use crate::js_errors::SourceMap;
+use crate::state::ThreadSafeState;
+use crate::worker::Worker;
+use deno::InspectorEvent;
+use deno::InspectorHost;
+use futures::Async;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str;
+use std::sync::Arc;
+use std::sync::Mutex;
+use url::Url;
+
+/// The id of the Profiler.takePreciseCoverage request.
+const TAKE_COVERAGE_ID: u64 = 3;
+
+#[derive(Default)]
+struct Session {
+  /// What the inspector has yet to handle.
+  events: VecDeque<InspectorEvent>,
+  /// The result of Profiler.takePreciseCoverage, once the inspector sent it.
+  result: Option<Value>,
+}
+
+/// Stands in for a DevTools client which only asks for the coverage.
+struct CoverageHost {
+  session: Arc<Mutex<Session>>,
+}
+
+impl InspectorHost for CoverageHost {
+  fn send(&mut self, message: &str) {
+    // The other responses and the events are of no interest.
+    if let Ok(Value::Object(mut message)) = serde_json::from_str(message) {
+      if message.get("id").and_then(Value::as_u64) == Some(TAKE_COVERAGE_ID) {
+        self.session.lock().unwrap().result = message.remove("result");
+      }
+    }
+  }
+
+  fn poll_event(&mut self) -> Async<InspectorEvent> {
+    // All requests are queued before the inspector is polled for them, so
+    // there is no task to notify.
+    match self.session.lock().unwrap().events.pop_front() {
+      Some(event) => Async::Ready(event),
+      None => Async::NotReady,
+    }
+  }
+
+  fn wait_event(&mut self) -> InspectorEvent {
+    unreachable!("the debugger is not enabled, JavaScript never pauses")
+  }
+}
+
+/// The coverage of a worker, started by `start()`.
+pub struct Coverage {
+  session: Arc<Mutex<Session>>,
+}
+
+/// Starts the precise coverage of `worker`, with block granularity and the
+/// number of calls. Do so before it executes anything, V8 only counts what
+/// was compiled after.
+pub fn start(worker: &mut Worker) -> Coverage {
+  let session = Arc::new(Mutex::new(Session::default()));
+  {
+    let mut session = session.lock().unwrap();
+    session.events.push_back(InspectorEvent::Connected);
+    for message in &[
+      json!({"id": 1, "method": "Profiler.enable"}),
+      json!({
+        "id": 2,
+        "method": "Profiler.startPreciseCoverage",
+        "params": {"callCount": true, "detailed": true},
+      }),
+    ] {
+      session
+        .events
+        .push_back(InspectorEvent::Message(message.to_string()));
+    }
+  }
+  worker.set_inspector(Box::new(CoverageHost {
+    session: session.clone(),
+  }));
+  worker.poll_inspector();
+  Coverage { session }
+}
+
+impl Coverage {
+  /// Takes the coverage so far, the ScriptCoverage of each script.
+  pub fn take(&self, worker: &mut Worker) -> Vec<Value> {
+    let message = json!({
+      "id": TAKE_COVERAGE_ID,
+      "method": "Profiler.takePreciseCoverage",
+    });
+    self
+      .session
+      .lock()
+      .unwrap()
+      .events
+      .push_back(InspectorEvent::Message(message.to_string()));
+    // The inspector responds at once.
+    worker.poll_inspector();
+    match self.session.lock().unwrap().result.take() {
+      Some(Value::Object(mut result)) => match result.remove("result") {
+        Some(Value::Array(scripts)) => scripts,
+        _ => Vec::new(),
+      },
+      _ => Vec::new(),
+    }
+  }
+}
+
+/// The part of a script which ran `count` times, from `start` to before `end`
+/// in UTF-16 code units. The ranges of a script nest.
+#[derive(Debug, PartialEq)]
+struct Range {
+  start: usize,
+  end: usize,
+  count: u64,
+}
+
+fn ranges(script: &Value) -> Vec<Range> {
+  let mut ranges = Vec::new();
+  for function in script["functions"].as_array().into_iter().flatten() {
+    for range in function["ranges"].as_array().into_iter().flatten() {
+      if let (Some(start), Some(end), Some(count)) = (
+        range["startOffset"].as_u64(),
+        range["endOffset"].as_u64(),
+        range["count"].as_u64(),
+      ) {
+        ranges.push(Range {
+          start: start as usize,
+          end: end as usize,
+          count,
+        });
+      }
+    }
+  }
+  ranges
+}
+
+/// How often the code at `offset` ran: the count of the innermost range
+/// around it. None if it is outside any function.
+fn count_at(ranges: &[Range], offset: usize) -> Option<u64> {
+  ranges
+    .iter()
+    .filter(|r| r.start <= offset && offset < r.end)
+    .min_by_key(|r| r.end - r.start)
+    .map(|r| r.count)
+}
+
+/// How often each line of a file ran, by line number starting at 1. Lines
+/// without code are left out.
+type Lines = BTreeMap<u32, u64>;
+
+/// The lines of the original file of `js_source`, which ran as `ranges`
+/// say. Without a source map, `js_source` is the original, and the count of
+/// a line is that of its first character which isn't white space. Otherwise
+/// it is the highest count of the code mapped to it.
+fn count_lines(
+  js_source: &str,
+  ranges: &[Range],
+  source_map: Option<&SourceMap>,
+) -> Lines {
+  // The offset where each line starts, and where its code starts.
+  let mut line_starts = Vec::new();
+  let mut code_starts = Vec::new();
+  let mut offset = 0;
+  for line in js_source.split('\n') {
+    line_starts.push(offset);
+    let mut code_start = None;
+    for c in line.chars() {
+      if code_start.is_none() && !c.is_whitespace() {
+        code_start = Some(offset);
+      }
+      offset += c.len_utf16();
+    }
+    code_starts.push(code_start);
+    offset += 1;
+  }
+  let mut lines = Lines::new();
+  match source_map {
+    None => {
+      for (line, code_start) in code_starts.iter().enumerate() {
+        let count = code_start.and_then(|start| count_at(ranges, start));
+        if let Some(count) = count {
+          lines.insert(line as u32 + 1, count);
+        }
+      }
+    }
+    Some(source_map) => {
+      for mapping in source_map.mappings.by_generated_location() {
+        let line = mapping.generated_line as usize;
+        if let (Some(original), Some(line_start)) =
+          (&mapping.original, line_starts.get(line))
+        {
+          let offset = line_start + mapping.generated_column as usize;
+          if let Some(count) = count_at(ranges, offset) {
+            let entry = lines.entry(original.original_line + 1).or_insert(0);
+            *entry = (*entry).max(count);
+          }
+        }
+      }
+    }
+  }
+  lines
+}
+
+/// The coverage of a module.
+#[derive(Debug, PartialEq)]
+struct FileCoverage {
+  /// The path relative to the current directory, or the URL of a remote
+  /// module, as matched by the globs and printed.
+  name: String,
+  /// The absolute path, or the URL, for the lcov file.
+  source_file: String,
+  lines: Lines,
+}
+
+impl FileCoverage {
+  fn covered(&self) -> usize {
+    self.lines.values().filter(|count| **count > 0).count()
+  }
+}
+
+/// Which modules are covered: without `--coverage-include` the local ones,
+/// neither the remote ones nor the bundles of the runtime. The main module of
+/// `deno test`, which only imports the test files, never is.
+struct Filter {
+  include: Vec<Glob>,
+  exclude: Vec<Glob>,
+  cwd: PathBuf,
+}
+
+impl Filter {
+  fn new(flags: &DenoFlags, cwd: PathBuf) -> Self {
+    Self {
+      include: flags
+        .coverage_include
+        .iter()
+        .map(|p| Glob::new(p))
+        .collect(),
+      exclude: flags
+        .coverage_exclude
+        .iter()
+        .map(|p| Glob::new(p))
+        .collect(),
+      cwd,
+    }
+  }
+
+  /// Returns the name and the lcov source file of the module `url`, if it is
+  /// covered.
+  fn names(&self, url: &str, origin: ModuleOrigin) -> Option<(String, String)> {
+    let url = Url::parse(url).ok()?;
+    let names = match url.to_file_path() {
+      Ok(_) if origin == ModuleOrigin::Memory => return None,
+      Ok(path) => {
+        let name = path.strip_prefix(&self.cwd).unwrap_or(&path);
+        (
+          name.to_string_lossy().replace('\\', "/"),
+          path.to_string_lossy().to_string(),
+        )
+      }
+      Err(()) if url.scheme() == "http" || url.scheme() == "https" => {
+        if self.include.is_empty() {
+          return None;
+        }
+        (url.to_string(), url.to_string())
+      }
+      Err(()) => return None,
+    };
+    let is_match = |glob: &Glob| glob.is_match(Path::new(&names.0));
+    if !self.include.is_empty() && !self.include.iter().any(is_match) {
+      return None;
+    }
+    if self.exclude.iter().any(is_match) {
+      return None;
+    }
+    Some(names)
+  }
+}
+
+fn file_coverage(
+  state: &ThreadSafeState,
+  filter: &Filter,
+  script: &Value,
+) -> Option<FileCoverage> {
+  let url = script["url"].as_str()?;
+  let (name, source_file) = filter.names(url, deno_dir::module_origin(url))?;
+  let module = state.dir.fetch_module_meta_data(url, ".", true).ok()?;
+  let source_map = module
+    .maybe_source_map
+    .as_ref()
+    .and_then(|map| SourceMap::from_json(str::from_utf8(map).ok()?));
+  let lines =
+    count_lines(&module.js_source(), &ranges(script), source_map.as_ref());
+  Some(FileCoverage {
+    name,
+    source_file,
+    lines,
+  })
+}
+
+fn percent(covered: usize, total: usize) -> String {
+  let percent = if total == 0 {
+    100.0
+  } else {
+    covered as f64 * 100.0 / total as f64
+  };
+  format!("{:.2}% ({}/{} lines)", percent, covered, total)
+}
+
+/// The percent of the lines covered, of each file and in total.
+fn summary(files: &[FileCoverage]) -> String {
+  let mut summary = String::from("coverage:\n");
+  let (mut covered, mut total) = (0, 0);
+  for file in files {
+    covered += file.covered();
+    total += file.lines.len();
+    summary.push_str(&format!(
+      "  {} {}\n",
+      file.name,
+      percent(file.covered(), file.lines.len())
+    ));
+  }
+  summary.push_str(&format!("  total {}\n", percent(covered, total)));
+  summary
+}
+
+/// The lcov tracefile format of `geninfo`, with the lines only.
+fn lcov(files: &[FileCoverage]) -> String {
+  let mut lcov = String::new();
+  for file in files {
+    lcov.push_str(&format!("TN:\nSF:{}\n", file.source_file));
+    for (line, count) in &file.lines {
+      lcov.push_str(&format!("DA:{},{}\n", line, count));
+    }
+    lcov.push_str(&format!(
+      "LF:{}\nLH:{}\nend_of_record\n",
+      file.lines.len(),
+      file.covered()
+    ));
+  }
+  lcov
+}
+
+/// Prints the summary of the coverage `scripts`, and writes them to
+/// lcov.info in the directory of `--coverage`.
+pub fn report(state: &ThreadSafeState, scripts: &[Value]) -> io::Result<()> {
+  let filter = Filter::new(&state.flags, std::env::current_dir()?);
+  let mut files: Vec<FileCoverage> = scripts
+    .iter()
+    .filter_map(|script| file_coverage(state, &filter, script))
+    .collect();
+  files.sort_by(|a, b| a.name.cmp(&b.name));
+  files.dedup_by(|a, b| a.source_file == b.source_file);
+  let dir = Path::new(state.flags.coverage.as_ref().unwrap());
+  fs::create_dir_all(dir)?;
+  let path = dir.join("lcov.info");
+  fs::write(&path, lcov(&files))?;
+  print!("\n{}", summary(&files));
+  println!("coverage written to {}", path.display());
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn range(start: usize, end: usize, count: u64) -> Range {
+    Range { start, end, count }
+  }
+
+  #[test]
+  fn test_ranges() {
+    let script = json!({
+      "url": "file:///a.js",
+      "functions": [
+        {"ranges": [{"startOffset": 0, "endOffset": 20, "count": 1}]},
+        {"ranges": [
+          {"startOffset": 5, "endOffset": 15, "count": 3},
+          {"startOffset": 8, "endOffset": 10, "count": 0},
+        ]},
+      ],
+    });
+    let ranges = ranges(&script);
+    assert_eq!(
+      ranges,
+      vec![range(0, 20, 1), range(5, 15, 3), range(8, 10, 0)]
+    );
+    assert_eq!(count_at(&ranges, 0), Some(1));
+    assert_eq!(count_at(&ranges, 5), Some(3));
+    assert_eq!(count_at(&ranges, 9), Some(0));
+    assert_eq!(count_at(&ranges, 10), Some(3));
+    assert_eq!(count_at(&ranges, 20), None);
+  }
+
+  #[test]
+  fn test_count_lines() {
+    let source = "x();\n\n  function f() {\n    g();\n  }\n";
+    let ranges = vec![range(0, 38, 1), range(8, 35, 0)];
+    let lines = count_lines(source, &ranges, None);
+    let expected: Lines =
+      vec![(1, 1), (3, 0), (4, 0), (5, 0)].into_iter().collect();
+    assert_eq!(lines, expected);
+  }
+
+  #[test]
+  fn test_count_lines_source_map() {
+    // "𝄞" is two UTF-16 code units, the columns of source maps count them.
+    let source = "a();\n\"𝄞\"; b();\n";
+    let map = json!({
+      "version": 3,
+      "sources": ["a.ts"],
+      // Line 1 is from line 1, both statements of line 2 from line 3.
+      "mappings": "AAAA;AAEA,MAAA",
+    });
+    let source_map = SourceMap::from_json(&map.to_string()).unwrap();
+    let ranges = vec![range(0, 16, 1), range(11, 15, 0)];
+    let lines = count_lines(source, &ranges, Some(&source_map));
+    let expected: Lines = vec![(1, 1), (3, 1)].into_iter().collect();
+    assert_eq!(lines, expected);
+
+    let ranges = vec![range(0, 5, 1), range(5, 16, 0)];
+    let lines = count_lines(source, &ranges, Some(&source_map));
+    let expected: Lines = vec![(1, 1), (3, 0)].into_iter().collect();
+    assert_eq!(lines, expected);
+  }
+
+  #[test]
+  fn test_filter() {
+    let cwd = if cfg!(windows) {
+      PathBuf::from("C:\\src")
+    } else {
+      PathBuf::from("/src")
+    };
+    let url =
+      |path: &str| Url::from_file_path(cwd.join(path)).unwrap().to_string();
+    let filter = Filter::new(&DenoFlags::default(), cwd.clone());
+    let names = filter.names(&url("lib/a.ts"), ModuleOrigin::Local).unwrap();
+    assert_eq!(names.0, "lib/a.ts");
+    assert_eq!(names.1, cwd.join("lib/a.ts").to_string_lossy());
+    assert!(filter
+      .names(&url("$test.js"), ModuleOrigin::Memory)
+      .is_none());
+    let remote = "https://deno.land/std/fmt/colors.ts";
+    assert!(filter.names(remote, ModuleOrigin::Cache).is_none());
+    assert!(filter
+      .names("gen/cli/bundle/main.js", ModuleOrigin::Cache)
+      .is_none());
+
+    let flags = DenoFlags {
+      coverage_include: vec!["lib/**".to_string(), "https://**".to_string()],
+      coverage_exclude: vec!["*_test.ts".to_string()],
+      ..DenoFlags::default()
+    };
+    let filter = Filter::new(&flags, cwd.clone());
+    assert!(filter
+      .names(&url("lib/a.ts"), ModuleOrigin::Local)
+      .is_some());
+    assert!(filter
+      .names(&url("lib/a_test.ts"), ModuleOrigin::Local)
+      .is_none());
+    assert!(filter.names(&url("b.ts"), ModuleOrigin::Local).is_none());
+    let names = filter.names(remote, ModuleOrigin::Cache).unwrap();
+    assert_eq!(names, (remote.to_string(), remote.to_string()));
+  }
+
+  #[test]
+  fn test_report() {
+    let files = vec![
+      FileCoverage {
+        name: "a.ts".to_string(),
+        source_file: "/src/a.ts".to_string(),
+        lines: vec![(1, 2), (2, 0), (4, 1)].into_iter().collect(),
+      },
+      FileCoverage {
+        name: "b.ts".to_string(),
+        source_file: "/src/b.ts".to_string(),
+        lines: Lines::new(),
+      },
+    ];
+    assert_eq!(
+      lcov(&files),
+      "TN:\nSF:/src/a.ts\nDA:1,2\nDA:2,0\nDA:4,1\nLF:3\nLH:2\nend_of_record\n\
+       TN:\nSF:/src/b.ts\nLF:0\nLH:0\nend_of_record\n"
+    );
+    assert_eq!(
+      summary(&files),
+      "coverage:\n  a.ts 66.67% (2/3 lines)\n  b.ts 100.00% (0/0 lines)\n  \
+       total 66.67% (2/3 lines)\n"
+    );
+  }
+}
@@ -1,4 +1,5 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+use crate::ansi;
 use crate::compiler::ModuleMetaData;
 use crate::errors;
 use crate::errors::DenoError;
@@ -6,10 +7,13 @@ use crate::errors::DenoResult;
 use crate::errors::ErrorKind;
 use crate::fs as deno_fs;
 use crate::http_util;
+use crate::http_util::CacheValidators;
+use crate::import_map::ImportMap;
 use crate::js_errors::SourceMapGetter;
 use crate::msg;
 use crate::tokio_util;
 use crate::version;
+use base64;
 use dirs;
 use futures::future::{loop_fn, Either, Loop};
 use futures::Future;
@@ -17,13 +21,19 @@ use http;
 use ring;
 use serde_json;
 use std;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt::Write;
 use std::fs;
+use std::path::Component;
 use std::path::Path;
 use std::path::PathBuf;
 use std::result::Result;
 use std::str;
+use std::sync::Mutex;
+use tempfile;
 use url;
+use url::percent_encoding::percent_decode;
 use url::Url;
 
 /// Gets corresponding MediaType given extension
@@ -32,10 +42,106 @@ fn extmap(ext: &str) -> msg::MediaType {
     "ts" => msg::MediaType::TypeScript,
     "js" => msg::MediaType::JavaScript,
     "json" => msg::MediaType::Json,
+    "wasm" => msg::MediaType::Wasm,
     _ => msg::MediaType::Unknown,
   }
 }
 
+lazy_static! {
+  // Modules which only exist in memory, like the script `deno -` read from
+  // stdin, and the compiled output of data: URLs. They are shared by the
+  // DenoDir of every worker, so that the compiler finds them as well.
+  static ref MEMORY_MODULES: Mutex<HashMap<String, ModuleMetaData>> =
+    Mutex::new(HashMap::new());
+  // The remote modules which were downloaded by this process, by any worker.
+  static ref DOWNLOADED_MODULES: Mutex<HashSet<String>> =
+    Mutex::new(HashSet::new());
+  // Held while compiled output is written to gen/, as the compiler workers
+  // finish in any order.
+  static ref GEN_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Makes `source_code` the module at `path`, which does not need to exist,
+/// and returns its URL. Like for a file its type comes from the extension and
+/// its relative imports resolve against its directory.
+pub fn add_memory_module(path: &Path, source_code: Vec<u8>) -> String {
+  let module_name = Url::from_file_path(path).unwrap().to_string();
+  let out = ModuleMetaData {
+    module_name: module_name.clone(),
+    module_redirect_source_name: None,
+    filename: deno_fs::normalize_path(path),
+    media_type: map_file_extension(path),
+    source_code,
+    maybe_output_code_filename: None,
+    maybe_output_code: None,
+    maybe_source_map_filename: None,
+    maybe_source_map: None,
+    maybe_types: None,
+  };
+  MEMORY_MODULES.lock().unwrap().insert(module_name.clone(), out);
+  module_name
+}
+
+fn get_memory_module(module_name: &str) -> Option<ModuleMetaData> {
+  MEMORY_MODULES.lock().unwrap().get(module_name).cloned()
+}
+
+/// Where a module was loaded from, shown by `deno info`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModuleOrigin {
+  Local,
+  Memory,
+  Cache,
+  Network,
+  Data,
+}
+
+impl ModuleOrigin {
+  pub fn describe(self) -> &'static str {
+    match self {
+      ModuleOrigin::Local => "local",
+      ModuleOrigin::Memory => "memory",
+      ModuleOrigin::Cache => "cache",
+      ModuleOrigin::Network => "network",
+      ModuleOrigin::Data => "data URL",
+    }
+  }
+}
+
+/// Where the module `module_name` was loaded from by this process. A remote
+/// module which was only revalidated counts as coming from the cache.
+pub fn module_origin(module_name: &str) -> ModuleOrigin {
+  if is_data_url(module_name) {
+    ModuleOrigin::Data
+  } else if MEMORY_MODULES.lock().unwrap().contains_key(module_name) {
+    ModuleOrigin::Memory
+  } else if DOWNLOADED_MODULES.lock().unwrap().contains(module_name) {
+    ModuleOrigin::Network
+  } else if module_name.starts_with("file:") {
+    ModuleOrigin::Local
+  } else {
+    ModuleOrigin::Cache
+  }
+}
+
+/// How the root of the DenoDir was chosen, shown by `deno info`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RootSource {
+  Flag,
+  Env,
+  Default,
+}
+
+impl RootSource {
+  pub fn describe(self) -> &'static str {
+    match self {
+      RootSource::Flag => "set by --deno-dir",
+      RootSource::Env => "set by the DENO_DIR environment variable",
+      RootSource::Default => "default",
+    }
+  }
+}
+
 #[derive(Clone)]
 pub struct DenoDir {
   // Example: /Users/rld/.deno/
@@ -51,12 +157,48 @@ pub struct DenoDir {
   // This splits to http and https deps
   pub deps_http: PathBuf,
   pub deps_https: PathBuf,
+  // With --cached-only a remote module missing from deps is an error instead
+  // of being downloaded.
+  pub cached_only: bool,
+  // Applied before any other resolution, see --importmap.
+  pub import_map: Option<ImportMap>,
+  // With --quiet, or a log level above info, downloads are not announced.
+  pub quiet: bool,
+  // Redirects followed per remote module, see --max-redirects.
+  pub max_redirects: usize,
+  // With --reload=full remote modules are fetched without conditional
+  // requests and compiled again.
+  pub reload_full: bool,
+  /// Compiler options on top of the defaults of the compiler, sent along with
+  /// every compile request. Part of the key of the compiled output.
+  pub compiler_options: serde_json::Value,
+  /// Modules are compiled without type checking, see `--no-check`.
+  pub no_check: bool,
+  /// Local imports which don't exist are probed for, see `probe_local()`.
+  pub compat_resolve: bool,
 }
 
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
 impl DenoDir {
+  /// The root to pass to `new()`: `--deno-dir` takes precedence over the
+  /// DENO_DIR environment variable.
+  pub fn custom_root(flag: Option<&str>) -> (Option<PathBuf>, RootSource) {
+    if let Some(root) = flag {
+      return (Some(PathBuf::from(root)), RootSource::Flag);
+    }
+    match std::env::var("DENO_DIR") {
+      Ok(root) => (Some(PathBuf::from(root)), RootSource::Env),
+      Err(_) => (None, RootSource::Default),
+    }
+  }
+
   // Must be called before using any function from this module.
   // https://github.com/denoland/deno/blob/golang/deno_dir.go#L99-L111
-  pub fn new(custom_root: Option<PathBuf>) -> std::io::Result<Self> {
+  pub fn new(
+    custom_root: Option<PathBuf>,
+    cached_only: bool,
+  ) -> std::io::Result<Self> {
     // Only setup once.
     let home_dir = dirs::home_dir().expect("Could not get home directory.");
     let fallback = home_dir.join(".deno");
@@ -79,6 +221,14 @@ impl DenoDir {
       deps,
       deps_http,
       deps_https,
+      cached_only,
+      import_map: None,
+      quiet: false,
+      max_redirects: DEFAULT_MAX_REDIRECTS,
+      reload_full: false,
+      compiler_options: json!({}),
+      no_check: false,
+      compat_resolve: false,
     };
 
     // TODO Lazily create these directories.
@@ -96,13 +246,31 @@ impl DenoDir {
     Ok(deno_dir)
   }
 
+  /// Identifies the compiler which produced the output in `gen/`, so that the
+  /// output of another deno or TypeScript version or of other compiler
+  /// options is compiled again instead of being reused.
+  pub fn compiler_stamp(self: &Self) -> String {
+    let mut stamp = format!(
+      "{} {} {}",
+      version::DENO,
+      version::typescript(),
+      self.compiler_options
+    );
+    // Output which was not type checked is kept apart.
+    if self.no_check {
+      stamp.push_str(" no-check");
+    }
+    stamp
+  }
+
   // https://github.com/denoland/deno/blob/golang/deno_dir.go#L32-L35
   pub fn cache_path(
     self: &Self,
     filename: &str,
     source_code: &[u8],
   ) -> (PathBuf, PathBuf) {
-    let cache_key = source_code_hash(filename, source_code, version::DENO);
+    let cache_key =
+      source_code_hash(filename, source_code, &self.compiler_stamp());
     (
       self.gen.join(cache_key.to_string() + ".js"),
       self.gen.join(cache_key.to_string() + ".js.map"),
@@ -115,19 +283,25 @@ impl DenoDir {
   ) -> std::io::Result<()> {
     let (cache_path, source_map_path) = self
       .cache_path(&module_meta_data.filename, &module_meta_data.source_code);
-    // TODO(ry) This is a race condition w.r.t to exists() -- probably should
-    // create the file in exclusive mode. A worry is what might happen is there
-    // are two processes and one reads the cache file while the other is in the
-    // midst of writing it.
+    // Data URLs are never written to disk, their output is kept in memory.
+    if is_data_url(&module_meta_data.module_name) {
+      MEMORY_MODULES.lock().unwrap().insert(
+        module_meta_data.module_name.clone(),
+        module_meta_data.clone(),
+      );
+      return Ok(());
+    }
+    let _lock = GEN_LOCK.lock().unwrap();
     if cache_path.exists() && source_map_path.exists() {
       Ok(())
     } else {
+      // Another process reading the output sees all of it or nothing.
       match &module_meta_data.maybe_output_code {
-        Some(output_code) => fs::write(cache_path, output_code),
+        Some(output_code) => write_atomic(&cache_path, output_code),
         _ => Ok(()),
       }?;
       match &module_meta_data.maybe_source_map {
-        Some(source_map) => fs::write(source_map_path, source_map),
+        Some(source_map) => write_atomic(&source_map_path, source_map),
         _ => Ok(()),
       }?;
       Ok(())
@@ -155,73 +329,89 @@ impl DenoDir {
     let (module_name, filename) = result.unwrap();
 
     let gen = self.gen.clone();
-
-    Either::B(
-      get_source_code_async(
+    let compiler_stamp = self.compiler_stamp();
+    let reload_full = self.reload_full;
+    // A missing remote module under --cached-only already has a message
+    // naming the URL.
+    let is_cached_only_miss = self.cached_only && is_remote(&module_name);
+
+    let source = match get_memory_module(&module_name) {
+      Some(out) => Either::A(futures::future::ok(out)),
+      None if is_data_url(&module_name) => {
+        Either::A(futures::future::result(fetch_data_url(&module_name)))
+      }
+      None => Either::B(get_source_code_async(
         self,
         module_name.as_str(),
         filename.as_str(),
         use_cache,
-      ).then(move |result| {
-        let mut out = match result {
-          Ok(out) => out,
-          Err(err) => {
-            if err.kind() == ErrorKind::NotFound {
-              // For NotFound, change the message to something better.
-              return Err(errors::new(
-                ErrorKind::NotFound,
-                format!(
-                  "Cannot resolve module \"{}\" from \"{}\"",
-                  specifier, referrer
-                ),
-              ));
-            } else {
-              return Err(err);
-            }
-          }
-        };
+      )),
+    };
 
-        if out.source_code.starts_with(b"#!") {
-          out.source_code = filter_shebang(out.source_code);
+    Either::B(source.then(move |result| {
+      let mut out = match result {
+        Ok(out) => out,
+        Err(err) => {
+          if err.kind() == ErrorKind::NotFound && !is_cached_only_miss {
+            // For NotFound, change the message to something better.
+            return Err(errors::new(
+              ErrorKind::NotFound,
+              format!(
+                "Cannot resolve module \"{}\" from \"{}\"",
+                specifier, referrer
+              ),
+            ));
+          } else {
+            return Err(err);
+          }
         }
+      };
 
-        // If TypeScript we have to also load corresponding compile js and
-        // source maps (called output_code and output_source_map)
-        if out.media_type != msg::MediaType::TypeScript || !use_cache {
-          return Ok(out);
-        }
+      if out.source_code.starts_with(b"#!") {
+        out.source_code = filter_shebang(out.source_code);
+      }
 
-        let cache_key =
-          source_code_hash(&out.filename, &out.source_code, version::DENO);
-        let (output_code_filename, output_source_map_filename) = (
-          gen.join(cache_key.to_string() + ".js"),
-          gen.join(cache_key.to_string() + ".js.map"),
-        );
-
-        let result =
-          load_cache2(&output_code_filename, &output_source_map_filename);
-        match result {
-          Err(err) => {
-            if err.kind() == std::io::ErrorKind::NotFound {
-              // If there's no compiled JS or source map, that's ok, just
-              // return what we have.
-              Ok(out)
-            } else {
-              Err(err.into())
-            }
-          }
-          Ok((output_code, source_map)) => {
-            out.maybe_output_code = Some(output_code);
-            out.maybe_source_map = Some(source_map);
-            out.maybe_output_code_filename =
-              Some(output_code_filename.to_str().unwrap().to_string());
-            out.maybe_source_map_filename =
-              Some(output_source_map_filename.to_str().unwrap().to_string());
+      // If TypeScript we have to also load corresponding compile js and
+      // source maps (called output_code and output_source_map). A reloaded
+      // remote module keeps its compiled output if its source is unchanged,
+      // e.g. after a 304 Not Modified, as the output is keyed by the source.
+      let use_compiled = (use_cache
+        || (is_remote(&out.module_name) && !reload_full))
+        && !is_data_url(&out.module_name);
+      if out.media_type != msg::MediaType::TypeScript || !use_compiled {
+        return Ok(out);
+      }
+
+      let cache_key =
+        source_code_hash(&out.filename, &out.source_code, &compiler_stamp);
+      let (output_code_filename, output_source_map_filename) = (
+        gen.join(cache_key.to_string() + ".js"),
+        gen.join(cache_key.to_string() + ".js.map"),
+      );
+
+      let result =
+        load_cache2(&output_code_filename, &output_source_map_filename);
+      match result {
+        Err(err) => {
+          if err.kind() == std::io::ErrorKind::NotFound {
+            // If there's no compiled JS or source map, that's ok, just
+            // return what we have.
             Ok(out)
+          } else {
+            Err(err.into())
           }
         }
-      }),
-    )
+        Ok((output_code, source_map)) => {
+          out.maybe_output_code = Some(output_code);
+          out.maybe_source_map = Some(source_map);
+          out.maybe_output_code_filename =
+            Some(output_code_filename.to_str().unwrap().to_string());
+          out.maybe_source_map_filename =
+            Some(output_source_map_filename.to_str().unwrap().to_string());
+          Ok(out)
+        }
+      }
+    }))
   }
 
   /// Synchronous version of fetch_module_meta_data_async
@@ -238,7 +428,7 @@ impl DenoDir {
   }
 
   // Prototype: https://github.com/denoland/deno/blob/golang/os.go#L56-L68
-  fn src_file_to_url(self: &Self, filename: &str) -> String {
+  pub fn src_file_to_url(self: &Self, filename: &str) -> String {
     let filename_path = Path::new(filename);
     if filename_path.starts_with(&self.deps) {
       let (rest, prefix) = if filename_path.starts_with(&self.deps_https) {
@@ -284,11 +474,25 @@ impl DenoDir {
       referrer = referrer_path.to_str().unwrap().to_string() + "/";
     }
 
+    if let Some(ref import_map) = self.import_map {
+      let referrer_url = if referrer.ends_with('/') && !is_remote(&referrer) {
+        Url::from_directory_path(&referrer).ok()
+      } else {
+        parse_local_or_remote(&referrer).ok()
+      };
+      let referrer_url =
+        referrer_url.map(|u| u.to_string()).unwrap_or_default();
+      if let Some(url) = import_map.resolve(&specifier, &referrer_url) {
+        debug!("import map resolved {} to {}", specifier, url);
+        return Ok(url);
+      }
+    }
+
     let j = if is_remote(&specifier)
       || (Path::new(&specifier).is_absolute() && !is_remote(&referrer))
     {
       parse_local_or_remote(&specifier)?
-    } else if referrer.ends_with('/') {
+    } else if referrer.ends_with('/') && !is_data_url(&referrer) {
       let r = Url::from_directory_path(&referrer);
       // TODO(ry) Properly handle error.
       if r.is_err() {
@@ -303,19 +507,31 @@ impl DenoDir {
     Ok(j)
   }
 
-  /// Returns (module name, local filename)
+  /// Returns (module name, local filename). The filename of a data: URL is
+  /// made up, see data_url_filename().
   pub fn resolve_module(
     self: &Self,
     specifier: &str,
     referrer: &str,
-  ) -> Result<(String, String), url::ParseError> {
+  ) -> DenoResult<(String, String)> {
     let j = self.resolve_module_url(specifier, referrer)?;
 
-    let module_name = j.to_string();
+    let mut module_name = j.to_string();
     let filename;
     match j.scheme() {
       "file" => {
-        filename = deno_fs::normalize_path(j.to_file_path().unwrap().as_ref());
+        let mut path = file_url_to_path(&j)?;
+        if self.compat_resolve {
+          let probed = self.probe_local(&module_name, &path);
+          if probed != path {
+            module_name = Url::from_file_path(&probed).unwrap().to_string();
+            path = probed;
+          }
+        }
+        filename = deno_fs::normalize_path(&path);
+      }
+      "data" => {
+        filename = data_url_filename(&module_name);
       }
       "https" => {
         filename = deno_fs::normalize_path(
@@ -334,8 +550,64 @@ impl DenoDir {
     debug!("module_name: {}, filename: {}", module_name, filename);
     Ok((module_name, filename))
   }
+
+  /// With `--compat-resolve`, the file which the local import `module_name`
+  /// of the missing file `path` stands for: the first of `path.ts`,
+  /// `path.js`, `path/index.ts` and `path/index.js` which exists. What is
+  /// found is recorded in `gen/`, and kept for as long as the file exists
+  /// so that adding another candidate doesn't change what is loaded, until
+  /// `--reload=full`. Without any candidate `path` is kept, and reported as
+  /// missing.
+  fn probe_local(self: &Self, module_name: &str, path: &Path) -> PathBuf {
+    if path.is_file() {
+      return path.to_path_buf();
+    }
+    let record_filename = self
+      .gen
+      .join(sha1_hex(module_name.as_bytes()) + ".resolved.json");
+    if !self.reload_full {
+      let recorded = fs::read_to_string(&record_filename)
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|record| record[RESOLVED_TO].as_str().map(PathBuf::from));
+      if let Some(recorded) = recorded {
+        if recorded.is_file() {
+          debug!("{} was resolved to {}", module_name, recorded.display());
+          return recorded;
+        }
+      }
+    }
+    let name = path.file_name().map_or("".into(), |n| n.to_os_string());
+    let files = COMPAT_EXTENSIONS.iter().map(|ext| {
+      let mut file_name = name.clone();
+      file_name.push(ext);
+      path.with_file_name(file_name)
+    });
+    let indexes = COMPAT_EXTENSIONS
+      .iter()
+      .map(|ext| path.join(["index", *ext].concat()));
+    for candidate in files.chain(indexes) {
+      if candidate.is_file() {
+        debug!("{} probed to {}", module_name, candidate.display());
+        let record = json!({ RESOLVED_TO: candidate.to_string_lossy() });
+        if let Err(err) =
+          write_atomic(&record_filename, record.to_string().as_bytes())
+        {
+          debug!("not recording {}: {}", record_filename.display(), err);
+        }
+        return candidate;
+      }
+    }
+    path.to_path_buf()
+  }
 }
 
+/// In the order `--compat-resolve` tries them, first after the name of the
+/// import, then after `index` in the directory of that name.
+const COMPAT_EXTENSIONS: &[&str] = &[".ts", ".js"];
+
+static RESOLVED_TO: &'static str = "resolved_to";
+
 impl SourceMapGetter for DenoDir {
   fn get_source_map(&self, script_name: &str) -> Option<Vec<u8>> {
     match self.fetch_module_meta_data(script_name, ".", true) {
@@ -346,6 +618,16 @@ impl SourceMapGetter for DenoDir {
       },
     }
   }
+
+  fn get_source_line(&self, script_name: &str, line: usize) -> Option<String> {
+    match self.fetch_module_meta_data(script_name, ".", true) {
+      Err(_e) => None,
+      Ok(out) => match str::from_utf8(&out.source_code) {
+        Err(_e) => None,
+        Ok(source_code) => source_code.lines().nth(line).map(String::from),
+      },
+    }
+  }
 }
 
 /// This fetches source code, locally or remotely.
@@ -406,6 +688,16 @@ fn get_source_code_async(
 
   debug!("is remote but didn't find module");
 
+  if deno_dir.cached_only {
+    return Either::A(futures::future::err(errors::new(
+      ErrorKind::NotFound,
+      format!(
+        "Cannot find \"{}\" in the cache and --cached-only forbids downloading it",
+        module_name
+      ),
+    )));
+  }
+
   // not cached/local, try remote.
   Either::B(
     fetch_remote_source_async(deno_dir, &module_name, &filename).and_then(
@@ -468,6 +760,15 @@ fn load_cache2(
   Ok((read_output_code, read_source_map))
 }
 
+/// Writes `contents` to a temporary file next to `path` and renames it to
+/// `path`.
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+  let file = tempfile::NamedTempFile::new_in(path.parent().unwrap())?;
+  fs::write(file.path(), contents)?;
+  file.persist(path).map_err(|err| err.error)?;
+  Ok(())
+}
+
 fn source_code_hash(
   filename: &str,
   source_code: &[u8],
@@ -486,12 +787,115 @@ fn source_code_hash(
   out
 }
 
+/// The path of the file: URL `url`, percent-decoded, with the `.` and `..`
+/// which decoding may bring out resolved, so that the cache and the
+/// permission checks see the file which is loaded. Other hosts than
+/// localhost are only file shares on Windows.
+fn file_url_to_path(url: &Url) -> DenoResult<PathBuf> {
+  let path = url.to_file_path().map_err(|()| {
+    errors::new(
+      ErrorKind::InvalidInput,
+      format!("\"{}\" is not a local file", url),
+    )
+  })?;
+  let mut normalized = PathBuf::new();
+  for component in path.components() {
+    match component {
+      Component::CurDir => {}
+      Component::ParentDir => {
+        normalized.pop();
+      }
+      component => normalized.push(component.as_os_str()),
+    }
+  }
+  Ok(normalized)
+}
+
+fn is_data_url(module_name: &str) -> bool {
+  module_name.starts_with("data:")
+}
+
+/// The media type of the data: URL `url`, whether its data is base64, and
+/// the data, still percent-encoded. None without the comma before the data.
+fn split_data_url(url: &str) -> Option<(&str, bool, &str)> {
+  let url = &url["data:".len()..];
+  let url = url.split('#').next().unwrap();
+  let comma = url.find(',')?;
+  let (header, data) = (&url[..comma], &url[comma + 1..]);
+  if header.to_lowercase().ends_with(";base64") {
+    Some((&header[..header.len() - ";base64".len()], true, data))
+  } else {
+    Some((header, false, data))
+  }
+}
+
+/// A data: URL is named by a hash of it in diagnostics, with the extension
+/// of its type, as it can be too long to read, e.g. `$data$/0a4e….ts`.
+fn data_url_filename(url: &str) -> String {
+  let media_type = split_data_url(url)
+    .map_or(msg::MediaType::Unknown, |(mime, _, _)| {
+      map_content_type(Path::new(""), Some(mime))
+    });
+  let extension = match media_type {
+    msg::MediaType::TypeScript => ".ts",
+    msg::MediaType::JavaScript => ".js",
+    msg::MediaType::Json => ".json",
+    msg::MediaType::Wasm => ".wasm",
+    msg::MediaType::Unknown => "",
+  };
+  ["$data$/", &sha1_hex(url.as_bytes()), extension].concat()
+}
+
+fn sha1_hex(data: &[u8]) -> String {
+  let digest = ring::digest::digest(&ring::digest::SHA1, data);
+  let mut out = String::new();
+  for byte in digest.as_ref() {
+    write!(&mut out, "{:02x}", byte).unwrap();
+  }
+  out
+}
+
+/// The module the data: URL `module_name` holds. It is decoded each time its
+/// source is needed, nothing of it is cached on disk.
+fn fetch_data_url(module_name: &str) -> DenoResult<ModuleMetaData> {
+  let invalid = |reason: &str| {
+    errors::new(
+      ErrorKind::InvalidData,
+      format!("Invalid data URL {}: {}", module_name, reason),
+    )
+  };
+  let (mime, is_base64, data) =
+    split_data_url(module_name).ok_or_else(|| invalid("no comma"))?;
+  let data: Vec<u8> = percent_decode(data.as_bytes()).collect();
+  let source_code = if is_base64 {
+    base64::decode(&data).map_err(|e| invalid(&e.to_string()))?
+  } else {
+    data
+  };
+  let media_type = map_content_type(Path::new(""), Some(mime));
+  if media_type == msg::MediaType::Unknown {
+    return Err(invalid(&format!("unsupported media type \"{}\"", mime)));
+  }
+  Ok(ModuleMetaData {
+    module_name: module_name.to_string(),
+    module_redirect_source_name: None,
+    filename: data_url_filename(module_name),
+    media_type,
+    source_code,
+    maybe_output_code_filename: None,
+    maybe_output_code: None,
+    maybe_source_map_filename: None,
+    maybe_source_map: None,
+    maybe_types: None,
+  })
+}
+
 fn is_remote(module_name: &str) -> bool {
   module_name.starts_with("http://") || module_name.starts_with("https://")
 }
 
 fn parse_local_or_remote(p: &str) -> Result<url::Url, url::ParseError> {
-  if is_remote(p) || p.starts_with("file:") {
+  if is_remote(p) || p.starts_with("file:") || is_data_url(p) {
     Url::parse(p)
   } else {
     Url::from_file_path(p).map_err(|_err| url::ParseError::IdnaError)
@@ -505,6 +909,7 @@ fn map_file_extension(path: &Path) -> msg::MediaType {
       Some("ts") => msg::MediaType::TypeScript,
       Some("js") => msg::MediaType::JavaScript,
       Some("json") => msg::MediaType::Json,
+      Some("wasm") => msg::MediaType::Wasm,
       _ => msg::MediaType::Unknown,
     },
   }
@@ -519,7 +924,7 @@ fn map_content_type(path: &Path, content_type: Option<&str>) -> msg::MediaType {
       // dealing with the actual media type
       let ct_vector: Vec<&str> = content_type.split(';').collect();
       let ct: &str = ct_vector.first().unwrap();
-      match ct.to_lowercase().as_ref() {
+      match ct.trim().to_lowercase().as_ref() {
         "application/typescript"
         | "text/typescript"
         | "video/vnd.dlna.mpeg-tts"
@@ -531,7 +936,10 @@ fn map_content_type(path: &Path, content_type: Option<&str>) -> msg::MediaType {
         | "text/ecmascript"
         | "application/x-javascript" => msg::MediaType::JavaScript,
         "application/json" | "text/json" => msg::MediaType::Json,
-        "text/plain" => map_file_extension(path),
+        "application/wasm" => msg::MediaType::Wasm,
+        // Servers which don't know about WebAssembly often use
+        // application/octet-stream for it.
+        "text/plain" | "application/octet-stream" => map_file_extension(path),
         _ => {
           debug!("unknown content type: {}", content_type);
           msg::MediaType::Unknown
@@ -542,7 +950,7 @@ fn map_content_type(path: &Path, content_type: Option<&str>) -> msg::MediaType {
   }
 }
 
-fn filter_shebang(bytes: Vec<u8>) -> Vec<u8> {
+pub fn filter_shebang(bytes: Vec<u8>) -> Vec<u8> {
   let string = str::from_utf8(&bytes).unwrap();
   if let Some(i) = string.find('\n') {
     let (_, rest) = string.split_at(i);
@@ -560,8 +968,9 @@ fn fetch_remote_source_async(
   filename: &str,
 ) -> impl Future<Item = Option<ModuleMetaData>, Error = DenoError> {
   use crate::http_util::FetchOnceResult;
-  {
-    eprintln!("Downloading {}", module_name);
+  if !deno_dir.quiet {
+    let downloading = ansi::green_bold("Downloading".to_string());
+    eprintln!("{} {}", downloading, module_name);
   }
 
   let filename = filename.to_owned();
@@ -571,62 +980,111 @@ fn fetch_remote_source_async(
   // cached file, containing just the media type and possible redirect target (both are http headers).
   // If redirect target is present, the file itself if not cached.
   // In future resolutions, we would instead follow this redirect target ("redirect_to").
+  // The URLs requested so far, starting with module_name.
+  let chain = vec![module_name.clone()];
   loop_fn(
-    (
-      deno_dir.clone(),
-      None,
-      None,
-      module_name.clone(),
-      filename.clone(),
-    ),
-    |(
-      dir,
-      mut maybe_initial_module_name,
-      mut maybe_initial_filename,
-      module_name,
-      filename,
-    )| {
+    (deno_dir.clone(), chain, None, module_name.clone(), filename.clone()),
+    |(dir, mut chain, mut maybe_initial_filename, module_name, filename)| {
       let url = module_name.parse::<http::uri::Uri>().unwrap();
+      // A cached copy is only downloaded again if it changed.
+      let validators = if !dir.reload_full && Path::new(&filename).exists() {
+        get_source_code_headers(&filename).validators
+      } else {
+        CacheValidators::default()
+      };
       // Single pass fetch, either yields code or yields redirect.
-      http_util::fetch_string_once(url).and_then(move |fetch_once_result| {
+      let fetch = http_util::fetch_string_once(url, &validators);
+      fetch.and_then(move |fetch_once_result| {
         match fetch_once_result {
+          FetchOnceResult::NotModified => {
+            let initial_module_name = if chain.len() > 1 {
+              Some(chain[0].clone())
+            } else {
+              None
+            };
+            let out = fetch_local_source(
+              &dir,
+              &module_name,
+              &filename,
+              initial_module_name,
+            )?;
+            if let Some(initial_filename) = maybe_initial_filename {
+              save_source_code_headers(
+                &initial_filename,
+                get_source_code_headers(&filename).mime_type,
+                Some(module_name.clone()),
+                None,
+                &CacheValidators::default(),
+              );
+            }
+            Ok(Loop::Break(out))
+          }
           FetchOnceResult::Redirect(url) => {
             // If redirects, update module_name and filename for next looped call.
-            let resolve_result = dir
+            let (new_module_name, new_filename) = dir
               .resolve_module(&(url.to_string()), ".")
-              .map_err(DenoError::from);
-            match resolve_result {
-              Ok((new_module_name, new_filename)) => {
-                if maybe_initial_module_name.is_none() {
-                  maybe_initial_module_name = Some(module_name.clone());
-                  maybe_initial_filename = Some(filename.clone());
-                }
-                // Not yet completed. Follow the redirect and loop.
-                Ok(Loop::Continue((
-                  dir,
-                  maybe_initial_module_name,
-                  maybe_initial_filename,
-                  new_module_name,
-                  new_filename,
-                )))
-              }
-              Err(e) => Err(e),
+              .map_err(DenoError::from)?;
+            let is_loop = chain.contains(&new_module_name);
+            let hops = chain.len();
+            chain.push(new_module_name.clone());
+            if is_loop {
+              return Err(redirect_error("Redirect loop", &chain));
+            }
+            if hops > dir.max_redirects {
+              let reason =
+                format!("Too many redirects (limit {})", dir.max_redirects);
+              return Err(redirect_error(&reason, &chain));
             }
+            if maybe_initial_filename.is_none() {
+              maybe_initial_filename = Some(filename.clone());
+            }
+            // Not yet completed. Follow the redirect and loop.
+            Ok(Loop::Continue((
+              dir,
+              chain,
+              maybe_initial_filename,
+              new_module_name,
+              new_filename,
+            )))
           }
-          FetchOnceResult::Code(source, maybe_content_type) => {
+          FetchOnceResult::Code(
+            source,
+            maybe_content_type,
+            maybe_types,
+            validators,
+          ) => {
             // We land on the code.
             let p = PathBuf::from(filename.clone());
+            let media_type = map_content_type(
+              &p,
+              maybe_content_type.as_ref().map(|s| s.as_str()),
+            );
+            // Only WebAssembly modules are binary.
+            if media_type != msg::MediaType::Wasm
+              && str::from_utf8(&source).is_err()
+            {
+              return Err(errors::new(
+                ErrorKind::InvalidData,
+                format!(
+                  "Import '{}' failed: the source is not UTF-8",
+                  module_name
+                ),
+              ));
+            }
             match p.parent() {
               Some(ref parent) => fs::create_dir_all(parent),
               None => Ok(()),
             }?;
             // Write file and create .headers.json for the file.
             deno_fs::write_file(&p, &source, 0o666)?;
+            DOWNLOADED_MODULES.lock().unwrap().insert(module_name.clone());
             {
               save_source_code_headers(
                 &filename,
                 maybe_content_type.clone(),
                 None,
+                maybe_types.clone(),
+                &validators,
               );
             }
             // Check if this file is downloaded due to some old redirect request.
@@ -643,22 +1101,26 @@ fn fetch_remote_source_async(
                   &maybe_initial_filename.clone().unwrap(),
                   maybe_content_type.clone(),
                   Some(module_name.clone()),
+                  None,
+                  &CacheValidators::default(),
                 );
               }
             }
             Ok(Loop::Break(Some(ModuleMetaData {
               module_name: module_name.to_string(),
-              module_redirect_source_name: maybe_initial_module_name,
+              module_redirect_source_name: if chain.len() > 1 {
+                Some(chain[0].clone())
+              } else {
+                None
+              },
               filename: filename.to_string(),
-              media_type: map_content_type(
-                &p,
-                maybe_content_type.as_ref().map(|s| s.as_str()),
-              ),
-              source_code: source.as_bytes().to_owned(),
+              media_type,
+              source_code: source,
               maybe_output_code_filename: None,
               maybe_output_code: None,
               maybe_source_map_filename: None,
               maybe_source_map: None,
+              maybe_types: types_url(&module_name, maybe_types),
             })))
           }
         }
@@ -667,6 +1129,13 @@ fn fetch_remote_source_async(
   )
 }
 
+fn redirect_error(reason: &str, chain: &[String]) -> DenoError {
+  errors::new(
+    ErrorKind::Other,
+    format!("{} fetching \"{}\": {}", reason, chain[0], chain.join(" -> ")),
+  )
+}
+
 /// Fetch remote source code.
 #[cfg(test)]
 fn fetch_remote_source(
@@ -697,7 +1166,13 @@ fn fetch_local_source(
   module_initial_source_name: Option<String>,
 ) -> DenoResult<Option<ModuleMetaData>> {
   let p = Path::new(&filename);
-  let source_code_headers = get_source_code_headers(&filename);
+  // The media type of a local file only depends on its extension, headers
+  // are recorded for downloads.
+  let source_code_headers = if is_remote(module_name) {
+    get_source_code_headers(&filename)
+  } else {
+    SourceCodeHeaders::default()
+  };
   // If source code headers says that it would redirect elsewhere,
   // (meaning that the source file might not exist; only .headers.json is present)
   // Abort reading attempts to the cached source file and and follow the redirect.
@@ -750,10 +1225,27 @@ fn fetch_local_source(
     maybe_output_code: None,
     maybe_source_map_filename: None,
     maybe_source_map: None,
+    maybe_types: types_url(module_name, source_code_headers.types),
   }))
 }
 
-#[derive(Debug)]
+/// The URL of the declarations which the X-TypeScript-Types header `types` of
+/// the module `module_name` points to.
+fn types_url(module_name: &str, types: Option<String>) -> Option<String> {
+  let types = types?;
+  match Url::parse(module_name).and_then(|url| url.join(&types)) {
+    Ok(url) => Some(url.to_string()),
+    Err(err) => {
+      debug!(
+        "ignoring X-TypeScript-Types {} of {}: {}",
+        types, module_name, err
+      );
+      None
+    }
+  }
+}
+
+#[derive(Debug, Default)]
 /// Header metadata associated with a particular "symbolic" source code file.
 /// (the associated source code file might not be cached, while remaining
 /// a user accessible entity through imports (due to redirects)).
@@ -763,10 +1255,17 @@ pub struct SourceCodeHeaders {
   /// Where should we actually look for source code.
   /// This should be an absolute path!
   pub redirect_to: Option<String>,
+  /// The X-TypeScript-Types header, relative to the URL of the module.
+  pub types: Option<String>,
+  /// Make reloading the source code conditional.
+  pub validators: CacheValidators,
 }
 
 static MIME_TYPE: &'static str = "mime_type";
 static REDIRECT_TO: &'static str = "redirect_to";
+static TYPES: &'static str = "types";
+static ETAG: &'static str = "etag";
+static LAST_MODIFIED: &'static str = "last_modified";
 
 fn source_code_headers_filename(filename: &str) -> String {
   [&filename, ".headers.json"].concat()
@@ -790,13 +1289,15 @@ fn get_source_code_headers(filename: &str) -> SourceCodeHeaders {
       return SourceCodeHeaders {
         mime_type: headers[MIME_TYPE].as_str().map(String::from),
         redirect_to: headers[REDIRECT_TO].as_str().map(String::from),
+        types: headers[TYPES].as_str().map(String::from),
+        validators: CacheValidators {
+          etag: headers[ETAG].as_str().map(String::from),
+          last_modified: headers[LAST_MODIFIED].as_str().map(String::from),
+        },
       };
     }
   }
-  SourceCodeHeaders {
-    mime_type: None,
-    redirect_to: None,
-  }
+  SourceCodeHeaders::default()
 }
 
 /// Save headers related to source filename to {filename}.headers.json file,
@@ -808,6 +1309,8 @@ fn save_source_code_headers(
   filename: &str,
   mime_type: Option<String>,
   redirect_to: Option<String>,
+  types: Option<String>,
+  validators: &CacheValidators,
 ) {
   let headers_filename = source_code_headers_filename(filename);
   // Remove possibly existing stale .headers.json file.
@@ -837,6 +1340,15 @@ fn save_source_code_headers(
   if redirect_to.is_some() {
     value_map.insert(REDIRECT_TO.to_string(), json!(redirect_to.unwrap()));
   }
+  if let Some(ref types) = types {
+    value_map.insert(TYPES.to_string(), json!(types));
+  }
+  if let Some(ref etag) = validators.etag {
+    value_map.insert(ETAG.to_string(), json!(etag));
+  }
+  if let Some(ref last_modified) = validators.last_modified {
+    value_map.insert(LAST_MODIFIED.to_string(), json!(last_modified));
+  }
   // Only save to file when there is actually data.
   if !value_map.is_empty() {
     let _ = serde_json::to_string(&value_map).map(|s| {
@@ -860,8 +1372,8 @@ mod tests {
 
   fn test_setup() -> (TempDir, DenoDir) {
     let temp_dir = TempDir::new().expect("tempdir fail");
-    let deno_dir =
-      DenoDir::new(Some(temp_dir.path().to_path_buf())).expect("setup fail");
+    let deno_dir = DenoDir::new(Some(temp_dir.path().to_path_buf()), false)
+      .expect("setup fail");
     (temp_dir, deno_dir)
   }
 
@@ -904,7 +1416,8 @@ mod tests {
     let (temp_dir, deno_dir) = test_setup();
     let filename = "hello.js";
     let source_code = b"1+2";
-    let hash = source_code_hash(filename, source_code, version::DENO);
+    let hash =
+      source_code_hash(filename, source_code, &deno_dir.compiler_stamp());
     assert_eq!(
       (
         temp_dir.path().join(format!("gen/{}.js", hash)),
@@ -922,7 +1435,8 @@ mod tests {
     let source_code = b"1+2";
     let output_code = b"1+2 // output code";
     let source_map = b"{}";
-    let hash = source_code_hash(filename, source_code, version::DENO);
+    let hash =
+      source_code_hash(filename, source_code, &deno_dir.compiler_stamp());
     let (cache_path, source_map_path) =
       deno_dir.cache_path(filename, source_code);
     assert!(cache_path.ends_with(format!("gen/{}.js", hash)));
@@ -938,6 +1452,7 @@ mod tests {
       maybe_output_code_filename: None,
       maybe_source_map: Some(source_map[..].to_owned()),
       maybe_source_map_filename: None,
+      maybe_types: None,
     };
 
     let r = deno_dir.code_cache(&out);
@@ -946,6 +1461,44 @@ mod tests {
     assert_eq!(output_code[..].to_owned(), fs::read(&cache_path).unwrap());
   }
 
+  #[test]
+  fn test_fetch_compiled_output_stamp() {
+    let (temp_dir, mut deno_dir) = test_setup();
+    let path = temp_dir.path().join("a.ts");
+    fs::write(&path, "export {};\n").unwrap();
+    let filename = deno_fs::normalize_path(&path);
+    let write_output = |deno_dir: &DenoDir, stamp: &str| {
+      let hash = source_code_hash(&filename, b"export {};\n", stamp);
+      let js = deno_dir.gen.join(format!("{}.js", hash));
+      fs::write(js, "// output").unwrap();
+      let map = deno_dir.gen.join(format!("{}.js.map", hash));
+      fs::write(map, "{}").unwrap();
+    };
+    let fetch = |deno_dir: &DenoDir| {
+      deno_dir
+        .fetch_module_meta_data(&filename, ".", true)
+        .unwrap()
+        .maybe_output_code
+    };
+
+    // The output of another version is compiled again without --reload.
+    write_output(&deno_dir, "0.3.6 3.4.1 {}");
+    assert_eq!(fetch(&deno_dir), None);
+
+    let stamp = deno_dir.compiler_stamp();
+    write_output(&deno_dir, &stamp);
+    assert_eq!(fetch(&deno_dir), Some(b"// output".to_vec()));
+
+    deno_dir.compiler_options = json!({ "strict": true });
+    assert_eq!(fetch(&deno_dir), None);
+
+    // Nor is checked output used without type checking, or the other way
+    // around.
+    deno_dir.compiler_options = json!({});
+    deno_dir.no_check = true;
+    assert_eq!(fetch(&deno_dir), None);
+  }
+
   #[test]
   fn test_source_code_hash() {
     assert_eq!(
@@ -989,6 +1542,11 @@ mod tests {
       &filename,
       Some("text/typescript".to_owned()),
       Some("http://deno.land/a.js".to_owned()),
+      None,
+      &CacheValidators {
+        etag: Some("\"abc\"".to_owned()),
+        last_modified: None,
+      },
     );
     let headers2 = get_source_code_headers(&filename);
     assert_eq!(headers2.mime_type.clone().unwrap(), "text/typescript");
@@ -996,6 +1554,8 @@ mod tests {
       headers2.redirect_to.clone().unwrap(),
       "http://deno.land/a.js"
     );
+    assert_eq!(headers2.validators.etag.unwrap(), "\"abc\"");
+    assert!(headers2.validators.last_modified.is_none());
   }
 
   #[test]
@@ -1020,8 +1580,8 @@ mod tests {
         "export { printHello } from \"./print_hello.ts\";\n".as_bytes()
       );
       assert_eq!(&(r.media_type), &msg::MediaType::TypeScript);
-      // Should not create .headers.json file due to matching ext
-      assert!(fs::read_to_string(&headers_file_name).is_err());
+      // No mime type is recorded due to matching ext
+      assert!(get_source_code_headers(&filename).mime_type.is_none());
 
       // Modify .headers.json, write using fs write and read using save_source_code_headers
       let _ =
@@ -1046,6 +1606,8 @@ mod tests {
         &filename,
         Some("application/json".to_owned()),
         None,
+        None,
+        &CacheValidators::default(),
       );
       let result3 = get_source_code(&deno_dir, module_name, &filename, true);
       assert!(result3.is_ok());
@@ -1070,9 +1632,9 @@ mod tests {
       let expected4 =
         "export { printHello } from \"./print_hello.ts\";\n".as_bytes();
       assert_eq!(r4.source_code, expected4);
-      // Now the old .headers.json file should have gone! Resolved back to TypeScript
+      // Now the old mime type should have gone! Resolved back to TypeScript
       assert_eq!(&(r4.media_type), &msg::MediaType::TypeScript);
-      assert!(fs::read_to_string(&headers_file_name).is_err());
+      assert!(get_source_code_headers(&filename).mime_type.is_none());
     });
   }
 
@@ -1088,7 +1650,6 @@ mod tests {
           .join("localhost_PORT4545/tests/subdir/mismatch_ext.ts")
           .as_ref(),
       );
-      let headers_file_name = source_code_headers_filename(&filename);
 
       let result = get_source_code(&deno_dir, module_name, &filename, true);
       assert!(result.is_ok());
@@ -1107,6 +1668,8 @@ mod tests {
         &filename,
         Some("text/typescript".to_owned()),
         None,
+        None,
+        &CacheValidators::default(),
       );
       let result2 = get_source_code(&deno_dir, module_name, &filename, true);
       assert!(result2.is_ok());
@@ -1116,7 +1679,7 @@ mod tests {
       // If get_source_code does not call remote, this should be TypeScript
       // as we modified before! (we do not overwrite .headers.json due to no http fetch)
       assert_eq!(&(r2.media_type), &msg::MediaType::TypeScript);
-      assert!(fs::read_to_string(&headers_file_name).is_err());
+      assert!(get_source_code_headers(&filename).mime_type.is_none());
 
       // Don't use_cache
       let result3 = get_source_code(&deno_dir, module_name, &filename, false);
@@ -1255,6 +1818,39 @@ mod tests {
     });
   }
 
+  #[test]
+  fn test_get_source_code_cached_only() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let deno_dir = DenoDir::new(Some(temp_dir.path().to_path_buf()), true)
+      .expect("setup fail");
+    tokio_util::init(|| {
+      let module_name = "http://localhost:4545/tests/subdir/mod2.ts";
+      let filename = deno_fs::normalize_path(
+        deno_dir
+          .deps_http
+          .join("localhost_PORT4545/tests/subdir/mod2.ts")
+          .as_ref(),
+      );
+
+      // Not cached yet, so nothing must be downloaded.
+      let result = get_source_code(&deno_dir, module_name, &filename, true);
+      let err = result.unwrap_err();
+      assert_eq!(err.kind(), ErrorKind::NotFound);
+      assert!(err.to_string().contains(module_name));
+      assert!(fs::read_to_string(&filename).is_err());
+
+      // The error names the URL rather than the specifier.
+      let result = deno_dir.fetch_module_meta_data(module_name, ".", true);
+      assert!(result.unwrap_err().to_string().contains("--cached-only"));
+
+      // Once cached the module is used as usual.
+      fs::create_dir_all(Path::new(&filename).parent().unwrap()).unwrap();
+      fs::write(&filename, "export const a = 1;").unwrap();
+      let result = get_source_code(&deno_dir, module_name, &filename, true);
+      assert!(result.is_ok());
+    });
+  }
+
   #[test]
   fn test_fetch_source_async_1() {
     use crate::tokio_util;
@@ -1269,7 +1865,6 @@ mod tests {
           .join("127.0.0.1_PORT4545/tests/subdir/mt_video_mp2t.t3.ts")
           .as_ref(),
       );
-      let headers_file_name = source_code_headers_filename(&filename);
 
       let result = tokio_util::block_on(fetch_remote_source_async(
         &deno_dir,
@@ -1280,14 +1875,16 @@ mod tests {
       let r = result.unwrap().unwrap();
       assert_eq!(r.source_code, b"export const loaded = true;\n");
       assert_eq!(&(r.media_type), &msg::MediaType::TypeScript);
-      // matching ext, no .headers.json file created
-      assert!(fs::read_to_string(&headers_file_name).is_err());
+      // matching ext, no mime type recorded in .headers.json
+      assert!(get_source_code_headers(&filename).mime_type.is_none());
 
       // Modify .headers.json, make sure read from local
       save_source_code_headers(
         &filename,
         Some("text/javascript".to_owned()),
         None,
+        None,
+        &CacheValidators::default(),
       );
       let result2 =
         fetch_local_source(&deno_dir, &module_name, &filename, None);
@@ -1313,21 +1910,22 @@ mod tests {
           .join("localhost_PORT4545/tests/subdir/mt_video_mp2t.t3.ts")
           .as_ref(),
       );
-      let headers_file_name = source_code_headers_filename(&filename);
 
       let result = fetch_remote_source(&deno_dir, module_name, &filename);
       assert!(result.is_ok());
       let r = result.unwrap().unwrap();
       assert_eq!(r.source_code, "export const loaded = true;\n".as_bytes());
       assert_eq!(&(r.media_type), &msg::MediaType::TypeScript);
-      // matching ext, no .headers.json file created
-      assert!(fs::read_to_string(&headers_file_name).is_err());
+      // matching ext, no mime type recorded in .headers.json
+      assert!(get_source_code_headers(&filename).mime_type.is_none());
 
       // Modify .headers.json, make sure read from local
       save_source_code_headers(
         &filename,
         Some("text/javascript".to_owned()),
         None,
+        None,
+        &CacheValidators::default(),
       );
       let result2 = fetch_local_source(&deno_dir, module_name, &filename, None);
       assert!(result2.is_ok());
@@ -1338,6 +1936,121 @@ mod tests {
     });
   }
 
+  #[test]
+  fn test_fetch_source_conditional() {
+    use crate::tokio_util;
+    tokio_util::init(|| {
+      let (_temp_dir, mut deno_dir) = test_setup();
+      let module_name = "http://localhost:4545/etag_script.ts";
+      let filename = deno_fs::normalize_path(
+        deno_dir
+          .deps_http
+          .join("localhost_PORT4545/etag_script.ts")
+          .as_ref(),
+      );
+      let source = "console.log(\"etag\");\n".as_bytes();
+      let r = fetch_remote_source(&deno_dir, module_name, &filename)
+        .unwrap()
+        .unwrap();
+      assert_eq!(r.source_code, source);
+      let headers = get_source_code_headers(&filename);
+      assert_eq!(headers.validators.etag.unwrap(), "\"33a64df5\"");
+
+      // The server answers 304, so the cached copy is kept.
+      fs::write(&filename, "// cached\n").unwrap();
+      let r = fetch_remote_source(&deno_dir, module_name, &filename)
+        .unwrap()
+        .unwrap();
+      assert_eq!(r.source_code, b"// cached\n");
+
+      // --reload=full
+      deno_dir.reload_full = true;
+      let r = fetch_remote_source(&deno_dir, module_name, &filename)
+        .unwrap()
+        .unwrap();
+      assert_eq!(r.source_code, source);
+    });
+  }
+
+  #[test]
+  fn test_fetch_source_types_header() {
+    use crate::tokio_util;
+    tokio_util::init(|| {
+      let (_temp_dir, deno_dir) = test_setup();
+      let module_name = "http://localhost:4545/tests/subdir/type_headers.js";
+      let types = "http://localhost:4545/tests/subdir/type_headers.d.ts";
+      let filename = deno_fs::normalize_path(
+        deno_dir
+          .deps_http
+          .join("localhost_PORT4545/tests/subdir/type_headers.js")
+          .as_ref(),
+      );
+      let r = fetch_remote_source(&deno_dir, module_name, &filename)
+        .unwrap()
+        .unwrap();
+      assert_eq!(r.maybe_types.unwrap(), types);
+      let headers = get_source_code_headers(&filename);
+      assert_eq!(headers.types.unwrap(), "./type_headers.d.ts");
+
+      // The header is kept along with the cached copy.
+      let r = fetch_local_source(&deno_dir, module_name, &filename, None)
+        .unwrap()
+        .unwrap();
+      assert_eq!(r.maybe_types.unwrap(), types);
+    });
+  }
+
+  #[test]
+  fn test_types_url() {
+    let module_name = "https://deno.land/x/a/mod.js";
+    assert_eq!(
+      types_url(module_name, Some("../types/mod.d.ts".to_owned())).unwrap(),
+      "https://deno.land/x/types/mod.d.ts"
+    );
+    assert_eq!(
+      types_url(module_name, Some("https://cdn.land/mod.d.ts".to_owned()))
+        .unwrap(),
+      "https://cdn.land/mod.d.ts"
+    );
+    assert!(types_url(module_name, None).is_none());
+  }
+
+  #[test]
+  fn test_fetch_local_source_media_type() {
+    let (temp_dir, deno_dir) = test_setup();
+    let path = temp_dir.path().join("a.js");
+    fs::write(&path, "export {};\n").unwrap();
+    let filename = deno_fs::normalize_path(&path);
+    let module_name = Url::from_file_path(&path).unwrap().to_string();
+    // Only downloads use recorded headers.
+    save_source_code_headers(
+      &filename,
+      Some("text/typescript".to_owned()),
+      None,
+      None,
+      &CacheValidators::default(),
+    );
+    let r = fetch_local_source(&deno_dir, &module_name, &filename, None)
+      .unwrap()
+      .unwrap();
+    assert_eq!(r.media_type, msg::MediaType::JavaScript);
+  }
+
+  #[test]
+  fn test_fetch_memory_module() {
+    let (temp_dir, deno_dir) = test_setup();
+    let path = temp_dir.path().join("$stdin.ts");
+    let module_name = add_memory_module(&path, b"export {};\n".to_vec());
+    let r = deno_dir
+      .fetch_module_meta_data("./$stdin.ts", &module_name, true)
+      .unwrap();
+    assert_eq!(r.module_name, module_name);
+    assert_eq!(r.filename, deno_fs::normalize_path(&path));
+    assert_eq!(r.media_type, msg::MediaType::TypeScript);
+    assert_eq!(r.source_code, b"export {};\n");
+    assert!(!path.exists());
+  }
+
   #[test]
   fn test_fetch_source_2() {
     use crate::tokio_util;
@@ -1729,7 +2442,9 @@ mod tests {
     let expected_path = cwd.join("..").join(specifier);
     let expected_module_name =
       Url::from_file_path(&expected_path).unwrap().to_string();
-    let expected_filename = deno_fs::normalize_path(&expected_path);
+    // The filename is normalized, the name is not.
+    let expected_filename =
+      deno_fs::normalize_path(&cwd.parent().unwrap().join(specifier));
 
     let (module_name, filename) =
       deno_dir.resolve_module(specifier, "..").unwrap();
@@ -1742,6 +2457,128 @@ mod tests {
     assert_eq!(filename, expected_filename);
   }
 
+  #[test]
+  fn test_resolve_module_file_url() {
+    let (_temp_dir, deno_dir) = test_setup();
+
+    let specifier = file_url!("/a%20b/c/%2E%2E/d%2F..%2Fe.ts");
+    let (module_name, filename) =
+      deno_dir.resolve_module(specifier, ".").unwrap();
+    assert_eq!(module_name, file_url!("/a%20b/d%2F..%2Fe.ts"));
+    assert_eq!(
+      filename,
+      deno_fs::normalize_path(Path::new(add_root!("/a b/e.ts")))
+    );
+
+    if !cfg!(target_os = "windows") {
+      let r = deno_dir.resolve_module("file://deno.land/a.ts", ".");
+      assert_eq!(r.unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+  }
+
+  #[test]
+  fn test_resolve_module_data_url() {
+    let (_temp_dir, deno_dir) = test_setup();
+
+    let specifier = "data:application/typescript;base64,ZXhwb3J0IHt9Owo=";
+    let (module_name, filename) =
+      deno_dir.resolve_module(specifier, ".").unwrap();
+    assert_eq!(module_name, specifier);
+    assert!(filename.starts_with("$data$/"));
+    assert!(filename.ends_with(".ts"));
+    // Absolute imports resolve from a data: URL, relative ones can't.
+    let (module_name, _) = deno_dir
+      .resolve_module("http://localhost:4545/a.ts", specifier)
+      .unwrap();
+    assert_eq!(module_name, "http://localhost:4545/a.ts");
+    assert!(deno_dir.resolve_module("./a.ts", specifier).is_err());
+  }
+
+  #[test]
+  fn test_resolve_module_compat_resolve() {
+    let (temp_dir, mut deno_dir) = test_setup();
+    let src = temp_dir.path().join("src");
+    fs::create_dir_all(src.join("dir")).unwrap();
+    fs::write(src.join("utils.ts"), "").unwrap();
+    fs::write(src.join("utils.js"), "").unwrap();
+    fs::write(src.join("lib.js"), "").unwrap();
+    fs::write(src.join("dir").join("index.ts"), "").unwrap();
+    let referrer = Url::from_file_path(src.join("main.ts")).unwrap();
+    let referrer = referrer.as_str();
+    let resolve = |deno_dir: &DenoDir, specifier: &str| {
+      let (module_name, filename) =
+        deno_dir.resolve_module(specifier, referrer).unwrap();
+      assert_eq!(
+        filename,
+        Url::parse(&module_name)
+          .unwrap()
+          .to_file_path()
+          .unwrap()
+          .to_str()
+          .unwrap()
+      );
+      filename
+    };
+    let file = |name: &str| deno_fs::normalize_path(&src.join(name));
+
+    // Without the flag nothing is probed.
+    assert_eq!(resolve(&deno_dir, "./utils"), file("utils"));
+
+    deno_dir.compat_resolve = true;
+    assert_eq!(resolve(&deno_dir, "./utils"), file("utils.ts"));
+    assert_eq!(resolve(&deno_dir, "./lib"), file("lib.js"));
+    assert_eq!(resolve(&deno_dir, "./dir"), file("dir/index.ts"));
+    assert_eq!(resolve(&deno_dir, "./utils.js"), file("utils.js"));
+    assert_eq!(resolve(&deno_dir, "./missing"), file("missing"));
+
+    // What was found is kept when another candidate comes along.
+    fs::write(src.join("lib.ts"), "").unwrap();
+    assert_eq!(resolve(&deno_dir, "./lib"), file("lib.js"));
+    deno_dir.reload_full = true;
+    assert_eq!(resolve(&deno_dir, "./lib"), file("lib.ts"));
+    deno_dir.reload_full = false;
+    fs::remove_file(src.join("lib.ts")).unwrap();
+    assert_eq!(resolve(&deno_dir, "./lib"), file("lib.js"));
+
+    // Remote imports are never probed.
+    let (module_name, _) = deno_dir
+      .resolve_module("http://localhost:4545/utils", referrer)
+      .unwrap();
+    assert_eq!(module_name, "http://localhost:4545/utils");
+  }
+
+  #[test]
+  fn test_fetch_data_url() {
+    let (_temp_dir, deno_dir) = test_setup();
+
+    let module_name = "data:application/typescript;base64,ZXhwb3J0IHt9Owo=";
+    let r = deno_dir
+      .fetch_module_meta_data(module_name, ".", true)
+      .unwrap();
+    assert_eq!(r.module_name, module_name);
+    assert_eq!(r.media_type, msg::MediaType::TypeScript);
+    assert_eq!(r.source_code, b"export {};\n");
+    assert_eq!(module_origin(module_name), ModuleOrigin::Data);
+
+    let r = fetch_data_url("data:text/javascript,console.log(%22hi%22)#x");
+    let r = r.unwrap();
+    assert_eq!(r.media_type, msg::MediaType::JavaScript);
+    assert_eq!(r.source_code, b"console.log(\"hi\")");
+    assert!(fetch_data_url("data:text/javascript").is_err());
+    assert!(fetch_data_url("data:text/html,<p>").is_err());
+    assert!(fetch_data_url("data:text/javascript;base64,%%").is_err());
+
+    // Its output is kept in memory, not written to gen/.
+    let mut out = r.clone();
+    out.maybe_output_code = Some(b"compiled".to_vec());
+    deno_dir.code_cache(&out).unwrap();
+    assert_eq!(fs::read_dir(&deno_dir.gen).unwrap().count(), 0);
+    let r = deno_dir
+      .fetch_module_meta_data(&out.module_name, ".", true)
+      .unwrap();
+    assert_eq!(r.maybe_output_code, out.maybe_output_code);
+  }
+
   #[test]
   fn test_map_file_extension() {
     assert_eq!(
@@ -1760,6 +2597,10 @@ mod tests {
       map_file_extension(Path::new("foo/bar.json")),
       msg::MediaType::Json
     );
+    assert_eq!(
+      map_file_extension(Path::new("foo/bar.wasm")),
+      msg::MediaType::Wasm
+    );
     assert_eq!(
       map_file_extension(Path::new("foo/bar.txt")),
       msg::MediaType::Unknown
@@ -1807,6 +2648,13 @@ mod tests {
       map_content_type(Path::new("foo/bar"), Some("text/typescript")),
       msg::MediaType::TypeScript
     );
+    assert_eq!(
+      map_content_type(
+        Path::new("foo/bar"),
+        Some("Text/TypeScript ; charset=utf-8")
+      ),
+      msg::MediaType::TypeScript
+    );
     assert_eq!(
       map_content_type(Path::new("foo/bar"), Some("video/vnd.dlna.mpeg-tts")),
       msg::MediaType::TypeScript
@@ -1851,6 +2699,17 @@ mod tests {
       map_content_type(Path::new("foo/bar.ts"), Some("text/plain")),
       msg::MediaType::TypeScript
     );
+    assert_eq!(
+      map_content_type(Path::new("foo/bar"), Some("application/wasm")),
+      msg::MediaType::Wasm
+    );
+    assert_eq!(
+      map_content_type(
+        Path::new("foo/bar.wasm"),
+        Some("application/octet-stream")
+      ),
+      msg::MediaType::Wasm
+    );
     assert_eq!(
       map_content_type(Path::new("foo/bar.ts"), Some("foo/bar")),
       msg::MediaType::Unknown
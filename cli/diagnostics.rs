@@ -0,0 +1,404 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! TypeScript diagnostics, printed like rustc prints errors: the message with
+//! its code, the location and an excerpt of the source marking the span. With
+//! `--diagnostics=json` they are printed as JSON for editors instead.
+use crate::ansi;
+use crate::errors;
+use ansi_term::Color::Blue;
+use ansi_term::Color::Cyan;
+use ansi_term::Color::Red;
+use ansi_term::Color::Yellow;
+use ansi_term::Style;
+use atty;
+use serde_json;
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Lines of source code are cut to this many columns around the span.
+const MAX_LINE_WIDTH: usize = 100;
+/// Spans over more lines only show their first and last lines.
+const MAX_SPAN_LINES: usize = 4;
+const TAB_WIDTH: usize = 4;
+const ELLIPSIS: &str = "...";
+
+/// A zero indexed position in the source, `character` counts UTF-16 code
+/// units like TypeScript does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+  pub line: usize,
+  pub character: usize,
+}
+
+/// A diagnostic as the compiler worker sends it, see js/compiler.ts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+  pub category: String,
+  pub code: i64,
+  pub message: String,
+  pub file_name: Option<String>,
+  pub start: Option<Position>,
+  pub end: Option<Position>,
+  /// The lines from `start` to `end`.
+  pub source_lines: Vec<String>,
+  /// Other places the message refers to, e.g. where a name is declared.
+  pub related: Vec<Diagnostic>,
+}
+
+impl Diagnostic {
+  pub fn from_json_value(v: &Value) -> Option<Self> {
+    let position = |v: Option<&Value>| {
+      v.and_then(|v| {
+        Some(Position {
+          line: v["line"].as_u64()? as usize,
+          character: v["character"].as_u64()? as usize,
+        })
+      })
+    };
+    let strings = |v: Option<&Value>| {
+      v.and_then(|v| v.as_array()).map_or(Vec::new(), |lines| {
+        lines
+          .iter()
+          .filter_map(|line| line.as_str().map(String::from))
+          .collect()
+      })
+    };
+    Some(Diagnostic {
+      category: v.get("category")?.as_str()?.to_string(),
+      code: v.get("code")?.as_i64()?,
+      message: v.get("message")?.as_str()?.to_string(),
+      file_name: v
+        .get("fileName")
+        .and_then(|f| f.as_str())
+        .map(String::from),
+      start: position(v.get("start")),
+      end: position(v.get("end")),
+      source_lines: strings(v.get("sourceLines")),
+      related: from_json_value(v.get("related").unwrap_or(&Value::Null)),
+    })
+  }
+
+  pub fn to_json_value(&self) -> Value {
+    let position = |p: &Option<Position>| match p {
+      Some(p) => json!({ "line": p.line, "character": p.character }),
+      None => Value::Null,
+    };
+    let related: Vec<Value> =
+      self.related.iter().map(|r| r.to_json_value()).collect();
+    json!({
+      "category": self.category,
+      "code": self.code,
+      "message": self.message,
+      "fileName": self.file_name,
+      "start": position(&self.start),
+      "end": position(&self.end),
+      "sourceLines": self.source_lines,
+      "related": related,
+    })
+  }
+
+  /// Renders the diagnostic, colored unless `color` is false.
+  pub fn render(&self, color: bool) -> String {
+    let mut out = String::new();
+    let (label, label_color) = match self.category.as_str() {
+      "error" => ("error", Red),
+      "warning" => ("warning", Yellow),
+      _ => ("note", Cyan),
+    };
+    let header = format!("{}[TS{}]", label, self.code);
+    let mut lines = self.message.lines();
+    writeln!(
+      out,
+      "{}: {}",
+      paint(color, Style::new().bold().fg(label_color), &header),
+      paint(color, Style::new().bold(), lines.next().unwrap_or(""))
+    ).unwrap();
+    for line in lines {
+      writeln!(out, "{}", line).unwrap();
+    }
+    self.render_snippet(&mut out, color, label_color);
+    for related in &self.related {
+      writeln!(
+        out,
+        "{}: {}",
+        paint(color, Style::new().bold().fg(Cyan), "note"),
+        related.message
+      ).unwrap();
+      related.render_snippet(&mut out, color, Cyan);
+    }
+    out
+  }
+
+  fn render_snippet(
+    &self,
+    out: &mut String,
+    color: bool,
+    marker_color: ansi_term::Color,
+  ) {
+    let (file_name, start, end) = match (&self.file_name, self.start, self.end)
+    {
+      (Some(file_name), Some(start), Some(end)) => (file_name, start, end),
+      (Some(file_name), _, _) => {
+        writeln!(out, "  --> {}", file_name).unwrap();
+        return;
+      }
+      _ => return,
+    };
+    let gutter = (end.line + 1).to_string().len();
+    let margin = paint(color, Style::new().bold().fg(Blue), "|");
+    let pad = " ".repeat(gutter);
+    writeln!(
+      out,
+      "{}{} {}:{}:{}",
+      pad,
+      paint(color, Style::new().bold().fg(Blue), "-->"),
+      file_name,
+      start.line + 1,
+      start.character + 1
+    ).unwrap();
+    writeln!(out, "{} {}", pad, margin).unwrap();
+
+    let span_lines = self.source_lines.len();
+    for (i, source_line) in self.source_lines.iter().enumerate() {
+      if span_lines > MAX_SPAN_LINES && i > 0 && i < span_lines - 1 {
+        if i == 1 {
+          writeln!(out, "{}", paint(color, Style::new().bold().fg(Blue), "..."))
+            .unwrap();
+        }
+        continue;
+      }
+      let line = start.line + i;
+      let from = if i == 0 { start.character } else { 0 };
+      let to = if i == span_lines - 1 {
+        end.character
+      } else {
+        utf16_len(source_line)
+      };
+      let (text, marker) = excerpt(source_line, from, to);
+      let line_number = format!("{:>width$}", line + 1, width = gutter);
+      writeln!(
+        out,
+        "{} {} {}",
+        paint(color, Style::new().bold().fg(Blue), &line_number),
+        margin,
+        text
+      ).unwrap();
+      writeln!(
+        out,
+        "{} {} {}",
+        pad,
+        margin,
+        paint(color, Style::new().bold().fg(marker_color), &marker)
+      ).unwrap();
+    }
+  }
+}
+
+/// The diagnostics in the `diagnostics` of a compiler response.
+pub fn from_json_value(v: &Value) -> Vec<Diagnostic> {
+  match v.as_array() {
+    Some(diagnostics) => diagnostics
+      .iter()
+      .filter_map(Diagnostic::from_json_value)
+      .collect(),
+    None => Vec::new(),
+  }
+}
+
+/// Prints the diagnostics of the modules which failed to compile to stdout,
+/// as one JSON array with `json`, and exits. All TypeScript errors are
+/// terminal for deno.
+pub fn exit_with_diagnostics(diagnostics: &[Diagnostic], json: bool) -> ! {
+  if json {
+    let values: Vec<Value> =
+      diagnostics.iter().map(|d| d.to_json_value()).collect();
+    println!("{}", serde_json::to_string(&values).unwrap());
+  } else {
+    let color = ansi::use_color_on(atty::Stream::Stdout);
+    for d in diagnostics {
+      println!("{}", d.render(color));
+    }
+  }
+  std::process::exit(errors::EXIT_COMPILE_ERROR);
+}
+
+fn paint(color: bool, style: Style, s: &str) -> String {
+  if color {
+    style.paint(s).to_string()
+  } else {
+    s.to_string()
+  }
+}
+
+fn utf16_len(s: &str) -> usize {
+  s.chars().map(char::len_utf16).sum()
+}
+
+/// Returns `line` as it is displayed, with tabs expanded and cut around the
+/// span if it is too long, and the carets marking the span below it. `from`
+/// and `to` are in UTF-16 code units.
+fn excerpt(line: &str, from: usize, to: usize) -> (String, String) {
+  // The display columns of each character, and of the span.
+  let mut chars = Vec::new();
+  let mut column = 0;
+  let mut units = 0;
+  let (mut span_start, mut span_end) = (None, None);
+  for c in line.chars() {
+    if units >= from && span_start.is_none() {
+      span_start = Some(column);
+    }
+    if units >= to && span_end.is_none() {
+      span_end = Some(column);
+    }
+    let width = if c == '\t' { TAB_WIDTH } else { 1 };
+    chars.push((c, column, width));
+    column += width;
+    units += c.len_utf16();
+  }
+  let span_start = span_start.unwrap_or(column);
+  let span_end = span_end.unwrap_or(column).max(span_start + 1);
+
+  // The window of columns which is shown.
+  let (mut window_start, mut window_end) = (0, column);
+  if column > MAX_LINE_WIDTH {
+    let span_width = (span_end - span_start).min(MAX_LINE_WIDTH);
+    let context = (MAX_LINE_WIDTH - span_width) / 2;
+    window_start = span_start.saturating_sub(context);
+    window_end = (window_start + MAX_LINE_WIDTH).min(column);
+    window_start = window_end.saturating_sub(MAX_LINE_WIDTH);
+  }
+
+  let mut text = String::new();
+  let mut marker = String::new();
+  let mut offset = 0;
+  if window_start > 0 {
+    text.push_str(ELLIPSIS);
+    offset = ELLIPSIS.len();
+  }
+  for &(c, column, width) in &chars {
+    if column < window_start || column + width > window_end {
+      continue;
+    }
+    if c == '\t' {
+      text.push_str(&" ".repeat(TAB_WIDTH));
+    } else {
+      text.push(c);
+    }
+  }
+  if window_end < column {
+    text.push_str(ELLIPSIS);
+  }
+  let marker_start = span_start.max(window_start) - window_start + offset;
+  let marker_end =
+    span_end.min(window_end).max(span_start + 1) - window_start + offset;
+  marker.push_str(&" ".repeat(marker_start));
+  marker.push_str(&"^".repeat(marker_end.saturating_sub(marker_start).max(1)));
+  (text, marker)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn diagnostic() -> Diagnostic {
+    Diagnostic {
+      category: "error".to_string(),
+      code: 2322,
+      message: "Type '\"a\"' is not assignable to type 'number'.".to_string(),
+      file_name: Some("file:///a.ts".to_string()),
+      start: Some(Position {
+        line: 9,
+        character: 6,
+      }),
+      end: Some(Position {
+        line: 9,
+        character: 7,
+      }),
+      source_lines: vec!["const a: number = \"a\";".to_string()],
+      related: vec![],
+    }
+  }
+
+  #[test]
+  fn test_render() {
+    assert_eq!(
+      diagnostic().render(false),
+      "error[TS2322]: Type '\"a\"' is not assignable to type 'number'.\n\
+       \x20 --> file:///a.ts:10:7\n\
+       \x20  |\n\
+       10 | const a: number = \"a\";\n\
+       \x20  |       ^\n"
+    );
+
+    let d = Diagnostic {
+      category: "warning".to_string(),
+      message: "Chained.\n  Cause.".to_string(),
+      start: Some(Position {
+        line: 0,
+        character: 2,
+      }),
+      end: Some(Position {
+        line: 1,
+        character: 1,
+      }),
+      source_lines: vec!["\tf(a,".to_string(), "b)".to_string()],
+      related: vec![Diagnostic {
+        category: "message".to_string(),
+        message: "Declared here.".to_string(),
+        start: None,
+        source_lines: vec![],
+        ..diagnostic()
+      }],
+      ..diagnostic()
+    };
+    assert_eq!(
+      d.render(false),
+      "warning[TS2322]: Chained.\n\
+       \x20 Cause.\n\
+       \x20--> file:///a.ts:1:3\n\
+       \x20 |\n\
+       1 |     f(a,\n\
+       \x20 |      ^^^\n\
+       2 | b)\n\
+       \x20 | ^\n\
+       note: Declared here.\n\
+       \x20 --> file:///a.ts\n"
+    );
+  }
+
+  #[test]
+  fn test_excerpt() {
+    assert_eq!(excerpt("abc", 1, 2), ("abc".to_string(), " ^".to_string()));
+    // An empty span is marked by one caret.
+    assert_eq!(excerpt("abc", 3, 3), ("abc".to_string(), "   ^".to_string()));
+    // Tabs are expanded, positions are in UTF-16 code units.
+    assert_eq!(
+      excerpt("\t😀x", 3, 4),
+      ("    😀x".to_string(), "     ^".to_string())
+    );
+
+    let long = format!("{}needle{}", "a".repeat(200), "b".repeat(200));
+    let (text, marker) = excerpt(&long, 200, 206);
+    assert!(text.starts_with("...a") && text.ends_with("b..."));
+    assert_eq!(text.len(), MAX_LINE_WIDTH + 2 * ELLIPSIS.len());
+    let caret = marker.find('^').unwrap();
+    assert_eq!(&text[caret..caret + 6], "needle");
+    assert_eq!(marker.trim(), "^^^^^^");
+
+    // Only cut where it is too long.
+    let (text, _) = excerpt(&long, 0, 1);
+    assert!(!text.starts_with(ELLIPSIS) && text.ends_with(ELLIPSIS));
+  }
+
+  #[test]
+  fn test_json() {
+    let d = Diagnostic {
+      related: vec![diagnostic()],
+      ..diagnostic()
+    };
+    let v = d.to_json_value();
+    assert_eq!(v["start"], json!({ "line": 9, "character": 6 }));
+    assert_eq!(v["related"][0]["code"], json!(2322));
+    assert_eq!(Diagnostic::from_json_value(&v), Some(d));
+    assert_eq!(from_json_value(&json!([{ "code": 1 }])), vec![]);
+  }
+}
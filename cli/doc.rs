@@ -0,0 +1,164 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! `deno doc` prints the documentation the compiler takes from a module, see
+//! js/doc.ts: the signature of each export with its JSDoc comment below, and
+//! the members of the classes and interfaces, indented.
+use crate::compiler;
+use crate::errors;
+use crate::errors::RustOrJsError;
+use crate::msg::ErrorKind;
+use crate::state::ThreadSafeState;
+use serde_json::Value;
+
+fn children(node: &Value) -> &[Value] {
+  node["children"].as_array().map_or(&[][..], Vec::as_slice)
+}
+
+/// The nodes `path` names, e.g. the members `read` of the exports `Buffer`
+/// for `Buffer.read`. Overloads share a name, there may be several.
+fn find<'a>(nodes: &'a [Value], path: &str) -> Vec<&'a Value> {
+  let mut names = path.split('.');
+  let first = names.next().unwrap();
+  let mut found: Vec<&Value> =
+    nodes.iter().filter(|node| node["name"] == first).collect();
+  for name in names {
+    found = found
+      .into_iter()
+      .flat_map(children)
+      .filter(|node| node["name"] == name)
+      .collect();
+  }
+  found
+}
+
+fn render_node(node: &Value, indent: usize, out: &mut String) {
+  let pad = " ".repeat(indent);
+  let signature = node["signature"].as_str().unwrap_or("");
+  for line in signature.lines() {
+    out.push_str(&format!("{}{}\n", pad, line));
+  }
+  if let Some(js_doc) = node["jsDoc"].as_str() {
+    for line in js_doc.lines() {
+      if line.is_empty() {
+        out.push('\n');
+      } else {
+        out.push_str(&format!("{}  {}\n", pad, line));
+      }
+    }
+  }
+  let children = children(node);
+  if !children.is_empty() {
+    out.push('\n');
+    for child in children {
+      render_node(child, indent + 2, out);
+    }
+  }
+}
+
+/// The documentation as text, a blank line between the exports.
+fn render(nodes: &[&Value]) -> String {
+  let mut out = String::new();
+  for (i, node) in nodes.iter().enumerate() {
+    if i > 0 {
+      out.push('\n');
+    }
+    render_node(node, 0, &mut out);
+  }
+  out
+}
+
+/// Prints the documentation of `main_module`, as JSON with `--json`, only
+/// that of the symbol of `deno doc <module> <symbol>` if there is one.
+pub fn print(
+  state: &ThreadSafeState,
+  main_module: &str,
+) -> Result<(), RustOrJsError> {
+  let nodes = compiler::doc_sync(state.clone(), main_module, ".")?;
+  let nodes = nodes.as_array().map_or(&[][..], Vec::as_slice);
+  let selected: Vec<&Value> = match state.flags.doc_filter {
+    Some(ref path) => {
+      let found = find(nodes, path);
+      if found.is_empty() {
+        let msg = format!("\"{}\" is not exported by {}", path, main_module);
+        return Err(errors::new(ErrorKind::NotFound, msg).into());
+      }
+      found
+    }
+    None => nodes.iter().collect(),
+  };
+  if state.flags.doc_json {
+    println!("{}", serde_json::to_string_pretty(&selected).unwrap());
+  } else {
+    print!("{}", render(&selected));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn nodes() -> Vec<Value> {
+    let location = json!({"moduleId": "file:///mod.ts", "line": 1});
+    vec![
+      json!({
+        "kind": "class",
+        "name": "Buffer",
+        "signature": "class Buffer implements Reader",
+        "jsDoc": "A growable buffer.\n\nNot thread safe.",
+        "location": location,
+        "children": [
+          {
+            "kind": "method",
+            "name": "read",
+            "signature": "read(p: Uint8Array): number",
+            "jsDoc": "Reads into `p`.",
+            "location": location,
+          },
+          {
+            "kind": "property",
+            "name": "length",
+            "signature": "readonly length: number",
+            "location": location,
+          },
+        ],
+      }),
+      json!({
+        "kind": "function",
+        "name": "read",
+        "signature": "function read(r: Reader): Uint8Array",
+        "location": location,
+      }),
+    ]
+  }
+
+  #[test]
+  fn test_find() {
+    let nodes = nodes();
+    let found = find(&nodes, "read");
+    assert_eq!(found, vec![&nodes[1]]);
+    let found = find(&nodes, "Buffer.read");
+    assert_eq!(found, vec![&nodes[0]["children"][0]]);
+    assert!(find(&nodes, "Buffer.write").is_empty());
+    assert!(find(&nodes, "read.length").is_empty());
+  }
+
+  #[test]
+  fn test_render() {
+    let nodes = nodes();
+    let selected: Vec<&Value> = nodes.iter().collect();
+    assert_eq!(
+      render(&selected),
+      "class Buffer implements Reader
+  A growable buffer.
+
+  Not thread safe.
+
+  read(p: Uint8Array): number
+    Reads into `p`.
+  readonly length: number
+
+function read(r: Reader): Uint8Array
+"
+    );
+  }
+}
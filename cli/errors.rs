@@ -4,6 +4,7 @@ pub use crate::msg::ErrorKind;
 use crate::resolve_addr::ResolveAddrError;
 use deno::JSError;
 use hyper;
+use notify;
 use std;
 use std::fmt;
 use std::io;
@@ -11,6 +12,19 @@ use url;
 
 pub type DenoResult<T> = std::result::Result<T, DenoError>;
 
+// Exit codes of the deno process, so that scripts can tell failures apart.
+// The manual documents them, do not renumber.
+/// An uncaught JavaScript exception.
+pub const EXIT_JS_ERROR: i32 = 1;
+/// Invalid command line flags.
+pub const EXIT_USAGE_ERROR: i32 = 2;
+/// An uncaught permission denial, e.g. under `--no-prompt`.
+pub const EXIT_PERMISSION_DENIED: i32 = 3;
+/// A module could not be resolved or fetched.
+pub const EXIT_MODULE_ERROR: i32 = 4;
+/// TypeScript reported errors, see also `os.exit(5)` in js/compiler.ts.
+pub const EXIT_COMPILE_ERROR: i32 = 5;
+
 #[derive(Debug)]
 pub struct DenoError {
   repr: Repr,
@@ -56,7 +70,8 @@ impl DenoError {
           WriteZero => ErrorKind::WriteZero,
           Other => ErrorKind::Other,
           UnexpectedEof => ErrorKind::UnexpectedEof,
-          _ => unreachable!(),
+          // Kinds added to std later.
+          _ => ErrorKind::Other,
         }
       }
       Repr::UrlErr(ref err) => {
@@ -128,12 +143,24 @@ impl std::error::Error for DenoError {
 impl From<io::Error> for DenoError {
   #[inline]
   fn from(err: io::Error) -> Self {
+    // A DenoError which had to pass as an io error, see `into_io_error()`.
+    if err.get_ref().map_or(false, |e| e.is::<DenoError>()) {
+      return *err.into_inner().unwrap().downcast::<DenoError>().unwrap();
+    }
     Self {
       repr: Repr::IoErr(err),
     }
   }
 }
 
+impl DenoError {
+  /// Wraps the error for the io traits, converting it back into a DenoError
+  /// recovers it with its kind.
+  pub fn into_io_error(self) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, self)
+  }
+}
+
 impl From<url::ParseError> for DenoError {
   #[inline]
   fn from(err: url::ParseError) -> Self {
@@ -168,17 +195,52 @@ impl From<ResolveAddrError> for DenoError {
   }
 }
 
+impl From<notify::Error> for DenoError {
+  fn from(e: notify::Error) -> Self {
+    match e {
+      notify::Error::Io(io_err) => Self {
+        repr: Repr::IoErr(io_err),
+      },
+      notify::Error::PathNotFound => Self {
+        repr: Repr::Simple(ErrorKind::NotFound, "path not found".to_string()),
+      },
+      e => Self {
+        repr: Repr::Simple(ErrorKind::Other, e.to_string()),
+      },
+    }
+  }
+}
+
 pub fn bad_resource() -> DenoError {
   new(ErrorKind::BadResource, String::from("bad resource id"))
 }
 
-pub fn permission_denied() -> DenoError {
+/// The access of `message`, e.g. `read access to "/etc/passwd"`, was denied
+/// at the prompt or had been revoked.
+pub fn permission_denied(message: &str) -> DenoError {
   new(
     ErrorKind::PermissionDenied,
-    String::from("permission denied"),
+    format!("{} was denied", message),
   )
 }
 
+/// Denied without asking, because prompts are off or can't be answered. The
+/// error names `flag`, which allows the access, and `flag=<scope>` which
+/// allows only its resource, if the flag takes a whitelist.
+pub fn permission_denied_without_prompt(
+  message: &str,
+  flag: &str,
+  scope: Option<&str>,
+) -> DenoError {
+  let msg = match scope {
+    Some(scope) => {
+      format!("{}, run again with {} or {}={}", message, flag, flag, scope)
+    }
+    None => format!("{}, run again with {}", message, flag),
+  };
+  new(ErrorKind::PermissionDenied, msg)
+}
+
 pub fn op_not_implemented() -> DenoError {
   new(ErrorKind::OpNotAvaiable, String::from("op not implemented"))
 }
@@ -209,6 +271,28 @@ impl From<JSError> for RustOrJsError {
   }
 }
 
+impl RustOrJsError {
+  /// The code to exit with when this error ends the process. Errors on the
+  /// Rust side come from loading the module graph, errors on the JS side are
+  /// uncaught exceptions.
+  pub fn exit_code(&self) -> i32 {
+    match self {
+      RustOrJsError::Rust(e) if e.kind() == ErrorKind::PermissionDenied => {
+        EXIT_PERMISSION_DENIED
+      }
+      RustOrJsError::Rust(_) => EXIT_MODULE_ERROR,
+      // The DenoErrors of ops carry their kind, an error which only has the
+      // name of one doesn't.
+      RustOrJsError::Js(e)
+        if e.kind == Some(ErrorKind::PermissionDenied as i64) =>
+      {
+        EXIT_PERMISSION_DENIED
+      }
+      RustOrJsError::Js(_) => EXIT_JS_ERROR,
+    }
+  }
+}
+
 impl fmt::Display for RustOrJsError {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     match self {
@@ -1,6 +1,11 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+use crate::completions;
+use crate::shell_words;
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use deno::v8_set_flags;
+use std::collections::HashSet;
+use std::fs;
+use std::net::SocketAddr;
 
 // Creates vector of strings, Vec<String>
 #[cfg(test)]
@@ -8,126 +13,787 @@ macro_rules! svec {
     ($($x:expr),*) => (vec![$($x.to_string()),*]);
 }
 
+/// Verbosity of the CLI, see `--log-level`. Each level includes the ones
+/// after it.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum LogLevel {
+  Debug,
+  Info,
+  Warn,
+  Error,
+}
+
+impl Default for LogLevel {
+  fn default() -> Self {
+    LogLevel::Info
+  }
+}
+
+/// When output is colored, see `--color`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorMode {
+  /// Only on terminals, and not if NO_COLOR is set.
+  Auto,
+  Always,
+  Never,
+}
+
+impl Default for ColorMode {
+  fn default() -> Self {
+    ColorMode::Auto
+  }
+}
+
+/// How `deno fmt` indents, see `--indent`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FmtIndent {
+  Spaces(usize),
+  Tab,
+}
+
+/// The options of `deno fmt`, used both to check and to write files. Unset
+/// options are left to the formatter's defaults.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct FmtConfig {
+  pub line_width: Option<usize>,
+  pub indent: Option<FmtIndent>,
+  pub single_quote: bool,
+  pub no_semicolons: bool,
+}
+
 #[cfg_attr(feature = "cargo-clippy", allow(stutter))]
 #[derive(Clone, Debug, PartialEq, Default)]
 pub struct DenoFlags {
-  pub log_debug: bool,
+  pub log_level: LogLevel,
+  pub quiet: bool,
+  pub color: ColorMode,
   pub version: bool,
+  pub version_json: bool,
   pub reload: bool,
+  pub reload_whitelist: Vec<String>,
+  /// `--reload=full` skips conditional requests.
+  pub reload_full: bool,
+  pub cached_only: bool,
+  /// TypeScript is only transpiled, see `--no-check`.
+  pub no_check: bool,
+  /// Local imports without an extension are probed for, see
+  /// `--compat-resolve`.
+  pub compat_resolve: bool,
+  /// TypeScript diagnostics are printed as JSON, see `--diagnostics`.
+  pub diagnostics_json: bool,
+  /// Uncaught errors also show the frames of deno's own code.
+  pub full_stack_traces: bool,
+  /// Unhandled promise rejections only print a warning, see
+  /// `--unhandled-rejections`.
+  pub unhandled_rejections_warn: bool,
+  /// The names of the ops `--trace-ops` prints, every op if empty.
+  pub trace_ops: Option<Vec<String>>,
+  /// Takes precedence over DENO_DIR, see `--deno-dir`.
+  pub deno_dir: Option<String>,
+  /// Redirects followed per remote module, see `--max-redirects`.
+  pub max_redirects: Option<usize>,
+  /// How many compiler workers compile modules at once, by default one per
+  /// CPU.
+  pub compile_jobs: Option<usize>,
+  pub import_map_path: Option<String>,
+  /// A tsconfig.json whose compiler options are used, see `--config`.
+  pub config_path: Option<String>,
+  pub ca_file: Option<String>,
+  /// In MB, see `--max-heap-size`.
+  pub max_heap_size: Option<u32>,
+  /// In MB, see `--initial-heap-size`.
+  pub initial_heap_size: Option<u32>,
+  pub seed: Option<u32>,
+  /// The options given to V8 with `--v8-flags`.
+  pub v8_flags: Vec<String>,
+  /// Where to serve the DevTools protocol, see `--inspect`.
+  pub inspect: Option<SocketAddr>,
+  /// Like `inspect`, but the entry module waits for the debugger, see
+  /// `--inspect-brk`.
+  pub inspect_brk: Option<SocketAddr>,
+  pub watch: bool,
+  /// The type of a script read from stdin, see `--ext`.
+  pub stdin_ext: Option<String>,
   pub allow_read: bool,
+  pub read_whitelist: Vec<String>,
   pub allow_write: bool,
+  pub write_whitelist: Vec<String>,
   pub allow_net: bool,
+  pub net_whitelist: Vec<String>,
   pub allow_env: bool,
+  pub env_whitelist: Vec<String>,
   pub allow_run: bool,
+  pub run_whitelist: Vec<String>,
   pub allow_high_precision: bool,
+  pub deny_read: bool,
+  pub deny_write: bool,
+  pub deny_net: bool,
+  pub deny_env: bool,
+  pub deny_run: bool,
   pub no_prompts: bool,
+  /// Show the whole stack of the call which asks for a permission at the
+  /// prompt, not only where in the script it is.
+  pub prompt_stack: bool,
+  /// Fail instead of warning about deno flags given after the script.
+  pub strict_flags: bool,
+  /// Start the isolates from the bundles of js/, not from the snapshots.
+  pub no_snapshot: bool,
+  pub lock: Option<String>,
+  pub lock_write: bool,
   pub types: bool,
+  pub types_output: Option<String>,
+  /// The shell of `deno completions <shell>`.
+  pub completions: Option<String>,
   pub prefetch: bool,
+  pub bundle: bool,
+  pub bundle_output: Option<String>,
+  pub bundle_sourcemap: bool,
+  pub doc: bool,
+  pub doc_json: bool,
+  /// The symbol of `deno doc <module> <symbol>`, a path like `Buffer.read`.
+  pub doc_filter: Option<String>,
+  pub install: bool,
+  pub install_force: bool,
+  pub upgrade: bool,
+  /// The version of `deno upgrade --version`, by default the latest one.
+  pub upgrade_version: Option<String>,
+  pub upgrade_output: Option<String>,
+  pub upgrade_dry_run: bool,
+  pub clean: bool,
+  pub clean_dry_run: bool,
   pub info: bool,
+  pub info_json: bool,
+  /// How many levels of dependencies `deno info` lists, all by default.
+  pub info_depth: Option<usize>,
   pub fmt: bool,
+  pub fmt_check: bool,
+  pub fmt_config: FmtConfig,
+  /// The globs of `deno fmt --ignore`.
+  pub fmt_ignore: Vec<String>,
+  /// How many files `deno fmt` formats at once, by default one per CPU.
+  pub fmt_jobs: Option<usize>,
+  pub bench: bool,
+  pub bench_json: bool,
+  /// The regular expression of `deno bench --filter`, which the names of the
+  /// benchmarks to run match.
+  pub bench_filter: Option<String>,
+  /// How often each benchmark is timed, and runs before untimed.
+  pub bench_iterations: Option<usize>,
+  pub bench_warmup: Option<usize>,
+  pub test: bool,
+  /// Only the tests whose name contains it run.
+  pub test_filter: Option<String>,
+  pub test_fail_fast: bool,
+  /// The directory of `deno test --coverage`, where lcov.info is written.
+  pub coverage: Option<String>,
+  /// The globs of `--coverage-include`, which replace the modules covered by
+  /// default, and of `--coverage-exclude`.
+  pub coverage_include: Vec<String>,
+  pub coverage_exclude: Vec<String>,
   pub eval: bool,
+  pub eval_print: bool,
+  pub eval_ts: bool,
+  /// The code of each -e, in the order given.
+  pub eval_fragments: Vec<String>,
 }
 
-impl<'a> From<ArgMatches<'a>> for DenoFlags {
-  fn from(matches: ArgMatches) -> DenoFlags {
-    let mut flags = DenoFlags::default();
+impl DenoFlags {
+  /// Whether informational messages, like "Downloading" and "Compiling", are
+  /// printed.
+  pub fn shows_info(&self) -> bool {
+    !self.quiet && self.log_level <= LogLevel::Info
+  }
+
+  /// Where the inspector listens, for either of `--inspect` and
+  /// `--inspect-brk`. Clap makes sure at most one of them is given.
+  pub fn inspector_addr(&self) -> Option<SocketAddr> {
+    self.inspect.or(self.inspect_brk)
+  }
+
+  /// Grants every permission. This is the single source of truth for what
+  /// `--allow-all` means, so new `allow_*` fields must be added here.
+  pub fn set_all_permissions(&mut self) {
+    self.allow_read = true;
+    self.allow_write = true;
+    self.allow_net = true;
+    self.allow_env = true;
+    self.allow_run = true;
+    self.allow_high_precision = true;
+  }
 
-    if matches.is_present("log-debug") {
-      flags.log_debug = true;
+  /// The flags of `deno run` which set_flags() parses back into these, as far
+  /// as they apply to running a script: the permissions, and how modules are
+  /// loaded, compiled and run. A helper process started with them runs like
+  /// this one. The subcommands and their own flags are left out. Each flag is
+  /// one string, values after `=`.
+  pub fn to_argv(&self) -> Vec<String> {
+    let flag = |name: &str, value: Option<String>| match value {
+      Some(value) => format!("--{}={}", name, value),
+      None => format!("--{}", name),
+    };
+    let mut argv = Vec::new();
+    let permissions = [
+      ("allow-read", self.allow_read, &self.read_whitelist),
+      ("allow-write", self.allow_write, &self.write_whitelist),
+      ("allow-net", self.allow_net, &self.net_whitelist),
+      ("allow-env", self.allow_env, &self.env_whitelist),
+      ("allow-run", self.allow_run, &self.run_whitelist),
+    ];
+    for (name, allow, whitelist) in permissions.iter() {
+      if *allow {
+        argv.push(flag(*name, None));
+      } else if !whitelist.is_empty() {
+        argv.push(flag(*name, Some(whitelist.join(","))));
+      }
     }
-    if matches.is_present("version") {
-      flags.version = true;
+    // --lock-write requires --lock, which is given first.
+    if let Some(ref lock) = self.lock {
+      argv.push(flag("lock", Some(lock.clone())));
     }
-    if matches.is_present("reload") {
-      flags.reload = true;
+    let switches = [
+      ("allow-high-precision", self.allow_high_precision),
+      ("deny-read", self.deny_read),
+      ("deny-write", self.deny_write),
+      ("deny-net", self.deny_net),
+      ("deny-env", self.deny_env),
+      ("deny-run", self.deny_run),
+      ("no-prompt", self.no_prompts),
+      ("prompt-stack", self.prompt_stack),
+      ("lock-write", self.lock_write),
+      ("quiet", self.quiet),
+      ("cached-only", self.cached_only),
+      ("no-check", self.no_check),
+      ("compat-resolve", self.compat_resolve),
+      ("full-stack-traces", self.full_stack_traces),
+      ("watch", self.watch),
+      ("strict-flags", self.strict_flags),
+      ("no-snapshot", self.no_snapshot),
+    ];
+    for (name, on) in switches.iter() {
+      if *on {
+        argv.push(flag(*name, None));
+      }
     }
-    if matches.is_present("allow-read") {
-      flags.allow_read = true;
+    // Only those which have no value parsed back the same way.
+    if self.reload_full {
+      argv.push(flag("reload", Some("full".to_string())));
+    } else if self.reload {
+      argv.push(flag("reload", None));
+    } else if !self.reload_whitelist.is_empty() {
+      argv.push(flag("reload", Some(self.reload_whitelist.join(","))));
     }
-    if matches.is_present("allow-write") {
-      flags.allow_write = true;
+    match self.trace_ops {
+      Some(ref names) if names.is_empty() => argv.push(flag("trace-ops", None)),
+      Some(ref names) => argv.push(flag("trace-ops", Some(names.join(",")))),
+      None => {}
     }
-    if matches.is_present("allow-net") {
-      flags.allow_net = true;
+    let log_level = match self.log_level {
+      LogLevel::Debug => Some("debug"),
+      LogLevel::Info => None,
+      LogLevel::Warn => Some("warn"),
+      LogLevel::Error => Some("error"),
+    };
+    let color = match self.color {
+      ColorMode::Auto => None,
+      ColorMode::Always => Some("always"),
+      ColorMode::Never => Some("never"),
+    };
+    let options = [
+      ("log-level", log_level.map(String::from)),
+      ("color", color.map(String::from)),
+      (
+        "diagnostics",
+        Some("json".to_string()).filter(|_| self.diagnostics_json),
+      ),
+      (
+        "unhandled-rejections",
+        Some("warn".to_string()).filter(|_| self.unhandled_rejections_warn),
+      ),
+      ("deno-dir", self.deno_dir.clone()),
+      ("max-redirects", self.max_redirects.map(|n| n.to_string())),
+      ("compile-jobs", self.compile_jobs.map(|n| n.to_string())),
+      ("importmap", self.import_map_path.clone()),
+      ("config", self.config_path.clone()),
+      ("cert", self.ca_file.clone()),
+      ("max-heap-size", self.max_heap_size.map(|n| n.to_string())),
+      (
+        "initial-heap-size",
+        self.initial_heap_size.map(|n| n.to_string()),
+      ),
+      ("seed", self.seed.map(|n| n.to_string())),
+      ("inspect", self.inspect.map(|addr| addr.to_string())),
+      ("inspect-brk", self.inspect_brk.map(|addr| addr.to_string())),
+      ("ext", self.stdin_ext.clone()),
+    ];
+    for (name, value) in options.iter() {
+      if value.is_some() {
+        argv.push(flag(*name, value.clone()));
+      }
     }
-    if matches.is_present("allow-env") {
-      flags.allow_env = true;
+    if !self.v8_flags.is_empty() {
+      argv.push(flag("v8-flags", Some(self.v8_flags.join(","))));
     }
-    if matches.is_present("allow-run") {
-      flags.allow_run = true;
+    argv
+  }
+}
+
+impl<'a> From<ArgMatches<'a>> for DenoFlags {
+  fn from(matches: ArgMatches) -> DenoFlags {
+    let mut flags = DenoFlags::default();
+
+    if matches.is_present("version") {
+      flags.version = true;
     }
-    if matches.is_present("allow-high-precision") {
-      flags.allow_high_precision = true;
+    if let Some(version_match) = matches.subcommand_matches("version") {
+      flags.version = true;
+      if version_match.is_present("json") {
+        flags.version_json = true;
+      }
     }
-    if matches.is_present("allow-all") {
-      flags.allow_read = true;
-      flags.allow_env = true;
-      flags.allow_net = true;
-      flags.allow_run = true;
-      flags.allow_read = true;
-      flags.allow_write = true;
-      flags.allow_high_precision = true;
+    parse_run_args(&mut flags, &matches);
+    if let Some(run_match) = matches.subcommand_matches("run") {
+      parse_run_args(&mut flags, run_match);
     }
-    if matches.is_present("no-prompt") {
-      flags.no_prompts = true;
+    if let Some(repl_match) = matches.subcommand_matches("repl") {
+      parse_run_args(&mut flags, repl_match);
     }
     if matches.is_present("types") {
       flags.types = true;
     }
+    if let Some(types_match) = matches.subcommand_matches("types") {
+      flags.types = true;
+      flags.types_output = types_match.value_of("output").map(String::from);
+    }
+    if let Some(completions_match) = matches.subcommand_matches("completions") {
+      flags.completions = completions_match.value_of("shell").map(String::from);
+    }
     if matches.is_present("prefetch") {
       flags.prefetch = true;
     }
-    if matches.is_present("info") {
+    if let Some(prefetch_match) = matches.subcommand_matches("prefetch") {
+      flags.prefetch = true;
+      parse_run_args(&mut flags, prefetch_match);
+    }
+    if let Some(bundle_match) = matches.subcommand_matches("bundle") {
+      flags.bundle = true;
+      flags.bundle_output = bundle_match.value_of("out_file").map(String::from);
+      if bundle_match.is_present("sourcemap") {
+        flags.bundle_sourcemap = true;
+      }
+      parse_run_args(&mut flags, bundle_match);
+    }
+    if let Some(doc_match) = matches.subcommand_matches("doc") {
+      flags.doc = true;
+      if doc_match.is_present("json") {
+        flags.doc_json = true;
+      }
+      flags.doc_filter = doc_match.value_of("symbol").map(String::from);
+      parse_run_args(&mut flags, doc_match);
+    }
+    if let Some(install_match) = matches.subcommand_matches("install") {
+      flags.install = true;
+      if install_match.is_present("force") {
+        flags.install_force = true;
+      }
+    }
+    if let Some(upgrade_match) = matches.subcommand_matches("upgrade") {
+      flags.upgrade = true;
+      flags.upgrade_version =
+        upgrade_match.value_of("version").map(String::from);
+      flags.upgrade_output = upgrade_match.value_of("output").map(String::from);
+      if upgrade_match.is_present("dry-run") {
+        flags.upgrade_dry_run = true;
+      }
+    }
+    if let Some(clean_match) = matches.subcommand_matches("clean") {
+      flags.clean = true;
+      if clean_match.is_present("dry-run") {
+        flags.clean_dry_run = true;
+      }
+      if let Some(deno_dir) = clean_match.value_of("deno-dir") {
+        flags.deno_dir = Some(deno_dir.to_string());
+      }
+    }
+    if let Some(info_match) = matches.subcommand_matches("info") {
       flags.info = true;
+      if info_match.is_present("json") {
+        flags.info_json = true;
+      }
+      if let Some(depth) = info_match.value_of("depth") {
+        flags.info_depth = Some(depth.parse().unwrap());
+      }
+      if let Some(deno_dir) = info_match.value_of("deno-dir") {
+        flags.deno_dir = Some(deno_dir.to_string());
+      }
+      parse_color_args(&mut flags, info_match);
     }
-    if matches.is_present("fmt") {
+    if let Some(fmt_match) = matches.subcommand_matches("fmt") {
       flags.fmt = true;
+      if fmt_match.is_present("check") {
+        flags.fmt_check = true;
+      }
+      let config = &mut flags.fmt_config;
+      // Both were validated by clap.
+      if let Some(width) = fmt_match.value_of("line-width") {
+        config.line_width = Some(width.parse().unwrap());
+      }
+      config.indent = match fmt_match.value_of("indent") {
+        None => None,
+        Some("tab") => Some(FmtIndent::Tab),
+        Some(n) => Some(FmtIndent::Spaces(n.parse().unwrap())),
+      };
+      if fmt_match.is_present("single-quote") {
+        config.single_quote = true;
+      }
+      if fmt_match.is_present("no-semicolons") {
+        config.no_semicolons = true;
+      }
+      if let Some(ignore) = fmt_match.values_of("ignore") {
+        flags.fmt_ignore = ignore.map(String::from).collect();
+      }
+      if let Some(jobs) = fmt_match.value_of("jobs") {
+        flags.fmt_jobs = Some(jobs.parse().unwrap());
+      }
+      parse_color_args(&mut flags, fmt_match);
+    }
+    if let Some(bench_match) = matches.subcommand_matches("bench") {
+      flags.bench = true;
+      if bench_match.is_present("json") {
+        flags.bench_json = true;
+      }
+      flags.bench_filter = bench_match.value_of("filter").map(String::from);
+      // Both were validated by clap.
+      if let Some(iterations) = bench_match.value_of("iterations") {
+        flags.bench_iterations = Some(iterations.parse().unwrap());
+      }
+      if let Some(warmup) = bench_match.value_of("warmup") {
+        flags.bench_warmup = Some(warmup.parse().unwrap());
+      }
+      parse_run_args(&mut flags, bench_match);
     }
-    if matches.is_present("eval") {
+    if let Some(test_match) = matches.subcommand_matches("test") {
+      flags.test = true;
+      flags.test_filter = test_match.value_of("filter").map(String::from);
+      if test_match.is_present("fail-fast") {
+        flags.test_fail_fast = true;
+      }
+      if test_match.is_present("coverage") {
+        flags.coverage = Some(
+          test_match
+            .value_of("coverage")
+            .unwrap_or("coverage")
+            .to_string(),
+        );
+      }
+      if let Some(globs) = test_match.values_of("coverage-include") {
+        flags.coverage_include = globs.map(String::from).collect();
+      }
+      if let Some(globs) = test_match.values_of("coverage-exclude") {
+        flags.coverage_exclude = globs.map(String::from).collect();
+      }
+      parse_run_args(&mut flags, test_match);
+    }
+    if let Some(eval_match) = matches.subcommand_matches("eval") {
+      flags.eval = true;
+      if eval_match.is_present("print") {
+        flags.eval_print = true;
+      }
+      if eval_match.is_present("ts") {
+        flags.eval_ts = true;
+      }
+      parse_color_args(&mut flags, eval_match);
+    }
+    if let Some(fragments) = matches.values_of("eval-code") {
       flags.eval = true;
+      flags.eval_fragments = fragments.map(String::from).collect();
     }
 
     flags
   }
 }
 
+/// Parses the flags of `color_args()`.
+fn parse_color_args(flags: &mut DenoFlags, matches: &ArgMatches) {
+  if matches.is_present("no-color") {
+    flags.color = ColorMode::Never;
+  }
+  match matches.value_of("color") {
+    Some("auto") => flags.color = ColorMode::Auto,
+    Some("always") => flags.color = ColorMode::Always,
+    Some("never") => flags.color = ColorMode::Never,
+    _ => {}
+  }
+}
+
+/// Parses the flags added by `add_run_args`. These are accepted both at the
+/// top level and by the `run` subcommand.
+fn parse_run_args(flags: &mut DenoFlags, matches: &ArgMatches) {
+  if matches.is_present("log-debug") {
+    flags.log_level = LogLevel::Debug;
+  }
+  match matches.value_of("log-level") {
+    Some("debug") => flags.log_level = LogLevel::Debug,
+    Some("info") => flags.log_level = LogLevel::Info,
+    Some("warn") => flags.log_level = LogLevel::Warn,
+    Some("error") => flags.log_level = LogLevel::Error,
+    _ => {}
+  }
+  if matches.is_present("quiet") {
+    flags.quiet = true;
+  }
+  parse_color_args(flags, matches);
+  if matches.is_present("reload") {
+    if matches.value_of("reload") == Some("full") {
+      flags.reload = true;
+      flags.reload_full = true;
+    } else if matches.value_of("reload").is_some() {
+      let reload_wl = matches.values_of("reload").unwrap();
+      flags.reload_whitelist =
+        reload_wl.map(std::string::ToString::to_string).collect();
+      debug!("reload whitelist: {:#?}", &flags.reload_whitelist);
+    } else {
+      flags.reload = true;
+    }
+  }
+  if matches.is_present("cached-only") {
+    flags.cached_only = true;
+  }
+  if matches.is_present("no-check") {
+    flags.no_check = true;
+  }
+  if matches.is_present("compat-resolve") {
+    flags.compat_resolve = true;
+  }
+  if matches.value_of("diagnostics") == Some("json") {
+    flags.diagnostics_json = true;
+  }
+  if matches.is_present("full-stack-traces") {
+    flags.full_stack_traces = true;
+  }
+  if matches.value_of("unhandled-rejections") == Some("warn") {
+    flags.unhandled_rejections_warn = true;
+  }
+  if matches.is_present("trace-ops") {
+    flags.trace_ops = Some(match matches.values_of("trace-ops") {
+      Some(names) => names.map(std::string::ToString::to_string).collect(),
+      None => vec![],
+    });
+  }
+  if let Some(deno_dir) = matches.value_of("deno-dir") {
+    flags.deno_dir = Some(deno_dir.to_string());
+  }
+  // Checked by is_max_redirects().
+  if let Some(max) = matches.value_of("max-redirects") {
+    flags.max_redirects = Some(max.parse().unwrap());
+  }
+  // Checked by is_jobs().
+  if let Some(jobs) = matches.value_of("compile-jobs") {
+    flags.compile_jobs = Some(jobs.parse().unwrap());
+  }
+  // The values were checked by is_heap_size().
+  if let Some(size) = matches.value_of("max-heap-size") {
+    flags.max_heap_size = Some(size.parse().unwrap());
+  }
+  if let Some(size) = matches.value_of("initial-heap-size") {
+    flags.initial_heap_size = Some(size.parse().unwrap());
+  }
+  // Checked by is_seed().
+  if let Some(seed) = matches.value_of("seed") {
+    flags.seed = Some(seed.parse().unwrap());
+  }
+  // Checked by is_inspect_addr().
+  let inspect_addr = |name: &str| -> SocketAddr {
+    let addr = matches.value_of(name).unwrap_or(DEFAULT_INSPECT_ADDR);
+    addr.parse().unwrap()
+  };
+  if matches.is_present("inspect") {
+    flags.inspect = Some(inspect_addr("inspect"));
+  }
+  if matches.is_present("inspect-brk") {
+    flags.inspect_brk = Some(inspect_addr("inspect-brk"));
+  }
+  if let Some(ca_file) = matches.value_of("cert") {
+    flags.ca_file = Some(ca_file.to_string());
+  }
+  if let Some(import_map_path) = matches.value_of("importmap") {
+    flags.import_map_path = Some(import_map_path.to_string());
+  }
+  if let Some(config_path) = matches.value_of("config") {
+    flags.config_path = Some(config_path.to_string());
+  }
+  if matches.is_present("allow-read") {
+    if matches.value_of("allow-read").is_some() {
+      let read_wl = matches.values_of("allow-read").unwrap();
+      flags.read_whitelist =
+        read_wl.map(std::string::ToString::to_string).collect();
+      debug!("read whitelist: {:#?}", &flags.read_whitelist);
+    } else {
+      flags.allow_read = true;
+    }
+  }
+  if matches.is_present("allow-write") {
+    if matches.value_of("allow-write").is_some() {
+      let write_wl = matches.values_of("allow-write").unwrap();
+      flags.write_whitelist =
+        write_wl.map(std::string::ToString::to_string).collect();
+      debug!("write whitelist: {:#?}", &flags.write_whitelist);
+    } else {
+      flags.allow_write = true;
+    }
+  }
+  if matches.is_present("allow-net") {
+    if matches.value_of("allow-net").is_some() {
+      let net_wl = matches.values_of("allow-net").unwrap();
+      flags.net_whitelist =
+        net_wl.map(std::string::ToString::to_string).collect();
+      debug!("net whitelist: {:#?}", &flags.net_whitelist);
+    } else {
+      flags.allow_net = true;
+    }
+  }
+  if matches.is_present("allow-env") {
+    if matches.value_of("allow-env").is_some() {
+      let env_wl = matches.values_of("allow-env").unwrap();
+      flags.env_whitelist =
+        env_wl.map(std::string::ToString::to_string).collect();
+      debug!("env whitelist: {:#?}", &flags.env_whitelist);
+    } else {
+      flags.allow_env = true;
+    }
+  }
+  if matches.is_present("allow-run") {
+    if matches.value_of("allow-run").is_some() {
+      let run_wl = matches.values_of("allow-run").unwrap();
+      flags.run_whitelist =
+        run_wl.map(std::string::ToString::to_string).collect();
+      debug!("run whitelist: {:#?}", &flags.run_whitelist);
+    } else {
+      flags.allow_run = true;
+    }
+  }
+  if matches.is_present("allow-high-precision") {
+    flags.allow_high_precision = true;
+  }
+  if matches.is_present("allow-all") {
+    flags.set_all_permissions();
+  }
+  if matches.is_present("deny-read") {
+    flags.deny_read = true;
+  }
+  if matches.is_present("deny-write") {
+    flags.deny_write = true;
+  }
+  if matches.is_present("deny-net") {
+    flags.deny_net = true;
+  }
+  if matches.is_present("deny-env") {
+    flags.deny_env = true;
+  }
+  if matches.is_present("deny-run") {
+    flags.deny_run = true;
+  }
+  if let Some(lock) = matches.value_of("lock") {
+    flags.lock = Some(lock.to_string());
+  }
+  if matches.is_present("lock-write") {
+    flags.lock_write = true;
+  }
+  if matches.is_present("no-prompt") {
+    flags.no_prompts = true;
+  }
+  if matches.is_present("prompt-stack") {
+    flags.prompt_stack = true;
+  }
+  if matches.is_present("watch") {
+    flags.watch = true;
+  }
+  if matches.is_present("strict-flags") {
+    flags.strict_flags = true;
+  }
+  if matches.is_present("no-snapshot") {
+    flags.no_snapshot = true;
+  }
+  if let Some(ext) = matches.value_of("ext") {
+    flags.stdin_ext = Some(ext.to_string());
+  }
+}
+
 static ENV_VARIABLES_HELP: &str = "ENVIRONMENT VARIABLES:
     DENO_DIR        Set deno's base directory
+    DENO_CERT       Load root certificates from a PEM file, like --cert
+    DENO_FLAGS      Flags to run with, like those of --flags-file
     NO_COLOR        Set to disable color";
 
-fn create_cli_app<'a, 'b>() -> App<'a, 'b> {
-  let cli_app = App::new("deno")
-    .bin_name("deno")
-    .global_settings(&[AppSettings::ColorNever])
-    .settings(&[
-      AppSettings::AllowExternalSubcommands,
-      AppSettings::DisableHelpSubcommand,
-    ]).after_help(ENV_VARIABLES_HELP)
+/// Also accepted by `deno info`, which shows the directory in effect, and by
+/// `deno clean`.
+fn deno_dir_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("deno-dir")
+    .long("deno-dir")
+    .value_name("DIR")
+    .takes_value(true)
+    .help("Set deno's base directory, overriding DENO_DIR")
+}
+
+/// Also accepted by the subcommands which print more than a script does.
+fn color_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+  vec![
+    Arg::with_name("color")
+      .long("color")
+      .value_name("WHEN")
+      .takes_value(true)
+      .possible_values(&["auto", "always", "never"])
+      .help("Color the output, auto only colors terminals without NO_COLOR"),
+    Arg::with_name("no-color")
+      .long("no-color")
+      .conflicts_with("color")
+      .help("Do not color the output, same as --color=never"),
+  ]
+}
+
+/// Adds the flags that control how a script is run. They are shared between
+/// the top level (`deno [FLAGS] script.ts`) and `deno run [FLAGS] script.ts`.
+fn add_run_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+  app
     .arg(
-      Arg::with_name("version")
-        .short("v")
-        .long("version")
-        .help("Print the version"),
-    ).arg(
       Arg::with_name("allow-read")
         .long("allow-read")
-        .help("Allow file system read access"),
+        .min_values(0)
+        .takes_value(true)
+        .use_delimiter(true)
+        .require_equals(true)
+        .help("Allow file system read access (optionally to a list of paths)"),
     ).arg(
       Arg::with_name("allow-write")
         .long("allow-write")
-        .help("Allow file system write access"),
+        .min_values(0)
+        .takes_value(true)
+        .use_delimiter(true)
+        .require_equals(true)
+        .help("Allow file system write access (optionally to a list of paths)"),
     ).arg(
       Arg::with_name("allow-net")
         .long("allow-net")
-        .help("Allow network access"),
+        .min_values(0)
+        .takes_value(true)
+        .use_delimiter(true)
+        .require_equals(true)
+        .help("Allow network access (optionally to a list of hosts)"),
     ).arg(
       Arg::with_name("allow-env")
         .long("allow-env")
-        .help("Allow environment access"),
+        .min_values(0)
+        .takes_value(true)
+        .use_delimiter(true)
+        .require_equals(true)
+        .help("Allow environment access (optionally to a list of variables)"),
     ).arg(
       Arg::with_name("allow-run")
         .long("allow-run")
-        .help("Allow running subprocesses"),
+        .min_values(0)
+        .takes_value(true)
+        .use_delimiter(true)
+        .require_equals(true)
+        .help("Allow running subprocesses (optionally a list of commands)"),
     ).arg(
       Arg::with_name("allow-high-precision")
         .long("allow-high-precision")
@@ -137,24 +803,154 @@ fn create_cli_app<'a, 'b>() -> App<'a, 'b> {
         .short("A")
         .long("allow-all")
         .help("Allow all permissions"),
+    ).arg(
+      Arg::with_name("deny-read")
+        .long("deny-read")
+        .help("Deny file system read access, overriding any allow flag"),
+    ).arg(
+      Arg::with_name("deny-write")
+        .long("deny-write")
+        .help("Deny file system write access, overriding any allow flag"),
+    ).arg(
+      Arg::with_name("deny-net")
+        .long("deny-net")
+        .help("Deny network access, overriding any allow flag"),
+    ).arg(
+      Arg::with_name("deny-env")
+        .long("deny-env")
+        .help("Deny environment access, overriding any allow flag"),
+    ).arg(
+      Arg::with_name("deny-run")
+        .long("deny-run")
+        .help("Deny running subprocesses, overriding any allow flag"),
     ).arg(
       Arg::with_name("no-prompt")
         .long("no-prompt")
         .help("Do not use prompts"),
+    ).arg(
+      Arg::with_name("prompt-stack")
+        .long("prompt-stack")
+        .help("Show the whole stack of the calls which prompt"),
+    ).arg(
+      Arg::with_name("lock")
+        .long("lock")
+        .value_name("FILE")
+        .takes_value(true)
+        .require_equals(true)
+        .help("Check remote modules against the SHA-256 hashes in a lock file"),
+    ).arg(
+      Arg::with_name("lock-write")
+        .long("lock-write")
+        .requires("lock")
+        .help("Write the hashes of all remote modules to the lock file"),
     ).arg(
       Arg::with_name("log-debug")
         .short("D")
         .long("log-debug")
-        .help("Log debug output"),
+        .conflicts_with("log-level")
+        .help("Log debug output, same as --log-level=debug"),
     ).arg(
+      Arg::with_name("log-level")
+        .long("log-level")
+        .value_name("LEVEL")
+        .takes_value(true)
+        .possible_values(&["debug", "info", "warn", "error"])
+        .help("Set the log level, above info downloads are not announced"),
+    ).arg(
+      Arg::with_name("quiet")
+        .short("q")
+        .long("quiet")
+        .help("Suppress diagnostic output, errors are still printed"),
+    ).args(&color_args())
+    .arg(
       Arg::with_name("reload")
         .short("r")
         .long("reload")
-        .help("Reload source code cache (recompile TypeScript)"),
+        .min_values(0)
+        .takes_value(true)
+        .use_delimiter(true)
+        .require_equals(true)
+        .help(
+          "Reload changed modules (=full for all, or the given URL prefixes)",
+        ),
     ).arg(
-      Arg::with_name("v8-options")
-        .long("v8-options")
-        .help("Print V8 command line options"),
+      Arg::with_name("no-check")
+        .long("no-check")
+        .help("Skip type checking, only syntax errors are reported"),
+    ).arg(
+      Arg::with_name("compat-resolve")
+        .long("compat-resolve")
+        .help("Probe for the extension or index file of local imports"),
+    ).arg(
+      Arg::with_name("diagnostics")
+        .long("diagnostics")
+        .value_name("FORMAT")
+        .takes_value(true)
+        .possible_values(&["pretty", "json"])
+        .help("Print TypeScript diagnostics for people or as JSON for editors"),
+    ).arg(
+      Arg::with_name("full-stack-traces")
+        .long("full-stack-traces")
+        .help("Include the frames of deno's own code in stack traces"),
+    ).arg(
+      Arg::with_name("unhandled-rejections")
+        .long("unhandled-rejections")
+        .value_name("MODE")
+        .takes_value(true)
+        .possible_values(&["error", "warn"])
+        .help("Fail on promises rejected without a handler, or only warn"),
+    ).arg(
+      Arg::with_name("trace-ops")
+        .long("trace-ops")
+        .min_values(0)
+        .takes_value(true)
+        .use_delimiter(true)
+        .require_equals(true)
+        .help("Print every op to stderr (optionally only the ops named)"),
+    ).arg(
+      Arg::with_name("cached-only")
+        .long("cached-only")
+        .help("Only use modules from the cache, never download them"),
+    ).arg(deno_dir_arg())
+    .arg(
+      Arg::with_name("max-redirects")
+        .long("max-redirects")
+        .value_name("N")
+        .takes_value(true)
+        .validator(is_max_redirects)
+        .help("Follow at most N redirects per remote module (default 10)"),
+    ).arg(
+      Arg::with_name("compile-jobs")
+        .long("compile-jobs")
+        .value_name("N")
+        .takes_value(true)
+        .validator(is_jobs)
+        .help("Compile N modules at once, by default one per CPU"),
+    ).arg(
+      Arg::with_name("cert")
+        .long("cert")
+        .value_name("FILE")
+        .takes_value(true)
+        .help("Load root certificates from a PEM file to fetch modules with"),
+    ).arg(
+      Arg::with_name("flags-file")
+        .long("flags-file")
+        .value_name("FILE")
+        .takes_value(true)
+        .help("Read more flags from a file, the flags given take precedence"),
+    ).arg(
+      Arg::with_name("importmap")
+        .long("importmap")
+        .value_name("FILE")
+        .takes_value(true)
+        .help("Load an import map to remap module specifiers"),
+    ).arg(
+      Arg::with_name("config")
+        .short("c")
+        .long("config")
+        .value_name("FILE")
+        .takes_value(true)
+        .help("Load compiler options from a tsconfig.json"),
     ).arg(
       Arg::with_name("v8-flags")
         .long("v8-flags")
@@ -162,234 +958,3119 @@ fn create_cli_app<'a, 'b>() -> App<'a, 'b> {
         .require_equals(true)
         .help("Set V8 command line options"),
     ).arg(
-      Arg::with_name("types")
-        .long("types")
-        .help("Print runtime TypeScript declarations"),
+      Arg::with_name("max-heap-size")
+        .long("max-heap-size")
+        .value_name("MB")
+        .takes_value(true)
+        .validator(is_heap_size)
+        .help("Set the maximum size of the JavaScript heap in MB"),
     ).arg(
-      Arg::with_name("prefetch")
-        .long("prefetch")
-        .help("Prefetch the dependencies"),
-    ).subcommand(
-      SubCommand::with_name("info")
-        .setting(AppSettings::DisableVersion)
-        .about("Show source file related info")
-        .arg(Arg::with_name("file").takes_value(true).required(true)),
-    ).subcommand(
-      SubCommand::with_name("eval")
-        .setting(AppSettings::DisableVersion)
-        .about("Eval script")
-        .arg(Arg::with_name("code").takes_value(true).required(true)),
-    ).subcommand(
-      SubCommand::with_name("fmt")
-        .setting(AppSettings::DisableVersion)
-        .about("Format files")
-        .arg(
-          Arg::with_name("files")
-            .takes_value(true)
-            .multiple(true)
-            .required(true),
-        ),
-    ).subcommand(
-      // this is a fake subcommand - it's used in conjunction with
-      // AppSettings:AllowExternalSubcommand to treat it as an
-      // entry point script
-      SubCommand::with_name("<script>").about("Script to run"),
-    );
-
-  cli_app
-}
-
-#[cfg_attr(feature = "cargo-clippy", allow(stutter))]
-pub fn set_flags(
-  args: Vec<String>,
-) -> Result<(DenoFlags, Vec<String>), String> {
-  let mut rest_argv: Vec<String> = vec!["deno".to_string()];
-  let cli_app = create_cli_app();
-  let matches = cli_app.get_matches_from(args);
-
+      Arg::with_name("initial-heap-size")
+        .long("initial-heap-size")
+        .value_name("MB")
+        .takes_value(true)
+        .validator(is_heap_size)
+        .help("Set the initial size of the JavaScript heap in MB"),
+    ).arg(
+      Arg::with_name("seed")
+        .long("seed")
+        .value_name("NUMBER")
+        .takes_value(true)
+        .validator(is_seed)
+        .help("Seed Math.random() and V8's hash tables for reproducible runs"),
+    ).arg(
+      Arg::with_name("inspect")
+        .long("inspect")
+        .value_name("HOST:PORT")
+        .min_values(0)
+        .takes_value(true)
+        .require_equals(true)
+        .validator(is_inspect_addr)
+        .conflicts_with("inspect-brk")
+        .help("Serve the DevTools protocol (default 127.0.0.1:9229)"),
+    ).arg(
+      Arg::with_name("inspect-brk")
+        .long("inspect-brk")
+        .value_name("HOST:PORT")
+        .min_values(0)
+        .takes_value(true)
+        .require_equals(true)
+        .validator(is_inspect_addr)
+        .help("Like --inspect, but wait for the debugger and break on start"),
+    ).arg(
+      Arg::with_name("watch")
+        .long("watch")
+        .help("Restart the script when one of its local files changes"),
+    ).arg(
+      Arg::with_name("strict-flags")
+        .long("strict-flags")
+        .help("Fail on deno flags which come after the script, not warn"),
+    ).arg(
+      Arg::with_name("no-snapshot")
+        .long("no-snapshot")
+        .help("Start from source instead of the snapshots, for debugging"),
+    ).arg(
+      Arg::with_name("ext")
+        .long("ext")
+        .value_name("EXT")
+        .takes_value(true)
+        .possible_values(&["ts", "js"])
+        .help("Set the type of the script read from stdin by -, default ts"),
+    )
+}
+
+const DEFAULT_INSPECT_ADDR: &str = "127.0.0.1:9229";
+
+/// The fake subcommand which stands for the script, see create_cli_app().
+const SCRIPT_SUBCOMMAND: &str = "<script>";
+
+fn is_inspect_addr(value: String) -> Result<(), String> {
+  match value.parse::<SocketAddr>() {
+    Ok(_) => Ok(()),
+    Err(_) => Err(format!("{} is not a HOST:PORT address", value)),
+  }
+}
+
+/// V8 takes the seed as a signed int and treats 0 as "random".
+fn is_seed(value: String) -> Result<(), String> {
+  match value.parse::<i32>() {
+    Ok(seed) if seed > 0 => Ok(()),
+    _ => Err(format!("{} is not a positive 32 bit integer", value)),
+  }
+}
+
+fn is_max_redirects(value: String) -> Result<(), String> {
+  match value.parse::<usize>() {
+    Ok(_) => Ok(()),
+    Err(_) => Err(format!("{} is not a number of redirects", value)),
+  }
+}
+
+fn is_line_width(value: String) -> Result<(), String> {
+  match value.parse::<usize>() {
+    Ok(width) if width > 0 => Ok(()),
+    _ => Err(format!("{} is not a positive line width", value)),
+  }
+}
+
+fn is_depth(value: String) -> Result<(), String> {
+  match value.parse::<usize>() {
+    Ok(_) => Ok(()),
+    _ => Err(format!("{} is not a valid depth", value)),
+  }
+}
+
+fn is_jobs(value: String) -> Result<(), String> {
+  match value.parse::<usize>() {
+    Ok(jobs) if jobs > 0 => Ok(()),
+    _ => Err(format!("{} is not a positive number of jobs", value)),
+  }
+}
+
+fn is_iterations(value: String) -> Result<(), String> {
+  match value.parse::<usize>() {
+    Ok(iterations) if iterations > 0 => Ok(()),
+    _ => Err(format!("{} is not a positive number of iterations", value)),
+  }
+}
+
+fn is_warmup(value: String) -> Result<(), String> {
+  match value.parse::<usize>() {
+    Ok(_) => Ok(()),
+    _ => Err(format!("{} is not a number of warmup runs", value)),
+  }
+}
+
+fn is_indent(value: String) -> Result<(), String> {
+  if value == "tab" {
+    return Ok(());
+  }
+  match value.parse::<usize>() {
+    Ok(n) if n > 0 => Ok(()),
+    _ => Err(format!("{} is not a positive number of spaces or tab", value)),
+  }
+}
+
+fn is_heap_size(value: String) -> Result<(), String> {
+  match value.parse::<u32>() {
+    Ok(size) if size > 0 => Ok(()),
+    _ => Err(format!("{} is not a positive number of MB", value)),
+  }
+}
+
+pub fn create_cli_app<'a, 'b>() -> App<'a, 'b> {
+  let cli_app = App::new("deno")
+    .bin_name("deno")
+    .global_settings(&[AppSettings::ColorNever])
+    .settings(&[
+      AppSettings::AllowExternalSubcommands,
+      AppSettings::DisableHelpSubcommand,
+    ]).after_help(ENV_VARIABLES_HELP)
+    .arg(
+      Arg::with_name("version")
+        .short("v")
+        .long("version")
+        .help("Print the version"),
+    ).arg(
+      Arg::with_name("eval-code")
+        .short("e")
+        .long("eval")
+        .value_name("CODE")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help("Eval code, the code of all -e runs as one script"),
+    );
+
+  add_run_args(cli_app)
+    .arg(
+      Arg::with_name("v8-options")
+        .long("v8-options")
+        .help("Print V8 command line options"),
+    ).arg(
+      Arg::with_name("types")
+        .long("types")
+        .help("Print runtime TypeScript declarations (alias of deno types)"),
+    ).arg(
+      Arg::with_name("prefetch")
+        .long("prefetch")
+        .help("Prefetch the dependencies (deprecated, use deno prefetch)"),
+    ).subcommand(
+      SubCommand::with_name("info")
+        .setting(AppSettings::DisableVersion)
+        .about("Show source file related info")
+        .long_about(
+          "Show where a module and its compiled output are cached and list its
+dependencies as a tree, with the size of each module and whether it came from
+the cache or the network. A module whose dependencies are listed further up is
+marked with …, an import which cycles back with (cycle). Without a file, show
+the DENO_DIR in effect and how it was set, the size of its caches and the
+environment variables deno reads.
+
+  deno info https://deno.land/std/http/file_server.ts
+  deno info --depth 1 https://deno.land/std/http/file_server.ts
+  deno info",
+        ).arg(Arg::with_name("file").takes_value(true))
+        .arg(
+          Arg::with_name("json")
+            .long("json")
+            .help("Output info as JSON, e.g. to be consumed by other tools"),
+        ).arg(
+          Arg::with_name("depth")
+            .long("depth")
+            .value_name("N")
+            .takes_value(true)
+            .validator(is_depth)
+            .help("List dependencies only N levels deep"),
+        ).arg(deno_dir_arg())
+        .args(&color_args()),
+    ).subcommand(
+      add_run_args(
+        SubCommand::with_name("prefetch")
+          .setting(AppSettings::DisableVersion)
+          .about("Download and compile a module and its dependencies")
+          .long_about(
+            "Download and compile a module and all of its dependencies into
+DENO_DIR without running it. Each module is printed as it is loaded and the
+exit code is non-zero if any of them fails to download or compile.
+
+  deno prefetch https://deno.land/std/http/file_server.ts",
+          ).arg(Arg::with_name("file").takes_value(true).required(true)),
+      ),
+    ).subcommand(
+      add_run_args(
+        SubCommand::with_name("bundle")
+          .setting(AppSettings::DisableVersion)
+          .about("Bundle a module and its dependencies into a single file")
+          .long_about(
+            "Type check a module and all of its dependencies and write them as
+a single self-contained script, to out_file or stdout. The script runs with
+deno like the module it was generated from.
+
+  deno bundle https://deno.land/std/http/file_server.ts file_server.js",
+          ).arg(
+            Arg::with_name("source_file")
+              .takes_value(true)
+              .required(true),
+          ).arg(Arg::with_name("out_file").takes_value(true))
+          .arg(
+            Arg::with_name("sourcemap")
+              .long("sourcemap")
+              .help("Include an inline source map of all modules"),
+          ),
+      ),
+    ).subcommand(
+      add_run_args(
+        SubCommand::with_name("doc")
+          .setting(AppSettings::DisableVersion)
+          .about("Show the documentation of a module")
+          .long_about(
+            "Print the signatures of the functions, classes, interfaces and
+type aliases a module exports, with their JSDoc comments. The module is
+parsed, not run, also what it re-exports from other modules.
+
+  deno doc https://deno.land/std/http/server.ts
+  deno doc https://deno.land/std/http/server.ts ServerRequest.respond
+  deno doc --json mod.ts",
+          ).arg(
+            Arg::with_name("source_file")
+              .takes_value(true)
+              .required(true),
+          ).arg(
+            Arg::with_name("symbol")
+              .takes_value(true)
+              .help("Only show this symbol, or a member like Buffer.read"),
+          ).arg(
+            Arg::with_name("json")
+              .long("json")
+              .help("Print the documentation as JSON"),
+          ),
+      ),
+    ).subcommand(
+      SubCommand::with_name("install")
+        .settings(&[AppSettings::DisableVersion, AppSettings::TrailingVarArg])
+        .about("Install a script as an executable")
+        .long_about(
+          "Write a small executable to ~/.deno/bin which runs a script with
+the flags given after its URL:
+
+  deno install serve https://deno.land/std/http/file_server.ts --allow-net
+
+Add ~/.deno/bin to PATH to run the script as `serve`.",
+        ).arg(
+          Arg::with_name("force")
+            .short("f")
+            .long("force")
+            .help("Overwrite an existing installation"),
+        ).arg(Arg::with_name("exe_name").takes_value(true).required(true))
+        .arg(Arg::with_name("module").takes_value(true).required(true))
+        .arg(
+          Arg::with_name("args")
+            .takes_value(true)
+            .multiple(true)
+            .allow_hyphen_values(true),
+        ),
+    ).subcommand(
+      SubCommand::with_name("upgrade")
+        .setting(AppSettings::DisableVersion)
+        .about("Upgrade deno to the latest or a given version")
+        .long_about(
+          "Replace the deno executable with a release downloaded from GitHub,
+the latest one unless a version is given. The download is checked against
+the checksum published with the release. It needs no --allow-net, no script
+runs:
+
+  deno upgrade
+  deno upgrade --version 0.4.0
+  deno upgrade --output ~/bin/deno-next",
+        ).arg(
+          Arg::with_name("version")
+            .long("version")
+            .value_name("VERSION")
+            .takes_value(true)
+            .help("The version to install, e.g. 0.4.0"),
+        ).arg(
+          Arg::with_name("output")
+            .long("output")
+            .value_name("PATH")
+            .takes_value(true)
+            .help("Install to PATH, keeping the running executable"),
+        ).arg(
+          Arg::with_name("dry-run")
+            .long("dry-run")
+            .help("Download and check the release, but install nothing"),
+        ),
+    ).subcommand(
+      SubCommand::with_name("clean")
+        .setting(AppSettings::DisableVersion)
+        .about("Remove cached modules and compiled output")
+        .long_about(
+          "Remove the remote modules and compiled output cached in DENO_DIR.
+With a URL prefix only the modules under it and their compiled output are
+removed:
+
+  deno clean
+  deno clean https://deno.land/std/",
+        ).arg(Arg::with_name("prefix").takes_value(true))
+        .arg(
+          Arg::with_name("dry-run")
+            .long("dry-run")
+            .help("List the files which would be removed, but keep them"),
+        ).arg(deno_dir_arg()),
+    ).subcommand(
+      SubCommand::with_name("version")
+        .setting(AppSettings::DisableVersion)
+        .about("Print the versions of deno, V8 and TypeScript")
+        .arg(
+          Arg::with_name("json")
+            .long("json")
+            .help("Output the versions as JSON, e.g. to pin toolchains"),
+        ),
+    ).subcommand(
+      SubCommand::with_name("types")
+        .setting(AppSettings::DisableVersion)
+        .about("Print runtime TypeScript declarations")
+        .long_about(
+          "Print the TypeScript declarations of the deno runtime, prefixed with
+the version of deno that generated them:
+
+  deno types -o lib.deno_runtime.d.ts",
+        ).arg(
+          Arg::with_name("output")
+            .short("o")
+            .long("output")
+            .value_name("FILE")
+            .takes_value(true)
+            .help("Write the declarations to a file, creating its directory"),
+        ),
+    ).subcommand(
+      SubCommand::with_name("completions")
+        .setting(AppSettings::DisableVersion)
+        .about("Print the completion script of a shell")
+        .long_about(
+          "Print the script which completes the subcommands, flags and paths of
+deno in bash, zsh, fish or PowerShell, e.g.
+
+  deno completions bash > /usr/local/etc/bash_completion.d/deno.bash
+  source /usr/local/etc/bash_completion.d/deno.bash",
+        ).arg(
+          Arg::with_name("shell")
+            .possible_values(completions::SHELLS)
+            .required(true),
+        ),
+    ).subcommand(
+      SubCommand::with_name("eval")
+        .setting(AppSettings::DisableVersion)
+        .about("Eval script")
+        .long_about(
+          "Eval a script given on the command line, e.g.
+
+  deno eval \"console.log('hello')\"
+  deno eval -p \"1 + 2\"
+  deno eval --ts \"const n: number = 1; console.log(n)\"",
+        ).arg(
+          Arg::with_name("print")
+            .short("p")
+            .long("print")
+            .help("Print the value of the last expression, once it resolves"),
+        ).arg(
+          Arg::with_name("ts")
+            .long("ts")
+            .help("Compile the script as TypeScript"),
+        ).arg(Arg::with_name("code").takes_value(true).required(true))
+        .args(&color_args()),
+    ).subcommand(
+      SubCommand::with_name("fmt")
+        .setting(AppSettings::DisableVersion)
+        .about("Format files")
+        .long_about(
+          "Format files with prettier. Directories are searched for .ts, .tsx,
+.js, .jsx, .json and .md files, skipping node_modules.
+
+  deno fmt
+  deno fmt src --ignore \"src/gen/**\"
+  deno fmt --check a.ts",
+        ).arg(
+          Arg::with_name("check")
+            .long("check")
+            .help("Report unformatted files instead of rewriting them"),
+        ).arg(
+          Arg::with_name("line-width")
+            .long("line-width")
+            .value_name("N")
+            .takes_value(true)
+            .validator(is_line_width)
+            .help("Wrap lines longer than N characters"),
+        ).arg(
+          Arg::with_name("indent")
+            .long("indent")
+            .value_name("N|tab")
+            .takes_value(true)
+            .validator(is_indent)
+            .help("Indent with N spaces, or with tabs"),
+        ).arg(
+          Arg::with_name("single-quote")
+            .long("single-quote")
+            .help("Use single instead of double quotes"),
+        ).arg(
+          Arg::with_name("no-semicolons")
+            .long("no-semicolons")
+            .help("Only add semicolons where they are needed"),
+        ).arg(
+          Arg::with_name("jobs")
+            .short("j")
+            .long("jobs")
+            .value_name("N")
+            .takes_value(true)
+            .validator(is_jobs)
+            .help("Format N files at once, by default one per CPU"),
+        ).arg(
+          Arg::with_name("ignore")
+            .long("ignore")
+            .value_name("GLOB")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Skip the files and directories matching GLOB"),
+        ).arg(
+          Arg::with_name("files")
+            .takes_value(true)
+            .multiple(true)
+            .help(
+              "Files and directories to format, by default the current \
+               directory, or - to format stdin to stdout",
+            ),
+        ).args(&color_args()),
+    ).subcommand(
+      add_run_args(
+        SubCommand::with_name("bench")
+          .setting(AppSettings::DisableVersion)
+          .about("Run benchmarks")
+          .long_about(
+            "Run the benchmarks registered with Deno.bench() in this process,
+one after the other. Directories are searched for files named *_bench.ts,
+*_bench.tsx, *_bench.js and *_bench.jsx, skipping node_modules.
+
+  deno bench
+  deno bench --allow-read bench/ --filter \"^parse\"
+  deno bench --json --iterations 1000 url_bench.ts",
+          ).arg(
+            Arg::with_name("json")
+              .long("json")
+              .help("Print the results as JSON, once all benchmarks ran"),
+          ).arg(
+            Arg::with_name("filter")
+              .long("filter")
+              .value_name("REGEX")
+              .takes_value(true)
+              .help("Only run the benchmarks whose name matches REGEX"),
+          ).arg(
+            Arg::with_name("iterations")
+              .long("iterations")
+              .value_name("N")
+              .takes_value(true)
+              .validator(is_iterations)
+              .help("Time each benchmark N times, by default 100"),
+          ).arg(
+            Arg::with_name("warmup")
+              .long("warmup")
+              .value_name("N")
+              .takes_value(true)
+              .validator(is_warmup)
+              .help("Run each benchmark N times before, by default 10"),
+          ).arg(
+            Arg::with_name("files")
+              .takes_value(true)
+              .multiple(true)
+              .help(
+                "Bench files and directories, by default the current \
+                 directory",
+              ),
+          ),
+      ),
+    ).subcommand(
+      add_run_args(
+        SubCommand::with_name("test")
+          .setting(AppSettings::DisableVersion)
+          .about("Run tests")
+          .long_about(
+            "Run the tests registered with Deno.test() in this process, one
+after the other, with the permissions given. Directories are searched for
+files named test.ts or *_test.ts, also with the extensions .tsx, .js and .jsx,
+skipping node_modules. The exit code is 1 if a test failed.
+
+  deno test
+  deno test --allow-net src/ --filter http
+  deno test --fail-fast url_test.ts
+  deno test --coverage --coverage-exclude '**/*_test.ts'",
+          ).arg(
+            Arg::with_name("filter")
+              .long("filter")
+              .value_name("SUBSTRING")
+              .takes_value(true)
+              .help("Only run the tests whose name contains SUBSTRING"),
+          ).arg(
+            Arg::with_name("fail-fast")
+              .long("fail-fast")
+              .help("Stop at the first test which fails"),
+          ).arg(
+            Arg::with_name("coverage")
+              .long("coverage")
+              .value_name("DIR")
+              .takes_value(true)
+              .min_values(0)
+              .require_equals(true)
+              .conflicts_with_all(&["inspect", "inspect-brk"])
+              .help(
+                "Collect the code coverage, print a summary and write \
+                 DIR/lcov.info, by default coverage/lcov.info",
+              ),
+          ).arg(
+            Arg::with_name("coverage-include")
+              .long("coverage-include")
+              .value_name("GLOB")
+              .takes_value(true)
+              .multiple(true)
+              .number_of_values(1)
+              .requires("coverage")
+              .help(
+                "Cover only the modules matching GLOB, remote ones too, \
+                 instead of the local modules",
+              ),
+          ).arg(
+            Arg::with_name("coverage-exclude")
+              .long("coverage-exclude")
+              .value_name("GLOB")
+              .takes_value(true)
+              .multiple(true)
+              .number_of_values(1)
+              .requires("coverage")
+              .help("Do not cover the modules matching GLOB"),
+          ).arg(
+            Arg::with_name("files")
+              .takes_value(true)
+              .multiple(true)
+              .help(
+                "Test files and directories, by default the current \
+                 directory",
+              ),
+          ),
+      ),
+    ).subcommand(
+      add_run_args(
+        SubCommand::with_name("repl")
+          .setting(AppSettings::DisableVersion)
+          .about("Read Eval Print Loop (default when no script is given)"),
+      ),
+    ).subcommand(
+      add_run_args(
+        SubCommand::with_name("run")
+          .settings(&[
+            AppSettings::AllowExternalSubcommands,
+            AppSettings::DisableHelpSubcommand,
+            AppSettings::DisableVersion,
+            AppSettings::SubcommandRequired,
+          ]).about("Run a program given a filename or url to the source code")
+          .long_about(
+            "Run a program given a filename or url to the source code.
+
+Flags placed before the script name are deno flags, everything after the
+script name is passed to the script untouched:
+
+  deno run --allow-net server.ts --port 8080
+
+Everything after -- is the script and its arguments, even if it looks like a
+deno flag:
+
+  deno run --allow-net -- --script-named-like-a-flag.ts",
+          ),
+      ).subcommand(
+        // this is a fake subcommand - it's used in conjunction with
+        // AppSettings:AllowExternalSubcommand to treat it as an
+        // entry point script
+        SubCommand::with_name(SCRIPT_SUBCOMMAND).about("Script to run"),
+      ),
+    ).subcommand(
+      // this is a fake subcommand - it's used in conjunction with
+      // AppSettings:AllowExternalSubcommand to treat it as an
+      // entry point script
+      SubCommand::with_name(SCRIPT_SUBCOMMAND).about("Script to run"),
+    )
+}
+
+/// Appends the script name and its arguments, captured by clap as an
+/// external subcommand, to `rest_argv`.
+fn push_script_args(
+  rest_argv: &mut Vec<String>,
+  script: &str,
+  script_match: &ArgMatches,
+) {
+  rest_argv.extend(vec![script.to_string()]);
+  // check if there are any extra arguments that should
+  // be passed to script
+  if script_match.is_present("") {
+    let script_args: Vec<String> = script_match
+      .values_of("")
+      .unwrap()
+      .map(String::from)
+      .collect();
+    rest_argv.extend(script_args);
+  }
+}
+
+/// The long flags of add_run_args(), which test_run_flag_names() checks.
+const RUN_FLAG_NAMES: &[&str] = &[
+  "allow-read",
+  "allow-write",
+  "allow-net",
+  "allow-env",
+  "allow-run",
+  "allow-high-precision",
+  "allow-all",
+  "deny-read",
+  "deny-write",
+  "deny-net",
+  "deny-env",
+  "deny-run",
+  "no-prompt",
+  "prompt-stack",
+  "lock",
+  "lock-write",
+  "log-debug",
+  "log-level",
+  "color",
+  "no-color",
+  "quiet",
+  "reload",
+  "no-check",
+  "compat-resolve",
+  "diagnostics",
+  "full-stack-traces",
+  "unhandled-rejections",
+  "trace-ops",
+  "cached-only",
+  "max-redirects",
+  "compile-jobs",
+  "cert",
+  "flags-file",
+  "importmap",
+  "config",
+  "deno-dir",
+  "v8-flags",
+  "max-heap-size",
+  "initial-heap-size",
+  "seed",
+  "inspect",
+  "inspect-brk",
+  "watch",
+  "strict-flags",
+  "no-snapshot",
+  "ext",
+];
+
+/// The Levenshtein distance of `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut row: Vec<usize> = (0..=b.len()).collect();
+  for (i, ca) in a.chars().enumerate() {
+    let mut diagonal = row[0];
+    row[0] = i + 1;
+    for (j, cb) in b.iter().enumerate() {
+      let above = row[j + 1];
+      row[j + 1] = if ca == *cb {
+        diagonal
+      } else {
+        1 + diagonal.min(above).min(row[j])
+      };
+      diagonal = above;
+    }
+  }
+  row[b.len()]
+}
+
+/// Warnings about the flags which lead the arguments of `script` and look
+/// like the deno flags `known`, or are a misspelling of one. They go to the
+/// script, which then runs without what they were meant to allow. Flags
+/// after the first argument which is none are the script's own.
+fn misplaced_flags(
+  script: &str,
+  script_args: &[String],
+  known: &[&str],
+) -> Vec<String> {
+  let mut warnings = Vec::new();
+  for arg in script_args.iter().take_while(|arg| arg.starts_with("--")) {
+    let name = arg[2..].split('=').next().unwrap();
+    // What follows -- is the script's own, whatever it looks like.
+    if name.is_empty() {
+      break;
+    }
+    if known.iter().any(|flag| *flag == name) {
+      warnings.push(format!(
+        "{} comes after the script, so the script gets it, not deno. Put it \
+         before the script: deno run {} {}",
+        arg, arg, script
+      ));
+      continue;
+    }
+    let closest = known.iter().min_by_key(|flag| edit_distance(name, flag));
+    if let Some(closest) = closest {
+      let max_distance = if name.len() > 5 { 2 } else { 1 };
+      if name.starts_with("allow")
+        || edit_distance(name, closest) <= max_distance
+      {
+        warnings.push(format!(
+          "{} is passed to the script, did you mean --{} before the script?",
+          arg, closest
+        ));
+      }
+    }
+  }
+  warnings
+}
+
+/// Passes `--v8-flags` on to V8 and returns them.
+fn apply_v8_flags(matches: &ArgMatches) -> Vec<String> {
+  if matches.is_present("v8-flags") {
+    let v8_flags: Vec<String> = matches
+      .values_of("v8-flags")
+      .unwrap()
+      .map(String::from)
+      .collect();
+
+    let mut v8_args = v8_flags.clone();
+    v8_args.insert(1, "deno".to_string());
+    v8_set_flags(v8_args);
+    v8_flags
+  } else {
+    vec![]
+  }
+}
+
+/// Translates the heap size flags to their V8 options. They are set after
+/// `v8_flags`, so they take precedence over the same options given there.
+fn apply_heap_size_flags(flags: &DenoFlags, v8_flags: &[String]) {
+  let heap_size_flags = [
+    ("--max-heap-size", "--max-old-space-size", flags.max_heap_size),
+    (
+      "--initial-heap-size",
+      "--initial-old-space-size",
+      flags.initial_heap_size,
+    ),
+  ];
+  let mut v8_args = vec!["deno".to_string()];
+  for (flag, v8_option, size) in heap_size_flags.iter() {
+    if let Some(size) = size {
+      // V8 accepts both dashes and underscores in option names.
+      if v8_flags
+        .iter()
+        .any(|f| f.replace('_', "-").starts_with(v8_option))
+      {
+        eprintln!(
+          "Warning: {} overrides {} given with --v8-flags",
+          flag, v8_option
+        );
+      }
+      v8_args.push(format!("{}={}", v8_option, size));
+    }
+  }
+  if v8_args.len() > 1 {
+    v8_set_flags(v8_args);
+  }
+}
+
+/// Parses `args` with clap. What follows the first `--` is taken verbatim:
+/// where the script is expected it is returned as the script and its
+/// arguments, even if it looks like a deno flag or subcommand, elsewhere clap
+/// takes it as the positional arguments of the subcommand.
+fn get_matches(
+  args: Vec<String>,
+) -> Result<(ArgMatches<'static>, Option<Vec<String>>), String> {
+  if let Some(args) = install_args(&args) {
+    return Ok((parse(args)?, None));
+  }
+  let separator = match args.iter().position(|arg| arg == "--") {
+    Some(separator) => separator,
+    None => return Ok((parse(args)?, None)),
+  };
+  // Whether the script is expected after the flags is told by putting the
+  // script subcommand in the place of the separator.
+  let mut probe = args[..separator].to_vec();
+  probe.push(SCRIPT_SUBCOMMAND.to_string());
+  if let Ok(matches) = create_cli_app().get_matches_from_safe(probe) {
+    if script_name(&matches) == Some(SCRIPT_SUBCOMMAND) {
+      return Ok((matches, Some(args[separator + 1..].to_vec())));
+    }
+  }
+  Ok((parse(args)?, None))
+}
+
+/// `deno install` passes every argument after the module verbatim to the
+/// script, even `-f` or one that looks like any other flag, so a `--` is put
+/// after the module for clap to take them as the arguments of install.
+fn install_args(args: &[String]) -> Option<Vec<String>> {
+  let install = args.iter().position(|arg| arg == "install")?;
+  // The flags of install take no values, the module is the second word.
+  let module = args[install + 1..]
+    .iter()
+    .enumerate()
+    .filter(|(_, arg)| !arg.starts_with('-'))
+    .nth(1)
+    .map(|(i, _)| install + 1 + i)?;
+  let matches = create_cli_app()
+    .get_matches_from_safe(args[..=module].to_vec())
+    .ok()?;
+  // "install" may as well have been the value of a flag or a script.
+  if matches.subcommand_matches("install")?.value_of("module")
+    != Some(args[module].as_str())
+  {
+    return None;
+  }
+  let mut args = args.to_vec();
+  args.insert(module + 1, "--".to_string());
+  Some(args)
+}
+
+fn parse(args: Vec<String>) -> Result<ArgMatches<'static>, String> {
+  match create_cli_app().get_matches_from_safe(args) {
+    Ok(matches) => Ok(matches),
+    // Usage errors are returned so that main() exits with EXIT_USAGE_ERROR.
+    Err(e) if e.use_stderr() => Err(e.message),
+    // --help
+    Err(e) => e.exit(),
+  }
+}
+
+/// The subcommands of create_cli_app(), which test_subcommand_names() checks.
+const SUBCOMMAND_NAMES: &[&str] = &[
+  "bench",
+  "bundle",
+  "clean",
+  "completions",
+  "doc",
+  "eval",
+  "fmt",
+  "info",
+  "install",
+  "prefetch",
+  "repl",
+  "run",
+  "test",
+  "types",
+  "upgrade",
+  "version",
+];
+
+/// The script matched by deno itself or by `run`, which clap takes as an
+/// external subcommand.
+fn script_name<'a>(matches: &'a ArgMatches) -> Option<&'a str> {
+  match matches.subcommand() {
+    // run has no subcommands of its own.
+    ("run", Some(run_match)) => run_match.subcommand_name(),
+    ("", _) => None,
+    (name, _) if SUBCOMMAND_NAMES.contains(&name) => None,
+    (name, _) => Some(name),
+  }
+}
+
+/// The long names of the short flags of `deno run`.
+const SHORT_FLAGS: &[(char, &str)] = &[
+  ('A', "allow-all"),
+  ('c', "config"),
+  ('D', "log-debug"),
+  ('q', "quiet"),
+  ('r', "reload"),
+];
+
+/// The names of the flags `word` gives, several for `-Dr`.
+fn flag_names(word: &str) -> Vec<String> {
+  if word.starts_with("--") {
+    return vec![word[2..].splitn(2, '=').next().unwrap().to_string()];
+  }
+  word[1..]
+    .chars()
+    .map(|c| {
+      let short = SHORT_FLAGS.iter().find(|(short, _)| *short == c);
+      short.map_or(c.to_string(), |(_, long)| long.to_string())
+    }).collect()
+}
+
+/// The words of `text`, which `source` holds, each flag with the values
+/// after it. Only flags are allowed, and not `--flags-file`.
+fn preset_flags(source: &str, text: &str) -> Result<Vec<Vec<String>>, String> {
+  let words =
+    shell_words::split(text).map_err(|e| format!("{}: {}", source, e))?;
+  let mut flags: Vec<Vec<String>> = Vec::new();
+  for word in words {
+    if word == "--flags-file" || word.starts_with("--flags-file=") {
+      return Err(format!("{}: --flags-file cannot be given here", source));
+    }
+    if word.starts_with('-') && word != "-" && word != "--" {
+      flags.push(vec![word]);
+      continue;
+    }
+    match flags.last_mut() {
+      Some(flag) => flag.push(word),
+      None => {
+        return Err(format!("{}: \"{}\" is not a deno flag", source, word))
+      }
+    }
+  }
+  Ok(flags)
+}
+
+/// The environment variable of the flags which add_preset_flags() adds.
+pub const FLAGS_ENV: &str = "DENO_FLAGS";
+
+/// Puts the flags of `DENO_FLAGS`, as `env_flags`, and those of the file of
+/// `--flags-file` before the ones of `args`, for set_flags() to parse. Those
+/// of `args` take precedence over those of the file, and those of the file
+/// over those of DENO_FLAGS: a flag given before one of the same name is
+/// dropped, along with its values.
+pub fn add_preset_flags(
+  args: Vec<String>,
+  env_flags: Option<&str>,
+) -> Result<Vec<String>, String> {
+  // Usage errors are left to set_flags().
+  let matches = match get_matches(args.clone()) {
+    Ok((matches, _)) => matches,
+    Err(_) => return Ok(args),
+  };
+  let subcommand = matches.subcommand().1;
+  let given = |name: &str| {
+    matches.is_present(name)
+      || subcommand.map_or(false, |matches| matches.is_present(name))
+  };
+  let flags_file = subcommand
+    .and_then(|matches| matches.value_of("flags-file"))
+    .or_else(|| matches.value_of("flags-file"));
+
+  // By precedence, lowest first.
+  let mut sources = Vec::new();
+  if let Some(text) = env_flags {
+    sources.push(preset_flags("DENO_FLAGS", text)?);
+  }
+  if let Some(path) = flags_file {
+    let text = fs::read_to_string(path)
+      .map_err(|e| format!("cannot read the flags file {}: {}", path, e))?;
+    sources.push(preset_flags(path, &text)?);
+  }
+  let mut given_later: HashSet<String> = HashSet::new();
+  let mut kept: Vec<Vec<Vec<String>>> = Vec::new();
+  for flags in sources.into_iter().rev() {
+    let names: Vec<String> =
+      flags.iter().flat_map(|flag| flag_names(&flag[0])).collect();
+    let overridden = |flag: &Vec<String>| {
+      flag_names(&flag[0])
+        .iter()
+        .any(|name| given(name) || given_later.contains(name))
+    };
+    kept.push(flags.into_iter().filter(|flag| !overridden(flag)).collect());
+    given_later.extend(names);
+  }
+
+  let mut argv = args[..1].to_vec();
+  argv.extend(kept.into_iter().rev().flatten().flatten());
+  argv.extend(args.into_iter().skip(1));
+  Ok(argv)
+}
+
+#[cfg_attr(feature = "cargo-clippy", allow(stutter))]
+pub fn set_flags(
+  args: Vec<String>,
+) -> Result<(DenoFlags, Vec<String>), String> {
+  let mut rest_argv: Vec<String> = vec!["deno".to_string()];
+  let (matches, script_argv) = get_matches(args)?;
+  // After -- the arguments of the script are told apart on purpose.
+  let checks_script_args =
+    script_argv.is_none() && script_name(&matches).is_some();
+
+  if let Some(fragments) = matches.values_of("eval-code") {
+    // Script arguments are only told apart from a script after --.
+    if matches.subcommand_name().is_some() && script_argv.is_none() {
+      return Err(
+        "-e cannot be used with a script or a subcommand, \
+         pass arguments to the code after --"
+          .to_string(),
+      );
+    }
+    rest_argv.push(fragments.collect::<Vec<_>>().join("\n"));
+  }
+
   match matches.subcommand() {
+    // The script and its arguments were given after --.
+    _ if script_argv.is_some() => {
+      let script_argv = script_argv.unwrap();
+      if script_argv.is_empty() && matches.subcommand_name() == Some("run") {
+        return Err("deno run -- needs a script to run".to_string());
+      }
+      rest_argv.extend(script_argv);
+    }
     ("eval", Some(info_match)) => {
       let code: &str = info_match.value_of("code").unwrap();
       rest_argv.extend(vec![code.to_string()]);
     }
     ("info", Some(info_match)) => {
-      let file: &str = info_match.value_of("file").unwrap();
+      // Without a file the DENO_DIR in effect and the environment are shown.
+      if let Some(file) = info_match.value_of("file") {
+        rest_argv.extend(vec![file.to_string()]);
+      }
+    }
+    ("prefetch", Some(prefetch_match)) => {
+      let file: &str = prefetch_match.value_of("file").unwrap();
+      rest_argv.extend(vec![file.to_string()]);
+    }
+    ("install", Some(install_match)) => {
+      let exe_name: &str = install_match.value_of("exe_name").unwrap();
+      let module: &str = install_match.value_of("module").unwrap();
+      rest_argv.extend(vec![exe_name.to_string(), module.to_string()]);
+      if let Some(args) = install_match.values_of("args") {
+        rest_argv.extend(args.map(String::from));
+      }
+    }
+    ("clean", Some(clean_match)) => {
+      if let Some(prefix) = clean_match.value_of("prefix") {
+        rest_argv.extend(vec![prefix.to_string()]);
+      }
+    }
+    ("bundle", Some(bundle_match)) => {
+      let file: &str = bundle_match.value_of("source_file").unwrap();
+      rest_argv.extend(vec![file.to_string()]);
+    }
+    ("doc", Some(doc_match)) => {
+      let file: &str = doc_match.value_of("source_file").unwrap();
       rest_argv.extend(vec![file.to_string()]);
     }
-    ("fmt", Some(fmt_match)) => {
-      let files: Vec<String> = fmt_match
-        .values_of("files")
-        .unwrap()
-        .map(String::from)
-        .collect();
-      rest_argv.extend(files);
+    ("fmt", Some(fmt_match)) => {
+      if let Some(files) = fmt_match.values_of("files") {
+        rest_argv.extend(files.map(String::from));
+      }
+    }
+    ("bench", Some(bench_match)) => {
+      if let Some(files) = bench_match.values_of("files") {
+        rest_argv.extend(files.map(String::from));
+      }
+    }
+    ("test", Some(test_match)) => {
+      if let Some(files) = test_match.values_of("files") {
+        rest_argv.extend(files.map(String::from));
+      }
+    }
+    // With no main module in rest_argv the REPL is started, same as when
+    // deno is invoked without a script.
+    ("repl", Some(_)) => {}
+    // Subcommands which take no files, their names are no script.
+    ("types", Some(_))
+    | ("version", Some(_))
+    | ("completions", Some(_))
+    | ("upgrade", Some(_)) => {}
+    ("run", Some(run_match)) => {
+      if let (script, Some(script_match)) = run_match.subcommand() {
+        push_script_args(&mut rest_argv, script, script_match);
+      }
+    }
+    (script, Some(script_match)) => {
+      push_script_args(&mut rest_argv, script, script_match);
+    }
+    _ => {}
+  }
+
+  if matches.is_present("v8-options") {
+    // display v8 help and exit
+    // TODO(bartlomieju): this relies on `v8_set_flags` to swap `--v8-options` to help
+    v8_set_flags(vec!["deno".to_string(), "--v8-options".to_string()]);
+  }
+
+  let mut v8_flags = apply_v8_flags(&matches);
+  if let Some(run_match) = matches.subcommand_matches("run") {
+    v8_flags.extend(apply_v8_flags(run_match));
+  }
+  if let Some(repl_match) = matches.subcommand_matches("repl") {
+    v8_flags.extend(apply_v8_flags(repl_match));
+  }
+  if let Some(prefetch_match) = matches.subcommand_matches("prefetch") {
+    v8_flags.extend(apply_v8_flags(prefetch_match));
+  }
+  if let Some(bundle_match) = matches.subcommand_matches("bundle") {
+    v8_flags.extend(apply_v8_flags(bundle_match));
+  }
+  if let Some(doc_match) = matches.subcommand_matches("doc") {
+    v8_flags.extend(apply_v8_flags(doc_match));
+  }
+  if let Some(bench_match) = matches.subcommand_matches("bench") {
+    v8_flags.extend(apply_v8_flags(bench_match));
+  }
+  if let Some(test_match) = matches.subcommand_matches("test") {
+    v8_flags.extend(apply_v8_flags(test_match));
+  }
+
+  let mut flags = DenoFlags::from(matches);
+  flags.v8_flags = v8_flags;
+  if let (Some(max), Some(initial)) =
+    (flags.max_heap_size, flags.initial_heap_size)
+  {
+    if max < initial {
+      return Err(format!(
+        "--max-heap-size {} MB is less than --initial-heap-size {} MB",
+        max, initial
+      ));
+    }
+  }
+  if flags.quiet && flags.log_level == LogLevel::Debug {
+    return Err(
+      "--quiet cannot be used together with --log-debug or --log-level=debug"
+        .to_string(),
+    );
+  }
+  if flags.quiet && flags.trace_ops.is_some() {
+    return Err("--quiet cannot be used together with --trace-ops".to_string());
+  }
+  if flags.cached_only && (flags.reload || !flags.reload_whitelist.is_empty())
+  {
+    return Err(
+      "--cached-only and --reload cannot be used together, reloading needs \
+       to download modules"
+        .to_string(),
+    );
+  }
+  // Clap only sees the conflict with the flags of `deno test` itself.
+  if flags.coverage.is_some() && flags.inspector_addr().is_some() {
+    return Err(
+      "--coverage cannot be used together with --inspect or --inspect-brk, \
+       both take the inspector of the isolate"
+        .to_string(),
+    );
+  }
+  if flags.watch {
+    let runs_script = rest_argv.len() > 1
+      && !(flags.eval
+        || flags.info
+        || flags.prefetch
+        || flags.bundle
+        || flags.doc
+        || flags.fmt
+        || flags.bench
+        || flags.test);
+    if !runs_script {
+      return Err("--watch needs a script to run".to_string());
+    }
+    if rest_argv[1] == "-" {
+      return Err("--watch cannot watch a script read from stdin".to_string());
+    }
+  }
+  if checks_script_args
+    && rest_argv.get(2).map_or(false, |arg| arg.starts_with("--"))
+  {
+    let warnings =
+      misplaced_flags(&rest_argv[1], &rest_argv[2..], RUN_FLAG_NAMES);
+    if flags.strict_flags && !warnings.is_empty() {
+      return Err(warnings.join("\n"));
+    }
+    for warning in warnings {
+      eprintln!("Warning: {}", warning);
+    }
+  }
+  apply_heap_size_flags(&flags, &flags.v8_flags);
+  if let Some(seed) = flags.seed {
+    // Only Math.random() is affected, the random numbers of ops never come
+    // from V8.
+    v8_set_flags(vec![
+      "deno".to_string(),
+      format!("--random-seed={}", seed),
+      format!("--hash-seed={}", seed),
+    ]);
+  }
+  Ok((flags, rest_argv))
+}
+
+#[test]
+fn test_set_flags_1() {
+  let (flags, rest) = set_flags(svec!["deno", "--version"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      version: true,
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_2() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "-r", "-D", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      log_level: LogLevel::Debug,
+      reload: true,
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_3() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "-r", "--allow-write", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      reload: true,
+      allow_write: true,
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_4() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "-Dr", "--allow-write", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      log_level: LogLevel::Debug,
+      reload: true,
+      allow_write: true,
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_5() {
+  let (flags, rest) = set_flags(svec!["deno", "--types"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      types: true,
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_flags_6() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "--allow-net", "gist.ts", "--title", "X"]).unwrap();
+  assert_eq!(rest, svec!["deno", "gist.ts", "--title", "X"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_net: true,
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_flags_7() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "--allow-all", "gist.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "gist.ts"]);
+  assert!(flags.allow_net);
+  assert!(flags.allow_env);
+  assert!(flags.allow_run);
+  assert!(flags.allow_read);
+  assert!(flags.allow_write);
+  assert!(flags.allow_high_precision);
+  assert!(flags.read_whitelist.is_empty());
+  assert!(flags.write_whitelist.is_empty());
+  assert!(flags.net_whitelist.is_empty());
+  assert!(!flags.deny_read);
+  assert!(!flags.deny_write);
+  assert!(!flags.deny_net);
+  assert!(!flags.deny_env);
+  assert!(!flags.deny_run);
+  assert!(!flags.no_prompts);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_net: true,
+      allow_env: true,
+      allow_run: true,
+      allow_read: true,
+      allow_write: true,
+      allow_high_precision: true,
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_flags_8() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "--allow-read", "gist.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "gist.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_read: true,
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_flags_9() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "--allow-high-precision", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_high_precision: true,
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_flags_10() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "--allow-net=deno.land,github.com:443",
+    "script.ts"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_net: false,
+      net_whitelist: svec!["deno.land", "github.com:443"],
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_flags_11() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "--allow-net", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_net: true,
+      net_whitelist: vec![],
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_flags_12() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "--allow-read=/tmp,/var/data",
+    "--allow-write=/tmp",
+    "script.ts"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_read: false,
+      read_whitelist: svec!["/tmp", "/var/data"],
+      allow_write: false,
+      write_whitelist: svec!["/tmp"],
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_flags_13() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "run",
+    "--allow-net",
+    "script.ts",
+    "--allow-net"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts", "--allow-net"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_net: true,
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_flags_14() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "-r",
+    "run",
+    "--allow-read=/tmp",
+    "script.ts",
+    "-D"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts", "-D"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      reload: true,
+      read_whitelist: svec!["/tmp"],
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_flags_15() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "-A", "--deny-net", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_net: true,
+      allow_env: true,
+      allow_run: true,
+      allow_read: true,
+      allow_write: true,
+      allow_high_precision: true,
+      deny_net: true,
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_all_permissions() {
+  // Inspect the Debug representation so that every `allow_*` field is
+  // checked, including ones added after this test was written.
+  let mut flags = DenoFlags::default();
+  flags.set_all_permissions();
+  let repr = format!("{:?}", flags);
+  let re = regex::Regex::new(r"(allow_\w+): (true|false)").unwrap();
+  let fields: Vec<_> = re.captures_iter(&repr).collect();
+  assert!(!fields.is_empty());
+  for field in fields {
+    let (name, value) = (&field[1], &field[2]);
+    assert_eq!(value, "true", "{} not set by set_all_permissions()", name);
+  }
+}
+
+#[test]
+fn test_set_flags_16() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "info", "--json", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      info: true,
+      info_json: true,
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_flags_17() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "fmt", "--check", "a.ts", "b.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "a.ts", "b.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      fmt: true,
+      fmt_check: true,
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_flags_18() {
+  let (flags, rest) = set_flags(svec!["deno", "fmt", "-"]).unwrap();
+  assert_eq!(rest, svec!["deno", "-"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      fmt: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, rest) = set_flags(svec!["deno", "fmt"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      fmt: true,
+      ..DenoFlags::default()
+    }
+  )
+}
+
+#[test]
+fn test_set_flags_19() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "repl", "--allow-net", "--reload"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_net: true,
+      reload: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, rest) = set_flags(svec!["deno"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert_eq!(flags, DenoFlags::default());
+}
+
+#[test]
+fn test_set_flags_20() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "--reload=https://deno.land/x/mylib/,http://127.0.0.1:4545/",
+    "script.ts"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      reload_whitelist: svec![
+        "https://deno.land/x/mylib/",
+        "http://127.0.0.1:4545/"
+      ],
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, rest) =
+    set_flags(svec!["deno", "-D", "--reload", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      log_level: LogLevel::Debug,
+      reload: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "run",
+    "-D",
+    "--reload=https://deno.land/x/mylib/",
+    "script.ts"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      log_level: LogLevel::Debug,
+      reload_whitelist: svec!["https://deno.land/x/mylib/"],
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_21() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "--allow-env=HOME,PATH,CI", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_env: false,
+      env_whitelist: svec!["HOME", "PATH", "CI"],
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_22() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "--allow-run=git,cargo", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_run: false,
+      run_whitelist: svec!["git", "cargo"],
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_23() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "prefetch", "--reload", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      prefetch: true,
+      reload: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, rest) =
+    set_flags(svec!["deno", "--prefetch", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      prefetch: true,
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_24() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "run",
+    "--lock=lock.json",
+    "--lock-write",
+    "script.ts"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      lock: Some("lock.json".to_string()),
+      lock_write: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, rest) =
+    set_flags(svec!["deno", "--lock=lock.json", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      lock: Some("lock.json".to_string()),
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_25() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "--cached-only", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      cached_only: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let err =
+    set_flags(svec!["deno", "--cached-only", "-r", "script.ts"]).unwrap_err();
+  assert!(err.contains("--cached-only"));
+  assert!(
+    set_flags(svec!["deno", "--reload=http://a/", "run", "--cached-only", "x"])
+      .is_err()
+  );
+}
+
+#[test]
+fn test_set_flags_26() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "--importmap",
+    "import_map.json",
+    "script.ts"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      import_map_path: Some("import_map.json".to_string()),
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_27() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "run", "--cert=my_ca.pem", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      ca_file: Some("my_ca.pem".to_string()),
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_28() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "types", "-o", "out/lib.deno.d.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      types: true,
+      types_output: Some("out/lib.deno.d.ts".to_string()),
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, _) = set_flags(svec!["deno", "types"]).unwrap();
+  assert_eq!(
+    flags,
+    DenoFlags {
+      types: true,
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_29() {
+  let err =
+    set_flags(svec!["deno", "--no-such-flag", "script.ts"]).unwrap_err();
+  assert!(err.contains("--no-such-flag"));
+  assert!(set_flags(svec!["deno", "prefetch"]).is_err());
+}
+
+#[test]
+fn test_set_flags_30() {
+  let (flags, rest) = set_flags(svec!["deno", "-q", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      quiet: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let err = set_flags(svec!["deno", "run", "--quiet", "-D", "script.ts"])
+    .unwrap_err();
+  assert!(err.contains("--quiet"));
+}
+
+#[test]
+fn test_set_flags_31() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "--log-level", "warn", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      log_level: LogLevel::Warn,
+      ..DenoFlags::default()
+    }
+  );
+  assert!(!flags.shows_info());
+
+  let (flags, _) =
+    set_flags(svec!["deno", "run", "--log-level=debug", "script.ts"]).unwrap();
+  assert_eq!(flags.log_level, LogLevel::Debug);
+  assert!(flags.shows_info());
+
+  assert!(set_flags(svec!["deno", "--log-level=loud", "script.ts"]).is_err());
+  assert!(
+    set_flags(svec!["deno", "-q", "--log-level=debug", "script.ts"]).is_err()
+  );
+}
+
+#[test]
+fn test_set_flags_32() {
+  let (flags, rest) = set_flags(svec!["deno", "version", "--json"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      version: true,
+      version_json: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, _) = set_flags(svec!["deno", "version"]).unwrap();
+  assert_eq!(
+    flags,
+    DenoFlags {
+      version: true,
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_33() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "--max-heap-size",
+    "2048",
+    "--initial-heap-size=512",
+    "script.ts"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      max_heap_size: Some(2048),
+      initial_heap_size: Some(512),
+      ..DenoFlags::default()
+    }
+  );
+
+  assert!(set_flags(svec!["deno", "--max-heap-size=0", "x.ts"]).is_err());
+  assert!(set_flags(svec!["deno", "--max-heap-size=-1", "x.ts"]).is_err());
+  assert!(set_flags(svec!["deno", "--max-heap-size=1.5", "x.ts"]).is_err());
+  let err = set_flags(svec![
+    "deno",
+    "run",
+    "--max-heap-size=256",
+    "--initial-heap-size=512",
+    "x.ts"
+  ]).unwrap_err();
+  assert!(err.contains("--max-heap-size"));
+}
+
+#[test]
+fn test_set_flags_34() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "run", "--seed", "250", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      seed: Some(250),
+      ..DenoFlags::default()
+    }
+  );
+
+  assert!(set_flags(svec!["deno", "--seed=0", "script.ts"]).is_err());
+  assert!(set_flags(svec!["deno", "--seed=abc", "script.ts"]).is_err());
+  assert!(set_flags(svec!["deno", "--seed=4294967295", "x.ts"]).is_err());
+}
+
+#[test]
+fn test_set_flags_35() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "run", "--inspect", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      inspect: Some("127.0.0.1:9229".parse().unwrap()),
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, rest) =
+    set_flags(svec!["deno", "--inspect-brk=0.0.0.0:9230", "script.ts"])
+      .unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      inspect_brk: Some("0.0.0.0:9230".parse().unwrap()),
+      ..DenoFlags::default()
+    }
+  );
+
+  assert!(set_flags(svec!["deno", "--inspect=9229", "script.ts"]).is_err());
+  assert!(
+    set_flags(svec!["deno", "--inspect", "--inspect-brk", "script.ts"])
+      .is_err()
+  );
+}
+
+#[test]
+fn test_set_flags_36() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "run", "--watch", "script.ts", "--watch"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts", "--watch"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      watch: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  assert!(set_flags(svec!["deno", "--watch"]).is_err());
+  assert!(set_flags(svec!["deno", "repl", "--watch"]).is_err());
+  assert!(set_flags(svec!["deno", "prefetch", "--watch", "x.ts"]).is_err());
+}
+
+#[test]
+fn test_set_flags_37() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "bundle",
+    "--reload",
+    "--sourcemap",
+    "main.ts",
+    "out.js"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "main.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      reload: true,
+      bundle: true,
+      bundle_output: Some("out.js".to_string()),
+      bundle_sourcemap: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, rest) = set_flags(svec!["deno", "bundle", "main.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "main.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      bundle: true,
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_38() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "install",
+    "-f",
+    "serve",
+    "https://deno.land/std/http/file_server.ts",
+    "--allow-net",
+    "-f",
+    "--allow-read"
+  ]).unwrap();
+  assert_eq!(
+    rest,
+    svec![
+      "deno",
+      "serve",
+      "https://deno.land/std/http/file_server.ts",
+      "--allow-net",
+      "-f",
+      "--allow-read"
+    ]
+  );
+  assert_eq!(
+    flags,
+    DenoFlags {
+      install: true,
+      install_force: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let (_, rest) =
+    set_flags(svec!["deno", "install", "serve", "server.ts", "--", "-A"])
+      .unwrap();
+  assert_eq!(rest, svec!["deno", "serve", "server.ts", "--", "-A"]);
+
+  assert!(set_flags(svec!["deno", "install", "serve"]).is_err());
+}
+
+#[test]
+fn test_set_flags_39() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "run", "--deno-dir", "cache", "script.ts"])
+      .unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      deno_dir: Some("cache".to_string()),
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, rest) =
+    set_flags(svec!["deno", "info", "--deno-dir", "cache"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      info: true,
+      deno_dir: Some("cache".to_string()),
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_40() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "clean",
+    "--dry-run",
+    "--deno-dir",
+    "cache",
+    "https://deno.land/std/"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "https://deno.land/std/"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      clean: true,
+      clean_dry_run: true,
+      deno_dir: Some("cache".to_string()),
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_41() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "--max-redirects", "2", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      max_redirects: Some(2),
+      ..DenoFlags::default()
+    }
+  );
+
+  assert!(set_flags(svec!["deno", "--max-redirects", "-1", "x.ts"]).is_err());
+}
+
+#[test]
+fn test_set_flags_42() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "--reload=full", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      reload: true,
+      reload_full: true,
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_43() {
+  let (flags, rest) = set_flags(svec!["deno", "--", "--version"]).unwrap();
+  assert_eq!(rest, svec!["deno", "--version"]);
+  assert_eq!(flags, DenoFlags::default());
+
+  // Subcommand names are scripts as well.
+  let (flags, rest) =
+    set_flags(svec!["deno", "--allow-net", "--", "info", "--allow-read"])
+      .unwrap();
+  assert_eq!(rest, svec!["deno", "info", "--allow-read"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_net: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  // After the script, -- is one of its arguments.
+  let (_, rest) = set_flags(svec!["deno", "script.ts", "--", "-a"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts", "--", "-a"]);
+
+  let (_, rest) = set_flags(svec!["deno", "--"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+}
+
+#[test]
+fn test_set_flags_44() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "run",
+    "--allow-write",
+    "--",
+    "--script.ts",
+    "--",
+    "--reload"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "--script.ts", "--", "--reload"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_write: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  assert!(set_flags(svec!["deno", "run", "--"]).is_err());
+}
+
+#[test]
+fn test_set_flags_45() {
+  let (flags, rest) = set_flags(svec!["deno", "eval", "--", "-1"]).unwrap();
+  assert_eq!(rest, svec!["deno", "-1"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      eval: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, rest) =
+    set_flags(svec!["deno", "info", "--json", "--", "-x.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "-x.ts"]);
+  assert!(flags.info && flags.info_json);
+
+  let (flags, rest) =
+    set_flags(svec!["deno", "prefetch", "--", "--x.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "--x.ts"]);
+  assert!(flags.prefetch);
+
+  let (flags, rest) =
+    set_flags(svec!["deno", "bundle", "--", "-x.ts", "-x.js"]).unwrap();
+  assert_eq!(rest, svec!["deno", "-x.ts"]);
+  assert!(flags.bundle);
+  assert_eq!(flags.bundle_output, Some("-x.js".to_string()));
+}
+
+#[test]
+fn test_set_flags_46() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "install",
+    "-f",
+    "--",
+    "-serve",
+    "file_server.ts",
+    "--allow-net"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "-serve", "file_server.ts", "--allow-net"]);
+  assert!(flags.install && flags.install_force);
+
+  let (flags, rest) =
+    set_flags(svec!["deno", "clean", "--dry-run", "--", "-x"]).unwrap();
+  assert_eq!(rest, svec!["deno", "-x"]);
+  assert!(flags.clean && flags.clean_dry_run);
+
+  let (flags, rest) =
+    set_flags(svec!["deno", "fmt", "--", "-x.ts", "--check"]).unwrap();
+  assert_eq!(rest, svec!["deno", "-x.ts", "--check"]);
+  assert!(flags.fmt && !flags.fmt_check);
+}
+
+#[test]
+fn test_set_flags_47() {
+  let (flags, rest) = set_flags(svec!["deno", "repl", "--"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert_eq!(flags, DenoFlags::default());
+  assert!(set_flags(svec!["deno", "repl", "--", "x.ts"]).is_err());
+
+  let (flags, _) = set_flags(svec!["deno", "version", "--"]).unwrap();
+  assert!(flags.version);
+
+  let (flags, _) = set_flags(svec!["deno", "types", "--"]).unwrap();
+  assert!(flags.types);
+}
+
+#[test]
+fn test_set_flags_48() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "--ext", "js", "-", "arg"]).unwrap();
+  assert_eq!(rest, svec!["deno", "-", "arg"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      stdin_ext: Some("js".to_string()),
+      ..DenoFlags::default()
+    }
+  );
+
+  assert!(set_flags(svec!["deno", "--ext", "coffee", "-"]).is_err());
+  assert!(set_flags(svec!["deno", "--watch", "-"]).is_err());
+}
+
+#[test]
+fn test_set_flags_49() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "eval", "-p", "--ts", "1 as number"]).unwrap();
+  assert_eq!(rest, svec!["deno", "1 as number"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      eval: true,
+      eval_print: true,
+      eval_ts: true,
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_50() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "--allow-net",
+    "-e",
+    "const a = 1;",
+    "--eval",
+    "console.log(a)"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "const a = 1;\nconsole.log(a)"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      allow_net: true,
+      eval: true,
+      eval_fragments: svec!["const a = 1;", "console.log(a)"],
+      ..DenoFlags::default()
+    }
+  );
+
+  let (_, rest) = set_flags(svec!["deno", "-e", "1", "--", "a", "b"]).unwrap();
+  assert_eq!(rest, svec!["deno", "1", "a", "b"]);
+
+  assert!(set_flags(svec!["deno", "-e", "1", "script.ts"]).is_err());
+  assert!(set_flags(svec!["deno", "-e", "1", "info"]).is_err());
+}
+
+#[test]
+fn test_set_flags_51() {
+  let (flags, _) = set_flags(svec!["deno", "--no-color", "script.ts"]).unwrap();
+  assert_eq!(flags.color, ColorMode::Never);
+
+  let (flags, _) =
+    set_flags(svec!["deno", "run", "--color=always", "script.ts"]).unwrap();
+  assert_eq!(flags.color, ColorMode::Always);
+
+  let (flags, _) =
+    set_flags(svec!["deno", "fmt", "--check", "--color", "always"]).unwrap();
+  assert_eq!(flags.color, ColorMode::Always);
+
+  let (flags, _) = set_flags(svec!["deno", "info", "--no-color"]).unwrap();
+  assert_eq!(flags.color, ColorMode::Never);
+
+  let (flags, _) = set_flags(svec!["deno", "script.ts"]).unwrap();
+  assert_eq!(flags.color, ColorMode::Auto);
+
+  assert!(
+    set_flags(svec!["deno", "--color=never", "--no-color", "script.ts"])
+      .is_err()
+  );
+  assert!(set_flags(svec!["deno", "--color=sometimes", "script.ts"]).is_err());
+}
+
+#[test]
+fn test_set_flags_52() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "fmt",
+    "--check",
+    "--line-width",
+    "100",
+    "--indent",
+    "tab",
+    "--single-quote",
+    "--no-semicolons",
+    "a.ts"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "a.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      fmt: true,
+      fmt_check: true,
+      fmt_config: FmtConfig {
+        line_width: Some(100),
+        indent: Some(FmtIndent::Tab),
+        single_quote: true,
+        no_semicolons: true,
+      },
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, _) = set_flags(svec!["deno", "fmt", "--indent=4"]).unwrap();
+  assert_eq!(flags.fmt_config.indent, Some(FmtIndent::Spaces(4)));
+
+  assert!(set_flags(svec!["deno", "fmt", "--indent", "0"]).is_err());
+  assert!(set_flags(svec!["deno", "fmt", "--indent", "tabs"]).is_err());
+  assert!(set_flags(svec!["deno", "fmt", "--line-width", "0"]).is_err());
+}
+
+#[test]
+fn test_set_flags_53() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "fmt",
+    "--ignore",
+    "gen",
+    "--ignore=*.min.js",
+    "src",
+    "tests"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "src", "tests"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      fmt: true,
+      fmt_ignore: svec!["gen", "*.min.js"],
+      ..DenoFlags::default()
+    }
+  );
+}
+
+#[test]
+fn test_set_flags_54() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "fmt", "--jobs", "4", "--check"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      fmt: true,
+      fmt_check: true,
+      fmt_jobs: Some(4),
+      ..DenoFlags::default()
     }
-    (script, Some(script_match)) => {
-      rest_argv.extend(vec![script.to_string()]);
-      // check if there are any extra arguments that should
-      // be passed to script
-      if script_match.is_present("") {
-        let script_args: Vec<String> = script_match
-          .values_of("")
-          .unwrap()
-          .map(String::from)
-          .collect();
-        rest_argv.extend(script_args);
-      }
+  );
+
+  let (flags, _) = set_flags(svec!["deno", "fmt", "-j2", "src"]).unwrap();
+  assert_eq!(flags.fmt_jobs, Some(2));
+
+  assert!(set_flags(svec!["deno", "fmt", "--jobs", "0"]).is_err());
+}
+
+#[test]
+fn test_set_flags_55() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "info", "--depth", "2", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      info: true,
+      info_depth: Some(2),
+      ..DenoFlags::default()
     }
-    _ => {}
-  }
+  );
 
-  if matches.is_present("v8-options") {
-    // display v8 help and exit
-    // TODO(bartlomieju): this relies on `v8_set_flags` to swap `--v8-options` to help
-    v8_set_flags(vec!["deno".to_string(), "--v8-options".to_string()]);
-  }
+  let (flags, _) =
+    set_flags(svec!["deno", "info", "--depth=0", "script.ts"]).unwrap();
+  assert_eq!(flags.info_depth, Some(0));
 
-  if matches.is_present("v8-flags") {
-    let mut v8_flags: Vec<String> = matches
-      .values_of("v8-flags")
-      .unwrap()
-      .map(String::from)
-      .collect();
+  assert!(set_flags(svec!["deno", "info", "--depth", "-1", "a.ts"]).is_err());
+}
 
-    v8_flags.insert(1, "deno".to_string());
-    v8_set_flags(v8_flags);
-  }
+#[test]
+fn test_set_flags_56() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "--compile-jobs", "2", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      compile_jobs: Some(2),
+      ..DenoFlags::default()
+    }
+  );
 
-  let flags = DenoFlags::from(matches);
-  Ok((flags, rest_argv))
+  let (flags, _) =
+    set_flags(svec!["deno", "run", "--compile-jobs=1", "script.ts"]).unwrap();
+  assert_eq!(flags.compile_jobs, Some(1));
+
+  assert!(set_flags(svec!["deno", "--compile-jobs", "0", "a.ts"]).is_err());
 }
 
 #[test]
-fn test_set_flags_1() {
-  let (flags, rest) = set_flags(svec!["deno", "--version"]).unwrap();
-  assert_eq!(rest, svec!["deno"]);
+fn test_set_flags_57() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "run", "--no-check", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
   assert_eq!(
     flags,
     DenoFlags {
-      version: true,
+      no_check: true,
       ..DenoFlags::default()
     }
   );
 }
 
 #[test]
-fn test_set_flags_2() {
+fn test_set_flags_58() {
   let (flags, rest) =
-    set_flags(svec!["deno", "-r", "-D", "script.ts"]).unwrap();
+    set_flags(svec!["deno", "run", "--config", "tsconfig.json", "script.ts"])
+      .unwrap();
   assert_eq!(rest, svec!["deno", "script.ts"]);
   assert_eq!(
     flags,
     DenoFlags {
-      log_debug: true,
-      reload: true,
+      config_path: Some("tsconfig.json".to_string()),
       ..DenoFlags::default()
     }
   );
+
+  let (flags, _) =
+    set_flags(svec!["deno", "-c", "tsconfig.json", "script.ts"]).unwrap();
+  assert_eq!(flags.config_path, Some("tsconfig.json".to_string()));
 }
 
 #[test]
-fn test_set_flags_3() {
+fn test_set_flags_59() {
   let (flags, rest) =
-    set_flags(svec!["deno", "-r", "--allow-write", "script.ts"]).unwrap();
+    set_flags(svec!["deno", "run", "--full-stack-traces", "script.ts"])
+      .unwrap();
   assert_eq!(rest, svec!["deno", "script.ts"]);
   assert_eq!(
     flags,
     DenoFlags {
-      reload: true,
-      allow_write: true,
+      full_stack_traces: true,
       ..DenoFlags::default()
     }
   );
 }
 
 #[test]
-fn test_set_flags_4() {
+fn test_set_flags_60() {
   let (flags, rest) =
-    set_flags(svec!["deno", "-Dr", "--allow-write", "script.ts"]).unwrap();
+    set_flags(svec!["deno", "run", "--diagnostics=json", "script.ts"]).unwrap();
   assert_eq!(rest, svec!["deno", "script.ts"]);
   assert_eq!(
     flags,
     DenoFlags {
-      log_debug: true,
-      reload: true,
-      allow_write: true,
+      diagnostics_json: true,
       ..DenoFlags::default()
     }
   );
+
+  let (flags, _) =
+    set_flags(svec!["deno", "bundle", "--diagnostics", "pretty", "a.ts"])
+      .unwrap();
+  assert!(!flags.diagnostics_json);
+  assert!(set_flags(svec!["deno", "--diagnostics=xml", "a.ts"]).is_err());
 }
 
 #[test]
-fn test_set_flags_5() {
-  let (flags, rest) = set_flags(svec!["deno", "--types"]).unwrap();
-  assert_eq!(rest, svec!["deno"]);
+fn test_set_flags_61() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "run",
+    "--unhandled-rejections=warn",
+    "script.ts"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
   assert_eq!(
     flags,
     DenoFlags {
-      types: true,
+      unhandled_rejections_warn: true,
       ..DenoFlags::default()
     }
-  )
+  );
+
+  let (flags, _) =
+    set_flags(svec!["deno", "--unhandled-rejections", "error", "a.ts"])
+      .unwrap();
+  assert!(!flags.unhandled_rejections_warn);
+  assert!(
+    set_flags(svec!["deno", "--unhandled-rejections=ignore", "a.ts"]).is_err()
+  );
 }
 
 #[test]
-fn test_set_flags_6() {
+fn test_set_flags_62() {
   let (flags, rest) =
-    set_flags(svec!["deno", "--allow-net", "gist.ts", "--title", "X"]).unwrap();
-  assert_eq!(rest, svec!["deno", "gist.ts", "--title", "X"]);
+    set_flags(svec!["deno", "--trace-ops", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      trace_ops: Some(vec![]),
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, _) =
+    set_flags(svec!["deno", "run", "--trace-ops=read,write,fetch", "a.ts"])
+      .unwrap();
+  assert_eq!(flags.trace_ops, Some(svec!["read", "write", "fetch"]));
+
+  let err = set_flags(svec!["deno", "-q", "--trace-ops", "a.ts"]).unwrap_err();
+  assert!(err.contains("--trace-ops"));
+}
+
+#[test]
+fn test_set_flags_63() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "bench",
+    "--allow-read",
+    "--json",
+    "--filter",
+    "^parse",
+    "--iterations",
+    "50",
+    "--warmup=0",
+    "bench",
+    "url_bench.ts"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "bench", "url_bench.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      bench: true,
+      bench_json: true,
+      bench_filter: Some("^parse".to_string()),
+      bench_iterations: Some(50),
+      bench_warmup: Some(0),
+      allow_read: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, rest) = set_flags(svec!["deno", "bench"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert!(flags.bench && flags.bench_iterations.is_none());
+
+  assert!(set_flags(svec!["deno", "bench", "--iterations", "0"]).is_err());
+  assert!(set_flags(svec!["deno", "bench", "--warmup", "-1"]).is_err());
+}
+
+#[test]
+fn test_set_flags_64() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "test",
+    "--allow-net",
+    "--filter",
+    "http",
+    "--fail-fast",
+    "src",
+    "url_test.ts"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "src", "url_test.ts"]);
   assert_eq!(
     flags,
     DenoFlags {
+      test: true,
+      test_filter: Some("http".to_string()),
+      test_fail_fast: true,
       allow_net: true,
       ..DenoFlags::default()
     }
-  )
+  );
+
+  let (flags, rest) = set_flags(svec!["deno", "test"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert!(flags.test && !flags.test_fail_fast);
 }
 
 #[test]
-fn test_set_flags_7() {
+fn test_set_flags_65() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "test",
+    "--coverage",
+    "--coverage-include",
+    "src/**",
+    "--coverage-include",
+    "https://deno.land/std/**",
+    "--coverage-exclude",
+    "*_test.ts",
+    "src"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "src"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      test: true,
+      coverage: Some("coverage".to_string()),
+      coverage_include: svec!["src/**", "https://deno.land/std/**"],
+      coverage_exclude: svec!["*_test.ts"],
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, _) =
+    set_flags(svec!["deno", "test", "--coverage=target/cov"]).unwrap();
+  assert_eq!(flags.coverage, Some("target/cov".to_string()));
+
+  assert!(set_flags(svec!["deno", "test", "--coverage", "--inspect"]).is_err());
+  assert!(set_flags(svec!["deno", "--inspect", "test", "--coverage"]).is_err());
+  let args =
+    add_preset_flags(svec!["deno", "test", "--coverage"], Some("--inspect"))
+      .unwrap();
+  assert!(set_flags(args).is_err());
+  assert!(
+    set_flags(svec!["deno", "test", "--coverage-exclude", "a.ts"]).is_err()
+  );
+}
+
+#[test]
+fn test_set_flags_66() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "doc",
+    "--reload",
+    "--json",
+    "mod.ts",
+    "Buffer.read"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "mod.ts"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      doc: true,
+      doc_json: true,
+      doc_filter: Some("Buffer.read".to_string()),
+      reload: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, rest) = set_flags(svec!["deno", "doc", "mod.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "mod.ts"]);
+  assert!(flags.doc && flags.doc_filter.is_none());
+  assert!(set_flags(svec!["deno", "doc"]).is_err());
+}
+
+#[test]
+fn test_set_flags_67() {
+  let (flags, rest) = set_flags(svec!["deno", "completions", "zsh"]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      completions: Some("zsh".to_string()),
+      ..DenoFlags::default()
+    }
+  );
+
+  assert!(set_flags(svec!["deno", "completions"]).is_err());
+  assert!(set_flags(svec!["deno", "completions", "tcsh"]).is_err());
+}
+
+#[test]
+fn test_set_flags_68() {
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "upgrade",
+    "--version",
+    "0.4.0",
+    "--output",
+    "bin/deno",
+    "--dry-run"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno"]);
+  assert_eq!(
+    flags,
+    DenoFlags {
+      upgrade: true,
+      upgrade_version: Some("0.4.0".to_string()),
+      upgrade_output: Some("bin/deno".to_string()),
+      upgrade_dry_run: true,
+      ..DenoFlags::default()
+    }
+  );
+
+  let (flags, _) = set_flags(svec!["deno", "upgrade"]).unwrap();
+  assert!(flags.upgrade && flags.upgrade_version.is_none());
+  assert!(set_flags(svec!["deno", "upgrade", "0.4.0"]).is_err());
+}
+
+#[test]
+fn test_set_flags_69() {
+  // Usage errors come back with the usage text, main() prints them.
+  let err = set_flags(svec!["deno", "--v8-flags", "script.ts"]).unwrap_err();
+  assert!(err.contains("--v8-flags"), "{}", err);
+  assert!(err.contains("USAGE:"), "{}", err);
+
+  let err =
+    set_flags(svec!["deno", "run", "--allow-reed", "x.ts"]).unwrap_err();
+  assert!(err.contains("--allow-reed"), "{}", err);
+  assert!(err.contains("USAGE:"), "{}", err);
+
+  let err = set_flags(svec!["deno", "completions", "tcsh"]).unwrap_err();
+  assert!(err.contains("bash"), "{}", err);
+}
+
+#[test]
+fn test_misplaced_flags() {
+  let known = ["allow-net", "allow-read", "reload", "seed"];
+  let args =
+    svec!["--allow-net", "--allow-nett", "--relaod", "--port=80", "80"];
+  let warnings = misplaced_flags("server.ts", &args, &known);
+  assert_eq!(warnings.len(), 3);
+  assert!(warnings[0].contains("deno run --allow-net server.ts"));
+  assert!(warnings[1].contains("did you mean --allow-net"));
+  assert!(warnings[2].contains("did you mean --reload"));
+
+  // Any --allow flag is suspect, the closest one is suggested.
+  let warnings = misplaced_flags("a.ts", &svec!["--allow-reading"], &known);
+  assert_eq!(warnings.len(), 1);
+  assert!(warnings[0].contains("did you mean --allow-read"));
+
+  // The flags of the script, and all flags after an argument which is none.
+  let args = svec!["--verbose", "--out=dist", "--seeds", "serve", "--reload"];
+  assert!(misplaced_flags("a.ts", &args, &known).len() == 1);
+  let args = svec!["--verbose", "--out=dist", "serve", "--allow-net"];
+  assert!(misplaced_flags("a.ts", &args, &known).is_empty());
+  assert!(misplaced_flags("a.ts", &svec!["--", "--reload"], &known).is_empty());
+}
+
+#[test]
+fn test_run_flag_names() {
+  // The long flags which the help of `deno run` lists, but --help.
+  let help =
+    match create_cli_app().get_matches_from_safe(vec!["deno", "run", "-h"]) {
+      Err(err) => err.message,
+      Ok(_) => unreachable!(),
+    };
+  let flag = regex::Regex::new(r"^\s+(?:-\w, )?--([\w-]+)").unwrap();
+  let mut listed: Vec<String> = help
+    .lines()
+    .filter_map(|line| flag.captures(line))
+    .map(|captures| captures[1].to_string())
+    .filter(|name| name != "help")
+    .collect();
+  listed.sort();
+  let mut names: Vec<String> =
+    RUN_FLAG_NAMES.iter().map(|name| name.to_string()).collect();
+  names.sort();
+  assert_eq!(names, listed);
+}
+
+#[test]
+fn test_subcommand_names() {
+  let help = match create_cli_app().get_matches_from_safe(vec!["deno", "-h"]) {
+    Err(err) => err.message,
+    Ok(_) => unreachable!(),
+  };
+  let subcommands = help.split("SUBCOMMANDS:").nth(1).unwrap();
+  let subcommand = regex::Regex::new(r"^    ([\w-]+)").unwrap();
+  let mut listed: Vec<String> = subcommands
+    .lines()
+    .skip(1)
+    .take_while(|line| !line.is_empty())
+    .filter_map(|line| subcommand.captures(line))
+    .map(|captures| captures[1].to_string())
+    .collect();
+  listed.sort();
+  assert_eq!(SUBCOMMAND_NAMES.to_vec(), listed);
+}
+
+#[test]
+fn test_set_flags_70() {
   let (flags, rest) =
-    set_flags(svec!["deno", "--allow-all", "gist.ts"]).unwrap();
-  assert_eq!(rest, svec!["deno", "gist.ts"]);
+    set_flags(svec!["deno", "script.ts", "--allow-nett", "--port", "80"])
+      .unwrap();
+  assert_eq!(
+    rest,
+    svec!["deno", "script.ts", "--allow-nett", "--port", "80"]
+  );
+  assert_eq!(flags, DenoFlags::default());
+
+  let err = set_flags(svec![
+    "deno",
+    "run",
+    "--strict-flags",
+    "script.ts",
+    "--allow-nett"
+  ]).unwrap_err();
+  assert!(err.contains("did you mean --allow-net"), "{}", err);
+  let err = set_flags(svec!["deno", "--strict-flags", "script.ts", "--reload"])
+    .unwrap_err();
+  assert!(err.contains("deno run --reload script.ts"), "{}", err);
+
+  // The arguments of the script after -- are its own.
+  let (flags, rest) = set_flags(svec![
+    "deno",
+    "--strict-flags",
+    "--",
+    "script.ts",
+    "--reload"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts", "--reload"]);
+  assert!(flags.strict_flags && !flags.reload);
+  let (_, rest) = set_flags(svec![
+    "deno",
+    "run",
+    "--strict-flags",
+    "script.ts",
+    "serve",
+    "--allow-net"
+  ]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts", "serve", "--allow-net"]);
+}
+
+#[test]
+fn test_set_flags_71() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "run", "--no-snapshot", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
   assert_eq!(
     flags,
     DenoFlags {
-      allow_net: true,
-      allow_env: true,
-      allow_run: true,
-      allow_read: true,
-      allow_write: true,
-      allow_high_precision: true,
+      no_snapshot: true,
       ..DenoFlags::default()
     }
-  )
+  );
 }
 
 #[test]
-fn test_set_flags_8() {
+fn test_set_flags_72() {
   let (flags, rest) =
-    set_flags(svec!["deno", "--allow-read", "gist.ts"]).unwrap();
-  assert_eq!(rest, svec!["deno", "gist.ts"]);
+    set_flags(svec!["deno", "--prompt-stack", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
   assert_eq!(
     flags,
     DenoFlags {
-      allow_read: true,
+      prompt_stack: true,
       ..DenoFlags::default()
     }
-  )
+  );
 }
 
 #[test]
-fn test_set_flags_9() {
+fn test_set_flags_73() {
   let (flags, rest) =
-    set_flags(svec!["deno", "--allow-high-precision", "script.ts"]).unwrap();
+    set_flags(svec!["deno", "--flags-file", "flags.txt", "script.ts"]).unwrap();
+  assert_eq!(rest, svec!["deno", "script.ts"]);
+  assert_eq!(flags, DenoFlags::default());
+}
+
+#[test]
+fn test_set_flags_74() {
+  let (flags, rest) =
+    set_flags(svec!["deno", "run", "--compat-resolve", "script.ts"]).unwrap();
   assert_eq!(rest, svec!["deno", "script.ts"]);
   assert_eq!(
     flags,
     DenoFlags {
-      allow_high_precision: true,
+      compat_resolve: true,
       ..DenoFlags::default()
     }
-  )
+  );
+}
+
+#[test]
+fn test_add_preset_flags() {
+  let args = svec!["deno", "--allow-read", "script.ts", "--log-level=warn"];
+  let env_flags = "--log-level debug --allow-read=/tmp '--allow-net=a b' -Dr";
+  assert_eq!(
+    add_preset_flags(args.clone(), Some(env_flags)).unwrap(),
+    svec![
+      "deno",
+      "--log-level",
+      "debug",
+      "--allow-net=a b",
+      "-Dr",
+      "--allow-read",
+      "script.ts",
+      "--log-level=warn"
+    ]
+  );
+  // The flags given take precedence, with or without "=".
+  let args = svec!["deno", "run", "--reload", "--log-level", "warn", "x.ts"];
+  let mut expected = args.clone();
+  expected.insert(1, "-q".to_string());
+  assert_eq!(
+    add_preset_flags(args.clone(), Some("-r --log-level=debug -q")).unwrap(),
+    expected
+  );
+  assert_eq!(add_preset_flags(args.clone(), None).unwrap(), args);
+  assert!(add_preset_flags(args.clone(), Some("x.ts")).is_err());
+  assert!(add_preset_flags(args.clone(), Some("'--quiet")).is_err());
+  assert!(add_preset_flags(args, Some("--flags-file=a")).is_err());
+}
+
+#[test]
+fn test_add_preset_flags_file() {
+  let dir = tempfile::TempDir::new().unwrap();
+  let path = dir.path().join("flags.txt");
+  let file = path.to_str().unwrap();
+  let text = "# Shared\n--reload --seed 1\n--allow-read=\"/a b\"\n";
+  fs::write(&path, text).unwrap();
+  let args = svec!["deno", "--flags-file", file, "--seed=2", "x.ts"];
+  // The file takes precedence over DENO_FLAGS.
+  assert_eq!(
+    add_preset_flags(args, Some("--allow-read --allow-env")).unwrap(),
+    svec![
+      "deno",
+      "--allow-env",
+      "--reload",
+      "--allow-read=/a b",
+      "--flags-file",
+      file,
+      "--seed=2",
+      "x.ts"
+    ]
+  );
+  let (flags, _) = set_flags(
+    add_preset_flags(svec!["deno", "--flags-file", file, "x.ts"], None)
+      .unwrap(),
+  ).unwrap();
+  assert!(flags.reload);
+  assert_eq!(flags.seed, Some(1));
+  assert_eq!(flags.read_whitelist, svec!["/a b"]);
+
+  fs::write(&path, "--flags-file other.txt").unwrap();
+  let args = svec!["deno", "--flags-file", file, "x.ts"];
+  assert!(add_preset_flags(args, None).is_err());
+  let args = svec!["deno", "--flags-file=missing.txt", "x.ts"];
+  assert!(add_preset_flags(args, None).is_err());
+}
+
+#[cfg(test)]
+fn random_list<R: rand::Rng>(rng: &mut R) -> Vec<String> {
+  use rand::seq::SliceRandom;
+  let pool = ["a", "/tmp/b c", "deno.land", "127.0.0.1:4545"];
+  let n = rng.gen_range(1, pool.len() + 1);
+  pool
+    .choose_multiple(rng, n)
+    .map(|s| s.to_string())
+    .collect()
+}
+
+/// Flags of `deno run`, as set_flags() can return them.
+#[cfg(test)]
+fn random_run_flags<R: rand::Rng>(rng: &mut R) -> DenoFlags {
+  use rand::seq::SliceRandom;
+  let mut flags = DenoFlags::default();
+  {
+    let mut permissions = [
+      (&mut flags.allow_read, &mut flags.read_whitelist),
+      (&mut flags.allow_write, &mut flags.write_whitelist),
+      (&mut flags.allow_net, &mut flags.net_whitelist),
+      (&mut flags.allow_env, &mut flags.env_whitelist),
+      (&mut flags.allow_run, &mut flags.run_whitelist),
+    ];
+    for (allow, whitelist) in permissions.iter_mut() {
+      match rng.gen_range(0, 3) {
+        0 => **allow = true,
+        1 => **whitelist = random_list(rng),
+        _ => {}
+      }
+    }
+  }
+  flags.allow_high_precision = rng.gen();
+  flags.deny_read = rng.gen();
+  flags.deny_write = rng.gen();
+  flags.deny_net = rng.gen();
+  flags.deny_env = rng.gen();
+  flags.deny_run = rng.gen();
+  flags.no_prompts = rng.gen();
+  if rng.gen() {
+    flags.lock = Some("lock.json".to_string());
+    flags.lock_write = rng.gen();
+  }
+  let levels = [
+    LogLevel::Debug,
+    LogLevel::Info,
+    LogLevel::Warn,
+    LogLevel::Error,
+  ];
+  flags.log_level = *levels.choose(rng).unwrap();
+  let colors = [ColorMode::Auto, ColorMode::Always, ColorMode::Never];
+  flags.color = *colors.choose(rng).unwrap();
+  match rng.gen_range(0, 4) {
+    0 => flags.reload = true,
+    1 => {
+      flags.reload = true;
+      flags.reload_full = true;
+    }
+    2 => flags.reload_whitelist = random_list(rng),
+    _ => flags.cached_only = rng.gen(),
+  }
+  if rng.gen() {
+    flags.trace_ops = Some(if rng.gen() { vec![] } else { random_list(rng) });
+  }
+  // --quiet can't be combined with debug logging or --trace-ops.
+  flags.quiet = flags.log_level != LogLevel::Debug
+    && flags.trace_ops.is_none()
+    && rng.gen();
+  flags.no_check = rng.gen();
+  flags.compat_resolve = rng.gen();
+  flags.diagnostics_json = rng.gen();
+  flags.full_stack_traces = rng.gen();
+  flags.unhandled_rejections_warn = rng.gen();
+  flags.watch = rng.gen();
+  flags.strict_flags = rng.gen();
+  flags.no_snapshot = rng.gen();
+  flags.prompt_stack = rng.gen();
+  let path = |rng: &mut R, path: &str| {
+    if rng.gen() {
+      Some(path.to_string())
+    } else {
+      None
+    }
+  };
+  flags.deno_dir = path(rng, "/tmp/deno dir");
+  flags.import_map_path = path(rng, "import_map.json");
+  flags.config_path = path(rng, "tsconfig.json");
+  flags.ca_file = path(rng, "certs.pem");
+  flags.stdin_ext = path(rng, "js");
+  if rng.gen() {
+    flags.max_redirects = Some(rng.gen_range(0, 30));
+  }
+  if rng.gen() {
+    flags.compile_jobs = Some(rng.gen_range(1, 17));
+  }
+  if rng.gen() {
+    let initial = rng.gen_range(256, 1024);
+    flags.initial_heap_size = Some(initial);
+    if rng.gen() {
+      flags.max_heap_size = Some(initial + rng.gen_range(0, 2048));
+    }
+  }
+  if rng.gen() {
+    flags.seed = Some(rng.gen_range(1, std::i32::MAX as u32));
+  }
+  if rng.gen() {
+    let addrs = ["127.0.0.1:9229", "0.0.0.0:9230", "[::1]:9229"];
+    let addr = Some(addrs.choose(rng).unwrap().parse().unwrap());
+    if rng.gen() {
+      flags.inspect_brk = addr;
+    } else {
+      flags.inspect = addr;
+    }
+  }
+  flags
+}
+
+#[test]
+fn test_to_argv() {
+  assert!(DenoFlags::default().to_argv().is_empty());
+  let flags = DenoFlags {
+    allow_net: true,
+    read_whitelist: svec!["/tmp", "."],
+    reload_full: true,
+    reload: true,
+    trace_ops: Some(vec![]),
+    seed: Some(42),
+    ..DenoFlags::default()
+  };
+  assert_eq!(
+    flags.to_argv(),
+    svec![
+      "--allow-read=/tmp,.",
+      "--allow-net",
+      "--reload=full",
+      "--trace-ops",
+      "--seed=42"
+    ]
+  );
+
+  // Whatever the flags, they are parsed back into the same. A failure is
+  // reproduced with the seed it prints.
+  use rand::SeedableRng;
+  let seed: u64 = rand::random();
+  let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+  for _ in 0..500 {
+    let flags = random_run_flags(&mut rng);
+    let mut argv = svec!["deno", "run"];
+    argv.extend(flags.to_argv());
+    argv.push("script.ts".to_string());
+    let (parsed, rest) = set_flags(argv.clone()).unwrap_or_else(|err| {
+      panic!("seed {}: {:?} from {:?}: {}", seed, argv, flags, err)
+    });
+    assert_eq!(rest, svec!["deno", "script.ts"], "seed {}", seed);
+    assert_eq!(parsed, flags, "seed {}: {:?}", seed, argv);
+  }
 }
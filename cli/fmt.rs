@@ -0,0 +1,524 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! `deno fmt` finds the files to format and formats them with std's prettier,
+//! several at once. Directories are searched for the kinds of files the
+//! formatter supports, skipping `node_modules` and the paths matching one of
+//! the `--ignore` globs.
+use crate::flags::FmtConfig;
+use crate::flags::FmtIndent;
+use regex::Regex;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use tempfile::NamedTempFile;
+
+pub const PRETTIER_URL: &str = "https://deno.land/std/prettier/main.ts";
+
+const EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "json", "md"];
+const SKIPPED_DIRS: &[&str] = &["node_modules"];
+
+/// The options of std's prettier for `config`.
+pub fn prettier_args(config: &FmtConfig) -> Vec<String> {
+  let mut args = Vec::new();
+  if let Some(width) = config.line_width {
+    args.push(format!("--print-width={}", width));
+  }
+  match config.indent {
+    Some(FmtIndent::Spaces(n)) => args.push(format!("--tab-width={}", n)),
+    Some(FmtIndent::Tab) => args.push("--use-tabs".to_string()),
+    None => {}
+  }
+  if config.single_quote {
+    args.push("--single-quote".to_string());
+  }
+  if config.no_semicolons {
+    args.push("--no-semi".to_string());
+  }
+  args
+}
+
+/// A pattern of `--ignore`. `*` and `?` match within a path component, `**`
+/// across components. A pattern without a `/` matches any file or directory
+/// of that name, otherwise it matches the path as found below the given
+/// directories, e.g. `src/gen/a.ts` for `deno fmt src`.
+#[derive(Debug)]
+pub struct Glob {
+  regex: Regex,
+  matches_name: bool,
+}
+
+impl Glob {
+  pub fn new(pattern: &str) -> Self {
+    let mut regex = String::from("^");
+    let mut chars = pattern.trim_start_matches("./").chars().peekable();
+    while let Some(c) = chars.next() {
+      match c {
+        '*' if chars.peek() == Some(&'*') => {
+          chars.next();
+          if chars.peek() == Some(&'/') {
+            chars.next();
+            regex.push_str("(?:.*/)?");
+          } else {
+            regex.push_str(".*");
+          }
+        }
+        '*' => regex.push_str("[^/]*"),
+        '?' => regex.push_str("[^/]"),
+        c => regex.push_str(&regex::escape(&c.to_string())),
+      }
+    }
+    regex.push('$');
+    Self {
+      regex: Regex::new(&regex).unwrap(),
+      matches_name: !pattern.contains('/'),
+    }
+  }
+
+  pub fn is_match(&self, path: &Path) -> bool {
+    let path = path.strip_prefix(".").unwrap_or(path);
+    if self.matches_name {
+      path
+        .file_name()
+        .map_or(false, |name| self.regex.is_match(&name.to_string_lossy()))
+    } else {
+      self.regex.is_match(&path.to_string_lossy().replace('\\', "/"))
+    }
+  }
+}
+
+/// Returns the files to format for the `paths` given on the command line.
+/// Files are taken as they are, directories are searched. Each file is listed
+/// once, also when symlinks lead to it several times, and entries which can't
+/// be read are passed to `warn` and skipped.
+pub fn collect_files<F: FnMut(String)>(
+  paths: &[PathBuf],
+  ignore: &[Glob],
+  warn: F,
+) -> Vec<PathBuf> {
+  collect_files_matching(paths, ignore, has_extension, warn)
+}
+
+/// Like `collect_files()`, for the files found in directories for which
+/// `is_wanted` is true, e.g. the bench files of `deno bench`.
+pub fn collect_files_matching<F: FnMut(String)>(
+  paths: &[PathBuf],
+  ignore: &[Glob],
+  is_wanted: fn(&Path) -> bool,
+  mut warn: F,
+) -> Vec<PathBuf> {
+  let mut collector = Collector {
+    ignore,
+    is_wanted,
+    warn: &mut warn,
+    visited: HashSet::new(),
+    files: Vec::new(),
+  };
+  for path in paths {
+    match fs::metadata(path) {
+      Ok(ref metadata) if metadata.is_dir() => collector.collect_dir(path),
+      Ok(_) => collector.add_file(path.clone()),
+      Err(err) => {
+        (collector.warn)(format!("Unable to read {}: {}", path.display(), err))
+      }
+    }
+  }
+  collector.files
+}
+
+struct Collector<'a> {
+  ignore: &'a [Glob],
+  is_wanted: fn(&Path) -> bool,
+  warn: &'a mut dyn FnMut(String),
+  /// The canonical paths of the files and directories seen so far.
+  visited: HashSet<PathBuf>,
+  files: Vec<PathBuf>,
+}
+
+impl<'a> Collector<'a> {
+  /// Whether `path` was not seen before, by any other name.
+  fn visit(&mut self, path: &Path) -> bool {
+    match fs::canonicalize(path) {
+      Ok(canonical) => self.visited.insert(canonical),
+      Err(err) => {
+        (self.warn)(format!("Unable to read {}: {}", path.display(), err));
+        false
+      }
+    }
+  }
+
+  fn add_file(&mut self, path: PathBuf) {
+    if self.visit(&path) {
+      self.files.push(path);
+    }
+  }
+
+  fn collect_dir(&mut self, dir: &Path) {
+    // A symlink loop leads back to a directory which was visited already.
+    if !self.visit(dir) {
+      return;
+    }
+    let entries = match fs::read_dir(dir) {
+      Ok(entries) => entries,
+      Err(err) => {
+        (self.warn)(format!("Unable to read {}: {}", dir.display(), err));
+        return;
+      }
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+      match entry {
+        Ok(entry) => paths.push(entry.path()),
+        Err(err) => {
+          (self.warn)(format!("Unable to read {}: {}", dir.display(), err))
+        }
+      }
+    }
+    // The order doesn't depend on the file system.
+    paths.sort();
+    for path in paths {
+      if self.ignore.iter().any(|glob| glob.is_match(&path)) {
+        continue;
+      }
+      // Follows symlinks.
+      match fs::metadata(&path) {
+        Ok(metadata) => {
+          if metadata.is_dir() {
+            if !is_skipped_dir(&path) {
+              self.collect_dir(&path);
+            }
+          } else if (self.is_wanted)(&path) {
+            self.add_file(path);
+          }
+        }
+        Err(err) => {
+          (self.warn)(format!("Unable to read {}: {}", path.display(), err))
+        }
+      }
+    }
+  }
+}
+
+fn is_skipped_dir(path: &Path) -> bool {
+  path
+    .file_name()
+    .map_or(false, |name| SKIPPED_DIRS.iter().any(|dir| name == *dir))
+}
+
+fn has_extension(path: &Path) -> bool {
+  path
+    .extension()
+    .map_or(false, |ext| EXTENSIONS.iter().any(|e| ext == *e))
+}
+
+/// Formats source code with std's prettier, in a child deno which reads the
+/// code from stdin and writes the formatted code to stdout.
+#[derive(Clone)]
+pub struct Formatter {
+  program: PathBuf,
+  args: Vec<String>,
+}
+
+impl Formatter {
+  /// A formatter with the options of `prettier_args()`.
+  pub fn new(prettier_args: Vec<String>) -> Self {
+    let mut args = vec![
+      "run".to_string(),
+      "--allow-read".to_string(),
+      PRETTIER_URL.to_string(),
+    ];
+    args.extend(prettier_args);
+    Self {
+      program: env::current_exe().unwrap(),
+      args,
+    }
+  }
+
+  /// Formats `source`, the contents of `path`. Its extension tells which
+  /// parser to use, as the formatter doesn't see the file name.
+  fn format(&self, path: &Path, source: &[u8]) -> Result<Vec<u8>, String> {
+    let mut command = Command::new(&self.program);
+    command.args(&self.args);
+    if let Some(parser) = parser(path) {
+      command.arg(format!("--stdin-parser={}", parser));
+    }
+    let mut child = command
+      .arg("-")
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()
+      .map_err(|err| err.to_string())?;
+    // The formatter reads all of stdin before it writes anything.
+    child
+      .stdin
+      .take()
+      .unwrap()
+      .write_all(source)
+      .map_err(|err| err.to_string())?;
+    let output = child.wait_with_output().map_err(|err| err.to_string())?;
+    if !output.status.success() {
+      return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(output.stdout)
+  }
+}
+
+fn parser(path: &Path) -> Option<&'static str> {
+  match path.extension()?.to_str()? {
+    "ts" | "tsx" => Some("typescript"),
+    "js" | "jsx" => Some("babel"),
+    "json" => Some("json"),
+    "md" => Some("markdown"),
+    _ => None,
+  }
+}
+
+/// What `format_files()` did with a file. Unformatted files which are only
+/// checked count as changed.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+  Changed,
+  Unchanged,
+  Failed(String),
+}
+
+/// Formats `files` with up to `jobs` formatters running at once, or with
+/// `check` only finds out which of them are not formatted. The results are
+/// sorted by path, whatever order the files were done in.
+pub fn format_files(
+  formatter: &Formatter,
+  files: Vec<PathBuf>,
+  jobs: usize,
+  check: bool,
+) -> Vec<(PathBuf, Outcome)> {
+  let mut files = files.into_iter();
+  let mut results = Vec::new();
+  // The first file is formatted alone, so that prettier is downloaded and
+  // compiled once instead of by every job at the same time.
+  if let Some(first) = files.next() {
+    let outcome = format_file(formatter, &first, check);
+    results.push((first, outcome));
+  }
+
+  let queue = Arc::new(Mutex::new(files));
+  let (sender, receiver) = mpsc::channel();
+  let workers: Vec<_> = (0..jobs)
+    .map(|_| {
+      let formatter = formatter.clone();
+      let queue = queue.clone();
+      let sender = sender.clone();
+      thread::spawn(move || loop {
+        let next = queue.lock().unwrap().next();
+        match next {
+          Some(file) => {
+            let outcome = format_file(&formatter, &file, check);
+            sender.send((file, outcome)).unwrap();
+          }
+          None => break,
+        }
+      })
+    }).collect();
+  drop(sender);
+  results.extend(receiver);
+  for worker in workers {
+    worker.join().unwrap();
+  }
+  results.sort_by(|a, b| a.0.cmp(&b.0));
+  results
+}
+
+fn format_file(formatter: &Formatter, path: &Path, check: bool) -> Outcome {
+  let result = fs::read(path)
+    .map_err(|err| err.to_string())
+    .and_then(|source| {
+      let formatted = formatter.format(path, &source)?;
+      if formatted == source {
+        return Ok(Outcome::Unchanged);
+      }
+      if !check {
+        write_atomic(path, &formatted).map_err(|err| err.to_string())?;
+      }
+      Ok(Outcome::Changed)
+    });
+  result.unwrap_or_else(Outcome::Failed)
+}
+
+/// Replaces the contents of `path` by renaming a file written next to it, so
+/// that an interrupted write leaves the old contents in place.
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+  let dir = match path.parent() {
+    Some(dir) if dir != Path::new("") => dir,
+    _ => Path::new("."),
+  };
+  let mut file = NamedTempFile::new_in(dir)?;
+  file.write_all(contents)?;
+  fs::set_permissions(file.path(), fs::metadata(path)?.permissions())?;
+  file.persist(path).map_err(|err| err.error)?;
+  Ok(())
+}
+
+/// Prints the results of `format_files()`, the unformatted files first when
+/// checking, and returns the exit code.
+pub fn report(
+  results: &[(PathBuf, Outcome)],
+  check: bool,
+  shows_info: bool,
+) -> i32 {
+  let mut changed = 0;
+  let mut failed = 0;
+  for (path, outcome) in results {
+    match outcome {
+      Outcome::Changed => {
+        changed += 1;
+        if check {
+          println!("{}", path.display());
+        }
+      }
+      Outcome::Unchanged => {}
+      Outcome::Failed(err) => {
+        failed += 1;
+        eprintln!("Unable to format {}: {}", path.display(), err);
+      }
+    }
+  }
+  let unchanged = results.len() - changed - failed;
+  if check && changed > 0 {
+    eprintln!("{} of {} files are not formatted", changed, results.len());
+  } else if shows_info {
+    if check {
+      eprintln!("{} files are formatted", unchanged);
+    } else {
+      eprintln!("Formatted {} files, {} unchanged", changed, unchanged);
+    }
+  }
+  if failed > 0 || (check && changed > 0) {
+    1
+  } else {
+    0
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  fn write(path: &Path) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, "").unwrap();
+  }
+
+  fn collect(paths: &[PathBuf], ignore: &[&str]) -> Vec<PathBuf> {
+    let ignore: Vec<Glob> = ignore.iter().map(|p| Glob::new(p)).collect();
+    collect_files(paths, &ignore, |warning| panic!("{}", warning))
+  }
+
+  #[test]
+  fn test_glob() {
+    let glob = Glob::new("*.min.js");
+    assert!(glob.is_match(Path::new("./src/a.min.js")));
+    assert!(!glob.is_match(Path::new("src/a.js")));
+    let glob = Glob::new("src/gen/**");
+    assert!(glob.is_match(Path::new("./src/gen/a/b.ts")));
+    assert!(!glob.is_match(Path::new("lib/src/gen/a.ts")));
+    let glob = Glob::new("**/fixture?.ts");
+    assert!(glob.is_match(Path::new("fixture1.ts")));
+    assert!(glob.is_match(Path::new("a/b/fixture2.ts")));
+    assert!(!glob.is_match(Path::new("a/fixture10.ts")));
+    assert!(Glob::new("a+b").is_match(Path::new("a+b")));
+  }
+
+  #[test]
+  fn test_collect_files() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let root = temp_dir.path();
+    write(&root.join("a.ts"));
+    write(&root.join("b.txt"));
+    write(&root.join("docs/README.md"));
+    write(&root.join("node_modules/c.js"));
+    write(&root.join("gen/d.ts"));
+    write(&root.join("e.min.js"));
+
+    assert_eq!(
+      collect(&[root.to_path_buf()], &["gen", "*.min.js"]),
+      vec![root.join("a.ts"), root.join("docs/README.md")]
+    );
+    // Given files are formatted whatever their extension.
+    assert_eq!(
+      collect(&[root.join("b.txt"), root.join("b.txt")], &[]),
+      vec![root.join("b.txt")]
+    );
+
+    let mut warnings = Vec::new();
+    let files =
+      collect_files(&[root.join("missing.ts")], &[], |w| warnings.push(w));
+    assert!(files.is_empty());
+    assert_eq!(warnings.len(), 1);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_collect_files_symlinks() {
+    use std::os::unix::fs::symlink;
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let root = temp_dir.path();
+    write(&root.join("src/a.ts"));
+    symlink(root, root.join("src/loop")).unwrap();
+    symlink(root.join("src/a.ts"), root.join("link.ts")).unwrap();
+    assert_eq!(collect(&[root.to_path_buf()], &[]), vec![root.join("link.ts")]);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_format_files() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let a = temp_dir.path().join("a.ts");
+    let b = temp_dir.path().join("b.ts");
+    let missing = temp_dir.path().join("missing.ts");
+    fs::write(&a, "abc").unwrap();
+    fs::write(&b, "ABC").unwrap();
+    // Ignores the parser and - arguments.
+    let formatter = Formatter {
+      program: PathBuf::from("sh"),
+      args: vec!["-c".to_string(), "tr a-z A-Z".to_string()],
+    };
+    let files = vec![b.clone(), missing.clone(), a.clone()];
+
+    let results = format_files(&formatter, files.clone(), 2, true);
+    assert_eq!(results[0], (a.clone(), Outcome::Changed));
+    assert_eq!(results[1], (b.clone(), Outcome::Unchanged));
+    assert_eq!(results[2].0, missing);
+    assert_eq!(fs::read_to_string(&a).unwrap(), "abc");
+    assert_eq!(report(&results, true, false), 1);
+
+    let results = format_files(&formatter, files, 2, false);
+    assert_eq!(results[0], (a.clone(), Outcome::Changed));
+    assert_eq!(fs::read_to_string(&a).unwrap(), "ABC");
+    assert_eq!(report(&results[..2], false, false), 0);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_write_atomic() {
+    use std::os::unix::fs::PermissionsExt;
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let path = temp_dir.path().join("a.ts");
+    fs::write(&path, "old").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    write_atomic(&path, b"new").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+    // Only the file itself is left.
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+  }
+}
@@ -1,16 +1,25 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+use crate::errors;
+use crate::errors::DenoResult;
 use std;
-use std::fs::{create_dir, DirBuilder, File, OpenOptions};
+use std::collections::VecDeque;
+use std::fs::{create_dir, DirBuilder, DirEntry, File, Metadata, OpenOptions};
 use std::io::ErrorKind;
 use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use rand;
 use rand::Rng;
 
+#[cfg(any(unix))]
+use libc;
+#[cfg(any(unix))]
+use std::ffi::CString;
 #[cfg(any(unix))]
 use std::os::unix::fs::DirBuilderExt;
 #[cfg(any(unix))]
+use std::os::unix::fs::OpenOptionsExt;
+#[cfg(any(unix))]
 use std::os::unix::fs::PermissionsExt;
 
 pub fn write_file<T: AsRef<[u8]>>(
@@ -55,23 +64,45 @@ fn set_permissions(_file: &mut File, _perm: u32) -> std::io::Result<()> {
   Ok(())
 }
 
-pub fn make_temp_dir(
+/// Creates a new directory, or with `is_file` an empty file, in `dir` or
+/// else the temporary directory of the system, named `prefix`, a random part
+/// and `suffix`. Only the owner can access it. Returns its absolute path.
+pub fn make_temp(
   dir: Option<&Path>,
   prefix: Option<&str>,
   suffix: Option<&str>,
+  is_file: bool,
 ) -> std::io::Result<PathBuf> {
   let prefix_ = prefix.unwrap_or("");
   let suffix_ = suffix.unwrap_or("");
-  let mut buf: PathBuf = match dir {
+  // Either could otherwise name an entry outside of `dir`.
+  for part in &[prefix_, suffix_] {
+    if part.contains('/') || part.contains('\\') {
+      return Err(std::io::Error::new(
+        ErrorKind::InvalidInput,
+        format!("Invalid temp name \"{}\", it has a path separator", part),
+      ));
+    }
+  }
+  let dir = match dir {
     Some(ref p) => p.to_path_buf(),
     None => std::env::temp_dir(),
-  }.join("_");
+  };
+  let dir = if dir.is_absolute() {
+    dir
+  } else {
+    std::env::current_dir()?.join(dir)
+  };
+  let mut buf = dir.join("_");
   let mut rng = rand::thread_rng();
   loop {
     let unique = rng.gen::<u32>();
     buf.set_file_name(format!("{}{:08x}{}", prefix_, unique, suffix_));
-    // TODO: on posix, set mode flags to 0o700.
-    let r = create_dir(buf.as_path());
+    let r = if is_file {
+      create_temp_file(buf.as_path())
+    } else {
+      create_temp_dir(buf.as_path())
+    };
     match r {
       Err(ref e) if e.kind() == ErrorKind::AlreadyExists => continue,
       Ok(_) => return Ok(buf),
@@ -80,6 +111,29 @@ pub fn make_temp_dir(
   }
 }
 
+fn create_temp_dir(path: &Path) -> std::io::Result<()> {
+  let mut builder = DirBuilder::new();
+  set_dir_permission(&mut builder, 0o700);
+  builder.create(path)
+}
+
+fn create_temp_file(path: &Path) -> std::io::Result<()> {
+  let mut options = OpenOptions::new();
+  options.write(true).create_new(true);
+  set_open_permission(&mut options, 0o600);
+  options.open(path).map(|_| ())
+}
+
+#[cfg(any(unix))]
+fn set_open_permission(options: &mut OpenOptions, perm: u32) {
+  options.mode(perm);
+}
+
+#[cfg(not(any(unix)))]
+fn set_open_permission(_options: &mut OpenOptions, _perm: u32) {
+  // NOOP on windows
+}
+
 pub fn mkdir(path: &Path, perm: u32, recursive: bool) -> std::io::Result<()> {
   debug!("mkdir -p {}", path.display());
   let mut builder = DirBuilder::new();
@@ -108,3 +162,329 @@ pub fn normalize_path(path: &Path) -> String {
     s
   }
 }
+
+#[cfg(any(unix))]
+pub fn chown(path: &str, uid: u32, gid: u32) -> DenoResult<()> {
+  let c_path = match CString::new(path) {
+    Ok(c_path) => c_path,
+    Err(_) => {
+      return Err(errors::new(
+        errors::ErrorKind::InvalidInput,
+        format!("Invalid path \"{}\"", path),
+      ))
+    }
+  };
+  if unsafe { libc::chown(c_path.as_ptr(), uid, gid) } == 0 {
+    Ok(())
+  } else {
+    Err(std::io::Error::last_os_error().into())
+  }
+}
+
+#[cfg(not(any(unix)))]
+pub fn chown(_path: &str, _uid: u32, _gid: u32) -> DenoResult<()> {
+  Err(errors::new(
+    errors::ErrorKind::NotSupported,
+    "chown is not supported on Windows".to_string(),
+  ))
+}
+
+/// Sets the umask to `mask` and returns the previous one. Without `mask` the
+/// umask is only returned, which still sets it for a moment, as that is the
+/// only way to read it.
+#[cfg(any(unix))]
+pub fn umask(mask: Option<u32>) -> DenoResult<u32> {
+  let previous = unsafe { libc::umask(mask.unwrap_or(0) as libc::mode_t) };
+  if mask.is_none() {
+    unsafe { libc::umask(previous) };
+  }
+  Ok(previous as u32)
+}
+
+#[cfg(not(any(unix)))]
+pub fn umask(_mask: Option<u32>) -> DenoResult<u32> {
+  Err(errors::new(
+    errors::ErrorKind::NotSupported,
+    "umask is not supported on Windows".to_string(),
+  ))
+}
+
+/// Copies the contents and the permissions of the file `from` to `to`. An
+/// existing `to` is replaced with `overwrite`, otherwise it is an
+/// `AlreadyExists` error.
+pub fn copy_file(from: &Path, to: &Path, overwrite: bool) -> DenoResult<()> {
+  let mut reader = File::open(from)?;
+  let metadata = reader.metadata()?;
+  if !metadata.is_file() {
+    return Err(errors::new(
+      errors::ErrorKind::InvalidInput,
+      format!("{} is not a file", from.display()),
+    ));
+  }
+  if !overwrite && clone_file(from, to)? {
+    return Ok(());
+  }
+  let mut options = OpenOptions::new();
+  if overwrite {
+    options.write(true).create(true).truncate(true);
+  } else {
+    options.write(true).create_new(true);
+  }
+  // Only the owner can read the copy until it has the permissions of `from`.
+  set_open_permission(&mut options, 0o600);
+  let mut writer = options.open(to)?;
+  copy_contents(&mut reader, &mut writer, metadata.len())?;
+  writer.set_permissions(metadata.permissions())?;
+  Ok(())
+}
+
+/// Clones `from` to the new file `to`, which shares the blocks of `from`
+/// until either is written. True if it did, false if the file system cannot.
+#[cfg(target_os = "macos")]
+fn clone_file(from: &Path, to: &Path) -> DenoResult<bool> {
+  extern "C" {
+    fn clonefile(
+      src: *const libc::c_char,
+      dst: *const libc::c_char,
+      flags: u32,
+    ) -> libc::c_int;
+  }
+  let c_from = path_to_cstring(from)?;
+  let c_to = path_to_cstring(to)?;
+  if unsafe { clonefile(c_from.as_ptr(), c_to.as_ptr(), 0) } == 0 {
+    return Ok(true);
+  }
+  let err = std::io::Error::last_os_error();
+  match err.raw_os_error() {
+    Some(libc::ENOTSUP) | Some(libc::EXDEV) => Ok(false),
+    _ => Err(err.into()),
+  }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn clone_file(_from: &Path, _to: &Path) -> DenoResult<bool> {
+  Ok(false)
+}
+
+/// Copies `len` bytes inside the kernel, between files on the same file
+/// system this can share their blocks too. From wherever it stops, e.g. when
+/// the kernel is too old, the rest is read and written.
+#[cfg(target_os = "linux")]
+fn copy_contents(
+  reader: &mut File,
+  writer: &mut File,
+  len: u64,
+) -> std::io::Result<()> {
+  use std::os::unix::io::AsRawFd;
+  let mut remaining = len;
+  while remaining > 0 {
+    let chunk = std::cmp::min(remaining, 1 << 30) as usize;
+    let copied = unsafe {
+      libc::syscall(
+        libc::SYS_copy_file_range,
+        reader.as_raw_fd(),
+        std::ptr::null_mut::<libc::loff_t>(),
+        writer.as_raw_fd(),
+        std::ptr::null_mut::<libc::loff_t>(),
+        chunk,
+        0,
+      )
+    };
+    if copied < 0 {
+      let err = std::io::Error::last_os_error();
+      match err.raw_os_error() {
+        Some(libc::ENOSYS) | Some(libc::EXDEV) | Some(libc::EINVAL)
+        | Some(libc::EPERM) => break,
+        _ => return Err(err),
+      }
+    }
+    if copied == 0 {
+      break;
+    }
+    remaining -= copied as u64;
+  }
+  std::io::copy(reader, writer).map(|_| ())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn copy_contents(
+  reader: &mut File,
+  writer: &mut File,
+  _len: u64,
+) -> std::io::Result<()> {
+  std::io::copy(reader, writer).map(|_| ())
+}
+
+/// Renames `from` to `to`. An existing `to` is replaced with `overwrite`,
+/// otherwise it is an `AlreadyExists` error. When they are on different file
+/// systems, which the OS cannot rename across, `from` is copied and removed.
+pub fn rename(from: &Path, to: &Path, overwrite: bool) -> DenoResult<()> {
+  if !overwrite && to.symlink_metadata().is_ok() {
+    return Err(errors::new(
+      errors::ErrorKind::AlreadyExists,
+      format!("{} already exists", to.display()),
+    ));
+  }
+  match std::fs::rename(from, to) {
+    Ok(()) => Ok(()),
+    Err(ref e) if is_cross_device(e) => {
+      copy_all(from, to, overwrite)?;
+      remove_all(from)?;
+      Ok(())
+    }
+    Err(e) => Err(e.into()),
+  }
+}
+
+#[cfg(any(unix))]
+fn is_cross_device(err: &std::io::Error) -> bool {
+  err.raw_os_error() == Some(libc::EXDEV)
+}
+
+#[cfg(not(any(unix)))]
+fn is_cross_device(err: &std::io::Error) -> bool {
+  // ERROR_NOT_SAME_DEVICE
+  err.raw_os_error() == Some(17)
+}
+
+/// Copies `from` to `to` the way `rename()` would have moved it, a directory
+/// with everything in it and a symlink as a link to the same target.
+fn copy_all(from: &Path, to: &Path, overwrite: bool) -> DenoResult<()> {
+  let metadata = from.symlink_metadata()?;
+  let file_type = metadata.file_type();
+  if file_type.is_symlink() {
+    if overwrite && to.symlink_metadata().is_ok() {
+      std::fs::remove_file(to)?;
+    }
+    copy_symlink(from, to)
+  } else if file_type.is_dir() {
+    // Like a rename, only an empty directory can be replaced.
+    if overwrite && to.is_dir() {
+      std::fs::remove_dir(to)?;
+    }
+    create_dir(to)?;
+    for entry in std::fs::read_dir(from)? {
+      let entry = entry?;
+      copy_all(&entry.path(), &to.join(entry.file_name()), false)?;
+    }
+    std::fs::set_permissions(to, metadata.permissions())?;
+    Ok(())
+  } else {
+    copy_file(from, to, overwrite)
+  }
+}
+
+#[cfg(any(unix))]
+fn copy_symlink(from: &Path, to: &Path) -> DenoResult<()> {
+  std::os::unix::fs::symlink(std::fs::read_link(from)?, to)?;
+  Ok(())
+}
+
+#[cfg(not(any(unix)))]
+fn copy_symlink(from: &Path, to: &Path) -> DenoResult<()> {
+  let target = std::fs::read_link(from)?;
+  if from.is_dir() {
+    std::os::windows::fs::symlink_dir(target, to)?;
+  } else {
+    std::os::windows::fs::symlink_file(target, to)?;
+  }
+  Ok(())
+}
+
+fn remove_all(path: &Path) -> std::io::Result<()> {
+  if path.symlink_metadata()?.is_dir() {
+    std::fs::remove_dir_all(path)
+  } else {
+    remove_file_or_link(path)
+  }
+}
+
+/// Removes the file or symlink `path`, a symlink itself and never what it
+/// points to.
+#[cfg(any(unix))]
+pub fn remove_file_or_link(path: &Path) -> std::io::Result<()> {
+  std::fs::remove_file(path)
+}
+
+/// A symlink to a directory is removed like a directory on Windows.
+#[cfg(not(any(unix)))]
+pub fn remove_file_or_link(path: &Path) -> std::io::Result<()> {
+  std::fs::remove_file(path).or_else(|e| {
+    let is_link = path
+      .symlink_metadata()
+      .map(|m| m.file_type().is_symlink())
+      .unwrap_or(false);
+    if is_link {
+      std::fs::remove_dir(path)
+    } else {
+      Err(e)
+    }
+  })
+}
+
+/// The entries of the directory `path` with their metadata, that of a
+/// symlink itself. With `recursive` the entries of the directories below come
+/// after those of their parents, symlinks to directories are not followed, so
+/// that a cycle of links ends. A directory is read to the end before the next
+/// one is opened, however deep the tree.
+pub fn read_dir(
+  path: &Path,
+  recursive: bool,
+) -> std::io::Result<Vec<(DirEntry, Metadata)>> {
+  let mut entries = Vec::new();
+  let mut pending = VecDeque::new();
+  pending.push_back(path.to_path_buf());
+  while let Some(dir) = pending.pop_front() {
+    for entry in std::fs::read_dir(&dir)? {
+      let entry = entry?;
+      let metadata = entry.metadata()?;
+      if recursive && metadata.is_dir() {
+        pending.push_back(entry.path());
+      }
+      entries.push((entry, metadata));
+    }
+  }
+  Ok(entries)
+}
+
+#[cfg(target_os = "macos")]
+fn path_to_cstring(path: &Path) -> DenoResult<CString> {
+  use std::os::unix::ffi::OsStrExt;
+  CString::new(path.as_os_str().as_bytes()).map_err(|_| {
+    errors::new(
+      errors::ErrorKind::InvalidInput,
+      format!("Invalid path \"{}\"", path.display()),
+    )
+  })
+}
+
+/// Resolves `path` against the current working directory and canonicalizes
+/// every component that exists on disk. Unlike `fs::canonicalize()` this works
+/// for paths that do not exist yet (e.g. a file about to be created), while
+/// still resolving symlinks and `..` in the part of the path that does exist.
+pub fn resolve_from_cwd(path: &Path) -> std::io::Result<PathBuf> {
+  let absolute = if path.is_absolute() {
+    path.to_path_buf()
+  } else {
+    std::env::current_dir()?.join(path)
+  };
+  let mut resolved = PathBuf::new();
+  for component in absolute.components() {
+    match component {
+      Component::Prefix(_) | Component::RootDir => {
+        resolved.push(component.as_os_str())
+      }
+      Component::CurDir => {}
+      Component::ParentDir => {
+        resolved.pop();
+      }
+      Component::Normal(name) => {
+        resolved.push(name);
+        if let Ok(canonical) = resolved.canonicalize() {
+          resolved = canonical;
+        }
+      }
+    }
+  }
+  Ok(resolved)
+}
@@ -0,0 +1,196 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! File system watching for `Deno.watchFs()`, backed by the notify crate,
+//! which uses inotify on Linux, FSEvents on macOS and ReadDirectoryChangesW
+//! on Windows.
+use crate::errors;
+use crate::errors::DenoError;
+use crate::errors::DenoResult;
+use crate::msg::ErrorKind;
+use crate::msg::FsEventKind;
+use futures;
+use futures::sync::mpsc;
+use futures::Async;
+use futures::Poll;
+use futures::Stream;
+use notify;
+use notify::DebouncedEvent;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Watcher;
+use std::path::PathBuf;
+use std::sync::mpsc as std_mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// How long notify waits for more events on a path before it reports one,
+/// several writes of a file in a row are reported as a single "modify".
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(50);
+
+/// A change of the file system. Events of a batch have different kinds.
+#[derive(Debug, PartialEq)]
+pub struct FsEvent {
+  pub kind: FsEventKind,
+  pub paths: Vec<PathBuf>,
+}
+
+/// The watch of some paths, see `watch()`. It ends when dropped.
+pub struct FsWatcher {
+  _watcher: RecommendedWatcher,
+  receiver: mpsc::UnboundedReceiver<DebouncedEvent>,
+  /// An error which came after the events of the last batch.
+  error: Option<DenoError>,
+  /// The `poll_events()` which waits for events, woken when the watcher is
+  /// closed.
+  task: Option<futures::task::Task>,
+}
+
+impl FsWatcher {
+  /// Resolves to the events which happened since the last call, at least
+  /// one, with the duplicates removed.
+  pub fn poll_events(&mut self) -> Poll<Vec<FsEvent>, DenoError> {
+    if let Some(e) = self.error.take() {
+      return Err(e);
+    }
+    let mut events = Vec::new();
+    loop {
+      match self.receiver.poll() {
+        Ok(Async::Ready(Some(DebouncedEvent::Error(e, _)))) => {
+          if events.is_empty() {
+            return Err(e.into());
+          }
+          self.error = Some(e.into());
+          break;
+        }
+        Ok(Async::Ready(Some(event))) => {
+          for (kind, path) in translate(event) {
+            add_event(&mut events, kind, path);
+          }
+        }
+        // The watcher lives as long as `self`, unless its backend failed.
+        Ok(Async::Ready(None)) | Err(()) => {
+          return Err(errors::new(
+            ErrorKind::Other,
+            "the file watcher stopped".to_string(),
+          ))
+        }
+        Ok(Async::NotReady) => break,
+      }
+    }
+    if events.is_empty() {
+      self.task = Some(futures::task::current());
+      Ok(Async::NotReady)
+    } else {
+      Ok(Async::Ready(events))
+    }
+  }
+}
+
+impl Drop for FsWatcher {
+  fn drop(&mut self) {
+    if let Some(task) = self.task.take() {
+      task.notify();
+    }
+  }
+}
+
+/// The kinds and paths of the changes notify reports. A rename removes the
+/// old path and creates the new one.
+fn translate(event: DebouncedEvent) -> Vec<(FsEventKind, PathBuf)> {
+  match event {
+    DebouncedEvent::Create(path) => vec![(FsEventKind::Create, path)],
+    DebouncedEvent::Write(path) | DebouncedEvent::Chmod(path) => {
+      vec![(FsEventKind::Modify, path)]
+    }
+    DebouncedEvent::Remove(path) => vec![(FsEventKind::Remove, path)],
+    DebouncedEvent::Rename(from, to) => {
+      vec![(FsEventKind::Remove, from), (FsEventKind::Create, to)]
+    }
+    // Notices come before the debounced events, a rescan has no paths.
+    _ => vec![],
+  }
+}
+
+/// Adds `path` to the event of `kind`, unless it is already there.
+fn add_event(events: &mut Vec<FsEvent>, kind: FsEventKind, path: PathBuf) {
+  match events.iter_mut().find(|e| e.kind == kind) {
+    Some(event) => {
+      if !event.paths.contains(&path) {
+        event.paths.push(path);
+      }
+    }
+    None => events.push(FsEvent {
+      kind,
+      paths: vec![path],
+    }),
+  }
+}
+
+/// Starts watching `paths`, and with `recursive` everything below them.
+pub fn watch(paths: &[PathBuf], recursive: bool) -> DenoResult<FsWatcher> {
+  let (std_sender, std_receiver) = std_mpsc::channel();
+  let mut watcher = notify::watcher(std_sender, DEBOUNCE_DELAY)?;
+  let mode = if recursive {
+    RecursiveMode::Recursive
+  } else {
+    RecursiveMode::NonRecursive
+  };
+  for path in paths {
+    watcher.watch(path, mode)?;
+  }
+  // notify reports to a std channel, this thread forwards the events to
+  // one which wakes the futures waiting for them. It ends with the watcher.
+  let (sender, receiver) = mpsc::unbounded();
+  thread::spawn(move || {
+    for event in std_receiver {
+      if sender.unbounded_send(event).is_err() {
+        break;
+      }
+    }
+  });
+  Ok(FsWatcher {
+    _watcher: watcher,
+    receiver,
+    error: None,
+    task: None,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn add_event_coalesces() {
+    let mut events = Vec::new();
+    add_event(&mut events, FsEventKind::Create, PathBuf::from("a"));
+    add_event(&mut events, FsEventKind::Modify, PathBuf::from("a"));
+    add_event(&mut events, FsEventKind::Create, PathBuf::from("b"));
+    add_event(&mut events, FsEventKind::Create, PathBuf::from("a"));
+    assert_eq!(
+      events,
+      vec![
+        FsEvent {
+          kind: FsEventKind::Create,
+          paths: vec![PathBuf::from("a"), PathBuf::from("b")],
+        },
+        FsEvent {
+          kind: FsEventKind::Modify,
+          paths: vec![PathBuf::from("a")],
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn translate_rename() {
+    let event =
+      DebouncedEvent::Rename(PathBuf::from("old"), PathBuf::from("new"));
+    assert_eq!(
+      translate(event),
+      vec![
+        (FsEventKind::Remove, PathBuf::from("old")),
+        (FsEventKind::Create, PathBuf::from("new")),
+      ]
+    );
+  }
+}
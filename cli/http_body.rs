@@ -1,5 +1,6 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
 
+use futures;
 use futures::Async;
 use futures::Poll;
 use hyper::body::Payload;
@@ -16,6 +17,8 @@ pub struct HttpBody {
   body: Body,
   chunk: Option<Chunk>,
   pos: usize,
+  /// The read which waits for the next chunk, woken when the body is closed.
+  task: Option<futures::task::Task>,
 }
 
 impl HttpBody {
@@ -24,6 +27,17 @@ impl HttpBody {
       body,
       chunk: None,
       pos: 0,
+      task: None,
+    }
+  }
+}
+
+/// Dropping an unfinished body aborts its connection, which hyper does not
+/// reuse.
+impl Drop for HttpBody {
+  fn drop(&mut self) {
+    if let Some(task) = self.task.take() {
+      task.notify();
     }
   }
 }
@@ -65,7 +79,10 @@ impl AsyncRead for HttpBody {
         // TODO Need to map hyper::Error into std::io::Error.
         io::Error::new(io::ErrorKind::Other, e),
       ),
-      Ok(Async::NotReady) => Ok(Async::NotReady),
+      Ok(Async::NotReady) => {
+        self.task = Some(futures::task::current());
+        Ok(Async::NotReady)
+      }
       Ok(Async::Ready(maybe_chunk)) => match maybe_chunk {
         None => Ok(Async::Ready(0)),
         Some(chunk) => {
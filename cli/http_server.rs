@@ -0,0 +1,190 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! The HTTP server of `Deno.serveHttp()`, backed by hyper. Connections are
+//! accepted from a listener resource while the program waits for a request,
+//! and served on their own after that: hyper keeps them alive, answers
+//! pipelined requests in the order they came and rejects malformed ones with
+//! a 400. The requests wait in a queue until the program accepts them.
+use crate::errors;
+use crate::errors::DenoError;
+use crate::msg::ErrorKind;
+use crate::resources::ReadStream;
+use crate::resources::Resource;
+use futures;
+use futures::future::Shared;
+use futures::sync::mpsc;
+use futures::sync::oneshot;
+use futures::Async;
+use futures::Future;
+use futures::Poll;
+use futures::Stream;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::Body;
+use hyper::Request;
+use hyper::Response;
+use hyper::StatusCode;
+use std::io;
+use std::net::SocketAddr;
+use tokio;
+use tokio::net::TcpStream;
+use tokio_rustls::TlsAcceptor;
+
+/// Sends the response to a request of the server.
+pub type Responder = oneshot::Sender<Response<Body>>;
+
+/// A request which waits for the program to send its response.
+pub struct ServerRequest {
+  pub request: Request<Body>,
+  pub remote_addr: SocketAddr,
+  pub responder: Responder,
+}
+
+pub struct HttpServer {
+  listener: Resource,
+  /// For a TLS listener, the handshake comes before the requests.
+  tls_acceptor: Option<TlsAcceptor>,
+  sender: mpsc::UnboundedSender<ServerRequest>,
+  receiver: mpsc::UnboundedReceiver<ServerRequest>,
+  /// Dropped when the server is closed, which ends its connections.
+  closing: Option<oneshot::Sender<()>>,
+  closed: Shared<oneshot::Receiver<()>>,
+  /// The `poll_request()` which waits for a request, woken when the server
+  /// is closed.
+  task: Option<futures::task::Task>,
+}
+
+impl HttpServer {
+  pub fn new(listener: Resource, tls_acceptor: Option<TlsAcceptor>) -> Self {
+    let (sender, receiver) = mpsc::unbounded();
+    let (closing, closed) = oneshot::channel();
+    HttpServer {
+      listener,
+      tls_acceptor,
+      sender,
+      receiver,
+      closing: Some(closing),
+      closed: closed.shared(),
+      task: None,
+    }
+  }
+
+  /// Resolves to the next request, or to None once the server or its
+  /// listener is closed.
+  pub fn poll_request(&mut self) -> Poll<Option<ServerRequest>, DenoError> {
+    if self.closing.is_none() {
+      return Ok(Async::Ready(None));
+    }
+    loop {
+      match self.listener.poll_accept() {
+        Ok(Async::Ready((stream, addr))) => self.serve(stream, addr),
+        Ok(Async::NotReady) => break,
+        Err(ref e) if e.kind() == ErrorKind::BadResource => {
+          self.close();
+          return Ok(Async::Ready(None));
+        }
+        Err(e) => return Err(e),
+      }
+    }
+    match self.receiver.poll() {
+      Ok(Async::Ready(Some(request))) => Ok(Async::Ready(Some(request))),
+      Ok(Async::NotReady) => {
+        self.task = Some(futures::task::current());
+        Ok(Async::NotReady)
+      }
+      // The server has a sender itself.
+      Ok(Async::Ready(None)) | Err(()) => unreachable!(),
+    }
+  }
+
+  /// Ends the connections, the requests which were not answered yet fail.
+  pub fn close(&mut self) {
+    self.closing.take();
+    if let Some(task) = self.task.take() {
+      task.notify();
+    }
+  }
+
+  fn serve(&self, stream: TcpStream, remote_addr: SocketAddr) {
+    let sender = self.sender.clone();
+    let service = service_fn(move |request: Request<Body>| {
+      let (responder, response) = oneshot::channel();
+      let _ = sender.unbounded_send(ServerRequest {
+        request,
+        remote_addr,
+        responder,
+      });
+      // The responder is dropped without a response when the server closes.
+      response.or_else(|_| {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+        Ok::<_, io::Error>(response)
+      })
+    });
+    let connection: Box<dyn Future<Item = (), Error = ()> + Send> =
+      match self.tls_acceptor {
+        None => Box::new(
+          Http::new()
+            .serve_connection(stream, service)
+            .map_err(|e| debug!("HTTP connection failed: {}", e)),
+        ),
+        Some(ref acceptor) => Box::new(
+          acceptor
+            .accept(stream)
+            .map_err(|e| debug!("TLS handshake failed: {}", e))
+            .and_then(move |stream| {
+              Http::new()
+                .serve_connection(stream, service)
+                .map_err(|e| debug!("HTTP connection failed: {}", e))
+            }),
+        ),
+      };
+    tokio::spawn(connection.select2(self.closed.clone()).then(|_| Ok(())));
+  }
+}
+
+impl Drop for HttpServer {
+  fn drop(&mut self) {
+    self.close();
+  }
+}
+
+/// The body of a response streamed from a resource. `finished` resolves when
+/// the stream ended or was dropped, e.g. because the client went away, after
+/// which the resource may be closed.
+pub struct StreamedBody {
+  stream: ReadStream,
+  finished: Option<oneshot::Sender<()>>,
+}
+
+impl Stream for StreamedBody {
+  type Item = Vec<u8>;
+  type Error = DenoError;
+
+  fn poll(&mut self) -> Poll<Option<Vec<u8>>, DenoError> {
+    let result = self.stream.poll();
+    match result {
+      Ok(Async::Ready(None)) | Err(_) => {
+        self.finished.take();
+      }
+      _ => {}
+    }
+    result
+  }
+}
+
+pub fn streamed_body(stream: ReadStream) -> (Body, oneshot::Receiver<()>) {
+  let (sender, finished) = oneshot::channel();
+  let body = StreamedBody {
+    stream,
+    finished: Some(sender),
+  };
+  (Body::wrap_stream(body), finished)
+}
+
+/// The error of a response which could not be sent.
+pub fn respond_error(reason: &str) -> DenoError {
+  errors::new(
+    ErrorKind::BrokenPipe,
+    format!("Unable to respond: {}", reason),
+  )
+}
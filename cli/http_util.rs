@@ -1,30 +1,85 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+use crate::cookie_jar::COOKIE_JAR;
 use crate::errors;
 use crate::errors::DenoError;
-#[cfg(test)]
+use crate::errors::DenoResult;
+use crate::errors::ErrorKind;
+use crate::msg::FetchRedirect;
 use futures::future::{loop_fn, Loop};
 use futures::{future, Future, Stream};
 use hyper;
 use hyper::client::{Client, HttpConnector};
+use hyper::header::HeaderName;
+use hyper::header::AUTHORIZATION;
+use hyper::header::CONTENT_LENGTH;
 use hyper::header::CONTENT_TYPE;
+use hyper::header::COOKIE;
+use hyper::header::ETAG;
+use hyper::header::IF_MODIFIED_SINCE;
+use hyper::header::IF_NONE_MATCH;
+use hyper::header::LAST_MODIFIED;
+use hyper::header::LOCATION;
+use hyper::header::SET_COOKIE;
+use hyper::header::TRANSFER_ENCODING;
+use hyper::Body;
+use hyper::HeaderMap;
+use hyper::Method;
+use hyper::Request;
+use hyper::Response;
+use hyper::StatusCode;
 use hyper::Uri;
 use hyper_rustls;
+use rustls;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use std::sync::RwLock;
+use url::Url;
+use webpki_roots;
 
 type Connector = hyper_rustls::HttpsConnector<HttpConnector>;
 
+const NUM_DNS_THREADS: usize = 4;
+
 lazy_static! {
-  static ref CONNECTOR: Connector = {
-    let num_dns_threads = 4;
-    Connector::new(num_dns_threads)
-  };
+  static ref CONNECTOR: RwLock<Connector> =
+    RwLock::new(Connector::new(NUM_DNS_THREADS));
 }
 
 pub fn get_client() -> Client<Connector, hyper::Body> {
   // TODO use Hyper's connection pool.
-  let c = CONNECTOR.clone();
+  let c = CONNECTOR.read().unwrap().clone();
   Client::builder().build(c)
 }
 
+/// Trusts the root certificates in the PEM file `filename` in addition to the
+/// default ones, see `--cert`. Must be called before anything is fetched.
+pub fn add_root_certs(filename: &str) -> Result<(), DenoError> {
+  let invalid = |reason: &str| {
+    errors::new(
+      ErrorKind::InvalidData,
+      format!("Unable to load certificates from \"{}\": {}", filename, reason),
+    )
+  };
+  let file = File::open(filename).map_err(|e| invalid(&e.to_string()))?;
+  let mut config = rustls::ClientConfig::new();
+  config
+    .root_store
+    .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+  match config.root_store.add_pem_file(&mut BufReader::new(file)) {
+    Err(()) => return Err(invalid("not a PEM file")),
+    Ok((0, _)) => return Err(invalid("no certificates found")),
+    Ok((_, n)) if n > 0 => {
+      return Err(invalid(&format!("{} invalid certificate(s)", n)))
+    }
+    Ok(_) => {}
+  }
+  let mut http = HttpConnector::new(NUM_DNS_THREADS);
+  http.enforce_http(false);
+  *CONNECTOR.write().unwrap() = Connector::from((http, config));
+  Ok(())
+}
+
 /// Construct the next uri based on base uri and location header fragment
 /// See <https://tools.ietf.org/html/rfc3986#section-4.2>
 fn resolve_uri_from_location(base_uri: &Uri, location: &str) -> Uri {
@@ -57,8 +112,19 @@ fn resolve_uri_from_location(base_uri: &Uri, location: &str) -> Uri {
   }
 }
 
-#[cfg(test)]
-use crate::errors::DenoResult;
+/// The statuses which are followed when fetching modules. Other 3xx
+/// responses, like 300 Multiple Choices, are errors.
+fn is_redirect(status: StatusCode) -> bool {
+  match status {
+    StatusCode::MOVED_PERMANENTLY
+    | StatusCode::FOUND
+    | StatusCode::SEE_OTHER
+    | StatusCode::TEMPORARY_REDIRECT
+    | StatusCode::PERMANENT_REDIRECT => true,
+    _ => false,
+  }
+}
+
 #[cfg(test)]
 use crate::tokio_util;
 #[cfg(test)]
@@ -67,75 +133,290 @@ pub fn fetch_sync_string(module_name: &str) -> DenoResult<(String, String)> {
   tokio_util::block_on(fetch_string(module_name))
 }
 
+/// The ETag and Last-Modified headers of a response, which make a later
+/// request for the same URL conditional.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CacheValidators {
+  pub etag: Option<String>,
+  pub last_modified: Option<String>,
+}
+
+/// Where the declarations of a JavaScript module are, relative to its URL.
+const X_TYPESCRIPT_TYPES: &str = "x-typescript-types";
+
 pub enum FetchOnceResult {
-  // (code, maybe_content_type)
-  Code(String, Option<String>),
+  // (code, maybe_content_type, maybe_types, validators)
+  Code(Vec<u8>, Option<String>, Option<String>, CacheValidators),
   Redirect(http::uri::Uri),
+  // The copy the validators came from is still current.
+  NotModified,
 }
 
 /// Asynchronously fetchs the given HTTP URL one pass only.
 /// If no redirect is present and no error occurs,
-/// yields Code(code, maybe_content_type).
+/// yields Code(code, maybe_content_type, maybe_types, validators), the types
+/// being the X-TypeScript-Types header.
 /// If redirect occurs, does not follow and
 /// yields Redirect(url).
+/// The request is conditional if `validators` are given, an unchanged
+/// resource yields NotModified.
 pub fn fetch_string_once(
   url: http::uri::Uri,
+  validators: &CacheValidators,
 ) -> impl Future<Item = FetchOnceResult, Error = DenoError> {
-  type FetchAttempt = (Option<String>, Option<String>, Option<FetchOnceResult>);
+  let mut request = Request::get(url.clone());
+  if let Some(ref etag) = validators.etag {
+    request.header(IF_NONE_MATCH, etag.as_str());
+  }
+  if let Some(ref last_modified) = validators.last_modified {
+    request.header(IF_MODIFIED_SINCE, last_modified.as_str());
+  }
+  let request = request.body(Body::empty()).unwrap();
   let client = get_client();
-  client
-    .get(url.clone())
-    .map_err(DenoError::from)
-    .and_then(
-      move |response| -> Box<
-        dyn Future<Item = FetchAttempt, Error = DenoError> + Send,
-      > {
-        if response.status().is_redirection() {
-          let location_string = response
-            .headers()
-            .get("location")
-            .expect("url redirection should provide 'location' header")
-            .to_str()
-            .unwrap()
-            .to_string();
-          debug!("Redirecting to {}...", &location_string);
-          let new_url = resolve_uri_from_location(&url, &location_string);
-          // Boxed trait object turns out to be the savior for 2+ types yielding same results.
-          return Box::new(future::ok(None).join3(
-            future::ok(None),
-            future::ok(Some(FetchOnceResult::Redirect(new_url))),
-          ));
-        } else if response.status().is_client_error()
-          || response.status().is_server_error()
+  client.request(request).map_err(DenoError::from).and_then(
+    move |response| -> Box<
+      dyn Future<Item = FetchOnceResult, Error = DenoError> + Send,
+    > {
+      if response.status() == StatusCode::NOT_MODIFIED {
+        debug!("Not modified {}", &url);
+        return Box::new(future::ok(FetchOnceResult::NotModified));
+      }
+      if is_redirect(response.status()) {
+        let location_string = match response
+          .headers()
+          .get(LOCATION)
+          .and_then(|location| location.to_str().ok())
         {
-          return Box::new(future::err(errors::new(
-            errors::ErrorKind::Other,
-            format!("Import '{}' failed: {}", &url, response.status()),
-          )));
-        }
-        let content_type = response
+          Some(location) => location.to_string(),
+          None => {
+            return Box::new(future::err(errors::new(
+              errors::ErrorKind::Other,
+              format!(
+                "Import '{}' failed: {} without a Location header",
+                &url,
+                response.status()
+              ),
+            )))
+          }
+        };
+        debug!("Redirecting to {}...", &location_string);
+        let new_url = resolve_uri_from_location(&url, &location_string);
+        return Box::new(future::ok(FetchOnceResult::Redirect(new_url)));
+      } else if !response.status().is_success() {
+        return Box::new(future::err(errors::new(
+          errors::ErrorKind::Other,
+          format!("Import '{}' failed: {}", &url, response.status()),
+        )));
+      }
+      let header = |name| {
+        response
           .headers()
-          .get(CONTENT_TYPE)
-          .map(|content_type| content_type.to_str().unwrap().to_owned());
-        let body = response
-          .into_body()
-          .concat2()
-          .map(|body| String::from_utf8(body.to_vec()).ok())
-          .map_err(DenoError::from);
-        Box::new(body.join3(future::ok(content_type), future::ok(None)))
-      },
-    )
-    .and_then(move |(maybe_code, maybe_content_type, maybe_redirect)| {
-      if let Some(redirect) = maybe_redirect {
-        future::ok(redirect)
-      } else {
-        // maybe_code should always contain code here!
-        future::ok(FetchOnceResult::Code(
-          maybe_code.unwrap(),
-          maybe_content_type,
-        ))
+          .get(name)
+          .and_then(|value| value.to_str().ok())
+          .map(String::from)
+      };
+      let content_type = header(CONTENT_TYPE);
+      let types = header(HeaderName::from_static(X_TYPESCRIPT_TYPES));
+      let validators = CacheValidators {
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+      };
+      let body = response
+        .into_body()
+        .concat2()
+        .map_err(DenoError::from)
+        .map(move |body| {
+          let code = body.to_vec();
+          FetchOnceResult::Code(code, content_type, types, validators)
+        });
+      Box::new(body)
+    },
+  )
+}
+
+/// The redirects which `fetch()` follows when none is given, as browsers do.
+pub const DEFAULT_MAX_REDIRECTS: u32 = 20;
+
+/// The body of a request of `fetch()`. A stream can only be sent once, so a
+/// 307 or 308 redirect, which repeats the body, can't be followed with it.
+pub enum FetchBody {
+  Empty,
+  Buffered(Vec<u8>),
+  Stream(Body),
+}
+
+#[derive(Clone, Copy)]
+pub struct FetchOptions {
+  pub redirect: FetchRedirect,
+  pub max_redirects: u32,
+  /// Sends the cookies of `COOKIE_JAR` and keeps the ones of the responses.
+  pub cookies: bool,
+}
+
+/// Checks whether a URL, the first one or one redirected to, may be fetched.
+pub type CheckUrl = Arc<dyn Fn(&Url) -> DenoResult<()> + Send + Sync>;
+
+/// A request of `fetch()` as it is redirected.
+struct Redirects {
+  method: Method,
+  url: Url,
+  headers: HeaderMap,
+  body: FetchBody,
+  options: FetchOptions,
+  check_url: CheckUrl,
+  hops: u32,
+}
+
+impl Redirects {
+  fn request(&mut self) -> DenoResult<Request<Body>> {
+    let body = match std::mem::replace(&mut self.body, FetchBody::Empty) {
+      FetchBody::Empty => Body::empty(),
+      FetchBody::Buffered(bytes) => {
+        let body = Body::from(bytes.clone());
+        self.body = FetchBody::Buffered(bytes);
+        body
+      }
+      FetchBody::Stream(stream) => stream,
+    };
+    let uri = self
+      .url
+      .as_str()
+      .parse::<Uri>()
+      .map_err(|e| errors::new(ErrorKind::InvalidUri, e.to_string()))?;
+    let mut request = Request::new(body);
+    *request.method_mut() = self.method.clone();
+    *request.uri_mut() = uri;
+    *request.headers_mut() = self.headers.clone();
+    if self.options.cookies && !self.headers.contains_key(COOKIE) {
+      if let Some(cookies) = COOKIE_JAR.lock().unwrap().header(&self.url) {
+        if let Ok(value) = cookies.parse() {
+          request.headers_mut().insert(COOKIE, value);
+        }
       }
-    })
+    }
+    Ok(request)
+  }
+
+  /// Breaks with the response and its URL unless it redirects, which is then
+  /// followed as the options allow.
+  fn follow(
+    mut self,
+    response: Response<Body>,
+    streamed: bool,
+  ) -> DenoResult<Loop<(Response<Body>, Url, bool), Self>> {
+    if self.options.cookies {
+      let mut jar = COOKIE_JAR.lock().unwrap();
+      for set_cookie in response.headers().get_all(SET_COOKIE) {
+        if let Ok(set_cookie) = set_cookie.to_str() {
+          jar.store(&self.url, set_cookie);
+        }
+      }
+    }
+    let status = response.status();
+    let location = response
+      .headers()
+      .get(LOCATION)
+      .and_then(|location| location.to_str().ok())
+      .map(String::from);
+    let location = match location {
+      Some(location)
+        if is_redirect(status)
+          && self.options.redirect != FetchRedirect::Manual =>
+      {
+        location
+      }
+      _ => {
+        let redirected = self.hops > 0;
+        return Ok(Loop::Break((response, self.url, redirected)));
+      }
+    };
+    let next = self.url.join(&location)?;
+    if self.options.redirect == FetchRedirect::Error {
+      return Err(errors::new(
+        ErrorKind::RedirectNotAllowed,
+        format!("Redirected to {}, but the redirect mode is \"error\"", next),
+      ));
+    }
+    if self.hops >= self.options.max_redirects {
+      return Err(errors::new(
+        ErrorKind::TooManyRedirects,
+        format!(
+          "More than {} redirects, the last one to {}",
+          self.options.max_redirects, next
+        ),
+      ));
+    }
+    (self.check_url)(&next)?;
+
+    let to_get = match status {
+      StatusCode::SEE_OTHER => self.method != Method::HEAD,
+      StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => {
+        self.method == Method::POST
+      }
+      _ => false,
+    };
+    if to_get {
+      self.method = Method::GET;
+      self.body = FetchBody::Empty;
+      self.headers.remove(CONTENT_LENGTH);
+      self.headers.remove(CONTENT_TYPE);
+      self.headers.remove(TRANSFER_ENCODING);
+    } else if streamed {
+      return Err(errors::new(
+        ErrorKind::RedirectNotAllowed,
+        format!("Redirected to {}, but the body can't be sent again", next),
+      ));
+    }
+    // The credentials of one origin are not sent to another.
+    if next.origin() != self.url.origin() {
+      self.headers.remove(AUTHORIZATION);
+      self.headers.remove(COOKIE);
+    }
+    debug!("Redirecting to {}...", next);
+    self.url = next;
+    self.hops += 1;
+    Ok(Loop::Continue(self))
+  }
+}
+
+/// Fetches `url` like the `fetch()` of scripts. Resolves to the response, the
+/// URL it came from and whether it was redirected to it, as soon as the
+/// headers are there. Only the URLs redirected to are checked with
+/// `check_url`.
+pub fn fetch(
+  method: Method,
+  url: Url,
+  headers: HeaderMap,
+  body: FetchBody,
+  options: FetchOptions,
+  check_url: CheckUrl,
+) -> impl Future<Item = (Response<Body>, Url, bool), Error = DenoError> {
+  let client = get_client();
+  let redirects = Redirects {
+    method,
+    url,
+    headers,
+    body,
+    options,
+    check_url,
+    hops: 0,
+  };
+  loop_fn(redirects, move |mut redirects| {
+    let streamed = match redirects.body {
+      FetchBody::Stream(_) => true,
+      _ => false,
+    };
+    let request = match redirects.request() {
+      Ok(request) => request,
+      Err(e) => return future::Either::A(future::err(e)),
+    };
+    future::Either::B(
+      client
+        .request(request)
+        .map_err(DenoError::from)
+        .and_then(move |response| redirects.follow(response, streamed)),
+    )
+  })
 }
 
 #[cfg(test)]
@@ -0,0 +1,319 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! Import maps remap module specifiers, e.g. bare specifiers like "lodash",
+//! before they are resolved. See https://github.com/WICG/import-maps
+use crate::errors;
+use crate::errors::DenoResult;
+use crate::fs as deno_fs;
+use crate::msg::ErrorKind;
+use serde_json;
+use serde_json::Map;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use url::Url;
+
+/// Specifier prefix (or exact specifier) to address, ordered so that the
+/// longest, most specific, key comes first.
+type SpecifierMap = Vec<(String, Url)>;
+
+#[derive(Debug)]
+pub struct ImportMap {
+  imports: SpecifierMap,
+  /// Scope URL prefix to its specifier map, most specific scope first.
+  scopes: Vec<(String, SpecifierMap)>,
+}
+
+impl ImportMap {
+  /// Reads the import map given with `--importmap`. Relative addresses in
+  /// the map are resolved against the location of the map itself.
+  pub fn load(filename: &str) -> DenoResult<ImportMap> {
+    let path = deno_fs::resolve_from_cwd(Path::new(filename))?;
+    let source = fs::read_to_string(&path).map_err(|e| {
+      let kind = errors::DenoError::from(e).kind();
+      errors::new(kind, format!("Unable to read import map \"{}\"", filename))
+    })?;
+    let base_url = Url::from_file_path(&path).unwrap();
+    ImportMap::from_json(base_url, &source).map_err(|e| {
+      let msg = format!("Invalid import map \"{}\": {}", filename, e);
+      errors::new(e.kind(), msg)
+    })
+  }
+
+  pub fn from_json(base_url: Url, source: &str) -> DenoResult<ImportMap> {
+    // serde_json errors already include the line and column.
+    let v: Value =
+      serde_json::from_str(source).map_err(|e| invalid(e.to_string()))?;
+    let map = match v {
+      Value::Object(map) => map,
+      _ => return Err(invalid("expected a JSON object".to_string())),
+    };
+
+    let imports = match map.get("imports") {
+      None => vec![],
+      Some(Value::Object(imports)) => {
+        parse_specifier_map(&base_url, source, imports)?
+      }
+      Some(_) => {
+        return Err(invalid(format!(
+          "\"imports\" must be an object{}",
+          position_of(source, "imports")
+        )))
+      }
+    };
+
+    let mut scopes = vec![];
+    match map.get("scopes") {
+      None => {}
+      Some(Value::Object(scope_map)) => {
+        for (scope, imports) in scope_map {
+          let scope_url = base_url.join(scope).map_err(|e| {
+            invalid(format!(
+              "invalid scope \"{}\": {}{}",
+              scope,
+              e,
+              position_of(source, scope)
+            ))
+          })?;
+          let imports = match imports {
+            Value::Object(imports) => {
+              parse_specifier_map(&base_url, source, imports)?
+            }
+            _ => {
+              return Err(invalid(format!(
+                "scope \"{}\" must be an object{}",
+                scope,
+                position_of(source, scope)
+              )))
+            }
+          };
+          scopes.push((scope_url.to_string(), imports));
+        }
+      }
+      Some(_) => {
+        return Err(invalid(format!(
+          "\"scopes\" must be an object{}",
+          position_of(source, "scopes")
+        )))
+      }
+    }
+    scopes.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+
+    Ok(ImportMap { imports, scopes })
+  }
+
+  /// Returns the address `specifier` maps to when imported from `referrer`,
+  /// or None if the map has no entry for it. Matching scopes are consulted
+  /// from the most to the least specific one before the top level imports.
+  pub fn resolve(&self, specifier: &str, referrer: &str) -> Option<Url> {
+    let specifier = match Url::parse(referrer) {
+      Ok(ref referrer_url) if is_relative(specifier) => {
+        referrer_url.join(specifier).ok()?.to_string()
+      }
+      _ => specifier.to_string(),
+    };
+    self
+      .scopes
+      .iter()
+      .filter(|(scope, _)| referrer.starts_with(scope.as_str()))
+      .filter_map(|(_, imports)| resolve_in(imports, &specifier))
+      .next()
+      .or_else(|| resolve_in(&self.imports, &specifier))
+  }
+}
+
+fn invalid(msg: String) -> errors::DenoError {
+  errors::new(ErrorKind::InvalidData, msg)
+}
+
+fn is_relative(specifier: &str) -> bool {
+  specifier.starts_with('/')
+    || specifier.starts_with("./")
+    || specifier.starts_with("../")
+}
+
+fn parse_specifier_map(
+  base_url: &Url,
+  source: &str,
+  map: &Map<String, Value>,
+) -> DenoResult<SpecifierMap> {
+  let mut out = vec![];
+  for (key, value) in map {
+    let address = match value {
+      Value::String(address) => address,
+      _ => {
+        return Err(invalid(format!(
+          "the address of \"{}\" must be a string{}",
+          key,
+          position_of(source, key)
+        )))
+      }
+    };
+    // A relative address is resolved against the map. Anything else has to
+    // be a full URL, a bare address would be relative to nothing.
+    let address_url = if is_relative(address) {
+      base_url.join(address).ok()
+    } else {
+      Url::parse(address).ok()
+    };
+    let address_url = match address_url {
+      Some(url) => url,
+      None => {
+        return Err(invalid(format!(
+          "the address \"{}\" of \"{}\" is neither a URL nor starts with \"/\", \"./\" or \"../\"{}",
+          address,
+          key,
+          position_of(source, address)
+        )))
+      }
+    };
+    if key.ends_with('/') && !address_url.as_str().ends_with('/') {
+      return Err(invalid(format!(
+        "the address of \"{}\" must end with \"/\" like the specifier{}",
+        key,
+        position_of(source, key)
+      )));
+    }
+    // URL-like keys are normalized the same way as the specifiers they are
+    // compared with.
+    let key = if is_relative(key) {
+      match base_url.join(key) {
+        Ok(url) => url.to_string(),
+        Err(_) => key.to_string(),
+      }
+    } else {
+      key.to_string()
+    };
+    out.push((key, address_url));
+  }
+  out.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+  Ok(out)
+}
+
+fn resolve_in(imports: &[(String, Url)], specifier: &str) -> Option<Url> {
+  for (key, address) in imports {
+    if key == specifier {
+      return Some(address.clone());
+    }
+    if key.ends_with('/') && specifier.starts_with(key.as_str()) {
+      return address.join(&specifier[key.len()..]).ok();
+    }
+  }
+  None
+}
+
+/// Describes where the quoted string `needle` first occurs in `source`, to
+/// point at invalid entries which serde_json parsed just fine.
+fn position_of(source: &str, needle: &str) -> String {
+  let quoted = serde_json::to_string(needle).unwrap();
+  match source.find(&quoted) {
+    None => "".to_string(),
+    Some(offset) => {
+      let before = &source[..offset];
+      let line = before.matches('\n').count() + 1;
+      let column = offset - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+      format!(" at line {} column {}", line, column)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn base_url() -> Url {
+    Url::parse("file:///project/import_map.json").unwrap()
+  }
+
+  #[test]
+  fn resolve_imports() {
+    let map = ImportMap::from_json(
+      base_url(),
+      r#"{
+        "imports": {
+          "lodash": "https://unpkg.com/lodash-es@4/lodash.js",
+          "lodash/": "https://unpkg.com/lodash-es@4/",
+          "utils": "./src/utils.ts",
+          "/vendor/": "./third_party/"
+        }
+      }"#,
+    ).unwrap();
+    let r = "file:///project/main.ts";
+    let resolve = |s: &str| map.resolve(s, r).map(|u| u.to_string());
+    assert_eq!(
+      resolve("lodash"),
+      Some("https://unpkg.com/lodash-es@4/lodash.js".to_string())
+    );
+    assert_eq!(
+      resolve("lodash/fp.js"),
+      Some("https://unpkg.com/lodash-es@4/fp.js".to_string())
+    );
+    assert_eq!(
+      resolve("utils"),
+      Some("file:///project/src/utils.ts".to_string())
+    );
+    assert_eq!(
+      resolve("/vendor/a.ts"),
+      Some("file:///project/third_party/a.ts".to_string())
+    );
+    assert_eq!(resolve("./local.ts"), None);
+    assert_eq!(resolve("unknown"), None);
+  }
+
+  #[test]
+  fn resolve_scopes() {
+    let map = ImportMap::from_json(
+      base_url(),
+      r#"{
+        "imports": { "dep": "./dep_v1.ts" },
+        "scopes": {
+          "./legacy/": { "dep": "./dep_v0.ts" },
+          "./legacy/new/": { "other": "./other.ts" }
+        }
+      }"#,
+    ).unwrap();
+    let resolve = |s: &str, r: &str| map.resolve(s, r).unwrap().to_string();
+    assert_eq!(
+      resolve("dep", "file:///project/main.ts"),
+      "file:///project/dep_v1.ts"
+    );
+    assert_eq!(
+      resolve("dep", "file:///project/legacy/a.ts"),
+      "file:///project/dep_v0.ts"
+    );
+    // Falls back to the less specific scope.
+    assert_eq!(
+      resolve("dep", "file:///project/legacy/new/a.ts"),
+      "file:///project/dep_v0.ts"
+    );
+    assert_eq!(
+      resolve("other", "file:///project/legacy/new/a.ts"),
+      "file:///project/other.ts"
+    );
+    assert!(map.resolve("other", "file:///project/main.ts").is_none());
+  }
+
+  #[test]
+  fn invalid_maps() {
+    let err = ImportMap::from_json(base_url(), "{\n  \"imports\": {,\n}")
+      .unwrap_err()
+      .to_string();
+    assert!(err.contains("line 2 column"), err);
+
+    let err = ImportMap::from_json(
+      base_url(),
+      "{\n  \"imports\": {\n    \"lodash\": \"lodash.js\"\n  }\n}",
+    ).unwrap_err()
+    .to_string();
+    assert!(err.contains("\"lodash.js\""), err);
+    assert!(err.contains("line 3 column 15"), err);
+
+    let err = ImportMap::from_json(
+      base_url(),
+      r#"{ "imports": { "lodash/": "./lodash.js" } }"#,
+    ).unwrap_err()
+    .to_string();
+    assert!(err.contains("must end with \"/\""), err);
+
+    assert!(ImportMap::from_json(base_url(), "[]").is_err());
+  }
+}
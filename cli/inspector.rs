@@ -0,0 +1,352 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! The DevTools endpoint behind `--inspect` and `--inspect-brk`: a small HTTP
+//! server which lists the main isolate as debug target at `/json` and passes
+//! the DevTools protocol over a WebSocket. One client is served at a time.
+use crate::websocket;
+use crate::websocket::Frame;
+use crate::websocket::CLOSE;
+use crate::websocket::CONTINUATION;
+use crate::websocket::PING;
+use crate::websocket::PONG;
+use crate::websocket::TEXT;
+use deno::InspectorEvent;
+use deno::InspectorHost;
+use futures::sync::mpsc;
+use futures::Async;
+use futures::Stream;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+type Client = Arc<Mutex<Option<TcpStream>>>;
+
+/// Passes the DevTools protocol between the isolate and the WebSocket client
+/// of the server thread started by `listen()`.
+pub struct WebSocketHost {
+  events: mpsc::UnboundedReceiver<InspectorEvent>,
+  client: Client,
+}
+
+impl InspectorHost for WebSocketHost {
+  fn send(&mut self, message: &str) {
+    let mut client = self.client.lock().unwrap();
+    let failed = match *client {
+      Some(ref mut stream) => {
+        write_frame(stream, TEXT, message.as_bytes()).is_err()
+      }
+      None => false,
+    };
+    if failed {
+      // The reading side of the connection reports the disconnect.
+      *client = None;
+    }
+  }
+
+  fn poll_event(&mut self) -> Async<InspectorEvent> {
+    match self.events.poll() {
+      Ok(Async::Ready(Some(event))) => Async::Ready(event),
+      _ => Async::NotReady,
+    }
+  }
+
+  fn wait_event(&mut self) -> InspectorEvent {
+    match (&mut self.events).wait().next() {
+      Some(Ok(event)) => event,
+      // The server thread is gone, there is nobody left to wait for.
+      _ => InspectorEvent::Disconnected,
+    }
+  }
+}
+
+/// Starts serving DevTools clients at `addr` on a separate thread and prints
+/// where to connect to.
+pub fn listen(addr: SocketAddr) -> io::Result<WebSocketHost> {
+  let listener = TcpListener::bind(addr)?;
+  let addr = listener.local_addr()?;
+  let id = new_target_id();
+  let (tx, rx) = mpsc::unbounded();
+  let client: Client = Arc::new(Mutex::new(None));
+
+  eprintln!("Debugger listening on ws://{}/{}", addr, id);
+  eprintln!("Open {} in Chrome to debug", devtools_url(&addr, &id));
+
+  let client_ = client.clone();
+  thread::spawn(move || {
+    for stream in listener.incoming() {
+      let stream = match stream {
+        Ok(stream) => stream,
+        Err(_) => continue,
+      };
+      let session = Session {
+        addr,
+        id: id.clone(),
+        events: tx.clone(),
+        client: client_.clone(),
+      };
+      thread::spawn(move || {
+        if let Err(err) = session.serve(stream) {
+          debug!("inspector connection failed: {}", err);
+        }
+      });
+    }
+  });
+
+  Ok(WebSocketHost { events: rx, client })
+}
+
+struct Session {
+  addr: SocketAddr,
+  id: String,
+  events: mpsc::UnboundedSender<InspectorEvent>,
+  client: Client,
+}
+
+impl Session {
+  fn serve(&self, mut stream: TcpStream) -> io::Result<()> {
+    let (path, key) = read_request(&mut stream)?;
+    match (path.as_str(), key) {
+      ("/json", None) | ("/json/list", None) => {
+        let targets = json!([{
+          "description": "deno",
+          "devtoolsFrontendUrl": devtools_url(&self.addr, &self.id),
+          "id": self.id,
+          "title": "deno",
+          "type": "node",
+          "url": "file://",
+          "webSocketDebuggerUrl":
+            format!("ws://{}/{}", self.addr, self.id),
+        }]);
+        write_json(&mut stream, &targets)
+      }
+      ("/json/version", None) => {
+        let version = json!({
+          "Browser": format!("Deno/{}", crate::version::DENO),
+          "Protocol-Version": "1.3",
+          "V8-Version": crate::version::v8(),
+        });
+        write_json(&mut stream, &version)
+      }
+      (path, Some(key)) if path == format!("/{}", self.id) => {
+        self.serve_websocket(stream, &key)
+      }
+      _ => write!(
+        stream,
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n"
+      ),
+    }
+  }
+
+  fn serve_websocket(
+    &self,
+    mut stream: TcpStream,
+    key: &str,
+  ) -> io::Result<()> {
+    {
+      let mut client = self.client.lock().unwrap();
+      if client.is_some() {
+        return write!(
+          stream,
+          "HTTP/1.1 409 Conflict\r\nContent-Length: 0\r\n\r\n"
+        );
+      }
+      write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        websocket::accept_key(key)
+      )?;
+      *client = Some(stream.try_clone()?);
+    }
+    self.send_event(InspectorEvent::Connected);
+
+    let result = self.read_messages(&mut stream);
+
+    *self.client.lock().unwrap() = None;
+    self.send_event(InspectorEvent::Disconnected);
+    result
+  }
+
+  fn read_messages(&self, stream: &mut TcpStream) -> io::Result<()> {
+    let mut buf = vec![];
+    let mut message = vec![];
+    loop {
+      let Frame {
+        fin,
+        opcode,
+        payload,
+      } = read_frame(stream, &mut buf)?;
+      match opcode {
+        TEXT | CONTINUATION => {
+          message.extend_from_slice(&payload);
+          if fin {
+            let text = String::from_utf8_lossy(&message).into_owned();
+            self.send_event(InspectorEvent::Message(text));
+            message.clear();
+          }
+        }
+        PING => {
+          if let Some(ref mut client) = *self.client.lock().unwrap() {
+            write_frame(client, PONG, &payload)?;
+          }
+        }
+        CLOSE => {
+          if let Some(ref mut client) = *self.client.lock().unwrap() {
+            write_frame(client, CLOSE, &[]).ok();
+          }
+          return Ok(());
+        }
+        _ => {}
+      }
+    }
+  }
+
+  fn send_event(&self, event: InspectorEvent) {
+    // Fails only when the isolate is gone.
+    self.events.unbounded_send(event).ok();
+  }
+}
+
+fn new_target_id() -> String {
+  let s = format!(
+    "{:016x}{:016x}",
+    rand::random::<u64>(),
+    rand::random::<u64>()
+  );
+  format!(
+    "{}-{}-{}-{}-{}",
+    &s[0..8],
+    &s[8..12],
+    &s[12..16],
+    &s[16..20],
+    &s[20..32]
+  )
+}
+
+fn devtools_url(addr: &SocketAddr, id: &str) -> String {
+  format!(
+    "devtools://devtools/bundled/js_app.html?experiments=true&v8only=true&ws={}/{}",
+    addr, id
+  )
+}
+
+/// Reads the head of an HTTP request and returns its path, and the
+/// Sec-WebSocket-Key header if the request is a WebSocket upgrade.
+fn read_request(
+  stream: &mut TcpStream,
+) -> io::Result<(String, Option<String>)> {
+  let mut head = vec![];
+  let mut byte = [0; 1];
+  while !head.ends_with(b"\r\n\r\n") {
+    if head.len() > 16 * 1024 {
+      return Err(invalid_data("request head too large"));
+    }
+    stream.read_exact(&mut byte)?;
+    head.push(byte[0]);
+  }
+  let head = String::from_utf8_lossy(&head);
+  let mut lines = head.split("\r\n");
+  let path = lines
+    .next()
+    .and_then(|line| line.split(' ').nth(1))
+    .ok_or_else(|| invalid_data("invalid request line"))?;
+  let key = lines
+    .filter_map(|line| {
+      let mut parts = line.splitn(2, ':');
+      let name = parts.next()?.trim();
+      let value = parts.next()?.trim();
+      if name.eq_ignore_ascii_case("sec-websocket-key") {
+        Some(value.to_string())
+      } else {
+        None
+      }
+    }).next();
+  Ok((path.to_string(), key))
+}
+
+fn write_json(
+  stream: &mut TcpStream,
+  value: &serde_json::Value,
+) -> io::Result<()> {
+  let body = serde_json::to_string_pretty(value).unwrap();
+  write!(
+    stream,
+    "HTTP/1.1 200 OK\r\n\
+     Content-Type: application/json; charset=UTF-8\r\n\
+     Content-Length: {}\r\n\r\n{}",
+    body.len(),
+    body
+  )
+}
+
+/// Reads the next frame of the client. `buf` keeps what was read after it.
+fn read_frame<R: Read>(r: &mut R, buf: &mut Vec<u8>) -> io::Result<Frame> {
+  loop {
+    match websocket::parse_frame(buf, true) {
+      Ok(Some((frame, len))) => {
+        buf.drain(..len);
+        return Ok(frame);
+      }
+      Ok(None) => {}
+      Err(websocket::Failure(_, reason)) => return Err(invalid_data(reason)),
+    }
+    let mut chunk = [0; 16 * 1024];
+    let nread = r.read(&mut chunk)?;
+    if nread == 0 {
+      return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    buf.extend_from_slice(&chunk[..nread]);
+  }
+}
+
+/// Writes an unfragmented, unmasked WebSocket frame, as sent by servers.
+fn write_frame<W: Write>(
+  w: &mut W,
+  opcode: u8,
+  payload: &[u8],
+) -> io::Result<()> {
+  w.write_all(&websocket::encode_frame(opcode, payload, None))
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_frames() {
+    // The frames of a client may arrive in pieces, or together.
+    let mut bytes = vec![];
+    for len in &[0, 125, 126, 0xffff, 0x10000] {
+      let payload = vec![b'x'; *len];
+      let frame = websocket::encode_frame(TEXT, &payload, Some(rand::random()));
+      bytes.extend_from_slice(&frame);
+    }
+    let mut reader = bytes.as_slice();
+    let mut buf = vec![];
+    for len in &[0, 125, 126, 0xffff, 0x10000] {
+      let frame = read_frame(&mut reader, &mut buf).unwrap();
+      assert!(frame.fin);
+      assert_eq!(frame.opcode, TEXT);
+      assert_eq!(frame.payload, vec![b'x'; *len]);
+    }
+    let err = read_frame(&mut reader, &mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    // Servers don't mask their frames, clients have to.
+    let mut frame = vec![];
+    write_frame(&mut frame, TEXT, b"Hello").unwrap();
+    let err = read_frame(&mut frame.as_slice(), &mut vec![]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+  }
+}
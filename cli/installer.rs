@@ -0,0 +1,159 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! `deno install` writes shims to ~/.deno/bin which run a module with the
+//! flags given at install time, e.g.
+//! `deno install serve https://deno.land/std/http/file_server.ts --allow-net`.
+use crate::errors;
+use crate::errors::DenoError;
+use crate::errors::DenoResult;
+use crate::fs as deno_fs;
+use crate::msg::ErrorKind;
+use std::env;
+use std::path::Path;
+use std::path::PathBuf;
+use url::Url;
+
+/// Where shims are installed to.
+pub fn bin_dir() -> PathBuf {
+  let home_dir = dirs::home_dir().expect("Could not get home directory.");
+  home_dir.join(".deno").join("bin")
+}
+
+/// Installs a shim called `exe_name` into `bin_dir` which runs `module` with
+/// `args` as deno flags and returns its path. An existing shim is only
+/// replaced with `force`.
+pub fn install(
+  bin_dir: &Path,
+  exe_name: &str,
+  module: &str,
+  args: &[String],
+  force: bool,
+) -> DenoResult<PathBuf> {
+  if !is_plain(exe_name, "-_") {
+    return Err(errors::new(
+      ErrorKind::InvalidInput,
+      format!(
+        "Invalid executable name \"{}\", use letters, digits, - and _",
+        exe_name
+      ),
+    ));
+  }
+  // A local module is run from wherever the shim is invoked.
+  let module = match Url::parse(module) {
+    Ok(url) => url.to_string(),
+    Err(_) => deno_fs::resolve_from_cwd(Path::new(module))?
+      .to_string_lossy()
+      .into_owned(),
+  };
+
+  let (filename, shim) = if cfg!(windows) {
+    (format!("{}.cmd", exe_name), cmd_shim(&module, args))
+  } else {
+    (exe_name.to_string(), sh_shim(&module, args))
+  };
+  let path = bin_dir.join(filename);
+  if path.exists() && !force {
+    return Err(errors::new(
+      ErrorKind::AlreadyExists,
+      format!(
+        "\"{}\" is already installed, use -f/--force to overwrite it",
+        path.display()
+      ),
+    ));
+  }
+  deno_fs::mkdir(bin_dir, 0o755, true)?;
+  deno_fs::write_file(&path, shim, 0o755).map_err(|e| {
+    let kind = DenoError::from(e).kind();
+    errors::new(kind, format!("Unable to write \"{}\"", path.display()))
+  })?;
+  Ok(path)
+}
+
+/// Whether `dir` is one of the directories in PATH.
+pub fn is_in_path(dir: &Path) -> bool {
+  match env::var_os("PATH") {
+    Some(paths) => env::split_paths(&paths).any(|p| p == dir),
+    None => false,
+  }
+}
+
+fn sh_shim(module: &str, args: &[String]) -> String {
+  let mut command = vec!["deno".to_string(), "run".to_string()];
+  command.extend(args.iter().map(|arg| sh_quote(arg)));
+  command.push(sh_quote(module));
+  format!(
+    "#!/bin/sh\n# Generated by deno install.\n{} \"$@\"\n",
+    command.join(" ")
+  )
+}
+
+fn cmd_shim(module: &str, args: &[String]) -> String {
+  let mut command = vec!["deno".to_string(), "run".to_string()];
+  command.extend(args.iter().map(|arg| cmd_quote(arg)));
+  command.push(cmd_quote(module));
+  format!(
+    "@REM Generated by deno install.\r\n@{} %*\r\n",
+    command.join(" ")
+  )
+}
+
+/// Whether `arg` needs no quotes, given the punctuation which is safe.
+fn is_plain(arg: &str, safe: &str) -> bool {
+  !arg.is_empty()
+    && arg
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || safe.contains(c))
+}
+
+fn sh_quote(arg: &str) -> String {
+  if is_plain(arg, "-_=./:,@%+") {
+    arg.to_string()
+  } else {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+  }
+}
+
+fn cmd_quote(arg: &str) -> String {
+  if is_plain(arg, "-_=./:,@+\\") {
+    arg.to_string()
+  } else {
+    // %% keeps cmd from expanding variables in the shim.
+    format!("\"{}\"", arg.replace('"', "\"\"").replace('%', "%%"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+  use tempfile::TempDir;
+
+  #[test]
+  fn test_sh_shim() {
+    let args = vec!["--allow-net".to_string(), "--allow-read=/a b".to_string()];
+    assert_eq!(
+      sh_shim("https://deno.land/std/http/file_server.ts", &args),
+      "#!/bin/sh\n# Generated by deno install.\ndeno run --allow-net \
+       '--allow-read=/a b' https://deno.land/std/http/file_server.ts \"$@\"\n"
+    );
+    assert_eq!(sh_quote("it's"), "'it'\\''s'");
+    assert_eq!(cmd_quote("100%"), "\"100%%\"");
+  }
+
+  #[test]
+  fn test_install() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let bin_dir = temp_dir.path().join("bin");
+    let url = "http://localhost:4545/tests/subdir/print_hello.ts";
+
+    let path = install(&bin_dir, "hello", url, &[], false).unwrap();
+    assert!(path.starts_with(&bin_dir));
+    assert!(fs::read_to_string(&path).unwrap().contains(url));
+
+    // Only overwritten with force.
+    let err = install(&bin_dir, "hello", url, &[], false).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    assert!(install(&bin_dir, "hello", url, &[], true).is_ok());
+
+    assert!(install(&bin_dir, "../hello", url, &[], true).is_err());
+  }
+}
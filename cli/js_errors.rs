@@ -18,15 +18,18 @@ struct StackFrameColor<'a>(&'a StackFrame);
 pub trait SourceMapGetter {
   /// Returns the raw source map file.
   fn get_source_map(&self, script_name: &str) -> Option<Vec<u8>>;
+  /// Returns the zero indexed `line` of the original source.
+  fn get_source_line(&self, script_name: &str, line: usize) -> Option<String>;
 }
 
 /// Cached filename lookups. The key can be None if a previous lookup failed to
 /// find a SourceMap.
 type CachedMaps = HashMap<String, Option<SourceMap>>;
 
-struct SourceMap {
-  mappings: Mappings,
-  sources: Vec<String>,
+/// A parsed source map, `deno test --coverage` maps its lines with it too.
+pub struct SourceMap {
+  pub mappings: Mappings,
+  pub sources: Vec<String>,
 }
 
 impl<'a> fmt::Display for StackFrameColor<'a> {
@@ -65,9 +68,7 @@ impl<'a> fmt::Display for JSErrorColor<'a> {
     if e.script_resource_name.is_some() {
       let script_resource_name = e.script_resource_name.as_ref().unwrap();
       // Avoid showing internal code from gen/cli/bundle/main.js
-      if script_resource_name != "gen/cli/bundle/main.js"
-        && script_resource_name != "gen/cli/bundle/compiler.js"
-      {
+      if !is_internal(script_resource_name) {
         if e.line_number.is_some() && e.start_column.is_some() {
           assert!(e.line_number.is_some());
           assert!(e.start_column.is_some());
@@ -103,7 +104,7 @@ impl<'a> fmt::Display for JSErrorColor<'a> {
 }
 
 impl SourceMap {
-  fn from_json(json_str: &str) -> Option<Self> {
+  pub fn from_json(json_str: &str) -> Option<Self> {
     // Ugly. Maybe use serde_derive.
     match serde_json::from_str::<serde_json::Value>(json_str) {
       Ok(serde_json::Value::Object(map)) => match map["mappings"].as_str() {
@@ -137,27 +138,33 @@ impl SourceMap {
   }
 }
 
-fn frame_apply_source_map(
-  frame: &StackFrame,
+/// Whether `script_name` is one of the bundles of the runtime or the compiler.
+fn is_internal(script_name: &str) -> bool {
+  script_name == "gen/cli/bundle/main.js"
+    || script_name == "gen/cli/bundle/compiler.js"
+}
+
+/// Returns the original position of the zero indexed `line` and `column` of
+/// `script_name`, or the position itself if it has no source map.
+fn get_orig_position(
+  script_name: &str,
+  line: i64,
+  column: i64,
   mappings_map: &mut CachedMaps,
   getter: &dyn SourceMapGetter,
-) -> StackFrame {
-  let maybe_sm = get_mappings(frame.script_name.as_ref(), mappings_map, getter);
-  let frame_pos = (
-    frame.script_name.to_owned(),
-    frame.line as i64,
-    frame.column as i64,
-  );
-  let (script_name, line, column) = match maybe_sm {
-    None => frame_pos,
+) -> (String, i64, i64) {
+  let position = (script_name.to_owned(), line, column);
+  let maybe_sm = get_mappings(script_name, mappings_map, getter);
+  match maybe_sm {
+    None => position,
     Some(sm) => match sm.mappings.original_location_for(
-      frame.line as u32,
-      frame.column as u32,
+      line as u32,
+      column as u32,
       Bias::default(),
     ) {
-      None => frame_pos,
+      None => position,
       Some(mapping) => match &mapping.original {
-        None => frame_pos,
+        None => position,
         Some(original) => {
           let orig_source = sm.sources[original.source as usize].clone();
           (
@@ -168,7 +175,32 @@ fn frame_apply_source_map(
         }
       },
     },
-  };
+  }
+}
+
+/// Like get_orig_position(), for a single position.
+pub fn apply_source_map_position(
+  script_name: &str,
+  line: i64,
+  column: i64,
+  getter: &dyn SourceMapGetter,
+) -> (String, i64, i64) {
+  let mut mappings_map: CachedMaps = HashMap::new();
+  get_orig_position(script_name, line, column, &mut mappings_map, getter)
+}
+
+fn frame_apply_source_map(
+  frame: &StackFrame,
+  mappings_map: &mut CachedMaps,
+  getter: &dyn SourceMapGetter,
+) -> StackFrame {
+  let (script_name, line, column) = get_orig_position(
+    &frame.script_name,
+    frame.line,
+    frame.column,
+    mappings_map,
+    getter,
+  );
 
   StackFrame {
     script_name,
@@ -181,28 +213,59 @@ fn frame_apply_source_map(
   }
 }
 
+/// Maps the position and the frames of `js_error` to the original sources.
+/// Frames of the runtime's own code are left out unless `full_stack_traces`.
 pub fn apply_source_map(
   js_error: &JSError,
   getter: &dyn SourceMapGetter,
+  full_stack_traces: bool,
 ) -> JSError {
   let mut mappings_map: CachedMaps = HashMap::new();
   let mut frames = Vec::<StackFrame>::new();
   for frame in &js_error.frames {
+    if !full_stack_traces && is_internal(&frame.script_name) {
+      continue;
+    }
     let f = frame_apply_source_map(&frame, &mut mappings_map, getter);
     frames.push(f);
   }
+
+  let mut script_resource_name = js_error.script_resource_name.clone();
+  let mut source_line = js_error.source_line.clone();
+  let mut line_number = js_error.line_number;
+  let mut start_column = js_error.start_column;
+  let mut end_column = js_error.end_column;
+  if let (Some(name), Some(line), Some(start)) =
+    (&js_error.script_resource_name, line_number, start_column)
+  {
+    // The internal bundles are not displayed, their position stays as is.
+    if !is_internal(name) {
+      // line_number is one indexed.
+      let (orig_name, orig_line, orig_start) =
+        get_orig_position(name, line - 1, start, &mut mappings_map, getter);
+      if (&orig_name, orig_line, orig_start) != (name, line - 1, start) {
+        source_line = getter.get_source_line(&orig_name, orig_line as usize);
+        end_column = end_column.map(|end| orig_start + end - start);
+        script_resource_name = Some(orig_name);
+        line_number = Some(orig_line + 1);
+        start_column = Some(orig_start);
+      }
+    }
+  }
+
   JSError {
     message: js_error.message.clone(),
     frames,
     error_level: js_error.error_level,
-    source_line: js_error.source_line.clone(),
-    // TODO the following need to be source mapped:
-    script_resource_name: js_error.script_resource_name.clone(),
-    line_number: js_error.line_number,
+    source_line,
+    script_resource_name,
+    line_number,
+    // Offsets into the compiled script, which are left as they are.
     start_position: js_error.start_position,
     end_position: js_error.end_position,
-    start_column: js_error.start_column,
-    end_column: js_error.end_column,
+    start_column,
+    end_column,
+    kind: js_error.kind,
   }
 }
 
@@ -269,6 +332,7 @@ mod tests {
       error_level: None,
       start_column: None,
       end_column: None,
+      kind: None,
       frames: vec![
         StackFrame {
           line: 4,
@@ -312,6 +376,19 @@ mod tests {
       };
       Some(s.as_bytes().to_owned())
     }
+
+    fn get_source_line(
+      &self,
+      script_name: &str,
+      line: usize,
+    ) -> Option<String> {
+      let s = match script_name {
+        "foo_bar.ts" => "// foo_bar.ts\n\nfunction foo() {}\n\n\
+                         foo();\n  foo(1, 2, 3);\n",
+        _ => return None,
+      };
+      s.lines().nth(line).map(|s| s.to_string())
+    }
   }
 
   #[test]
@@ -324,7 +401,7 @@ mod tests {
   fn js_error_apply_source_map_1() {
     let e = error1();
     let getter = MockSourceMapGetter {};
-    let actual = apply_source_map(&e, &getter);
+    let actual = apply_source_map(&e, &getter, true);
     let expected = JSError {
       message: "Error: foo bar".to_string(),
       source_line: None,
@@ -335,6 +412,7 @@ mod tests {
       error_level: None,
       start_column: None,
       end_column: None,
+      kind: None,
       frames: vec![
         StackFrame {
           line: 5,
@@ -380,6 +458,7 @@ mod tests {
       error_level: None,
       start_column: None,
       end_column: None,
+      kind: None,
       frames: vec![StackFrame {
         line: 11,
         column: 12,
@@ -391,11 +470,51 @@ mod tests {
       }],
     };
     let getter = MockSourceMapGetter {};
-    let actual = apply_source_map(&e, &getter);
+    let actual = apply_source_map(&e, &getter, true);
     assert_eq!(actual.message, "TypeError: baz");
     // Because this is accessing the live bundle, this test might be more fragile
     assert_eq!(actual.frames.len(), 1);
     assert!(actual.frames[0].script_name.ends_with("js/util.ts"));
+
+    // Internal frames are left out by default.
+    let actual = apply_source_map(&e, &getter, false);
+    assert_eq!(actual.frames.len(), 0);
+  }
+
+  #[test]
+  fn js_error_apply_source_map_3() {
+    let e = JSError {
+      message: "Error: foo bar".to_string(),
+      source_line: Some("    foo(1, 2, 3);".to_string()),
+      script_resource_name: Some("foo_bar.ts".to_string()),
+      line_number: Some(5),
+      start_position: Some(100),
+      end_position: Some(104),
+      error_level: Some(8),
+      start_column: Some(16),
+      end_column: Some(20),
+      kind: None,
+      frames: vec![],
+    };
+    let getter = MockSourceMapGetter {};
+    let actual = apply_source_map(&e, &getter, false);
+    assert_eq!(
+      actual,
+      JSError {
+        source_line: Some("  foo(1, 2, 3);".to_string()),
+        line_number: Some(6),
+        start_column: Some(12),
+        end_column: Some(16),
+        ..e.clone()
+      }
+    );
+
+    // Without a source map the position is kept.
+    let e = JSError {
+      script_resource_name: Some("deno_main.js".to_string()),
+      ..e
+    };
+    assert_eq!(apply_source_map(&e, &getter, false), e);
   }
 
   #[test]
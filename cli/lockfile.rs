@@ -0,0 +1,134 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+use crate::errors;
+use crate::errors::DenoResult;
+use crate::msg::ErrorKind;
+use serde_json;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+
+/// Maps remote module URLs to the SHA-256 of their source code, see `--lock`
+/// and `--lock-write`.
+#[derive(Debug)]
+pub struct Lockfile {
+  pub filename: String,
+  pub write: bool,
+  map: BTreeMap<String, String>,
+}
+
+impl Lockfile {
+  /// With `write` the lock file starts out empty and is filled while modules
+  /// are loaded, otherwise the existing lock file is read.
+  pub fn new(filename: String, write: bool) -> DenoResult<Lockfile> {
+    let map = if write {
+      BTreeMap::new()
+    } else {
+      let s = fs::read_to_string(&filename)?;
+      serde_json::from_str(&s).map_err(|e| {
+        errors::new(
+          ErrorKind::InvalidData,
+          format!("Unable to parse lock file \"{}\": {}", filename, e),
+        )
+      })?
+    };
+    Ok(Lockfile {
+      filename,
+      write,
+      map,
+    })
+  }
+
+  /// Writes the collected hashes, sorted by URL so that the file diffs well.
+  pub fn write(&self) -> DenoResult<()> {
+    let j = serde_json::to_string_pretty(&self.map).unwrap();
+    fs::write(&self.filename, j + "\n")?;
+    debug!("lock file written: {}", self.filename);
+    Ok(())
+  }
+
+  /// Records the hash of `source_code` when writing the lock file, otherwise
+  /// compares it against the recorded one.
+  pub fn check_or_insert(
+    &mut self,
+    url: &str,
+    source_code: &[u8],
+  ) -> DenoResult<()> {
+    let actual = sha256_hex(source_code);
+    if self.write {
+      self.map.insert(url.to_string(), actual);
+      return Ok(());
+    }
+    match self.map.get(url) {
+      Some(expected) if *expected == actual => Ok(()),
+      Some(expected) => Err(errors::new(
+        ErrorKind::InvalidData,
+        format!(
+          "Integrity check failed for \"{}\"\n  expected: {}\n  actual:   {}\nThe lock file is \"{}\"",
+          url, expected, actual, self.filename
+        ),
+      )),
+      None => Err(errors::new(
+        ErrorKind::InvalidData,
+        format!(
+          "\"{}\" is not in the lock file \"{}\" (hash {}), use --lock-write to update it",
+          url, self.filename, actual
+        ),
+      )),
+    }
+  }
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+  let digest = ring::digest::digest(&ring::digest::SHA256, data);
+  let mut out = String::new();
+  for byte in digest.as_ref() {
+    write!(&mut out, "{:02x}", byte).unwrap();
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  #[test]
+  fn test_lockfile_write_and_check() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let filename =
+      temp_dir.path().join("lock.json").to_str().unwrap().to_string();
+    let url = "http://localhost:4545/tests/subdir/mod2.ts";
+
+    let mut lockfile = Lockfile::new(filename.clone(), true).unwrap();
+    lockfile.check_or_insert(url, b"export const a = 1;").unwrap();
+    lockfile.write().unwrap();
+
+    let mut lockfile = Lockfile::new(filename.clone(), false).unwrap();
+    assert!(lockfile.check_or_insert(url, b"export const a = 1;").is_ok());
+
+    // Tampered source code.
+    let err = lockfile
+      .check_or_insert(url, b"export const a = 2;")
+      .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let msg = err.to_string();
+    assert!(msg.contains(url));
+    assert!(msg.contains(&sha256_hex(b"export const a = 1;")));
+    assert!(msg.contains(&sha256_hex(b"export const a = 2;")));
+
+    // Module missing from the lock file.
+    let err = lockfile
+      .check_or_insert("http://localhost:4545/other.ts", b"")
+      .unwrap_err();
+    assert!(err.to_string().contains("http://localhost:4545/other.ts"));
+  }
+
+  #[test]
+  fn test_lockfile_invalid() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let filename = temp_dir.path().join("lock.json");
+    fs::write(&filename, "not json").unwrap();
+    let filename = filename.to_str().unwrap().to_string();
+    assert!(Lockfile::new(filename, false).is_err());
+  }
+}
@@ -11,37 +11,74 @@ extern crate clap;
 extern crate deno;
 
 mod ansi;
+mod bench;
+mod clean;
+mod completions;
+mod cookie_jar;
+mod coverage;
 pub mod compiler;
 pub mod deno_dir;
+mod diagnostics;
+mod doc;
 pub mod errors;
 pub mod flags;
+mod fmt;
 mod fs;
-mod global_timer;
+mod fs_events;
 mod http_body;
+mod http_server;
 mod http_util;
+pub mod import_map;
+mod inspector;
+mod installer;
 pub mod js_errors;
+mod lockfile;
 pub mod modules;
 pub mod msg;
 pub mod msg_util;
+mod op_trace;
 pub mod ops;
+mod performance;
 pub mod permissions;
 mod repl;
 pub mod resolve_addr;
 pub mod resources;
+mod shell_words;
+mod signal;
 mod startup_data;
 pub mod state;
+mod test_runner;
+mod timers;
+mod tls;
 mod tokio_util;
 mod tokio_write;
+mod tsconfig;
+mod upgrade;
 pub mod version;
+mod wasm;
+mod watch;
+mod websocket;
 pub mod worker;
 
+use crate::compiler::CompileError;
+use crate::diagnostics::exit_with_diagnostics;
+use crate::errors::DenoError;
+use crate::errors::DenoResult;
 use crate::errors::RustOrJsError;
+use crate::flags::LogLevel;
 use crate::state::ThreadSafeState;
 use crate::worker::Worker;
+use deno::JSError;
+use futures::future::poll_fn;
 use futures::lazy;
+use futures::Async;
 use futures::Future;
+use futures::Poll;
 use log::{LevelFilter, Metadata, Record};
 use std::env;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
 
 static LOGGER: Logger = Logger;
 
@@ -62,7 +99,7 @@ impl log::Log for Logger {
 
 fn print_err_and_exit(err: RustOrJsError) {
   eprintln!("{}", err.to_string());
-  std::process::exit(1);
+  std::process::exit(err.exit_code());
 }
 
 fn js_check<E>(r: Result<(), E>)
@@ -74,63 +111,507 @@ where
   }
 }
 
+static LIB_RUNTIME_DTS: &str = include_str!(concat!(
+  env!("GN_OUT_DIR"),
+  "/gen/cli/lib/lib.deno_runtime.d.ts"
+));
+
+/// Prints the runtime declarations for `deno types`, or writes them to
+/// `output`. The header names the version so that stale copies stand out.
+fn write_types(output: Option<&str>) -> DenoResult<()> {
+  let content = format!(
+    "// TypeScript declarations of the deno {} runtime, generated by\n\
+     // `deno types`. Regenerate them after upgrading deno.\n\n{}",
+    version::DENO,
+    LIB_RUNTIME_DTS
+  );
+  match output {
+    None => print!("{}", content),
+    Some(filename) => {
+      let path = Path::new(filename);
+      path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(path, content))
+        .map_err(|e| {
+          let kind = DenoError::from(e).kind();
+          errors::new(kind, format!("Unable to write \"{}\"", filename))
+        })?;
+    }
+  }
+  Ok(())
+}
+
+/// Returns the script which `deno eval` runs for the code it was given.
+fn eval_source(state: &ThreadSafeState) -> DenoResult<String> {
+  let mut code = state.argv[1].clone();
+  if state.flags.eval_ts {
+    // Compiled as if it was a file in the cwd, which errors refer to.
+    let path = env::current_dir()?.join("$eval.ts");
+    let module_name = deno_dir::add_memory_module(&path, code.into_bytes());
+    let out = worker::fetch_module_meta_data_and_maybe_compile(
+      state,
+      &module_name,
+      ".",
+    )?;
+    code = out.js_source();
+  }
+  if state.flags.eval_print {
+    // An indirect eval returns the value of the last expression statement.
+    return Ok(format!(
+      "Promise.resolve((0, eval)({})).then(value => console.log(value));\n",
+      serde_json::to_string(&code).unwrap()
+    ));
+  }
+  // Wrap provided script in async function, so that it can use top-level
+  // await like the main module can. Its rejection fails the program like an
+  // unhandled rejection does.
+  Ok(format!(
+    "async function _topLevelWrapper(){{
+          {}
+        }}
+        _topLevelWrapper();
+        ",
+    code
+  ))
+}
+
+/// Adds the -e the error is in to the message of an error which the script of
+/// `eval_source()` threw, like a syntax error.
+fn with_eval_fragment(mut err: JSError, fragments: &[String]) -> JSError {
+  if let Some(line_number) = err.line_number {
+    // The code starts on the second line of the wrapper.
+    let mut last_line = 1;
+    for (i, fragment) in fragments.iter().enumerate() {
+      last_line += fragment.split('\n').count() as i64;
+      if line_number <= last_line {
+        err.message = format!("{} (in -e #{})", err.message, i + 1);
+        break;
+      }
+    }
+  }
+  err
+}
+
+/// Writes the bundle of `main_module` for `deno bundle`, to stdout if no
+/// output file was given.
+fn write_bundle(
+  state: &ThreadSafeState,
+  main_module: &str,
+) -> Result<(), RustOrJsError> {
+  let bundle = compiler::bundle_sync(
+    state.clone(),
+    main_module,
+    ".",
+    state.flags.bundle_sourcemap,
+  ).map_err(|err| match err {
+    CompileError::Diagnostics(d) => {
+      exit_with_diagnostics(&d, state.flags.diagnostics_json)
+    }
+    CompileError::Js(e) => RustOrJsError::Js(e),
+  })?;
+  match state.flags.bundle_output {
+    None => print!("{}", bundle),
+    Some(ref filename) => {
+      std::fs::write(filename, bundle).map_err(|e| {
+        let kind = DenoError::from(e).kind();
+        errors::new(kind, format!("Unable to write \"{}\"", filename))
+      })?;
+    }
+  }
+  Ok(())
+}
+
+/// The environment variables shown by `deno info` without a file.
+const INFO_ENV_VARS: &[&str] = &["DENO_DIR", "DENO_CERT", "NO_COLOR"];
+
+/// Prints the DENO_DIR in effect, the size of its caches and the environment
+/// variables deno reads, for `deno info` without a file.
+fn print_deno_dir_info(state: &ThreadSafeState) {
+  let deno_dir_flag = state.flags.deno_dir.as_ref().map(String::as_str);
+  let (_, source) = deno_dir::DenoDir::custom_root(deno_dir_flag);
+  let dir = &state.dir;
+  let cache_size = clean::cache_size(dir);
+  if let Err(ref err) = cache_size {
+    debug!("unable to measure the cache: {}", err);
+  }
+  let env_vars: Vec<(&str, Option<String>)> = INFO_ENV_VARS
+    .iter()
+    .map(|name| (*name, env::var(name).ok()))
+    .collect();
+  if state.flags.info_json {
+    let mut env_json = serde_json::Map::new();
+    for (name, value) in &env_vars {
+      env_json.insert(name.to_string(), json!(value));
+    }
+    let info = json!({
+      "denoDir": dir.root,
+      "source": match source {
+        deno_dir::RootSource::Flag => "flag",
+        deno_dir::RootSource::Env => "env",
+        deno_dir::RootSource::Default => "default",
+      },
+      "deps": dir.deps,
+      "gen": dir.gen,
+      "cacheSize": cache_size.ok(),
+      "env": env_json,
+    });
+    println!("{}", serde_json::to_string_pretty(&info).unwrap());
+  } else {
+    println!(
+      "{} {} ({})",
+      ansi::bold_stdout("deno dir:".to_string()),
+      dir.root.display(),
+      source.describe()
+    );
+    println!(
+      "{} {}",
+      ansi::bold_stdout("deps:".to_string()),
+      dir.deps.display()
+    );
+    println!("{} {}", ansi::bold_stdout("gen:".to_string()), dir.gen.display());
+    println!(
+      "{} {}",
+      ansi::bold_stdout("cache size:".to_string()),
+      match cache_size {
+        Ok(size) => format!("{} bytes", size),
+        Err(_) => "unknown".to_string(),
+      }
+    );
+    println!("{}", ansi::bold_stdout("environment:".to_string()));
+    for (name, value) in env_vars {
+      match value {
+        Some(value) => println!("  {}={}", name, value),
+        None => println!("  {} is not set", name),
+      }
+    }
+  }
+}
+
 fn main() {
   #[cfg(windows)]
   ansi_term::enable_ansi_support().ok(); // For Windows 10
 
   log::set_logger(&LOGGER).unwrap();
   let args = env::args().collect();
+  let env_flags = env::var(flags::FLAGS_ENV).ok();
   let (mut flags, mut rest_argv) =
-    flags::set_flags(args).unwrap_or_else(|err| {
+    flags::add_preset_flags(args, env_flags.as_ref().map(String::as_str))
+      .and_then(flags::set_flags)
+      .unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(errors::EXIT_USAGE_ERROR)
+      });
+  ansi::set_color_mode(flags.color);
+
+  if flags.ca_file.is_none() {
+    flags.ca_file = env::var("DENO_CERT").ok();
+  }
+  if let Some(ref ca_file) = flags.ca_file {
+    if let Err(err) = http_util::add_root_certs(ca_file) {
       eprintln!("{}", err);
-      std::process::exit(1)
-    });
+      std::process::exit(1);
+    }
+  }
 
-  log::set_max_level(if flags.log_debug {
-    LevelFilter::Debug
-  } else {
-    LevelFilter::Warn
+  // Made absolute once, so that workers started after Deno.chdir(), like the
+  // compiler, use the same directory.
+  if let Some(ref deno_dir) = flags.deno_dir {
+    match fs::resolve_from_cwd(Path::new(deno_dir)) {
+      Ok(path) => flags.deno_dir = Some(path.to_string_lossy().into_owned()),
+      Err(err) => {
+        eprintln!("Unable to resolve --deno-dir \"{}\": {}", deno_dir, err);
+        std::process::exit(1);
+      }
+    }
+  }
+
+  // Informational output of the CLI itself does not go through `log`, see
+  // DenoFlags::shows_info(), so info level logging stays off except for
+  // debugging.
+  log::set_max_level(match flags.log_level {
+    LogLevel::Debug => LevelFilter::Debug,
+    LogLevel::Info | LogLevel::Warn => LevelFilter::Warn,
+    LogLevel::Error => LevelFilter::Error,
   });
 
+  if flags.types {
+    js_check(write_types(flags.types_output.as_ref().map(String::as_str)));
+    std::process::exit(0);
+  }
+
+  if let Some(ref shell) = flags.completions {
+    print!("{}", completions::generate(shell));
+    std::process::exit(0);
+  }
+
+  if flags.install {
+    let bin_dir = installer::bin_dir();
+    let exe_name = &rest_argv[1];
+    let module = &rest_argv[2];
+    let args = &rest_argv[3..];
+    match installer::install(
+      &bin_dir,
+      exe_name,
+      module,
+      args,
+      flags.install_force,
+    ) {
+      Ok(path) => println!("Installed {}", path.display()),
+      Err(err) => {
+        eprintln!("{}", err);
+        std::process::exit(1);
+      }
+    }
+    if !installer::is_in_path(&bin_dir) {
+      eprintln!(
+        "Warning: {} is not in PATH, add it to run {}",
+        bin_dir.display(),
+        exe_name
+      );
+    }
+    std::process::exit(0);
+  }
+
+  if flags.upgrade {
+    let options = upgrade::Options {
+      version: flags.upgrade_version.clone(),
+      output: flags.upgrade_output.as_ref().map(PathBuf::from),
+      dry_run: flags.upgrade_dry_run,
+      quiet: !flags.shows_info(),
+    };
+    match upgrade::upgrade(&options) {
+      Ok(upgrade::Outcome::UpToDate(version)) => {
+        println!("deno {} is the latest version", version)
+      }
+      Ok(upgrade::Outcome::Installed(version, path)) => {
+        if options.dry_run {
+          println!("Would install deno {} at {}", version, path.display());
+        } else {
+          println!("Installed deno {} at {}", version, path.display());
+        }
+      }
+      Err(err) => {
+        eprintln!("Unable to upgrade: {}", err);
+        std::process::exit(1);
+      }
+    }
+    std::process::exit(0);
+  }
+
+  if flags.clean {
+    let deno_dir_flag = flags.deno_dir.as_ref().map(String::as_str);
+    let (custom_root, _) = deno_dir::DenoDir::custom_root(deno_dir_flag);
+    let prefix = rest_argv.get(1).map(String::as_str);
+    let result = deno_dir::DenoDir::new(custom_root, false)
+      .and_then(|dir| clean::clean(&dir, prefix, flags.clean_dry_run));
+    match result {
+      Ok(removed) => {
+        if flags.clean_dry_run {
+          for path in &removed.files {
+            println!("{}", path.display());
+          }
+          println!(
+            "Would remove {} files, {} bytes",
+            removed.files.len(),
+            removed.bytes
+          );
+        } else {
+          println!(
+            "Removed {} files, {} bytes",
+            removed.files.len(),
+            removed.bytes
+          );
+        }
+      }
+      Err(err) => {
+        eprintln!("Unable to clean the cache: {}", err);
+        std::process::exit(1);
+      }
+    }
+    std::process::exit(0);
+  }
+
   if flags.fmt {
-    rest_argv.insert(1, "https://deno.land/std/prettier/main.ts".to_string());
+    // With no files given and a buffer piped in, format stdin to stdout. This
+    // makes `:%!deno fmt` work in editors. Otherwise the current directory is
+    // formatted.
+    if rest_argv.len() == 1 {
+      let default = if atty::is(atty::Stream::Stdin) { "." } else { "-" };
+      rest_argv.push(default.to_string());
+    }
+    let use_stdin = rest_argv.iter().skip(1).any(|arg| arg == "-");
+    // The same options for --check, so that checked files are formatted the
+    // way writing them would.
+    let prettier_args = fmt::prettier_args(&flags.fmt_config);
+    if !use_stdin {
+      let paths: Vec<PathBuf> =
+        rest_argv.drain(1..).map(PathBuf::from).collect();
+      let ignore: Vec<fmt::Glob> =
+        flags.fmt_ignore.iter().map(|p| fmt::Glob::new(p)).collect();
+      let files = fmt::collect_files(&paths, &ignore, |warning| {
+        eprintln!("Warning: {}", warning)
+      });
+      if files.is_empty() {
+        eprintln!("No files to format");
+        std::process::exit(0);
+      }
+      let formatter = fmt::Formatter::new(prettier_args);
+      let jobs = flags.fmt_jobs.unwrap_or_else(num_cpus::get);
+      let results = fmt::format_files(&formatter, files, jobs, flags.fmt_check);
+      std::process::exit(fmt::report(
+        &results,
+        flags.fmt_check,
+        flags.shows_info(),
+      ));
+    }
+    // Stdin is formatted by this process, to stdout.
+    rest_argv.insert(1, fmt::PRETTIER_URL.to_string());
+    rest_argv.splice(2..2, prettier_args);
+    if flags.fmt_check {
+      rest_argv.insert(2, "--check".to_string());
+    }
     flags.allow_read = true;
-    flags.allow_write = true;
+  }
+
+  if flags.bench || flags.test {
+    if rest_argv.len() == 1 {
+      rest_argv.push(".".to_string());
+    }
+    let paths: Vec<PathBuf> = rest_argv.drain(1..).map(PathBuf::from).collect();
+    let warn = |warning: String| eprintln!("Warning: {}", warning);
+    let (files, kind) = if flags.bench {
+      (bench::collect_files(&paths, warn), "bench")
+    } else {
+      (test_runner::collect_files(&paths, warn), "test")
+    };
+    if files.is_empty() {
+      eprintln!("No {} files found", kind);
+      std::process::exit(0);
+    }
+    // The main module imports them by URL.
+    let files: Vec<PathBuf> = files
+      .into_iter()
+      .map(|file| fs::resolve_from_cwd(&file).unwrap_or(file))
+      .collect();
+    let source = if flags.bench {
+      bench::main_source(&files, &flags)
+    } else {
+      test_runner::main_source(&files, &flags)
+    };
+    let path = env::current_dir().unwrap().join(format!("${}.js", kind));
+    rest_argv.push(deno_dir::add_memory_module(&path, source.into_bytes()));
+    if flags.bench {
+      // The timings are not coarsened.
+      flags.allow_high_precision = true;
+    }
+  }
+
+  if flags.watch {
+    watch::run(&flags, &rest_argv[1..]);
+  }
+
+  // `deno -` runs the script piped to stdin.
+  if rest_argv.get(1).map(String::as_str) == Some("-") && !flags.eval {
+    let mut source_code = Vec::new();
+    if let Err(err) = std::io::stdin().read_to_end(&mut source_code) {
+      eprintln!("Unable to read the script from stdin: {}", err);
+      std::process::exit(1);
+    }
+    let ext = flags.stdin_ext.as_ref().map(String::as_str).unwrap_or("ts");
+    // In the cwd, so that relative imports resolve against it.
+    let path = env::current_dir().unwrap().join(format!("$stdin.{}", ext));
+    rest_argv[1] = deno_dir::add_memory_module(&path, source_code);
   }
 
   let should_prefetch = flags.prefetch || flags.info;
   let should_display_info = flags.info;
 
   let state = ThreadSafeState::new(flags, rest_argv, ops::op_selector_std);
+  if should_display_info && state.argv.len() == 1 {
+    print_deno_dir_info(&state);
+    std::process::exit(0);
+  }
   let mut main_worker = Worker::new(
     "main".to_string(),
-    startup_data::deno_isolate_init(),
+    startup_data::deno_isolate_init(!state.flags.no_snapshot),
     state.clone(),
   );
 
+  if let Some(addr) = state.flags.inspector_addr() {
+    match inspector::listen(addr) {
+      Ok(host) => main_worker.set_inspector(Box::new(host)),
+      Err(err) => {
+        eprintln!("Unable to start the inspector on {}: {}", addr, err);
+        std::process::exit(1);
+      }
+    }
+  }
+
+  // Before the runtime and the tests execute.
+  let coverage = if state.flags.coverage.is_some() {
+    Some(coverage::start(&mut main_worker))
+  } else {
+    None
+  };
+
   let main_future = lazy(move || {
+    if state.flags.bundle {
+      match state.main_module() {
+        Some(main_module) => js_check(write_bundle(&state, &main_module)),
+        None => {
+          eprintln!("Unable to resolve \"{}\"", state.argv[1]);
+          std::process::exit(errors::EXIT_MODULE_ERROR);
+        }
+      }
+      std::process::exit(0);
+    }
+
+    if state.flags.doc {
+      match state.main_module() {
+        Some(main_module) => js_check(doc::print(&state, &main_module)),
+        None => {
+          eprintln!("Unable to resolve \"{}\"", state.argv[1]);
+          std::process::exit(errors::EXIT_MODULE_ERROR);
+        }
+      }
+      std::process::exit(0);
+    }
+
     // Setup runtime.
     js_check(main_worker.execute("denoMain()"));
 
+    if state.flags.inspect_brk.is_some() {
+      main_worker.wait_for_debugger();
+    }
+
     if state.flags.eval {
-      // Wrap provided script in async function so asynchronous methods
-      // work. This is required until top-level await is not supported.
-      let js_source = format!(
-        "async function _topLevelWrapper(){{
-          {}
-        }}
-        _topLevelWrapper();
-        ",
-        &state.argv[1]
-      );
       // ATM imports in `deno eval` are not allowed
       // TODO Support ES modules once Worker supports evaluating anonymous modules.
-      js_check(main_worker.execute(&js_source));
+      match eval_source(&state) {
+        Ok(js_source) => {
+          let result = main_worker.execute(&js_source);
+          let fragments = &state.flags.eval_fragments;
+          js_check(result.map_err(|err| with_eval_fragment(err, fragments)))
+        }
+        Err(err) => print_err_and_exit(err.into()),
+      }
     } else {
       // Execute main module.
       if let Some(main_module) = state.main_module() {
         debug!("main_module {}", main_module);
-        js_check(main_worker.execute_mod(&main_module, should_prefetch));
+        let result = main_worker.execute_mod(&main_module, should_prefetch);
+        if let Ok(addr) = env::var(watch::MODULES_ENV) {
+          // Also after errors, so that the fixed file restarts the script.
+          let modules = state.modules.lock().unwrap();
+          if let Err(err) = watch::report_modules(&addr, &modules) {
+            debug!("unable to report modules to --watch: {}", err);
+          }
+        }
+        js_check(result);
+        if state.flags.lock_write {
+          js_check(state.write_lockfile());
+        }
         if should_display_info {
           // Display file info and exit. Do not run file
           main_worker.print_file_info(&main_module);
@@ -139,9 +620,21 @@ fn main() {
       }
     }
 
-    main_worker.then(|result| {
-      js_check(result);
-      Ok(())
+    poll_fn(move || -> Poll<(), ()> {
+      match main_worker.poll() {
+        Ok(Async::NotReady) => return Ok(Async::NotReady),
+        result => js_check(result.map(|_| ())),
+      }
+      if let Some(ref coverage) = coverage {
+        let scripts = coverage.take(&mut main_worker);
+        if let Err(err) = coverage::report(&state, &scripts) {
+          eprintln!("Unable to write the coverage: {}", err);
+          std::process::exit(1);
+        }
+      }
+      // Nothing is left to do, the unload hooks run before the exit.
+      js_check(main_worker.execute("denoUnload()"));
+      Ok(Async::Ready(()))
     })
   });
 
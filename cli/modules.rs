@@ -1,8 +1,12 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
 use crate::ansi;
+use crate::deno_dir;
 use crate::deno_dir::DenoDir;
+use crate::deno_dir::ModuleOrigin;
+use crate::errors;
 use crate::msg;
 use deno::deno_mod;
+use serde_json;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt;
@@ -10,6 +14,8 @@ use std::fmt;
 pub struct ModuleInfo {
   name: String,
   children: Vec<deno_mod>,
+  /// The size of the source, or of the binary of a WebAssembly module.
+  size: Option<usize>,
 }
 
 /// A symbolic module entity.
@@ -94,6 +100,11 @@ impl Modules {
     self.info.get(&id).map(|i| &i.name)
   }
 
+  /// The names of all registered modules, aliases excluded.
+  pub fn names(&self) -> impl Iterator<Item = &String> {
+    self.info.values().map(|i| &i.name)
+  }
+
   pub fn is_registered(&self, name: &str) -> bool {
     self.by_name.get(name).is_some()
   }
@@ -107,10 +118,21 @@ impl Modules {
       ModuleInfo {
         name,
         children: Vec::new(),
+        size: None,
       },
     );
   }
 
+  pub fn set_size(&mut self, id: deno_mod, size: usize) {
+    if let Some(info) = self.info.get_mut(&id) {
+      info.size = Some(size);
+    }
+  }
+
+  pub fn get_size(&self, id: deno_mod) -> Option<usize> {
+    self.info.get(&id).and_then(|i| i.size)
+  }
+
   pub fn alias(&mut self, name: &str, target: &str) {
     self.by_name.alias(name.to_owned(), target.to_owned());
   }
@@ -145,7 +167,14 @@ impl Modules {
     }
   }
 
-  pub fn print_file_info(&self, deno_dir: &DenoDir, filename: String) {
+  /// Prints the module `filename` and its dependency tree, `max_depth` levels
+  /// deep if given.
+  pub fn print_file_info(
+    &self,
+    deno_dir: &DenoDir,
+    filename: String,
+    max_depth: Option<usize>,
+  ) {
     // TODO Note the --reload flag is ignored here.
     let maybe_out = deno_dir.fetch_module_meta_data(&filename, ".", true);
     if maybe_out.is_err() {
@@ -154,86 +183,231 @@ impl Modules {
     }
     let out = maybe_out.unwrap();
 
-    println!("{} {}", ansi::bold("local:".to_string()), &(out.filename));
+    if let Some(ref requested) = out.module_redirect_source_name {
+      println!("{} {}", ansi::bold_stdout("requested:".to_string()), requested);
+      println!(
+        "{} {}",
+        ansi::bold_stdout("resolved:".to_string()),
+        out.module_name
+      );
+    }
+    // A data: URL is nowhere on disk.
+    if deno_dir::module_origin(&out.module_name) != ModuleOrigin::Data {
+      println!(
+        "{} {}",
+        ansi::bold_stdout("local:".to_string()),
+        &(out.filename)
+      );
+    }
     println!(
       "{} {}",
-      ansi::bold("type:".to_string()),
+      ansi::bold_stdout("type:".to_string()),
       msg::enum_name_media_type(out.media_type)
     );
     if out.maybe_output_code_filename.is_some() {
       println!(
         "{} {}",
-        ansi::bold("compiled:".to_string()),
+        ansi::bold_stdout("compiled:".to_string()),
         out.maybe_output_code_filename.as_ref().unwrap(),
       );
     }
     if out.maybe_source_map_filename.is_some() {
       println!(
         "{} {}",
-        ansi::bold("map:".to_string()),
+        ansi::bold_stdout("map:".to_string()),
         out.maybe_source_map_filename.as_ref().unwrap()
       );
     }
 
-    let deps = Deps::new(self, &out.module_name);
-    println!("{}{}", ansi::bold("deps:\n".to_string()), deps.name);
+    let deps = Deps::new(self, &out.module_name, max_depth);
+    println!("{}{}", ansi::bold_stdout("deps:\n".to_string()), deps.label());
     if let Some(ref depsdeps) = deps.deps {
       for d in depsdeps {
         println!("{}", d);
       }
     }
   }
+
+  /// Same as `print_file_info` but prints a single JSON object to stdout, so
+  /// that the output can be consumed by other tools. Errors go to stderr.
+  pub fn print_file_info_json(&self, deno_dir: &DenoDir, filename: String) {
+    let maybe_out = deno_dir.fetch_module_meta_data(&filename, ".", true);
+    if maybe_out.is_err() {
+      eprintln!("{}", maybe_out.unwrap_err());
+      std::process::exit(errors::EXIT_MODULE_ERROR);
+    }
+    let out = maybe_out.unwrap();
+
+    let mut deps = Vec::new();
+    Deps::new(self, &out.module_name, None).collect_names(&mut deps);
+    // The first entry is the module itself.
+    deps.remove(0);
+
+    let is_data =
+      deno_dir::module_origin(&out.module_name) == ModuleOrigin::Data;
+    let info = json!({
+      "module": out.module_name,
+      // Differs from "module" after redirects.
+      "requested": out
+        .module_redirect_source_name
+        .as_ref()
+        .unwrap_or(&out.module_name),
+      // A data: URL is nowhere on disk.
+      "local": if is_data { None } else { Some(&out.filename) },
+      "type": msg::enum_name_media_type(out.media_type),
+      "compiled": out.maybe_output_code_filename,
+      "map": out.maybe_source_map_filename,
+      "size": out.source_code.len(),
+      "deps": deps,
+    });
+    println!("{}", serde_json::to_string_pretty(&info).unwrap());
+  }
+}
+
+/// Why the dependencies of a module in the tree are not listed below it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Elided {
+  /// Listed further up the tree already.
+  Duplicate,
+  /// Imports one of the modules it is listed below.
+  Cycle,
+  /// Beyond the maximum depth.
+  Depth,
 }
 
 pub struct Deps {
   pub name: String,
+  pub size: Option<usize>,
+  pub origin: ModuleOrigin,
   pub deps: Option<Vec<Deps>>,
+  pub elided: Option<Elided>,
   prefix: String,
   is_last: bool,
 }
 
 impl Deps {
-  pub fn new(modules: &Modules, module_name: &str) -> Deps {
-    let mut seen = HashSet::new();
+  pub fn new(
+    modules: &Modules,
+    module_name: &str,
+    max_depth: Option<usize>,
+  ) -> Deps {
+    let mut tree = Tree {
+      modules,
+      max_depth,
+      seen: HashSet::new(),
+      ancestors: Vec::new(),
+    };
     let id = modules.get_id(module_name).unwrap();
-    Self::helper(&mut seen, "".to_string(), true, modules, id)
+    tree.helper("".to_string(), true, id)
   }
 
-  fn helper(
-    seen: &mut HashSet<deno_mod>,
-    prefix: String,
-    is_last: bool,
-    modules: &Modules,
-    id: deno_mod,
-  ) -> Deps {
-    let name = modules.get_name(id).unwrap().to_string();
-    if seen.contains(&id) {
-      Deps {
-        name,
-        prefix,
-        deps: None,
-        is_last,
+  /// Collects the names of this module and all of its transitive
+  /// dependencies, each listed once, in depth-first order.
+  pub fn collect_names(&self, names: &mut Vec<String>) {
+    if !names.contains(&self.name) {
+      names.push(self.name.clone());
+    }
+    if let Some(ref deps) = self.deps {
+      for d in deps {
+        d.collect_names(names);
+      }
+    }
+  }
+
+  /// The name of the module, where it came from, its size and why its
+  /// dependencies are left out.
+  pub fn label(&self) -> String {
+    let name = display_name(&self.name);
+    let mut label = match self.size {
+      Some(size) => {
+        format!("{} ({} bytes, {})", name, size, self.origin.describe())
       }
+      None => format!("{} ({})", name, self.origin.describe()),
+    };
+    match self.elided {
+      Some(Elided::Duplicate) | Some(Elided::Depth) => label.push_str(" …"),
+      Some(Elided::Cycle) => label.push_str(" (cycle)"),
+      None => {}
+    }
+    label
+  }
+}
+
+/// The name of a module as `deno info` prints it. A data: URL holds the whole
+/// module, only its start is shown.
+fn display_name(name: &str) -> String {
+  const DATA_URL_SHOWN: usize = 48;
+  if deno_dir::module_origin(name) != ModuleOrigin::Data
+    || name.chars().count() <= DATA_URL_SHOWN
+  {
+    return name.to_string();
+  }
+  let start: String = name.chars().take(DATA_URL_SHOWN).collect();
+  start + "…"
+}
+
+/// The state of building a dependency tree.
+struct Tree<'a> {
+  modules: &'a Modules,
+  max_depth: Option<usize>,
+  /// The modules whose dependencies are listed somewhere in the tree.
+  seen: HashSet<deno_mod>,
+  /// The path from the root to the module being expanded.
+  ancestors: Vec<deno_mod>,
+}
+
+impl<'a> Tree<'a> {
+  fn helper(&mut self, prefix: String, is_last: bool, id: deno_mod) -> Deps {
+    let modules = self.modules;
+    let name = modules.get_name(id).unwrap().to_string();
+    let size = modules.get_size(id);
+    let origin = deno_dir::module_origin(&name);
+    let child_ids = modules.get_children(id).unwrap();
+    let elided = if self.ancestors.contains(&id) {
+      Some(Elided::Cycle)
+    } else if child_ids.is_empty() {
+      None
+    } else if self.seen.contains(&id) {
+      Some(Elided::Duplicate)
+    } else if Some(self.ancestors.len()) == self.max_depth {
+      Some(Elided::Depth)
     } else {
-      seen.insert(id);
-      let child_ids = modules.get_children(id).unwrap();
-      let child_count = child_ids.iter().count();
-      let deps = child_ids
-        .iter()
-        .enumerate()
-        .map(|(index, dep_id)| {
-          let new_is_last = index == child_count - 1;
-          let mut new_prefix = prefix.clone();
-          new_prefix.push(if is_last { ' ' } else { '│' });
-          new_prefix.push(' ');
-          Self::helper(seen, new_prefix, new_is_last, modules, *dep_id)
-        }).collect();
-      Deps {
+      None
+    };
+    if elided.is_some() {
+      return Deps {
         name,
+        size,
+        origin,
         prefix,
-        deps: Some(deps),
+        deps: None,
+        elided,
         is_last,
-      }
+      };
+    }
+
+    self.seen.insert(id);
+    self.ancestors.push(id);
+    let child_count = child_ids.len();
+    let deps = child_ids
+      .iter()
+      .enumerate()
+      .map(|(index, dep_id)| {
+        let new_is_last = index == child_count - 1;
+        let mut new_prefix = prefix.clone();
+        new_prefix.push(if is_last { ' ' } else { '│' });
+        new_prefix.push(' ');
+        self.helper(new_prefix, new_is_last, *dep_id)
+      }).collect();
+    self.ancestors.pop();
+    Deps {
+      name,
+      size,
+      origin,
+      prefix,
+      deps: Some(deps),
+      elided: None,
+      is_last,
     }
   }
 }
@@ -250,7 +424,7 @@ impl fmt::Display for Deps {
       self.prefix,
       if self.is_last { "└" } else { "├" },
       if has_children { "┬" } else { "─" },
-      self.name
+      self.label()
     )?;
 
     if let Some(ref deps) = self.deps {
@@ -21,6 +21,7 @@ impl<'a> From<&'a state::Metrics> for MetricsResArgs {
       bytes_sent_control: m.bytes_sent_control.load(Ordering::SeqCst) as u64,
       bytes_sent_data: m.bytes_sent_data.load(Ordering::SeqCst) as u64,
       bytes_received: m.bytes_received.load(Ordering::SeqCst) as u64,
+      ..Default::default()
     }
   }
 }
@@ -3,11 +3,13 @@
 use crate::errors;
 use crate::errors::DenoResult;
 use crate::msg;
+use crate::performance::PerformanceEntry;
 
 use flatbuffers;
 use http::header::HeaderName;
 use http::uri::Uri;
 use http::Method;
+use http::StatusCode;
 use hyper::header::HeaderMap;
 use hyper::header::HeaderValue;
 use hyper::Body;
@@ -33,6 +35,23 @@ pub fn serialize_key_value<'bldr>(
   )
 }
 
+pub fn serialize_performance_entry<'bldr>(
+  builder: &mut flatbuffers::FlatBufferBuilder<'bldr>,
+  entry: &PerformanceEntry,
+) -> flatbuffers::WIPOffset<msg::PerformanceEntry<'bldr>> {
+  let name = builder.create_string(&entry.name);
+  let entry_type = builder.create_string(entry.entry_type.as_str());
+  msg::PerformanceEntry::create(
+    builder,
+    &msg::PerformanceEntryArgs {
+      name: Some(name),
+      entry_type: Some(entry_type),
+      start_time: entry.start_time,
+      duration: entry.duration,
+    },
+  )
+}
+
 pub fn serialize_request_header<'bldr>(
   builder: &mut flatbuffers::FlatBufferBuilder<'bldr>,
   r: &Request<Body>,
@@ -40,12 +59,7 @@ pub fn serialize_request_header<'bldr>(
   let method = builder.create_string(r.method().as_str());
   let url = builder.create_string(r.uri().to_string().as_ref());
 
-  let mut fields = Vec::new();
-  for (key, val) in r.headers().iter() {
-    let kv = serialize_key_value(builder, key.as_ref(), val.to_str().unwrap());
-    fields.push(kv);
-  }
-  let fields = builder.create_vector(fields.as_ref());
+  let fields = serialize_fields(builder, r.headers());
 
   msg::HttpHeader::create(
     builder,
@@ -70,7 +84,9 @@ pub fn serialize_fields<'bldr>(
 > {
   let mut fields = Vec::new();
   for (key, val) in headers.iter() {
-    let kv = serialize_key_value(builder, key.as_ref(), val.to_str().unwrap());
+    // Values which are not ASCII, as clients may send, are not dropped.
+    let val = String::from_utf8_lossy(val.as_bytes());
+    let kv = serialize_key_value(builder, key.as_ref(), &val);
     fields.push(kv);
   }
   builder.create_vector(fields.as_ref())
@@ -94,6 +110,41 @@ pub fn serialize_http_response<'bldr>(
   )
 }
 
+/// The header names and values come from the program, so they are checked,
+/// unlike the ones of a request.
+pub fn deserialize_response(
+  header_msg: msg::HttpHeader<'_>,
+  body: Body,
+) -> DenoResult<Response<Body>> {
+  let mut r = Response::new(body);
+
+  assert!(!header_msg.is_request());
+
+  let status = header_msg.status();
+  *r.status_mut() = StatusCode::from_u16(status)
+    .map_err(|_| invalid_input(format!("Invalid status code {}", status)))?;
+
+  if let Some(fields) = header_msg.fields() {
+    let headers = r.headers_mut();
+    for i in 0..fields.len() {
+      let kv = fields.get(i);
+      let key = kv.key().unwrap();
+      let name = HeaderName::from_bytes(key.as_bytes())
+        .map_err(|_| invalid_input(format!("Invalid header name {}", key)))?;
+      let value = kv.value().unwrap();
+      let v = HeaderValue::from_str(value).map_err(|_| {
+        invalid_input(format!("Invalid value of header {}", key))
+      })?;
+      headers.append(name, v);
+    }
+  }
+  Ok(r)
+}
+
+fn invalid_input(message: String) -> errors::DenoError {
+  errors::new(msg::ErrorKind::InvalidInput, message)
+}
+
 pub fn deserialize_request(
   header_msg: msg::HttpHeader<'_>,
   body: Body,
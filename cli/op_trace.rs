@@ -0,0 +1,151 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+
+//! `--trace-ops` prints a line to stderr for every op once it completed, like
+//! strace does for system calls:
+//!
+//!   trace-ops: Read async 153us sent=56+65536 received=48
+//!   trace-ops: Open async 41us sent=80+0 received=40 error=PermissionDenied
+//!
+//! That is the name of the op, whether JS waited for it, how long it took from
+//! its dispatch, the bytes of the control message and of the zero copy buffer,
+//! and the bytes of the response. Without the flag only the flag is checked.
+
+use crate::flags::DenoFlags;
+use crate::msg;
+use crate::msg::ErrorKind;
+use std::time::Duration;
+use std::time::Instant;
+
+pub struct OpTrace {
+  name: &'static str,
+  is_sync: bool,
+  bytes_sent_control: usize,
+  bytes_sent_zero_copy: usize,
+  start: Instant,
+}
+
+impl OpTrace {
+  /// Starts to trace the op if `--trace-ops` selects it.
+  pub fn start(
+    flags: &DenoFlags,
+    inner_type: msg::Any,
+    is_sync: bool,
+    bytes_sent_control: usize,
+    bytes_sent_zero_copy: usize,
+  ) -> Option<Self> {
+    let filter = flags.trace_ops.as_ref()?;
+    let name = msg::enum_name_any(inner_type);
+    if !is_traced(filter, name) {
+      return None;
+    }
+    Some(OpTrace {
+      name,
+      is_sync,
+      bytes_sent_control,
+      bytes_sent_zero_copy,
+      start: Instant::now(),
+    })
+  }
+
+  pub fn finish(self, bytes_received: usize, error: Option<ErrorKind>) {
+    eprintln!(
+      "{}",
+      format_line(
+        self.name,
+        self.is_sync,
+        self.start.elapsed(),
+        self.bytes_sent_control,
+        self.bytes_sent_zero_copy,
+        bytes_received,
+        error,
+      )
+    );
+  }
+}
+
+/// Without names every op is traced, otherwise those named, ignoring case.
+fn is_traced(filter: &[String], name: &str) -> bool {
+  filter.is_empty() || filter.iter().any(|f| f.eq_ignore_ascii_case(name))
+}
+
+fn format_line(
+  name: &str,
+  is_sync: bool,
+  elapsed: Duration,
+  bytes_sent_control: usize,
+  bytes_sent_zero_copy: usize,
+  bytes_received: usize,
+  error: Option<ErrorKind>,
+) -> String {
+  let micros =
+    elapsed.as_secs() * 1_000_000 + u64::from(elapsed.subsec_micros());
+  let mut line = format!(
+    "trace-ops: {} {} {}us sent={}+{} received={}",
+    name,
+    if is_sync { "sync" } else { "async" },
+    micros,
+    bytes_sent_control,
+    bytes_sent_zero_copy,
+    bytes_received,
+  );
+  if let Some(kind) = error {
+    line.push_str(&format!(" error={}", msg::enum_name_error_kind(kind)));
+  }
+  line
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn filter() {
+    assert!(is_traced(&[], "Read"));
+    let filter = vec!["read".to_string(), "fetch".to_string()];
+    assert!(is_traced(&filter, "Read"));
+    assert!(is_traced(&filter, "Fetch"));
+    assert!(!is_traced(&filter, "Write"));
+    assert!(!is_traced(&filter, "ReadDir"));
+  }
+
+  #[test]
+  fn line() {
+    assert_eq!(
+      format_line(
+        "Read",
+        false,
+        Duration::from_micros(1500),
+        56,
+        1024,
+        48,
+        None
+      ),
+      "trace-ops: Read async 1500us sent=56+1024 received=48"
+    );
+    assert_eq!(
+      format_line(
+        "Open",
+        true,
+        Duration::from_secs(2),
+        80,
+        0,
+        40,
+        Some(ErrorKind::PermissionDenied)
+      ),
+      "trace-ops: Open sync 2000000us sent=80+0 received=40 \
+       error=PermissionDenied"
+    );
+  }
+
+  #[test]
+  fn disabled() {
+    let flags = DenoFlags::default();
+    assert!(OpTrace::start(&flags, msg::Any::Read, false, 56, 0).is_none());
+    let flags = DenoFlags {
+      trace_ops: Some(vec!["write".to_string()]),
+      ..DenoFlags::default()
+    };
+    assert!(OpTrace::start(&flags, msg::Any::Read, false, 56, 0).is_none());
+    assert!(OpTrace::start(&flags, msg::Any::Write, false, 56, 0).is_some());
+  }
+}
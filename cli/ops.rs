@@ -3,22 +3,39 @@ use atty;
 use crate::ansi;
 use crate::errors;
 use crate::errors::{DenoError, DenoResult, ErrorKind};
+use crate::flags::LogLevel;
 use crate::fs as deno_fs;
+use crate::fs_events;
+use crate::http_server;
+use crate::http_server::HttpServer;
 use crate::http_util;
+use crate::http_util::FetchBody;
 use crate::js_errors::apply_source_map;
+use crate::js_errors::apply_source_map_position;
 use crate::js_errors::JSErrorColor;
 use crate::msg;
 use crate::msg_util;
+use crate::op_trace::OpTrace;
+use crate::performance;
+use crate::performance::PerformanceEntry;
+use crate::permissions::PermissionState;
+use crate::permissions::WorkerPermissions;
 use crate::repl;
 use crate::resolve_addr::resolve_addr;
 use crate::resources;
 use crate::resources::table_entries;
 use crate::resources::Resource;
+use crate::signal;
 use crate::startup_data;
 use crate::state::ThreadSafeState;
+use crate::state::UserWorker;
+use crate::timers::DueTimers;
+use crate::tls;
 use crate::tokio_util;
 use crate::tokio_write;
 use crate::version;
+use crate::websocket;
+use crate::websocket::Message;
 use crate::worker::Worker;
 use deno::deno_buf;
 use deno::js_check;
@@ -33,25 +50,38 @@ use futures::Sink;
 use futures::Stream;
 use hyper;
 use hyper::rt::Future;
+use net2::TcpBuilder;
 use remove_dir_all::remove_dir_all;
 use std;
 use std::convert::From;
 use std::fs;
+use std::net::IpAddr;
 use std::net::Shutdown;
+use std::net::SocketAddr;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
-use std::time::{Duration, Instant, UNIX_EPOCH};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
 use tokio;
 use tokio::net::TcpListener;
 use tokio::net::TcpStream;
+use tokio::net::UdpSocket;
+use tokio::timer::Timeout;
 use tokio_process::CommandExt;
+use tokio_rustls::client::TlsStream as ClientTlsStream;
+use tokio_rustls::server::TlsStream as ServerTlsStream;
 use tokio_threadpool;
 
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 #[cfg(unix)]
 use std::os::unix::process::ExitStatusExt;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+#[cfg(unix)]
+use tokio::net::UnixStream;
 
 type OpResult = DenoResult<Buf>;
 
@@ -92,19 +122,35 @@ pub fn dispatch_all(
     None => panic!("Unhandled message {}", msg::enum_name_any(inner_type)),
   };
 
+  // Started before the op is created, since some ops do their work then.
+  let trace = OpTrace::start(
+    &state.flags,
+    inner_type,
+    is_sync,
+    bytes_sent_control,
+    bytes_sent_zero_copy,
+  );
+
   let op: Box<OpWithError> = op_func(state, &base, zero_copy);
+  let op = if is_sync {
+    op
+  } else {
+    state.cancellable(cmd_id, op)
+  };
 
   let state = state.clone();
   state.metrics_op_dispatched(bytes_sent_control, bytes_sent_zero_copy);
 
+  // The kind of the error, if any, is kept for the trace.
+  let op = op.map(|buf| -> (Buf, Option<ErrorKind>) { (buf, None) });
   let boxed_op = Box::new(
-    op.or_else(move |err: DenoError| -> Result<Buf, ()> {
+    op.or_else(move |err: DenoError| -> Result<_, ()> {
       debug!("op err {}", err);
       // No matter whether we got an Err or Ok, we want a serialized message to
       // send back. So transform the DenoError into a deno_buf.
       let builder = &mut FlatBufferBuilder::new();
       let errmsg_offset = builder.create_string(&format!("{}", err));
-      Ok(serialize_response(
+      let buf = serialize_response(
         cmd_id,
         builder,
         msg::BaseArgs {
@@ -112,8 +158,9 @@ pub fn dispatch_all(
           error_kind: err.kind(),
           ..Default::default()
         },
-      ))
-    }).and_then(move |buf: Buf| -> Result<Buf, ()> {
+      );
+      Ok((buf, Some(err.kind())))
+    }).and_then(move |(buf, error_kind)| -> Result<Buf, ()> {
       // Handle empty responses. For sync responses we just want
       // to send null. For async we want to send a small message
       // with the cmd_id.
@@ -130,6 +177,9 @@ pub fn dispatch_all(
         )
       };
       state.metrics_op_completed(buf.len());
+      if let Some(trace) = trace {
+        trace.finish(buf.len(), error_kind);
+      }
       Ok(buf)
     }).map_err(|err| panic!("unexpected error {:?}", err)),
   );
@@ -157,26 +207,47 @@ pub fn op_selector_compiler(inner_type: msg::Any) -> Option<OpCreator> {
 pub fn op_selector_std(inner_type: msg::Any) -> Option<OpCreator> {
   match inner_type {
     msg::Any::Accept => Some(op_accept),
+    msg::Any::ApplySourceMap => Some(op_apply_source_map),
+    msg::Any::Batch => Some(op_batch),
+    msg::Any::Cancel => Some(op_cancel),
     msg::Any::Chdir => Some(op_chdir),
     msg::Any::Chmod => Some(op_chmod),
+    msg::Any::Chown => Some(op_chown),
     msg::Any::Close => Some(op_close),
     msg::Any::CopyFile => Some(op_copy_file),
     msg::Any::Cwd => Some(op_cwd),
+    msg::Any::DatagramListen => Some(op_datagram_listen),
+    msg::Any::DatagramMulticast => Some(op_datagram_multicast),
+    msg::Any::DatagramReceive => Some(op_datagram_receive),
+    msg::Any::DatagramSend => Some(op_datagram_send),
+    msg::Any::DeleteEnv => Some(op_delete_env),
     msg::Any::Dial => Some(op_dial),
+    msg::Any::DialTls => Some(op_dial_tls),
     msg::Any::Environ => Some(op_env),
     msg::Any::Exit => Some(op_exit),
     msg::Any::Fetch => Some(op_fetch),
     msg::Any::FormatError => Some(op_format_error),
-    msg::Any::GlobalTimer => Some(op_global_timer),
-    msg::Any::GlobalTimerStop => Some(op_global_timer_stop),
+    msg::Any::FsEventsOpen => Some(op_fs_events_open),
+    msg::Any::FsEventsPoll => Some(op_fs_events_poll),
+    msg::Any::HttpAccept => Some(op_http_accept),
+    msg::Any::HttpRespond => Some(op_http_respond),
+    msg::Any::HttpServe => Some(op_http_serve),
     msg::Any::IsTTY => Some(op_is_tty),
+    msg::Any::Kill => Some(op_kill),
     msg::Any::Link => Some(op_link),
     msg::Any::Listen => Some(op_listen),
+    msg::Any::ListenTls => Some(op_listen_tls),
     msg::Any::MakeTempDir => Some(op_make_temp_dir),
+    msg::Any::MakeTempFile => Some(op_make_temp_file),
     msg::Any::Metrics => Some(op_metrics),
     msg::Any::Mkdir => Some(op_mkdir),
     msg::Any::Now => Some(op_now),
     msg::Any::Open => Some(op_open),
+    msg::Any::PerformanceClear => Some(op_performance_clear),
+    msg::Any::PerformanceGetEntries => Some(op_performance_get_entries),
+    msg::Any::PerformanceMark => Some(op_performance_mark),
+    msg::Any::PerformanceMeasure => Some(op_performance_measure),
+    msg::Any::PermissionQuery => Some(op_query_permission),
     msg::Any::PermissionRevoke => Some(op_revoke_permission),
     msg::Any::Permissions => Some(op_permissions),
     msg::Any::Read => Some(op_read),
@@ -192,14 +263,26 @@ pub fn op_selector_std(inner_type: msg::Any) -> Option<OpCreator> {
     msg::Any::Seek => Some(op_seek),
     msg::Any::SetEnv => Some(op_set_env),
     msg::Any::Shutdown => Some(op_shutdown),
+    msg::Any::SignalBind => Some(op_signal_bind),
+    msg::Any::SignalPoll => Some(op_signal_poll),
     msg::Any::Start => Some(op_start),
     msg::Any::Stat => Some(op_stat),
     msg::Any::Symlink => Some(op_symlink),
+    msg::Any::TimerStart => Some(op_timer_start),
+    msg::Any::TimersPoll => Some(op_timers_poll),
     msg::Any::Truncate => Some(op_truncate),
+    msg::Any::Umask => Some(op_umask),
+    msg::Any::UnixDial => Some(op_unix_dial),
+    msg::Any::UnixListen => Some(op_unix_listen),
+    msg::Any::WebSocketClose => Some(op_web_socket_close),
+    msg::Any::WebSocketDial => Some(op_web_socket_dial),
+    msg::Any::WebSocketReceive => Some(op_web_socket_receive),
+    msg::Any::WebSocketSend => Some(op_web_socket_send),
     msg::Any::CreateWorker => Some(op_create_worker),
     msg::Any::HostGetWorkerClosed => Some(op_host_get_worker_closed),
     msg::Any::HostGetMessage => Some(op_host_get_message),
     msg::Any::HostPostMessage => Some(op_host_post_message),
+    msg::Any::HostTerminateWorker => Some(op_host_terminate_worker),
     msg::Any::Write => Some(op_write),
 
     // TODO(ry) split these out so that only the appropriate Workers can access
@@ -221,23 +304,17 @@ fn op_now(
   data: deno_buf,
 ) -> Box<OpWithError> {
   assert_eq!(data.len(), 0);
-  let seconds = state.start_time.elapsed().as_secs();
-  let mut subsec_nanos = state.start_time.elapsed().subsec_nanos();
-  let reduced_time_precision = 2_000_000; // 2ms in nanoseconds
-
-  // If the permission is not enabled
-  // Round the nano result on 2 milliseconds
-  // see: https://developer.mozilla.org/en-US/docs/Web/API/DOMHighResTimeStamp#Reduced_time_precision
-  if !state.permissions.allows_high_precision() {
-    subsec_nanos -= subsec_nanos % reduced_time_precision
-  }
+  // Coarsened without the permission, see performance.rs.
+  let now = state
+    .performance
+    .now(state.permissions.allows_high_precision());
 
   let builder = &mut FlatBufferBuilder::new();
   let inner = msg::NowRes::create(
     builder,
     &msg::NowResArgs {
-      seconds,
-      subsec_nanos,
+      seconds: now.as_secs(),
+      subsec_nanos: now.subsec_nanos(),
     },
   );
   ok_future(serialize_response(
@@ -251,6 +328,122 @@ fn op_now(
   ))
 }
 
+fn performance_entry_res(cmd_id: u32, entry: &PerformanceEntry) -> Buf {
+  let builder = &mut FlatBufferBuilder::new();
+  let entry = msg_util::serialize_performance_entry(builder, entry);
+  let inner = msg::PerformanceEntryRes::create(
+    builder,
+    &msg::PerformanceEntryResArgs { entry: Some(entry) },
+  );
+  serialize_response(
+    cmd_id,
+    builder,
+    msg::BaseArgs {
+      inner: Some(inner.as_union_value()),
+      inner_type: msg::Any::PerformanceEntryRes,
+      ..Default::default()
+    },
+  )
+}
+
+fn parse_entry_type(entry_type: &str) -> DenoResult<performance::EntryType> {
+  performance::EntryType::parse(entry_type).ok_or_else(|| {
+    errors::new(
+      ErrorKind::InvalidInput,
+      format!("Invalid performance entry type \"{}\"", entry_type),
+    )
+  })
+}
+
+fn op_performance_mark(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let inner = base.inner_as_performance_mark().unwrap();
+  let name = inner.name().unwrap();
+  let entry = state
+    .performance
+    .mark(name, state.permissions.allows_high_precision());
+  ok_future(performance_entry_res(base.cmd_id(), &entry))
+}
+
+fn op_performance_measure(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_performance_measure().unwrap();
+  let name = inner.name().unwrap();
+  let result = state.performance.measure(
+    name,
+    inner.start_mark(),
+    inner.end_mark(),
+    state.permissions.allows_high_precision(),
+  );
+  Box::new(futures::future::result(
+    result.map(|entry| performance_entry_res(cmd_id, &entry)),
+  ))
+}
+
+fn op_performance_get_entries(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_performance_get_entries().unwrap();
+  let entry_type = match inner.entry_type().map(parse_entry_type) {
+    None => None,
+    Some(Ok(entry_type)) => Some(entry_type),
+    Some(Err(err)) => return odd_future(err),
+  };
+
+  let builder = &mut FlatBufferBuilder::new();
+  let entries: Vec<_> = state
+    .performance
+    .entries(entry_type, inner.name())
+    .iter()
+    .map(|entry| msg_util::serialize_performance_entry(builder, entry))
+    .collect();
+  let entries = builder.create_vector(&entries);
+  let inner = msg::PerformanceGetEntriesRes::create(
+    builder,
+    &msg::PerformanceGetEntriesResArgs {
+      entries: Some(entries),
+    },
+  );
+  ok_future(serialize_response(
+    cmd_id,
+    builder,
+    msg::BaseArgs {
+      inner: Some(inner.as_union_value()),
+      inner_type: msg::Any::PerformanceGetEntriesRes,
+      ..Default::default()
+    },
+  ))
+}
+
+fn op_performance_clear(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let inner = base.inner_as_performance_clear().unwrap();
+  match parse_entry_type(inner.entry_type().unwrap()) {
+    Ok(entry_type) => {
+      state.performance.clear(entry_type, inner.name());
+      ok_future(empty_buf())
+    }
+    Err(err) => odd_future(err),
+  }
+}
+
 fn op_is_tty(
   _state: &ThreadSafeState,
   base: &msg::Base<'_>,
@@ -319,12 +512,20 @@ fn op_start(
       pid: std::process::id(),
       argv: Some(argv_off),
       main_module,
-      debug_flag: state.flags.log_debug,
-      types_flag: state.flags.types,
+      debug_flag: state.flags.log_level == LogLevel::Debug,
+      quiet_flag: state.flags.quiet,
+      log_level: match state.flags.log_level {
+        LogLevel::Debug => msg::LogLevel::Debug,
+        LogLevel::Info => msg::LogLevel::Info,
+        LogLevel::Warn => msg::LogLevel::Warn,
+        LogLevel::Error => msg::LogLevel::Error,
+      },
       version_flag: state.flags.version,
+      version_json_flag: state.flags.version_json,
       v8_version: Some(v8_version_off),
       deno_version: Some(deno_version_off),
-      no_color: !ansi::use_color(),
+      // The runtime mostly prints to stdout.
+      no_color: !ansi::use_color_on(atty::Stream::Stdout),
       exec_path: Some(exec_path),
       ..Default::default()
     },
@@ -341,6 +542,43 @@ fn op_start(
   ))
 }
 
+fn op_apply_source_map(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let inner = base.inner_as_apply_source_map().unwrap();
+  let cmd_id = base.cmd_id();
+  let filename = inner.filename().unwrap();
+  let line = inner.line();
+  let column = inner.column();
+
+  let (orig_filename, orig_line, orig_column) = apply_source_map_position(
+    filename,
+    i64::from(line),
+    i64::from(column),
+    &state.dir,
+  );
+
+  let builder = &mut FlatBufferBuilder::new();
+  let msg_args = msg::ApplySourceMapArgs {
+    filename: Some(builder.create_string(&orig_filename)),
+    line: orig_line as i32,
+    column: orig_column as i32,
+  };
+  let res_inner = msg::ApplySourceMap::create(builder, &msg_args);
+  ok_future(serialize_response(
+    cmd_id,
+    builder,
+    msg::BaseArgs {
+      inner: Some(res_inner.as_union_value()),
+      inner_type: msg::Any::ApplySourceMap,
+      ..Default::default()
+    },
+  ))
+}
+
 fn op_format_error(
   state: &ThreadSafeState,
   base: &msg::Base<'_>,
@@ -351,7 +589,11 @@ fn op_format_error(
   let orig_error = String::from(inner.error().unwrap());
 
   let js_error = JSError::from_v8_exception(&orig_error).unwrap();
-  let js_error_mapped = apply_source_map(&js_error, &state.dir);
+  let js_error_mapped = apply_source_map(
+    &js_error,
+    &state.dir,
+    state.flags.full_stack_traces,
+  );
   let js_error_string = JSErrorColor(&js_error_mapped).to_string();
 
   let mut builder = FlatBufferBuilder::new();
@@ -413,7 +655,7 @@ fn op_fetch_module_meta_data(
 
   assert_eq!(state.dir.root.join("gen"), state.dir.gen, "Sanity check");
 
-  let use_cache = !state.flags.reload;
+  let use_cache = state.use_cache(specifier, referrer);
 
   Box::new(futures::future::result(|| -> OpResult {
     let builder = &mut FlatBufferBuilder::new();
@@ -421,12 +663,20 @@ fn op_fetch_module_meta_data(
     let out = state
       .dir
       .fetch_module_meta_data(specifier, referrer, use_cache)?;
-    let data_off = builder.create_vector(out.source_code.as_slice());
+    // The compiler sees WebAssembly modules as the JS module which loads them.
+    let source_code = if out.media_type == msg::MediaType::Wasm {
+      out.js_source().into_bytes()
+    } else {
+      out.source_code
+    };
+    let data_off = builder.create_vector(source_code.as_slice());
+    let types_url = out.maybe_types.map(|url| builder.create_string(&url));
     let msg_args = msg::FetchModuleMetaDataResArgs {
       module_name: Some(builder.create_string(&out.module_name)),
       filename: Some(builder.create_string(&out.filename)),
       media_type: out.media_type,
       data: Some(data_off),
+      types_url,
     };
     let inner = msg::FetchModuleMetaDataRes::create(builder, &msg_args);
     Ok(serialize_response(
@@ -455,20 +705,37 @@ fn op_chdir(
   }()))
 }
 
-fn op_global_timer_stop(
+fn op_timer_start(
   state: &ThreadSafeState,
   base: &msg::Base<'_>,
   data: deno_buf,
 ) -> Box<OpWithError> {
   assert!(base.sync());
   assert_eq!(data.len(), 0);
-  let state = state;
-  let mut t = state.global_timer.lock().unwrap();
-  t.cancel();
+  let inner = base.inner_as_timer_start().unwrap();
+  let delay = Duration::from_millis(u64::from(inner.delay()));
+  let mut timers = state.timers.lock().unwrap();
+  timers.start(base.cmd_id(), delay, inner.repeat());
+  ok_future(empty_buf())
+}
+
+/// An op which is done, or was never sent, is left alone, like a timer
+/// which fired.
+fn op_cancel(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let inner = base.inner_as_cancel().unwrap();
+  let cmd_id = inner.cmd_id();
+  if !state.cancel_op(cmd_id) {
+    state.timers.lock().unwrap().cancel(cmd_id);
+  }
   ok_future(empty_buf())
 }
 
-fn op_global_timer(
+fn op_timers_poll(
   state: &ThreadSafeState,
   base: &msg::Base<'_>,
   data: deno_buf,
@@ -476,31 +743,46 @@ fn op_global_timer(
   assert!(!base.sync());
   assert_eq!(data.len(), 0);
   let cmd_id = base.cmd_id();
-  let inner = base.inner_as_global_timer().unwrap();
-  let val = inner.timeout();
-  assert!(val >= 0);
-
-  let state = state;
-  let mut t = state.global_timer.lock().unwrap();
-  let deadline = Instant::now() + Duration::from_millis(val as u64);
-  let f = t.new_timeout(deadline);
+  let due = DueTimers::new(state.timers.clone());
 
-  Box::new(f.then(move |_| {
+  Box::new(due.map_err(|()| unreachable!()).map(move |ids| {
     let builder = &mut FlatBufferBuilder::new();
-    let inner =
-      msg::GlobalTimerRes::create(builder, &msg::GlobalTimerResArgs {});
-    Ok(serialize_response(
+    let ids = builder.create_vector(&ids);
+    let inner = msg::TimersPollRes::create(
+      builder,
+      &msg::TimersPollResArgs { ids: Some(ids) },
+    );
+    serialize_response(
       cmd_id,
       builder,
       msg::BaseArgs {
         inner: Some(inner.as_union_value()),
-        inner_type: msg::Any::GlobalTimerRes,
+        inner_type: msg::Any::TimersPollRes,
         ..Default::default()
       },
-    ))
+    )
   }))
 }
 
+lazy_static! {
+  /// Held while the environment is read or written. Changing it while another
+  /// thread reads it, as spawning a subprocess does, is unsound on unix.
+  static ref ENV_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// `std::env::set_var()` panics on these, instead of returning an error.
+fn check_env_key(key: &str) -> DenoResult<()> {
+  if key.is_empty() || key.contains('=') || key.contains('\0') {
+    return Err(errors::new(
+      ErrorKind::InvalidInput,
+      format!("Invalid environment variable name \"{}\"", key),
+    ));
+  }
+  Ok(())
+}
+
+/// An empty `value` sets the variable to the empty string on every platform,
+/// it is not deleted as by `set FOO=` in a Windows shell.
 fn op_set_env(
   state: &ThreadSafeState,
   base: &msg::Base<'_>,
@@ -510,13 +792,44 @@ fn op_set_env(
   let inner = base.inner_as_set_env().unwrap();
   let key = inner.key().unwrap();
   let value = inner.value().unwrap();
-  if let Err(e) = state.check_env() {
+  if let Err(e) = state.check_env_var(key) {
+    return odd_future(e);
+  }
+  if let Err(e) = check_env_key(key) {
     return odd_future(e);
   }
+  if value.contains('\0') {
+    return odd_future(errors::new(
+      ErrorKind::InvalidInput,
+      format!("Invalid value of environment variable \"{}\"", key),
+    ));
+  }
+  let _guard = ENV_LOCK.lock().unwrap();
   std::env::set_var(key, value);
   ok_future(empty_buf())
 }
 
+/// Variable names are case-insensitive on Windows, deleting "foo" also
+/// deletes "FOO".
+fn op_delete_env(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let inner = base.inner_as_delete_env().unwrap();
+  let key = inner.key().unwrap();
+  if let Err(e) = state.check_env_var(key) {
+    return odd_future(e);
+  }
+  if let Err(e) = check_env_key(key) {
+    return odd_future(e);
+  }
+  let _guard = ENV_LOCK.lock().unwrap();
+  std::env::remove_var(key);
+  ok_future(empty_buf())
+}
+
 fn op_env(
   state: &ThreadSafeState,
   base: &msg::Base<'_>,
@@ -525,13 +838,22 @@ fn op_env(
   assert_eq!(data.len(), 0);
   let cmd_id = base.cmd_id();
 
-  if let Err(e) = state.check_env() {
-    return odd_future(e);
+  // With `--allow-env=<names>` only the whitelisted variables are returned,
+  // unless access to the whole environment is granted.
+  let whitelist_only = !state.permissions.allows_env()
+    && state.permissions.has_env_whitelist();
+  if !whitelist_only {
+    if let Err(e) = state.check_env() {
+      return odd_future(e);
+    }
   }
 
   let builder = &mut FlatBufferBuilder::new();
+  let _guard = ENV_LOCK.lock().unwrap();
   let vars: Vec<_> = std::env::vars()
-    .map(|(key, value)| msg_util::serialize_key_value(builder, &key, &value))
+    .filter(|(key, _)| {
+      !whitelist_only || state.permissions.is_env_whitelisted(key)
+    }).map(|(key, value)| msg_util::serialize_key_value(builder, &key, &value))
     .collect();
   let tables = builder.create_vector(&vars);
   let inner = msg::EnvironRes::create(
@@ -579,6 +901,55 @@ fn op_permissions(
   ))
 }
 
+/// Takes no permission itself, not even to query a path or a host.
+fn op_query_permission(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let inner = base.inner_as_permission_query().unwrap();
+  let cmd_id = base.cmd_id();
+  let name = inner.name().unwrap();
+  let resource = match (name, inner.resource()) {
+    ("read", Some(path)) | ("write", Some(path)) => {
+      match deno_fs::resolve_from_cwd(Path::new(path)) {
+        Ok(path) => Some(path.to_str().unwrap().to_owned()),
+        Err(e) => return odd_future(e.into()),
+      }
+    }
+    (_, resource) => resource.map(str::to_owned),
+  };
+  let resource = resource.as_ref().map(String::as_str);
+  let result = if inner.request() {
+    state.permissions.request(name, resource)
+  } else {
+    state.permissions.query(name, resource)
+  };
+  let permission_state = match result {
+    Ok(PermissionState::Granted) => msg::PermissionState::Granted,
+    Ok(PermissionState::Denied) => msg::PermissionState::Denied,
+    Ok(PermissionState::Prompt) => msg::PermissionState::Prompt,
+    Err(e) => return odd_future(e),
+  };
+  let builder = &mut FlatBufferBuilder::new();
+  let inner = msg::PermissionQueryRes::create(
+    builder,
+    &msg::PermissionQueryResArgs {
+      state: permission_state,
+    },
+  );
+  ok_future(serialize_response(
+    cmd_id,
+    builder,
+    msg::BaseArgs {
+      inner: Some(inner.as_union_value()),
+      inner_type: msg::Any::PermissionQueryRes,
+      ..Default::default()
+    },
+  ))
+}
+
 fn op_revoke_permission(
   state: &ThreadSafeState,
   base: &msg::Base<'_>,
@@ -614,132 +985,532 @@ fn op_fetch(
   assert!(header.is_request());
   let url = header.url().unwrap();
 
-  let body = if data.is_empty() {
-    hyper::Body::empty()
+  let body = if inner.stream_body() {
+    assert_eq!(data.len(), 0);
+    match resources::read_stream(inner.body_rid()) {
+      Ok(stream) => FetchBody::Stream(hyper::Body::wrap_stream(stream)),
+      Err(e) => return odd_future(e),
+    }
+  } else if data.is_empty() {
+    FetchBody::Empty
   } else {
-    hyper::Body::from(Vec::from(&*data))
+    FetchBody::Buffered(Vec::from(&*data))
   };
 
-  let maybe_req = msg_util::deserialize_request(header, body);
+  let maybe_req = msg_util::deserialize_request(header, hyper::Body::empty());
   if let Err(e) = maybe_req {
     return odd_future(e);
   }
-  let req = maybe_req.unwrap();
+  let (parts, _) = maybe_req.unwrap().into_parts();
 
-  if let Err(e) = state.check_net(url) {
+  let url_ = match url::Url::parse(url) {
+    Ok(url_) => url_,
+    Err(err) => return odd_future(DenoError::from(err)),
+  };
+  if let Err(e) = state.check_net_url(&url_) {
     return odd_future(e);
   }
 
-  let client = http_util::get_client();
+  let options = http_util::FetchOptions {
+    redirect: inner.redirect(),
+    max_redirects: match inner.max_redirects() {
+      n if n < 0 => http_util::DEFAULT_MAX_REDIRECTS,
+      n => n as u32,
+    },
+    cookies: inner.cookies(),
+  };
+  // The hosts redirected to have to be allowed as well.
+  let state_ = state.clone();
+  let check_url: http_util::CheckUrl =
+    Arc::new(move |url: &url::Url| state_.check_net_url(url));
 
   debug!("Before fetch {}", url);
-  let future =
-    client
-      .request(req)
-      .map_err(DenoError::from)
-      .and_then(move |res| {
-        let builder = &mut FlatBufferBuilder::new();
-        let header_off = msg_util::serialize_http_response(builder, &res);
-        let body = res.into_body();
-        let body_resource = resources::add_hyper_body(body);
-        let inner = msg::FetchRes::create(
-          builder,
-          &msg::FetchResArgs {
-            header: Some(header_off),
-            body_rid: body_resource.rid,
-          },
-        );
+  let fetch = http_util::fetch(
+    parts.method,
+    url_,
+    parts.headers,
+    body,
+    options,
+    check_url,
+  );
+  let future = with_timeout(fetch, inner.timeout(), "Request timed out")
+    .and_then(move |(res, url, redirected)| {
+      // Responds as soon as the headers are there, the body is read from
+      // its resource. Closing it aborts the connection.
+      let builder = &mut FlatBufferBuilder::new();
+      let header_off = msg_util::serialize_http_response(builder, &res);
+      let url = builder.create_string(url.as_str());
+      let body = res.into_body();
+      let body_resource = resources::add_hyper_body(body);
+      let inner = msg::FetchRes::create(
+        builder,
+        &msg::FetchResArgs {
+          header: Some(header_off),
+          body_rid: body_resource.rid,
+          url: Some(url),
+          redirected,
+        },
+      );
 
-        Ok(serialize_response(
-          cmd_id,
-          builder,
-          msg::BaseArgs {
-            inner: Some(inner.as_union_value()),
-            inner_type: msg::Any::FetchRes,
-            ..Default::default()
-          },
-        ))
-      });
+      Ok(serialize_response(
+        cmd_id,
+        builder,
+        msg::BaseArgs {
+          inner: Some(inner.as_union_value()),
+          inner_type: msg::Any::FetchRes,
+          ..Default::default()
+        },
+      ))
+    });
   Box::new(future)
 }
 
-// This is just type conversion. Implement From trait?
-// See https://github.com/tokio-rs/tokio/blob/ffd73a64e7ec497622b7f939e38017afe7124dc4/tokio-fs/src/lib.rs#L76-L85
-fn convert_blocking<F>(f: F) -> Poll<Buf, DenoError>
-where
-  F: FnOnce() -> DenoResult<Buf>,
-{
-  use futures::Async::*;
-  match tokio_threadpool::blocking(f) {
-    Ok(Ready(Ok(v))) => Ok(v.into()),
-    Ok(Ready(Err(err))) => Err(err),
-    Ok(NotReady) => Ok(NotReady),
-    Err(err) => panic!("blocking error {}", err),
-  }
-}
-
-fn blocking<F>(is_sync: bool, f: F) -> Box<OpWithError>
-where
-  F: 'static + Send + FnOnce() -> DenoResult<Buf>,
-{
-  if is_sync {
-    Box::new(futures::future::result(f()))
-  } else {
-    Box::new(tokio_util::poll_fn(move || convert_blocking(f)))
-  }
-}
-
-fn op_make_temp_dir(
-  state: &ThreadSafeState,
+fn op_http_serve(
+  _state: &ThreadSafeState,
   base: &msg::Base<'_>,
   data: deno_buf,
 ) -> Box<OpWithError> {
+  assert!(base.sync());
   assert_eq!(data.len(), 0);
-  let base = Box::new(*base);
-  let inner = base.inner_as_make_temp_dir().unwrap();
+  // Permission to serve is granted together with permission to listen.
   let cmd_id = base.cmd_id();
+  let inner = base.inner_as_http_serve().unwrap();
+  let listener_rid = inner.listener_rid();
 
-  // FIXME
-  if let Err(e) = state.check_write("make_temp") {
-    return odd_future(e);
-  }
-
-  let dir = inner.dir().map(PathBuf::from);
-  let prefix = inner.prefix().map(String::from);
-  let suffix = inner.suffix().map(String::from);
-
-  blocking(base.sync(), move || -> OpResult {
-    // TODO(piscisaureus): use byte vector for paths, not a string.
-    // See https://github.com/denoland/deno/issues/627.
-    // We can't assume that paths are always valid utf8 strings.
-    let path = deno_fs::make_temp_dir(
-      // Converting Option<String> to Option<&str>
-      dir.as_ref().map(|x| &**x),
-      prefix.as_ref().map(|x| &**x),
-      suffix.as_ref().map(|x| &**x),
-    )?;
+  Box::new(futures::future::result((move || {
+    let tls_acceptor = match resources::get_type(listener_rid) {
+      Some(ref repr) if repr == "tcpListener" => None,
+      Some(ref repr) if repr == "tlsListener" => {
+        Some(resources::tls_acceptor(listener_rid)?)
+      }
+      _ => return Err(errors::bad_resource()),
+    };
+    let listener = Resource { rid: listener_rid };
+    let server = HttpServer::new(listener, tls_acceptor);
+    let resource = resources::add_http_server(server);
     let builder = &mut FlatBufferBuilder::new();
-    let path_off = builder.create_string(path.to_str().unwrap());
-    let inner = msg::MakeTempDirRes::create(
+    let inner = msg::HttpServeRes::create(
       builder,
-      &msg::MakeTempDirResArgs {
-        path: Some(path_off),
-      },
+      &msg::HttpServeResArgs { rid: resource.rid },
     );
     Ok(serialize_response(
       cmd_id,
       builder,
       msg::BaseArgs {
         inner: Some(inner.as_union_value()),
-        inner_type: msg::Any::MakeTempDirRes,
+        inner_type: msg::Any::HttpServeRes,
         ..Default::default()
       },
     ))
-  })
+  })()))
 }
 
-fn op_mkdir(
-  state: &ThreadSafeState,
+fn op_http_accept(
+  _state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_http_accept().unwrap();
+  let rid = inner.rid();
+
+  let server = match resources::get_http_server(rid) {
+    Err(e) => return odd_future(e),
+    Ok(server) => server,
+  };
+  let future =
+    futures::future::poll_fn(move || server.lock().unwrap().poll_request());
+  let future = future.and_then(move |maybe_request| {
+    let builder = &mut FlatBufferBuilder::new();
+    let args = match maybe_request {
+      None => msg::HttpAcceptResArgs {
+        done: true,
+        ..Default::default()
+      },
+      Some(request) => {
+        let header =
+          msg_util::serialize_request_header(builder, &request.request);
+        let remote_addr =
+          builder.create_string(&request.remote_addr.to_string());
+        let body = resources::add_hyper_body(request.request.into_body());
+        let responder = resources::add_http_responder(request.responder);
+        msg::HttpAcceptResArgs {
+          done: false,
+          header: Some(header),
+          body_rid: body.rid,
+          responder_rid: responder.rid,
+          remote_addr: Some(remote_addr),
+        }
+      }
+    };
+    let inner = msg::HttpAcceptRes::create(builder, &args);
+    Ok(serialize_response(
+      cmd_id,
+      builder,
+      msg::BaseArgs {
+        inner: Some(inner.as_union_value()),
+        inner_type: msg::Any::HttpAcceptRes,
+        ..Default::default()
+      },
+    ))
+  });
+  Box::new(future)
+}
+
+fn op_http_respond(
+  _state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  let inner = base.inner_as_http_respond().unwrap();
+  let header = inner.header().unwrap();
+  assert!(!header.is_request());
+
+  // A streamed body is sent with chunked transfer encoding, unless there is
+  // a content-length header. The op is done once all of it was read.
+  let (body, finished) = if inner.stream_body() {
+    assert_eq!(data.len(), 0);
+    match resources::read_stream(inner.body_rid()) {
+      Ok(stream) => {
+        let (body, finished) = http_server::streamed_body(stream);
+        (body, Some(finished))
+      }
+      Err(e) => return odd_future(e),
+    }
+  } else {
+    (hyper::Body::from(Vec::from(&*data)), None)
+  };
+  let response = match msg_util::deserialize_response(header, body) {
+    Ok(response) => response,
+    Err(e) => return odd_future(e),
+  };
+  if let Err(e) = resources::respond(inner.responder_rid(), response) {
+    return odd_future(e);
+  }
+  match finished {
+    None => ok_future(empty_buf()),
+    Some(finished) => Box::new(finished.then(|_| Ok(empty_buf()))),
+  }
+}
+
+fn op_web_socket_dial(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_web_socket_dial().unwrap();
+  let url = inner.url().unwrap();
+  let protocols: Vec<String> = match inner.protocols() {
+    None => Vec::new(),
+    Some(protocols) => (0..protocols.len())
+      .map(|i| protocols.get(i).to_string())
+      .collect(),
+  };
+
+  let url_ = match url::Url::parse(url) {
+    Ok(url_) => url_,
+    Err(err) => return odd_future(DenoError::from(err)),
+  };
+  if let Err(e) = state.check_net_url(&url_) {
+    return odd_future(e);
+  }
+  let future = match websocket::dial(&url_, &protocols) {
+    Ok(future) => future,
+    Err(e) => return odd_future(e),
+  };
+  let future = future.and_then(move |(websocket, protocol)| {
+    let resource = resources::add_websocket(websocket);
+    let builder = &mut FlatBufferBuilder::new();
+    let protocol = builder.create_string(&protocol.unwrap_or_default());
+    let inner = msg::WebSocketDialRes::create(
+      builder,
+      &msg::WebSocketDialResArgs {
+        rid: resource.rid,
+        protocol: Some(protocol),
+      },
+    );
+    Ok(serialize_response(
+      cmd_id,
+      builder,
+      msg::BaseArgs {
+        inner: Some(inner.as_union_value()),
+        inner_type: msg::Any::WebSocketDialRes,
+        ..Default::default()
+      },
+    ))
+  });
+  Box::new(future)
+}
+
+fn op_web_socket_send(
+  _state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  let inner = base.inner_as_web_socket_send().unwrap();
+  let rid = inner.rid();
+  let data = Vec::from(&*data);
+  let message = match inner.kind() {
+    msg::WebSocketMessageKind::Text => match String::from_utf8(data) {
+      Ok(text) => Message::Text(text),
+      Err(e) => {
+        return odd_future(errors::new(ErrorKind::InvalidData, e.to_string()))
+      }
+    },
+    msg::WebSocketMessageKind::Binary => Message::Binary(data),
+    msg::WebSocketMessageKind::Ping => Message::Ping(data),
+    msg::WebSocketMessageKind::Pong => Message::Pong(data),
+    msg::WebSocketMessageKind::Close => Message::Close(
+      Some(inner.code()),
+      String::from_utf8_lossy(&data).into_owned(),
+    ),
+  };
+
+  let mut message = Some(message);
+  let future = resources::poll_websocket(rid, move |websocket| {
+    if let Some(message) = message.take() {
+      websocket.send(message)?;
+    }
+    websocket.poll_flush()
+  });
+  Box::new(future.map(|_| empty_buf()))
+}
+
+fn op_web_socket_receive(
+  _state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_web_socket_receive().unwrap();
+  let rid = inner.rid();
+
+  let future =
+    resources::poll_websocket(rid, |websocket| websocket.poll_message());
+  let future = future.and_then(move |maybe_message| {
+    let builder = &mut FlatBufferBuilder::new();
+    let (kind, code, data) = match maybe_message {
+      None => {
+        let inner = msg::WebSocketReceiveRes::create(
+          builder,
+          &msg::WebSocketReceiveResArgs {
+            done: true,
+            ..Default::default()
+          },
+        );
+        return Ok(serialize_response(
+          cmd_id,
+          builder,
+          msg::BaseArgs {
+            inner: Some(inner.as_union_value()),
+            inner_type: msg::Any::WebSocketReceiveRes,
+            ..Default::default()
+          },
+        ));
+      }
+      Some(Message::Text(text)) => {
+        (msg::WebSocketMessageKind::Text, 0, text.into_bytes())
+      }
+      Some(Message::Binary(data)) => {
+        (msg::WebSocketMessageKind::Binary, 0, data)
+      }
+      Some(Message::Ping(data)) => (msg::WebSocketMessageKind::Ping, 0, data),
+      Some(Message::Pong(data)) => (msg::WebSocketMessageKind::Pong, 0, data),
+      Some(Message::Close(code, reason)) => (
+        msg::WebSocketMessageKind::Close,
+        code.unwrap_or(websocket::NO_STATUS_RECEIVED),
+        reason.into_bytes(),
+      ),
+    };
+    let data = builder.create_vector(&data);
+    let inner = msg::WebSocketReceiveRes::create(
+      builder,
+      &msg::WebSocketReceiveResArgs {
+        done: false,
+        kind,
+        code,
+        data: Some(data),
+      },
+    );
+    Ok(serialize_response(
+      cmd_id,
+      builder,
+      msg::BaseArgs {
+        inner: Some(inner.as_union_value()),
+        inner_type: msg::Any::WebSocketReceiveRes,
+        ..Default::default()
+      },
+    ))
+  });
+  Box::new(future)
+}
+
+fn op_web_socket_close(
+  _state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let inner = base.inner_as_web_socket_close().unwrap();
+  let rid = inner.rid();
+  let code = inner.code();
+  let reason = inner.reason().unwrap_or_default().to_string();
+
+  // The close is sent once, the op waits for the reply of the server.
+  let mut closing = false;
+  let future = resources::poll_websocket(rid, move |websocket| {
+    if !closing {
+      closing = true;
+      websocket.close(code, &reason)?;
+    }
+    websocket.poll_close()
+  });
+  Box::new(future.map(|_| empty_buf()))
+}
+
+/// Fails `future` with `TimedOut` and `message` unless it is done within
+/// `timeout` milliseconds, 0 for no limit. It is dropped then, which aborts
+/// it.
+fn with_timeout<F>(
+  future: F,
+  timeout: u32,
+  message: &'static str,
+) -> Box<dyn Future<Item = F::Item, Error = DenoError> + Send>
+where
+  F: Future<Error = DenoError> + Send + 'static,
+  F::Item: Send + 'static,
+{
+  if timeout == 0 {
+    return Box::new(future);
+  }
+  let duration = Duration::from_millis(u64::from(timeout));
+  Box::new(Timeout::new(future, duration).map_err(move |e| {
+    if e.is_elapsed() {
+      errors::new(ErrorKind::TimedOut, message.to_string())
+    } else if e.is_inner() {
+      e.into_inner().unwrap()
+    } else {
+      errors::new(ErrorKind::Other, "The timer failed".to_string())
+    }
+  }))
+}
+
+// This is just type conversion. Implement From trait?
+// See https://github.com/tokio-rs/tokio/blob/ffd73a64e7ec497622b7f939e38017afe7124dc4/tokio-fs/src/lib.rs#L76-L85
+fn convert_blocking<F>(f: F) -> Poll<Buf, DenoError>
+where
+  F: FnOnce() -> DenoResult<Buf>,
+{
+  use futures::Async::*;
+  match tokio_threadpool::blocking(f) {
+    Ok(Ready(Ok(v))) => Ok(v.into()),
+    Ok(Ready(Err(err))) => Err(err),
+    Ok(NotReady) => Ok(NotReady),
+    Err(err) => panic!("blocking error {}", err),
+  }
+}
+
+fn blocking<F>(is_sync: bool, f: F) -> Box<OpWithError>
+where
+  F: 'static + Send + FnOnce() -> DenoResult<Buf>,
+{
+  if is_sync {
+    Box::new(futures::future::result(f()))
+  } else {
+    Box::new(tokio_util::poll_fn(move || convert_blocking(f)))
+  }
+}
+
+fn op_make_temp_dir(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let inner = base.inner_as_make_temp_dir().unwrap();
+  let dir = inner.dir().map(PathBuf::from);
+  let prefix = inner.prefix().map(String::from);
+  let suffix = inner.suffix().map(String::from);
+  make_temp(state, base, dir, prefix, suffix, false)
+}
+
+fn op_make_temp_file(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let inner = base.inner_as_make_temp_file().unwrap();
+  let dir = inner.dir().map(PathBuf::from);
+  let prefix = inner.prefix().map(String::from);
+  let suffix = inner.suffix().map(String::from);
+  make_temp(state, base, dir, prefix, suffix, true)
+}
+
+fn make_temp(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  dir: Option<PathBuf>,
+  prefix: Option<String>,
+  suffix: Option<String>,
+  is_file: bool,
+) -> Box<OpWithError> {
+  let cmd_id = base.cmd_id();
+
+  // The entry is created in the directory, which is what has to be writable.
+  let parent = dir.clone().unwrap_or_else(std::env::temp_dir);
+  if let Err(e) = state.check_write(&parent.to_string_lossy()) {
+    return odd_future(e);
+  }
+
+  blocking(base.sync(), move || -> OpResult {
+    // TODO(piscisaureus): use byte vector for paths, not a string.
+    // See https://github.com/denoland/deno/issues/627.
+    // We can't assume that paths are always valid utf8 strings.
+    let path = deno_fs::make_temp(
+      // Converting Option<String> to Option<&str>
+      dir.as_ref().map(|x| &**x),
+      prefix.as_ref().map(|x| &**x),
+      suffix.as_ref().map(|x| &**x),
+      is_file,
+    )?;
+    let builder = &mut FlatBufferBuilder::new();
+    let path_off = builder.create_string(path.to_str().unwrap());
+    let (inner, inner_type) = if is_file {
+      let inner = msg::MakeTempFileRes::create(
+        builder,
+        &msg::MakeTempFileResArgs {
+          path: Some(path_off),
+        },
+      );
+      (inner.as_union_value(), msg::Any::MakeTempFileRes)
+    } else {
+      let inner = msg::MakeTempDirRes::create(
+        builder,
+        &msg::MakeTempDirResArgs {
+          path: Some(path_off),
+        },
+      );
+      (inner.as_union_value(), msg::Any::MakeTempDirRes)
+    };
+    Ok(serialize_response(
+      cmd_id,
+      builder,
+      msg::BaseArgs {
+        inner: Some(inner),
+        inner_type,
+        ..Default::default()
+      },
+    ))
+  })
+}
+
+fn op_mkdir(
+  state: &ThreadSafeState,
   base: &msg::Base<'_>,
   data: deno_buf,
 ) -> Box<OpWithError> {
@@ -773,6 +1544,12 @@ fn op_chmod(
   if let Err(e) = state.check_write(&path) {
     return odd_future(e);
   }
+  if _mode > 0o7777 {
+    return odd_future(errors::new(
+      ErrorKind::InvalidInput,
+      format!("Invalid mode 0o{:o}, it must be at most 0o7777", _mode),
+    ));
+  }
 
   blocking(base.sync(), move || {
     debug!("op_chmod {}", &path);
@@ -799,6 +1576,71 @@ fn op_chmod(
   })
 }
 
+fn op_chown(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let inner = base.inner_as_chown().unwrap();
+  let path = String::from(inner.path().unwrap());
+  let uid = inner.uid();
+  let gid = inner.gid();
+
+  if let Err(e) = state.check_write(&path) {
+    return odd_future(e);
+  }
+
+  blocking(base.sync(), move || {
+    debug!("op_chown {} {} {}", &path, uid, gid);
+    deno_fs::chown(&path, uid, gid)?;
+    Ok(empty_buf())
+  })
+}
+
+fn op_umask(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_umask().unwrap();
+  let mask = if inner.has_mask() {
+    Some(inner.mask())
+  } else {
+    None
+  };
+
+  if let Err(e) = state.check_write_unscoped("access to the umask") {
+    return odd_future(e);
+  }
+  if let Some(m) = mask {
+    if m > 0o777 {
+      return odd_future(errors::new(
+        ErrorKind::InvalidInput,
+        format!("Invalid umask 0o{:o}, it must be at most 0o777", m),
+      ));
+    }
+  }
+
+  let previous = match deno_fs::umask(mask) {
+    Ok(previous) => previous,
+    Err(e) => return odd_future(e),
+  };
+  let builder = &mut FlatBufferBuilder::new();
+  let inner = msg::UmaskRes::create(builder, &msg::UmaskResArgs { previous });
+  ok_future(serialize_response(
+    cmd_id,
+    builder,
+    msg::BaseArgs {
+      inner: Some(inner.as_union_value()),
+      inner_type: msg::Any::UmaskRes,
+      ..Default::default()
+    },
+  ))
+}
+
 fn op_open(
   state: &ThreadSafeState,
   base: &msg::Base<'_>,
@@ -809,65 +1651,65 @@ fn op_open(
   let inner = base.inner_as_open().unwrap();
   let filename_str = inner.filename().unwrap();
   let filename = PathBuf::from(&filename_str);
-  let mode = inner.mode().unwrap();
-
-  let mut open_options = tokio::fs::OpenOptions::new();
-
-  match mode {
-    "r" => {
-      open_options.read(true);
-    }
-    "r+" => {
-      open_options.read(true).write(true);
-    }
-    "w" => {
-      open_options.create(true).write(true).truncate(true);
-    }
-    "w+" => {
-      open_options
-        .read(true)
-        .create(true)
-        .write(true)
-        .truncate(true);
-    }
-    "a" => {
-      open_options.create(true).append(true);
-    }
-    "a+" => {
-      open_options.read(true).create(true).append(true);
-    }
-    "x" => {
-      open_options.create_new(true).write(true);
-    }
-    "x+" => {
-      open_options.create_new(true).read(true).write(true);
-    }
-    &_ => {
-      panic!("Unknown file open mode.");
+  let (read, write, append, truncate, create, create_new) = match inner.mode() {
+    None => (
+      inner.read(),
+      inner.write(),
+      inner.append(),
+      inner.truncate(),
+      inner.create(),
+      inner.create_new(),
+    ),
+    Some("r") => (true, false, false, false, false, false),
+    Some("r+") => (true, true, false, false, false, false),
+    Some("w") => (false, true, false, true, true, false),
+    Some("w+") => (true, true, false, true, true, false),
+    Some("a") => (false, false, true, false, true, false),
+    Some("a+") => (true, false, true, false, true, false),
+    Some("x") => (false, true, false, false, false, true),
+    Some("x+") => (true, true, false, false, false, true),
+    Some(mode) => {
+      return odd_future(errors::new(
+        ErrorKind::InvalidInput,
+        format!("Unknown file open mode: {}", mode),
+      ))
     }
+  };
+
+  if !read && !write && !append {
+    return odd_future(errors::new(
+      ErrorKind::InvalidInput,
+      "A file must be opened for reading, writing or appending".to_string(),
+    ));
+  }
+  if (truncate || create || create_new) && !write && !append {
+    return odd_future(errors::new(
+      ErrorKind::InvalidInput,
+      "Creating or truncating a file requires write or append".to_string(),
+    ));
   }
 
-  match mode {
-    "r" => {
-      if let Err(e) = state.check_read(&filename_str) {
-        return odd_future(e);
-      }
-    }
-    "w" | "a" | "x" => {
-      if let Err(e) = state.check_write(&filename_str) {
-        return odd_future(e);
-      }
+  // Appending only adds to the file, so it needs no more than writing.
+  if read {
+    if let Err(e) = state.check_read(&filename_str) {
+      return odd_future(e);
     }
-    &_ => {
-      if let Err(e) = state.check_read(&filename_str) {
-        return odd_future(e);
-      }
-      if let Err(e) = state.check_write(&filename_str) {
-        return odd_future(e);
-      }
+  }
+  if write || append {
+    if let Err(e) = state.check_write(&filename_str) {
+      return odd_future(e);
     }
   }
 
+  let mut open_options = tokio::fs::OpenOptions::new();
+  open_options
+    .read(read)
+    .write(write)
+    .append(append)
+    .truncate(truncate)
+    .create(create)
+    .create_new(create_new);
+
   let op = open_options
     .open(filename)
     .map_err(DenoError::from)
@@ -944,6 +1786,8 @@ fn op_read(
   match resources::lookup(rid) {
     None => odd_future(errors::bad_resource()),
     Some(resource) => {
+      // Reading into an empty buffer reads nothing, without reaching the end.
+      let len = data.len();
       let op = tokio::io::read(resource, data)
         .map_err(DenoError::from)
         .and_then(move |(_resource, _buf, nread)| {
@@ -952,7 +1796,7 @@ fn op_read(
             builder,
             &msg::ReadResArgs {
               nread: nread as u32,
-              eof: nread == 0,
+              eof: nread == 0 && len > 0,
             },
           );
           Ok(serialize_response(
@@ -1007,6 +1851,90 @@ fn op_write(
   }
 }
 
+/// The ops which a Batch may hold.
+fn op_selector_batch(inner_type: msg::Any) -> Option<OpCreator> {
+  match inner_type {
+    msg::Any::Read => Some(op_read),
+    msg::Any::Stat => Some(op_stat),
+    msg::Any::Write => Some(op_write),
+    _ => None,
+  }
+}
+
+/// Runs the ops of the batch one after the other, each as if it was
+/// dispatched on its own with its range of `data`, and responds with all of
+/// their responses. The batch is checked first, so that either all of its
+/// ops run or none.
+fn op_batch(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_batch().unwrap();
+  let ops = inner.ops().unwrap();
+
+  let mut batch = Vec::with_capacity(ops.len());
+  for i in 0..ops.len() {
+    let op = ops.get(i);
+    let control = op.control().unwrap();
+    let op_base = msg::get_root_as_base(control);
+    let inner_type = op_base.inner_type();
+    if !op_base.sync() || op_selector_batch(inner_type).is_none() {
+      return odd_future(errors::new(
+        ErrorKind::InvalidInput,
+        format!("{} can't be batched", msg::enum_name_any(inner_type)),
+      ));
+    }
+    let start = op.data_offset() as usize;
+    let len = op.data_len() as usize;
+    if start + len > data.len() {
+      return odd_future(errors::new(
+        ErrorKind::InvalidInput,
+        "The data of an op is out of the batch".to_string(),
+      ));
+    }
+    // The ops run one after the other, none of them outlives the batch.
+    let op_data =
+      unsafe { deno_buf::from_raw_parts(data[start..].as_ptr(), len) };
+    batch.push((control, op_data));
+  }
+
+  let responses: Vec<Buf> = batch
+    .into_iter()
+    .map(|(control, op_data)| {
+      let (_, op) = dispatch_all(state, control, op_data, op_selector_batch);
+      op.wait().unwrap()
+    }).collect();
+
+  let builder = &mut FlatBufferBuilder::new();
+  let results: Vec<_> = responses
+    .iter()
+    .map(|res| {
+      let res = builder.create_vector(res);
+      msg::BatchResult::create(
+        builder,
+        &msg::BatchResultArgs { res: Some(res) },
+      )
+    }).collect();
+  let results = builder.create_vector(&results);
+  let inner = msg::BatchRes::create(
+    builder,
+    &msg::BatchResArgs {
+      results: Some(results),
+    },
+  );
+  ok_future(serialize_response(
+    cmd_id,
+    builder,
+    msg::BaseArgs {
+      inner: Some(inner.as_union_value()),
+      inner_type: msg::Any::BatchRes,
+      ..Default::default()
+    },
+  ))
+}
+
 fn op_seek(
   _state: &ThreadSafeState,
   base: &msg::Base<'_>,
@@ -1046,9 +1974,11 @@ fn op_remove(
 
   blocking(base.sync(), move || {
     debug!("op_remove {}", path.display());
-    let metadata = fs::metadata(&path)?;
-    if metadata.is_file() {
-      fs::remove_file(&path)?;
+    // Symlinks are removed, never followed, so that nothing outside of the
+    // tree goes; the permission of the root covers everything below it.
+    let metadata = fs::symlink_metadata(&path)?;
+    if !metadata.is_dir() {
+      deno_fs::remove_file_or_link(&path)?;
     } else if recursive {
       remove_dir_all(&path)?;
     } else {
@@ -1069,6 +1999,7 @@ fn op_copy_file(
   let from = PathBuf::from(from_);
   let to_ = inner.to().unwrap();
   let to = PathBuf::from(to_);
+  let overwrite = inner.overwrite();
 
   if let Err(e) = state.check_read(&from_) {
     return odd_future(e);
@@ -1079,17 +2010,7 @@ fn op_copy_file(
 
   debug!("op_copy_file {} {}", from.display(), to.display());
   blocking(base.sync(), move || {
-    // On *nix, Rust deem non-existent path as invalid input
-    // See https://github.com/rust-lang/rust/issues/54800
-    // Once the issue is reolved, we should remove this workaround.
-    if cfg!(unix) && !from.is_file() {
-      return Err(errors::new(
-        ErrorKind::NotFound,
-        "File not found".to_string(),
-      ));
-    }
-
-    fs::copy(&from, &to)?;
+    deno_fs::copy_file(&from, &to, overwrite)?;
     Ok(empty_buf())
   })
 }
@@ -1201,7 +2122,9 @@ fn op_read_dir(
   let inner = base.inner_as_read_dir().unwrap();
   let cmd_id = base.cmd_id();
   let path = String::from(inner.path().unwrap());
+  let recursive = inner.recursive();
 
+  // Once for the root, the entries below are part of it.
   if let Err(e) = state.check_read(&path) {
     return odd_future(e);
   }
@@ -1209,10 +2132,9 @@ fn op_read_dir(
   blocking(base.sync(), move || -> OpResult {
     debug!("op_read_dir {}", path);
     let builder = &mut FlatBufferBuilder::new();
-    let entries: Vec<_> = fs::read_dir(Path::new(&path))?
-      .map(|entry| {
-        let entry = entry.unwrap();
-        let metadata = entry.metadata().unwrap();
+    let entries: Vec<_> = deno_fs::read_dir(Path::new(&path), recursive)?
+      .into_iter()
+      .map(|(entry, metadata)| {
         let file_type = metadata.file_type();
         let name = builder.create_string(entry.file_name().to_str().unwrap());
         let path = builder.create_string(entry.path().to_str().unwrap());
@@ -1260,15 +2182,21 @@ fn op_rename(
 ) -> Box<OpWithError> {
   assert_eq!(data.len(), 0);
   let inner = base.inner_as_rename().unwrap();
-  let oldpath = PathBuf::from(inner.oldpath().unwrap());
+  let oldpath_ = inner.oldpath().unwrap();
+  let oldpath = PathBuf::from(oldpath_);
   let newpath_ = inner.newpath().unwrap();
   let newpath = PathBuf::from(newpath_);
+  let overwrite = inner.overwrite();
+  // Moving across file systems reads `oldpath` to copy it.
+  if let Err(e) = state.check_read(&oldpath_) {
+    return odd_future(e);
+  }
   if let Err(e) = state.check_write(&newpath_) {
     return odd_future(e);
   }
   blocking(base.sync(), move || -> OpResult {
     debug!("op_rename {} {}", oldpath.display(), newpath.display());
-    fs::rename(&oldpath, &newpath)?;
+    deno_fs::rename(&oldpath, &newpath, overwrite)?;
     Ok(empty_buf())
   })
 }
@@ -1280,10 +2208,15 @@ fn op_link(
 ) -> Box<OpWithError> {
   assert_eq!(data.len(), 0);
   let inner = base.inner_as_link().unwrap();
-  let oldname = PathBuf::from(inner.oldname().unwrap());
+  let oldname_ = inner.oldname().unwrap();
+  let oldname = PathBuf::from(oldname_);
   let newname_ = inner.newname().unwrap();
   let newname = PathBuf::from(newname_);
 
+  // The link gives access to the contents of oldname.
+  if let Err(e) = state.check_read(&oldname_) {
+    return odd_future(e);
+  }
   if let Err(e) = state.check_write(&newname_) {
     return odd_future(e);
   }
@@ -1305,21 +2238,40 @@ fn op_symlink(
   let oldname = PathBuf::from(inner.oldname().unwrap());
   let newname_ = inner.newname().unwrap();
   let newname = PathBuf::from(newname_);
-
-  if let Err(e) = state.check_write(&newname_) {
+  #[cfg(windows)]
+  let symlink_type = inner.symlink_type();
+
+  // A relative oldname is relative to the directory of the link, which gives
+  // access to it.
+  let target = match newname.parent() {
+    Some(dir) => dir.join(&oldname),
+    None => oldname.clone(),
+  };
+  if let Err(e) = state.check_read(&target.to_string_lossy()) {
     return odd_future(e);
   }
-  // TODO Use type for Windows.
-  if cfg!(windows) {
-    return odd_future(errors::new(
-      ErrorKind::Other,
-      "Not implemented".to_string(),
-    ));
+  if let Err(e) = state.check_write(&newname_) {
+    return odd_future(e);
   }
+
   blocking(base.sync(), move || -> OpResult {
     debug!("op_symlink {} {}", oldname.display(), newname.display());
-    #[cfg(any(unix))]
+    #[cfg(unix)]
     std::os::unix::fs::symlink(&oldname, &newname)?;
+    #[cfg(windows)]
+    {
+      let is_dir = match symlink_type {
+        msg::SymlinkType::Dir => true,
+        msg::SymlinkType::File => false,
+        // A dangling link points to a file.
+        msg::SymlinkType::Auto => target.is_dir(),
+      };
+      if is_dir {
+        std::os::windows::fs::symlink_dir(&oldname, &newname)?;
+      } else {
+        std::os::windows::fs::symlink_file(&oldname, &newname)?;
+      }
+    }
     Ok(empty_buf())
   })
 }
@@ -1341,7 +2293,20 @@ fn op_read_link(
 
   blocking(base.sync(), move || -> OpResult {
     debug!("op_read_link {}", name.display());
-    let path = fs::read_link(&name)?;
+    let path = match fs::read_link(&name) {
+      Ok(path) => path,
+      Err(e) => {
+        // The errors for existing files which are not links differ between
+        // platforms.
+        return match fs::symlink_metadata(&name) {
+          Ok(ref m) if !m.file_type().is_symlink() => Err(errors::new(
+            ErrorKind::InvalidInput,
+            format!("{} is not a symbolic link", name.display()),
+          )),
+          _ => Err(e.into()),
+        };
+      }
+    };
     let builder = &mut FlatBufferBuilder::new();
     let path_off = builder.create_string(path.to_str().unwrap());
     let inner = msg::ReadlinkRes::create(
@@ -1452,120 +2417,562 @@ fn op_truncate(
   })
 }
 
+/// The backlog of a listener when none is given, as in mio and std.
+const DEFAULT_BACKLOG: i32 = 128;
+
 fn op_listen(
   state: &ThreadSafeState,
   base: &msg::Base<'_>,
   data: deno_buf,
 ) -> Box<OpWithError> {
   assert_eq!(data.len(), 0);
-  if let Err(e) = state.check_net("listen") {
-    return odd_future(e);
-  }
-
   let cmd_id = base.cmd_id();
-  let inner = base.inner_as_listen().unwrap();
-  let network = inner.network().unwrap();
-  assert_eq!(network, "tcp");
+  let inner = base.inner_as_listen().unwrap();
+  let network = inner.network().unwrap();
+  assert_eq!(network, "tcp");
+  let address = inner.address().unwrap();
+  let reuse_address = inner.reuse_address();
+  let reuse_port = inner.reuse_port();
+  let backlog = match inner.backlog() {
+    0 => DEFAULT_BACKLOG,
+    backlog => backlog.min(i32::max_value() as u32) as i32,
+  };
+
+  if let Err(e) = state.check_net(address) {
+    return odd_future(e);
+  }
+
+  Box::new(futures::future::result((move || {
+    let addr = resolve_addr(address).wait()?;
+
+    let std_listener =
+      bind_listener(&addr, reuse_address, reuse_port, backlog)?;
+    let listener =
+      TcpListener::from_std(std_listener, &tokio::reactor::Handle::default())?;
+    let local_addr = listener.local_addr()?;
+    let resource = resources::add_tcp_listener(listener);
+    listen_res(cmd_id, resource.rid, &local_addr.to_string())
+  })()))
+}
+
+/// Responds to the listen ops with the listener `rid`.
+fn listen_res(cmd_id: u32, rid: u32, local_addr: &str) -> OpResult {
+  let builder = &mut FlatBufferBuilder::new();
+  let local_addr = builder.create_string(local_addr);
+  let inner = msg::ListenRes::create(
+    builder,
+    &msg::ListenResArgs {
+      rid,
+      local_addr: Some(local_addr),
+    },
+  );
+  Ok(serialize_response(
+    cmd_id,
+    builder,
+    msg::BaseArgs {
+      inner: Some(inner.as_union_value()),
+      inner_type: msg::Any::ListenRes,
+      ..Default::default()
+    },
+  ))
+}
+
+fn op_listen_tls(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_listen_tls().unwrap();
+  let address = inner.address().unwrap();
+  let cert_file = inner.cert_file().unwrap();
+  let key_file = inner.key_file().unwrap();
+
+  let checked = state
+    .check_net(address)
+    .and_then(|()| state.check_read(cert_file))
+    .and_then(|()| state.check_read(key_file));
+  if let Err(e) = checked {
+    return odd_future(e);
+  }
+
+  Box::new(futures::future::result((move || {
+    let acceptor = tls::acceptor(cert_file, key_file)?;
+    let addr = resolve_addr(address).wait()?;
+
+    // The defaults of `Deno.listen()`.
+    let std_listener =
+      bind_listener(&addr, cfg!(not(windows)), false, DEFAULT_BACKLOG)?;
+    let listener =
+      TcpListener::from_std(std_listener, &tokio::reactor::Handle::default())?;
+    let local_addr = listener.local_addr()?;
+    let resource = resources::add_tls_listener(listener, acceptor);
+    listen_res(cmd_id, resource.rid, &local_addr.to_string())
+  })()))
+}
+
+/// Binds a listener to `addr`, the socket options have to be set before.
+fn bind_listener(
+  addr: &SocketAddr,
+  reuse_address: bool,
+  reuse_port: bool,
+  backlog: i32,
+) -> DenoResult<std::net::TcpListener> {
+  let builder = match addr {
+    SocketAddr::V4(_) => TcpBuilder::new_v4()?,
+    SocketAddr::V6(_) => TcpBuilder::new_v6()?,
+  };
+  builder.reuse_address(reuse_address)?;
+  set_reuse_port(&builder, reuse_port)?;
+  builder.bind(addr)?;
+  Ok(builder.listen(backlog)?)
+}
+
+#[cfg(unix)]
+fn set_reuse_port(builder: &TcpBuilder, reuse_port: bool) -> DenoResult<()> {
+  use net2::unix::UnixTcpBuilderExt;
+  builder.reuse_port(reuse_port)?;
+  Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_reuse_port(_builder: &TcpBuilder, reuse_port: bool) -> DenoResult<()> {
+  if reuse_port {
+    return Err(errors::new(
+      ErrorKind::NotSupported,
+      "SO_REUSEPORT is not supported on Windows".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+fn new_conn(cmd_id: u32, tcp_stream: TcpStream) -> OpResult {
+  let remote_addr = tcp_stream.peer_addr()?;
+  let local_addr = tcp_stream.local_addr()?;
+  let tcp_stream_resource = resources::add_tcp_stream(tcp_stream);
+  conn_res(
+    cmd_id,
+    tcp_stream_resource.rid,
+    &remote_addr.to_string(),
+    &local_addr.to_string(),
+  )
+}
+
+/// The addresses of a TLS connection are the ones of its TCP stream.
+fn new_client_tls_conn(
+  cmd_id: u32,
+  tls_stream: ClientTlsStream<TcpStream>,
+) -> OpResult {
+  let remote_addr = tls_stream.get_ref().0.peer_addr()?;
+  let local_addr = tls_stream.get_ref().0.local_addr()?;
+  let tls_stream_resource = resources::add_client_tls_stream(tls_stream);
+  conn_res(
+    cmd_id,
+    tls_stream_resource.rid,
+    &remote_addr.to_string(),
+    &local_addr.to_string(),
+  )
+}
+
+fn new_server_tls_conn(
+  cmd_id: u32,
+  tls_stream: ServerTlsStream<TcpStream>,
+) -> OpResult {
+  let remote_addr = tls_stream.get_ref().0.peer_addr()?;
+  let local_addr = tls_stream.get_ref().0.local_addr()?;
+  let tls_stream_resource = resources::add_server_tls_stream(tls_stream);
+  conn_res(
+    cmd_id,
+    tls_stream_resource.rid,
+    &remote_addr.to_string(),
+    &local_addr.to_string(),
+  )
+}
+
+/// Responds to the accept and dial ops with the connection `rid`.
+fn conn_res(
+  cmd_id: u32,
+  rid: u32,
+  remote_addr: &str,
+  local_addr: &str,
+) -> OpResult {
+  let builder = &mut FlatBufferBuilder::new();
+  let remote_addr = builder.create_string(remote_addr);
+  let local_addr = builder.create_string(local_addr);
+  let inner = msg::NewConn::create(
+    builder,
+    &msg::NewConnArgs {
+      rid,
+      remote_addr: Some(remote_addr),
+      local_addr: Some(local_addr),
+    },
+  );
+  Ok(serialize_response(
+    cmd_id,
+    builder,
+    msg::BaseArgs {
+      inner: Some(inner.as_union_value()),
+      inner_type: msg::Any::NewConn,
+      ..Default::default()
+    },
+  ))
+}
+
+fn op_accept(
+  _state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  // Permission to accept is granted together with permission to listen.
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_accept().unwrap();
+  let server_rid = inner.rid();
+
+  match resources::get_type(server_rid) {
+    None => odd_future(errors::bad_resource()),
+    #[cfg(unix)]
+    Some(ref repr) if repr == "unixListener" => {
+      let op = resources::accept_unix(server_rid).and_then(
+        move |(unix_stream, _socket_addr)| new_unix_conn(cmd_id, unix_stream),
+      );
+      Box::new(op)
+    }
+    // The handshake fails the accept, the listener can accept again.
+    Some(ref repr) if repr == "tlsListener" => {
+      let acceptor = match resources::tls_acceptor(server_rid) {
+        Ok(acceptor) => acceptor,
+        Err(e) => return odd_future(e),
+      };
+      let server_resource = resources::Resource { rid: server_rid };
+      let op = tokio_util::accept(server_resource)
+        .and_then(move |(tcp_stream, _socket_addr)| {
+          acceptor.accept(tcp_stream).map_err(tls::handshake_error)
+        })
+        .and_then(move |tls_stream| new_server_tls_conn(cmd_id, tls_stream));
+      Box::new(op)
+    }
+    Some(_) => {
+      let server_resource = resources::Resource { rid: server_rid };
+      let op = tokio_util::accept(server_resource).and_then(
+        move |(tcp_stream, _socket_addr)| new_conn(cmd_id, tcp_stream),
+      );
+      Box::new(op)
+    }
+  }
+}
+
+fn op_dial(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_dial().unwrap();
+  let network = inner.network().unwrap();
+  assert_eq!(network, "tcp"); // TODO Support others.
+  let address = inner.address().unwrap();
+  let timeout = inner.timeout();
+
+  if let Err(e) = state.check_net(address) {
+    return odd_future(e);
+  }
+
+  let connect = resolve_addr(address)
+    .map_err(DenoError::from)
+    .and_then(move |addr| TcpStream::connect(&addr).map_err(DenoError::from));
+  let op = with_timeout(connect, timeout, "Connection timed out")
+    .and_then(move |tcp_stream| new_conn(cmd_id, tcp_stream));
+  Box::new(op)
+}
+
+fn op_dial_tls(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_dial_tls().unwrap();
+  let address = inner.address().unwrap();
+  let hostname = inner.hostname().unwrap();
+  let ca_file = inner.ca_file();
+
+  let checked = match ca_file {
+    Some(ca_file) => state
+      .check_net(address)
+      .and_then(|()| state.check_read(ca_file)),
+    None => state.check_net(address),
+  };
+  if let Err(e) = checked {
+    return odd_future(e);
+  }
+  let connector = match tls::connector(ca_file) {
+    Ok(connector) => connector,
+    Err(e) => return odd_future(e),
+  };
+  let server_name = match tls::server_name(hostname) {
+    Ok(server_name) => server_name,
+    Err(e) => return odd_future(e),
+  };
+
+  let op = resolve_addr(address)
+    .map_err(DenoError::from)
+    .and_then(move |addr| TcpStream::connect(&addr).map_err(DenoError::from))
+    .and_then(move |tcp_stream| {
+      connector
+        .connect(server_name.as_ref(), tcp_stream)
+        .map_err(tls::handshake_error)
+    })
+    .and_then(move |tls_stream| new_client_tls_conn(cmd_id, tls_stream));
+  Box::new(op)
+}
+
+/// Listening on a unix socket creates its file, so it requires write access,
+/// and connecting to it both reads from and writes to it.
+#[cfg(unix)]
+fn check_unix_socket(state: &ThreadSafeState, path: &str) -> DenoResult<()> {
+  state.check_read(path)?;
+  state.check_write(path)
+}
+
+#[cfg(unix)]
+fn op_unix_listen(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_unix_listen().unwrap();
+  let path_ = inner.path().unwrap();
+  let path = PathBuf::from(path_);
+
+  if let Err(e) = check_unix_socket(state, path_) {
+    return odd_future(e);
+  }
+
+  Box::new(futures::future::result((move || {
+    debug!("op_unix_listen {}", path.display());
+    let listener = UnixListener::bind(&path)?;
+    let resource = resources::add_unix_listener(listener, path.clone());
+    listen_res(cmd_id, resource.rid, &path.to_string_lossy())
+  })()))
+}
+
+#[cfg(unix)]
+fn op_unix_dial(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_unix_dial().unwrap();
+  let path_ = inner.path().unwrap();
+  let path = PathBuf::from(path_);
+
+  if let Err(e) = check_unix_socket(state, path_) {
+    return odd_future(e);
+  }
+
+  let op = UnixStream::connect(&path)
+    .map_err(DenoError::from)
+    .and_then(move |unix_stream| new_unix_conn(cmd_id, unix_stream));
+  Box::new(op)
+}
+
+#[cfg(not(unix))]
+fn op_unix_listen(
+  _state: &ThreadSafeState,
+  _base: &msg::Base<'_>,
+  _data: deno_buf,
+) -> Box<OpWithError> {
+  odd_future(unix_sockets_not_supported())
+}
+
+#[cfg(not(unix))]
+fn op_unix_dial(
+  _state: &ThreadSafeState,
+  _base: &msg::Base<'_>,
+  _data: deno_buf,
+) -> Box<OpWithError> {
+  odd_future(unix_sockets_not_supported())
+}
+
+#[cfg(not(unix))]
+fn unix_sockets_not_supported() -> DenoError {
+  errors::new(
+    ErrorKind::NotSupported,
+    "Unix domain sockets are not supported on Windows".to_string(),
+  )
+}
+
+/// The addresses of a unix socket are paths, a client which did not bind to
+/// one has an empty address.
+#[cfg(unix)]
+fn new_unix_conn(cmd_id: u32, unix_stream: UnixStream) -> OpResult {
+  let path_of = |addr: std::os::unix::net::SocketAddr| {
+    addr
+      .as_pathname()
+      .map(|path| path.to_string_lossy().into_owned())
+      .unwrap_or_default()
+  };
+  let remote_addr = path_of(unix_stream.peer_addr()?);
+  let local_addr = path_of(unix_stream.local_addr()?);
+  let unix_stream_resource = resources::add_unix_stream(unix_stream);
+  conn_res(cmd_id, unix_stream_resource.rid, &remote_addr, &local_addr)
+}
+
+fn op_datagram_listen(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_datagram_listen().unwrap();
   let address = inner.address().unwrap();
+  let broadcast = inner.broadcast();
+
+  if let Err(e) = state.check_net(address) {
+    return odd_future(e);
+  }
 
   Box::new(futures::future::result((move || {
     let addr = resolve_addr(address).wait()?;
 
-    let listener = TcpListener::bind(&addr)?;
-    let resource = resources::add_tcp_listener(listener);
+    let socket = UdpSocket::bind(&addr)?;
+    socket.set_broadcast(broadcast)?;
+    let local_addr = socket.local_addr()?;
+    let resource = resources::add_udp_socket(socket);
 
     let builder = &mut FlatBufferBuilder::new();
-    let inner = msg::ListenRes::create(
+    let local_addr = builder.create_string(&local_addr.to_string());
+    let inner = msg::DatagramListenRes::create(
       builder,
-      &msg::ListenResArgs { rid: resource.rid },
+      &msg::DatagramListenResArgs {
+        rid: resource.rid,
+        local_addr: Some(local_addr),
+      },
     );
     Ok(serialize_response(
       cmd_id,
       builder,
       msg::BaseArgs {
         inner: Some(inner.as_union_value()),
-        inner_type: msg::Any::ListenRes,
+        inner_type: msg::Any::DatagramListenRes,
         ..Default::default()
       },
     ))
   })()))
 }
 
-fn new_conn(cmd_id: u32, tcp_stream: TcpStream) -> OpResult {
-  let tcp_stream_resource = resources::add_tcp_stream(tcp_stream);
-  // TODO forward socket_addr to client.
-
-  let builder = &mut FlatBufferBuilder::new();
-  let inner = msg::NewConn::create(
-    builder,
-    &msg::NewConnArgs {
-      rid: tcp_stream_resource.rid,
-      ..Default::default()
-    },
-  );
-  Ok(serialize_response(
-    cmd_id,
-    builder,
-    msg::BaseArgs {
-      inner: Some(inner.as_union_value()),
-      inner_type: msg::Any::NewConn,
-      ..Default::default()
-    },
-  ))
-}
-
-fn op_accept(
+fn op_datagram_send(
   state: &ThreadSafeState,
   base: &msg::Base<'_>,
   data: deno_buf,
 ) -> Box<OpWithError> {
-  assert_eq!(data.len(), 0);
-  if let Err(e) = state.check_net("accept") {
-    return odd_future(e);
-  }
   let cmd_id = base.cmd_id();
-  let inner = base.inner_as_accept().unwrap();
-  let server_rid = inner.rid();
+  let inner = base.inner_as_datagram_send().unwrap();
+  let rid = inner.rid();
+  let address = inner.address().unwrap();
 
-  match resources::lookup(server_rid) {
-    None => odd_future(errors::bad_resource()),
-    Some(server_resource) => {
-      let op = tokio_util::accept(server_resource)
-        .map_err(DenoError::from)
-        .and_then(move |(tcp_stream, _socket_addr)| {
-          new_conn(cmd_id, tcp_stream)
-        });
-      Box::new(op)
-    }
+  // Listening covers receiving from anyone, sending is to the address.
+  if let Err(e) = state.check_net(address) {
+    return odd_future(e);
   }
+
+  let op = resolve_addr(address)
+    .map_err(DenoError::from)
+    .and_then(move |addr| resources::send_datagram(rid, data, addr))
+    .and_then(move |nsent| {
+      let builder = &mut FlatBufferBuilder::new();
+      let inner = msg::DatagramSendRes::create(
+        builder,
+        &msg::DatagramSendResArgs {
+          nsent: nsent as u32,
+        },
+      );
+      Ok(serialize_response(
+        cmd_id,
+        builder,
+        msg::BaseArgs {
+          inner: Some(inner.as_union_value()),
+          inner_type: msg::Any::DatagramSendRes,
+          ..Default::default()
+        },
+      ))
+    });
+  Box::new(op)
 }
 
-fn op_dial(
-  state: &ThreadSafeState,
+fn op_datagram_receive(
+  _state: &ThreadSafeState,
   base: &msg::Base<'_>,
   data: deno_buf,
 ) -> Box<OpWithError> {
-  assert_eq!(data.len(), 0);
-  if let Err(e) = state.check_net("dial") {
-    return odd_future(e);
-  }
   let cmd_id = base.cmd_id();
-  let inner = base.inner_as_dial().unwrap();
-  let network = inner.network().unwrap();
-  assert_eq!(network, "tcp"); // TODO Support others.
-  let address = inner.address().unwrap();
+  let inner = base.inner_as_datagram_receive().unwrap();
+  let rid = inner.rid();
 
-  let op =
-    resolve_addr(address)
-      .map_err(DenoError::from)
-      .and_then(move |addr| {
-        TcpStream::connect(&addr)
-          .map_err(DenoError::from)
-          .and_then(move |tcp_stream| new_conn(cmd_id, tcp_stream))
-      });
+  let receive = match resources::receive_datagram(rid, data) {
+    Ok(receive) => receive,
+    Err(e) => return odd_future(e),
+  };
+  let op = receive.and_then(move |(_data, nread, truncated, remote_addr)| {
+    let builder = &mut FlatBufferBuilder::new();
+    let remote_addr = builder.create_string(&remote_addr.to_string());
+    let inner = msg::DatagramReceiveRes::create(
+      builder,
+      &msg::DatagramReceiveResArgs {
+        nread: nread as u32,
+        remote_addr: Some(remote_addr),
+        truncated,
+      },
+    );
+    Ok(serialize_response(
+      cmd_id,
+      builder,
+      msg::BaseArgs {
+        inner: Some(inner.as_union_value()),
+        inner_type: msg::Any::DatagramReceiveRes,
+        ..Default::default()
+      },
+    ))
+  });
   Box::new(op)
 }
 
+fn op_datagram_multicast(
+  _state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let inner = base.inner_as_datagram_multicast().unwrap();
+  let rid = inner.rid();
+  let group_ = inner.group().unwrap();
+  let join = inner.join();
+
+  // Joining a group needs no more permission than listening did.
+  let group = match group_.parse::<IpAddr>() {
+    Ok(group) if group.is_multicast() => group,
+    _ => {
+      return odd_future(errors::new(
+        ErrorKind::InvalidInput,
+        format!("{} is not a multicast address", group_),
+      ))
+    }
+  };
+  match resources::set_multicast(rid, group, join) {
+    Ok(()) => ok_future(empty_buf()),
+    Err(e) => odd_future(e),
+  }
+}
+
 fn op_metrics(
   state: &ThreadSafeState,
   base: &msg::Base<'_>,
@@ -1577,7 +2984,10 @@ fn op_metrics(
   let builder = &mut FlatBufferBuilder::new();
   let inner = msg::MetricsRes::create(
     builder,
-    &msg::MetricsResArgs::from(&state.metrics),
+    &msg::MetricsResArgs {
+      resources_open: resources::table_len() as u64,
+      ..msg::MetricsResArgs::from(&state.metrics)
+    },
   );
   ok_future(serialize_response(
     cmd_id,
@@ -1650,16 +3060,16 @@ fn op_run(
   assert!(base.sync());
   let cmd_id = base.cmd_id();
 
-  if let Err(e) = state.check_run() {
-    return odd_future(e);
-  }
-
   assert_eq!(data.len(), 0);
   let inner = base.inner_as_run().unwrap();
   let args = inner.args().unwrap();
   let env = inner.env().unwrap();
   let cwd = inner.cwd();
 
+  if let Err(e) = state.check_run_command(args.get(0)) {
+    return odd_future(e);
+  }
+
   let mut c = Command::new(args.get(0));
   (1..args.len()).for_each(|i| {
     let arg = args.get(i);
@@ -1675,8 +3085,12 @@ fn op_run(
   c.stdout(subprocess_stdio_map(inner.stdout()));
   c.stderr(subprocess_stdio_map(inner.stderr()));
 
-  // Spawn the command.
-  let child = match c.spawn_async() {
+  // Spawn the command, the child starts with a copy of the environment.
+  let spawned = {
+    let _guard = ENV_LOCK.lock().unwrap();
+    c.spawn_async()
+  };
+  let child = match spawned {
     Ok(v) => v,
     Err(err) => {
       return odd_future(err.into());
@@ -1725,10 +3139,8 @@ fn op_run_status(
   let inner = base.inner_as_run_status().unwrap();
   let rid = inner.rid();
 
-  if let Err(e) = state.check_run() {
-    return odd_future(e);
-  }
-
+  // No permission check here, the child could only have been spawned by
+  // op_run if running it was granted.
   let future = match resources::child_status(rid) {
     Err(e) => {
       return odd_future(e);
@@ -1771,6 +3183,184 @@ fn op_run_status(
   Box::new(future)
 }
 
+fn op_kill(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let inner = base.inner_as_kill().unwrap();
+  let pid = inner.pid();
+  let signo = inner.signo();
+
+  if let Err(e) = state.check_run() {
+    return odd_future(e);
+  }
+
+  match signal::kill(pid, signo) {
+    Ok(()) => ok_future(empty_buf()),
+    Err(e) => odd_future(e),
+  }
+}
+
+fn op_signal_bind(
+  _state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_signal_bind().unwrap();
+  let name = inner.signal().unwrap();
+
+  let stream = match signal::bind(name) {
+    Err(e) => return odd_future(e),
+    Ok(stream) => stream,
+  };
+  let resource = resources::add_signal(stream);
+
+  let builder = &mut FlatBufferBuilder::new();
+  let inner = msg::SignalBindRes::create(
+    builder,
+    &msg::SignalBindResArgs { rid: resource.rid },
+  );
+  ok_future(serialize_response(
+    cmd_id,
+    builder,
+    msg::BaseArgs {
+      inner: Some(inner.as_union_value()),
+      inner_type: msg::Any::SignalBindRes,
+      ..Default::default()
+    },
+  ))
+}
+
+fn op_signal_poll(
+  _state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_signal_poll().unwrap();
+  let rid = inner.rid();
+
+  let future = match resources::poll_signal(rid) {
+    Err(e) => return odd_future(e),
+    Ok(f) => f,
+  };
+  let future = future.and_then(move |done| {
+    let builder = &mut FlatBufferBuilder::new();
+    let inner =
+      msg::SignalPollRes::create(builder, &msg::SignalPollResArgs { done });
+    Ok(serialize_response(
+      cmd_id,
+      builder,
+      msg::BaseArgs {
+        inner: Some(inner.as_union_value()),
+        inner_type: msg::Any::SignalPollRes,
+        ..Default::default()
+      },
+    ))
+  });
+  Box::new(future)
+}
+
+fn op_fs_events_open(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_fs_events_open().unwrap();
+  let paths = inner.paths().unwrap();
+  let recursive = inner.recursive();
+
+  let mut watched = Vec::new();
+  for i in 0..paths.len() {
+    let path = paths.get(i);
+    if let Err(e) = state.check_read(path) {
+      return odd_future(e);
+    }
+    watched.push(PathBuf::from(path));
+  }
+
+  let watcher = match fs_events::watch(&watched, recursive) {
+    Err(e) => return odd_future(e),
+    Ok(watcher) => watcher,
+  };
+  let resource = resources::add_fs_watcher(watcher);
+
+  let builder = &mut FlatBufferBuilder::new();
+  let inner = msg::FsEventsOpenRes::create(
+    builder,
+    &msg::FsEventsOpenResArgs { rid: resource.rid },
+  );
+  ok_future(serialize_response(
+    cmd_id,
+    builder,
+    msg::BaseArgs {
+      inner: Some(inner.as_union_value()),
+      inner_type: msg::Any::FsEventsOpenRes,
+      ..Default::default()
+    },
+  ))
+}
+
+fn op_fs_events_poll(
+  _state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let cmd_id = base.cmd_id();
+  let inner = base.inner_as_fs_events_poll().unwrap();
+  let rid = inner.rid();
+
+  let future = match resources::poll_fs_events(rid) {
+    Err(e) => return odd_future(e),
+    Ok(f) => f,
+  };
+  let future = future.and_then(move |maybe_events| {
+    let builder = &mut FlatBufferBuilder::new();
+    let done = maybe_events.is_none();
+    let mut events = Vec::new();
+    for event in maybe_events.unwrap_or_default() {
+      let mut paths = Vec::new();
+      for path in &event.paths {
+        paths.push(builder.create_string(&path.to_string_lossy()));
+      }
+      let paths = builder.create_vector(&paths);
+      events.push(msg::FsEvent::create(
+        builder,
+        &msg::FsEventArgs {
+          kind: event.kind,
+          paths: Some(paths),
+        },
+      ));
+    }
+    let events = builder.create_vector(&events);
+    let inner = msg::FsEventsPollRes::create(
+      builder,
+      &msg::FsEventsPollResArgs {
+        events: Some(events),
+        done,
+      },
+    );
+    Ok(serialize_response(
+      cmd_id,
+      builder,
+      msg::BaseArgs {
+        inner: Some(inner.as_union_value()),
+        inner_type: msg::Any::FsEventsPollRes,
+        ..Default::default()
+      },
+    ))
+  });
+  Box::new(future)
+}
+
 struct GetMessageFuture {
   pub state: ThreadSafeState,
 }
@@ -1837,7 +3427,8 @@ fn op_worker_post_message(
     wc.0.clone()
   };
   let op = tx.send(d);
-  let op = op.map_err(|e| errors::new(ErrorKind::Other, e.to_string()));
+  // The host no longer listens.
+  let op = op.map_err(|e| errors::new(ErrorKind::BrokenPipe, e.to_string()));
   let op = op.and_then(move |_| -> DenoResult<Buf> {
     let builder = &mut FlatBufferBuilder::new();
 
@@ -1862,27 +3453,48 @@ fn op_create_worker(
   let cmd_id = base.cmd_id();
   let inner = base.inner_as_create_worker().unwrap();
   let specifier = inner.specifier().unwrap();
+  // Without the table the worker keeps all the permissions.
+  let keep = match inner.permissions() {
+    None => WorkerPermissions {
+      read: true,
+      write: true,
+      net: true,
+      env: true,
+      run: true,
+      high_precision: true,
+    },
+    Some(permissions) => WorkerPermissions {
+      read: permissions.read(),
+      write: permissions.write(),
+      net: permissions.net(),
+      env: permissions.env(),
+      run: permissions.run(),
+      high_precision: permissions.high_precision(),
+    },
+  };
 
   Box::new(futures::future::result(move || -> OpResult {
     let parent_state = state.clone();
 
-    let child_state = ThreadSafeState::new(
+    let child_state = ThreadSafeState::with_permissions(
       parent_state.flags.clone(),
       parent_state.argv.clone(),
       op_selector_std,
+      parent_state.permissions.for_worker(&keep),
     );
     let rid = child_state.resource.rid;
     let name = format!("USER-WORKER-{}", specifier);
 
-    let mut worker =
-      Worker::new(name, startup_data::deno_isolate_init(), child_state);
+    let startup_data =
+      startup_data::deno_isolate_init(!parent_state.flags.no_snapshot);
+    let mut worker = Worker::new(name, startup_data, child_state);
     js_check(worker.execute("denoMain()"));
     js_check(worker.execute("workerMain()"));
     let result = worker.execute_mod(specifier, false);
     match result {
       Ok(_) => {
         let mut workers_tl = parent_state.workers.lock().unwrap();
-        workers_tl.insert(rid, worker.shared());
+        workers_tl.insert(rid, UserWorker::spawn(worker));
         let builder = &mut FlatBufferBuilder::new();
         let msg_inner = msg::CreateWorkerRes::create(
           builder,
@@ -1916,25 +3528,74 @@ fn op_host_get_worker_closed(
   let rid = inner.rid();
   let state = state.clone();
 
-  let shared_worker_future = {
+  let closed = {
     let workers_tl = state.workers.lock().unwrap();
-    let worker = workers_tl.get(&rid).unwrap();
-    worker.clone()
+    match workers_tl.get(&rid) {
+      Some(worker) => worker.closed.clone(),
+      None => return odd_future(errors::bad_resource()),
+    }
   };
 
-  Box::new(shared_worker_future.then(move |_result| {
+  Box::new(closed.then(move |result| {
+    state.workers.lock().unwrap().remove(&rid);
+    // An uncaught error does not end the host, it gets it as an event.
     let builder = &mut FlatBufferBuilder::new();
-
+    let error = match result {
+      Ok(ref error) => (**error).clone(),
+      Err(_) => None,
+    };
+    let args = match error {
+      Some(ref err) => {
+        let formatted = JSErrorColor(err).to_string();
+        msg::HostGetWorkerClosedResArgs {
+          error: true,
+          message: Some(builder.create_string(&err.message)),
+          filename: err
+            .script_resource_name
+            .as_ref()
+            .map(|filename| builder.create_string(filename)),
+          line_number: err.line_number.unwrap_or(0) as i32,
+          column_number: err.start_column.unwrap_or(0) as i32,
+          formatted: Some(builder.create_string(&formatted)),
+        }
+      }
+      None => msg::HostGetWorkerClosedResArgs {
+        ..Default::default()
+      },
+    };
+    let inner = msg::HostGetWorkerClosedRes::create(builder, &args);
     Ok(serialize_response(
       cmd_id,
       builder,
       msg::BaseArgs {
+        inner: Some(inner.as_union_value()),
+        inner_type: msg::Any::HostGetWorkerClosedRes,
         ..Default::default()
       },
     ))
   }))
 }
 
+/// Stops the worker, whatever it is doing
+fn op_host_terminate_worker(
+  state: &ThreadSafeState,
+  base: &msg::Base<'_>,
+  data: deno_buf,
+) -> Box<OpWithError> {
+  assert_eq!(data.len(), 0);
+  let inner = base.inner_as_host_terminate_worker().unwrap();
+  let rid = inner.rid();
+
+  let mut workers_tl = state.workers.lock().unwrap();
+  match workers_tl.get_mut(&rid) {
+    Some(worker) => {
+      worker.terminate();
+      ok_future(empty_buf())
+    }
+    None => odd_future(errors::bad_resource()),
+  }
+}
+
 /// Get message from guest worker as host
 fn op_host_get_message(
   _state: &ThreadSafeState,
@@ -1946,8 +3607,8 @@ fn op_host_get_message(
   let inner = base.inner_as_host_get_message().unwrap();
   let rid = inner.rid();
 
-  let op = resources::get_message_from_worker(rid);
-  let op = op.map_err(move |_| -> DenoError { unimplemented!() });
+  // A worker which is done has no messages anymore, also once it was closed.
+  let op = resources::get_message_from_worker(rid).or_else(|_| Ok(None));
   let op = op.and_then(move |maybe_buf| -> DenoResult<Buf> {
     let builder = &mut FlatBufferBuilder::new();
 
@@ -1981,8 +3642,13 @@ fn op_host_post_message(
 
   let d = Vec::from(data.as_ref()).into_boxed_slice();
 
+  match resources::get_type(rid) {
+    Some(ref repr) if repr == "worker" => {}
+    _ => return odd_future(errors::bad_resource()),
+  }
   let op = resources::post_message_to_worker(rid, d);
-  let op = op.map_err(|e| errors::new(ErrorKind::Other, e.to_string()));
+  // The worker was terminated.
+  let op = op.map_err(|e| errors::new(ErrorKind::BrokenPipe, e.to_string()));
   let op = op.and_then(move |_| -> DenoResult<Buf> {
     let builder = &mut FlatBufferBuilder::new();
 
@@ -0,0 +1,294 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+
+//! The clock of `performance.now()` and the entries of `performance.mark()`
+//! and `performance.measure()`.
+//!
+//! Without `--allow-high-precision` the clock is coarsened to milliseconds,
+//! to make timing attacks harder. Rounding alone still leaks the exact time
+//! to a program which waits for the clock to tick, so the point where it
+//! ticks to the next millisecond is random for every millisecond. The same
+//! millisecond always ticks at the same point, which keeps the clock
+//! monotonic and makes averaging readings useless.
+//! See https://w3c.github.io/hr-time/#clock-resolution
+
+use crate::errors;
+use crate::errors::DenoResult;
+use crate::msg::ErrorKind;
+use rand;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+const NANOS_PER_MILLI: u64 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EntryType {
+  Mark,
+  Measure,
+}
+
+impl EntryType {
+  pub fn parse(entry_type: &str) -> Option<Self> {
+    match entry_type {
+      "mark" => Some(EntryType::Mark),
+      "measure" => Some(EntryType::Measure),
+      _ => None,
+    }
+  }
+
+  pub fn as_str(self) -> &'static str {
+    match self {
+      EntryType::Mark => "mark",
+      EntryType::Measure => "measure",
+    }
+  }
+}
+
+/// Times are in milliseconds since the start, as in JavaScript.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PerformanceEntry {
+  pub name: String,
+  pub entry_type: EntryType,
+  pub start_time: f64,
+  pub duration: f64,
+}
+
+pub struct Performance {
+  start_time: Instant,
+  /// Keys the points where the coarsened clock ticks.
+  secret: u64,
+  /// In the order they were added.
+  entries: Mutex<Vec<PerformanceEntry>>,
+}
+
+impl Performance {
+  pub fn new() -> Self {
+    Performance {
+      start_time: Instant::now(),
+      secret: rand::random(),
+      entries: Mutex::new(Vec::new()),
+    }
+  }
+
+  /// The time since the start, coarsened unless `high_precision`.
+  pub fn now(&self, high_precision: bool) -> Duration {
+    let elapsed = self.start_time.elapsed();
+    if high_precision {
+      elapsed
+    } else {
+      coarsen(elapsed, self.secret)
+    }
+  }
+
+  fn now_millis(&self, high_precision: bool) -> f64 {
+    let now = self.now(high_precision);
+    now.as_secs() as f64 * 1e3 + f64::from(now.subsec_nanos()) / 1e6
+  }
+
+  pub fn mark(&self, name: &str, high_precision: bool) -> PerformanceEntry {
+    let entry = PerformanceEntry {
+      name: name.to_string(),
+      entry_type: EntryType::Mark,
+      start_time: self.now_millis(high_precision),
+      duration: 0.0,
+    };
+    self.entries.lock().unwrap().push(entry.clone());
+    entry
+  }
+
+  /// Measures from the mark `start_mark`, or the start, to the mark
+  /// `end_mark`, or now. For marks of the same name the last one counts.
+  pub fn measure(
+    &self,
+    name: &str,
+    start_mark: Option<&str>,
+    end_mark: Option<&str>,
+    high_precision: bool,
+  ) -> DenoResult<PerformanceEntry> {
+    let mut entries = self.entries.lock().unwrap();
+    let mark_time = |mark: &str| -> DenoResult<f64> {
+      entries
+        .iter()
+        .rev()
+        .find(|e| e.entry_type == EntryType::Mark && e.name == mark)
+        .map(|e| e.start_time)
+        .ok_or_else(|| {
+          errors::new(
+            ErrorKind::NotFound,
+            format!("There is no mark named \"{}\"", mark),
+          )
+        })
+    };
+    let start_time = match start_mark {
+      Some(mark) => mark_time(mark)?,
+      None => 0.0,
+    };
+    let end_time = match end_mark {
+      Some(mark) => mark_time(mark)?,
+      None => self.now_millis(high_precision),
+    };
+    let entry = PerformanceEntry {
+      name: name.to_string(),
+      entry_type: EntryType::Measure,
+      start_time,
+      duration: end_time - start_time,
+    };
+    entries.push(entry.clone());
+    Ok(entry)
+  }
+
+  /// The entries of the type and name given, all of them for None, sorted
+  /// by their start.
+  pub fn entries(
+    &self,
+    entry_type: Option<EntryType>,
+    name: Option<&str>,
+  ) -> Vec<PerformanceEntry> {
+    let mut entries: Vec<PerformanceEntry> = self
+      .entries
+      .lock()
+      .unwrap()
+      .iter()
+      .filter(|e| is_match(e, entry_type, name))
+      .cloned()
+      .collect();
+    // The sort is stable, entries which started together keep their order.
+    entries.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap());
+    entries
+  }
+
+  pub fn clear(&self, entry_type: EntryType, name: Option<&str>) {
+    self
+      .entries
+      .lock()
+      .unwrap()
+      .retain(|e| !is_match(e, Some(entry_type), name));
+  }
+}
+
+impl Default for Performance {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+fn is_match(
+  entry: &PerformanceEntry,
+  entry_type: Option<EntryType>,
+  name: Option<&str>,
+) -> bool {
+  entry_type.map_or(true, |t| entry.entry_type == t)
+    && name.map_or(true, |n| entry.name == n)
+}
+
+/// Rounds `elapsed` to a millisecond: down before the point where the
+/// millisecond ticks, up after it.
+fn coarsen(elapsed: Duration, secret: u64) -> Duration {
+  let nanos =
+    elapsed.as_secs() * 1_000_000_000 + u64::from(elapsed.subsec_nanos());
+  let millis = nanos / NANOS_PER_MILLI;
+  let mut hasher = DefaultHasher::new();
+  (secret, millis).hash(&mut hasher);
+  let tick = hasher.finish() % NANOS_PER_MILLI;
+  if nanos % NANOS_PER_MILLI < tick {
+    Duration::from_millis(millis)
+  } else {
+    Duration::from_millis(millis + 1)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn coarsen_to_millis() {
+    let mut last = Duration::from_millis(0);
+    let mut rounded_up = 0;
+    // Every 10 microseconds over 100 milliseconds.
+    for i in 0..10_000 {
+      let elapsed = Duration::from_micros(i * 10);
+      let coarse = coarsen(elapsed, 42);
+      assert_eq!(coarse.subsec_nanos() % NANOS_PER_MILLI as u32, 0);
+      assert!(coarse >= last);
+      assert!(coarse + Duration::from_millis(1) > elapsed);
+      assert!(coarse <= elapsed + Duration::from_millis(1));
+      if coarse > elapsed {
+        rounded_up += 1;
+      }
+      last = coarse;
+    }
+    // Neither always rounded down nor always up.
+    assert!(rounded_up > 1000 && rounded_up < 9000);
+  }
+
+  #[test]
+  fn coarsen_jitter() {
+    // The point where a millisecond ticks differs between milliseconds.
+    let ticks: Vec<u64> = (0..20)
+      .map(|millis| {
+        (0..1000)
+          .find(|micros| {
+            let elapsed = Duration::from_micros(millis * 1000 + micros);
+            coarsen(elapsed, 7) > Duration::from_millis(millis)
+          })
+          .unwrap_or(1000)
+      })
+      .collect();
+    let first = ticks[0];
+    assert!(ticks.iter().any(|tick| *tick != first));
+  }
+
+  #[test]
+  fn high_precision() {
+    let performance = Performance::new();
+    std::thread::sleep(Duration::from_millis(2));
+    let now = performance.now(true);
+    assert!(now >= Duration::from_millis(2));
+    assert!(now.subsec_nanos() % NANOS_PER_MILLI as u32 != 0);
+    let coarse = performance.now(false);
+    assert_eq!(coarse.subsec_nanos() % NANOS_PER_MILLI as u32, 0);
+  }
+
+  #[test]
+  fn mark_and_measure() {
+    let performance = Performance::new();
+    let start = performance.mark("start", true);
+    let end = performance.mark("end", true);
+    assert_eq!(end.entry_type, EntryType::Mark);
+    assert!(end.start_time >= start.start_time);
+    let measure = performance
+      .measure("between", Some("start"), Some("end"), true)
+      .unwrap();
+    assert_eq!(
+      measure,
+      PerformanceEntry {
+        name: "between".to_string(),
+        entry_type: EntryType::Measure,
+        start_time: start.start_time,
+        duration: end.start_time - start.start_time,
+      }
+    );
+    let since_start = performance.measure("all", None, None, true).unwrap();
+    assert!(since_start.duration >= end.start_time);
+    let err = performance
+      .measure("missing", Some("nope"), None, true)
+      .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    let marks = performance.entries(Some(EntryType::Mark), None);
+    assert_eq!(marks, vec![start.clone(), end.clone()]);
+    assert_eq!(performance.entries(None, Some("between")), vec![measure]);
+    assert_eq!(performance.entries(None, None).len(), 4);
+    // Sorted by their start, the measure from the start comes first.
+    assert_eq!(performance.entries(None, None)[0], since_start);
+
+    performance.clear(EntryType::Mark, Some("start"));
+    assert_eq!(performance.entries(Some(EntryType::Mark), None), vec![end]);
+    performance.clear(EntryType::Measure, None);
+    assert_eq!(performance.entries(Some(EntryType::Measure), None), vec![]);
+  }
+}
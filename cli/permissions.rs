@@ -3,13 +3,24 @@ use atty;
 
 use crate::flags::DenoFlags;
 
-use ansi_term::Style;
+use crate::ansi;
+use crate::errors;
 use crate::errors::permission_denied;
+use crate::errors::permission_denied_without_prompt;
 use crate::errors::DenoResult;
+use crate::errors::ErrorKind;
+use crate::fs as deno_fs;
+use crate::js_errors::apply_source_map;
+use crate::js_errors::SourceMapGetter;
+use deno::StackFrame;
+use std::collections::HashSet;
+use std::env;
 use std::fmt;
 use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use url::Url;
 
 /// Tri-state value for storing permission state
 pub enum PermissionAccessorState {
@@ -61,6 +72,16 @@ impl PermissionAccessor {
     }
   }
 
+  /// Resolves a pair of allow/deny flags. Deny takes precedence over allow,
+  /// which takes precedence over the default of prompting.
+  fn from_flags(allow: bool, deny: bool) -> Self {
+    if deny {
+      Self::new(PermissionAccessorState::Deny)
+    } else {
+      Self::from(allow)
+    }
+  }
+
   pub fn is_allow(&self) -> bool {
     match self.get_state() {
       PermissionAccessorState::Allow => true,
@@ -122,129 +143,504 @@ impl Default for PermissionAccessor {
   }
 }
 
+/// The state of a permission as `Deno.permissions.query()` tells it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PermissionState {
+  Granted,
+  Denied,
+  /// Access is prompted for.
+  Prompt,
+}
+
+/// Which permissions of its parent a worker keeps, the others are denied.
+pub struct WorkerPermissions {
+  pub read: bool,
+  pub write: bool,
+  pub net: bool,
+  pub env: bool,
+  pub run: bool,
+  pub high_precision: bool,
+}
+
+/// What maps the stack of the call which prompts to the script's sources.
+#[derive(Clone, Default)]
+pub struct PromptSourceMaps(pub Option<Arc<dyn SourceMapGetter + Send + Sync>>);
+
+impl fmt::Debug for PromptSourceMaps {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.pad("PromptSourceMaps")
+  }
+}
+
 #[cfg_attr(feature = "cargo-clippy", allow(stutter))]
 #[derive(Debug, Default)]
 pub struct DenoPermissions {
   // Keep in sync with src/permissions.ts
+  // The whitelists start out with the values given on the command line and
+  // grow when a prompt is answered with "allow always for this ...".
   pub allow_read: PermissionAccessor,
+  pub read_whitelist: Arc<Mutex<Vec<PathBuf>>>,
   pub allow_write: PermissionAccessor,
+  pub write_whitelist: Arc<Mutex<Vec<PathBuf>>>,
   pub allow_net: PermissionAccessor,
+  pub net_whitelist: Arc<Mutex<HashSet<String>>>,
   pub allow_env: PermissionAccessor,
+  pub env_whitelist: Arc<Mutex<HashSet<String>>>,
   pub allow_run: PermissionAccessor,
+  pub run_whitelist: Arc<Mutex<Vec<String>>>,
   pub allow_high_precision: PermissionAccessor,
   pub no_prompts: AtomicBool,
+  /// See `--prompt-stack`.
+  pub prompt_stack: bool,
+  pub source_maps: PromptSourceMaps,
 }
 
 impl DenoPermissions {
   pub fn from_flags(flags: &DenoFlags) -> Self {
+    // A deny flag also voids the corresponding whitelist.
+    let read_whitelist = if flags.deny_read {
+      vec![]
+    } else {
+      resolve_whitelist(&flags.read_whitelist)
+    };
+    let write_whitelist = if flags.deny_write {
+      vec![]
+    } else {
+      resolve_whitelist(&flags.write_whitelist)
+    };
+    let net_whitelist = if flags.deny_net {
+      HashSet::new()
+    } else {
+      flags.net_whitelist.iter().cloned().collect()
+    };
+    let run_whitelist = if flags.deny_run {
+      vec![]
+    } else {
+      flags.run_whitelist.clone()
+    };
+    let env_whitelist = if flags.deny_env {
+      HashSet::new()
+    } else {
+      flags.env_whitelist.iter().map(|k| env_var_key(k)).collect()
+    };
     Self {
-      allow_read: PermissionAccessor::from(flags.allow_read),
-      allow_write: PermissionAccessor::from(flags.allow_write),
-      allow_env: PermissionAccessor::from(flags.allow_env),
-      allow_net: PermissionAccessor::from(flags.allow_net),
-      allow_run: PermissionAccessor::from(flags.allow_run),
+      allow_read: PermissionAccessor::from_flags(
+        flags.allow_read,
+        flags.deny_read,
+      ),
+      read_whitelist: Arc::new(Mutex::new(read_whitelist)),
+      allow_write: PermissionAccessor::from_flags(
+        flags.allow_write,
+        flags.deny_write,
+      ),
+      write_whitelist: Arc::new(Mutex::new(write_whitelist)),
+      allow_env: PermissionAccessor::from_flags(
+        flags.allow_env,
+        flags.deny_env,
+      ),
+      env_whitelist: Arc::new(Mutex::new(env_whitelist)),
+      allow_net: PermissionAccessor::from_flags(
+        flags.allow_net,
+        flags.deny_net,
+      ),
+      net_whitelist: Arc::new(Mutex::new(net_whitelist)),
+      allow_run: PermissionAccessor::from_flags(
+        flags.allow_run,
+        flags.deny_run,
+      ),
+      run_whitelist: Arc::new(Mutex::new(run_whitelist)),
       allow_high_precision: PermissionAccessor::from(
         flags.allow_high_precision,
       ),
       no_prompts: AtomicBool::new(flags.no_prompts),
+      prompt_stack: flags.prompt_stack,
+      source_maps: PromptSourceMaps::default(),
+    }
+  }
+
+  /// The permissions of a worker: the ones it keeps of these, the permissions
+  /// of its parent right now. A worker never has more than its parent.
+  pub fn for_worker(&self, keep: &WorkerPermissions) -> Self {
+    fn accessor(parent: &PermissionAccessor, keep: bool) -> PermissionAccessor {
+      if keep {
+        PermissionAccessor::new(parent.get_state())
+      } else {
+        PermissionAccessor::new(PermissionAccessorState::Deny)
+      }
+    }
+    fn whitelist<T: Clone + Default>(
+      parent: &Arc<Mutex<T>>,
+      keep: bool,
+    ) -> Arc<Mutex<T>> {
+      let whitelist = if keep {
+        parent.lock().unwrap().clone()
+      } else {
+        T::default()
+      };
+      Arc::new(Mutex::new(whitelist))
+    }
+    Self {
+      allow_read: accessor(&self.allow_read, keep.read),
+      read_whitelist: whitelist(&self.read_whitelist, keep.read),
+      allow_write: accessor(&self.allow_write, keep.write),
+      write_whitelist: whitelist(&self.write_whitelist, keep.write),
+      allow_net: accessor(&self.allow_net, keep.net),
+      net_whitelist: whitelist(&self.net_whitelist, keep.net),
+      allow_env: accessor(&self.allow_env, keep.env),
+      env_whitelist: whitelist(&self.env_whitelist, keep.env),
+      allow_run: accessor(&self.allow_run, keep.run),
+      run_whitelist: whitelist(&self.run_whitelist, keep.run),
+      allow_high_precision: accessor(
+        &self.allow_high_precision,
+        keep.high_precision,
+      ),
+      no_prompts: AtomicBool::new(self.no_prompts.load(Ordering::SeqCst)),
+      prompt_stack: self.prompt_stack,
+      source_maps: self.source_maps.clone(),
     }
   }
 
+  /// Check access to run `command`, the program passed to `Deno.run()`.
+  pub fn check_run_command(&self, command: &str) -> DenoResult<()> {
+    if self.is_run_whitelisted(command) {
+      return Ok(());
+    }
+    self.check_accessor(
+      &self.allow_run,
+      "--allow-run",
+      &format!("access to run \"{}\"", command),
+      Some(("command", command)),
+      || self.run_whitelist.lock().unwrap().push(command.to_string()),
+    )
+  }
+
+  /// Check access to send signals to processes, as `Deno.kill()` does. The
+  /// commands of `--allow-run=<commands>` do not grant it.
   pub fn check_run(&self) -> DenoResult<()> {
-    match self.allow_run.get_state() {
-      PermissionAccessorState::Allow => Ok(()),
-      PermissionAccessorState::Ask => {
-        match self.try_permissions_prompt("access to run a subprocess") {
-          Err(e) => Err(e),
-          Ok(v) => {
-            self.allow_run.update_with_prompt_result(&v);
-            v.check()?;
-            Ok(())
-          }
-        }
+    self.check_accessor(
+      &self.allow_run,
+      "--allow-run",
+      "access to send signals to processes",
+      None,
+      || {},
+    )
+  }
+
+  /// Returns true if `command` refers to one of the commands passed to
+  /// `--allow-run=<commands>`. A whitelisted bare name matches the same bare
+  /// name, otherwise the command and the whitelist entry are both resolved
+  /// (relative to the current directory, or through PATH for bare names) and
+  /// the resulting paths are compared.
+  fn is_run_whitelisted(&self, command: &str) -> bool {
+    let run_whitelist = self.run_whitelist.lock().unwrap();
+    if run_whitelist.is_empty() {
+      return false;
+    }
+    if !has_path_separator(command) {
+      let name = normalize_command_name(command);
+      let matches_name = run_whitelist.iter().any(|entry| {
+        !has_path_separator(entry) && normalize_command_name(entry) == name
+      });
+      if matches_name {
+        return true;
       }
-      PermissionAccessorState::Deny => Err(permission_denied()),
+    }
+    match resolve_command(command) {
+      None => false,
+      Some(path) => run_whitelist
+        .iter()
+        .any(|entry| resolve_command(entry).map_or(false, |p| p == path)),
     }
   }
 
   pub fn check_read(&self, filename: &str) -> DenoResult<()> {
-    match self.allow_read.get_state() {
-      PermissionAccessorState::Allow => Ok(()),
-      PermissionAccessorState::Ask => match self
-        .try_permissions_prompt(&format!("read access to \"{}\"", filename))
-      {
-        Err(e) => Err(e),
-        Ok(v) => {
-          self.allow_read.update_with_prompt_result(&v);
-          v.check()?;
-          Ok(())
-        }
-      },
-      PermissionAccessorState::Deny => Err(permission_denied()),
+    if is_whitelisted(&self.read_whitelist.lock().unwrap(), filename) {
+      return Ok(());
     }
+    self.check_accessor(
+      &self.allow_read,
+      "--allow-read",
+      &format!("read access to \"{}\"", filename),
+      Some(("path", filename)),
+      || grant_path(&self.read_whitelist, filename),
+    )
   }
 
   pub fn check_write(&self, filename: &str) -> DenoResult<()> {
-    match self.allow_write.get_state() {
-      PermissionAccessorState::Allow => Ok(()),
-      PermissionAccessorState::Ask => match self
-        .try_permissions_prompt(&format!("write access to \"{}\"", filename))
-      {
-        Err(e) => Err(e),
-        Ok(v) => {
-          self.allow_write.update_with_prompt_result(&v);
-          v.check()?;
-          Ok(())
-        }
-      },
-      PermissionAccessorState::Deny => Err(permission_denied()),
+    if is_whitelisted(&self.write_whitelist.lock().unwrap(), filename) {
+      return Ok(());
     }
+    self.check_accessor(
+      &self.allow_write,
+      "--allow-write",
+      &format!("write access to \"{}\"", filename),
+      Some(("path", filename)),
+      || grant_path(&self.write_whitelist, filename),
+    )
   }
 
-  pub fn check_net(&self, domain_name: &str) -> DenoResult<()> {
-    match self.allow_net.get_state() {
-      PermissionAccessorState::Allow => Ok(()),
-      PermissionAccessorState::Ask => match self.try_permissions_prompt(
-        &format!("network access to \"{}\"", domain_name),
-      ) {
-        Err(e) => Err(e),
-        Ok(v) => {
-          self.allow_net.update_with_prompt_result(&v);
-          v.check()?;
-          Ok(())
-        }
-      },
-      PermissionAccessorState::Deny => Err(permission_denied()),
+  /// Check write access which is not about a path, such as changing the
+  /// umask. The paths of `--allow-write=<paths>` do not grant it.
+  pub fn check_write_unscoped(&self, message: &str) -> DenoResult<()> {
+    self.check_accessor(
+      &self.allow_write,
+      "--allow-write",
+      message,
+      None,
+      || {},
+    )
+  }
+
+  /// Check network access to a "host" or "host:port" pair, such as the
+  /// address given to `Deno.dial()` or `Deno.listen()`.
+  pub fn check_net(&self, host_and_port: &str) -> DenoResult<()> {
+    if self.is_net_whitelisted(host_and_port) {
+      return Ok(());
     }
+    self.check_net_inner(host_and_port, net_host(host_and_port))
+  }
+
+  /// Returns true if the "host" or "host:port" pair, or its host, was passed
+  /// to `--allow-net=<hosts>`.
+  fn is_net_whitelisted(&self, host_and_port: &str) -> bool {
+    let net_whitelist = self.net_whitelist.lock().unwrap();
+    net_whitelist.contains(host_and_port)
+      || net_whitelist.contains(net_host(host_and_port))
+  }
+
+  /// Check network access to the host of an URL, such as the one requested by
+  /// `fetch()`.
+  pub fn check_net_url(&self, url: &Url) -> DenoResult<()> {
+    let host = url.host_str().unwrap_or("");
+    let whitelisted = {
+      let net_whitelist = self.net_whitelist.lock().unwrap();
+      net_whitelist.contains(host)
+        || url.port_or_known_default().map_or(false, |port| {
+          net_whitelist.contains(&format!("{}:{}", host, port))
+        })
+    };
+    if whitelisted {
+      return Ok(());
+    }
+    self.check_net_inner(url.as_str(), host)
+  }
+
+  fn check_net_inner(&self, domain_name: &str, host: &str) -> DenoResult<()> {
+    self.check_accessor(
+      &self.allow_net,
+      "--allow-net",
+      &format!("network access to \"{}\"", domain_name),
+      if host.is_empty() {
+        None
+      } else {
+        Some(("host", host))
+      },
+      || {
+        self.net_whitelist.lock().unwrap().insert(host.to_string());
+      },
+    )
   }
 
+  /// Check access to the whole environment, such as `Deno.env()`.
   pub fn check_env(&self) -> DenoResult<()> {
-    match self.allow_env.get_state() {
+    self.check_accessor(
+      &self.allow_env,
+      "--allow-env",
+      "access to environment variables",
+      None,
+      || {},
+    )
+  }
+
+  /// Check access to a single environment variable.
+  pub fn check_env_var(&self, name: &str) -> DenoResult<()> {
+    if self.is_env_whitelisted(name) {
+      return Ok(());
+    }
+    self.check_accessor(
+      &self.allow_env,
+      "--allow-env",
+      &format!("access to environment variable \"{}\"", name),
+      Some(("variable", name)),
+      || {
+        self.env_whitelist.lock().unwrap().insert(env_var_key(name));
+      },
+    )
+  }
+
+  /// Returns true if the variable was passed to `--allow-env=<names>`.
+  /// Variable names are case-insensitive on Windows.
+  pub fn is_env_whitelisted(&self, name: &str) -> bool {
+    self.env_whitelist.lock().unwrap().contains(&env_var_key(name))
+  }
+
+  pub fn has_env_whitelist(&self) -> bool {
+    !self.env_whitelist.lock().unwrap().is_empty()
+  }
+
+  /// Checks `accessor`, prompting the user if its state is "Ask". If `scope`
+  /// is given, the kind of the resource, e.g. "path", and the resource, the
+  /// prompt offers to allow the resource for the rest of the run, in which
+  /// case `grant_scope` is called to remember it. `flag` is the flag which
+  /// allows the access, errors name it when there is no prompt, along with
+  /// the form of it which whitelists the resource.
+  fn check_accessor<F: FnOnce()>(
+    &self,
+    accessor: &PermissionAccessor,
+    flag: &str,
+    message: &str,
+    scope: Option<(&str, &str)>,
+    grant_scope: F,
+  ) -> DenoResult<()> {
+    match accessor.get_state() {
       PermissionAccessorState::Allow => Ok(()),
       PermissionAccessorState::Ask => {
-        match self.try_permissions_prompt("access to environment variables") {
+        match self.try_permissions_prompt(flag, message, scope) {
           Err(e) => Err(e),
           Ok(v) => {
-            self.allow_env.update_with_prompt_result(&v);
-            v.check()?;
+            accessor.update_with_prompt_result(&v);
+            if let PromptResult::AllowScope = v {
+              grant_scope();
+            }
+            v.check(message)?;
             Ok(())
           }
         }
       }
-      PermissionAccessorState::Deny => Err(permission_denied()),
+      PermissionAccessorState::Deny => Err(permission_denied(message)),
     }
   }
 
+  /// Whether a prompt can be answered. Without a terminal to answer on, as
+  /// when stdin is piped, it is the same as --no-prompt.
+  fn can_prompt(&self) -> bool {
+    !self.no_prompts.load(Ordering::SeqCst)
+      && atty::is(atty::Stream::Stdin)
+      && atty::is(atty::Stream::Stderr)
+  }
+
   /// Try to present the user with a permission prompt
-  /// will error with permission_denied if no_prompts is enabled
-  fn try_permissions_prompt(&self, message: &str) -> DenoResult<PromptResult> {
-    if self.no_prompts.load(Ordering::SeqCst) {
-      return Err(permission_denied());
+  /// will error with permission_denied if there can be no prompt.
+  fn try_permissions_prompt(
+    &self,
+    flag: &str,
+    message: &str,
+    scope: Option<(&str, &str)>,
+  ) -> DenoResult<PromptResult> {
+    if !self.can_prompt() {
+      let scope = scope.map(|s| s.1);
+      return Err(permission_denied_without_prompt(message, flag, scope));
+    }
+    let stack = self.script_stack();
+    permission_prompt(flag, message, scope, &stack, self.prompt_stack)
+  }
+
+  /// The frames of the script's own code in the JavaScript which is running,
+  /// innermost first, mapped to its sources. Only taken for a prompt, as it
+  /// is not cheap.
+  fn script_stack(&self) -> Vec<StackFrame> {
+    match (deno::current_stack_trace(), &self.source_maps.0) {
+      (Some(stack), Some(getter)) => {
+        apply_source_map(&stack, getter.as_ref(), false).frames
+      }
+      _ => vec![],
     }
-    if !atty::is(atty::Stream::Stdin) || !atty::is(atty::Stream::Stderr) {
-      return Err(permission_denied());
+  }
+
+  /// The accessor of the permission `name`, as JS names it, with the flag
+  /// which allows it and what it allows.
+  fn permission(
+    &self,
+    name: &str,
+  ) -> DenoResult<(&PermissionAccessor, &'static str, &'static str)> {
+    match name {
+      "read" => Ok((&self.allow_read, "--allow-read", "read access")),
+      "write" => Ok((&self.allow_write, "--allow-write", "write access")),
+      "net" => Ok((&self.allow_net, "--allow-net", "network access")),
+      "env" => Ok((
+        &self.allow_env,
+        "--allow-env",
+        "access to environment variables",
+      )),
+      "run" => {
+        Ok((&self.allow_run, "--allow-run", "access to run subprocesses"))
+      }
+      "highPrecision" => Ok((
+        &self.allow_high_precision,
+        "--allow-high-precision",
+        "high precision time measurement",
+      )),
+      _ => Err(errors::new(
+        ErrorKind::InvalidInput,
+        format!("Unknown permission \"{}\"", name),
+      )),
+    }
+  }
+
+  /// The state of the permission `name`, for `resource` if given: a path,
+  /// a host, a command or a variable, as the checks take them. Whitelisted
+  /// resources are granted. Access which would be prompted for is denied
+  /// when there can be no prompt.
+  pub fn query(
+    &self,
+    name: &str,
+    resource: Option<&str>,
+  ) -> DenoResult<PermissionState> {
+    let (accessor, _, _) = self.permission(name)?;
+    let whitelisted = |resource| match name {
+      "read" => is_whitelisted(&self.read_whitelist.lock().unwrap(), resource),
+      "write" => {
+        is_whitelisted(&self.write_whitelist.lock().unwrap(), resource)
+      }
+      "net" => self.is_net_whitelisted(resource),
+      "env" => self.is_env_whitelisted(resource),
+      "run" => self.is_run_whitelisted(resource),
+      _ => false,
     };
-    permission_prompt(message)
+    Ok(match accessor.get_state() {
+      PermissionAccessorState::Allow => PermissionState::Granted,
+      PermissionAccessorState::Deny => PermissionState::Denied,
+      PermissionAccessorState::Ask => {
+        if resource.map_or(false, whitelisted) {
+          PermissionState::Granted
+        } else if self.can_prompt() {
+          PermissionState::Prompt
+        } else {
+          PermissionState::Denied
+        }
+      }
+    })
+  }
+
+  /// Like `query()`, but prompts for access which would be prompted for, as
+  /// the checks do. The answer holds for the checks after.
+  pub fn request(
+    &self,
+    name: &str,
+    resource: Option<&str>,
+  ) -> DenoResult<PermissionState> {
+    let state = self.query(name, resource)?;
+    if state != PermissionState::Prompt {
+      return Ok(state);
+    }
+    let result = match (name, resource) {
+      ("read", Some(path)) => self.check_read(path),
+      ("write", Some(path)) => self.check_write(path),
+      ("net", Some(host)) => self.check_net(host),
+      ("env", Some(variable)) => self.check_env_var(variable),
+      ("run", Some(command)) => self.check_run_command(command),
+      _ => {
+        let (accessor, flag, message) = self.permission(name)?;
+        self.check_accessor(accessor, flag, message, None, || {})
+      }
+    };
+    match result {
+      Ok(()) => Ok(PermissionState::Granted),
+      Err(ref e) if e.kind() == ErrorKind::PermissionDenied => {
+        Ok(PermissionState::Denied)
+      }
+      Err(e) => Err(e),
+    }
   }
 
   pub fn allows_run(&self) -> bool {
@@ -271,28 +667,36 @@ impl DenoPermissions {
     self.allow_high_precision.is_allow()
   }
 
+  // Revoking also drops the whitelist, including everything granted through
+  // a prompt, so later checks prompt again (or fail under --no-prompt).
+
   pub fn revoke_run(&self) -> DenoResult<()> {
     self.allow_run.revoke();
+    self.run_whitelist.lock().unwrap().clear();
     Ok(())
   }
 
   pub fn revoke_read(&self) -> DenoResult<()> {
     self.allow_read.revoke();
+    self.read_whitelist.lock().unwrap().clear();
     Ok(())
   }
 
   pub fn revoke_write(&self) -> DenoResult<()> {
     self.allow_write.revoke();
+    self.write_whitelist.lock().unwrap().clear();
     Ok(())
   }
 
   pub fn revoke_net(&self) -> DenoResult<()> {
     self.allow_net.revoke();
+    self.net_whitelist.lock().unwrap().clear();
     Ok(())
   }
 
   pub fn revoke_env(&self) -> DenoResult<()> {
     self.allow_env.revoke();
+    self.env_whitelist.lock().unwrap().clear();
     Ok(())
   }
   pub fn revoke_high_precision(&self) -> DenoResult<()> {
@@ -301,21 +705,136 @@ impl DenoPermissions {
   }
 }
 
-/// Quad-state value for representing user input on permission prompt
+/// The host of a "host" or "host:port" pair. An IPv6 address only has a port
+/// in brackets, like "[::1]:4545", a bare one like "::1" is a host.
+fn net_host(host_and_port: &str) -> &str {
+  if host_and_port.starts_with('[') {
+    match host_and_port.find("]:") {
+      Some(end) => &host_and_port[..=end],
+      None => host_and_port,
+    }
+  } else if host_and_port.matches(':').count() == 1 {
+    host_and_port.split(':').next().unwrap()
+  } else {
+    host_and_port
+  }
+}
+
+/// Adds `filename` to a path whitelist after the user allowed it for the rest
+/// of the run.
+fn grant_path(whitelist: &Mutex<Vec<PathBuf>>, filename: &str) {
+  if let Ok(path) = deno_fs::resolve_from_cwd(Path::new(filename)) {
+    whitelist.lock().unwrap().push(path);
+  }
+}
+
+/// Resolve whitelisted paths once up front, so that later checks compare
+/// canonical paths against canonical paths.
+fn resolve_whitelist(paths: &[String]) -> Vec<PathBuf> {
+  paths
+    .iter()
+    .filter_map(|p| deno_fs::resolve_from_cwd(Path::new(p)).ok())
+    .collect()
+}
+
+/// Returns true if `filename`, once resolved, lies within one of the
+/// whitelisted directories. Resolving first means that neither `..` nor
+/// symlinks can be used to reach a file outside of the whitelist.
+fn is_whitelisted(whitelist: &[PathBuf], filename: &str) -> bool {
+  if whitelist.is_empty() {
+    return false;
+  }
+  match deno_fs::resolve_from_cwd(Path::new(filename)) {
+    Ok(path) => whitelist.iter().any(|prefix| path.starts_with(prefix)),
+    Err(_) => false,
+  }
+}
+
+#[cfg(windows)]
+const EXECUTABLE_EXTENSIONS: &[&str] = &["exe", "cmd", "bat", "com"];
+#[cfg(not(windows))]
+const EXECUTABLE_EXTENSIONS: &[&str] = &[];
+
+fn has_path_separator(command: &str) -> bool {
+  command.contains('/') || (cfg!(windows) && command.contains('\\'))
+}
+
+/// Normalizes a bare command name for whitelist lookups. On Windows names are
+/// case-insensitive and the executable extension is optional, so `git.exe`
+/// and `GIT` both become `git`.
+fn normalize_command_name(name: &str) -> String {
+  if !cfg!(windows) {
+    return name.to_string();
+  }
+  let name = name.to_lowercase();
+  for ext in EXECUTABLE_EXTENSIONS {
+    let suffix = format!(".{}", ext);
+    if name.ends_with(&suffix) {
+      return name[..name.len() - suffix.len()].to_string();
+    }
+  }
+  name
+}
+
+/// Returns `path` followed by the variants the OS would also try when
+/// spawning it, i.e. with an executable extension appended on Windows.
+fn executable_candidates(path: PathBuf) -> Vec<PathBuf> {
+  let mut candidates = vec![];
+  if path.extension().is_none() {
+    for ext in EXECUTABLE_EXTENSIONS {
+      candidates.push(path.with_extension(ext));
+    }
+  }
+  candidates.insert(0, path);
+  candidates
+}
+
+/// Resolves a command to the canonical path of the file that would be run.
+/// Paths are resolved against the current directory, bare names are looked up
+/// in PATH.
+fn resolve_command(command: &str) -> Option<PathBuf> {
+  let candidates = if has_path_separator(command) {
+    executable_candidates(PathBuf::from(command))
+  } else {
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths)
+      .flat_map(|dir| executable_candidates(dir.join(command)))
+      .collect()
+  };
+  candidates
+    .into_iter()
+    .find(|p| p.is_file())
+    .and_then(|p| deno_fs::resolve_from_cwd(&p).ok())
+}
+
+/// Normalizes an environment variable name for whitelist lookups, following
+/// the platform convention.
+fn env_var_key(name: &str) -> String {
+  if cfg!(windows) {
+    name.to_uppercase()
+  } else {
+    name.to_string()
+  }
+}
+
+/// Value for representing user input on permission prompt
 #[derive(Debug, Clone)]
 pub enum PromptResult {
   AllowAlways = 0,
   AllowOnce = 1,
   DenyOnce = 2,
   DenyAlways = 3,
+  /// Allow always, but only for the requested path, host, etc.
+  AllowScope = 4,
 }
 
 impl PromptResult {
-  /// If value is any form of deny this will error with permission_denied
-  pub fn check(&self) -> DenoResult<()> {
+  /// If value is any form of deny this will error with permission_denied,
+  /// naming the access of `message`.
+  pub fn check(&self, message: &str) -> DenoResult<()> {
     match self {
-      PromptResult::DenyOnce => Err(permission_denied()),
-      PromptResult::DenyAlways => Err(permission_denied()),
+      PromptResult::DenyOnce => Err(permission_denied(message)),
+      PromptResult::DenyAlways => Err(permission_denied(message)),
       _ => Ok(()),
     }
   }
@@ -328,29 +847,383 @@ impl fmt::Display for PromptResult {
       PromptResult::AllowOnce => f.pad("AllowOnce"),
       PromptResult::DenyOnce => f.pad("DenyOnce"),
       PromptResult::DenyAlways => f.pad("DenyAlways"),
+      PromptResult::AllowScope => f.pad("AllowScope"),
     }
   }
 }
 
-fn permission_prompt(message: &str) -> DenoResult<PromptResult> {
-  let msg = format!("⚠️  Deno requests {}. Grant? [a/y/n/d (a = allow always, y = allow once, n = deny once, d = deny always)] ", message);
-  // print to stderr so that if deno is > to a file this is still displayed.
-  eprint!("{}", Style::new().bold().paint(msg));
+/// Where the access of a prompt is asked for, e.g. " from
+/// file:///a.ts:3:7", as the innermost of `stack` is the caller in the script.
+fn call_site(stack: &[StackFrame]) -> String {
+  match stack.first() {
+    None => String::new(),
+    Some(frame) => format!(
+      " from {}:{}:{}",
+      frame.script_name,
+      frame.line + 1,
+      frame.column + 1
+    ),
+  }
+}
+
+/// Asks on stderr, so that the prompt doesn't end up in the output when stdout
+/// is redirected. The prompt names the call site of `stack`, with
+/// `full_stack` the whole stack is printed above it.
+fn permission_prompt(
+  flag: &str,
+  message: &str,
+  scope: Option<(&str, &str)>,
+  stack: &[StackFrame],
+  full_stack: bool,
+) -> DenoResult<PromptResult> {
+  let options = match scope {
+    Some((scope, _)) => format!(
+      "[a/s/y/n/d (a = allow always, s = allow always for this {}, y = allow once, n = deny once, d = deny always)]",
+      scope
+    ),
+    None => "[a/y/n/d (a = allow always, y = allow once, n = deny once, d = deny always)]".to_string(),
+  };
+  if full_stack {
+    for frame in stack {
+      eprintln!("{}", frame);
+    }
+  }
+  let msg = format!(
+    "⚠️  Deno requests {}{}. Grant? {} ",
+    message,
+    call_site(stack),
+    options
+  );
+  eprint!("{}", ansi::bold(msg));
   loop {
     let mut input = String::new();
     let stdin = io::stdin();
-    let _nread = stdin.read_line(&mut input)?;
+    let nread = stdin.read_line(&mut input)?;
+    if nread == 0 {
+      // stdin was closed before there was an answer.
+      eprintln!();
+      let scope = scope.map(|s| s.1);
+      return Err(permission_denied_without_prompt(message, flag, scope));
+    }
     let ch = input.chars().next().unwrap();
     match ch.to_ascii_lowercase() {
       'a' => return Ok(PromptResult::AllowAlways),
       'y' => return Ok(PromptResult::AllowOnce),
       'n' => return Ok(PromptResult::DenyOnce),
       'd' => return Ok(PromptResult::DenyAlways),
+      's' if scope.is_some() => return Ok(PromptResult::AllowScope),
       _ => {
         // If we don't get a recognized option try again.
-        let msg_again = format!("Unrecognized option '{}' [a/y/n/d (a = allow always, y = allow once, n = deny once, d = deny always)] ", ch);
-        eprint!("{}", Style::new().bold().paint(msg_again));
+        let msg_again = format!("Unrecognized option '{}' {} ", ch, options);
+        eprint!("{}", ansi::bold(msg_again));
       }
     };
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Creates vector of strings, Vec<String>
+  macro_rules! svec {
+      ($($x:expr),*) => (vec![$($x.to_string()),*]);
+  }
+
+  #[test]
+  fn check_net_whitelist() {
+    let perms = DenoPermissions::from_flags(&DenoFlags {
+      net_whitelist: svec!["localhost", "deno.land", "github.com:443"],
+      no_prompts: true,
+      ..Default::default()
+    });
+
+    assert!(perms.check_net("localhost").is_ok());
+    assert!(perms.check_net("localhost:4545").is_ok());
+    assert!(perms.check_net("deno.land:8000").is_ok());
+    assert!(perms.check_net("github.com:443").is_ok());
+    assert!(perms.check_net("github.com:80").is_err());
+    assert!(perms.check_net("example.com").is_err());
+
+    let url = |s: &str| Url::parse(s).unwrap();
+    assert!(perms.check_net_url(&url("http://localhost:4545/")).is_ok());
+    assert!(perms.check_net_url(&url("https://deno.land/std/")).is_ok());
+    assert!(perms.check_net_url(&url("https://github.com/x")).is_ok());
+    assert!(perms.check_net_url(&url("http://github.com/x")).is_err());
+    assert!(perms.check_net_url(&url("https://example.com/")).is_err());
+  }
+
+  #[test]
+  fn net_host_and_port() {
+    assert_eq!(net_host("deno.land"), "deno.land");
+    assert_eq!(net_host("deno.land:443"), "deno.land");
+    assert_eq!(net_host("127.0.0.1:4545"), "127.0.0.1");
+    assert_eq!(net_host("[::1]:4545"), "[::1]");
+    assert_eq!(net_host("[::1]"), "[::1]");
+    assert_eq!(net_host("::1"), "::1");
+    assert_eq!(net_host("fe80::1:4545"), "fe80::1:4545");
+  }
+
+  #[test]
+  fn check_read_write_whitelist() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let allowed = dir.path().join("allowed");
+    let outside = dir.path().join("outside");
+    std::fs::create_dir(&allowed).unwrap();
+    std::fs::create_dir(&outside).unwrap();
+    std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+
+    let allowed_str = allowed.to_str().unwrap();
+    let perms = DenoPermissions::from_flags(&DenoFlags {
+      read_whitelist: svec![allowed_str],
+      write_whitelist: svec![allowed_str],
+      no_prompts: true,
+      ..Default::default()
+    });
+
+    let path = |p: &PathBuf| p.to_str().unwrap().to_string();
+    assert!(perms.check_read(&path(&allowed)).is_ok());
+    assert!(perms.check_read(&path(&allowed.join("a/b.txt"))).is_ok());
+    assert!(perms.check_write(&path(&allowed.join("new.txt"))).is_ok());
+    let secret = path(&outside.join("secret.txt"));
+    assert!(perms.check_read(&secret).is_err());
+    assert!(perms.check_write(&path(&outside.join("new.txt"))).is_err());
+    assert!(perms.check_write_unscoped("change the umask").is_err());
+    // A sibling directory sharing the whitelisted prefix is not whitelisted.
+    assert!(perms.check_read(&format!("{}-2/x", allowed_str)).is_err());
+    // ".." must not escape the whitelisted directory.
+    let escape = allowed.join("..").join("outside").join("secret.txt");
+    assert!(perms.check_read(&path(&escape)).is_err());
+    let escape = allowed.join("new").join("..").join("..").join("x");
+    assert!(perms.check_write(&path(&escape)).is_err());
+
+    // Symlinks resolving outside of the whitelist are rejected.
+    #[cfg(unix)]
+    {
+      let link = allowed.join("link");
+      std::os::unix::fs::symlink(&outside, &link).unwrap();
+      assert!(perms.check_read(&path(&link.join("secret.txt"))).is_err());
+      assert!(perms.check_write(&path(&link.join("new.txt"))).is_err());
+    }
+  }
+
+  #[test]
+  fn deny_overrides_allow() {
+    let perms = DenoPermissions::from_flags(&DenoFlags {
+      allow_read: true,
+      allow_write: true,
+      allow_net: true,
+      allow_env: true,
+      allow_run: true,
+      deny_net: true,
+      deny_run: true,
+      ..Default::default()
+    });
+    assert!(perms.check_read("/a.txt").is_ok());
+    assert!(perms.check_write("/a.txt").is_ok());
+    assert!(perms.check_env().is_ok());
+    assert!(perms.check_net("deno.land:443").is_err());
+    assert!(perms.check_run_command("ls").is_err());
+    assert!(!perms.allows_net());
+    assert!(!perms.allows_run());
+  }
+
+  #[test]
+  fn deny_overrides_whitelist() {
+    let perms = DenoPermissions::from_flags(&DenoFlags {
+      net_whitelist: svec!["deno.land"],
+      read_whitelist: svec!["/"],
+      deny_net: true,
+      deny_read: true,
+      ..Default::default()
+    });
+    assert!(perms.check_net("deno.land").is_err());
+    assert!(perms.check_read("/a.txt").is_err());
+  }
+
+  #[test]
+  fn check_env_whitelist() {
+    let perms = DenoPermissions::from_flags(&DenoFlags {
+      env_whitelist: svec!["HOME", "CI"],
+      no_prompts: true,
+      ..Default::default()
+    });
+    assert!(perms.check_env_var("HOME").is_ok());
+    assert!(perms.check_env_var("CI").is_ok());
+    assert!(perms.check_env_var("SECRET_TOKEN").is_err());
+    assert!(perms.check_env().is_err());
+    assert_eq!(perms.is_env_whitelisted("home"), cfg!(windows));
+
+    let perms = DenoPermissions::from_flags(&DenoFlags {
+      env_whitelist: svec!["HOME"],
+      deny_env: true,
+      ..Default::default()
+    });
+    assert!(perms.check_env_var("HOME").is_err());
+  }
+
+  #[test]
+  fn check_run_whitelist() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let script = dir.path().join("script.sh");
+    std::fs::write(&script, "").unwrap();
+    let script_str = script.to_str().unwrap();
+
+    let perms = DenoPermissions::from_flags(&DenoFlags {
+      run_whitelist: svec!["git", script_str],
+      no_prompts: true,
+      ..Default::default()
+    });
+    assert!(perms.check_run_command("git").is_ok());
+    assert!(perms.check_run_command("cargo").is_err());
+    assert!(perms.check_run().is_err());
+    assert!(perms.check_run_command(script_str).is_ok());
+    let relative = dir.path().join("x").join("..").join("script.sh");
+    assert!(perms.check_run_command(relative.to_str().unwrap()).is_ok());
+    // A different file named like a whitelisted command is not whitelisted.
+    let fake_git = dir.path().join("git");
+    std::fs::write(&fake_git, "").unwrap();
+    assert!(perms.check_run_command(fake_git.to_str().unwrap()).is_err());
+    if cfg!(windows) {
+      assert!(perms.check_run_command("GIT.exe").is_ok());
+    }
+
+    let perms = DenoPermissions::from_flags(&DenoFlags {
+      run_whitelist: svec!["git"],
+      deny_run: true,
+      ..Default::default()
+    });
+    assert!(perms.check_run_command("git").is_err());
+  }
+
+  #[test]
+  fn revoke_drops_whitelist() {
+    let perms = DenoPermissions::from_flags(&DenoFlags {
+      allow_read: true,
+      net_whitelist: svec!["deno.land"],
+      env_whitelist: svec!["HOME"],
+      run_whitelist: svec!["git"],
+      no_prompts: true,
+      ..Default::default()
+    });
+    assert!(perms.check_read("/a.txt").is_ok());
+    assert!(perms.check_net("deno.land:443").is_ok());
+    assert!(perms.check_env_var("HOME").is_ok());
+    perms.revoke_read().unwrap();
+    perms.revoke_net().unwrap();
+    perms.revoke_env().unwrap();
+    perms.revoke_run().unwrap();
+    assert!(perms.check_read("/a.txt").is_err());
+    assert!(perms.check_net("deno.land:443").is_err());
+    assert!(perms.check_env_var("HOME").is_err());
+    assert!(perms.check_run_command("git").is_err());
+  }
+
+  #[test]
+  fn check_net_bare_flag() {
+    let perms = DenoPermissions::from_flags(&DenoFlags {
+      allow_net: true,
+      no_prompts: true,
+      ..Default::default()
+    });
+    assert!(perms.check_net("example.com:80").is_ok());
+    assert!(
+      perms
+        .check_net_url(&Url::parse("https://example.com/").unwrap())
+        .is_ok()
+    );
+  }
+
+  #[test]
+  fn query_permissions() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let allowed = dir.path().to_str().unwrap();
+    let inside = dir.path().join("x");
+    let perms = DenoPermissions::from_flags(&DenoFlags {
+      allow_env: true,
+      deny_run: true,
+      read_whitelist: svec![allowed],
+      net_whitelist: svec!["deno.land"],
+      no_prompts: true,
+      ..Default::default()
+    });
+    let query = |name, resource| perms.query(name, resource).unwrap();
+    assert_eq!(query("env", None), PermissionState::Granted);
+    assert_eq!(query("env", Some("HOME")), PermissionState::Granted);
+    assert_eq!(query("run", Some("git")), PermissionState::Denied);
+    let inside = inside.to_str().unwrap();
+    assert_eq!(query("read", Some(inside)), PermissionState::Granted);
+    assert_eq!(query("read", Some("/etc/passwd")), PermissionState::Denied);
+    assert_eq!(query("read", None), PermissionState::Denied);
+    assert_eq!(query("net", Some("deno.land:443")), PermissionState::Granted);
+    assert_eq!(query("net", Some("example.com")), PermissionState::Denied);
+    assert!(perms.query("disk", None).is_err());
+    // Without a prompt nothing more is granted, nor does it block.
+    let request = |name, resource| perms.request(name, resource).unwrap();
+    assert_eq!(request("write", None), PermissionState::Denied);
+    assert_eq!(request("net", Some("deno.land")), PermissionState::Granted);
+    assert!(perms.check_write("/a.txt").is_err());
+
+    perms.revoke_read().unwrap();
+    assert_eq!(query("read", Some(inside)), PermissionState::Denied);
+  }
+
+  #[test]
+  fn prompt_call_site() {
+    let frame = |script_name: &str, line, column| StackFrame {
+      line,
+      column,
+      script_name: script_name.to_string(),
+      function_name: "f".to_string(),
+      is_eval: false,
+      is_constructor: false,
+      is_wasm: false,
+    };
+    assert_eq!(call_site(&[]), "");
+    let stack = [
+      frame("file:///a/mod.ts", 2, 6),
+      frame("https://deno.land/x/b.ts", 10, 0),
+    ];
+    // The positions are zero indexed.
+    assert_eq!(call_site(&stack), " from file:///a/mod.ts:3:7");
+  }
+
+  #[test]
+  fn worker_permissions() {
+    let parent = DenoPermissions::from_flags(&DenoFlags {
+      allow_read: true,
+      net_whitelist: svec!["deno.land"],
+      env_whitelist: svec!["HOME"],
+      no_prompts: true,
+      ..Default::default()
+    });
+    let keep_all = WorkerPermissions {
+      read: true,
+      write: true,
+      net: true,
+      env: true,
+      run: true,
+      high_precision: true,
+    };
+    let worker = parent.for_worker(&keep_all);
+    assert!(worker.check_read("/a.txt").is_ok());
+    assert!(worker.check_net("deno.land:443").is_ok());
+    assert!(worker.check_env_var("HOME").is_ok());
+    // Keeping a permission the parent does not have grants nothing.
+    assert!(worker.check_write("/a.txt").is_err());
+    assert!(worker.check_net("example.com:80").is_err());
+    assert!(worker.check_run_command("git").is_err());
+
+    let worker = parent.for_worker(&WorkerPermissions {
+      read: false,
+      net: false,
+      ..keep_all
+    });
+    assert!(worker.check_read("/a.txt").is_err());
+    assert!(worker.check_net("deno.land:443").is_err());
+    assert!(worker.check_env_var("HOME").is_ok());
+    // The worker can't affect the permissions of its parent.
+    worker.revoke_env().unwrap();
+    assert!(parent.check_env_var("HOME").is_ok());
+  }
+}
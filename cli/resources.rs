@@ -12,9 +12,16 @@ use crate::errors;
 use crate::errors::bad_resource;
 use crate::errors::DenoError;
 use crate::errors::DenoResult;
+use crate::fs_events::FsEvent;
+use crate::fs_events::FsWatcher;
 use crate::http_body::HttpBody;
+use crate::http_server;
+use crate::http_server::HttpServer;
+use crate::http_server::Responder;
 use crate::repl::Repl;
+use crate::signal::SignalStream;
 use crate::state::WorkerChannels;
+use crate::websocket::WebSocket;
 
 use deno::Buf;
 
@@ -27,7 +34,7 @@ use hyper;
 use std;
 use std::collections::HashMap;
 use std::io::{Error, Read, Seek, SeekFrom, Write};
-use std::net::{Shutdown, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr};
 use std::process::ExitStatus;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
@@ -37,6 +44,9 @@ use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
 use tokio::sync::mpsc;
 use tokio_process;
+use tokio_rustls::client::TlsStream as ClientTlsStream;
+use tokio_rustls::server::TlsStream as ServerTlsStream;
+use tokio_rustls::TlsAcceptor;
 
 pub type ResourceId = u32; // Sometimes referred to RID.
 
@@ -46,6 +56,10 @@ type ResourceTable = HashMap<ResourceId, Repr>;
 
 #[cfg(not(windows))]
 use std::os::unix::io::FromRawFd;
+#[cfg(unix)]
+use std::os::unix::net::SocketAddr as UnixSocketAddr;
+#[cfg(unix)]
+use tokio::net::UnixStream;
 
 #[cfg(windows)]
 use std::os::windows::io::FromRawHandle;
@@ -83,14 +97,28 @@ enum Repr {
   Stdout(tokio::fs::File),
   Stderr(tokio::io::Stderr),
   FsFile(tokio::fs::File),
-  // Since TcpListener might be closed while there is a pending accept task,
-  // we need to track the task so that when the listener is closed,
-  // this pending task could be notified and die.
+  // Since TcpListener might be closed while there are pending accept tasks,
+  // we need to track the tasks so that when the listener is closed,
+  // these pending tasks could be notified and die.
   // Currently TcpListener itself does not take care of this issue.
   // See: https://github.com/tokio-rs/tokio/issues/846
-  TcpListener(tokio::net::TcpListener, Option<futures::task::Task>),
+  // It also only wakes the last task which polled it, so the others are
+  // woken to poll again whenever a connection is accepted.
+  TcpListener(tokio::net::TcpListener, Vec<futures::task::Task>),
   TcpStream(tokio::net::TcpStream),
+  // Like TcpListener, with the configuration of the handshakes of the
+  // accepted connections.
+  TlsListener(
+    tokio::net::TcpListener,
+    Vec<futures::task::Task>,
+    TlsAcceptor,
+  ),
+  // Boxed like Child, the sessions are large.
+  ClientTlsStream(Box<ClientTlsStream<TcpStream>>),
+  ServerTlsStream(Box<ServerTlsStream<TcpStream>>),
   HttpBody(HttpBody),
+  HttpServer(Arc<Mutex<HttpServer>>),
+  HttpResponder(Responder),
   Repl(Arc<Mutex<Repl>>),
   // Enum size is bounded by the largest variant.
   // Use `Box` around large `Child` struct.
@@ -100,6 +128,21 @@ enum Repr {
   ChildStdout(tokio_process::ChildStdout),
   ChildStderr(tokio_process::ChildStderr),
   Worker(WorkerChannels),
+  Signal(SignalStream),
+  FsEvents(FsWatcher),
+  WebSocket(Box<WebSocket>),
+  // Like TcpListener, with the tasks of the pending sends and receives.
+  UdpSocket(tokio::net::UdpSocket, Vec<futures::task::Task>),
+  // Like TcpListener, with the path of the socket file, which is removed
+  // when the listener is closed.
+  #[cfg(unix)]
+  UnixListener(
+    tokio::net::UnixListener,
+    Vec<futures::task::Task>,
+    std::path::PathBuf,
+  ),
+  #[cfg(unix)]
+  UnixStream(UnixStream),
 }
 
 /// If the given rid is open, this returns the type of resource, E.G. "worker".
@@ -118,6 +161,11 @@ pub fn table_entries() -> Vec<(u32, String)> {
     .collect()
 }
 
+/// The number of open resources, cheaper than `table_entries().len()`.
+pub fn table_len() -> usize {
+  RESOURCE_TABLE.lock().unwrap().len()
+}
+
 #[test]
 fn test_table_entries() {
   let mut entries = table_entries();
@@ -135,13 +183,26 @@ fn inspect_repr(repr: &Repr) -> String {
     Repr::FsFile(_) => "fsFile",
     Repr::TcpListener(_, _) => "tcpListener",
     Repr::TcpStream(_) => "tcpStream",
+    Repr::TlsListener(_, _, _) => "tlsListener",
+    Repr::ClientTlsStream(_) => "clientTlsStream",
+    Repr::ServerTlsStream(_) => "serverTlsStream",
     Repr::HttpBody(_) => "httpBody",
+    Repr::HttpServer(_) => "httpServer",
+    Repr::HttpResponder(_) => "httpResponder",
     Repr::Repl(_) => "repl",
     Repr::Child(_) => "child",
     Repr::ChildStdin(_) => "childStdin",
     Repr::ChildStdout(_) => "childStdout",
     Repr::ChildStderr(_) => "childStderr",
     Repr::Worker(_) => "worker",
+    Repr::Signal(_) => "signal",
+    Repr::FsEvents(_) => "fsEvents",
+    Repr::WebSocket(_) => "webSocket",
+    Repr::UdpSocket(_, _) => "udpSocket",
+    #[cfg(unix)]
+    Repr::UnixListener(_, _, _) => "unixListener",
+    #[cfg(unix)]
+    Repr::UnixStream(_) => "unixStream",
   };
 
   String::from(h_repr)
@@ -156,27 +217,91 @@ pub struct Resource {
 
 impl Resource {
   // TODO Should it return a Resource instead of net::TcpStream?
-  pub fn poll_accept(&mut self) -> Poll<(TcpStream, SocketAddr), Error> {
+  pub fn poll_accept(&mut self) -> Poll<(TcpStream, SocketAddr), DenoError> {
     let mut table = RESOURCE_TABLE.lock().unwrap();
     let maybe_repr = table.get_mut(&self.rid);
     match maybe_repr {
-      None => Err(std::io::Error::new(
-        std::io::ErrorKind::Other,
-        "Listener has been closed",
-      )),
+      // The listener has been closed.
+      None => Err(bad_resource()),
       Some(repr) => match repr {
-        Repr::TcpListener(ref mut s, _) => s.poll_accept(),
-        _ => panic!("Cannot accept"),
+        Repr::TcpListener(ref mut s, ref mut tasks)
+        | Repr::TlsListener(ref mut s, ref mut tasks, _) => {
+          track_task(tasks, s.poll_accept())
+        }
+        _ => Err(bad_resource()),
       },
     }
   }
 
+  #[cfg(unix)]
+  pub fn poll_accept_unix(
+    &mut self,
+  ) -> Poll<(UnixStream, UnixSocketAddr), DenoError> {
+    let mut table = RESOURCE_TABLE.lock().unwrap();
+    match table.get_mut(&self.rid) {
+      Some(Repr::UnixListener(ref mut s, ref mut tasks, _)) => {
+        track_task(tasks, s.poll_accept())
+      }
+      // Closed while waiting for a connection.
+      _ => Err(bad_resource()),
+    }
+  }
+
+  pub fn poll_recv_from(
+    &mut self,
+    buf: &mut [u8],
+  ) -> Poll<(usize, SocketAddr), DenoError> {
+    let mut table = RESOURCE_TABLE.lock().unwrap();
+    match table.get_mut(&self.rid) {
+      Some(Repr::UdpSocket(ref mut s, ref mut tasks)) => {
+        track_task(tasks, s.poll_recv_from(buf))
+      }
+      // Closed while waiting for a datagram.
+      _ => Err(bad_resource()),
+    }
+  }
+
+  pub fn poll_send_to(
+    &mut self,
+    buf: &[u8],
+    addr: &SocketAddr,
+  ) -> Poll<usize, DenoError> {
+    let mut table = RESOURCE_TABLE.lock().unwrap();
+    match table.get_mut(&self.rid) {
+      Some(Repr::UdpSocket(ref mut s, ref mut tasks)) => {
+        track_task(tasks, s.poll_send_to(buf, addr))
+      }
+      _ => Err(bad_resource()),
+    }
+  }
+
   // close(2) is done by dropping the value. Therefore we just need to remove
   // the resource from the RESOURCE_TABLE.
   pub fn close(&self) {
-    let mut table = RESOURCE_TABLE.lock().unwrap();
-    let r = table.remove(&self.rid);
+    // An HTTP server locks the table while it is locked itself.
+    let r = RESOURCE_TABLE.lock().unwrap().remove(&self.rid);
     assert!(r.is_some());
+    // The pending accepts, sends and receives fail with a bad resource now.
+    match r {
+      Some(Repr::TcpListener(_, tasks))
+      | Some(Repr::TlsListener(_, tasks, _))
+      | Some(Repr::UdpSocket(_, tasks)) => {
+        for task in tasks {
+          task.notify();
+        }
+      }
+      Some(Repr::HttpServer(server)) => server.lock().unwrap().close(),
+      #[cfg(unix)]
+      Some(Repr::UnixListener(listener, tasks, path)) => {
+        drop(listener);
+        for task in tasks {
+          task.notify();
+        }
+        // Nobody can connect to it anymore, another listener can bind to it.
+        let _ = std::fs::remove_file(path);
+      }
+      _ => {}
+    }
   }
 
   pub fn shutdown(&mut self, how: Shutdown) -> Result<(), DenoError> {
@@ -188,6 +313,17 @@ impl Resource {
         Repr::TcpStream(ref mut f) => {
           TcpStream::shutdown(f, how).map_err(DenoError::from)
         }
+        // The TCP stream is shut down without sending a close_notify.
+        Repr::ClientTlsStream(ref mut f) => {
+          TcpStream::shutdown(f.get_mut().0, how).map_err(DenoError::from)
+        }
+        Repr::ServerTlsStream(ref mut f) => {
+          TcpStream::shutdown(f.get_mut().0, how).map_err(DenoError::from)
+        }
+        #[cfg(unix)]
+        Repr::UnixStream(ref mut f) => {
+          UnixStream::shutdown(f, how).map_err(DenoError::from)
+        }
         _ => panic!("Cannot shutdown"),
       },
     }
@@ -205,11 +341,16 @@ impl AsyncRead for Resource {
     let mut table = RESOURCE_TABLE.lock().unwrap();
     let maybe_repr = table.get_mut(&self.rid);
     match maybe_repr {
-      None => panic!("bad rid"),
+      // Closed while waiting to read.
+      None => Err(bad_resource().into_io_error()),
       Some(repr) => match repr {
         Repr::FsFile(ref mut f) => f.poll_read(buf),
         Repr::Stdin(ref mut f) => f.poll_read(buf),
         Repr::TcpStream(ref mut f) => f.poll_read(buf),
+        Repr::ClientTlsStream(ref mut f) => f.poll_read(buf),
+        Repr::ServerTlsStream(ref mut f) => f.poll_read(buf),
+        #[cfg(unix)]
+        Repr::UnixStream(ref mut f) => f.poll_read(buf),
         Repr::HttpBody(ref mut f) => f.poll_read(buf),
         Repr::ChildStdout(ref mut f) => f.poll_read(buf),
         Repr::ChildStderr(ref mut f) => f.poll_read(buf),
@@ -234,12 +375,17 @@ impl AsyncWrite for Resource {
     let mut table = RESOURCE_TABLE.lock().unwrap();
     let maybe_repr = table.get_mut(&self.rid);
     match maybe_repr {
-      None => panic!("bad rid"),
+      // Closed while waiting to write.
+      None => Err(bad_resource().into_io_error()),
       Some(repr) => match repr {
         Repr::FsFile(ref mut f) => f.poll_write(buf),
         Repr::Stdout(ref mut f) => f.poll_write(buf),
         Repr::Stderr(ref mut f) => f.poll_write(buf),
         Repr::TcpStream(ref mut f) => f.poll_write(buf),
+        Repr::ClientTlsStream(ref mut f) => f.poll_write(buf),
+        Repr::ServerTlsStream(ref mut f) => f.poll_write(buf),
+        #[cfg(unix)]
+        Repr::UnixStream(ref mut f) => f.poll_write(buf),
         Repr::ChildStdin(ref mut f) => f.poll_write(buf),
         _ => panic!("Cannot write"),
       },
@@ -268,7 +414,7 @@ pub fn add_fs_file(fs_file: tokio::fs::File) -> Resource {
 pub fn add_tcp_listener(listener: tokio::net::TcpListener) -> Resource {
   let rid = new_rid();
   let mut tg = RESOURCE_TABLE.lock().unwrap();
-  let r = tg.insert(rid, Repr::TcpListener(listener, None));
+  let r = tg.insert(rid, Repr::TcpListener(listener, Vec::new()));
   assert!(r.is_none());
   Resource { rid }
 }
@@ -281,6 +427,72 @@ pub fn add_tcp_stream(stream: tokio::net::TcpStream) -> Resource {
   Resource { rid }
 }
 
+pub fn add_tls_listener(
+  listener: tokio::net::TcpListener,
+  acceptor: TlsAcceptor,
+) -> Resource {
+  let rid = new_rid();
+  let mut tg = RESOURCE_TABLE.lock().unwrap();
+  let r = tg.insert(rid, Repr::TlsListener(listener, Vec::new(), acceptor));
+  assert!(r.is_none());
+  Resource { rid }
+}
+
+/// The configuration of the handshakes of a resource of `add_tls_listener()`.
+pub fn tls_acceptor(rid: ResourceId) -> DenoResult<TlsAcceptor> {
+  let table = RESOURCE_TABLE.lock().unwrap();
+  match table.get(&rid) {
+    Some(Repr::TlsListener(_, _, acceptor)) => Ok(acceptor.clone()),
+    _ => Err(bad_resource()),
+  }
+}
+
+pub fn add_client_tls_stream(stream: ClientTlsStream<TcpStream>) -> Resource {
+  let rid = new_rid();
+  let mut tg = RESOURCE_TABLE.lock().unwrap();
+  let r = tg.insert(rid, Repr::ClientTlsStream(Box::new(stream)));
+  assert!(r.is_none());
+  Resource { rid }
+}
+
+pub fn add_server_tls_stream(stream: ServerTlsStream<TcpStream>) -> Resource {
+  let rid = new_rid();
+  let mut tg = RESOURCE_TABLE.lock().unwrap();
+  let r = tg.insert(rid, Repr::ServerTlsStream(Box::new(stream)));
+  assert!(r.is_none());
+  Resource { rid }
+}
+
+#[cfg(unix)]
+pub fn add_unix_listener(
+  listener: tokio::net::UnixListener,
+  path: std::path::PathBuf,
+) -> Resource {
+  let rid = new_rid();
+  let mut tg = RESOURCE_TABLE.lock().unwrap();
+  let r = tg.insert(rid, Repr::UnixListener(listener, Vec::new(), path));
+  assert!(r.is_none());
+  Resource { rid }
+}
+
+/// Waits for the next connection of a resource of `add_unix_listener()`.
+#[cfg(unix)]
+pub fn accept_unix(
+  rid: ResourceId,
+) -> impl Future<Item = (UnixStream, UnixSocketAddr), Error = DenoError> {
+  let mut resource = Resource { rid };
+  futures::future::poll_fn(move || resource.poll_accept_unix())
+}
+
+#[cfg(unix)]
+pub fn add_unix_stream(stream: UnixStream) -> Resource {
+  let rid = new_rid();
+  let mut tg = RESOURCE_TABLE.lock().unwrap();
+  let r = tg.insert(rid, Repr::UnixStream(stream));
+  assert!(r.is_none());
+  Resource { rid }
+}
+
 pub fn add_hyper_body(body: hyper::Body) -> Resource {
   let rid = new_rid();
   let mut tg = RESOURCE_TABLE.lock().unwrap();
@@ -290,6 +502,91 @@ pub fn add_hyper_body(body: hyper::Body) -> Resource {
   Resource { rid }
 }
 
+pub fn add_http_server(server: HttpServer) -> Resource {
+  let rid = new_rid();
+  let mut tg = RESOURCE_TABLE.lock().unwrap();
+  let r = tg.insert(rid, Repr::HttpServer(Arc::new(Mutex::new(server))));
+  assert!(r.is_none());
+  Resource { rid }
+}
+
+/// The server is polled outside of the table, as it accepts from its
+/// listener.
+pub fn get_http_server(rid: ResourceId) -> DenoResult<Arc<Mutex<HttpServer>>> {
+  let table = RESOURCE_TABLE.lock().unwrap();
+  match table.get(&rid) {
+    Some(Repr::HttpServer(server)) => Ok(server.clone()),
+    _ => Err(bad_resource()),
+  }
+}
+
+/// The resource of the response to a request of an HTTP server, used once.
+pub fn add_http_responder(responder: Responder) -> Resource {
+  let rid = new_rid();
+  let mut tg = RESOURCE_TABLE.lock().unwrap();
+  let r = tg.insert(rid, Repr::HttpResponder(responder));
+  assert!(r.is_none());
+  Resource { rid }
+}
+
+/// Sends `response` with the responder `rid`, which is removed.
+pub fn respond(
+  rid: ResourceId,
+  response: hyper::Response<hyper::Body>,
+) -> DenoResult<()> {
+  let mut table = RESOURCE_TABLE.lock().unwrap();
+  match table.get(&rid) {
+    Some(Repr::HttpResponder(_)) => {}
+    _ => return Err(bad_resource()),
+  }
+  match table.remove(&rid) {
+    Some(Repr::HttpResponder(responder)) => responder
+      .send(response)
+      .map_err(|_| http_server::respond_error("the connection is closed")),
+    _ => unreachable!(),
+  }
+}
+
+/// The size of the chunks of `read_stream()`.
+const READ_STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The chunks read from a resource until its end, see `read_stream()`.
+pub struct ReadStream {
+  resource: Resource,
+  buf: Vec<u8>,
+}
+
+impl Stream for ReadStream {
+  type Item = Vec<u8>;
+  type Error = DenoError;
+
+  fn poll(&mut self) -> Poll<Option<Vec<u8>>, DenoError> {
+    if self.buf.is_empty() {
+      self.buf = vec![0; READ_STREAM_CHUNK_SIZE];
+    }
+    let nread = try_ready!(self.resource.poll_read(&mut self.buf));
+    if nread == 0 {
+      return Ok(futures::Async::Ready(None));
+    }
+    let mut chunk = std::mem::replace(&mut self.buf, Vec::new());
+    chunk.truncate(nread);
+    Ok(futures::Async::Ready(Some(chunk)))
+  }
+}
+
+/// Reads the resource `rid` chunk by chunk, e.g. to stream a file as the
+/// body of a request. Only one chunk is in memory at a time, the resource
+/// stays open.
+pub fn read_stream(rid: ResourceId) -> DenoResult<ReadStream> {
+  match lookup(rid) {
+    Some(resource) => Ok(ReadStream {
+      resource,
+      buf: Vec::new(),
+    }),
+    None => Err(bad_resource()),
+  }
+}
+
 pub fn add_repl(repl: Repl) -> Resource {
   let rid = new_rid();
   let mut tg = RESOURCE_TABLE.lock().unwrap();
@@ -374,6 +671,235 @@ pub fn get_message_stream_from_worker(rid: ResourceId) -> WorkerReceiverStream {
   WorkerReceiverStream { rid }
 }
 
+pub fn add_signal(stream: SignalStream) -> Resource {
+  let rid = new_rid();
+  let mut tg = RESOURCE_TABLE.lock().unwrap();
+  let r = tg.insert(rid, Repr::Signal(stream));
+  assert!(r.is_none());
+  Resource { rid }
+}
+
+pub struct SignalPoll {
+  rid: ResourceId,
+}
+
+impl Future for SignalPoll {
+  /// Whether the signal stream is done, e.g. because it was closed.
+  type Item = bool;
+  type Error = DenoError;
+
+  fn poll(&mut self) -> Poll<bool, DenoError> {
+    let mut table = RESOURCE_TABLE.lock().unwrap();
+    let maybe_repr = table.get_mut(&self.rid);
+    match maybe_repr {
+      Some(Repr::Signal(ref mut s)) => s.poll_signal().map_err(DenoError::from),
+      // Closed while waiting for the signal.
+      None => Ok(true.into()),
+      _ => Err(bad_resource()),
+    }
+  }
+}
+
+/// Waits for the next signal of a resource of `add_signal()`.
+pub fn poll_signal(rid: ResourceId) -> DenoResult<SignalPoll> {
+  let table = RESOURCE_TABLE.lock().unwrap();
+  match table.get(&rid) {
+    Some(Repr::Signal(_)) => Ok(SignalPoll { rid }),
+    _ => Err(bad_resource()),
+  }
+}
+
+pub fn add_fs_watcher(watcher: FsWatcher) -> Resource {
+  let rid = new_rid();
+  let mut tg = RESOURCE_TABLE.lock().unwrap();
+  let r = tg.insert(rid, Repr::FsEvents(watcher));
+  assert!(r.is_none());
+  Resource { rid }
+}
+
+pub struct FsEventsPoll {
+  rid: ResourceId,
+}
+
+impl Future for FsEventsPoll {
+  /// The next batch of events, or None if the watcher was closed.
+  type Item = Option<Vec<FsEvent>>;
+  type Error = DenoError;
+
+  fn poll(&mut self) -> Poll<Option<Vec<FsEvent>>, DenoError> {
+    let mut table = RESOURCE_TABLE.lock().unwrap();
+    let maybe_repr = table.get_mut(&self.rid);
+    match maybe_repr {
+      Some(Repr::FsEvents(ref mut w)) => w.poll_events().map(|a| a.map(Some)),
+      // Closed while waiting for events.
+      None => Ok(None.into()),
+      _ => Err(bad_resource()),
+    }
+  }
+}
+
+/// Waits for the next events of a resource of `add_fs_watcher()`.
+pub fn poll_fs_events(rid: ResourceId) -> DenoResult<FsEventsPoll> {
+  let table = RESOURCE_TABLE.lock().unwrap();
+  match table.get(&rid) {
+    Some(Repr::FsEvents(_)) => Ok(FsEventsPoll { rid }),
+    _ => Err(bad_resource()),
+  }
+}
+
+pub fn add_websocket(websocket: WebSocket) -> Resource {
+  let rid = new_rid();
+  let mut tg = RESOURCE_TABLE.lock().unwrap();
+  let r = tg.insert(rid, Repr::WebSocket(Box::new(websocket)));
+  assert!(r.is_none());
+  Resource { rid }
+}
+
+/// Polls the WebSocket `rid` with `f` until it is ready, it fails with a bad
+/// resource once the WebSocket is closed.
+pub fn poll_websocket<T, F>(
+  rid: ResourceId,
+  mut f: F,
+) -> impl Future<Item = T, Error = DenoError>
+where
+  F: FnMut(&mut WebSocket) -> Poll<T, DenoError>,
+{
+  futures::future::poll_fn(move || {
+    let mut table = RESOURCE_TABLE.lock().unwrap();
+    match table.get_mut(&rid) {
+      Some(Repr::WebSocket(ref mut websocket)) => f(websocket),
+      _ => Err(bad_resource()),
+    }
+  })
+}
+
+/// Remembers the task which polled, unless `result` is ready. Sockets only
+/// wake the last task which polled them, so when one is ready all the others
+/// are woken to poll again.
+fn track_task<T>(
+  tasks: &mut Vec<futures::task::Task>,
+  result: Poll<T, Error>,
+) -> Poll<T, DenoError> {
+  match result {
+    Ok(futures::Async::NotReady) => {
+      if !tasks.iter().any(|task| task.will_notify_current()) {
+        tasks.push(futures::task::current());
+      }
+      Ok(futures::Async::NotReady)
+    }
+    result => {
+      for task in tasks.drain(..) {
+        task.notify();
+      }
+      result.map_err(DenoError::from)
+    }
+  }
+}
+
+pub fn add_udp_socket(socket: tokio::net::UdpSocket) -> Resource {
+  let rid = new_rid();
+  let mut tg = RESOURCE_TABLE.lock().unwrap();
+  let r = tg.insert(rid, Repr::UdpSocket(socket, Vec::new()));
+  assert!(r.is_none());
+  Resource { rid }
+}
+
+/// The largest payload of a datagram. Receiving into a buffer of this size
+/// tells whether the buffer of the caller was too small.
+const MAX_DATAGRAM_SIZE: usize = 65536;
+
+pub struct DatagramReceive<B: AsMut<[u8]>> {
+  resource: Resource,
+  buf: Option<B>,
+  /// What a `buf` smaller than MAX_DATAGRAM_SIZE receives into first.
+  scratch: Vec<u8>,
+}
+
+impl<B: AsMut<[u8]>> Future for DatagramReceive<B> {
+  /// The buffer, the length of the payload in it, whether the payload was
+  /// longer, and the address of the sender.
+  type Item = (B, usize, bool, SocketAddr);
+  type Error = DenoError;
+
+  fn poll(&mut self) -> Poll<Self::Item, DenoError> {
+    let (nread, truncated, addr) = {
+      let buf = self.buf.as_mut().unwrap().as_mut();
+      if self.scratch.is_empty() {
+        let (nread, addr) = try_ready!(self.resource.poll_recv_from(buf));
+        (nread, false, addr)
+      } else {
+        let (payload_len, addr) =
+          try_ready!(self.resource.poll_recv_from(&mut self.scratch));
+        let nread = payload_len.min(buf.len());
+        buf[..nread].copy_from_slice(&self.scratch[..nread]);
+        (nread, payload_len > nread, addr)
+      }
+    };
+    let buf = self.buf.take().unwrap();
+    Ok((buf, nread, truncated, addr).into())
+  }
+}
+
+/// Waits for the next datagram of a resource of `add_udp_socket()` and
+/// receives it into `buf`. Only a payload longer than `buf` is copied, it
+/// is received apart first to tell that it did not fit.
+pub fn receive_datagram<B: AsMut<[u8]>>(
+  rid: ResourceId,
+  mut buf: B,
+) -> DenoResult<DatagramReceive<B>> {
+  let table = RESOURCE_TABLE.lock().unwrap();
+  match table.get(&rid) {
+    Some(Repr::UdpSocket(_, _)) => {
+      let scratch = if buf.as_mut().len() < MAX_DATAGRAM_SIZE {
+        vec![0; MAX_DATAGRAM_SIZE]
+      } else {
+        Vec::new()
+      };
+      Ok(DatagramReceive {
+        resource: Resource { rid },
+        buf: Some(buf),
+        scratch,
+      })
+    }
+    _ => Err(bad_resource()),
+  }
+}
+
+/// Sends `buf` as one datagram to `addr`, resolves to the number of bytes
+/// sent.
+pub fn send_datagram<B: AsRef<[u8]>>(
+  rid: ResourceId,
+  buf: B,
+  addr: SocketAddr,
+) -> impl Future<Item = usize, Error = DenoError> {
+  let mut resource = Resource { rid };
+  futures::future::poll_fn(move || resource.poll_send_to(buf.as_ref(), &addr))
+}
+
+/// Joins or leaves the multicast `group` on the default interface.
+pub fn set_multicast(
+  rid: ResourceId,
+  group: IpAddr,
+  join: bool,
+) -> DenoResult<()> {
+  let mut table = RESOURCE_TABLE.lock().unwrap();
+  let socket = match table.get_mut(&rid) {
+    Some(Repr::UdpSocket(ref mut s, _)) => s,
+    _ => return Err(bad_resource()),
+  };
+  match (group, join) {
+    (IpAddr::V4(group), true) => {
+      socket.join_multicast_v4(&group, &Ipv4Addr::UNSPECIFIED)?
+    }
+    (IpAddr::V4(group), false) => {
+      socket.leave_multicast_v4(&group, &Ipv4Addr::UNSPECIFIED)?
+    }
+    (IpAddr::V6(group), true) => socket.join_multicast_v6(&group, 0)?,
+    (IpAddr::V6(group), false) => socket.leave_multicast_v6(&group, 0)?,
+  }
+  Ok(())
+}
+
 #[cfg_attr(feature = "cargo-clippy", allow(stutter))]
 pub struct ChildResources {
   pub child_rid: ResourceId,
@@ -468,7 +994,7 @@ pub fn lookup(rid: ResourceId) -> Option<Resource> {
 // https://github.com/tokio-rs/tokio/pull/785
 pub fn seek(
   resource: Resource,
-  offset: i32,
+  offset: i64,
   whence: u32,
 ) -> Box<dyn Future<Item = (), Error = DenoError> + Send> {
   let mut table = RESOURCE_TABLE.lock().unwrap();
@@ -476,7 +1002,7 @@ pub fn seek(
   // It is put back below while still holding the lock.
   let maybe_repr = table.remove(&resource.rid);
   match maybe_repr {
-    None => panic!("bad rid"),
+    None => Box::new(futures::future::err(bad_resource())),
     Some(Repr::FsFile(f)) => {
       // Trait Clone not implemented on tokio::fs::File,
       // so convert to std File first.
@@ -495,9 +1021,15 @@ pub fn seek(
       );
       // Translate seek mode to Rust repr.
       let seek_from = match whence {
-        0 => SeekFrom::Start(offset as u64),
-        1 => SeekFrom::Current(i64::from(offset)),
-        2 => SeekFrom::End(i64::from(offset)),
+        0 if offset >= 0 => SeekFrom::Start(offset as u64),
+        0 => {
+          return Box::new(futures::future::err(errors::new(
+            errors::ErrorKind::InvalidInput,
+            format!("Cannot seek to the negative offset {}", offset),
+          )));
+        }
+        1 => SeekFrom::Current(offset),
+        2 => SeekFrom::End(offset),
         _ => {
           return Box::new(futures::future::err(errors::new(
             errors::ErrorKind::InvalidSeekMode,
@@ -519,6 +1051,10 @@ pub fn seek(
         futures::future::result(result)
       }))
     }
-    _ => panic!("cannot seek"),
+    Some(repr) => {
+      // Only files can seek, the resource stays as it was.
+      table.insert(resource.rid, repr);
+      Box::new(futures::future::err(bad_resource()))
+    }
   }
 }
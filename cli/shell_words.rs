@@ -0,0 +1,155 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! Splits text into words the way a POSIX shell would, without expanding
+//! anything: quotes, backslashes and comments only. `DENO_FLAGS` and the
+//! files of `--flags-file` are split this way, so that what is written there
+//! means what it would on a command line.
+
+enum State {
+  /// Between words.
+  Delimiter,
+  /// After a backslash which starts a word.
+  Backslash,
+  Unquoted,
+  UnquotedBackslash,
+  SingleQuoted,
+  DoubleQuoted,
+  DoubleQuotedBackslash,
+  /// From a `#` which starts a word to the end of the line.
+  Comment,
+}
+
+/// The words of `text`. A quote which is not closed is an error.
+pub fn split(text: &str) -> Result<Vec<String>, String> {
+  use self::State::*;
+  let mut words = Vec::new();
+  let mut word = String::new();
+  let mut state = Delimiter;
+  let mut chars = text.chars();
+  loop {
+    let c = chars.next();
+    state = match state {
+      Delimiter => match c {
+        None => break,
+        Some('\'') => SingleQuoted,
+        Some('"') => DoubleQuoted,
+        Some('\\') => Backslash,
+        Some('#') => Comment,
+        Some(c) if c.is_whitespace() => Delimiter,
+        Some(c) => {
+          word.push(c);
+          Unquoted
+        }
+      },
+      Backslash => match c {
+        None => {
+          word.push('\\');
+          words.push(word);
+          break;
+        }
+        // A line continuation.
+        Some('\n') => Delimiter,
+        Some(c) => {
+          word.push(c);
+          Unquoted
+        }
+      },
+      Unquoted => match c {
+        None => {
+          words.push(word);
+          break;
+        }
+        Some('\'') => SingleQuoted,
+        Some('"') => DoubleQuoted,
+        Some('\\') => UnquotedBackslash,
+        Some(c) if c.is_whitespace() => {
+          words.push(std::mem::replace(&mut word, String::new()));
+          Delimiter
+        }
+        Some(c) => {
+          word.push(c);
+          Unquoted
+        }
+      },
+      UnquotedBackslash => match c {
+        None => {
+          word.push('\\');
+          words.push(word);
+          break;
+        }
+        Some('\n') => Unquoted,
+        Some(c) => {
+          word.push(c);
+          Unquoted
+        }
+      },
+      SingleQuoted => match c {
+        None => return Err("missing closing quote '".to_string()),
+        Some('\'') => Unquoted,
+        Some(c) => {
+          word.push(c);
+          SingleQuoted
+        }
+      },
+      DoubleQuoted => match c {
+        None => return Err("missing closing quote \"".to_string()),
+        Some('"') => Unquoted,
+        Some('\\') => DoubleQuotedBackslash,
+        Some(c) => {
+          word.push(c);
+          DoubleQuoted
+        }
+      },
+      // Within double quotes only these are escaped, the backslash is kept
+      // before anything else.
+      DoubleQuotedBackslash => match c {
+        None => return Err("missing closing quote \"".to_string()),
+        Some('\n') => DoubleQuoted,
+        Some(c @ '$') | Some(c @ '`') | Some(c @ '"') | Some(c @ '\\') => {
+          word.push(c);
+          DoubleQuoted
+        }
+        Some(c) => {
+          word.push('\\');
+          word.push(c);
+          DoubleQuoted
+        }
+      },
+      Comment => match c {
+        None => break,
+        Some('\n') => Delimiter,
+        Some(_) => Comment,
+      },
+    }
+  }
+  Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Creates vector of strings, Vec<String>
+  macro_rules! svec {
+      ($($x:expr),*) => (vec![$($x.to_string()),*]);
+  }
+
+  fn words(text: &str) -> Vec<String> {
+    split(text).unwrap()
+  }
+
+  #[test]
+  fn test_split() {
+    assert_eq!(words(""), Vec::<String>::new());
+    assert_eq!(words("  --reload \t -D\n"), svec!["--reload", "-D"]);
+    assert_eq!(words("--allow-read='/a b'"), svec!["--allow-read=/a b"]);
+    assert_eq!(words(r#"a"b c"d"#), svec!["ab cd"]);
+    assert_eq!(words(r"a\ b \'c"), svec!["a b", "'c"]);
+    assert_eq!(words(r#""\$x \"y\" \n""#), svec![r#"$x "y" \n"#]);
+    assert_eq!(words(r"'\'"), svec![r"\"]);
+    assert_eq!(words("''"), svec![""]);
+    assert_eq!(words("a\\\nb \\\n c"), svec!["ab", "c"]);
+    assert_eq!(words("# all\n--quiet # on\na#b"), svec!["--quiet", "a#b"]);
+    assert!(split("'a").is_err());
+    assert!(split("\"a\\\"").is_err());
+  }
+}
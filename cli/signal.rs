@@ -0,0 +1,211 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! The signals a program can listen to with `Deno.signal()`. While a signal
+//! has listeners its default action, which usually ends the program, is
+//! suppressed and the signal is delivered to them instead. Without listeners,
+//! also once the last one is closed, the signal acts as it would if deno had
+//! never looked at it.
+use crate::errors;
+use crate::errors::DenoResult;
+use crate::msg::ErrorKind;
+use futures;
+use futures::Async;
+use futures::Future;
+use futures::Poll;
+use futures::Stream;
+use std::io;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use tokio_signal;
+
+#[cfg(unix)]
+use libc;
+
+#[cfg(windows)]
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
+
+/// The signals which can be listened to on this platform, by name.
+#[cfg(unix)]
+const SIGNALS: &[(&str, i32)] = &[
+  ("SIGHUP", libc::SIGHUP),
+  ("SIGINT", libc::SIGINT),
+  ("SIGTERM", libc::SIGTERM),
+  ("SIGUSR1", libc::SIGUSR1),
+  ("SIGUSR2", libc::SIGUSR2),
+];
+
+/// Windows only has console control events, which the C runtime maps to
+/// these two signals.
+#[cfg(windows)]
+const SIGNALS: &[(&str, i32)] = &[("SIGBREAK", 21), ("SIGINT", 2)];
+
+lazy_static! {
+  /// The number of open listeners of each signal of `SIGNALS`. Read by the
+  /// signal handler, so it is initialized before the handler is installed.
+  static ref LISTENERS: Vec<AtomicUsize> =
+    SIGNALS.iter().map(|_| AtomicUsize::new(0)).collect();
+  /// Which signals of `SIGNALS` have the handler of `default_action()`.
+  static ref INSTALLED: Mutex<Vec<bool>> =
+    Mutex::new(vec![false; SIGNALS.len()]);
+}
+
+fn has_listeners(signo: i32) -> bool {
+  match SIGNALS.iter().position(|(_, n)| *n == signo) {
+    Some(index) => LISTENERS[index].load(Ordering::SeqCst) > 0,
+    None => false,
+  }
+}
+
+/// Runs before the handler of tokio-signal, which chains to it. Without
+/// listeners the default action is restored and the signal raised again, so
+/// that the program ends the way it would have without deno.
+#[cfg(unix)]
+extern "C" fn default_action(signo: libc::c_int) {
+  if !has_listeners(signo) {
+    unsafe {
+      libc::signal(signo, libc::SIG_DFL);
+      libc::raise(signo);
+    }
+  }
+}
+
+/// Console control handlers run in the reverse order of their installation,
+/// this one runs before the one of tokio-signal, which would always report
+/// the event as handled.
+#[cfg(windows)]
+unsafe extern "system" fn default_action(ctrl_type: DWORD) -> BOOL {
+  use winapi::shared::ntstatus::STATUS_CONTROL_C_EXIT;
+  use winapi::um::processthreadsapi::ExitProcess;
+  use winapi::um::wincon::{CTRL_BREAK_EVENT, CTRL_C_EVENT};
+  let signo = match ctrl_type {
+    CTRL_C_EVENT => 2,
+    CTRL_BREAK_EVENT => 21,
+    _ => return FALSE,
+  };
+  if !has_listeners(signo) {
+    ExitProcess(STATUS_CONTROL_C_EXIT as u32);
+  }
+  FALSE
+}
+
+/// The stream of a signal, see `bind()`.
+pub struct SignalStream {
+  index: usize,
+  inner: Box<dyn Stream<Item = (), Error = io::Error> + Send>,
+  /// The `poll_signal()` which waits for the signal, woken when the stream
+  /// is closed.
+  task: Option<futures::task::Task>,
+}
+
+impl SignalStream {
+  /// Resolves to false when the signal arrives, and to true when there are no
+  /// more signals.
+  pub fn poll_signal(&mut self) -> Poll<bool, io::Error> {
+    match self.inner.poll()? {
+      Async::Ready(Some(())) => Ok(Async::Ready(false)),
+      Async::Ready(None) => Ok(Async::Ready(true)),
+      Async::NotReady => {
+        self.task = Some(futures::task::current());
+        Ok(Async::NotReady)
+      }
+    }
+  }
+}
+
+impl Drop for SignalStream {
+  fn drop(&mut self) {
+    LISTENERS[self.index].fetch_sub(1, Ordering::SeqCst);
+    if let Some(task) = self.task.take() {
+      task.notify();
+    }
+  }
+}
+
+/// Starts listening to the signal called `name`, e.g. "SIGINT".
+pub fn bind(name: &str) -> DenoResult<SignalStream> {
+  let index = match SIGNALS.iter().position(|(n, _)| *n == name) {
+    Some(index) => index,
+    None => {
+      return Err(errors::new(
+        ErrorKind::InvalidInput,
+        format!("Cannot listen to {} on this platform", name),
+      ))
+    }
+  };
+  let inner = new_stream(index)?;
+  LISTENERS[index].fetch_add(1, Ordering::SeqCst);
+  Ok(SignalStream {
+    index,
+    inner,
+    task: None,
+  })
+}
+
+#[cfg(unix)]
+fn new_stream(
+  index: usize,
+) -> io::Result<Box<dyn Stream<Item = (), Error = io::Error> + Send>> {
+  let signo = SIGNALS[index].1;
+  let mut installed = INSTALLED.lock().unwrap();
+  if !installed[index] {
+    lazy_static::initialize(&LISTENERS);
+    unsafe {
+      libc::signal(signo, default_action as libc::sighandler_t);
+    }
+    installed[index] = true;
+  }
+  let signal = tokio_signal::unix::Signal::new(signo).wait()?;
+  Ok(Box::new(signal.map(|_| ())))
+}
+
+#[cfg(windows)]
+fn new_stream(
+  index: usize,
+) -> io::Result<Box<dyn Stream<Item = (), Error = io::Error> + Send>> {
+  use tokio_signal::windows::Event;
+  use winapi::um::consoleapi::SetConsoleCtrlHandler;
+  let event = match SIGNALS[index].0 {
+    "SIGBREAK" => Event::ctrl_break().wait()?,
+    _ => Event::ctrl_c().wait()?,
+  };
+  // After tokio-signal installed its handler, see `default_action()`.
+  let mut installed = INSTALLED.lock().unwrap();
+  if !installed.iter().any(|i| *i) {
+    lazy_static::initialize(&LISTENERS);
+    if unsafe { SetConsoleCtrlHandler(Some(default_action), TRUE) } == FALSE {
+      return Err(io::Error::last_os_error());
+    }
+  }
+  installed[index] = true;
+  Ok(Box::new(event))
+}
+
+/// Sends the signal `signo` to the process `pid`.
+#[cfg(unix)]
+pub fn kill(pid: i32, signo: i32) -> DenoResult<()> {
+  if unsafe { libc::kill(pid as libc::pid_t, signo) } == 0 {
+    Ok(())
+  } else {
+    Err(io::Error::last_os_error().into())
+  }
+}
+
+/// Windows has no signals to send, so the process is terminated whatever
+/// `signo` is.
+#[cfg(windows)]
+pub fn kill(pid: i32, _signo: i32) -> DenoResult<()> {
+  use winapi::um::handleapi::CloseHandle;
+  use winapi::um::processthreadsapi::{OpenProcess, TerminateProcess};
+  use winapi::um::winnt::PROCESS_TERMINATE;
+  let handle = unsafe { OpenProcess(PROCESS_TERMINATE, FALSE, pid as DWORD) };
+  if handle.is_null() {
+    return Err(io::Error::last_os_error().into());
+  }
+  let result = if unsafe { TerminateProcess(handle, 1) } == FALSE {
+    Err(io::Error::last_os_error().into())
+  } else {
+    Ok(())
+  };
+  unsafe { CloseHandle(handle) };
+  result
+}
@@ -1,56 +1,118 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! The isolates start from V8 snapshots of the bundles of js/, made at build
+//! time, which saves evaluating them, most of all the TypeScript compiler.
+//! The bundles themselves are the fallback: for `--no-snapshot`, in builds
+//! without snapshots, and for a snapshot of another version of V8, which V8
+//! would refuse.
 use deno::{Script, StartupData};
 
-pub fn deno_isolate_init() -> StartupData<'static> {
-  if cfg!(feature = "no-snapshot-init") {
-    debug!("Deno isolate init without snapshots.");
-    #[cfg(not(feature = "check-only"))]
-    let source_bytes =
-      include_bytes!(concat!(env!("GN_OUT_DIR"), "/gen/cli/bundle/main.js"));
-    #[cfg(feature = "check-only")]
-    let source_bytes = b"";
-
-    StartupData::Script(Script {
-      filename: "gen/cli/bundle/main.js",
-      source: std::str::from_utf8(&source_bytes[..]).unwrap(),
-    })
-  } else {
-    debug!("Deno isolate init with snapshots.");
-    #[cfg(not(any(feature = "check-only", feature = "no-snapshot-init")))]
-    let data =
-      include_bytes!(concat!(env!("GN_OUT_DIR"), "/gen/cli/snapshot_deno.bin"));
-    #[cfg(any(feature = "check-only", feature = "no-snapshot-init"))]
-    let data = b"";
-
-    StartupData::Snapshot(data)
+#[cfg(not(feature = "check-only"))]
+const MAIN_SOURCE: &[u8] =
+  include_bytes!(concat!(env!("GN_OUT_DIR"), "/gen/cli/bundle/main.js"));
+#[cfg(feature = "check-only")]
+const MAIN_SOURCE: &[u8] = b"";
+
+#[cfg(not(feature = "check-only"))]
+const COMPILER_SOURCE: &[u8] =
+  include_bytes!(concat!(env!("GN_OUT_DIR"), "/gen/cli/bundle/compiler.js"));
+#[cfg(feature = "check-only")]
+const COMPILER_SOURCE: &[u8] = b"";
+
+#[cfg(not(any(feature = "check-only", feature = "no-snapshot-init")))]
+const MAIN_SNAPSHOT: &[u8] =
+  include_bytes!(concat!(env!("GN_OUT_DIR"), "/gen/cli/snapshot_deno.bin"));
+#[cfg(any(feature = "check-only", feature = "no-snapshot-init"))]
+const MAIN_SNAPSHOT: &[u8] = b"";
+
+#[cfg(not(any(feature = "check-only", feature = "no-snapshot-init")))]
+const COMPILER_SNAPSHOT: &[u8] = include_bytes!(concat!(
+  env!("GN_OUT_DIR"),
+  "/gen/cli/snapshot_compiler.bin"
+));
+#[cfg(any(feature = "check-only", feature = "no-snapshot-init"))]
+const COMPILER_SNAPSHOT: &[u8] = b"";
+
+/// The header of a snapshot holds the version of V8 which made it, padded
+/// with zeros, after a few counts.
+const SNAPSHOT_HEADER_LEN: usize = 128;
+
+/// Whether `snapshot` was made by the V8 of this build.
+fn is_loadable(snapshot: &[u8], v8_version: &str) -> bool {
+  let header = &snapshot[..snapshot.len().min(SNAPSHOT_HEADER_LEN)];
+  let version = v8_version.as_bytes();
+  header.windows(version.len() + 1).any(|window| {
+    window[..version.len()] == *version && window[version.len()] == 0
+  })
+}
+
+fn isolate_init(
+  name: &str,
+  snapshot: &'static [u8],
+  filename: &'static str,
+  source: &'static [u8],
+  use_snapshot: bool,
+) -> StartupData<'static> {
+  if use_snapshot && !snapshot.is_empty() {
+    if is_loadable(snapshot, deno::v8_version()) {
+      debug!("{} isolate init with snapshots.", name);
+      return StartupData::Snapshot(snapshot);
+    }
+    warn!(
+      "The snapshot of the {} isolate was made by another version of V8, \
+       starting from source",
+      name
+    );
   }
+  debug!("{} isolate init without snapshots.", name);
+  StartupData::Script(Script {
+    filename,
+    source: std::str::from_utf8(source).unwrap(),
+  })
 }
 
-pub fn compiler_isolate_init() -> StartupData<'static> {
-  if cfg!(feature = "no-snapshot-init") {
-    debug!("Compiler isolate init without snapshots.");
-    #[cfg(not(feature = "check-only"))]
-    let source_bytes = include_bytes!(concat!(
-      env!("GN_OUT_DIR"),
-      "/gen/cli/bundle/compiler.js"
-    ));
-    #[cfg(feature = "check-only")]
-    let source_bytes = b"";
-
-    StartupData::Script(Script {
-      filename: "gen/cli/bundle/compiler.js",
-      source: std::str::from_utf8(&source_bytes[..]).unwrap(),
-    })
-  } else {
-    debug!("Deno isolate init with snapshots.");
-    #[cfg(not(any(feature = "check-only", feature = "no-snapshot-init")))]
-    let data = include_bytes!(concat!(
-      env!("GN_OUT_DIR"),
-      "/gen/cli/snapshot_compiler.bin"
-    ));
-    #[cfg(any(feature = "check-only", feature = "no-snapshot-init"))]
-    let data = b"";
-
-    StartupData::Snapshot(data)
+/// Starts from source if `use_snapshot` is false, see `--no-snapshot`.
+pub fn deno_isolate_init(use_snapshot: bool) -> StartupData<'static> {
+  isolate_init(
+    "Deno",
+    MAIN_SNAPSHOT,
+    "gen/cli/bundle/main.js",
+    MAIN_SOURCE,
+    use_snapshot,
+  )
+}
+
+pub fn compiler_isolate_init(use_snapshot: bool) -> StartupData<'static> {
+  isolate_init(
+    "Compiler",
+    COMPILER_SNAPSHOT,
+    "gen/cli/bundle/compiler.js",
+    COMPILER_SOURCE,
+    use_snapshot,
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn header(version: &str) -> Vec<u8> {
+    let mut snapshot = vec![2, 0, 0, 0, 1, 0, 0, 0];
+    snapshot.extend(version.as_bytes());
+    snapshot.resize(8 + 64, 0);
+    snapshot.extend(&[0xde, 0xad, 0xbe, 0xef]);
+    snapshot
+  }
+
+  #[test]
+  fn test_is_loadable() {
+    assert!(is_loadable(&header("7.4.158"), "7.4.158"));
+    assert!(!is_loadable(&header("7.4.158"), "7.5.51"));
+    // Not a prefix of a longer version.
+    assert!(!is_loadable(&header("7.4.1580"), "7.4.158"));
+    assert!(!is_loadable(b"", "7.4.158"));
+    if !MAIN_SNAPSHOT.is_empty() {
+      assert!(is_loadable(MAIN_SNAPSHOT, deno::v8_version()));
+      assert!(is_loadable(COMPILER_SNAPSHOT, deno::v8_version()));
+    }
   }
 }
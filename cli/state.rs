@@ -1,33 +1,92 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+use crate::compiler::ModuleMetaData;
 use crate::deno_dir;
+use crate::errors;
 use crate::errors::DenoResult;
+use crate::errors::ErrorKind;
 use crate::flags;
-use crate::global_timer::GlobalTimer;
+use crate::import_map::ImportMap;
+use crate::lockfile::Lockfile;
 use crate::modules::Modules;
 use crate::ops;
+use crate::performance::Performance;
 use crate::permissions::DenoPermissions;
+use crate::permissions::PromptSourceMaps;
 use crate::resources;
 use crate::resources::ResourceId;
+use crate::timers::Timers;
+use crate::tsconfig::TsConfig;
 use crate::worker::Worker;
 use deno::deno_buf;
 use deno::Buf;
 use deno::Dispatch;
+use deno::IsolateHandle;
+use deno::JSError;
 use deno::Op;
+use futures::future::Either;
 use futures::future::Shared;
+use futures::sync::oneshot;
+use futures::Future;
 use std;
 use std::collections::HashMap;
-use std::env;
 use std::ops::Deref;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::time::Instant;
+use std::sync::Once;
+use tokio;
 use tokio::sync::mpsc as async_mpsc;
 
 pub type WorkerSender = async_mpsc::Sender<Buf>;
 pub type WorkerReceiver = async_mpsc::Receiver<Buf>;
 pub type WorkerChannels = (WorkerSender, WorkerReceiver);
-pub type UserWorkerTable = HashMap<ResourceId, Shared<Worker>>;
+pub type UserWorkerTable = HashMap<ResourceId, UserWorker>;
+
+/// A worker of `new Worker()`. It runs on the thread pool, at the same time
+/// as the isolate which created it, until it is done or terminated.
+pub struct UserWorker {
+  /// Resolves when the worker is done, to the error which ended it, if any.
+  pub closed: Shared<oneshot::Receiver<Option<JSError>>>,
+  isolate: IsolateHandle,
+  terminated: Arc<AtomicBool>,
+  /// The worker is dropped when this is dropped.
+  terminate: Option<oneshot::Sender<()>>,
+}
+
+impl UserWorker {
+  pub fn spawn(mut worker: Worker) -> Self {
+    let isolate = worker.isolate_handle();
+    let terminated = Arc::new(AtomicBool::new(false));
+    let (terminate, terminate_rx) = oneshot::channel::<()>();
+    let (closed_tx, closed) = oneshot::channel();
+    let terminated_ = terminated.clone();
+    tokio::spawn(worker.select2(terminate_rx).then(move |result| {
+      // A terminated worker fails with "execution terminated", that is no
+      // error of the worker.
+      let error = match result {
+        Err(Either::A((err, _))) if !terminated_.load(Ordering::SeqCst) => {
+          Some(err)
+        }
+        _ => None,
+      };
+      let _ = closed_tx.send(error);
+      Ok(())
+    }));
+    UserWorker {
+      closed: closed.shared(),
+      isolate,
+      terminated,
+      terminate: Some(terminate),
+    }
+  }
+
+  /// Interrupts the JavaScript the worker runs, if any, and drops it.
+  pub fn terminate(&mut self) {
+    self.terminated.store(true, Ordering::SeqCst);
+    self.isolate.terminate_execution();
+    self.terminate.take();
+  }
+}
 
 // AtomicU64 is currently unstable
 #[derive(Default)]
@@ -55,10 +114,13 @@ pub struct State {
   pub flags: flags::DenoFlags,
   pub metrics: Metrics,
   pub modules: Mutex<Modules>,
+  pub lockfile: Option<Mutex<Lockfile>>,
   pub worker_channels: Mutex<WorkerChannels>,
-  pub global_timer: Mutex<GlobalTimer>,
+  pub timers: Arc<Mutex<Timers>>,
+  /// What cancels each pending async op, by its cmd_id.
+  cancellers: Mutex<HashMap<u32, oneshot::Sender<()>>>,
   pub workers: Mutex<UserWorkerTable>,
-  pub start_time: Instant,
+  pub performance: Performance,
   pub resource: resources::Resource,
   pub dispatch_selector: ops::OpSelector,
 }
@@ -92,7 +154,20 @@ impl ThreadSafeState {
     argv_rest: Vec<String>,
     dispatch_selector: ops::OpSelector,
   ) -> Self {
-    let custom_root = env::var("DENO_DIR").map(|s| s.into()).ok();
+    let permissions = DenoPermissions::from_flags(&flags);
+    Self::with_permissions(flags, argv_rest, dispatch_selector, permissions)
+  }
+
+  /// The state of an isolate with `permissions` rather than the ones of the
+  /// flags, as for a worker.
+  pub fn with_permissions(
+    flags: flags::DenoFlags,
+    argv_rest: Vec<String>,
+    dispatch_selector: ops::OpSelector,
+    mut permissions: DenoPermissions,
+  ) -> Self {
+    let deno_dir_flag = flags.deno_dir.as_ref().map(String::as_str);
+    let (custom_root, _) = deno_dir::DenoDir::custom_root(deno_dir_flag);
 
     let (worker_in_tx, worker_in_rx) = async_mpsc::channel::<Buf>(1);
     let (worker_out_tx, worker_out_rx) = async_mpsc::channel::<Buf>(1);
@@ -100,17 +175,65 @@ impl ThreadSafeState {
     let external_channels = (worker_in_tx, worker_out_rx);
     let resource = resources::add_worker(external_channels);
 
+    let mut dir =
+      deno_dir::DenoDir::new(custom_root, flags.cached_only).unwrap();
+    dir.quiet = !flags.shows_info();
+    dir.reload_full = flags.reload_full;
+    dir.no_check = flags.no_check;
+    dir.compat_resolve = flags.compat_resolve;
+    if let Some(max_redirects) = flags.max_redirects {
+      dir.max_redirects = max_redirects;
+    }
+    dir.import_map = flags.import_map_path.as_ref().map(|filename| {
+      ImportMap::load(filename).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+      })
+    });
+    if let Some(filename) = flags.config_path.as_ref() {
+      let config = TsConfig::load(filename).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+      });
+      // Every worker has its own state, the compiler workers included.
+      static WARN_IGNORED: Once = Once::new();
+      WARN_IGNORED.call_once(|| {
+        for option in &config.ignored {
+          eprintln!(
+            "Warning: \"{}\" in {} is ignored, deno sets it itself",
+            option, filename
+          );
+        }
+      });
+      dir.compiler_options = config.compiler_options;
+    }
+
+    let lockfile = flags.lock.as_ref().map(|filename| {
+      match Lockfile::new(filename.clone(), flags.lock_write) {
+        Ok(lockfile) => Mutex::new(lockfile),
+        Err(err) => {
+          eprintln!("{}", err);
+          std::process::exit(1);
+        }
+      }
+    });
+
+    // Prompts name the call site in the script's sources.
+    permissions.source_maps = PromptSourceMaps(Some(Arc::new(dir.clone())));
+
     ThreadSafeState(Arc::new(State {
-      dir: deno_dir::DenoDir::new(custom_root).unwrap(),
+      dir,
       argv: argv_rest,
-      permissions: DenoPermissions::from_flags(&flags),
+      permissions,
       flags,
       metrics: Metrics::default(),
       modules: Mutex::new(Modules::new()),
+      lockfile,
       worker_channels: Mutex::new(internal_channels),
-      global_timer: Mutex::new(GlobalTimer::new()),
+      timers: Arc::new(Mutex::new(Timers::default())),
+      cancellers: Mutex::new(HashMap::new()),
       workers: Mutex::new(UserWorkerTable::new()),
-      start_time: Instant::now(),
+      performance: Performance::new(),
       resource,
       dispatch_selector,
     }))
@@ -133,6 +256,47 @@ impl ThreadSafeState {
     }
   }
 
+  /// Returns whether the module may be loaded from the cache. With
+  /// `--reload=<prefixes>` only modules whose URL starts with one of the
+  /// prefixes are fetched and compiled again.
+  pub fn use_cache(&self, specifier: &str, referrer: &str) -> bool {
+    if self.flags.reload {
+      return false;
+    }
+    if self.flags.reload_whitelist.is_empty() {
+      return true;
+    }
+    match self.dir.resolve_module_url(specifier, referrer) {
+      Ok(url) => !self
+        .flags
+        .reload_whitelist
+        .iter()
+        .any(|prefix| url.as_str().starts_with(prefix.as_str())),
+      Err(_) => true,
+    }
+  }
+
+  /// Checks a remote module against the lock file given with `--lock`, or
+  /// records its hash with `--lock-write`.
+  pub fn check_lock(&self, out: &ModuleMetaData) -> DenoResult<()> {
+    let is_remote = out.module_name.starts_with("http://")
+      || out.module_name.starts_with("https://");
+    match self.lockfile {
+      Some(ref lockfile) if is_remote => lockfile
+        .lock()
+        .unwrap()
+        .check_or_insert(&out.module_name, &out.source_code),
+      _ => Ok(()),
+    }
+  }
+
+  pub fn write_lockfile(&self) -> DenoResult<()> {
+    match self.lockfile {
+      Some(ref lockfile) => lockfile.lock().unwrap().write(),
+      None => Ok(()),
+    }
+  }
+
   #[inline]
   pub fn check_read(&self, filename: &str) -> DenoResult<()> {
     self.permissions.check_read(filename)
@@ -143,14 +307,34 @@ impl ThreadSafeState {
     self.permissions.check_write(filename)
   }
 
+  #[inline]
+  pub fn check_write_unscoped(&self, message: &str) -> DenoResult<()> {
+    self.permissions.check_write_unscoped(message)
+  }
+
   #[inline]
   pub fn check_env(&self) -> DenoResult<()> {
     self.permissions.check_env()
   }
 
   #[inline]
-  pub fn check_net(&self, filename: &str) -> DenoResult<()> {
-    self.permissions.check_net(filename)
+  pub fn check_env_var(&self, name: &str) -> DenoResult<()> {
+    self.permissions.check_env_var(name)
+  }
+
+  #[inline]
+  pub fn check_net(&self, host_and_port: &str) -> DenoResult<()> {
+    self.permissions.check_net(host_and_port)
+  }
+
+  #[inline]
+  pub fn check_net_url(&self, url: &url::Url) -> DenoResult<()> {
+    self.permissions.check_net_url(url)
+  }
+
+  #[inline]
+  pub fn check_run_command(&self, command: &str) -> DenoResult<()> {
+    self.permissions.check_run_command(command)
   }
 
   #[inline]
@@ -166,6 +350,38 @@ impl ThreadSafeState {
     ThreadSafeState::new(flags, rest_argv, ops::op_selector_std)
   }
 
+  /// Makes `op` fail with Interrupted if `cancel_op(cmd_id)` is called
+  /// while it is pending. It is dropped then, the resource it works on is
+  /// not.
+  pub fn cancellable(
+    &self,
+    cmd_id: u32,
+    op: Box<ops::OpWithError>,
+  ) -> Box<ops::OpWithError> {
+    let (cancel, cancelled) = oneshot::channel::<()>();
+    self.cancellers.lock().unwrap().insert(cmd_id, cancel);
+    let state = self.clone();
+    Box::new(op.select2(cancelled).then(move |result| {
+      state.cancellers.lock().unwrap().remove(&cmd_id);
+      match result {
+        Ok(Either::A((buf, _))) => Ok(buf),
+        Err(Either::A((err, _))) => Err(err),
+        Ok(Either::B(_)) | Err(Either::B(_)) => Err(errors::new(
+          ErrorKind::Interrupted,
+          "The op was cancelled".to_string(),
+        )),
+      }
+    }))
+  }
+
+  /// Cancels the pending async op `cmd_id`, returns whether there is one.
+  pub fn cancel_op(&self, cmd_id: u32) -> bool {
+    match self.cancellers.lock().unwrap().remove(&cmd_id) {
+      Some(cancel) => cancel.send(()).is_ok(),
+      None => false,
+    }
+  }
+
   pub fn metrics_op_dispatched(
     &self,
     bytes_sent_control: usize,
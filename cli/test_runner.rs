@@ -0,0 +1,128 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! `deno test` finds the test files and runs them in this process, like
+//! `deno bench` does for bench files: as the imports of a main module which
+//! then runs the tests they registered with `Deno.test()`.
+use crate::flags::DenoFlags;
+use crate::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use url::Url;
+
+const EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx"];
+
+/// Whether `path` is a test file, `test.ts` or e.g. `url_test.ts`.
+pub fn is_test_file(path: &Path) -> bool {
+  let has_extension = path
+    .extension()
+    .map_or(false, |ext| EXTENSIONS.iter().any(|e| ext == *e));
+  has_extension
+    && path.file_stem().map_or(false, |stem| {
+      let stem = stem.to_string_lossy();
+      stem == "test" || stem.ends_with("_test")
+    })
+}
+
+/// Returns the test files for the `paths` given on the command line. Files
+/// are taken as they are, directories are searched like by `deno fmt`.
+pub fn collect_files<F: FnMut(String)>(
+  paths: &[PathBuf],
+  warn: F,
+) -> Vec<PathBuf> {
+  fmt::collect_files_matching(paths, &[], is_test_file, warn)
+}
+
+/// The source of the main module, a script which imports the `files`, which
+/// must be absolute, in order and then runs the tests. It exits with 1 if a
+/// test failed, with `--coverage` only once the event loop drained, so that
+/// the coverage is taken.
+pub fn main_source(files: &[PathBuf], flags: &DenoFlags) -> String {
+  let options = json!({
+    "filter": flags.test_filter,
+    "failFast": flags.test_fail_fast,
+  });
+  runner_source(
+    files,
+    &format!("Deno.runTests({})", options),
+    flags.coverage.is_some(),
+  )
+}
+
+/// A script which imports `files` and then evaluates `run`, an expression
+/// which resolves to a report with the list of what `failed`. With
+/// `exit_at_unload` the exit code is set by an unload hook instead of exiting
+/// at once.
+pub fn runner_source(
+  files: &[PathBuf],
+  run: &str,
+  exit_at_unload: bool,
+) -> String {
+  let mut source = String::new();
+  for file in files {
+    let url = Url::from_file_path(file).unwrap();
+    source.push_str(&format!(
+      "import {};\n",
+      serde_json::to_string(url.as_str()).unwrap()
+    ));
+  }
+  let exit = if exit_at_unload {
+    "Deno.addUnloadHook(() => Deno.exit(1));"
+  } else {
+    "Deno.exit(1);"
+  };
+  source.push_str(&format!(
+    "\n{}.then(report => {{
+  if (report.failed.length > 0) {{
+    {}
+  }}
+}});\n",
+    run, exit
+  ));
+  source
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_is_test_file() {
+    assert!(is_test_file(Path::new("url_test.ts")));
+    assert!(is_test_file(Path::new("src/test.js")));
+    assert!(is_test_file(Path::new("src/parse_test.tsx")));
+    assert!(!is_test_file(Path::new("url.ts")));
+    assert!(!is_test_file(Path::new("test_util.ts")));
+    assert!(!is_test_file(Path::new("url_test.json")));
+    assert!(!is_test_file(Path::new("mytest.ts")));
+  }
+
+  #[test]
+  fn test_main_source() {
+    let flags = DenoFlags {
+      test_filter: Some("http".to_string()),
+      test_fail_fast: true,
+      ..DenoFlags::default()
+    };
+    let file = if cfg!(windows) {
+      PathBuf::from("C:\\src\\url_test.ts")
+    } else {
+      PathBuf::from("/src/url_test.ts")
+    };
+    let source = main_source(&[file], &flags);
+    let url = if cfg!(windows) {
+      "file:///C:/src/url_test.ts"
+    } else {
+      "file:///src/url_test.ts"
+    };
+    assert!(source.starts_with(&format!("import \"{}\";\n", url)));
+    assert!(source.contains("\"filter\":\"http\""));
+    assert!(source.contains("\"failFast\":true"));
+    assert!(source.contains("    Deno.exit(1);\n"));
+
+    let flags = DenoFlags {
+      coverage: Some("coverage".to_string()),
+      ..DenoFlags::default()
+    };
+    let source = main_source(&[], &flags);
+    assert!(source.contains("Deno.addUnloadHook(() => Deno.exit(1));"));
+  }
+}
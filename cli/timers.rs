@@ -0,0 +1,203 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+
+//! The timers of `setTimeout()` and `setInterval()`.
+//!
+//! The deadlines are kept in a queue here, on the monotonic clock. JS waits
+//! for the timers which are due with a single op, `DueTimers`, which resolves
+//! to their ids: by deadline, and in the order they were started for the same
+//! deadline. An interval is rescheduled from its previous deadline rather than
+//! from when it fired, so that it does not drift when the program is busy.
+
+use futures::task;
+use futures::task::Task;
+use futures::Async;
+use futures::Future;
+use futures::Poll;
+use std::cmp;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::timer::Delay;
+
+pub type TimerId = u32;
+
+/// A deadline, and the number of the timer among those started, which orders
+/// the timers with the same deadline.
+type Key = (Instant, u64);
+
+struct Timer {
+  key: Key,
+  /// The delay of an interval.
+  interval: Option<Duration>,
+}
+
+#[derive(Default)]
+pub struct Timers {
+  queue: BTreeMap<Key, TimerId>,
+  timers: HashMap<TimerId, Timer>,
+  started: u64,
+  /// The `DueTimers` which waits, woken when the first deadline changes.
+  task: Option<Task>,
+}
+
+impl Timers {
+  /// Starts the timer `id`, again if it exists.
+  pub fn start(&mut self, id: TimerId, delay: Duration, repeat: bool) {
+    self.cancel(id);
+    let interval = if repeat { Some(delay) } else { None };
+    self.insert(id, Instant::now() + delay, interval);
+    self.notify();
+  }
+
+  /// Cancelling a timer which does not exist, or is done, does nothing.
+  pub fn cancel(&mut self, id: TimerId) {
+    if let Some(timer) = self.timers.remove(&id) {
+      self.queue.remove(&timer.key);
+      self.notify();
+    }
+  }
+
+  fn insert(
+    &mut self,
+    id: TimerId,
+    deadline: Instant,
+    interval: Option<Duration>,
+  ) {
+    let key = (deadline, self.started);
+    self.started += 1;
+    self.queue.insert(key, id);
+    self.timers.insert(id, Timer { key, interval });
+  }
+
+  fn notify(&mut self) {
+    if let Some(task) = self.task.take() {
+      task.notify();
+    }
+  }
+
+  /// Removes the timers which are due at `now`, in order. The intervals among
+  /// them are rescheduled, never before `now`.
+  fn take_due(&mut self, now: Instant) -> Vec<TimerId> {
+    let mut due = Vec::new();
+    let mut intervals = Vec::new();
+    while let Some(&(deadline, started)) = self.queue.keys().next() {
+      if deadline > now {
+        break;
+      }
+      let id = self.queue.remove(&(deadline, started)).unwrap();
+      let timer = self.timers.remove(&id).unwrap();
+      if let Some(delay) = timer.interval {
+        intervals.push((id, cmp::max(deadline + delay, now), delay));
+      }
+      due.push(id);
+    }
+    // Only after the loop, an interval of 0 ms would be due again at once.
+    for (id, deadline, delay) in intervals {
+      self.insert(id, deadline, Some(delay));
+    }
+    due
+  }
+}
+
+/// Resolves to the ids of the timers which are due, or to no ids once no
+/// timer is left.
+pub struct DueTimers {
+  timers: Arc<Mutex<Timers>>,
+  delay: Option<Delay>,
+}
+
+impl DueTimers {
+  pub fn new(timers: Arc<Mutex<Timers>>) -> Self {
+    DueTimers {
+      timers,
+      delay: None,
+    }
+  }
+}
+
+impl Future for DueTimers {
+  type Item = Vec<TimerId>;
+  type Error = ();
+
+  fn poll(&mut self) -> Poll<Vec<TimerId>, ()> {
+    let mut timers = self.timers.lock().unwrap();
+    let due = timers.take_due(Instant::now());
+    let first = match timers.queue.keys().next() {
+      Some(&(deadline, _)) if due.is_empty() => deadline,
+      _ => return Ok(Async::Ready(due)),
+    };
+    timers.task = Some(task::current());
+    match self.delay {
+      Some(ref mut delay) if delay.deadline() == first => {}
+      Some(ref mut delay) => delay.reset(first),
+      None => self.delay = Some(Delay::new(first)),
+    }
+    match self.delay.as_mut().unwrap().poll() {
+      Ok(Async::NotReady) => Ok(Async::NotReady),
+      // The deadline passed since the timers were taken.
+      Ok(Async::Ready(())) => {
+        task::current().notify();
+        Ok(Async::NotReady)
+      }
+      Err(err) => panic!("Timer failed: {}", err),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+  }
+
+  #[test]
+  fn order() {
+    let mut timers = Timers::default();
+    let start = Instant::now();
+    timers.insert(1, start + ms(20), None);
+    timers.insert(2, start + ms(10), None);
+    timers.insert(3, start + ms(10), None);
+    timers.insert(4, start + ms(30), None);
+    assert!(timers.take_due(start).is_empty());
+    // Those with the same deadline in the order they were started.
+    assert_eq!(timers.take_due(start + ms(20)), vec![2, 3, 1]);
+    assert_eq!(timers.take_due(start + ms(40)), vec![4]);
+    assert!(timers.queue.is_empty() && timers.timers.is_empty());
+  }
+
+  #[test]
+  fn cancel() {
+    let mut timers = Timers::default();
+    timers.start(1, ms(0), false);
+    timers.start(2, ms(0), false);
+    timers.cancel(1);
+    timers.cancel(7);
+    assert_eq!(timers.take_due(Instant::now() + ms(1)), vec![2]);
+    // Done, cancelling it does nothing.
+    timers.cancel(2);
+  }
+
+  #[test]
+  fn interval() {
+    let mut timers = Timers::default();
+    let start = Instant::now();
+    timers.insert(1, start + ms(10), Some(ms(10)));
+    // Late, the next deadline is still 20 ms.
+    assert_eq!(timers.take_due(start + ms(15)), vec![1]);
+    assert_eq!(timers.queue.keys().next().unwrap().0, start + ms(20));
+    // Later than a whole interval, it is not due twice at once.
+    assert_eq!(timers.take_due(start + ms(45)), vec![1]);
+    assert_eq!(timers.queue.keys().next().unwrap().0, start + ms(45));
+    timers.cancel(1);
+    assert!(timers.queue.is_empty());
+
+    timers.insert(2, start, Some(ms(0)));
+    assert_eq!(timers.take_due(start), vec![2]);
+    assert_eq!(timers.timers.len(), 1);
+  }
+}
@@ -0,0 +1,120 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! The TLS configurations of `Deno.dialTLS()` and `Deno.listenTLS()`, backed
+//! by rustls. Unlike `--cert`, which applies to fetch, the CA file of a dial
+//! is only trusted by that connection.
+use crate::errors;
+use crate::errors::DenoError;
+use crate::errors::DenoResult;
+use crate::msg::ErrorKind;
+use rustls;
+use rustls::internal::pemfile;
+use rustls::Certificate;
+use rustls::NoClientAuth;
+use rustls::PrivateKey;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::sync::Arc;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::TlsConnector;
+use webpki::DNSName;
+use webpki::DNSNameRef;
+use webpki_roots;
+
+fn invalid_file(filename: &str, reason: &str) -> DenoError {
+  errors::new(
+    ErrorKind::InvalidData,
+    format!("Unable to load \"{}\": {}", filename, reason),
+  )
+}
+
+fn open(filename: &str) -> DenoResult<BufReader<File>> {
+  let file =
+    File::open(filename).map_err(|e| invalid_file(filename, &e.to_string()))?;
+  Ok(BufReader::new(file))
+}
+
+/// Trusts the default root certificates, and with `ca_file` the ones in that
+/// PEM file as well.
+pub fn connector(ca_file: Option<&str>) -> DenoResult<TlsConnector> {
+  let mut config = rustls::ClientConfig::new();
+  config
+    .root_store
+    .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+  if let Some(filename) = ca_file {
+    let invalid = |reason: &str| invalid_file(filename, reason);
+    match config.root_store.add_pem_file(&mut open(filename)?) {
+      Err(()) => return Err(invalid("not a PEM file")),
+      Ok((0, _)) => return Err(invalid("no certificates found")),
+      Ok((_, n)) if n > 0 => {
+        return Err(invalid(&format!("{} invalid certificate(s)", n)))
+      }
+      Ok(_) => {}
+    }
+  }
+  Ok(TlsConnector::from(Arc::new(config)))
+}
+
+/// The name the certificate of the server has to be issued for, also sent to
+/// it as SNI.
+pub fn server_name(hostname: &str) -> DenoResult<DNSName> {
+  match DNSNameRef::try_from_ascii_str(hostname) {
+    Ok(name) => Ok(name.to_owned()),
+    Err(_) => Err(errors::new(
+      ErrorKind::InvalidInput,
+      format!("Invalid server name \"{}\"", hostname),
+    )),
+  }
+}
+
+/// Serves the certificate chain of the PEM file `cert_file`, with the PKCS#8
+/// or RSA private key of the PEM file `key_file`.
+pub fn acceptor(cert_file: &str, key_file: &str) -> DenoResult<TlsAcceptor> {
+  let certs = load_certs(cert_file)?;
+  let key = load_key(key_file)?;
+  let mut config = rustls::ServerConfig::new(NoClientAuth::new());
+  config
+    .set_single_cert(certs, key)
+    .map_err(|e| invalid_file(cert_file, &e.to_string()))?;
+  Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn load_certs(filename: &str) -> DenoResult<Vec<Certificate>> {
+  match pemfile::certs(&mut open(filename)?) {
+    Err(()) => Err(invalid_file(filename, "not a PEM file")),
+    Ok(ref certs) if certs.is_empty() => {
+      Err(invalid_file(filename, "no certificates found"))
+    }
+    Ok(certs) => Ok(certs),
+  }
+}
+
+fn load_key(filename: &str) -> DenoResult<PrivateKey> {
+  let mut keys = pemfile::pkcs8_private_keys(&mut open(filename)?)
+    .map_err(|_| invalid_file(filename, "not a PEM file"))?;
+  if keys.is_empty() {
+    keys = pemfile::rsa_private_keys(&mut open(filename)?)
+      .map_err(|_| invalid_file(filename, "not a PEM file"))?;
+  }
+  match keys.len() {
+    0 => Err(invalid_file(filename, "no private key found")),
+    _ => Ok(keys.remove(0)),
+  }
+}
+
+/// rustls reports why a handshake failed, e.g. an expired certificate, as
+/// the message of an io error, which is kept.
+pub fn handshake_error(err: io::Error) -> DenoError {
+  io::Error::new(err.kind(), format!("TLS handshake failed: {}", err)).into()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn server_name_rejects_ip_addresses() {
+    assert!(server_name("localhost").is_ok());
+    assert!(server_name("127.0.0.1").is_err());
+  }
+}
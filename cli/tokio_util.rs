@@ -1,9 +1,9 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+use crate::errors::DenoError;
 use crate::resources::Resource;
 use futures;
 use futures::Future;
 use futures::Poll;
-use std::io;
 use std::mem;
 use std::net::SocketAddr;
 use tokio;
@@ -82,7 +82,7 @@ pub struct Accept {
 
 impl Future for Accept {
   type Item = (TcpStream, SocketAddr);
-  type Error = io::Error;
+  type Error = DenoError;
 
   fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
     let (stream, addr) = match self.state {
@@ -124,6 +124,7 @@ where
   }
 }
 
+#[cfg(test)]
 pub fn panic_on_error<I, E, F>(f: F) -> impl Future<Item = I, Error = ()>
 where
   F: Future<Item = I, Error = E>,
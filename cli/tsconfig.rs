@@ -0,0 +1,349 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! The `compilerOptions` of a `tsconfig.json` given with `--config`. Only the
+//! options which make sense under deno are passed on to the compiler, the
+//! ones deno sets itself are ignored with a warning.
+use crate::errors;
+use crate::errors::DenoResult;
+use crate::fs as deno_fs;
+use crate::msg::ErrorKind;
+use serde_json;
+use serde_json::Map;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// The kind of value a compiler option takes.
+#[derive(Clone, Copy)]
+enum Kind {
+  Bool,
+  String,
+  OneOf(&'static [&'static str]),
+  /// A list of the libraries bundled with deno.
+  Lib,
+}
+
+const TARGETS: &[&str] = &[
+  "es3", "es5", "es6", "es2015", "es2016", "es2017", "es2018", "es2019",
+  "esnext",
+];
+
+const JSX: &[&str] = &["preserve", "react", "react-native"];
+
+/// The libraries in js/assets.ts.
+const LIBS: &[&str] = &[
+  "es5",
+  "es2015",
+  "es2015.collection",
+  "es2015.core",
+  "es2015.generator",
+  "es2015.iterable",
+  "es2015.promise",
+  "es2015.proxy",
+  "es2015.reflect",
+  "es2015.symbol",
+  "es2015.symbol.wellknown",
+  "es2016",
+  "es2016.array.include",
+  "es2017",
+  "es2017.intl",
+  "es2017.object",
+  "es2017.sharedmemory",
+  "es2017.string",
+  "es2017.typedarrays",
+  "es2018",
+  "es2018.asynciterable",
+  "es2018.intl",
+  "es2018.promise",
+  "es2018.regexp",
+  "es2019",
+  "es2019.array",
+  "es2019.string",
+  "es2019.symbol",
+  "esnext",
+  "esnext.array",
+  "esnext.asynciterable",
+  "esnext.bigint",
+  "esnext.intl",
+  "esnext.symbol",
+];
+
+const SUPPORTED: &[(&str, Kind)] = &[
+  ("allowJs", Kind::Bool),
+  ("allowSyntheticDefaultImports", Kind::Bool),
+  ("allowUnreachableCode", Kind::Bool),
+  ("allowUnusedLabels", Kind::Bool),
+  ("alwaysStrict", Kind::Bool),
+  ("checkJs", Kind::Bool),
+  ("downlevelIteration", Kind::Bool),
+  ("emitDecoratorMetadata", Kind::Bool),
+  ("esModuleInterop", Kind::Bool),
+  ("experimentalDecorators", Kind::Bool),
+  ("jsx", Kind::OneOf(JSX)),
+  ("jsxFactory", Kind::String),
+  ("keyofStringsOnly", Kind::Bool),
+  ("lib", Kind::Lib),
+  ("noFallthroughCasesInSwitch", Kind::Bool),
+  ("noImplicitAny", Kind::Bool),
+  ("noImplicitReturns", Kind::Bool),
+  ("noImplicitThis", Kind::Bool),
+  ("noImplicitUseStrict", Kind::Bool),
+  ("noStrictGenericChecks", Kind::Bool),
+  ("noUnusedLocals", Kind::Bool),
+  ("noUnusedParameters", Kind::Bool),
+  ("removeComments", Kind::Bool),
+  ("strict", Kind::Bool),
+  ("strictBindCallApply", Kind::Bool),
+  ("strictFunctionTypes", Kind::Bool),
+  ("strictNullChecks", Kind::Bool),
+  ("strictPropertyInitialization", Kind::Bool),
+  ("suppressExcessPropertyErrors", Kind::Bool),
+  ("suppressImplicitAnyIndexErrors", Kind::Bool),
+  ("target", Kind::OneOf(TARGETS)),
+];
+
+/// Options which deno sets itself, e.g. because it resolves and caches
+/// modules on its own.
+const IGNORED: &[&str] = &[
+  "allowNonTsExtensions",
+  "baseUrl",
+  "composite",
+  "declaration",
+  "declarationMap",
+  "incremental",
+  "inlineSourceMap",
+  "inlineSources",
+  "isolatedModules",
+  "module",
+  "moduleResolution",
+  "noEmit",
+  "noLib",
+  "noResolve",
+  "outDir",
+  "outFile",
+  "paths",
+  "resolveJsonModule",
+  "rootDir",
+  "rootDirs",
+  "sourceMap",
+  "typeRoots",
+  "types",
+];
+
+#[derive(Debug, PartialEq)]
+pub struct TsConfig {
+  /// The supported options, in the form of a `tsconfig.json`.
+  pub compiler_options: Value,
+  /// The options which were left out.
+  pub ignored: Vec<String>,
+}
+
+impl TsConfig {
+  /// Reads the config file given with `--config`.
+  pub fn load(filename: &str) -> DenoResult<TsConfig> {
+    let path = deno_fs::resolve_from_cwd(Path::new(filename))?;
+    let source = fs::read_to_string(&path).map_err(|e| {
+      let kind = errors::DenoError::from(e).kind();
+      errors::new(kind, format!("Unable to read config file \"{}\"", filename))
+    })?;
+    TsConfig::from_json(&source).map_err(|e| {
+      let msg = format!("Invalid config file \"{}\": {}", filename, e);
+      errors::new(e.kind(), msg)
+    })
+  }
+
+  /// Parses a `tsconfig.json`, which may contain comments and trailing
+  /// commas.
+  pub fn from_json(source: &str) -> DenoResult<TsConfig> {
+    let json = strip_comments(source);
+    // serde_json errors already include the line and column, which comments
+    // do not shift as they are replaced by spaces.
+    let v: Value =
+      serde_json::from_str(&json).map_err(|e| invalid(e.to_string()))?;
+    let options = match v {
+      Value::Object(mut map) => match map.remove("compilerOptions") {
+        None => Map::new(),
+        Some(Value::Object(options)) => options,
+        Some(_) => {
+          return Err(invalid(
+            "\"compilerOptions\" must be an object".to_string(),
+          ))
+        }
+      },
+      _ => return Err(invalid("expected a JSON object".to_string())),
+    };
+
+    let mut compiler_options = Map::new();
+    let mut ignored = Vec::new();
+    for (name, value) in options {
+      if IGNORED.contains(&name.as_str()) {
+        ignored.push(name);
+        continue;
+      }
+      let kind = match SUPPORTED.iter().find(|(n, _)| *n == name) {
+        Some((_, kind)) => *kind,
+        None => {
+          return Err(invalid(format!("unknown compiler option \"{}\"", name)))
+        }
+      };
+      check_value(&name, &value, kind)?;
+      compiler_options.insert(name, value);
+    }
+    Ok(TsConfig {
+      compiler_options: Value::Object(compiler_options),
+      ignored,
+    })
+  }
+}
+
+fn check_value(name: &str, value: &Value, kind: Kind) -> DenoResult<()> {
+  let is_valid = match (kind, value) {
+    (Kind::Bool, Value::Bool(_)) => true,
+    (Kind::String, Value::String(_)) => true,
+    (Kind::OneOf(values), Value::String(s)) => {
+      values.contains(&s.to_lowercase().as_str())
+    }
+    (Kind::Lib, Value::Array(libs)) => libs.iter().all(|lib| match lib {
+      Value::String(lib) => LIBS.contains(&lib.to_lowercase().as_str()),
+      _ => false,
+    }),
+    _ => false,
+  };
+  if is_valid {
+    return Ok(());
+  }
+  let expected = match kind {
+    Kind::Bool => "true or false".to_string(),
+    Kind::String => "a string".to_string(),
+    Kind::OneOf(values) => format!("one of {}", values.join(", ")),
+    Kind::Lib => format!("a list of {}", LIBS.join(", ")),
+  };
+  Err(invalid(format!(
+    "\"{}\" must be {}, not {}",
+    name, expected, value
+  )))
+}
+
+fn invalid(msg: String) -> errors::DenoError {
+  errors::new(ErrorKind::InvalidData, msg)
+}
+
+/// Replaces the comments and the trailing commas of `source` with spaces,
+/// newlines are kept.
+fn strip_comments(source: &str) -> String {
+  let mut out: Vec<char> = Vec::with_capacity(source.len());
+  let chars: Vec<char> = source.chars().collect();
+  // The position in `out` of the last comma outside of a string.
+  let mut comma = None;
+  let mut i = 0;
+  while i < chars.len() {
+    let c = chars[i];
+    let next = chars.get(i + 1).cloned();
+    if c == '"' {
+      // Strings are copied as they are, up to the closing quote.
+      out.push(c);
+      i += 1;
+      while i < chars.len() {
+        out.push(chars[i]);
+        if chars[i] == '\\' && i + 1 < chars.len() {
+          out.push(chars[i + 1]);
+          i += 1;
+        } else if chars[i] == '"' {
+          break;
+        }
+        i += 1;
+      }
+      comma = None;
+    } else if c == '/' && next == Some('/') {
+      while i < chars.len() && chars[i] != '\n' {
+        out.push(' ');
+        i += 1;
+      }
+      continue;
+    } else if c == '/' && next == Some('*') {
+      let mut j = i + 3;
+      while j < chars.len() && !(chars[j - 1] == '*' && chars[j] == '/') {
+        j += 1;
+      }
+      for &c in &chars[i..=j.min(chars.len() - 1)] {
+        out.push(if c == '\n' { '\n' } else { ' ' });
+      }
+      i = j + 1;
+      continue;
+    } else if c == '}' || c == ']' {
+      if let Some(comma) = comma.take() {
+        out[comma] = ' ';
+      }
+      out.push(c);
+    } else {
+      if c == ',' {
+        comma = Some(out.len());
+      } else if !c.is_whitespace() {
+        comma = None;
+      }
+      out.push(c);
+    }
+    i += 1;
+  }
+  out.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_from_json() {
+    let config = TsConfig::from_json(
+      r#"{
+        // Comments and trailing commas are allowed.
+        "compilerOptions": {
+          "strict": true, /* like in tsc */
+          "target": "ES2017",
+          "lib": ["esnext", "es2017.intl"],
+          "outDir": "build",
+        },
+        "include": ["src/**/*.ts"]
+      }"#,
+    ).unwrap();
+    assert_eq!(
+      config,
+      TsConfig {
+        compiler_options: json!({
+          "strict": true,
+          "target": "ES2017",
+          "lib": ["esnext", "es2017.intl"],
+        }),
+        ignored: vec!["outDir".to_string()],
+      }
+    );
+
+    let config = TsConfig::from_json("{}").unwrap();
+    assert_eq!(config.compiler_options, json!({}));
+  }
+
+  #[test]
+  fn test_invalid_config() {
+    let err = |source: &str| TsConfig::from_json(source).unwrap_err();
+    let e = err("{\n  \"compilerOptions\": {,\n}");
+    assert!(e.to_string().contains("line 2 column"), e.to_string());
+    let e = err(r#"{ "compilerOptions": { "strict": "yes" } }"#);
+    assert_eq!(e.to_string(), "\"strict\" must be true or false, not \"yes\"");
+    let e = err(r#"{ "compilerOptions": { "target": "es2042" } }"#);
+    assert!(e.to_string().starts_with("\"target\" must be one of es3, "));
+    let e = err(r#"{ "compilerOptions": { "lib": ["dom"] } }"#);
+    assert!(e.to_string().starts_with("\"lib\" must be a list of es5, "));
+    let e = err(r#"{ "compilerOptions": { "stict": true } }"#);
+    assert_eq!(e.to_string(), "unknown compiler option \"stict\"");
+    assert!(TsConfig::from_json(r#"{ "compilerOptions": [] }"#).is_err());
+  }
+
+  #[test]
+  fn test_strip_comments() {
+    assert_eq!(
+      strip_comments("{\"a\": \"//\\\"\", // b\n\"c\": [1,],}"),
+      "{\"a\": \"//\\\"\",     \n\"c\": [1 ] }"
+    );
+    assert_eq!(strip_comments("/* a\nb */1"), "    \n    1");
+    assert_eq!(strip_comments("1 /* unterminated"), "1                ");
+  }
+}
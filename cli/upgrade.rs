@@ -0,0 +1,354 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! `deno upgrade` replaces the running executable with a release downloaded
+//! from GitHub, the latest one unless `--version` names another. The archive
+//! is checked against the SHA-256 checksum published next to it. No script
+//! runs, so the downloads need no `--allow-net`.
+use crate::ansi;
+use crate::errors;
+use crate::errors::DenoError;
+use crate::errors::DenoResult;
+use crate::http_util;
+use crate::lockfile::sha256_hex;
+use crate::msg::ErrorKind;
+use crate::msg::FetchRedirect;
+use crate::version;
+use futures::future;
+use futures::Future;
+use futures::Stream;
+use hyper::header::HeaderValue;
+use hyper::header::USER_AGENT;
+use hyper::HeaderMap;
+use hyper::Method;
+use std::fs;
+use std::fs::File;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::runtime::Runtime;
+use url::Url;
+
+const LATEST_URL: &str =
+  "https://api.github.com/repos/denoland/deno/releases/latest";
+const DOWNLOAD_URL: &str = "https://github.com/denoland/deno/releases/download";
+
+pub struct Options {
+  /// The version to install, e.g. "0.4.0", by default the latest one.
+  pub version: Option<String>,
+  /// Where to install it instead of over the running executable.
+  pub output: Option<PathBuf>,
+  /// Download and check the release, but neither run nor install it.
+  pub dry_run: bool,
+  /// Don't print the URLs downloaded.
+  pub quiet: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+  /// The latest version is the running one.
+  UpToDate(String),
+  /// The version which was installed, or would be with `--dry-run`, and
+  /// where.
+  Installed(String, PathBuf),
+}
+
+/// The major, minor and patch numbers of a version like "0.4.0" or "v0.4.0".
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+  let version = version.trim_start_matches('v');
+  let parts: Vec<u64> = version
+    .split('.')
+    .map(str::parse::<u64>)
+    .collect::<Result<_, _>>()
+    .ok()?;
+  match parts[..] {
+    [major, minor, patch] => Some((major, minor, patch)),
+    _ => None,
+  }
+}
+
+/// The name of the release archive of this platform.
+fn archive_name() -> Option<&'static str> {
+  if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+    Some("deno_linux_x64.gz")
+  } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+    Some("deno_osx_x64.gz")
+  } else if cfg!(all(windows, target_arch = "x86_64")) {
+    Some("deno_win_x64.zip")
+  } else {
+    None
+  }
+}
+
+/// Downloads `url`, following redirects to HTTPS URLs only.
+fn download(
+  url: &str,
+  quiet: bool,
+) -> impl Future<Item = Vec<u8>, Error = DenoError> {
+  if !quiet {
+    let downloading = ansi::green_bold("Downloading".to_string());
+    eprintln!("{} {}", downloading, url);
+  }
+  let url = Url::parse(url).unwrap();
+  let mut headers = HeaderMap::new();
+  // The API of GitHub rejects requests without one.
+  let user_agent = format!("Deno/{}", version::DENO);
+  headers.insert(USER_AGENT, HeaderValue::from_str(&user_agent).unwrap());
+  let options = http_util::FetchOptions {
+    redirect: FetchRedirect::Follow,
+    max_redirects: http_util::DEFAULT_MAX_REDIRECTS,
+    cookies: false,
+  };
+  let check_url: http_util::CheckUrl = Arc::new(|url: &Url| {
+    if url.scheme() == "https" {
+      Ok(())
+    } else {
+      Err(errors::new(
+        ErrorKind::RedirectNotAllowed,
+        format!("Redirected to {}, which is not HTTPS", url),
+      ))
+    }
+  });
+  http_util::fetch(
+    Method::GET,
+    url,
+    headers,
+    http_util::FetchBody::Empty,
+    options,
+    check_url,
+  )
+  .and_then(|(response, url, _)| {
+    let status = response.status();
+    if !status.is_success() {
+      return future::Either::A(future::err(errors::new(
+        ErrorKind::NotFound,
+        format!("Unable to download {}: {}", url, status),
+      )));
+    }
+    let body = response.into_body().concat2().map_err(DenoError::from);
+    future::Either::B(body.map(|body| body.to_vec()))
+  })
+}
+
+/// The version of the latest release, e.g. "0.4.0".
+fn latest_version(runtime: &mut Runtime, quiet: bool) -> DenoResult<String> {
+  let body = runtime.block_on(download(LATEST_URL, quiet))?;
+  let invalid = |reason: String| {
+    errors::new(
+      ErrorKind::InvalidData,
+      format!(
+        "Unable to read the latest release at {}: {}",
+        LATEST_URL, reason
+      ),
+    )
+  };
+  let release: serde_json::Value =
+    serde_json::from_slice(&body).map_err(|e| invalid(e.to_string()))?;
+  release["tag_name"]
+    .as_str()
+    .map(|tag| tag.trim_start_matches('v').to_string())
+    .ok_or_else(|| invalid("no tag_name".to_string()))
+}
+
+/// Checks `archive` against a checksum file, whose first word is the
+/// SHA-256 hash in hex, as `sha256sum` writes it.
+fn verify(archive: &[u8], checksum: &str, url: &str) -> DenoResult<()> {
+  let expected = checksum.split_whitespace().next().unwrap_or("");
+  let actual = sha256_hex(archive);
+  if expected.eq_ignore_ascii_case(&actual) {
+    Ok(())
+  } else {
+    Err(errors::new(
+      ErrorKind::InvalidData,
+      format!(
+        "Checksum mismatch for {}\n  expected: {}\n  actual:   {}",
+        url, expected, actual
+      ),
+    ))
+  }
+}
+
+fn command_failed(what: &str, detail: String) -> DenoError {
+  errors::new(ErrorKind::CommandFailed, format!("{}: {}", what, detail))
+}
+
+/// Unpacks the executable of `archive` into `dir` and returns its path. The
+/// archives are unpacked with the tools every installation of the platform
+/// has, gzip and PowerShell.
+fn unpack(archive: &[u8], dir: &Path) -> DenoResult<PathBuf> {
+  let archive_path = dir.join(archive_name().unwrap());
+  fs::write(&archive_path, archive)?;
+  let (exe_path, status) = if cfg!(windows) {
+    let status = Command::new("powershell.exe")
+      .arg("-NoLogo")
+      .arg("-NoProfile")
+      .arg("-NonInteractive")
+      .arg("-Command")
+      .arg("Expand-Archive -Force $env:ARCHIVE $env:DIR")
+      .env("ARCHIVE", &archive_path)
+      .env("DIR", dir)
+      .status();
+    (dir.join("deno.exe"), status)
+  } else {
+    let exe_path = dir.join("deno");
+    let status = Command::new("gzip")
+      .arg("-dc")
+      .arg(&archive_path)
+      .stdout(File::create(&exe_path)?)
+      .status();
+    (exe_path, status)
+  };
+  let what = format!("Unable to unpack {}", archive_path.display());
+  match status {
+    Ok(ref status) if status.success() => {}
+    Ok(status) => return Err(command_failed(&what, status.to_string())),
+    Err(err) => return Err(command_failed(&what, err.to_string())),
+  }
+  if !exe_path.is_file() {
+    return Err(command_failed(&what, "no executable in it".to_string()));
+  }
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(&exe_path, fs::Permissions::from_mode(0o755))?;
+  }
+  Ok(exe_path)
+}
+
+/// Moves `new_exe` to `target` in one step, so that `target` is at all times
+/// either the old or the new executable. Windows doesn't replace a running
+/// executable, but renames it, so the old one is moved aside first.
+fn replace(new_exe: &Path, target: &Path) -> DenoResult<()> {
+  let file_name = target.file_name().unwrap().to_string_lossy();
+  // Next to the target, a rename across file systems wouldn't be atomic.
+  let staged = target.with_file_name(format!(".{}.new", file_name));
+  fs::copy(new_exe, &staged)?;
+  let old = target.with_file_name(format!("{}.old", file_name));
+  if cfg!(windows) && target.exists() {
+    // Left by the last upgrade, it is no longer running.
+    if old.exists() {
+      fs::remove_file(&old)?;
+    }
+    if let Err(err) = fs::rename(target, &old) {
+      fs::remove_file(&staged).ok();
+      return Err(err.into());
+    }
+  }
+  if let Err(err) = fs::rename(&staged, target) {
+    if cfg!(windows) && old.exists() {
+      fs::rename(&old, target).ok();
+    }
+    fs::remove_file(&staged).ok();
+    return Err(err.into());
+  }
+  Ok(())
+}
+
+/// Downloads the release `options` ask for and installs it, unless it is
+/// the running version and no other place to install it is given.
+pub fn upgrade(options: &Options) -> DenoResult<Outcome> {
+  let archive = archive_name().ok_or_else(|| {
+    errors::new(
+      ErrorKind::NotSupported,
+      "There are no releases of deno for this platform".to_string(),
+    )
+  })?;
+  let mut runtime = Runtime::new()?;
+  let version = match options.version {
+    Some(ref version) => {
+      if parse_version(version).is_none() {
+        return Err(errors::new(
+          ErrorKind::InvalidInput,
+          format!("Invalid version \"{}\", expected e.g. 0.4.0", version),
+        ));
+      }
+      version.trim_start_matches('v').to_string()
+    }
+    None => {
+      let latest = latest_version(&mut runtime, options.quiet)?;
+      let is_newer =
+        match (parse_version(&latest), parse_version(version::DENO)) {
+          (Some(latest), Some(current)) => latest > current,
+          _ => latest != version::DENO,
+        };
+      if !is_newer && options.output.is_none() {
+        return Ok(Outcome::UpToDate(latest));
+      }
+      latest
+    }
+  };
+  let url = format!("{}/v{}/{}", DOWNLOAD_URL, version, archive);
+  let checksum_url = format!("{}.sha256", url);
+  let bytes = runtime.block_on(download(&url, options.quiet))?;
+  let checksum = runtime.block_on(download(&checksum_url, options.quiet))?;
+  verify(&bytes, &String::from_utf8_lossy(&checksum), &url)?;
+
+  let target = match options.output {
+    Some(ref output) => output.clone(),
+    None => std::env::current_exe()?,
+  };
+  let dir = TempDir::new()?;
+  let new_exe = unpack(&bytes, dir.path())?;
+  // A dry run executes nothing it downloaded.
+  if options.dry_run {
+    return Ok(Outcome::Installed(version, target));
+  }
+  let runs = Command::new(&new_exe)
+    .arg("--version")
+    .output()
+    .map(|output| output.status.success())
+    .unwrap_or(false);
+  if !runs {
+    return Err(command_failed(
+      &format!("Unable to run deno {} from {}", version, url),
+      "it exited with an error".to_string(),
+    ));
+  }
+  replace(&new_exe, &target)?;
+  Ok(Outcome::Installed(version, target))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_version() {
+    assert_eq!(parse_version("0.4.0"), Some((0, 4, 0)));
+    assert_eq!(parse_version("v1.12.3"), Some((1, 12, 3)));
+    assert_eq!(parse_version("0.4"), None);
+    assert_eq!(parse_version("0.4.0-rc1"), None);
+    assert_eq!(parse_version("latest"), None);
+    assert!(parse_version("0.10.0") > parse_version("0.9.1"));
+  }
+
+  #[test]
+  fn test_verify() {
+    let hash = sha256_hex(b"deno");
+    assert!(verify(b"deno", &hash, "x").is_ok());
+    let line = format!("{}  deno_linux_x64.gz\n", hash.to_uppercase());
+    assert!(verify(b"deno", &line, "x").is_ok());
+    let err = verify(b"dino", &hash, "https://example.com/a.gz").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.to_string().contains("https://example.com/a.gz"));
+    assert!(verify(b"deno", "", "x").is_err());
+  }
+
+  #[test]
+  fn test_replace() {
+    let dir = TempDir::new().unwrap();
+    let new_exe = dir.path().join("new");
+    let target = dir.path().join("deno");
+    fs::write(&new_exe, "new").unwrap();
+    fs::write(&target, "old").unwrap();
+    replace(&new_exe, &target).unwrap();
+    assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+    assert!(!dir.path().join(".deno.new").exists());
+
+    // A place where nothing was installed yet.
+    let output = dir.path().join("bin").join("deno");
+    fs::create_dir(dir.path().join("bin")).unwrap();
+    replace(&new_exe, &output).unwrap();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "new");
+  }
+}
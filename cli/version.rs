@@ -1,6 +1,20 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
 pub const DENO: &str = env!("CARGO_PKG_VERSION");
 
+lazy_static! {
+  static ref TYPESCRIPT: String = {
+    let package: serde_json::Value = serde_json::from_str(include_str!(
+      "../third_party/node_modules/typescript/package.json"
+    )).unwrap();
+    package["version"].as_str().unwrap().to_string()
+  };
+}
+
 pub fn v8() -> &'static str {
   deno::v8_version()
 }
+
+/// The version of the TypeScript compiler in the snapshot.
+pub fn typescript() -> &'static str {
+  &TYPESCRIPT
+}
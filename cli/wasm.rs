@@ -0,0 +1,251 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! WebAssembly modules are loaded through a JS module which imports what the
+//! wasm module imports, instantiates it and exports its exports. That way the
+//! imports of a wasm module are part of the module graph like any other.
+use serde_json;
+
+const SECTION_IMPORT: u8 = 2;
+const SECTION_EXPORT: u8 = 7;
+
+const EXTERNAL_FUNCTION: u8 = 0;
+const EXTERNAL_TABLE: u8 = 1;
+const EXTERNAL_MEMORY: u8 = 2;
+const EXTERNAL_GLOBAL: u8 = 3;
+
+/// The module names of the imports and the names of the exports of a wasm
+/// module.
+#[derive(Debug, Default, PartialEq)]
+pub struct WasmInterface {
+  pub imports: Vec<String>,
+  pub exports: Vec<String>,
+}
+
+/// Returns the JS module which stands in for the wasm module `bytes`.
+pub fn js_source(bytes: &[u8]) -> String {
+  // Invalid bytes are left to V8, which reports a proper CompileError when
+  // the module is evaluated.
+  let interface = parse(bytes).unwrap_or_default();
+  let mut source = String::from("// @ts-nocheck\n");
+  for (i, specifier) in interface.imports.iter().enumerate() {
+    source.push_str(&format!(
+      "import * as wasmImport{} from {};\n",
+      i,
+      serde_json::to_string(specifier).unwrap()
+    ));
+  }
+  source.push_str(&format!(
+    "const wasmBytes = Uint8Array.from(atob(\"{}\"), c => c.charCodeAt(0));\n\
+     const wasmModule = new WebAssembly.Module(wasmBytes);\n\
+     const wasmInstance = new WebAssembly.Instance(wasmModule, {{\n",
+    base64::encode(bytes)
+  ));
+  for (i, specifier) in interface.imports.iter().enumerate() {
+    source.push_str(&format!(
+      "  {}: wasmImport{},\n",
+      serde_json::to_string(specifier).unwrap(),
+      i
+    ));
+  }
+  source.push_str("});\n");
+  for (i, name) in interface.exports.iter().enumerate() {
+    if !is_identifier_name(name) {
+      debug!("wasm export {:?} is not a valid JS export name", name);
+      continue;
+    }
+    source.push_str(&format!(
+      "const wasmExport{} = wasmInstance.exports[{}];\n\
+       export {{ wasmExport{} as {} }};\n",
+      i,
+      serde_json::to_string(name).unwrap(),
+      i,
+      name
+    ));
+  }
+  source
+}
+
+/// Reads the import and export sections of a wasm module. Each imported
+/// module is listed once.
+pub fn parse(bytes: &[u8]) -> Result<WasmInterface, String> {
+  let mut reader = Reader { bytes, pos: 0 };
+  if reader.take(4)? != b"\0asm" {
+    return Err("not a WebAssembly module".to_string());
+  }
+  if reader.take(4)? != [1, 0, 0, 0] {
+    return Err("unsupported WebAssembly version".to_string());
+  }
+  let mut interface = WasmInterface::default();
+  while !reader.is_empty() {
+    let id = reader.byte()?;
+    let size = reader.u32()? as usize;
+    let mut section = Reader {
+      bytes: reader.take(size)?,
+      pos: 0,
+    };
+    match id {
+      SECTION_IMPORT => {
+        for _ in 0..section.u32()? {
+          let module = section.name()?;
+          section.name()?;
+          section.import_desc()?;
+          if !interface.imports.contains(&module) {
+            interface.imports.push(module);
+          }
+        }
+      }
+      SECTION_EXPORT => {
+        for _ in 0..section.u32()? {
+          interface.exports.push(section.name()?);
+          section.byte()?;
+          section.u32()?;
+        }
+      }
+      _ => {}
+    }
+  }
+  Ok(interface)
+}
+
+/// Whether `name` can be used in `export { x as name }`.
+fn is_identifier_name(name: &str) -> bool {
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(c) if c.is_alphabetic() || c == '_' || c == '$' => {
+      chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+    }
+    _ => false,
+  }
+}
+
+struct Reader<'a> {
+  bytes: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn is_empty(&self) -> bool {
+    self.pos >= self.bytes.len()
+  }
+
+  fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+    if self.bytes.len() - self.pos < len {
+      return Err("unexpected end of WebAssembly module".to_string());
+    }
+    let bytes = &self.bytes[self.pos..self.pos + len];
+    self.pos += len;
+    Ok(bytes)
+  }
+
+  fn byte(&mut self) -> Result<u8, String> {
+    Ok(self.take(1)?[0])
+  }
+
+  /// An unsigned LEB128 number of at most 32 bits.
+  fn u32(&mut self) -> Result<u32, String> {
+    let mut result: u32 = 0;
+    for shift in (0..35).step_by(7) {
+      let byte = self.byte()?;
+      result |= u32::from(byte & 0x7f) << shift;
+      if byte & 0x80 == 0 {
+        return Ok(result);
+      }
+    }
+    Err("invalid LEB128 number in WebAssembly module".to_string())
+  }
+
+  fn name(&mut self) -> Result<String, String> {
+    let len = self.u32()? as usize;
+    let bytes = self.take(len)?;
+    String::from_utf8(bytes.to_vec())
+      .map_err(|_| "invalid name in WebAssembly module".to_string())
+  }
+
+  fn limits(&mut self) -> Result<(), String> {
+    let flags = self.u32()?;
+    self.u32()?;
+    if flags & 1 != 0 {
+      self.u32()?;
+    }
+    Ok(())
+  }
+
+  fn import_desc(&mut self) -> Result<(), String> {
+    match self.byte()? {
+      EXTERNAL_FUNCTION => {
+        self.u32()?;
+      }
+      EXTERNAL_TABLE => {
+        self.byte()?;
+        self.limits()?;
+      }
+      EXTERNAL_MEMORY => {
+        self.limits()?;
+      }
+      EXTERNAL_GLOBAL => {
+        self.byte()?;
+        self.byte()?;
+      }
+      kind => return Err(format!("unknown import kind {}", kind)),
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // (module
+  //   (import "./math.js" "double" (func $double (param i32) (result i32)))
+  //   (import "./math.js" "memory" (memory 1))
+  //   (func (export "quadruple") (param i32) (result i32)
+  //     (call $double (call $double (get_local 0)))))
+  const QUADRUPLE: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60,
+    0x01, 0x7f, 0x01, 0x7f, 0x02, 0x28, 0x02, 0x09, 0x2e, 0x2f, 0x6d, 0x61,
+    0x74, 0x68, 0x2e, 0x6a, 0x73, 0x06, 0x64, 0x6f, 0x75, 0x62, 0x6c, 0x65,
+    0x00, 0x00, 0x09, 0x2e, 0x2f, 0x6d, 0x61, 0x74, 0x68, 0x2e, 0x6a, 0x73,
+    0x06, 0x6d, 0x65, 0x6d, 0x6f, 0x72, 0x79, 0x02, 0x00, 0x01, 0x03, 0x02,
+    0x01, 0x00, 0x07, 0x0d, 0x01, 0x09, 0x71, 0x75, 0x61, 0x64, 0x72, 0x75,
+    0x70, 0x6c, 0x65, 0x00, 0x01, 0x0a, 0x0a, 0x01, 0x08, 0x00, 0x20, 0x00,
+    0x10, 0x00, 0x10, 0x00, 0x0b,
+  ];
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(
+      parse(QUADRUPLE).unwrap(),
+      WasmInterface {
+        imports: vec!["./math.js".to_string()],
+        exports: vec!["quadruple".to_string()],
+      }
+    );
+    assert!(parse(b"\0asm").is_err());
+    assert!(parse(b"export {};").is_err());
+    assert!(parse(&QUADRUPLE[..QUADRUPLE.len() - 1]).is_err());
+  }
+
+  #[test]
+  fn test_js_source() {
+    let source = js_source(QUADRUPLE);
+    assert!(source.contains("import * as wasmImport0 from \"./math.js\";\n"));
+    assert!(source.contains("  \"./math.js\": wasmImport0,\n"));
+    assert!(source.contains("export { wasmExport0 as quadruple };\n"));
+    assert!(source.contains(&base64::encode(QUADRUPLE)));
+
+    // Left to V8 to reject.
+    let source = js_source(b"export {};");
+    assert!(!source.contains("import"));
+    assert!(source.contains("new WebAssembly.Module(wasmBytes)"));
+  }
+
+  #[test]
+  fn test_is_identifier_name() {
+    assert!(is_identifier_name("quadruple"));
+    assert!(is_identifier_name("$_a1"));
+    assert!(is_identifier_name("default"));
+    assert!(!is_identifier_name("1a"));
+    assert!(!is_identifier_name("a-b"));
+    assert!(!is_identifier_name(""));
+  }
+}
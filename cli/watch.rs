@@ -0,0 +1,279 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! `--watch` runs the script in a child process and restarts it whenever one
+//! of its local modules changes. Once its module graph is loaded the child
+//! reports the local files of the graph back over a loopback connection, so
+//! that imports added since the last run are watched too.
+use crate::flags;
+use crate::flags::DenoFlags;
+use crate::fs as deno_fs;
+use crate::modules::Modules;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Child;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+use std::time::SystemTime;
+use url::Url;
+
+/// Tells the child where to report its modules to.
+pub const MODULES_ENV: &str = "DENO_WATCH_MODULES";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Editors often write a file several times per save.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Sends the local files of the loaded module graph to the watching process
+/// at `addr`. Remote modules are not watched.
+pub fn report_modules(addr: &str, modules: &Modules) -> io::Result<()> {
+  let mut report = format!("{}\n", std::process::id());
+  for name in modules.names() {
+    let path = Url::parse(name)
+      .ok()
+      .and_then(|url| url.to_file_path().ok());
+    if let Some(path) = path {
+      report.push_str(&path.to_string_lossy());
+      report.push('\n');
+    }
+  }
+  TcpStream::connect(addr)?.write_all(report.as_bytes())
+}
+
+/// The arguments of the child which runs `script_argv`, the script and its
+/// arguments, with `flags` but `--watch`. The flags are the parsed ones, so
+/// those of DENO_FLAGS and of `--flags-file` are passed on as they were
+/// applied, and the child doesn't read them again.
+fn child_argv(flags: &DenoFlags, script_argv: &[String]) -> Vec<String> {
+  let mut argv = vec!["run".to_string()];
+  argv.extend(flags.to_argv().into_iter().filter(|arg| arg != "--watch"));
+  // The arguments of the script are never taken for flags of deno.
+  argv.push("--".to_string());
+  argv.extend(script_argv.iter().cloned());
+  argv
+}
+
+/// Runs `script_argv`, the script and its arguments, with `flags` until deno
+/// is interrupted. Ctrl-C reaches the child as well, as both are in the
+/// foreground process group of the terminal.
+pub fn run(flags: &DenoFlags, script_argv: &[String]) -> ! {
+  let main_module = &script_argv[0];
+  let main_path = match local_path(main_module) {
+    Some(path) => path,
+    None => {
+      eprintln!("--watch needs a local script, not \"{}\"", main_module);
+      std::process::exit(1);
+    }
+  };
+  let args = child_argv(flags, script_argv);
+
+  let listener = TcpListener::bind("127.0.0.1:0")
+    .and_then(|listener| {
+      listener.set_nonblocking(true)?;
+      Ok(listener)
+    }).unwrap_or_else(|err| {
+      eprintln!("Unable to watch \"{}\": {}", main_module, err);
+      std::process::exit(1);
+    });
+  let report_addr = listener.local_addr().unwrap().to_string();
+
+  let mut watched = Watched::new();
+  watched.update(vec![main_path], SystemTime::now());
+  loop {
+    let started = SystemTime::now();
+    let mut child = Command::new(env::current_exe().unwrap())
+      .args(&args)
+      .env(MODULES_ENV, &report_addr)
+      .env_remove(flags::FLAGS_ENV)
+      .spawn()
+      .unwrap_or_else(|err| {
+        eprintln!("Unable to run \"{}\": {}", main_module, err);
+        std::process::exit(1);
+      });
+
+    let changed = wait_for_change(&mut watched, &listener, &mut child, started);
+    // Wait until the files settle.
+    loop {
+      thread::sleep(DEBOUNCE);
+      if watched.changed().is_none() {
+        break;
+      }
+    }
+    if flags.shows_info() {
+      eprintln!("[deno] restarting on change: {}", changed.display());
+    }
+    child.kill().ok();
+    child.wait().ok();
+  }
+}
+
+/// Until the child reports its modules, the files of the last graph stay
+/// watched. That covers a child which fails to compile before reporting.
+fn wait_for_change(
+  watched: &mut Watched,
+  listener: &TcpListener,
+  child: &mut Child,
+  started: SystemTime,
+) -> PathBuf {
+  loop {
+    thread::sleep(POLL_INTERVAL);
+    if let Some(paths) = accept_report(listener, child.id()) {
+      debug!("watching {:?}", paths);
+      watched.update(paths, started);
+    }
+    if let Some(path) = watched.changed() {
+      return path;
+    }
+    // Reaps a child that exited by itself, deno keeps watching.
+    child.try_wait().ok();
+  }
+}
+
+/// Returns the files reported by the child `pid`, if it reported them. The
+/// reports of other processes which inherited MODULES_ENV are ignored.
+fn accept_report(listener: &TcpListener, pid: u32) -> Option<Vec<PathBuf>> {
+  loop {
+    let (mut stream, _) = listener.accept().ok()?;
+    let mut report = String::new();
+    if stream.set_nonblocking(false).is_err()
+      || stream.read_to_string(&mut report).is_err()
+    {
+      continue;
+    }
+    let mut lines = report.lines();
+    if lines.next() != Some(pid.to_string().as_str()) {
+      continue;
+    }
+    return Some(lines.map(PathBuf::from).collect());
+  }
+}
+
+fn local_path(module: &str) -> Option<PathBuf> {
+  match Url::parse(module) {
+    Ok(url) => url.to_file_path().ok(),
+    // Not a URL, so a path.
+    Err(_) => deno_fs::resolve_from_cwd(Path::new(module)).ok(),
+  }
+}
+
+/// The watched files and their modification times at the last check.
+struct Watched {
+  mtimes: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl Watched {
+  fn new() -> Self {
+    Self {
+      mtimes: HashMap::new(),
+    }
+  }
+
+  /// Watches `paths` instead of the current files. Files which were watched
+  /// before keep their modification time, and new files modified after
+  /// `since` count as changed, so that changes made while the child was
+  /// loading are not missed.
+  fn update(&mut self, paths: Vec<PathBuf>, since: SystemTime) {
+    let mut mtimes = HashMap::new();
+    for path in paths {
+      let mtime = match self.mtimes.remove(&path) {
+        Some(mtime) => mtime,
+        None => match mtime(&path) {
+          Some(mtime) if mtime > since => Some(since),
+          mtime => mtime,
+        },
+      };
+      mtimes.insert(path, mtime);
+    }
+    self.mtimes = mtimes;
+  }
+
+  /// Returns a file which was modified, created or removed since the last
+  /// check, if there is one.
+  fn changed(&mut self) -> Option<PathBuf> {
+    let mut changed = None;
+    for (path, last) in self.mtimes.iter_mut() {
+      let current = mtime(path);
+      if current != *last {
+        *last = current;
+        changed = Some(path.clone());
+      }
+    }
+    changed
+  }
+}
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+  fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  // Creates vector of strings, Vec<String>
+  macro_rules! svec {
+      ($($x:expr),*) => (vec![$($x.to_string()),*]);
+  }
+
+  #[test]
+  fn test_child_argv() {
+    let args = flags::add_preset_flags(
+      svec!["deno", "--reload", "x.ts", "--watch"],
+      Some("--watch --allow-net"),
+    ).unwrap();
+    let (flags, rest_argv) = flags::set_flags(args).unwrap();
+    assert!(flags.watch);
+    assert_eq!(
+      child_argv(&flags, &rest_argv[1..]),
+      svec!["run", "--allow-net", "--reload", "--", "x.ts", "--watch"]
+    );
+    // V8 is given the same options in the child.
+    let (flags, rest_argv) = flags::set_flags(svec![
+      "deno",
+      "--watch",
+      "--v8-flags=--expose-gc",
+      "x.ts"
+    ]).unwrap();
+    assert_eq!(
+      child_argv(&flags, &rest_argv[1..]),
+      svec!["run", "--v8-flags=--expose-gc", "--", "x.ts"]
+    );
+  }
+
+  #[test]
+  fn test_watched_changed() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let a = temp_dir.path().join("a.ts");
+    let b = temp_dir.path().join("b.ts");
+    fs::write(&a, "import './b.ts';").unwrap();
+
+    let mut watched = Watched::new();
+    watched.update(vec![a.clone(), b.clone()], SystemTime::now());
+    assert_eq!(watched.changed(), None);
+
+    // A new file counts as a change, the next check is clean again.
+    fs::write(&b, "").unwrap();
+    assert_eq!(watched.changed(), Some(b.clone()));
+    assert_eq!(watched.changed(), None);
+
+    // Files which stay watched keep their state.
+    watched.update(vec![a.clone()], SystemTime::now());
+    fs::remove_file(&a).unwrap();
+    assert_eq!(watched.changed(), Some(a.clone()));
+    assert_eq!(watched.mtimes.len(), 1);
+
+    // A new file which was modified after the child started.
+    let mut watched = Watched::new();
+    let since = SystemTime::UNIX_EPOCH;
+    watched.update(vec![b.clone()], since);
+    assert_eq!(watched.changed(), Some(b.clone()));
+  }
+}
@@ -0,0 +1,720 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+//! The WebSocket client of `Deno.dialWebSocket()`, RFC 6455. The opening
+//! handshake is an upgrade of a request of the fetch client, the frames are
+//! read and written here: the frames of the client are masked, fragmented
+//! messages are reassembled and pings answered. The frame codec and the
+//! accept key are shared with the server of `--inspect`.
+use crate::errors;
+use crate::errors::DenoError;
+use crate::errors::DenoResult;
+use crate::http_util;
+use crate::msg::ErrorKind;
+use base64;
+use futures;
+use futures::Async;
+use futures::Future;
+use futures::Poll;
+use hyper::header::HeaderName;
+use hyper::header::CONNECTION;
+use hyper::header::SEC_WEBSOCKET_ACCEPT;
+use hyper::header::SEC_WEBSOCKET_KEY;
+use hyper::header::SEC_WEBSOCKET_PROTOCOL;
+use hyper::header::SEC_WEBSOCKET_VERSION;
+use hyper::header::UPGRADE;
+use hyper::upgrade::Upgraded;
+use hyper::Body;
+use hyper::Request;
+use hyper::StatusCode;
+use rand;
+use ring::digest;
+use std::collections::VecDeque;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use url::Url;
+
+/// Appended to the key of the handshake to compute the accept header.
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Larger messages fail the connection.
+const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+const READ_SIZE: usize = 16 * 1024;
+
+pub const CONTINUATION: u8 = 0x0;
+pub const TEXT: u8 = 0x1;
+pub const BINARY: u8 = 0x2;
+pub const CLOSE: u8 = 0x8;
+pub const PING: u8 = 0x9;
+pub const PONG: u8 = 0xa;
+
+/// The close code of a connection which ended without a close frame. It is
+/// reported, never sent.
+pub const ABNORMAL_CLOSURE: u16 = 1006;
+/// The code reported for a close frame without one.
+pub const NO_STATUS_RECEIVED: u16 = 1005;
+const PROTOCOL_ERROR: u16 = 1002;
+const INVALID_DATA: u16 = 1007;
+const MESSAGE_TOO_BIG: u16 = 1009;
+
+#[derive(Debug, PartialEq)]
+pub enum Message {
+  Text(String),
+  Binary(Vec<u8>),
+  Ping(Vec<u8>),
+  Pong(Vec<u8>),
+  /// The code, if there is one, and the reason.
+  Close(Option<u16>, String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Frame {
+  pub fin: bool,
+  pub opcode: u8,
+  /// Unmasked.
+  pub payload: Vec<u8>,
+}
+
+/// Why the connection has to be failed, the code is sent in the close frame.
+#[derive(Debug, PartialEq)]
+pub struct Failure(pub u16, pub &'static str);
+
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+  for (i, byte) in payload.iter_mut().enumerate() {
+    *byte ^= mask[i % 4];
+  }
+}
+
+/// Parses the frame at the start of `buf`, and returns it with its length.
+/// None if it is not complete yet. Only the frames of a client are `masked`.
+pub fn parse_frame(
+  buf: &[u8],
+  masked: bool,
+) -> Result<Option<(Frame, usize)>, Failure> {
+  if buf.len() < 2 {
+    return Ok(None);
+  }
+  let fin = buf[0] & 0x80 != 0;
+  let opcode = buf[0] & 0x0f;
+  if buf[0] & 0x70 != 0 {
+    return Err(Failure(PROTOCOL_ERROR, "reserved bits are set"));
+  }
+  match (buf[1] & 0x80 != 0, masked) {
+    (true, false) => {
+      return Err(Failure(PROTOCOL_ERROR, "the server masked a frame"))
+    }
+    (false, true) => {
+      return Err(Failure(PROTOCOL_ERROR, "the client did not mask a frame"))
+    }
+    _ => {}
+  }
+  let (len, start) = match buf[1] & 0x7f {
+    126 if buf.len() < 4 => return Ok(None),
+    126 => (u64::from(u16::from_be_bytes([buf[2], buf[3]])), 4),
+    127 if buf.len() < 10 => return Ok(None),
+    127 => {
+      let mut bytes = [0; 8];
+      bytes.copy_from_slice(&buf[2..10]);
+      (u64::from_be_bytes(bytes), 10)
+    }
+    len => (u64::from(len), 2),
+  };
+  if opcode >= CLOSE && (!fin || len > 125) {
+    return Err(Failure(PROTOCOL_ERROR, "invalid control frame"));
+  }
+  if len > MAX_MESSAGE_SIZE as u64 {
+    return Err(Failure(MESSAGE_TOO_BIG, "the message is too big"));
+  }
+  let mask_start = start;
+  let start = if masked { start + 4 } else { start };
+  let end = start + len as usize;
+  if buf.len() < end {
+    return Ok(None);
+  }
+  let mut payload = buf[start..end].to_vec();
+  if masked {
+    let mut mask = [0; 4];
+    mask.copy_from_slice(&buf[mask_start..start]);
+    apply_mask(&mut payload, mask);
+  }
+  let frame = Frame {
+    fin,
+    opcode,
+    payload,
+  };
+  Ok(Some((frame, end)))
+}
+
+/// A frame which is not fragmented. Only the frames of a client have a
+/// `mask`.
+pub fn encode_frame(
+  opcode: u8,
+  payload: &[u8],
+  mask: Option<[u8; 4]>,
+) -> Vec<u8> {
+  let mut frame = Vec::with_capacity(payload.len() + 14);
+  frame.push(0x80 | opcode);
+  let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+  let len = payload.len();
+  if len < 126 {
+    frame.push(mask_bit | len as u8);
+  } else if len <= 0xffff {
+    frame.push(mask_bit | 126);
+    frame.extend_from_slice(&(len as u16).to_be_bytes());
+  } else {
+    frame.push(mask_bit | 127);
+    frame.extend_from_slice(&(len as u64).to_be_bytes());
+  }
+  if let Some(mask) = mask {
+    frame.extend_from_slice(&mask);
+  }
+  let start = frame.len();
+  frame.extend_from_slice(payload);
+  if let Some(mask) = mask {
+    apply_mask(&mut frame[start..], mask);
+  }
+  frame
+}
+
+fn close_payload(code: Option<u16>, reason: &str) -> Vec<u8> {
+  match code {
+    None => Vec::new(),
+    Some(code) => {
+      let mut payload = code.to_be_bytes().to_vec();
+      payload.extend_from_slice(reason.as_bytes());
+      payload
+    }
+  }
+}
+
+fn parse_close_payload(
+  payload: Vec<u8>,
+) -> Result<(Option<u16>, String), Failure> {
+  match payload.len() {
+    0 => Ok((None, String::new())),
+    1 => Err(Failure(PROTOCOL_ERROR, "invalid close frame")),
+    _ => {
+      let code = u16::from_be_bytes([payload[0], payload[1]]);
+      match String::from_utf8(payload[2..].to_vec()) {
+        Ok(reason) => Ok((Some(code), reason)),
+        Err(_) => Err(Failure(INVALID_DATA, "the reason is not UTF-8")),
+      }
+    }
+  }
+}
+
+fn closed_error() -> DenoError {
+  errors::new(ErrorKind::BrokenPipe, "The WebSocket is closed".to_string())
+}
+
+/// A connection after the opening handshake. It ends with the closing
+/// handshake, or when it is dropped.
+pub struct WebSocket {
+  /// None once the closing handshake is done, or the connection failed.
+  io: Option<Upgraded>,
+  read_buf: Vec<u8>,
+  write_buf: Vec<u8>,
+  /// The opcode and the payloads so far of a fragmented message.
+  fragments: Option<(u8, Vec<u8>)>,
+  messages: VecDeque<Message>,
+  sent_close: bool,
+  received_close: bool,
+  /// The receives, sends and closes which wait, the connection is shared by
+  /// them. All are woken when one makes progress.
+  tasks: Vec<futures::task::Task>,
+}
+
+impl WebSocket {
+  fn new(io: Option<Upgraded>) -> Self {
+    WebSocket {
+      io,
+      read_buf: Vec::new(),
+      write_buf: Vec::new(),
+      fragments: None,
+      messages: VecDeque::new(),
+      sent_close: false,
+      received_close: false,
+      tasks: Vec::new(),
+    }
+  }
+
+  /// Queues `message`, which is written by `poll_flush()`. After a close
+  /// nothing can be sent anymore.
+  pub fn send(&mut self, message: Message) -> DenoResult<()> {
+    if self.sent_close || self.io.is_none() {
+      return Err(closed_error());
+    }
+    let (opcode, payload) = match message {
+      Message::Text(text) => (TEXT, text.into_bytes()),
+      Message::Binary(data) => (BINARY, data),
+      Message::Ping(data) => (PING, data),
+      Message::Pong(data) => (PONG, data),
+      Message::Close(code, reason) => (CLOSE, close_payload(code, &reason)),
+    };
+    if opcode >= CLOSE && payload.len() > 125 {
+      return Err(errors::new(
+        ErrorKind::InvalidInput,
+        "The data of a control frame is limited to 125 bytes".to_string(),
+      ));
+    }
+    self.write_frame(opcode, &payload);
+    Ok(())
+  }
+
+  /// Queues a close message, unless one was sent already, e.g. in reply to
+  /// the one of the server. `poll_close()` waits for the reply.
+  pub fn close(&mut self, code: u16, reason: &str) -> DenoResult<()> {
+    if self.sent_close || self.io.is_none() {
+      return Ok(());
+    }
+    self.send(Message::Close(Some(code), reason.to_string()))
+  }
+
+  fn write_frame(&mut self, opcode: u8, payload: &[u8]) {
+    if opcode == CLOSE {
+      self.sent_close = true;
+    }
+    let frame = encode_frame(opcode, payload, Some(rand::random()));
+    self.write_buf.extend_from_slice(&frame);
+  }
+
+  /// Writes the frames which were queued.
+  pub fn poll_flush(&mut self) -> Poll<(), DenoError> {
+    let result = self.poll_write_buf();
+    self.track_task(result)
+  }
+
+  fn poll_write_buf(&mut self) -> Poll<(), DenoError> {
+    let io = match self.io {
+      Some(ref mut io) => io,
+      None => {
+        self.write_buf.clear();
+        return Ok(Async::Ready(()));
+      }
+    };
+    while !self.write_buf.is_empty() {
+      let n = try_ready!(io.poll_write(&self.write_buf));
+      if n == 0 {
+        return Err(closed_error());
+      }
+      self.write_buf.drain(..n);
+    }
+    try_ready!(io.poll_flush());
+    self.close_if_done();
+    Ok(Async::Ready(()))
+  }
+
+  /// Resolves to the next message, or to None after the close message.
+  pub fn poll_message(&mut self) -> Poll<Option<Message>, DenoError> {
+    let result = self.poll_next_message();
+    self.track_task(result)
+  }
+
+  fn poll_next_message(&mut self) -> Poll<Option<Message>, DenoError> {
+    loop {
+      // The pongs and the reply to a close are written while reading.
+      self.poll_write_buf()?;
+      if let Some(message) = self.messages.pop_front() {
+        return Ok(Async::Ready(Some(message)));
+      }
+      if self.received_close {
+        return Ok(Async::Ready(None));
+      }
+      try_ready!(self.poll_frame());
+    }
+  }
+
+  /// Resolves once the close frame of the server arrived in reply to the
+  /// one which was sent. The messages which came before are kept for the
+  /// receives.
+  pub fn poll_close(&mut self) -> Poll<(), DenoError> {
+    let result = self.poll_close_reply();
+    self.track_task(result)
+  }
+
+  fn poll_close_reply(&mut self) -> Poll<(), DenoError> {
+    try_ready!(self.poll_write_buf());
+    while !self.received_close {
+      try_ready!(self.poll_frame());
+    }
+    self.poll_write_buf()
+  }
+
+  /// Reads and handles a frame.
+  fn poll_frame(&mut self) -> Poll<(), DenoError> {
+    loop {
+      match parse_frame(&self.read_buf, false) {
+        Err(failure) => return Err(self.fail(failure)),
+        Ok(Some((frame, len))) => {
+          self.read_buf.drain(..len);
+          if let Err(failure) = self.on_frame(frame) {
+            return Err(self.fail(failure));
+          }
+          return Ok(Async::Ready(()));
+        }
+        Ok(None) => {}
+      }
+      let nread = match self.io {
+        None => return Err(closed_error()),
+        Some(ref mut io) => {
+          let mut buf = [0; READ_SIZE];
+          let nread = try_ready!(io.poll_read(&mut buf));
+          self.read_buf.extend_from_slice(&buf[..nread]);
+          nread
+        }
+      };
+      if nread == 0 {
+        self.io = None;
+        self.received_close = true;
+        let reason = "the connection ended without a close frame";
+        let message = Message::Close(Some(ABNORMAL_CLOSURE), reason.into());
+        self.messages.push_back(message);
+        return Ok(Async::Ready(()));
+      }
+    }
+  }
+
+  fn on_frame(&mut self, frame: Frame) -> Result<(), Failure> {
+    let Frame {
+      fin,
+      opcode,
+      payload,
+    } = frame;
+    match opcode {
+      PING => {
+        if !self.sent_close {
+          self.write_frame(PONG, &payload);
+        }
+        self.messages.push_back(Message::Ping(payload));
+      }
+      PONG => self.messages.push_back(Message::Pong(payload)),
+      CLOSE => {
+        let (code, reason) = parse_close_payload(payload)?;
+        if !self.sent_close {
+          self.write_frame(CLOSE, &close_payload(code, ""));
+        }
+        self.received_close = true;
+        self.messages.push_back(Message::Close(code, reason));
+      }
+      TEXT | BINARY if self.fragments.is_some() => {
+        return Err(Failure(PROTOCOL_ERROR, "expected a continuation frame"));
+      }
+      TEXT | BINARY if fin => self.add_message(opcode, payload)?,
+      TEXT | BINARY => self.fragments = Some((opcode, payload)),
+      CONTINUATION => match self.fragments.take() {
+        None => {
+          return Err(Failure(PROTOCOL_ERROR, "unexpected continuation frame"))
+        }
+        Some((opcode, mut data)) => {
+          if data.len() + payload.len() > MAX_MESSAGE_SIZE {
+            return Err(Failure(MESSAGE_TOO_BIG, "the message is too big"));
+          }
+          data.extend_from_slice(&payload);
+          if fin {
+            self.add_message(opcode, data)?;
+          } else {
+            self.fragments = Some((opcode, data));
+          }
+        }
+      },
+      _ => return Err(Failure(PROTOCOL_ERROR, "unknown opcode")),
+    }
+    Ok(())
+  }
+
+  fn add_message(&mut self, opcode: u8, data: Vec<u8>) -> Result<(), Failure> {
+    let message = if opcode == TEXT {
+      match String::from_utf8(data) {
+        Ok(text) => Message::Text(text),
+        Err(_) => {
+          return Err(Failure(INVALID_DATA, "a text message is not UTF-8"))
+        }
+      }
+    } else {
+      Message::Binary(data)
+    };
+    self.messages.push_back(message);
+    Ok(())
+  }
+
+  /// Sends a close frame with the code of `failure`, if possible, and ends
+  /// the connection.
+  fn fail(&mut self, failure: Failure) -> DenoError {
+    let Failure(code, reason) = failure;
+    if !self.sent_close {
+      self.write_frame(CLOSE, &close_payload(Some(code), reason));
+      let _ = self.poll_write_buf();
+    }
+    self.io = None;
+    self.received_close = true;
+    errors::new(
+      ErrorKind::InvalidData,
+      format!("WebSocket protocol error: {}", reason),
+    )
+  }
+
+  /// The socket is closed after the closing handshake.
+  fn close_if_done(&mut self) {
+    if self.sent_close && self.received_close && self.write_buf.is_empty() {
+      self.io = None;
+    }
+  }
+
+  fn track_task<T>(
+    &mut self,
+    result: Poll<T, DenoError>,
+  ) -> Poll<T, DenoError> {
+    match result {
+      Ok(Async::NotReady) => {
+        if !self.tasks.iter().any(|task| task.will_notify_current()) {
+          self.tasks.push(futures::task::current());
+        }
+      }
+      _ => {
+        for task in self.tasks.drain(..) {
+          task.notify();
+        }
+      }
+    }
+    result
+  }
+}
+
+impl Drop for WebSocket {
+  fn drop(&mut self) {
+    for task in self.tasks.drain(..) {
+      task.notify();
+    }
+  }
+}
+
+/// The value of the accept header of the response to the handshake with
+/// `key`.
+pub fn accept_key(key: &str) -> String {
+  let hash =
+    digest::digest(&digest::SHA1, format!("{}{}", key, GUID).as_bytes());
+  base64::encode(hash.as_ref())
+}
+
+fn handshake_error(reason: String) -> DenoError {
+  errors::new(
+    ErrorKind::InvalidData,
+    format!("WebSocket handshake failed: {}", reason),
+  )
+}
+
+/// Opens a WebSocket to the "ws:" or "wss:" `url`. Resolves to it and the
+/// protocol the server chose of `protocols`, if any.
+pub fn dial(
+  url: &Url,
+  protocols: &[String],
+) -> DenoResult<
+  impl Future<Item = (WebSocket, Option<String>), Error = DenoError>,
+> {
+  let mut http_url = url.clone();
+  let scheme = match url.scheme() {
+    "ws" => "http",
+    "wss" => "https",
+    _ => {
+      return Err(errors::new(
+        ErrorKind::InvalidInput,
+        format!("{} is not a WebSocket URL", url),
+      ))
+    }
+  };
+  http_url.set_scheme(scheme).unwrap();
+  http_url.set_fragment(None);
+
+  let key = base64::encode(&rand::random::<[u8; 16]>());
+  let mut request = Request::get(http_url.as_str());
+  request
+    .header(UPGRADE, "websocket")
+    .header(CONNECTION, "Upgrade")
+    .header(SEC_WEBSOCKET_KEY, key.as_str())
+    .header(SEC_WEBSOCKET_VERSION, "13");
+  if !protocols.is_empty() {
+    request.header(SEC_WEBSOCKET_PROTOCOL, protocols.join(", ").as_str());
+  }
+  let request = request
+    .body(Body::empty())
+    .map_err(|e| errors::new(ErrorKind::InvalidInput, e.to_string()))?;
+
+  let protocols = protocols.to_vec();
+  let future = http_util::get_client()
+    .request(request)
+    .map_err(DenoError::from)
+    .and_then(move |response| {
+      if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+        return Err(handshake_error(format!(
+          "the server responded with {}",
+          response.status()
+        )));
+      }
+      let header = |name: HeaderName| {
+        response
+          .headers()
+          .get(name)
+          .and_then(|value| value.to_str().ok())
+          .map(String::from)
+      };
+      if header(SEC_WEBSOCKET_ACCEPT) != Some(accept_key(&key)) {
+        return Err(handshake_error("invalid Sec-WebSocket-Accept".into()));
+      }
+      let protocol = header(SEC_WEBSOCKET_PROTOCOL);
+      if let Some(ref protocol) = protocol {
+        if !protocols.contains(protocol) {
+          return Err(handshake_error(format!(
+            "the server chose the protocol \"{}\", which was not offered",
+            protocol
+          )));
+        }
+      }
+      Ok((response, protocol))
+    })
+    .and_then(|(response, protocol)| {
+      response
+        .into_body()
+        .on_upgrade()
+        .map_err(DenoError::from)
+        .map(move |io| (WebSocket::new(Some(io)), protocol))
+    });
+  Ok(future)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn frames(bytes: &[u8]) -> Vec<Frame> {
+    let mut frames = Vec::new();
+    let mut buf = bytes;
+    while let Some((frame, len)) = parse_frame(buf, false).unwrap() {
+      frames.push(frame);
+      buf = &buf[len..];
+    }
+    assert!(buf.is_empty());
+    frames
+  }
+
+  #[test]
+  fn accept_key_of_rfc() {
+    assert_eq!(
+      accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+      "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+  }
+
+  #[test]
+  fn encode_masked_frame() {
+    // The example of RFC 6455, 5.7.
+    let frame = encode_frame(TEXT, b"Hello", Some([0x37, 0xfa, 0x21, 0x3d]));
+    assert_eq!(
+      frame,
+      vec![0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58]
+    );
+    let frame = encode_frame(BINARY, &[0; 256], Some([0; 4]));
+    assert_eq!(&frame[..4], &[0x82, 0xfe, 0x01, 0x00]);
+    assert_eq!(frame.len(), 4 + 4 + 256);
+  }
+
+  #[test]
+  fn encode_server_frame() {
+    // The examples of RFC 6455, 5.7.
+    let frame = encode_frame(TEXT, b"Hello", None);
+    assert_eq!(frame, vec![0x81, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f]);
+    let frame = encode_frame(BINARY, &[0; 256], None);
+    assert_eq!(&frame[..4], &[0x82, 0x7e, 0x01, 0x00]);
+    assert_eq!(frame.len(), 4 + 256);
+  }
+
+  #[test]
+  fn parse_client_frames() {
+    let hello = [0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51];
+    assert_eq!(parse_frame(&hello, true), Ok(None));
+    let mut hello = hello.to_vec();
+    hello.push(0x58);
+    let (frame, len) = parse_frame(&hello, true).unwrap().unwrap();
+    assert_eq!(len, hello.len());
+    assert_eq!(frame.payload, b"Hello");
+    for len in &[0, 125, 126, 0xffff, 0x10000] {
+      let payload = vec![b'x'; *len];
+      let bytes = encode_frame(TEXT, &payload, Some(rand::random()));
+      let (frame, _) = parse_frame(&bytes, true).unwrap().unwrap();
+      assert_eq!(frame.payload, payload);
+    }
+    let unmasked = [0x81, 0x00];
+    assert_eq!(parse_frame(&unmasked, true).unwrap_err().0, PROTOCOL_ERROR);
+  }
+
+  #[test]
+  fn parse_frames() {
+    assert_eq!(parse_frame(&[0x81]), Ok(None));
+    assert_eq!(parse_frame(&[0x81, 0x05, 0x48]), Ok(None));
+    let bytes = [0x01, 0x03, 0x48, 0x65, 0x6c, 0x80, 0x02, 0x6c, 0x6f];
+    assert_eq!(
+      frames(&bytes),
+      vec![
+        Frame {
+          fin: false,
+          opcode: TEXT,
+          payload: b"Hel".to_vec(),
+        },
+        Frame {
+          fin: true,
+          opcode: CONTINUATION,
+          payload: b"lo".to_vec(),
+        },
+      ]
+    );
+    let mut bytes = vec![0x82, 0x7e, 0x01, 0x00];
+    bytes.extend_from_slice(&[7; 256]);
+    assert_eq!(frames(&bytes)[0].payload, vec![7; 256]);
+  }
+
+  #[test]
+  fn parse_invalid_frames() {
+    let masked = [0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f];
+    assert_eq!(parse_frame(&masked, false).unwrap_err().0, PROTOCOL_ERROR);
+    let fragmented_ping = [0x09, 0x00];
+    let failure = parse_frame(&fragmented_ping, false).unwrap_err();
+    assert_eq!(failure.0, PROTOCOL_ERROR);
+    let reserved = [0xc1, 0x00];
+    assert_eq!(parse_frame(&reserved, false).unwrap_err().0, PROTOCOL_ERROR);
+    let huge = [0x82, 0x7f, 0, 0, 0, 1, 0, 0, 0, 0];
+    assert_eq!(parse_frame(&huge, false).unwrap_err().0, MESSAGE_TOO_BIG);
+  }
+
+  #[test]
+  fn reassemble_and_reply() {
+    let mut ws = WebSocket::new(None);
+    let frame = |fin, opcode, payload: &[u8]| Frame {
+      fin,
+      opcode,
+      payload: payload.to_vec(),
+    };
+    ws.on_frame(frame(false, TEXT, b"Hel")).unwrap();
+    ws.on_frame(frame(true, PING, b"p")).unwrap();
+    ws.on_frame(frame(false, CONTINUATION, b"l")).unwrap();
+    ws.on_frame(frame(true, CONTINUATION, b"o")).unwrap();
+    ws.on_frame(frame(true, CLOSE, &[0x03, 0xe8, b'o', b'k']))
+      .unwrap();
+    assert_eq!(
+      ws.messages.drain(..).collect::<Vec<_>>(),
+      vec![
+        Message::Ping(b"p".to_vec()),
+        Message::Text("Hello".to_string()),
+        Message::Close(Some(1000), "ok".to_string()),
+      ]
+    );
+    // The pong, then the close reply.
+    assert_eq!(ws.write_buf[0], 0x80 | PONG);
+    assert!(ws.sent_close && ws.received_close);
+    assert_eq!(
+      ws.on_frame(frame(true, CONTINUATION, b"x")),
+      Err(Failure(PROTOCOL_ERROR, "unexpected continuation frame"))
+    );
+    assert_eq!(
+      ws.on_frame(frame(true, TEXT, &[0xff])),
+      Err(Failure(INVALID_DATA, "a text message is not UTF-8"))
+    );
+  }
+}
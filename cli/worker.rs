@@ -1,6 +1,10 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
 use crate::compiler::compile_async;
+use crate::compiler::CompileError;
 use crate::compiler::ModuleMetaData;
+use crate::diagnostics::exit_with_diagnostics;
+use crate::diagnostics::Diagnostic;
+use crate::errors;
 use crate::errors::DenoError;
 use crate::errors::RustOrJsError;
 use crate::js_errors;
@@ -10,11 +14,14 @@ use crate::state::ThreadSafeState;
 use crate::tokio_util;
 use deno;
 use deno::deno_mod;
+use deno::InspectorHost;
+use deno::IsolateHandle;
 use deno::JSError;
 use deno::StartupData;
 use futures::future::Either;
 use futures::Async;
 use futures::Future;
+use std::collections::HashSet;
 use std::sync::atomic::Ordering;
 
 /// Wraps deno::Isolate to provide source maps, ops for the CLI, and
@@ -52,36 +59,99 @@ impl Worker {
     self.inner.execute(js_filename, js_source)
   }
 
-  // TODO(ry) make this return a future.
-  fn mod_load_deps(&self, id: deno_mod) -> Result<(), RustOrJsError> {
-    // basically iterate over the imports, start loading them.
+  /// Interrupts the JavaScript of the worker from another thread.
+  pub fn isolate_handle(&mut self) -> IsolateHandle {
+    self.inner.shared_isolate_handle()
+  }
 
-    let referrer_name = {
-      let g = self.state.modules.lock().unwrap();
-      g.get_name(id).unwrap().clone()
-    };
+  /// Lets a DevTools client debug the worker, see `--inspect`.
+  pub fn set_inspector(&mut self, host: Box<dyn InspectorHost>) {
+    self.inner.set_inspector(host)
+  }
 
-    for specifier in self.inner.mod_get_imports(id) {
-      let (name, _local_filename) = self
-        .state
-        .dir
-        .resolve_module(&specifier, &referrer_name)
-        .map_err(DenoError::from)
-        .map_err(RustOrJsError::from)?;
+  /// Blocks until the DevTools client is ready and pauses on the next
+  /// statement, see `--inspect-brk`.
+  pub fn wait_for_debugger(&mut self) {
+    self.inner.inspector_wait_for_debugger()
+  }
+
+  /// Handles what the DevTools client sent so far, see `--coverage`.
+  pub fn poll_inspector(&mut self) {
+    self.inner.inspector_poll()
+  }
 
-      debug!("mod_load_deps {}", name);
+  /// Loads the module graph below `id` one level at a time. The new imports
+  /// of a level are fetched and compiled at the same time, as none of them
+  /// needs the output of another one.
+  // TODO(ry) make this return a future.
+  fn mod_load_deps(&self, id: deno_mod) -> Result<(), RustOrJsError> {
+    let mut parents = vec![id];
+    while !parents.is_empty() {
+      // The specifiers and referrers of the imports which are not loaded yet,
+      // each module only once.
+      let mut imports = Vec::new();
+      let mut names = HashSet::new();
+      for parent in parents.drain(..) {
+        let referrer_name = {
+          let g = self.state.modules.lock().unwrap();
+          g.get_name(parent).unwrap().clone()
+        };
+        for specifier in self.inner.mod_get_imports(parent) {
+          let (name, _local_filename) = self
+            .state
+            .dir
+            .resolve_module(&specifier, &referrer_name)
+            .map_err(DenoError::from)
+            .map_err(RustOrJsError::from)?;
+
+          debug!("mod_load_deps {}", name);
+
+          let is_registered =
+            self.state.modules.lock().unwrap().is_registered(&name);
+          if !is_registered && names.insert(name) {
+            imports.push((specifier, referrer_name.clone()));
+          }
+        }
+      }
 
-      if !self.state.modules.lock().unwrap().is_registered(&name) {
-        let out = fetch_module_meta_data_and_maybe_compile(
+      let loads = imports.iter().map(|(specifier, referrer)| {
+        fetch_module_meta_data_and_maybe_compile_async(
           &self.state,
-          &specifier,
-          &referrer_name,
-        )?;
-        let child_id = self.mod_new_and_register(
-          false,
-          &out.module_name.clone(),
-          &out.js_source(),
-        )?;
+          specifier,
+          referrer,
+        ).then(Ok::<_, ()>)
+      });
+      let results =
+        tokio_util::block_on(futures::future::join_all(loads)).unwrap();
+
+      // Errors are reported in the order of the imports, whichever load
+      // finished first.
+      let mut outs = Vec::new();
+      let mut diagnostics = Vec::new();
+      for result in results {
+        match result {
+          Ok(out) => outs.push(out),
+          Err(LoadError::Diagnostics(d)) => diagnostics.extend(d),
+          Err(LoadError::Deno(err)) => {
+            if diagnostics.is_empty() {
+              return Err(RustOrJsError::from(err));
+            }
+            break;
+          }
+        }
+      }
+      if !diagnostics.is_empty() {
+        exit_with_diagnostics(&diagnostics, self.state.flags.diagnostics_json);
+      }
+
+      for out in outs {
+        // Two imports can redirect to the same module.
+        let is_registered =
+          self.state.modules.lock().unwrap().is_registered(&out.module_name);
+        if !is_registered {
+          self.print_prefetched(&out.module_name);
+          parents.push(self.mod_new_and_register(false, &out)?);
+        }
 
         // The resolved module is an alias to another module (due to redirects).
         // Save such alias to the module map.
@@ -91,8 +161,6 @@ impl Worker {
             &out.module_name,
           );
         }
-
-        self.mod_load_deps(child_id)?;
       }
     }
 
@@ -124,13 +192,14 @@ impl Worker {
   ) -> Result<(), RustOrJsError> {
     let out = fetch_module_meta_data_and_maybe_compile(&self.state, url, ".")
       .map_err(RustOrJsError::from)?;
+    self.print_prefetched(&out.module_name);
 
     // Be careful.
     // url might not match the actual out.module_name
     // due to the mechanism of redirection.
 
     let id = self
-      .mod_new_and_register(true, &out.module_name.clone(), &out.js_source())
+      .mod_new_and_register(true, &out)
       .map_err(RustOrJsError::from)?;
 
     // The resolved module is an alias to another module (due to redirects).
@@ -162,15 +231,23 @@ impl Worker {
     Ok(())
   }
 
+  /// `deno prefetch` lists every module of the graph as it is loaded.
+  fn print_prefetched(&self, module_name: &str) {
+    if self.state.flags.prefetch {
+      println!("{}", module_name);
+    }
+  }
+
   /// Wraps Isolate::mod_new but registers with modules.
   fn mod_new_and_register(
     &self,
     main: bool,
-    name: &str,
-    source: &str,
+    out: &ModuleMetaData,
   ) -> Result<deno_mod, JSError> {
-    let id = self.inner.mod_new(main, name, source)?;
-    self.state.modules.lock().unwrap().register(id, &name);
+    let id = self.inner.mod_new(main, &out.module_name, &out.js_source())?;
+    let mut modules = self.state.modules.lock().unwrap();
+    modules.register(id, &out.module_name);
+    modules.set_size(id, out.source_code.len());
     Ok(id)
   }
 
@@ -183,12 +260,24 @@ impl Worker {
 
   pub fn print_file_info(&self, module: &str) {
     let m = self.state.modules.lock().unwrap();
-    m.print_file_info(&self.state.dir, module.to_string());
+    if self.state.flags.info_json {
+      m.print_file_info_json(&self.state.dir, module.to_string());
+    } else {
+      m.print_file_info(
+        &self.state.dir,
+        module.to_string(),
+        self.state.flags.info_depth,
+      );
+    }
   }
 
   /// Applies source map to the error.
   fn apply_source_map(&self, err: JSError) -> JSError {
-    js_errors::apply_source_map(&err, &self.state.dir)
+    js_errors::apply_source_map(
+      &err,
+      &self.state.dir,
+      self.state.flags.full_stack_traces,
+    )
   }
 }
 
@@ -197,7 +286,51 @@ impl Future for Worker {
   type Error = JSError;
 
   fn poll(&mut self) -> Result<Async<()>, Self::Error> {
-    self.inner.poll().map_err(|err| self.apply_source_map(err))
+    match self.inner.poll() {
+      Ok(Async::Ready(())) => {}
+      Ok(Async::NotReady) => return Ok(Async::NotReady),
+      Err(err) => return Err(self.apply_source_map(err)),
+    }
+    // The event loop has drained, so a rejection which has no handler by now
+    // never gets one.
+    let mut rejections: Vec<JSError> = self
+      .inner
+      .take_unhandled_rejections()
+      .into_iter()
+      .map(|err| self.apply_source_map(err))
+      .collect();
+    if self.state.flags.unhandled_rejections_warn {
+      for err in &rejections {
+        eprintln!(
+          "Warning: unhandled promise rejection\n{}",
+          JSErrorColor(err)
+        );
+      }
+      return Ok(Async::Ready(()));
+    }
+    // All but the last are printed here, the last is the error of the worker.
+    match rejections.pop() {
+      None => Ok(Async::Ready(())),
+      Some(last) => {
+        for err in &rejections {
+          eprintln!("{}", JSErrorColor(err));
+        }
+        Err(last)
+      }
+    }
+  }
+}
+
+/// What keeps a module from loading.
+enum LoadError {
+  /// The TypeScript diagnostics of the module.
+  Diagnostics(Vec<Diagnostic>),
+  Deno(DenoError),
+}
+
+impl From<DenoError> for LoadError {
+  fn from(err: DenoError) -> Self {
+    LoadError::Deno(err)
   }
 }
 
@@ -205,28 +338,36 @@ fn fetch_module_meta_data_and_maybe_compile_async(
   state: &ThreadSafeState,
   specifier: &str,
   referrer: &str,
-) -> impl Future<Item = ModuleMetaData, Error = DenoError> {
-  let use_cache = !state.flags.reload;
+) -> impl Future<Item = ModuleMetaData, Error = LoadError> {
+  let use_cache = state.use_cache(specifier, referrer);
   let state_ = state.clone();
   let specifier = specifier.to_string();
   let referrer = referrer.to_string();
   state
     .dir
     .fetch_module_meta_data_async(&specifier, &referrer, use_cache)
+    .map_err(LoadError::from)
     .and_then(move |out| {
+      if let Err(err) = state_.check_lock(&out) {
+        return Either::B(futures::future::err(LoadError::from(err)));
+      }
       if out.media_type == msg::MediaType::TypeScript
         && !out.has_output_code_and_source_map()
       {
         debug!(">>>>> compile_sync START");
         Either::A(
           compile_async(state_.clone(), &specifier, &referrer, &out)
-            .map_err(|e| {
-              debug!("compiler error exiting!");
-              eprintln!("{}", JSErrorColor(&e).to_string());
-              std::process::exit(1);
+            .map_err(|err| match err {
+              CompileError::Diagnostics(d) => LoadError::Diagnostics(d),
+              // A failure to load one of the imports.
+              CompileError::Js(e) => {
+                debug!("compiler error exiting!");
+                eprintln!("{}", JSErrorColor(&e).to_string());
+                std::process::exit(errors::EXIT_MODULE_ERROR);
+              }
             }).and_then(move |out| {
               debug!(">>>>> compile_sync END");
-              state_.dir.code_cache(&out)?;
+              state_.dir.code_cache(&out).map_err(DenoError::from)?;
               Ok(out)
             }),
         )
@@ -236,14 +377,19 @@ fn fetch_module_meta_data_and_maybe_compile_async(
     })
 }
 
-fn fetch_module_meta_data_and_maybe_compile(
+pub fn fetch_module_meta_data_and_maybe_compile(
   state: &ThreadSafeState,
   specifier: &str,
   referrer: &str,
 ) -> Result<ModuleMetaData, DenoError> {
   tokio_util::block_on(fetch_module_meta_data_and_maybe_compile_async(
     state, specifier, referrer,
-  ))
+  )).map_err(|err| match err {
+    LoadError::Diagnostics(d) => {
+      exit_with_diagnostics(&d, state.flags.diagnostics_json)
+    }
+    LoadError::Deno(err) => err,
+  })
 }
 
 #[cfg(test)]
@@ -309,8 +455,8 @@ mod tests {
 
   fn create_test_worker() -> Worker {
     let state = ThreadSafeState::mock();
-    let mut worker =
-      Worker::new("TEST".to_string(), startup_data::deno_isolate_init(), state);
+    let startup_data = startup_data::deno_isolate_init(true);
+    let mut worker = Worker::new("TEST".to_string(), startup_data, state);
     js_check(worker.execute("denoMain()"));
     js_check(worker.execute("workerMain()"));
     worker
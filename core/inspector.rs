@@ -0,0 +1,139 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+
+// Connects the V8 inspector of an Isolate to a DevTools client. libdeno only
+// speaks the DevTools protocol, the transport (e.g. a WebSocket server) is
+// provided by the embedder as an InspectorHost.
+
+use crate::libdeno;
+use futures::Async;
+use libc::c_char;
+use libc::c_void;
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::ffi::NulError;
+use std::ptr::null;
+
+/// Something a DevTools client did.
+#[derive(Debug, PartialEq)]
+pub enum InspectorEvent {
+  Connected,
+  /// A DevTools protocol message.
+  Message(String),
+  Disconnected,
+}
+
+/// The transport between the inspector and a single DevTools client.
+pub trait InspectorHost: Send {
+  /// Sends a DevTools protocol message to the client.
+  fn send(&mut self, message: &str);
+
+  /// Returns the next event of the client if there is one. Like a Stream it
+  /// must arrange for the current task to be notified when it returns
+  /// NotReady, so that an idle Isolate wakes up for the client.
+  fn poll_event(&mut self) -> Async<InspectorEvent>;
+
+  /// Blocks until the next event of the client. Used while JavaScript is
+  /// paused, when the event loop cannot run.
+  fn wait_event(&mut self) -> InspectorEvent;
+}
+
+pub(crate) struct Inspector {
+  libdeno_isolate: *const libdeno::isolate,
+  host: Box<dyn InspectorHost>,
+}
+
+impl Inspector {
+  pub fn new(
+    libdeno_isolate: *const libdeno::isolate,
+    host: Box<dyn InspectorHost>,
+  ) -> Box<Self> {
+    // Boxed so that the pointer given to libdeno stays valid.
+    let inspector = Box::new(Self {
+      libdeno_isolate,
+      host,
+    });
+    unsafe {
+      libdeno::deno_inspector_new(
+        libdeno_isolate,
+        inspector.as_raw_ptr(),
+        Self::message_cb,
+        Self::wait_cb,
+      )
+    };
+    inspector
+  }
+
+  /// Handles the events the client sent so far. `user_data` is passed to ops
+  /// the client causes, e.g. by evaluating code in the console.
+  pub fn poll(&mut self, user_data: *const c_void) {
+    while let Async::Ready(event) = self.host.poll_event() {
+      self.handle(event, user_data);
+    }
+  }
+
+  pub fn wait_for_debugger(&self, user_data: *const c_void) {
+    unsafe {
+      libdeno::deno_inspector_wait_for_debugger(self.libdeno_isolate, user_data)
+    }
+  }
+
+  fn handle(&self, event: InspectorEvent, user_data: *const c_void) {
+    match event {
+      InspectorEvent::Connected => unsafe {
+        libdeno::deno_inspector_connect(self.libdeno_isolate)
+      },
+      InspectorEvent::Message(message) => {
+        // A message the client can send is never a reason to stop the
+        // isolate, it is dropped instead.
+        if let Err(err) = self.dispatch(message, user_data) {
+          warn!("Dropping a message of the DevTools client: {}", err);
+        }
+      }
+      InspectorEvent::Disconnected => unsafe {
+        libdeno::deno_inspector_disconnect(self.libdeno_isolate)
+      },
+    }
+  }
+
+  /// Protocol messages are JSON, which escapes NUL. One holding a NUL byte
+  /// can't be passed on to V8 as a C string, and is an error.
+  fn dispatch(
+    &self,
+    message: String,
+    user_data: *const c_void,
+  ) -> Result<(), NulError> {
+    let message = CString::new(message)?;
+    unsafe {
+      libdeno::deno_inspector_dispatch(
+        self.libdeno_isolate,
+        user_data,
+        message.as_ptr(),
+      )
+    };
+    Ok(())
+  }
+
+  #[inline]
+  unsafe fn from_raw_ptr<'a>(ptr: *const c_void) -> &'a mut Self {
+    let ptr = ptr as *mut _;
+    &mut *ptr
+  }
+
+  #[inline]
+  fn as_raw_ptr(&self) -> *const c_void {
+    self as *const _ as *const c_void
+  }
+
+  extern "C" fn message_cb(user_data: *mut c_void, message: *const c_char) {
+    let inspector = unsafe { Inspector::from_raw_ptr(user_data) };
+    let message = unsafe { CStr::from_ptr(message) };
+    inspector.host.send(&message.to_string_lossy());
+  }
+
+  extern "C" fn wait_cb(user_data: *mut c_void) {
+    let inspector = unsafe { Inspector::from_raw_ptr(user_data) };
+    let event = inspector.host.wait_event();
+    // Only called while libdeno waits inside a call that set user_data.
+    inspector.handle(event, null());
+  }
+}
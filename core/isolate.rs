@@ -4,6 +4,8 @@
 // isolate to keep the Isolate struct from becoming too bloating for users who
 // do not need asynchronous module loading.
 
+use crate::inspector::Inspector;
+use crate::inspector::InspectorHost;
 use crate::js_errors::JSError;
 use crate::libdeno;
 use crate::libdeno::deno_buf;
@@ -16,6 +18,7 @@ use futures::Async;
 use futures::Future;
 use futures::Poll;
 use libc::c_void;
+use serde_json;
 use std::collections::VecDeque;
 use std::ffi::CStr;
 use std::ffi::CString;
@@ -93,6 +96,7 @@ pub struct Isolate<B: Dispatch> {
   shared: SharedQueue,
   pending_ops: VecDeque<PendingOp>,
   polled_recently: bool,
+  inspector: Option<Box<Inspector>>,
 }
 
 unsafe impl<B: Dispatch> Send for Isolate<B> {}
@@ -144,6 +148,7 @@ impl<B: Dispatch> Isolate<B> {
       needs_init,
       pending_ops: VecDeque::new(),
       polled_recently: false,
+      inspector: None,
     };
 
     // If we want to use execute this has to happen here sadly.
@@ -161,6 +166,34 @@ impl<B: Dispatch> Isolate<B> {
     }
   }
 
+  /// Enables debugging with the DevTools client behind `host`. Its messages
+  /// are handled whenever the isolate is polled, and while JavaScript is
+  /// paused in the debugger.
+  pub fn set_inspector(&mut self, host: Box<dyn InspectorHost>) {
+    assert!(self.inspector.is_none());
+    self.inspector = Some(Inspector::new(self.libdeno_isolate, host));
+  }
+
+  /// Blocks until the DevTools client is ready, then pauses on the next
+  /// statement that executes. Does nothing without set_inspector().
+  pub fn inspector_wait_for_debugger(&mut self) {
+    self.shared_init();
+    if let Some(inspector) = self.inspector.as_ref() {
+      inspector.wait_for_debugger(self.as_raw_ptr());
+    }
+  }
+
+  /// Handles what the DevTools client sent so far, without running the event
+  /// loop, e.g. before any JavaScript was executed. Does nothing without
+  /// set_inspector().
+  pub fn inspector_poll(&mut self) {
+    let _locker = LockerScope::new(self.libdeno_isolate);
+    let user_data = self.as_raw_ptr();
+    if let Some(inspector) = self.inspector.as_mut() {
+      inspector.poll(user_data);
+    }
+  }
+
   /// Executes a bit of built-in JavaScript to provide Deno.sharedQueue.
   pub fn shared_init(&mut self) {
     if self.needs_init {
@@ -270,10 +303,33 @@ impl<B: Dispatch> Isolate<B> {
     }
   }
 
-  fn check_promise_errors(&self) {
-    unsafe {
-      libdeno::deno_check_promise_errors(self.libdeno_isolate);
+  /// Returns the promises which were rejected and still have no handler, and
+  /// forgets them. `poll()` does not fail on these, as a handler may still be
+  /// attached in a later tick; check them once the isolate is idle.
+  pub fn take_unhandled_rejections(&mut self) -> Vec<JSError> {
+    let ptr =
+      unsafe { libdeno::deno_take_promise_errors(self.libdeno_isolate) };
+    if ptr.is_null() {
+      return Vec::new();
+    }
+    let cstr = unsafe { CStr::from_ptr(ptr) };
+    let json_str = cstr.to_str().unwrap();
+    debug!("promise errors\n{}\n", json_str);
+    let v: serde_json::Value = serde_json::from_str(json_str).unwrap();
+    let values = match v {
+      serde_json::Value::Array(values) => values,
+      _ => unreachable!(),
+    };
+    let mut rejections = Vec::new();
+    for value in values {
+      let mut err = JSError::from_json_value(value).unwrap();
+      if err.message.starts_with("Uncaught ") {
+        err.message =
+          err.message.replacen("Uncaught", "Uncaught (in promise)", 1);
+      }
+      rejections.push(err);
     }
+    rejections
   }
 
   fn respond(&mut self, maybe_buf: Option<&[u8]>) -> Result<(), JSError> {
@@ -438,6 +494,11 @@ impl<B: Dispatch> Future for Isolate<B> {
     // Lock the current thread for V8.
     let _locker = LockerScope::new(self.libdeno_isolate);
 
+    let user_data = self.as_raw_ptr();
+    if let Some(inspector) = self.inspector.as_mut() {
+      inspector.poll(user_data);
+    }
+
     // Clear poll_recently state both on the Isolate itself and
     // on the pending ops.
     self.polled_recently = false;
@@ -492,7 +553,6 @@ impl<B: Dispatch> Future for Isolate<B> {
       }
     }
 
-    self.check_promise_errors();
     if let Some(err) = self.last_exception() {
       return Err(err);
     }
@@ -536,6 +596,7 @@ pub fn js_check(r: Result<(), JSError>) {
 #[cfg(test)]
 pub mod tests {
   use super::*;
+  use crate::inspector::InspectorEvent;
   use std::sync::atomic::{AtomicUsize, Ordering};
 
   pub enum TestDispatchMode {
@@ -727,6 +788,31 @@ pub mod tests {
     assert_eq!(Ok(Async::Ready(())), isolate.poll());
   }
 
+  #[test]
+  fn test_unhandled_rejections() {
+    let mut isolate = TestDispatch::setup(TestDispatchMode::AsyncImmediate);
+    js_check(isolate.execute(
+      "reject.js",
+      r#"
+        Deno.core.setAsyncHandler((buf) => {
+          // Handled in a later tick, so it must not be reported.
+          handledLater.catch(() => {});
+        });
+        Promise.reject(new Error("unhandled"));
+        const handledLater = Promise.reject(new Error("handled later"));
+        Deno.core.send(new Uint8Array([42]));
+        "#,
+    ));
+    assert_eq!(Ok(Async::Ready(())), isolate.poll());
+    let rejections = isolate.take_unhandled_rejections();
+    assert_eq!(rejections.len(), 1);
+    assert_eq!(
+      rejections[0].message,
+      "Uncaught (in promise) Error: unhandled"
+    );
+    assert!(isolate.take_unhandled_rejections().is_empty());
+  }
+
   #[test]
   fn test_shared() {
     let mut isolate = TestDispatch::setup(TestDispatchMode::AsyncImmediate);
@@ -964,4 +1050,48 @@ pub mod tests {
     );
     assert_eq!(Ok(Async::Ready(())), isolate.poll());
   }
+
+  /// Plays back `events` and records what the inspector sends.
+  struct TestInspectorHost {
+    events: Vec<InspectorEvent>,
+    sent: Arc<Mutex<Vec<String>>>,
+  }
+
+  impl InspectorHost for TestInspectorHost {
+    fn send(&mut self, message: &str) {
+      self.sent.lock().unwrap().push(message.to_string());
+    }
+
+    fn poll_event(&mut self) -> Async<InspectorEvent> {
+      if self.events.is_empty() {
+        Async::NotReady
+      } else {
+        Async::Ready(self.events.remove(0))
+      }
+    }
+
+    fn wait_event(&mut self) -> InspectorEvent {
+      self.events.remove(0)
+    }
+  }
+
+  #[test]
+  fn test_inspector_drops_nul_messages() {
+    let mut isolate = TestDispatch::setup(TestDispatchMode::AsyncImmediate);
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    isolate.set_inspector(Box::new(TestInspectorHost {
+      events: vec![
+        InspectorEvent::Connected,
+        InspectorEvent::Message("{\"id\":1,\0}".to_string()),
+        InspectorEvent::Message(
+          "{\"id\":2,\"method\":\"Runtime.enable\"}".to_string(),
+        ),
+      ],
+      sent: sent.clone(),
+    }));
+    isolate.inspector_poll();
+    let sent = sent.lock().unwrap();
+    assert!(sent.iter().all(|message| !message.contains("\"id\":1")));
+    assert!(sent.iter().any(|message| message.contains("\"id\":2")));
+  }
 }
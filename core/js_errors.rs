@@ -3,12 +3,6 @@
 // Note that source_map_mappings requires 0-indexed line and column numbers but
 // V8 Exceptions are 1-indexed.
 
-// TODO: This currently only applies to uncaught exceptions. It would be nice to
-// also have source maps for situations like this:
-//   const err = new Error("Boo!");
-//   console.log(err.stack);
-// It would require calling into Rust from Error.prototype.prepareStackTrace.
-
 use serde_json;
 use std::fmt;
 use std::str;
@@ -36,6 +30,9 @@ pub struct JSError {
   pub error_level: Option<i64>,
   pub start_column: Option<i64>,
   pub end_column: Option<i64>,
+  /// The integer `kind` property of the Error, if it has one. Lets embedders
+  /// which throw errors with kinds tell them apart.
+  pub kind: Option<i64>,
 
   pub frames: Vec<StackFrame>,
 }
@@ -220,6 +217,7 @@ impl JSError {
     let error_level = obj.get("errorLevel").and_then(|v| v.as_i64());
     let start_column = obj.get("startColumn").and_then(|v| v.as_i64());
     let end_column = obj.get("endColumn").and_then(|v| v.as_i64());
+    let kind = obj.get("kind").and_then(|v| v.as_i64());
 
     let frames_v = &obj["frames"];
     if !frames_v.is_array() {
@@ -245,6 +243,7 @@ impl JSError {
       error_level,
       start_column,
       end_column,
+      kind,
       frames,
     })
   }
@@ -265,6 +264,7 @@ mod tests {
       error_level: None,
       start_column: None,
       end_column: None,
+      kind: None,
       frames: vec![
         StackFrame {
           line: 4,
@@ -401,9 +401,18 @@ mod tests {
     assert_eq!(e.error_level, Some(8));
     assert_eq!(e.start_column, Some(6));
     assert_eq!(e.end_column, Some(7));
+    assert_eq!(e.kind, None);
     assert_eq!(e.frames.len(), 1);
   }
 
+  #[test]
+  fn js_error_from_v8_exception_with_kind() {
+    let r = JSError::from_v8_exception(
+      r#"{"message":"Uncaught PermissionDenied: no","kind":3,"frames":[]}"#,
+    );
+    assert_eq!(r.unwrap().kind, Some(3));
+  }
+
   #[test]
   fn stack_frame_to_string() {
     let e = error1();
@@ -5,6 +5,7 @@ extern crate futures;
 extern crate libc;
 
 mod flags;
+mod inspector;
 mod isolate;
 mod js_errors;
 mod libdeno;
@@ -12,6 +13,8 @@ mod modules;
 mod shared_queue;
 
 pub use crate::flags::v8_set_flags;
+pub use crate::inspector::InspectorEvent;
+pub use crate::inspector::InspectorHost;
 pub use crate::isolate::*;
 pub use crate::js_errors::*;
 pub use crate::libdeno::deno_buf;
@@ -25,7 +28,25 @@ pub fn v8_version() -> &'static str {
   c_str.to_str().unwrap()
 }
 
+/// The stack of the JavaScript running on this thread, e.g. the calls which
+/// dispatched the op being handled, as a JSError with only frames. Frames are
+/// unmapped, None outside of JavaScript.
+pub fn current_stack_trace() -> Option<JSError> {
+  use std::ffi::CStr;
+  let ptr = unsafe { libdeno::deno_current_stack_trace() };
+  if ptr.is_null() {
+    return None;
+  }
+  let json = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap();
+  JSError::from_v8_exception(json)
+}
+
 #[test]
 fn test_v8_version() {
   assert!(v8_version().len() > 3);
 }
+
+#[test]
+fn test_current_stack_trace() {
+  assert!(current_stack_trace().is_none());
+}
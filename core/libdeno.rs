@@ -176,6 +176,13 @@ type deno_resolve_cb = unsafe extern "C" fn(
   referrer: deno_mod,
 ) -> deno_mod;
 
+#[allow(non_camel_case_types)]
+pub type deno_inspector_message_cb =
+  unsafe extern "C" fn(user_data: *mut c_void, message: *const c_char);
+
+#[allow(non_camel_case_types)]
+pub type deno_inspector_wait_cb = unsafe extern "C" fn(user_data: *mut c_void);
+
 #[repr(C)]
 pub struct deno_config<'a> {
   pub will_snapshot: c_int,
@@ -220,6 +227,7 @@ extern "C" {
   pub fn deno_delete(i: *const isolate);
   pub fn deno_last_exception(i: *const isolate) -> *const c_char;
   pub fn deno_check_promise_errors(i: *const isolate);
+  pub fn deno_take_promise_errors(i: *const isolate) -> *const c_char;
   pub fn deno_lock(i: *const isolate);
   pub fn deno_unlock(i: *const isolate);
   pub fn deno_respond(
@@ -235,6 +243,7 @@ extern "C" {
     js_source: *const c_char,
   );
   pub fn deno_terminate_execution(i: *const isolate);
+  pub fn deno_current_stack_trace() -> *const c_char;
 
   // Modules
 
@@ -268,6 +277,26 @@ extern "C" {
 
   pub fn deno_snapshot_new(i: *const isolate) -> Snapshot1<'static>;
 
+  // Inspector
+
+  pub fn deno_inspector_new(
+    i: *const isolate,
+    user_data: *const c_void,
+    message_cb: deno_inspector_message_cb,
+    wait_cb: deno_inspector_wait_cb,
+  );
+  pub fn deno_inspector_connect(i: *const isolate);
+  pub fn deno_inspector_disconnect(i: *const isolate);
+  pub fn deno_inspector_dispatch(
+    i: *const isolate,
+    user_data: *const c_void,
+    message: *const c_char,
+  );
+  pub fn deno_inspector_wait_for_debugger(
+    i: *const isolate,
+    user_data: *const c_void,
+  );
+
   #[allow(dead_code)]
   pub fn deno_snapshot_delete(s: &mut deno_snapshot);
 }
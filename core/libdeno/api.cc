@@ -223,6 +223,32 @@ void deno_check_promise_errors(Deno* d_) {
   }
 }
 
+const char* deno_take_promise_errors(Deno* d_) {
+  auto* d = unwrap(d_);
+  if (d->pending_promise_map_.size() == 0) {
+    return nullptr;
+  }
+  auto* isolate = d->isolate_;
+  v8::Locker locker(isolate);
+  v8::Isolate::Scope isolate_scope(isolate);
+  v8::HandleScope handle_scope(isolate);
+  auto context = d->context_.Get(d->isolate_);
+  v8::Context::Scope context_scope(context);
+
+  std::string json_str = "[";
+  auto it = d->pending_promise_map_.begin();
+  while (it != d->pending_promise_map_.end()) {
+    if (json_str.length() > 1) {
+      json_str += ",";
+    }
+    json_str += deno::EncodeExceptionAsJSON(context, it->second.Get(isolate));
+    it = d->pending_promise_map_.erase(it);
+  }
+  json_str += "]";
+  d->promise_errors_ = json_str;
+  return d->promise_errors_.c_str();
+}
+
 void deno_delete(Deno* d_) {
   deno::DenoIsolate* d = reinterpret_cast<deno::DenoIsolate*>(d_);
   delete d;
@@ -232,4 +258,83 @@ void deno_terminate_execution(Deno* d_) {
   deno::DenoIsolate* d = reinterpret_cast<deno::DenoIsolate*>(d_);
   d->isolate_->TerminateExecution();
 }
+
+const char* deno_current_stack_trace() {
+  // The innermost frames, deeper ones are left out.
+  const int kMaxFrames = 64;
+  auto* isolate = v8::Isolate::GetCurrent();
+  if (isolate == nullptr || !isolate->InContext()) {
+    return nullptr;
+  }
+  auto* d = deno::DenoIsolate::FromIsolate(isolate);
+  v8::HandleScope handle_scope(isolate);
+  auto context = isolate->GetCurrentContext();
+  auto stack_trace = v8::StackTrace::CurrentStackTrace(isolate, kMaxFrames);
+  if (stack_trace->GetFrameCount() == 0) {
+    return nullptr;
+  }
+  d->current_stack_trace_ = deno::EncodeStackTraceAsJSON(context, stack_trace);
+  return d->current_stack_trace_.c_str();
+}
+
+void deno_inspector_new(Deno* d_, void* user_data,
+                        deno_inspector_message_cb message_cb,
+                        deno_inspector_wait_cb wait_cb) {
+  auto* d = unwrap(d_);
+  CHECK_NULL(d->inspector_);
+  auto* isolate = d->isolate_;
+  v8::Locker locker(isolate);
+  v8::Isolate::Scope isolate_scope(isolate);
+  v8::HandleScope handle_scope(isolate);
+  auto context = d->context_.Get(isolate);
+  v8::Context::Scope context_scope(context);
+  d->inspector_ =
+      new deno::Inspector(isolate, context, user_data, message_cb, wait_cb);
+}
+
+void deno_inspector_connect(Deno* d_) {
+  auto* d = unwrap(d_);
+  CHECK_NOT_NULL(d->inspector_);
+  v8::Locker locker(d->isolate_);
+  v8::Isolate::Scope isolate_scope(d->isolate_);
+  v8::HandleScope handle_scope(d->isolate_);
+  d->inspector_->Connect();
+}
+
+void deno_inspector_disconnect(Deno* d_) {
+  auto* d = unwrap(d_);
+  CHECK_NOT_NULL(d->inspector_);
+  v8::Locker locker(d->isolate_);
+  v8::Isolate::Scope isolate_scope(d->isolate_);
+  v8::HandleScope handle_scope(d->isolate_);
+  d->inspector_->Disconnect();
+}
+
+void deno_inspector_dispatch(Deno* d_, void* user_data, const char* message) {
+  auto* d = unwrap(d_);
+  CHECK_NOT_NULL(d->inspector_);
+  // While paused the isolate is in the middle of a call that set user_data.
+  deno::UserDataScope user_data_scope(
+      d, d->user_data_ != nullptr ? d->user_data_ : user_data);
+  auto* isolate = d->isolate_;
+  v8::Locker locker(isolate);
+  v8::Isolate::Scope isolate_scope(isolate);
+  v8::HandleScope handle_scope(isolate);
+  auto context = d->context_.Get(isolate);
+  v8::Context::Scope context_scope(context);
+  d->inspector_->Dispatch(message);
+}
+
+void deno_inspector_wait_for_debugger(Deno* d_, void* user_data) {
+  auto* d = unwrap(d_);
+  CHECK_NOT_NULL(d->inspector_);
+  deno::UserDataScope user_data_scope(d, user_data);
+  auto* isolate = d->isolate_;
+  v8::Locker locker(isolate);
+  v8::Isolate::Scope isolate_scope(isolate);
+  v8::HandleScope handle_scope(isolate);
+  auto context = d->context_.Get(isolate);
+  v8::Context::Scope context_scope(context);
+  d->inspector_->WaitForDebugger();
+}
 }
@@ -90,10 +90,22 @@ void deno_zero_copy_release(Deno* d, size_t zero_copy_id);
 
 void deno_check_promise_errors(Deno* d);
 
+// Returns the exceptions of the promises which were rejected and still have no
+// handler, as a JSON array of the objects deno_last_exception() returns, and
+// forgets them. Returns NULL if there are none. The string is valid until the
+// next call.
+const char* deno_take_promise_errors(Deno* d);
+
 const char* deno_last_exception(Deno* d);
 
 void deno_terminate_execution(Deno* d);
 
+// Returns the stack of the JavaScript running on this thread, e.g. while
+// deno_recv_cb is called, as JSON in the shape of deno_last_exception() with
+// an empty message. Returns NULL if no JavaScript is running. The string is
+// valid until the next call.
+const char* deno_current_stack_trace();
+
 // Module API
 
 typedef int deno_mod;
@@ -116,6 +128,39 @@ void deno_mod_instantiate(Deno* d, void* user_data, deno_mod id,
 // If it succeeded deno_last_exception() will return NULL.
 void deno_mod_evaluate(Deno* d, void* user_data, deno_mod id);
 
+// Inspector API
+//
+// Backs Chrome DevTools debugging. libdeno only speaks the DevTools protocol,
+// the embedder provides the transport to the client.
+
+// Receives each protocol message for the client, valid for the duration of
+// the call only.
+typedef void (*deno_inspector_message_cb)(void* user_data,
+                                          const char* message);
+
+// Called repeatedly while V8 waits for the client, e.g. when paused at a
+// breakpoint. Must block until the client sent a message, and pass it on with
+// deno_inspector_dispatch() (or call deno_inspector_disconnect()).
+typedef void (*deno_inspector_wait_cb)(void* user_data);
+
+// Enables the inspector of "d". Must be called at most once.
+void deno_inspector_new(Deno* d, void* user_data,
+                        deno_inspector_message_cb message_cb,
+                        deno_inspector_wait_cb wait_cb);
+
+// Starts and ends the session of a client.
+void deno_inspector_connect(Deno* d);
+void deno_inspector_disconnect(Deno* d);
+
+// Passes a protocol message of the client to the inspector. Evaluating code
+// for the client can call deno_recv_cb with user_data, unless "d" is paused
+// inside a call that already set it.
+void deno_inspector_dispatch(Deno* d, void* user_data, const char* message);
+
+// Blocks until the client sends Runtime.runIfWaitingForDebugger, then makes
+// the next statement that executes pause. Used by --inspect-brk.
+void deno_inspector_wait_for_debugger(Deno* d, void* user_data);
+
 #ifdef __cplusplus
 }  // extern "C"
 #endif
@@ -4,6 +4,66 @@
 
 namespace deno {
 
+v8::Local<v8::Array> EncodeStackTraceAsArray(
+    v8::Local<v8::Context> context, v8::Local<v8::StackTrace> stack_trace) {
+  auto* isolate = context->GetIsolate();
+  v8::EscapableHandleScope handle_scope(isolate);
+  v8::Context::Scope context_scope(context);
+
+  uint32_t count = static_cast<uint32_t>(stack_trace->GetFrameCount());
+  auto frames = v8::Array::New(isolate, count);
+
+  for (uint32_t i = 0; i < count; ++i) {
+    auto frame = stack_trace->GetFrame(isolate, i);
+    auto frame_obj = v8::Object::New(isolate);
+    CHECK(frames->Set(context, i, frame_obj).FromJust());
+    auto line = v8::Integer::New(isolate, frame->GetLineNumber());
+    auto column = v8::Integer::New(isolate, frame->GetColumn());
+    CHECK(frame_obj->Set(context, v8_str("line"), line).FromJust());
+    CHECK(frame_obj->Set(context, v8_str("column"), column).FromJust());
+    CHECK(frame_obj
+              ->Set(context, v8_str("functionName"), frame->GetFunctionName())
+              .FromJust());
+    // scriptName can be empty in special conditions e.g. eval
+    auto scriptName = frame->GetScriptNameOrSourceURL();
+    if (scriptName.IsEmpty()) {
+      scriptName = v8_str("<unknown>");
+    }
+    CHECK(frame_obj->Set(context, v8_str("scriptName"), scriptName).FromJust());
+    CHECK(frame_obj
+              ->Set(context, v8_str("isEval"),
+                    v8::Boolean::New(isolate, frame->IsEval()))
+              .FromJust());
+    CHECK(frame_obj
+              ->Set(context, v8_str("isConstructor"),
+                    v8::Boolean::New(isolate, frame->IsConstructor()))
+              .FromJust());
+    CHECK(frame_obj
+              ->Set(context, v8_str("isWasm"),
+                    v8::Boolean::New(isolate, frame->IsWasm()))
+              .FromJust());
+  }
+  return handle_scope.Escape(frames);
+}
+
+std::string EncodeStackTraceAsJSON(v8::Local<v8::Context> context,
+                                   v8::Local<v8::StackTrace> stack_trace) {
+  auto* isolate = context->GetIsolate();
+  v8::HandleScope handle_scope(isolate);
+  v8::Context::Scope context_scope(context);
+
+  // The shape of an exception without a message, see EncodeMessageAsObject.
+  auto json_obj = v8::Object::New(isolate);
+  CHECK(json_obj->Set(context, v8_str("message"), v8_str("")).FromJust());
+  CHECK(json_obj
+            ->Set(context, v8_str("frames"),
+                  EncodeStackTraceAsArray(context, stack_trace))
+            .FromJust());
+  auto json_string = v8::JSON::Stringify(context, json_obj).ToLocalChecked();
+  v8::String::Utf8Value json_string_(isolate, json_string);
+  return std::string(ToCString(json_string_));
+}
+
 v8::Local<v8::Object> EncodeMessageAsObject(v8::Local<v8::Context> context,
                                             v8::Local<v8::Message> message) {
   auto* isolate = context->GetIsolate();
@@ -79,40 +139,7 @@ v8::Local<v8::Object> EncodeMessageAsObject(v8::Local<v8::Context> context,
 
   v8::Local<v8::Array> frames;
   if (!stack_trace.IsEmpty()) {
-    uint32_t count = static_cast<uint32_t>(stack_trace->GetFrameCount());
-    frames = v8::Array::New(isolate, count);
-
-    for (uint32_t i = 0; i < count; ++i) {
-      auto frame = stack_trace->GetFrame(isolate, i);
-      auto frame_obj = v8::Object::New(isolate);
-      CHECK(frames->Set(context, i, frame_obj).FromJust());
-      auto line = v8::Integer::New(isolate, frame->GetLineNumber());
-      auto column = v8::Integer::New(isolate, frame->GetColumn());
-      CHECK(frame_obj->Set(context, v8_str("line"), line).FromJust());
-      CHECK(frame_obj->Set(context, v8_str("column"), column).FromJust());
-      CHECK(frame_obj
-                ->Set(context, v8_str("functionName"), frame->GetFunctionName())
-                .FromJust());
-      // scriptName can be empty in special conditions e.g. eval
-      auto scriptName = frame->GetScriptNameOrSourceURL();
-      if (scriptName.IsEmpty()) {
-        scriptName = v8_str("<unknown>");
-      }
-      CHECK(
-          frame_obj->Set(context, v8_str("scriptName"), scriptName).FromJust());
-      CHECK(frame_obj
-                ->Set(context, v8_str("isEval"),
-                      v8::Boolean::New(isolate, frame->IsEval()))
-                .FromJust());
-      CHECK(frame_obj
-                ->Set(context, v8_str("isConstructor"),
-                      v8::Boolean::New(isolate, frame->IsConstructor()))
-                .FromJust());
-      CHECK(frame_obj
-                ->Set(context, v8_str("isWasm"),
-                      v8::Boolean::New(isolate, frame->IsWasm()))
-                .FromJust());
-    }
+    frames = EncodeStackTraceAsArray(context, stack_trace);
   } else {
     // No stack trace. We only have one stack frame of info..
     frames = v8::Array::New(isolate, 1);
@@ -149,6 +176,23 @@ std::string EncodeMessageAsJSON(v8::Local<v8::Context> context,
   return std::string(ToCString(json_string_));
 }
 
+// Copies the integer "kind" of an Error, like the one of the errors of ops,
+// so that the embedder does not have to tell errors apart by their message.
+void EncodeErrorKind(v8::Local<v8::Context> context,
+                     v8::Local<v8::Value> exception,
+                     v8::Local<v8::Object> json_obj) {
+  if (!exception->IsNativeError()) {
+    return;
+  }
+  // A getter of "kind" may throw, the error is encoded without a kind then.
+  v8::TryCatch try_catch(context->GetIsolate());
+  v8::Local<v8::Value> kind;
+  if (exception.As<v8::Object>()->Get(context, v8_str("kind")).ToLocal(&kind) &&
+      kind->IsInt32()) {
+    CHECK(json_obj->Set(context, v8_str("kind"), kind).FromJust());
+  }
+}
+
 v8::Local<v8::Object> EncodeExceptionAsObject(v8::Local<v8::Context> context,
                                               v8::Local<v8::Value> exception) {
   auto* isolate = context->GetIsolate();
@@ -157,6 +201,7 @@ v8::Local<v8::Object> EncodeExceptionAsObject(v8::Local<v8::Context> context,
 
   auto message = v8::Exception::CreateMessage(isolate, exception);
   auto json_obj = EncodeMessageAsObject(context, message);
+  EncodeErrorKind(context, exception, json_obj);
   json_obj = handle_scope.Escape(json_obj);
   return json_obj;
 }
@@ -167,8 +212,10 @@ std::string EncodeExceptionAsJSON(v8::Local<v8::Context> context,
   v8::HandleScope handle_scope(isolate);
   v8::Context::Scope context_scope(context);
 
-  auto message = v8::Exception::CreateMessage(isolate, exception);
-  return EncodeMessageAsJSON(context, message);
+  auto json_obj = EncodeExceptionAsObject(context, exception);
+  auto json_string = v8::JSON::Stringify(context, json_obj).ToLocalChecked();
+  v8::String::Utf8Value json_string_(isolate, json_string);
+  return std::string(ToCString(json_string_));
 }
 
 void HandleException(v8::Local<v8::Context> context,
@@ -7,6 +7,11 @@
 
 namespace deno {
 
+// The frames of `stack_trace` as JSON, in the shape of the exceptions below
+// but without a message.
+std::string EncodeStackTraceAsJSON(v8::Local<v8::Context> context,
+                                   v8::Local<v8::StackTrace> stack_trace);
+
 v8::Local<v8::Object> EncodeExceptionAsObject(v8::Local<v8::Context> context,
                                               v8::Local<v8::Value> exception);
 
@@ -0,0 +1,125 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+#include <string.h>
+#include <vector>
+
+#include "third_party/v8/src/base/logging.h"
+
+#include "inspector.h"
+#include "internal.h"
+
+namespace deno {
+
+// Deno has a single context per isolate.
+static const int kContextGroupId = 1;
+
+static v8_inspector::StringView ToStringView(const char* s) {
+  return v8_inspector::StringView(reinterpret_cast<const uint8_t*>(s),
+                                  strlen(s));
+}
+
+Inspector::Inspector(v8::Isolate* isolate, v8::Local<v8::Context> context,
+                     void* user_data, deno_inspector_message_cb message_cb,
+                     deno_inspector_wait_cb wait_cb)
+    : isolate_(isolate),
+      user_data_(user_data),
+      message_cb_(message_cb),
+      wait_cb_(wait_cb),
+      paused_(false),
+      waiting_for_debugger_(false) {
+  context_.Reset(isolate, context);
+  inspector_ = v8_inspector::V8Inspector::create(isolate, this);
+  inspector_->contextCreated(v8_inspector::V8ContextInfo(
+      context, kContextGroupId, ToStringView("deno")));
+}
+
+Inspector::~Inspector() {
+  session_.reset();
+  inspector_.reset();
+  context_.Reset();
+}
+
+void Inspector::Connect() {
+  CHECK(!session_);
+  session_ = inspector_->connect(kContextGroupId, this,
+                                 v8_inspector::StringView());
+}
+
+void Inspector::Disconnect() {
+  session_.reset();
+  // Nobody is left to resume execution.
+  paused_ = false;
+  waiting_for_debugger_ = false;
+}
+
+void Inspector::Dispatch(const char* message) {
+  if (!session_) {
+    return;
+  }
+  auto str = v8::String::NewFromUtf8(isolate_, message,
+                                     v8::NewStringType::kNormal)
+                 .ToLocalChecked();
+  std::vector<uint16_t> buf(str->Length());
+  str->Write(isolate_, buf.data(), 0, str->Length());
+  session_->dispatchProtocolMessage(
+      v8_inspector::StringView(buf.data(), buf.size()));
+}
+
+void Inspector::WaitForDebugger() {
+  waiting_for_debugger_ = true;
+  while (waiting_for_debugger_) {
+    wait_cb_(user_data_);
+  }
+  if (session_) {
+    session_->schedulePauseOnNextStatement(ToStringView("Break on start"),
+                                           ToStringView("{}"));
+  }
+}
+
+void Inspector::runMessageLoopOnPause(int context_group_id) {
+  CHECK_EQ(context_group_id, kContextGroupId);
+  paused_ = true;
+  while (paused_) {
+    wait_cb_(user_data_);
+  }
+}
+
+void Inspector::quitMessageLoopOnPause() { paused_ = false; }
+
+void Inspector::runIfWaitingForDebugger(int context_group_id) {
+  CHECK_EQ(context_group_id, kContextGroupId);
+  waiting_for_debugger_ = false;
+}
+
+v8::Local<v8::Context> Inspector::ensureDefaultContextInGroup(
+    int context_group_id) {
+  CHECK_EQ(context_group_id, kContextGroupId);
+  return context_.Get(isolate_);
+}
+
+void Inspector::sendResponse(
+    int call_id, std::unique_ptr<v8_inspector::StringBuffer> message) {
+  Send(message->string());
+}
+
+void Inspector::sendNotification(
+    std::unique_ptr<v8_inspector::StringBuffer> message) {
+  Send(message->string());
+}
+
+void Inspector::Send(const v8_inspector::StringView& message) {
+  v8::HandleScope handle_scope(isolate_);
+  v8::Local<v8::String> str =
+      message.is8Bit()
+          ? v8::String::NewFromOneByte(isolate_, message.characters8(),
+                                       v8::NewStringType::kNormal,
+                                       static_cast<int>(message.length()))
+                .ToLocalChecked()
+          : v8::String::NewFromTwoByte(isolate_, message.characters16(),
+                                       v8::NewStringType::kNormal,
+                                       static_cast<int>(message.length()))
+                .ToLocalChecked();
+  v8::String::Utf8Value utf8(isolate_, str);
+  message_cb_(user_data_, *utf8);
+}
+
+}  // namespace deno
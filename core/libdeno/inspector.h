@@ -0,0 +1,62 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+#ifndef INSPECTOR_H_
+#define INSPECTOR_H_
+
+#include <memory>
+#include <string>
+#include "deno.h"
+#include "third_party/v8/include/v8-inspector.h"
+#include "third_party/v8/include/v8.h"
+
+namespace deno {
+
+// Connects the V8 inspector of an isolate to a single DevTools client. The
+// transport is left to the embedder: protocol messages for the client are
+// passed to message_cb, and wait_cb is called whenever V8 has to block until
+// the client sends something, i.e. while paused at a breakpoint.
+class Inspector : public v8_inspector::V8InspectorClient,
+                  public v8_inspector::V8Inspector::Channel {
+ public:
+  Inspector(v8::Isolate* isolate, v8::Local<v8::Context> context,
+            void* user_data, deno_inspector_message_cb message_cb,
+            deno_inspector_wait_cb wait_cb);
+  ~Inspector() override;
+
+  void Connect();
+  void Disconnect();
+  void Dispatch(const char* message);
+  // Blocks until the client asks to run, then pauses on the next statement.
+  void WaitForDebugger();
+
+  // v8_inspector::V8InspectorClient
+  void runMessageLoopOnPause(int context_group_id) override;
+  void quitMessageLoopOnPause() override;
+  void runIfWaitingForDebugger(int context_group_id) override;
+  v8::Local<v8::Context> ensureDefaultContextInGroup(
+      int context_group_id) override;
+
+  // v8_inspector::V8Inspector::Channel
+  void sendResponse(
+      int call_id,
+      std::unique_ptr<v8_inspector::StringBuffer> message) override;
+  void sendNotification(
+      std::unique_ptr<v8_inspector::StringBuffer> message) override;
+  void flushProtocolNotifications() override {}
+
+ private:
+  void Send(const v8_inspector::StringView& message);
+
+  v8::Isolate* isolate_;
+  v8::Persistent<v8::Context> context_;
+  void* user_data_;
+  deno_inspector_message_cb message_cb_;
+  deno_inspector_wait_cb wait_cb_;
+  std::unique_ptr<v8_inspector::V8Inspector> inspector_;
+  std::unique_ptr<v8_inspector::V8InspectorSession> session_;
+  bool paused_;
+  bool waiting_for_debugger_;
+};
+
+}  // namespace deno
+
+#endif  // INSPECTOR_H_
@@ -7,6 +7,7 @@
 #include <utility>
 #include <vector>
 #include "deno.h"
+#include "inspector.h"
 #include "third_party/v8/include/v8.h"
 #include "third_party/v8/src/base/logging.h"
 
@@ -38,7 +39,8 @@ class DenoIsolate {
         recv_cb_(config.recv_cb),
         next_zero_copy_id_(1),  // zero_copy_id must not be zero.
         user_data_(nullptr),
-        resolve_cb_(nullptr) {
+        resolve_cb_(nullptr),
+        inspector_(nullptr) {
     array_buffer_allocator_ = v8::ArrayBuffer::Allocator::NewDefaultAllocator();
     if (config.load_snapshot.data_ptr) {
       snapshot_.data =
@@ -48,6 +50,10 @@ class DenoIsolate {
   }
 
   ~DenoIsolate() {
+    // The inspector refers to the context, which must still exist.
+    if (inspector_) {
+      delete inspector_;
+    }
     shared_ab_.Reset();
     if (locker_) {
       delete locker_;
@@ -111,11 +117,15 @@ class DenoIsolate {
   std::map<deno_mod, ModuleInfo> mods_;
   std::map<std::string, deno_mod> mods_by_name_;
   deno_resolve_cb resolve_cb_;
+  // Only with deno_inspector_new().
+  Inspector* inspector_;
 
   v8::Persistent<v8::Context> context_;
   std::map<size_t, v8::Persistent<v8::Value>> zero_copy_map_;
   std::map<int, v8::Persistent<v8::Value>> pending_promise_map_;
   std::string last_exception_;
+  std::string promise_errors_;
+  std::string current_stack_trace_;
   v8::Persistent<v8::Function> recv_;
   v8::StartupData snapshot_;
   v8::Persistent<v8::ArrayBuffer> global_import_buf_;
@@ -41,6 +41,20 @@ TEST(LibDenoTest, ErrorsCorrectly) {
   deno_delete(d);
 }
 
+TEST(LibDenoTest, ErrorKind) {
+  Deno* d = deno_new(deno_config{0, snapshot, empty, nullptr});
+  deno_execute(d, nullptr, "a.js",
+               "const e = new Error('denied'); e.kind = 3; throw e;");
+  EXPECT_NE(nullptr, strstr(deno_last_exception(d), "\"kind\":3"));
+  // Only the integer kind of an Error is encoded.
+  deno_execute(d, nullptr, "b.js", "throw { kind: 3 }");
+  EXPECT_EQ(nullptr, strstr(deno_last_exception(d), "\"kind\""));
+  deno_execute(d, nullptr, "c.js",
+               "const f = new Error('x'); f.kind = 'y'; throw f;");
+  EXPECT_EQ(nullptr, strstr(deno_last_exception(d), "\"kind\""));
+  deno_delete(d);
+}
+
 deno_buf strbuf(const char* str) {
   auto len = strlen(str);
 
@@ -248,6 +262,38 @@ TEST(LibDenoTest, CheckPromiseErrors) {
   deno_delete(d);
 }
 
+TEST(LibDenoTest, TakePromiseErrors) {
+  Deno* d = deno_new(deno_config{0, snapshot, empty, nullptr});
+  EXPECT_EQ(deno_take_promise_errors(d), nullptr);
+  deno_execute(d, nullptr, "a.js", "TakePromiseErrors()");
+  EXPECT_EQ(deno_last_exception(d), nullptr);
+  std::string errors = deno_take_promise_errors(d);
+  EXPECT_NE(errors.find("Uncaught Error: unhandled"), std::string::npos);
+  EXPECT_EQ(errors.find("handled later"), std::string::npos);
+  // The rejections are forgotten once taken.
+  EXPECT_EQ(deno_take_promise_errors(d), nullptr);
+  EXPECT_EQ(deno_last_exception(d), nullptr);
+  deno_delete(d);
+}
+
+TEST(LibDenoTest, CurrentStackTrace) {
+  static int count = 0;
+  auto recv_cb = [](auto _, auto buf, auto zero_copy_buf) {
+    count++;
+    std::string stack = deno_current_stack_trace();
+    EXPECT_EQ(stack.find("{\"message\":\"\",\"frames\":[{\"line\":"), 0u);
+    EXPECT_NE(stack.find("\"functionName\":\"inner\""), std::string::npos);
+    EXPECT_NE(stack.find("\"scriptName\":\"a.js\""), std::string::npos);
+  };
+  Deno* d = deno_new(deno_config{0, snapshot, empty, recv_cb});
+  EXPECT_EQ(deno_current_stack_trace(), nullptr);
+  deno_execute(d, nullptr, "a.js", "CurrentStackTrace()");
+  EXPECT_EQ(deno_last_exception(d), nullptr);
+  EXPECT_EQ(count, 1);
+  EXPECT_EQ(deno_current_stack_trace(), nullptr);
+  deno_delete(d);
+}
+
 TEST(LibDenoTest, LastException) {
   Deno* d = deno_new(deno_config{0, empty_snapshot, empty, nullptr});
   EXPECT_EQ(deno_last_exception(d), nullptr);
@@ -98,6 +98,19 @@ global.SnapshotBug = () => {
   assert("1,2,3" === String([1, 2, 3]));
 };
 
+global.TakePromiseErrors = () => {
+  Promise.reject(new Error("unhandled"));
+  const handledLater = Promise.reject(new Error("handled later"));
+  handledLater.catch(() => {});
+};
+
+global.CurrentStackTrace = () => {
+  function inner() {
+    Deno.core.send(new Uint8Array([42]));
+  }
+  inner();
+};
+
 global.GlobalErrorHandling = () => {
   eval("\n\n notdefined()\n//# sourceURL=helloworld.js");
 };
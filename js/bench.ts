@@ -0,0 +1,194 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+// The benchmarks of `deno bench`, which imports the bench files, so that
+// they register their benchmarks, and then runs them all in this process.
+import { window } from "./window";
+
+export type BenchmarkFunction = () => void | Promise<void>;
+
+export interface BenchmarkDefinition {
+  name: string;
+  fn: BenchmarkFunction;
+  /** How often the benchmark is timed, by default as `--iterations`. */
+  iterations?: number;
+  /** How often it runs untimed before, by default as `--warmup`. */
+  warmup?: number;
+}
+
+export interface BenchmarkOptions {
+  /** A regular expression, only the benchmarks whose name it matches run. */
+  filter?: string | null;
+  iterations?: number | null;
+  warmup?: number | null;
+  /** Print the report as JSON, once all benchmarks ran. */
+  json?: boolean;
+}
+
+/** The times are in milliseconds. */
+export interface BenchmarkResult {
+  name: string;
+  iterations: number;
+  mean: number;
+  median: number;
+  stddev: number;
+}
+
+export interface BenchmarkReport {
+  results: BenchmarkResult[];
+  /** The names of the benchmarks which threw. */
+  failed: string[];
+  /** How many benchmarks the filter skipped. */
+  filtered: number;
+}
+
+const DEFAULT_ITERATIONS = 100;
+const DEFAULT_WARMUP = 10;
+
+const benchmarks: BenchmarkDefinition[] = [];
+
+/** Registers a benchmark for `deno bench`, named after the function unless
+ * a name is given.
+ *
+ *       Deno.bench(function parseUrl(): void {
+ *         new URL("https://deno.land/std/http/server.ts");
+ *       });
+ *       Deno.bench({
+ *         name: "sleep",
+ *         iterations: 5,
+ *         fn: () => new Promise(resolve => setTimeout(resolve, 10))
+ *       });
+ */
+export function bench(fn: BenchmarkFunction): void;
+export function bench(name: string, fn: BenchmarkFunction): void;
+export function bench(definition: BenchmarkDefinition): void;
+export function bench(
+  nameOrFn: string | BenchmarkFunction | BenchmarkDefinition,
+  fn?: BenchmarkFunction
+): void {
+  let definition: BenchmarkDefinition;
+  if (typeof nameOrFn === "string") {
+    definition = { name: nameOrFn, fn: fn! };
+  } else if (typeof nameOrFn === "function") {
+    definition = { name: nameOrFn.name, fn: nameOrFn };
+  } else {
+    definition = nameOrFn;
+  }
+  if (typeof definition.fn !== "function") {
+    throw new TypeError("A benchmark needs a function");
+  }
+  if (!definition.name) {
+    throw new TypeError("A benchmark needs a name");
+  }
+  benchmarks.push(definition);
+}
+
+function median(sorted: number[]): number {
+  const middle = Math.floor(sorted.length / 2);
+  if (sorted.length % 2 === 1) {
+    return sorted[middle];
+  }
+  return (sorted[middle - 1] + sorted[middle]) / 2;
+}
+
+function summarize(name: string, times: number[]): BenchmarkResult {
+  const n = times.length;
+  const mean = times.reduce((sum, t) => sum + t, 0) / n;
+  // The sample standard deviation, 0 for a single run.
+  const variance =
+    n > 1 ? times.reduce((sum, t) => sum + (t - mean) ** 2, 0) / (n - 1) : 0;
+  return {
+    name,
+    iterations: n,
+    mean,
+    median: median(times.slice().sort((a, b) => a - b)),
+    stddev: Math.sqrt(variance)
+  };
+}
+
+async function runOnce(fn: BenchmarkFunction): Promise<void> {
+  const result = fn();
+  // Synchronous benchmarks are not slowed down by a microtask.
+  if (result instanceof Promise) {
+    await result;
+  }
+}
+
+async function measure(
+  definition: BenchmarkDefinition,
+  iterations: number,
+  warmup: number
+): Promise<number[]> {
+  for (let i = 0; i < warmup; i++) {
+    await runOnce(definition.fn);
+  }
+  const times: number[] = [];
+  for (let i = 0; i < iterations; i++) {
+    const start = window.performance.now();
+    await runOnce(definition.fn);
+    times.push(window.performance.now() - start);
+  }
+  return times;
+}
+
+function formatTime(ms: number): string {
+  return `${ms.toFixed(3)}ms`;
+}
+
+/** Runs the registered benchmarks one after the other, in the order they were
+ * registered, and prints the result of each. `deno bench` calls it after the
+ * bench files were imported.
+ */
+export async function runBenchmarks(
+  options: BenchmarkOptions = {}
+): Promise<BenchmarkReport> {
+  let filter: RegExp | undefined;
+  if (options.filter != null) {
+    try {
+      filter = new RegExp(options.filter);
+    } catch (e) {
+      throw new SyntaxError(`Invalid --filter: ${e.message}`);
+    }
+  }
+  const selected = benchmarks.filter(b => !filter || filter.test(b.name));
+  const report: BenchmarkReport = {
+    results: [],
+    failed: [],
+    filtered: benchmarks.length - selected.length
+  };
+  const log = options.json ? (): void => {} : console.log;
+  log(`running ${selected.length} benchmarks`);
+  for (const definition of selected) {
+    const iterations =
+      definition.iterations || options.iterations || DEFAULT_ITERATIONS;
+    const warmup =
+      definition.warmup != null
+        ? definition.warmup
+        : options.warmup != null
+        ? options.warmup
+        : DEFAULT_WARMUP;
+    let times: number[];
+    try {
+      times = await measure(definition, iterations, warmup);
+    } catch (e) {
+      report.failed.push(definition.name);
+      log(`bench ${definition.name} ... FAILED`);
+      console.error(e);
+      continue;
+    }
+    const result = summarize(definition.name, times);
+    report.results.push(result);
+    log(
+      `bench ${result.name} ... mean ${formatTime(result.mean)}` +
+        ` median ${formatTime(result.median)}` +
+        ` stddev ${formatTime(result.stddev)}` +
+        ` (${result.iterations} iterations)`
+    );
+  }
+  log(
+    `\n${selected.length} benchmarks, ${report.failed.length} failed,` +
+      ` ${report.filtered} filtered out`
+  );
+  if (options.json) {
+    console.log(JSON.stringify(report));
+  }
+  return report;
+}
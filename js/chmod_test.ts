@@ -68,6 +68,28 @@ testPerm({ write: false }, function chmodSyncPerm() {
   assertEquals(err.name, "PermissionDenied");
 });
 
+testPerm({ read: true, write: true }, function chmodSyncSpecialBits() {
+  if (!isNotWindows) {
+    return;
+  }
+  const tempDir = Deno.makeTempDirSync();
+  const dirname = tempDir + "/dir";
+  Deno.mkdirSync(dirname);
+  Deno.chmodSync(dirname, 0o1750);
+  assertEquals(Deno.statSync(dirname).mode & 0o7777, 0o1750);
+});
+
+testPerm({ write: true }, function chmodSyncInvalidMode() {
+  let err;
+  try {
+    Deno.chmodSync("/somefile.txt", 0o10000);
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.InvalidInput);
+  assertEquals(err.name, "InvalidInput");
+});
+
 testPerm({ read: true, write: true }, async function chmodSuccess() {
   const enc = new TextEncoder();
   const data = enc.encode("Hello");
@@ -0,0 +1,37 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import * as msg from "gen/cli/msg_generated";
+import * as flatbuffers from "./flatbuffers";
+import * as dispatch from "./dispatch";
+
+function req(
+  path: string,
+  uid: number,
+  gid: number
+): [flatbuffers.Builder, msg.Any, flatbuffers.Offset] {
+  const builder = flatbuffers.createBuilder();
+  const path_ = builder.createString(path);
+  const inner = msg.Chown.createChown(builder, path_, uid, gid);
+  return [builder, msg.Any.Chown, inner];
+}
+
+/** Changes the owner and group of a file/directory synchronously. Fails with
+ * `NotSupported` on Windows.
+ *
+ *       Deno.chownSync("/path/to/file", 1000, 1000);
+ */
+export function chownSync(path: string, uid: number, gid: number): void {
+  dispatch.sendSync(...req(path, uid, gid));
+}
+
+/** Changes the owner and group of a file/directory. Fails with
+ * `NotSupported` on Windows.
+ *
+ *       await Deno.chown("/path/to/file", 1000, 1000);
+ */
+export async function chown(
+  path: string,
+  uid: number,
+  gid: number
+): Promise<void> {
+  await dispatch.sendAsync(...req(path, uid, gid));
+}
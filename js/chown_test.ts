@@ -0,0 +1,52 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import { testPerm, assertEquals } from "./test_util.ts";
+
+const isNotWindows = Deno.build.os !== "win";
+
+async function getUidAndGid(): Promise<[number, number]> {
+  const p = Deno.run({
+    args: ["python", "-c", "import os; print(os.getuid(), os.getgid())"],
+    stdout: "piped"
+  });
+  const output = new TextDecoder().decode(await p.output());
+  p.close();
+  const [uid, gid] = output.trim().split(" ");
+  return [Number(uid), Number(gid)];
+}
+
+testPerm({ write: false }, function chownSyncPerm() {
+  let err;
+  try {
+    Deno.chownSync("/somefile.txt", 1000, 1000);
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+  assertEquals(err.name, "PermissionDenied");
+});
+
+testPerm({ write: true }, function chownSyncNotFound() {
+  let err;
+  try {
+    Deno.chownSync("/badfile.txt", 1000, 1000);
+  } catch (e) {
+    err = e;
+  }
+  const expected = isNotWindows
+    ? Deno.ErrorKind.NotFound
+    : Deno.ErrorKind.NotSupported;
+  assertEquals(err.kind, expected);
+});
+
+testPerm({ run: true, write: true }, async function chownSuccess() {
+  if (!isNotWindows) {
+    return;
+  }
+  const [uid, gid] = await getUidAndGid();
+  const tempDir = Deno.makeTempDirSync();
+  const filename = tempDir + "/test.txt";
+  Deno.writeFileSync(filename, new TextEncoder().encode("Hello"));
+  // Files can be given to their owner and group without being root.
+  Deno.chownSync(filename, uid, gid);
+  await Deno.chown(filename, uid, gid);
+});
@@ -5,8 +5,9 @@ import { window } from "./window";
 import { assetSourceCode } from "./assets";
 import { Console } from "./console";
 import { core } from "./core";
+import { DocModule, DocNode, documentModule } from "./doc";
 import * as os from "./os";
-import { TextDecoder, TextEncoder } from "./text_encoding";
+import { btoa, TextDecoder, TextEncoder } from "./text_encoding";
 import { clearTimer, setTimeout } from "./timers";
 import { postMessage, workerClose, workerMain } from "./workers";
 import { assert, log, notImplemented } from "./util";
@@ -47,6 +48,16 @@ interface CompilerLookup {
   specifier: ModuleSpecifier;
   referrer: ContainingFile;
   cmdId: number;
+  /** Overrides of the default compiler options, in the form of the
+   * `compilerOptions` of a `tsconfig.json`. */
+  compilerOptions?: object;
+  /** Set by `--no-check`, modules are only transpiled. */
+  noCheck?: boolean;
+  /** Set by `deno bundle`, which wants the whole module graph at once. */
+  bundle?: boolean;
+  inlineSourceMap?: boolean;
+  /** Set by `deno doc`, which wants the documentation of the exports. */
+  doc?: boolean;
 }
 
 /** Abstraction of the APIs required from the `os` module so they can be
@@ -61,9 +72,30 @@ interface Os {
  * be easily mocked.
  */
 interface Ts {
+  convertCompilerOptionsFromJson: typeof ts.convertCompilerOptionsFromJson;
   createLanguageService: typeof ts.createLanguageService;
-  formatDiagnosticsWithColorAndContext: typeof ts.formatDiagnosticsWithColorAndContext;
-  formatDiagnostics: typeof ts.formatDiagnostics;
+  flattenDiagnosticMessageText: typeof ts.flattenDiagnosticMessageText;
+  transpileModule: typeof ts.transpileModule;
+}
+
+/** A position in a source file, zero indexed. */
+interface Position {
+  line: number;
+  character: number;
+}
+
+/** A diagnostic as the privileged side renders it, see cli/diagnostics.rs.
+ * `sourceLines` are the lines from `start` to `end`.
+ */
+export interface Diagnostic {
+  category: string;
+  code: number;
+  message: string;
+  fileName?: string;
+  start?: Position;
+  end?: Position;
+  sourceLines?: string[];
+  related?: Diagnostic[];
 }
 
 /** A simple object structure for caching resolved modules and their contents.
@@ -73,6 +105,8 @@ interface Ts {
  */
 class ModuleMetaData implements ts.IScriptSnapshot {
   public scriptVersion = "";
+  /** The URL of its declarations, from an X-TypeScript-Types header. */
+  public typesUrl?: string;
 
   constructor(
     public readonly moduleId: ModuleId,
@@ -117,12 +151,37 @@ function getExtension(
       return fileName.endsWith(".d.ts") ? ts.Extension.Dts : ts.Extension.Ts;
     case msg.MediaType.Json:
       return ts.Extension.Json;
+    // The source of a WebAssembly module is the JS module which loads it.
+    case msg.MediaType.Wasm:
+      return ts.Extension.Js;
     case msg.MediaType.Unknown:
     default:
       throw TypeError("Cannot resolve extension.");
   }
 }
 
+/** The import or export which starts a text, and its specifier. */
+const IMPORT = /^\s*(?:import|export)\b[^"';]*(["'])([^"'\n]+)\1/;
+
+/** The `// @deno-types="./foo.d.ts"` comments of `sourceCode`, by the
+ * specifier of the import or export on the line after each of them: the
+ * declarations to type check that module against.
+ */
+function denoTypesOf(
+  sourceCode: SourceCode
+): Map<ModuleSpecifier, ModuleSpecifier> {
+  const directive = /^\s*\/\/\s*@deno-types\s*=\s*(["'])(.+?)\1.*$/gm;
+  const types = new Map<ModuleSpecifier, ModuleSpecifier>();
+  let match: RegExpExecArray | null;
+  while ((match = directive.exec(sourceCode))) {
+    const imported = IMPORT.exec(sourceCode.slice(directive.lastIndex));
+    if (imported) {
+      types.set(imported[2], match[2]);
+    }
+  }
+  return types;
+}
+
 /** Generate output code for a provided JSON string along with its source. */
 function jsonEsmTemplate(
   jsonString: string,
@@ -135,6 +194,180 @@ function jsonEsmTemplate(
   );
 }
 
+/** Defines the modules of a bundle and loads them on demand. Each module is
+ * emitted as an anonymous AMD module, which gets a `define` bound to its id and
+ * the ids its specifiers resolve to.
+ */
+const BUNDLE_LOADER = `const __denoBundle = (() => {
+  const modules = new Map();
+  function load(id) {
+    const mod = modules.get(id);
+    if (!mod.loaded) {
+      // Set before running the factory, so that cycles see partial exports.
+      mod.loaded = true;
+      const resolve = specifier => {
+        if (!(specifier in mod.resolutions)) {
+          throw new Error(\`Cannot resolve "\${specifier}" from "\${id}"\`);
+        }
+        return load(mod.resolutions[specifier]);
+      };
+      const args = mod.deps.map(dep => {
+        switch (dep) {
+          case "require":
+            return resolve;
+          case "exports":
+            return mod.exports;
+          case "module":
+            return mod;
+          default:
+            return resolve(dep);
+        }
+      });
+      const result = mod.factory(...args);
+      if (result !== undefined) {
+        mod.exports = result;
+      }
+    }
+    return mod.exports;
+  }
+  return {
+    definer(id, resolutions) {
+      return (deps, factory) => {
+        modules.set(id, { deps, factory, resolutions, exports: {} });
+      };
+    },
+    load
+  };
+})();
+`;
+
+/** Encodes a string as UTF-8 and then as base64. */
+function base64Utf8(s: string): string {
+  const bytes = new TextEncoder().encode(s);
+  let binary = "";
+  for (let i = 0; i < bytes.length; i++) {
+    binary += String.fromCharCode(bytes[i]);
+  }
+  return btoa(binary);
+}
+
+/** The positions of the `await`s of a module which are not in a function, in
+ * `await` expressions and `for await` statements. */
+function getTopLevelAwaits(sourceFile: ts.SourceFile): number[] {
+  const positions: number[] = [];
+  function visit(node: ts.Node): void {
+    if (ts.isFunctionLike(node)) {
+      return;
+    }
+    if (ts.isAwaitExpression(node)) {
+      positions.push(node.getStart(sourceFile));
+    } else if (ts.isForOfStatement(node) && node.awaitModifier) {
+      positions.push(node.awaitModifier.getStart(sourceFile));
+    }
+    ts.forEachChild(node, visit);
+  }
+  ts.forEachChild(sourceFile, visit);
+  return positions;
+}
+
+/** An `export {}` only makes a file a module, it exports nothing. */
+function isEmptyExport(statement: ts.Statement): boolean {
+  return (
+    ts.isExportDeclaration(statement) &&
+    !statement.moduleSpecifier &&
+    !!statement.exportClause &&
+    statement.exportClause.elements.length === 0
+  );
+}
+
+function hasExports(sourceFile: ts.SourceFile): boolean {
+  return sourceFile.statements.some(
+    statement =>
+      (ts.isExportDeclaration(statement) && !isEmptyExport(statement)) ||
+      ts.isExportAssignment(statement) ||
+      (statement.modifiers != null &&
+        statement.modifiers.some(
+          modifier => modifier.kind === ts.SyntaxKind.ExportKeyword
+        ))
+  );
+}
+
+/** The errors TypeScript reports for `await` and `for await` outside of an
+ * async function. */
+const AWAIT_OUTSIDE_ASYNC = [1308, 1103];
+
+/** Leaves out the errors about top-level `await` in modules without exports,
+ * which `topLevelAwait()` makes valid. In a module with exports the error
+ * says why it is not allowed instead.
+ */
+function allowTopLevelAwait(
+  diagnostics: ReadonlyArray<ts.Diagnostic>
+): ts.Diagnostic[] {
+  const result: ts.Diagnostic[] = [];
+  for (const diagnostic of diagnostics) {
+    const { code, file, start } = diagnostic;
+    if (
+      !AWAIT_OUTSIDE_ASYNC.includes(code) ||
+      !file ||
+      start == null ||
+      !getTopLevelAwaits(file).includes(start)
+    ) {
+      result.push(diagnostic);
+    } else if (hasExports(file)) {
+      result.push({
+        ...diagnostic,
+        messageText:
+          "Top-level 'await' is only allowed in modules without exports."
+      });
+    }
+  }
+  return result;
+}
+
+/** V8 does not support top-level `await` yet, so the statements of a module
+ * which uses it, apart from its imports, are run in an async function. Its
+ * rejection fails the program like an unhandled rejection does, and the ops
+ * it awaits keep the program running until it settles. Modules which import
+ * it do not wait for it, which is why it is only allowed in modules without
+ * exports, like the main module of a program.
+ */
+function topLevelAwait(): ts.Transformer<ts.SourceFile> {
+  return (sourceFile: ts.SourceFile): ts.SourceFile => {
+    if (getTopLevelAwaits(sourceFile).length === 0 || hasExports(sourceFile)) {
+      return sourceFile;
+    }
+    const imports: ts.Statement[] = [];
+    const body: ts.Statement[] = [];
+    for (const statement of sourceFile.statements) {
+      if (
+        ts.isImportDeclaration(statement) ||
+        ts.isImportEqualsDeclaration(statement) ||
+        isEmptyExport(statement)
+      ) {
+        imports.push(statement);
+      } else {
+        body.push(statement);
+      }
+    }
+    const main = ts.createArrowFunction(
+      [ts.createModifier(ts.SyntaxKind.AsyncKeyword)],
+      undefined,
+      [],
+      undefined,
+      ts.createToken(ts.SyntaxKind.EqualsGreaterThanToken),
+      ts.createBlock(body, true)
+    );
+    return ts.updateSourceFileNode(sourceFile, [
+      ...imports,
+      ts.createExpressionStatement(
+        ts.createCall(ts.createParen(main), undefined, [])
+      )
+    ]);
+  };
+}
+
+const TRANSFORMERS: ts.CustomTransformers = { before: [topLevelAwait] };
+
 /** A singleton class that combines the TypeScript Language Service host API
  * with Deno specific APIs to provide an interface for compiling and running
  * TypeScript and JavaScript modules.
@@ -179,9 +412,63 @@ class Compiler implements ts.LanguageServiceHost, ts.FormatDiagnosticsHost {
   // A reference to `typescript` module so it can be monkey patched during
   // testing
   private _ts: Ts = ts;
+  // Whether imports are type checked against the declarations given for
+  // them, for which `bundle()` would leave the modules themselves out.
+  private _useDeclarations = true;
 
   private readonly _assetsSourceCode: { [key: string]: string };
 
+  /** Set by `--quiet` or `--log-level` to stop announcing each compiled
+   * module. */
+  quiet = false;
+
+  /** Converts a TypeScript diagnostic, with the lines of source it spans, for
+   * the privileged side to print. */
+  private _toDiagnostic(
+    diagnostic: ts.Diagnostic | ts.DiagnosticRelatedInformation
+  ): Diagnostic {
+    const { category, code, file, start, length } = diagnostic;
+    const result: Diagnostic = {
+      category: ts.DiagnosticCategory[category].toLowerCase(),
+      code,
+      message: this._ts.flattenDiagnosticMessageText(
+        diagnostic.messageText,
+        "\n"
+      )
+    };
+    if (file && start != null) {
+      const moduleMetaData = this._getModuleMetaData(file.fileName);
+      result.fileName = moduleMetaData
+        ? moduleMetaData.moduleId
+        : file.fileName;
+      result.start = file.getLineAndCharacterOfPosition(start);
+      result.end = file.getLineAndCharacterOfPosition(start + (length || 0));
+      const lineStarts = file.getLineStarts();
+      result.sourceLines = [];
+      for (let line = result.start.line; line <= result.end.line; line++) {
+        const lineEnd =
+          line + 1 < lineStarts.length
+            ? lineStarts[line + 1]
+            : file.text.length;
+        result.sourceLines.push(
+          file.text.slice(lineStarts[line], lineEnd).replace(/\r?\n$/, "")
+        );
+      }
+    }
+    if ("relatedInformation" in diagnostic && diagnostic.relatedInformation) {
+      result.related = diagnostic.relatedInformation.map(related =>
+        this._toDiagnostic(related)
+      );
+    }
+    return result;
+  }
+
+  private _toDiagnostics(
+    diagnostics: ReadonlyArray<ts.Diagnostic>
+  ): Diagnostic[] {
+    return diagnostics.map(diagnostic => this._toDiagnostic(diagnostic));
+  }
+
   /** The TypeScript language service often refers to the resolved fileName of
    * a module, this is a shortcut to avoid unnecessary module resolution logic
    * for modules that may have been initially resolved by a `moduleSpecifier`
@@ -233,6 +520,7 @@ class Compiler implements ts.LanguageServiceHost, ts.FormatDiagnosticsHost {
     let moduleId: ModuleId | undefined;
     let mediaType = msg.MediaType.Unknown;
     let sourceCode: SourceCode | undefined;
+    let typesUrl: string | undefined;
     if (
       moduleSpecifier.startsWith(ASSETS) ||
       containingFile.startsWith(ASSETS)
@@ -260,6 +548,7 @@ class Compiler implements ts.LanguageServiceHost, ts.FormatDiagnosticsHost {
       fileName = fetchResponse.filename;
       mediaType = fetchResponse.mediaType;
       sourceCode = fetchResponse.sourceCode;
+      typesUrl = fetchResponse.typesUrl;
     }
     assert(moduleId != null, "No module ID.");
     assert(fileName != null, "No file name.");
@@ -283,10 +572,34 @@ class Compiler implements ts.LanguageServiceHost, ts.FormatDiagnosticsHost {
       mediaType,
       sourceCode
     );
+    moduleMetaData.typesUrl = typesUrl;
     this._moduleMetaDataMap.set(fileName!, moduleMetaData);
     return moduleMetaData;
   }
 
+  /** The module which the import `moduleSpecifier` of `containingFile` is
+   * type checked against: the declarations of a `// @deno-types` comment
+   * before the import, or else those of the X-TypeScript-Types header of a
+   * JavaScript module, or else the module itself. It still runs as it is.
+   */
+  private _resolveTypes(
+    moduleSpecifier: ModuleSpecifier,
+    containingFile: ContainingFile
+  ): ModuleMetaData {
+    const moduleMetaData = this._resolveModule(moduleSpecifier, containingFile);
+    if (!this._useDeclarations) {
+      return moduleMetaData;
+    }
+    const containing = this._moduleMetaDataMap.get(containingFile);
+    const types =
+      (containing &&
+        denoTypesOf(containing.sourceCode).get(moduleSpecifier)) ||
+      (moduleMetaData.mediaType === msg.MediaType.JavaScript
+        ? moduleMetaData.typesUrl
+        : undefined);
+    return types ? this._resolveModule(types, containingFile) : moduleMetaData;
+  }
+
   /** Caches the resolved `fileName` in relationship to the `moduleSpecifier`
    * and `containingFile` in order to reduce calls to the privileged side
    * to retrieve the contents of a module.
@@ -312,17 +625,28 @@ class Compiler implements ts.LanguageServiceHost, ts.FormatDiagnosticsHost {
 
   // Deno specific compiler API
 
-  /** Retrieve the output of the TypeScript compiler for a given module.
+  /** Retrieve the output of the TypeScript compiler for a given module, or
+   * the diagnostics which kept it from compiling. The privileged side prints
+   * the diagnostics, so that modules compiled by several compiler workers at
+   * once report them in a fixed order. With `noCheck` the module is only
+   * transpiled, which reports syntax errors but no type errors.
    */
   compile(
     moduleSpecifier: ModuleSpecifier,
-    containingFile: ContainingFile
-  ): { outputCode: OutputCode; sourceMap: SourceMap } {
+    containingFile: ContainingFile,
+    noCheck = false
+  ): {
+    outputCode: OutputCode;
+    sourceMap: SourceMap;
+    diagnostics?: Diagnostic[];
+  } {
     this._log("compiler.compile", { moduleSpecifier, containingFile });
     const moduleMetaData = this._resolveModule(moduleSpecifier, containingFile);
     const { fileName, mediaType, moduleId, sourceCode } = moduleMetaData;
     this._scriptFileNames = [fileName];
-    console.warn("Compiling", moduleId);
+    if (!this.quiet) {
+      console.warn("Compiling", moduleId);
+    }
     let outputCode: string;
     let sourceMap = "";
     // Instead of using TypeScript to transpile JSON modules, we will just do
@@ -332,11 +656,30 @@ class Compiler implements ts.LanguageServiceHost, ts.FormatDiagnosticsHost {
         sourceCode,
         fileName
       );
+    } else if (noCheck) {
+      const output = this._ts.transpileModule(sourceCode, {
+        compilerOptions: this._options,
+        fileName,
+        reportDiagnostics: true,
+        transformers: TRANSFORMERS
+      });
+      if (output.diagnostics && output.diagnostics.length > 0) {
+        return {
+          outputCode: "",
+          sourceMap: "",
+          diagnostics: this._toDiagnostics(output.diagnostics)
+        };
+      }
+      outputCode = moduleMetaData.outputCode = `${
+        output.outputText
+      }\n//# sourceURL=${fileName}`;
+      sourceMap = moduleMetaData.sourceMap = output.sourceMapText!;
     } else {
       const service = this._service;
       assert(
         mediaType === msg.MediaType.TypeScript ||
-          mediaType === msg.MediaType.JavaScript
+          mediaType === msg.MediaType.JavaScript ||
+          mediaType === msg.MediaType.Wasm
       );
       const output = service.getEmitOutput(fileName);
 
@@ -351,16 +694,14 @@ class Compiler implements ts.LanguageServiceHost, ts.FormatDiagnosticsHost {
           .getCompilerOptionsDiagnostics()
           .filter(diagnostic => diagnostic.code !== 5070),
         ...service.getSyntacticDiagnostics(fileName),
-        ...service.getSemanticDiagnostics(fileName)
+        ...allowTopLevelAwait(service.getSemanticDiagnostics(fileName))
       ];
       if (diagnostics.length > 0) {
-        const errMsg = os.noColor
-          ? this._ts.formatDiagnostics(diagnostics, this)
-          : this._ts.formatDiagnosticsWithColorAndContext(diagnostics, this);
-
-        console.log(errMsg);
-        // All TypeScript errors are terminal for deno
-        this._os.exit(1);
+        return {
+          outputCode: "",
+          sourceMap: "",
+          diagnostics: this._toDiagnostics(diagnostics)
+        };
       }
 
       assert(
@@ -392,8 +733,150 @@ class Compiler implements ts.LanguageServiceHost, ts.FormatDiagnosticsHost {
     return { outputCode, sourceMap };
   }
 
+  /** Type checks the module graph of a module and emits all of its modules,
+   * and the loader to run them, as a single script, or returns the
+   * diagnostics which kept it from compiling.
+   */
+  bundle(
+    moduleSpecifier: ModuleSpecifier,
+    containingFile: ContainingFile,
+    inlineSourceMap: boolean
+  ): { bundle: string; diagnostics?: Diagnostic[] } {
+    this._log("compiler.bundle", { moduleSpecifier, containingFile });
+    this._useDeclarations = false;
+    const root = this._resolveModule(moduleSpecifier, containingFile);
+    this._scriptFileNames = [root.fileName];
+    if (!this.quiet) {
+      console.warn("Bundling", root.moduleId);
+    }
+    const service = this._service;
+    const modules = service
+      .getProgram()!
+      .getSourceFiles()
+      .map(sourceFile => this._getModuleMetaData(sourceFile.fileName))
+      .filter(
+        (moduleMetaData): moduleMetaData is ModuleMetaData =>
+          moduleMetaData != null &&
+          !moduleMetaData.fileName.startsWith(ASSETS) &&
+          !moduleMetaData.fileName.endsWith(".d.ts")
+      );
+
+    const diagnostics = [
+      // See compile() for 5070.
+      ...service
+        .getCompilerOptionsDiagnostics()
+        .filter(diagnostic => diagnostic.code !== 5070)
+    ];
+    for (const { fileName, mediaType } of modules) {
+      if (mediaType !== msg.MediaType.Json) {
+        diagnostics.push(
+          ...service.getSyntacticDiagnostics(fileName),
+          ...allowTopLevelAwait(service.getSemanticDiagnostics(fileName))
+        );
+      }
+    }
+    if (diagnostics.length > 0) {
+      return { bundle: "", diagnostics: this._toDiagnostics(diagnostics) };
+    }
+
+    let bundle = BUNDLE_LOADER;
+    const sections: Array<{
+      offset: { line: number; column: number };
+      map: object;
+    }> = [];
+    for (const moduleMetaData of modules) {
+      const { fileName, mediaType, moduleId, sourceCode } = moduleMetaData;
+      const resolutions: { [specifier: string]: ModuleId } = {};
+      const fileNames = this._fileNamesMap.get(fileName) || new Map();
+      for (const [specifier, resolvedFileName] of fileNames) {
+        const resolved = this._moduleMetaDataMap.get(resolvedFileName);
+        if (resolved && !resolvedFileName.endsWith(".d.ts")) {
+          resolutions[specifier] = resolved.moduleId;
+        }
+      }
+      bundle += `(function (define) {\n// ${moduleId}\n`;
+      const line = bundle.split(EOL).length - 1;
+      if (mediaType === msg.MediaType.Json) {
+        bundle +=
+          `define(["exports"], function (exports) {\n` +
+          `  exports.__esModule = true;\n` +
+          `  exports.default = ${sourceCode.trim()};\n` +
+          `});\n`;
+      } else {
+        const output = this._ts.transpileModule(sourceCode, {
+          compilerOptions: {
+            ...this._options,
+            module: ts.ModuleKind.AMD,
+            sourceMap: inlineSourceMap
+          },
+          fileName,
+          transformers: TRANSFORMERS
+        });
+        let code = output.outputText;
+        if (output.sourceMapText) {
+          // The map of the bundle replaces the map of the module.
+          code = code.replace(/\/\/# sourceMappingURL=.*$/, "");
+          const map = JSON.parse(output.sourceMapText);
+          map.sources = [moduleId];
+          map.sourcesContent = [sourceCode];
+          delete map.file;
+          sections.push({ offset: { line, column: 0 }, map });
+        }
+        bundle += code.endsWith(EOL) ? code : code + EOL;
+      }
+      bundle +=
+        `})(__denoBundle.definer(${JSON.stringify(moduleId)}, ` +
+        `${JSON.stringify(resolutions)}));\n`;
+    }
+    bundle += `__denoBundle.load(${JSON.stringify(root.moduleId)});\n`;
+    if (inlineSourceMap) {
+      const indexMap = JSON.stringify({ version: 3, sections });
+      bundle +=
+        `//# sourceMappingURL=data:application/json;base64,` +
+        `${base64Utf8(indexMap)}\n`;
+    }
+    return { bundle };
+  }
+
+  /** Parses a module, and those it re-exports from, for the documentation of
+   * what it exports. Nothing is type checked.
+   */
+  doc(
+    moduleSpecifier: ModuleSpecifier,
+    containingFile: ContainingFile
+  ): { nodes: DocNode[] } {
+    this._log("compiler.doc", { moduleSpecifier, containingFile });
+    const parse = (specifier: string, referrer: string): DocModule => {
+      const { moduleId, fileName, sourceCode } = this._resolveModule(
+        specifier,
+        referrer
+      );
+      const sourceFile = ts.createSourceFile(
+        fileName,
+        sourceCode,
+        ts.ScriptTarget.ESNext,
+        true
+      );
+      return { moduleId, fileName, sourceFile };
+    };
+    return { nodes: documentModule(parse, moduleSpecifier, containingFile) };
+  }
+
   // TypeScript Language Service and Format Diagnostic Host API
 
+  /** Applies `compilerOptions` on top of the default compiler options. */
+  configure(compilerOptions: object): void {
+    const { options } = this._ts.convertCompilerOptionsFromJson(
+      compilerOptions,
+      ""
+    );
+    // The default library, the deno runtime, is only used without `lib`.
+    if (options.lib) {
+      options.lib.push("lib.deno_runtime.d.ts");
+    }
+    Object.assign(this._options, options);
+  }
+
   getCanonicalFileName(fileName: string): string {
     this._log("getCanonicalFileName", fileName);
     return fileName;
@@ -423,6 +906,7 @@ class Compiler implements ts.LanguageServiceHost, ts.FormatDiagnosticsHost {
         case msg.MediaType.TypeScript:
           return ts.ScriptKind.TS;
         case msg.MediaType.JavaScript:
+        case msg.MediaType.Wasm:
           return ts.ScriptKind.JS;
         case msg.MediaType.Json:
           return ts.ScriptKind.JSON;
@@ -446,6 +930,10 @@ class Compiler implements ts.LanguageServiceHost, ts.FormatDiagnosticsHost {
     return this._getModuleMetaData(fileName);
   }
 
+  getCustomTransformers(): ts.CustomTransformers {
+    return TRANSFORMERS;
+  }
+
   getCurrentDirectory(): string {
     this._log("getCurrentDirectory()");
     return "";
@@ -483,7 +971,7 @@ class Compiler implements ts.LanguageServiceHost, ts.FormatDiagnosticsHost {
     this._log("resolveModuleNames()", { moduleNames, containingFile });
     const resolvedModuleNames: ts.ResolvedModuleFull[] = [];
     for (const moduleName of moduleNames) {
-      const moduleMetaData = this._resolveModule(moduleName, containingFile);
+      const moduleMetaData = this._resolveTypes(moduleName, containingFile);
       // According to the interface we shouldn't return `undefined` but if we
       // fail to return the same length of modules to those we cannot resolve
       // then TypeScript fails on an assertion that the lengths can't be
@@ -522,10 +1010,26 @@ window.TextEncoder = TextEncoder;
 window.compilerMain = function compilerMain() {
   // workerMain should have already been called since a compiler is a worker.
   window.onmessage = ({ data }: { data: CompilerLookup }) => {
-    const { specifier, referrer, cmdId } = data;
+    const {
+      specifier,
+      referrer,
+      compilerOptions,
+      noCheck,
+      cmdId,
+      bundle,
+      inlineSourceMap,
+      doc
+    } = data;
 
     try {
-      const result = compiler.compile(specifier, referrer);
+      if (compilerOptions) {
+        compiler.configure(compilerOptions);
+      }
+      const result = doc
+        ? compiler.doc(specifier, referrer)
+        : bundle
+        ? compiler.bundle(specifier, referrer, !!inlineSourceMap)
+        : compiler.compile(specifier, referrer, !!noCheck);
       postMessage({
         success: true,
         cmdId,
@@ -542,5 +1046,7 @@ window.compilerMain = function compilerMain() {
 };
 
 export default function denoMain(): void {
-  os.start("TS");
+  const startResMsg = os.start("TS");
+  compiler.quiet =
+    startResMsg.quietFlag() || startResMsg.logLevel() > msg.LogLevel.Info;
 }
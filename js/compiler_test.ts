@@ -302,14 +302,10 @@ const tsMock = {
   createLanguageService() {
     return {} as any;
   },
-  formatDiagnostics(diagnostics: ReadonlyArray<any>, _host: any): string {
-    return JSON.stringify(diagnostics.map(({ messageText }) => messageText));
-  },
-  formatDiagnosticsWithColorAndContext(
-    diagnostics: ReadonlyArray<any>,
-    _host: any
-  ): string {
-    return JSON.stringify(diagnostics.map(({ messageText }) => messageText));
+  flattenDiagnosticMessageText(messageText: any, _newLine: string): string {
+    return typeof messageText === "string"
+      ? messageText
+      : messageText.messageText;
   }
 };
 
@@ -3,40 +3,65 @@ import * as msg from "gen/cli/msg_generated";
 import * as flatbuffers from "./flatbuffers";
 import * as dispatch from "./dispatch";
 
+export interface CopyFileOptions {
+  /** Replace the target if it exists. Defaults to false. */
+  overwrite?: boolean;
+}
+
 function req(
   from: string,
-  to: string
+  to: string,
+  options: CopyFileOptions
 ): [flatbuffers.Builder, msg.Any, flatbuffers.Offset] {
   const builder = flatbuffers.createBuilder();
   const from_ = builder.createString(from);
   const to_ = builder.createString(to);
-  const inner = msg.CopyFile.createCopyFile(builder, from_, to_);
+  const inner = msg.CopyFile.createCopyFile(
+    builder,
+    from_,
+    to_,
+    !!options.overwrite
+  );
   return [builder, msg.Any.CopyFile, inner];
 }
 
 /** Copies the contents of a file to another by name synchronously.
- * Creates a new file if target does not exists, and if target exists,
- * overwrites original content of the target file.
+ * Creates a new file if target does not exists. If target exists it is an
+ * `AlreadyExists` error, unless `overwrite` is set, which replaces the
+ * original content of the target file.
  *
  * It would also copy the permission of the original file
  * to the destination.
  *
+ * Requires the `--allow-read` flag for `from` and `--allow-write` for `to`.
+ *
  *       Deno.copyFileSync("from.txt", "to.txt");
  */
-export function copyFileSync(from: string, to: string): void {
-  dispatch.sendSync(...req(from, to));
+export function copyFileSync(
+  from: string,
+  to: string,
+  options: CopyFileOptions = {}
+): void {
+  dispatch.sendSync(...req(from, to, options));
 }
 
 /** Copies the contents of a file to another by name.
  *
- * Creates a new file if target does not exists, and if target exists,
- * overwrites original content of the target file.
+ * Creates a new file if target does not exists. If target exists it is an
+ * `AlreadyExists` error, unless `overwrite` is set, which replaces the
+ * original content of the target file.
  *
  * It would also copy the permission of the original file
  * to the destination.
  *
- *       await Deno.copyFile("from.txt", "to.txt");
+ * Requires the `--allow-read` flag for `from` and `--allow-write` for `to`.
+ *
+ *       await Deno.copyFile("from.txt", "to.txt", { overwrite: true });
  */
-export async function copyFile(from: string, to: string): Promise<void> {
-  await dispatch.sendAsync(...req(from, to));
+export async function copyFile(
+  from: string,
+  to: string,
+  options: CopyFileOptions = {}
+): Promise<void> {
+  await dispatch.sendAsync(...req(from, to, options));
 }
@@ -78,13 +78,61 @@ testPerm({ read: true, write: true }, function copyFileSyncOverwrite() {
   writeFileString(fromFilename, "Hello world!");
   // Make Dest exist and have different content
   writeFileString(toFilename, "Goodbye!");
-  Deno.copyFileSync(fromFilename, toFilename);
+  Deno.copyFileSync(fromFilename, toFilename, { overwrite: true });
   // No change to original file
   assertEquals(readFileString(fromFilename), "Hello world!");
   // Original == Dest
   assertSameContent(fromFilename, toFilename);
 });
 
+testPerm({ read: true, write: true }, function copyFileSyncNoOverwrite() {
+  const tempDir = Deno.makeTempDirSync();
+  const fromFilename = tempDir + "/from.txt";
+  const toFilename = tempDir + "/to.txt";
+  writeFileString(fromFilename, "Hello world!");
+  writeFileString(toFilename, "Goodbye!");
+  let err;
+  try {
+    Deno.copyFileSync(fromFilename, toFilename);
+  } catch (e) {
+    err = e;
+  }
+  assert(!!err);
+  assertEquals(err.kind, Deno.ErrorKind.AlreadyExists);
+  // The target is untouched
+  assertEquals(readFileString(toFilename), "Goodbye!");
+});
+
+testPerm({ read: true, write: true }, function copyFileSyncMode() {
+  if (Deno.build.os === "win") {
+    return;
+  }
+  const tempDir = Deno.makeTempDirSync();
+  const fromFilename = tempDir + "/from.txt";
+  const toFilename = tempDir + "/to.txt";
+  writeFileString(fromFilename, "Hello world!");
+  Deno.chmodSync(fromFilename, 0o751);
+  Deno.copyFileSync(fromFilename, toFilename);
+  assertEquals(Deno.statSync(toFilename).mode & 0o777, 0o751);
+  writeFileString(fromFilename, "Hello again!");
+  Deno.chmodSync(fromFilename, 0o640);
+  Deno.copyFileSync(fromFilename, toFilename, { overwrite: true });
+  assertEquals(Deno.statSync(toFilename).mode & 0o777, 0o640);
+  assertSameContent(fromFilename, toFilename);
+});
+
+testPerm({ read: true, write: true }, function copyFileSyncDirectory() {
+  const tempDir = Deno.makeTempDirSync();
+  let err;
+  try {
+    Deno.copyFileSync(tempDir, tempDir + "/to.txt");
+  } catch (e) {
+    err = e;
+  }
+  assert(!!err);
+  assertEquals(err.kind, Deno.ErrorKind.InvalidInput);
+});
+
 testPerm({ read: true, write: true }, async function copyFileSuccess() {
   const tempDir = Deno.makeTempDirSync();
   const fromFilename = tempDir + "/from.txt";
@@ -120,7 +168,7 @@ testPerm({ read: true, write: true }, async function copyFileOverwrite() {
   writeFileString(fromFilename, "Hello world!");
   // Make Dest exist and have different content
   writeFileString(toFilename, "Goodbye!");
-  await Deno.copyFile(fromFilename, toFilename);
+  await Deno.copyFile(fromFilename, toFilename, { overwrite: true });
   // No change to original file
   assertEquals(readFileString(fromFilename), "Hello world!");
   // Original == Dest
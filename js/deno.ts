@@ -1,7 +1,16 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
 
 // Public deno module.
-export { noColor, pid, env, exit, isTTY, execPath } from "./os";
+export {
+  noColor,
+  pid,
+  env,
+  exit,
+  addUnloadHook,
+  UnloadHook,
+  isTTY,
+  execPath
+} from "./os";
 export { chdir, cwd } from "./dir";
 export {
   File,
@@ -17,7 +26,8 @@ export {
   seek,
   seekSync,
   close,
-  OpenMode
+  OpenMode,
+  OpenOptions
 } from "./files";
 export {
   copy,
@@ -43,32 +53,96 @@ export { mkdirSync, mkdir } from "./mkdir";
 export {
   makeTempDirSync,
   makeTempDir,
-  MakeTempDirOptions
+  makeTempFileSync,
+  makeTempFile,
+  MakeTempDirOptions,
+  MakeTempOptions
 } from "./make_temp_dir";
 export { chmodSync, chmod } from "./chmod";
+export { chownSync, chown } from "./chown";
 export { removeSync, remove, RemoveOption } from "./remove";
-export { renameSync, rename } from "./rename";
+export { renameSync, rename, RenameOptions } from "./rename";
 export { readFileSync, readFile } from "./read_file";
-export { readDirSync, readDir } from "./read_dir";
-export { copyFileSync, copyFile } from "./copy_file";
+export { readDirSync, readDir, ReadDirOptions } from "./read_dir";
+export { copyFileSync, copyFile, CopyFileOptions } from "./copy_file";
 export { readlinkSync, readlink } from "./read_link";
 export { statSync, lstatSync, stat, lstat } from "./stat";
 export { linkSync, link } from "./link";
-export { symlinkSync, symlink } from "./symlink";
+export { symlinkSync, symlink, SymlinkType } from "./symlink";
 export { writeFileSync, writeFile, WriteFileOptions } from "./write_file";
-export { ErrorKind, DenoError } from "./errors";
+export { ErrorKind, DenoError, errors } from "./errors";
+export { CancellablePromise } from "./dispatch";
 export {
   permissions,
   revokePermission,
   Permission,
-  Permissions
+  Permissions,
+  PermissionDescriptor,
+  PermissionState,
+  PermissionStatus,
+  PermissionsApi
 } from "./permissions";
 export { truncateSync, truncate } from "./truncate";
+export { umask } from "./umask";
 export { FileInfo } from "./file_info";
-export { connect, dial, listen, Listener, Conn } from "./net";
+export {
+  connect,
+  dial,
+  listen,
+  listenDatagram,
+  Listener,
+  Conn,
+  ListenOptions,
+  DialOptions,
+  listenTLS,
+  dialTLS,
+  ListenTLSOptions,
+  DialTLSOptions,
+  UnixAddr,
+  Datagram,
+  DatagramConn,
+  ListenDatagramOptions
+} from "./net";
+export {
+  serveHttp,
+  HttpServer,
+  ServerRequest,
+  ServerResponse
+} from "./http_server";
+export {
+  dialWebSocket,
+  DialWebSocketOptions,
+  WebSocketConn,
+  WebSocketMessage
+} from "./websocket";
 export { metrics, Metrics } from "./metrics";
+export {
+  bench,
+  runBenchmarks,
+  BenchmarkDefinition,
+  BenchmarkFunction,
+  BenchmarkOptions,
+  BenchmarkReport,
+  BenchmarkResult
+} from "./bench";
+export {
+  test,
+  runTests,
+  RunTestsOptions,
+  TestDefinition,
+  TestFunction,
+  TestReport
+} from "./test_runner";
 export { resources } from "./resources";
-export { run, RunOptions, Process, ProcessStatus } from "./process";
+export { kill, run, RunOptions, Process, ProcessStatus } from "./process";
+export { signal, Signal, SignalStream } from "./signal";
+export {
+  watchFs,
+  FsEvent,
+  FsEventKind,
+  FsWatcher,
+  WatchFsOptions
+} from "./fs_events";
 export { inspect } from "./console";
 export { build, platform, OperatingSystem, Arch } from "./build";
 export { version } from "./version";
@@ -85,3 +159,8 @@ export { Console, stringifyArgs } from "./console";
 // TODO Don't expose DomIterableMixin.
 /** @internal */
 export { DomIterableMixin } from "./mixins/dom_iterable";
+// The ops sent with one dispatch, for tests and benchmarks.
+/** @internal */
+export { statSyncBatch } from "./stat";
+/** @internal */
+export { readSyncBatch, writeSyncBatch } from "./files";
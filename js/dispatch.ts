@@ -7,6 +7,16 @@ import * as util from "./util";
 
 let nextCmdId = 0;
 const promiseTable = new Map<number, util.Resolvable<msg.Base>>();
+// The types of the ops in the promise table.
+const pendingTypes = new Map<number, msg.Any>();
+
+// @internal
+/** The types of the async ops which were sent and did not complete yet, in
+ * the order they were sent.
+ */
+export function pendingOps(): msg.Any[] {
+  return Array.from(pendingTypes.values());
+}
 
 export function handleAsyncMsgFromRust(ui8: Uint8Array): void {
   const bb = new flatbuffers.ByteBuffer(ui8);
@@ -15,6 +25,7 @@ export function handleAsyncMsgFromRust(ui8: Uint8Array): void {
   const promise = promiseTable.get(cmdId);
   util.assert(promise != null, `Expecting promise in table. ${cmdId}`);
   promiseTable.delete(cmdId);
+  pendingTypes.delete(cmdId);
   const err = errors.maybeError(base);
   if (err != null) {
     promise!.reject(err);
@@ -49,13 +60,12 @@ function sendInternal(
   return [cmdId, response];
 }
 
-// @internal
-export function sendAsync(
+function sendAsyncInternal(
   builder: flatbuffers.Builder,
   innerType: msg.Any,
   inner: flatbuffers.Offset,
   data?: ArrayBufferView
-): Promise<msg.Base> {
+): [number, Promise<msg.Base>] {
   const [cmdId, response] = sendInternal(
     builder,
     innerType,
@@ -66,24 +76,168 @@ export function sendAsync(
   util.assert(response == null);
   const promise = util.createResolvable<msg.Base>();
   promiseTable.set(cmdId, promise);
-  return promise;
+  pendingTypes.set(cmdId, innerType);
+  return [cmdId, promise];
 }
 
 // @internal
-export function sendSync(
+export function sendAsync(
   builder: flatbuffers.Builder,
   innerType: msg.Any,
   inner: flatbuffers.Offset,
   data?: ArrayBufferView
-): null | msg.Base {
+): Promise<msg.Base> {
+  return sendAsyncInternal(builder, innerType, inner, data)[1];
+}
+
+/** The promise of an async op, like a read, which `cancel()` rejects with
+ * `Interrupted` while the op is pending. Unlike closing its resource, this
+ * leaves the resource open, e.g. a connection after a read which timed out.
+ * Cancelling an op which is done does nothing.
+ */
+export interface CancellablePromise<T> extends Promise<T> {
+  cancel(): void;
+}
+
+// @internal
+/** Stops the pending async op `opId`, or the timer which started with it. */
+export function cancel(opId: number): void {
+  const builder = flatbuffers.createBuilder();
+  const inner = msg.Cancel.createCancel(builder, opId);
+  const baseRes = sendSync(builder, msg.Any.Cancel, inner);
+  util.assert(baseRes == null);
+}
+
+// @internal
+/** `sendAsync()`, resolving to `decode()` of the response, and cancellable. */
+export function sendAsyncCancellable<T>(
+  decode: (baseRes: msg.Base) => T,
+  builder: flatbuffers.Builder,
+  innerType: msg.Any,
+  inner: flatbuffers.Offset,
+  data?: ArrayBufferView
+): CancellablePromise<T> {
+  const [cmdId, promise] = sendAsyncInternal(builder, innerType, inner, data);
+  const result = promise.then(decode) as CancellablePromise<T>;
+  result.cancel = (): void => cancel(cmdId);
+  return result;
+}
+
+// @internal
+/** `sendSync()`, which also returns the id of the op. */
+export function sendSyncWithId(
+  builder: flatbuffers.Builder,
+  innerType: msg.Any,
+  inner: flatbuffers.Offset,
+  data?: ArrayBufferView
+): [number, null | msg.Base] {
   const [cmdId, response] = sendInternal(builder, innerType, inner, data, true);
   util.assert(cmdId >= 0);
   if (response == null || response.length === 0) {
-    return null;
+    return [cmdId, null];
   } else {
     const bb = new flatbuffers.ByteBuffer(response);
     const baseRes = msg.Base.getRootAsBase(bb);
     errors.maybeThrowError(baseRes);
-    return baseRes;
+    return [cmdId, baseRes];
+  }
+}
+
+// @internal
+export function sendSync(
+  builder: flatbuffers.Builder,
+  innerType: msg.Any,
+  inner: flatbuffers.Offset,
+  data?: ArrayBufferView
+): null | msg.Base {
+  return sendSyncWithId(builder, innerType, inner, data)[1];
+}
+
+// @internal
+/** A sync op of `sendSyncBatch()`. Reads are given their data back, the data
+ * of the others is only sent.
+ */
+export interface BatchOp {
+  control: Uint8Array;
+  data?: Uint8Array;
+  reads: boolean;
+}
+
+// @internal
+/** Makes the op of a batch of the message of a `req` function. */
+export function batchOp(
+  builder: flatbuffers.Builder,
+  innerType: msg.Any,
+  inner: flatbuffers.Offset,
+  data?: Uint8Array
+): BatchOp {
+  const message = msg.Base.createBase(
+    builder,
+    nextCmdId++,
+    true,
+    0,
+    0,
+    innerType,
+    inner
+  );
+  builder.finish(message);
+  // The builder is reused for the next message.
+  const control = builder.asUint8Array().slice();
+  builder.inUse = false;
+  return { control, data, reads: innerType === msg.Any.Read };
+}
+
+// @internal
+/** Sends the sync ops `ops`, for now only stats, reads and writes, with one
+ * dispatch, which saves the cost of crossing into Rust for each of them. The
+ * results are in the order of the ops, the response of each op, or the error
+ * it failed with. The data of all the ops is copied into one buffer, so this
+ * is meant for small reads and writes.
+ */
+export function sendSyncBatch(ops: BatchOp[]): Array<null | msg.Base | Error> {
+  let length = 0;
+  for (const op of ops) {
+    length += op.data ? op.data.byteLength : 0;
+  }
+  const data = new Uint8Array(length);
+  const builder = flatbuffers.createBuilder();
+  const offsets: flatbuffers.Offset[] = [];
+  let dataOffset = 0;
+  for (const op of ops) {
+    const dataLen = op.data ? op.data.byteLength : 0;
+    if (op.data && !op.reads) {
+      data.set(op.data, dataOffset);
+    }
+    const control = msg.BatchOp.createControlVector(builder, op.control);
+    offsets.push(
+      msg.BatchOp.createBatchOp(builder, control, dataOffset, dataLen)
+    );
+    dataOffset += dataLen;
+  }
+  const ops_ = msg.Batch.createOpsVector(builder, offsets);
+  const inner = msg.Batch.createBatch(builder, ops_);
+  const baseRes = sendSync(builder, msg.Any.Batch, inner, data);
+  util.assert(baseRes != null);
+  util.assert(msg.Any.BatchRes === baseRes!.innerType());
+  const res = new msg.BatchRes();
+  util.assert(baseRes!.inner(res) != null);
+  util.assert(res.resultsLength() === ops.length);
+
+  const results: Array<null | msg.Base | Error> = [];
+  dataOffset = 0;
+  for (let i = 0; i < ops.length; i++) {
+    const op = ops[i];
+    if (op.data && op.reads) {
+      op.data.set(data.subarray(dataOffset, dataOffset + op.data.byteLength));
+    }
+    dataOffset += op.data ? op.data.byteLength : 0;
+    const opRes = res.results(i)!.resArray();
+    if (opRes == null || opRes.length === 0) {
+      results.push(null);
+      continue;
+    }
+    const base = msg.Base.getRootAsBase(new flatbuffers.ByteBuffer(opRes));
+    results.push(errors.maybeError(base) || base);
   }
+  return results;
 }
@@ -0,0 +1,318 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+// The documentation of `deno doc`, taken from the declarations a module
+// exports and their JSDoc comments. The modules are only parsed, they never
+// run.
+import * as ts from "typescript";
+
+export type DocKind =
+  | "function"
+  | "class"
+  | "interface"
+  | "typeAlias"
+  | "constructor"
+  | "method"
+  | "property";
+
+export interface DocNode {
+  kind: DocKind;
+  /** The name it is exported as, "default" for the default export. */
+  name: string;
+  /** The declaration without its body, e.g. `function f(a: string): void`. */
+  signature: string;
+  /** The text of its JSDoc comment, without the asterisks. */
+  jsDoc?: string;
+  /** Where it is declared, the line starts at 1. */
+  location: { moduleId: string; line: number };
+  /** The members of a class or an interface. */
+  children?: DocNode[];
+}
+
+/** A module, parsed. */
+export interface DocModule {
+  moduleId: string;
+  fileName: string;
+  sourceFile: ts.SourceFile;
+}
+
+/** Returns the module `specifier` which `containingFile` imports. */
+export type ParseModule = (
+  specifier: string,
+  containingFile: string
+) => DocModule;
+
+type Declaration =
+  | ts.FunctionDeclaration
+  | ts.ClassDeclaration
+  | ts.InterfaceDeclaration
+  | ts.TypeAliasDeclaration;
+
+type Member = ts.ClassElement | ts.TypeElement;
+
+function isDeclaration(node: ts.Node): node is Declaration {
+  return (
+    ts.isFunctionDeclaration(node) ||
+    ts.isClassDeclaration(node) ||
+    ts.isInterfaceDeclaration(node) ||
+    ts.isTypeAliasDeclaration(node)
+  );
+}
+
+function hasModifier(node: ts.Node, kind: ts.SyntaxKind): boolean {
+  return !!node.modifiers && node.modifiers.some(m => m.kind === kind);
+}
+
+function nameOf(node: Declaration | Member): string | undefined {
+  const { name } = node;
+  if (!name) {
+    return undefined;
+  }
+  return ts.isIdentifier(name) || ts.isStringLiteral(name)
+    ? name.text
+    : name.getText();
+}
+
+/** Whether `node` is the implementation of an overloaded function or method,
+ * which is hidden behind the signatures of its overloads, as in a `.d.ts`.
+ */
+function isImplementation(
+  node: ts.Node,
+  siblings: ReadonlyArray<Declaration | Member>
+): boolean {
+  const body = (n: ts.Node): ts.Node | undefined =>
+    (n as ts.FunctionLikeDeclaration).body;
+  return (
+    !!body(node) &&
+    siblings.some(
+      s =>
+        s !== node &&
+        s.kind === node.kind &&
+        !body(s) &&
+        nameOf(s) === nameOf(node as Declaration)
+    )
+  );
+}
+
+/** The text of the last JSDoc comment before `node`. */
+function jsDocOf(node: ts.Node, sourceFile: ts.SourceFile): string | undefined {
+  const { text } = sourceFile;
+  const comments = (ts.getLeadingCommentRanges(text, node.pos) || []).filter(
+    range =>
+      range.kind === ts.SyntaxKind.MultiLineCommentTrivia &&
+      text.startsWith("/**", range.pos)
+  );
+  if (comments.length === 0) {
+    return undefined;
+  }
+  const { pos, end } = comments[comments.length - 1];
+  return text
+    .slice(pos + 3, end - 2)
+    .split("\n")
+    .map(line => line.replace(/^\s*\* ?/, ""))
+    .join("\n")
+    .trim();
+}
+
+/** The text of `node` until `end`, without the export keywords and what
+ * starts the body.
+ */
+function signatureOf(
+  node: ts.Node,
+  sourceFile: ts.SourceFile,
+  end = node.end
+): string {
+  return sourceFile.text
+    .slice(node.getStart(sourceFile), end)
+    .replace(/^export\s+(default\s+)?/, "")
+    .replace(/[\s{=;]+$/, "");
+}
+
+function documentMember(
+  member: Member,
+  module: DocModule
+): DocNode | undefined {
+  const { sourceFile } = module;
+  if (
+    hasModifier(member, ts.SyntaxKind.PrivateKeyword) ||
+    (member.name && member.name.getText(sourceFile).startsWith("#"))
+  ) {
+    return undefined;
+  }
+  let kind: DocKind;
+  let end = member.end;
+  if (ts.isConstructorDeclaration(member)) {
+    kind = "constructor";
+  } else if (ts.isMethodDeclaration(member) || ts.isMethodSignature(member)) {
+    kind = "method";
+  } else if (
+    ts.isPropertyDeclaration(member) ||
+    ts.isPropertySignature(member) ||
+    ts.isGetAccessorDeclaration(member) ||
+    ts.isSetAccessorDeclaration(member)
+  ) {
+    kind = "property";
+    if (ts.isPropertyDeclaration(member) && member.initializer) {
+      end = member.initializer.pos;
+    }
+  } else {
+    return undefined;
+  }
+  const body = (member as ts.FunctionLikeDeclaration).body;
+  if (body) {
+    end = body.getStart(sourceFile);
+  }
+  return docNode(kind, nameOf(member) || kind, member, module, end);
+}
+
+function docNode(
+  kind: DocKind,
+  name: string,
+  declaration: ts.Node,
+  module: DocModule,
+  end: number
+): DocNode {
+  const { sourceFile } = module;
+  const start = declaration.getStart(sourceFile);
+  const result: DocNode = {
+    kind,
+    name,
+    signature: signatureOf(declaration, sourceFile, end),
+    location: {
+      moduleId: module.moduleId,
+      line: sourceFile.getLineAndCharacterOfPosition(start).line + 1
+    }
+  };
+  const jsDoc = jsDocOf(declaration, sourceFile);
+  if (jsDoc) {
+    result.jsDoc = jsDoc;
+  }
+  return result;
+}
+
+/** Documents `declaration` as `name`, by default its own name. */
+function documentDeclaration(
+  declaration: Declaration,
+  module: DocModule,
+  name = nameOf(declaration) || "default"
+): DocNode | undefined {
+  const statements = module.sourceFile.statements.filter(isDeclaration);
+  if (isImplementation(declaration, statements)) {
+    return undefined;
+  }
+  const { sourceFile } = module;
+  if (ts.isFunctionDeclaration(declaration)) {
+    const end = declaration.body
+      ? declaration.body.getStart(sourceFile)
+      : declaration.end;
+    return docNode("function", name, declaration, module, end);
+  }
+  if (ts.isTypeAliasDeclaration(declaration)) {
+    return docNode("typeAlias", name, declaration, module, declaration.end);
+  }
+  const kind = ts.isClassDeclaration(declaration) ? "class" : "interface";
+  // Up to the brace which starts the members.
+  const { pos } = declaration.members;
+  const result = docNode(kind, name, declaration, module, pos);
+  const members: ReadonlyArray<Member> = declaration.members;
+  result.children = [];
+  for (const member of members) {
+    if (!isImplementation(member, members)) {
+      const child = documentMember(member, module);
+      if (child) {
+        result.children.push(child);
+      }
+    }
+  }
+  return result;
+}
+
+/** `node` exported as `name`, which its signature shows, but for the default
+ * export.
+ */
+function exportedAs(node: DocNode, name: string): DocNode {
+  if (name === node.name) {
+    return node;
+  }
+  let { signature } = node;
+  if (name !== "default") {
+    const declared = node.name.replace(/\$/g, "\\$");
+    signature = signature.replace(new RegExp(`\\b${declared}\\b`), name);
+  }
+  return { ...node, name, signature };
+}
+
+/** The documentation of what the module `specifier` exports, in the order
+ * of its statements. Re-exports are followed through the modules they come
+ * from. A module which a cycle of re-exports leads back to is walked once,
+ * the cycle adds nothing to it.
+ */
+export function documentModule(
+  parse: ParseModule,
+  specifier: string,
+  containingFile: string
+): DocNode[] {
+  // null while a module is walked.
+  const walked = new Map<string, DocNode[] | null>();
+
+  function walk(module: DocModule): DocNode[] {
+    const done = walked.get(module.fileName);
+    if (done !== undefined) {
+      return done || [];
+    }
+    walked.set(module.fileName, null);
+    const { statements } = module.sourceFile;
+    const locals = (name: string): DocNode[] => {
+      const nodes: DocNode[] = [];
+      for (const statement of statements) {
+        if (isDeclaration(statement) && nameOf(statement) === name) {
+          const doc = documentDeclaration(statement, module);
+          if (doc) {
+            nodes.push(doc);
+          }
+        }
+      }
+      return nodes;
+    };
+    const nodes: DocNode[] = [];
+    for (const statement of statements) {
+      if (
+        isDeclaration(statement) &&
+        hasModifier(statement, ts.SyntaxKind.ExportKeyword)
+      ) {
+        const name = hasModifier(statement, ts.SyntaxKind.DefaultKeyword)
+          ? "default"
+          : undefined;
+        const doc = documentDeclaration(statement, module, name);
+        if (doc) {
+          nodes.push(doc);
+        }
+      } else if (ts.isExportDeclaration(statement)) {
+        const { exportClause, moduleSpecifier } = statement;
+        const from =
+          moduleSpecifier && ts.isStringLiteral(moduleSpecifier)
+            ? walk(parse(moduleSpecifier.text, module.fileName))
+            : undefined;
+        if (!exportClause) {
+          // `export *` leaves out the default export.
+          nodes.push(...from!.filter(n => n.name !== "default"));
+          continue;
+        }
+        for (const element of exportClause.elements) {
+          const name = (element.propertyName || element.name).text;
+          const found = from ? from.filter(n => n.name === name) : locals(name);
+          nodes.push(...found.map(n => exportedAs(n, element.name.text)));
+        }
+      } else if (
+        ts.isExportAssignment(statement) &&
+        !statement.isExportEquals &&
+        ts.isIdentifier(statement.expression)
+      ) {
+        const found = locals(statement.expression.text);
+        nodes.push(...found.map(n => exportedAs(n, "default")));
+      }
+    }
+    walked.set(module.fileName, nodes);
+    return nodes;
+  }
+
+  return walk(parse(specifier, containingFile));
+}
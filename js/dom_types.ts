@@ -30,7 +30,15 @@ type BodyInit =
   | FormData
   | URLSearchParams
   | ReadableStream
+  | ResourceBody
   | string;
+
+/** A resource which is streamed as the body of a request, like a `Deno.File`,
+ * a `Deno.Conn` or the body of another response. Not part of the standard.
+ */
+export interface ResourceBody {
+  readonly rid: number;
+}
 export type RequestInfo = Request | string;
 type ReferrerPolicy =
   | ""
@@ -421,6 +429,14 @@ export interface RequestInit {
   referrerPolicy?: ReferrerPolicy;
   signal?: AbortSignal | null;
   window?: any;
+  /** Milliseconds after which the request is aborted, if the headers of the
+   * response are not there by then. Not part of the standard.
+   */
+  timeout?: number;
+  /** How many redirects are followed, 20 by default. Not part of the
+   * standard.
+   */
+  maxRedirects?: number;
 }
 
 export interface ResponseInit {
@@ -0,0 +1,119 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import * as msg from "gen/cli/msg_generated";
+import * as flatbuffers from "./flatbuffers";
+import { sendSync } from "./dispatch";
+import { assert } from "./util";
+
+/** A frame of a stack trace as V8 passes it to `Error.prepareStackTrace`. */
+export interface CallSite {
+  getTypeName(): string | null;
+  getFunctionName(): string | null;
+  getMethodName(): string | null;
+  getFileName(): string | null;
+  getLineNumber(): number | null;
+  getColumnNumber(): number | null;
+  getEvalOrigin(): string | undefined;
+  isToplevel(): boolean;
+  isEval(): boolean;
+  isNative(): boolean;
+  isConstructor(): boolean;
+}
+
+interface ErrorConstructorV8 extends ErrorConstructor {
+  prepareStackTrace?: (error: Error, callSites: CallSite[]) => string;
+}
+
+export interface Location {
+  filename: string;
+  /** One indexed, like V8's. */
+  line: number;
+  /** One indexed, like V8's. */
+  column: number;
+}
+
+/** Returns the position in the original source of a position in compiled
+ * code, or the position itself if there is no source map for it.
+ */
+export function applySourceMap(location: Location): Location {
+  const { filename, line, column } = location;
+  const builder = flatbuffers.createBuilder();
+  const filename_ = builder.createString(filename);
+  const inner = msg.ApplySourceMap.createApplySourceMap(
+    builder,
+    filename_,
+    line - 1,
+    column - 1
+  );
+  const baseRes = sendSync(builder, msg.Any.ApplySourceMap, inner);
+  assert(baseRes != null);
+  assert(msg.Any.ApplySourceMap === baseRes!.innerType());
+  const res = new msg.ApplySourceMap();
+  assert(baseRes!.inner(res) != null);
+  return {
+    filename: res.filename()!,
+    line: res.line() + 1,
+    column: res.column() + 1
+  };
+}
+
+function getLocation(callSite: CallSite): string {
+  if (callSite.isNative()) {
+    return "native";
+  }
+  const fileName = callSite.getFileName();
+  const lineNumber = callSite.getLineNumber();
+  const columnNumber = callSite.getColumnNumber();
+  let location = "";
+  if (!fileName && callSite.isEval()) {
+    location = `${callSite.getEvalOrigin()}, `;
+  }
+  if (!fileName || lineNumber == null || columnNumber == null) {
+    return `${location}${fileName || "<anonymous>"}`;
+  }
+  const { filename, line, column } = applySourceMap({
+    filename: fileName,
+    line: lineNumber,
+    column: columnNumber
+  });
+  return `${location}${filename}:${line}:${column}`;
+}
+
+/** Formats a frame like V8 does, at the original position. */
+function formatCallSite(callSite: CallSite): string {
+  const location = getLocation(callSite);
+  const functionName = callSite.getFunctionName();
+  if (callSite.isConstructor()) {
+    return `new ${functionName || "<anonymous>"} (${location})`;
+  }
+  if (!callSite.isToplevel()) {
+    const typeName = callSite.getTypeName();
+    const methodName = callSite.getMethodName();
+    let name = functionName || methodName || "<anonymous>";
+    if (typeName && !(functionName || "").startsWith(typeName)) {
+      name = `${typeName}.${name}`;
+    }
+    if (functionName && methodName && !functionName.endsWith(methodName)) {
+      name += ` [as ${methodName}]`;
+    }
+    return `${name} (${location})`;
+  }
+  if (functionName) {
+    return `${functionName} (${location})`;
+  }
+  return location;
+}
+
+/** Used by V8 to build `error.stack`, the frames of which are source mapped
+ * this way.
+ */
+export function prepareStackTrace(error: Error, callSites: CallSite[]): string {
+  const header = error.message ? `${error.name}: ${error.message}` : error.name;
+  return callSites
+    .map((callSite): string => `\n    at ${formatCallSite(callSite)}`)
+    .reduce((stack, frame): string => stack + frame, header);
+}
+
+/** Source maps the `stack` of errors. */
+export function setPrepareStackTrace(): void {
+  (Error as ErrorConstructorV8).prepareStackTrace = prepareStackTrace;
+}
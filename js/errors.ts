@@ -3,7 +3,8 @@ import { Base, ErrorKind } from "gen/cli/msg_generated";
 export { ErrorKind } from "gen/cli/msg_generated";
 
 /** A Deno specific error.  The `kind` property is set to a specific error code
- * which can be used to in application logic.
+ * which can be used to in application logic. The errors of the common kinds
+ * are also instances of the classes of `Deno.errors`.
  *
  *       try {
  *         somethingThatMightThrow();
@@ -24,14 +25,164 @@ export class DenoError<T extends ErrorKind> extends Error {
   }
 }
 
+export class NotFound extends DenoError<ErrorKind.NotFound> {
+  constructor(msg: string) {
+    super(ErrorKind.NotFound, msg);
+  }
+}
+
+export class PermissionDenied extends DenoError<ErrorKind.PermissionDenied> {
+  constructor(msg: string) {
+    super(ErrorKind.PermissionDenied, msg);
+  }
+}
+
+export class ConnectionRefused extends DenoError<ErrorKind.ConnectionRefused> {
+  constructor(msg: string) {
+    super(ErrorKind.ConnectionRefused, msg);
+  }
+}
+
+export class ConnectionReset extends DenoError<ErrorKind.ConnectionReset> {
+  constructor(msg: string) {
+    super(ErrorKind.ConnectionReset, msg);
+  }
+}
+
+export class ConnectionAborted extends DenoError<ErrorKind.ConnectionAborted> {
+  constructor(msg: string) {
+    super(ErrorKind.ConnectionAborted, msg);
+  }
+}
+
+export class NotConnected extends DenoError<ErrorKind.NotConnected> {
+  constructor(msg: string) {
+    super(ErrorKind.NotConnected, msg);
+  }
+}
+
+export class AddrInUse extends DenoError<ErrorKind.AddrInUse> {
+  constructor(msg: string) {
+    super(ErrorKind.AddrInUse, msg);
+  }
+}
+
+export class AddrNotAvailable extends DenoError<ErrorKind.AddrNotAvailable> {
+  constructor(msg: string) {
+    super(ErrorKind.AddrNotAvailable, msg);
+  }
+}
+
+export class BrokenPipe extends DenoError<ErrorKind.BrokenPipe> {
+  constructor(msg: string) {
+    super(ErrorKind.BrokenPipe, msg);
+  }
+}
+
+export class AlreadyExists extends DenoError<ErrorKind.AlreadyExists> {
+  constructor(msg: string) {
+    super(ErrorKind.AlreadyExists, msg);
+  }
+}
+
+export class WouldBlock extends DenoError<ErrorKind.WouldBlock> {
+  constructor(msg: string) {
+    super(ErrorKind.WouldBlock, msg);
+  }
+}
+
+export class InvalidInput extends DenoError<ErrorKind.InvalidInput> {
+  constructor(msg: string) {
+    super(ErrorKind.InvalidInput, msg);
+  }
+}
+
+export class InvalidData extends DenoError<ErrorKind.InvalidData> {
+  constructor(msg: string) {
+    super(ErrorKind.InvalidData, msg);
+  }
+}
+
+export class TimedOut extends DenoError<ErrorKind.TimedOut> {
+  constructor(msg: string) {
+    super(ErrorKind.TimedOut, msg);
+  }
+}
+
+export class Interrupted extends DenoError<ErrorKind.Interrupted> {
+  constructor(msg: string) {
+    super(ErrorKind.Interrupted, msg);
+  }
+}
+
+export class WriteZero extends DenoError<ErrorKind.WriteZero> {
+  constructor(msg: string) {
+    super(ErrorKind.WriteZero, msg);
+  }
+}
+
+export class UnexpectedEof extends DenoError<ErrorKind.UnexpectedEof> {
+  constructor(msg: string) {
+    super(ErrorKind.UnexpectedEof, msg);
+  }
+}
+
+export class BadResource extends DenoError<ErrorKind.BadResource> {
+  constructor(msg: string) {
+    super(ErrorKind.BadResource, msg);
+  }
+}
+
+/** The classes of the errors of the kinds scripts most often tell apart,
+ * from the io, network and permission failures of ops. The errors of the
+ * other kinds are thrown as plain `DenoError`s.
+ *
+ *       try {
+ *         await Deno.open("missing.txt");
+ *       } catch (e) {
+ *         if (e instanceof Deno.errors.NotFound) {
+ *           console.error("No such file");
+ *         }
+ *       }
+ *
+ */
+export const errors = {
+  NotFound,
+  PermissionDenied,
+  ConnectionRefused,
+  ConnectionReset,
+  ConnectionAborted,
+  NotConnected,
+  AddrInUse,
+  AddrNotAvailable,
+  BrokenPipe,
+  AlreadyExists,
+  WouldBlock,
+  InvalidInput,
+  InvalidData,
+  TimedOut,
+  Interrupted,
+  WriteZero,
+  UnexpectedEof,
+  BadResource
+};
+
+type ErrorClass = new (msg: string) => DenoError<ErrorKind>;
+
 // @internal
 export function maybeError(base: Base): null | DenoError<ErrorKind> {
   const kind = base.errorKind();
   if (kind === ErrorKind.NoError) {
     return null;
-  } else {
-    return new DenoError(kind, base.error()!);
   }
+  // The classes are named after their kinds.
+  const errorClass = (errors as Record<string, ErrorClass | undefined>)[
+    ErrorKind[kind]
+  ];
+  if (errorClass) {
+    return new errorClass(base.error()!);
+  }
+  return new DenoError(kind, base.error()!);
 }
 
 // @internal
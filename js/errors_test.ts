@@ -0,0 +1,106 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import { test, testPerm, assert, assertEquals } from "./test_util.ts";
+
+test(function errorKindCodes() {
+  // Scripts may depend on the values, they don't change.
+  assertEquals(Deno.ErrorKind.NoError, 0);
+  assertEquals(Deno.ErrorKind.NotFound, 1);
+  assertEquals(Deno.ErrorKind.PermissionDenied, 2);
+  assertEquals(Deno.ErrorKind.ConnectionRefused, 3);
+  assertEquals(Deno.ErrorKind.BadResource, 19);
+  assertEquals(Deno.ErrorKind.RedirectNotAllowed, 43);
+});
+
+test(function errorClasses() {
+  const err = new Deno.errors.NotFound("gone");
+  assert(err instanceof Deno.errors.NotFound);
+  assert(err instanceof Deno.DenoError);
+  assert(err instanceof Error);
+  assertEquals(err.kind, Deno.ErrorKind.NotFound);
+  assertEquals(err.name, "NotFound");
+  assertEquals(err.message, "gone");
+  for (const name of Object.keys(Deno.errors)) {
+    // Each is named after its kind.
+    const errorClass = Deno.errors[name as keyof typeof Deno.errors];
+    assertEquals(new errorClass("").name, name);
+  }
+});
+
+testPerm({ read: true }, function errorsNotFoundSync() {
+  let err;
+  try {
+    Deno.openSync("this/file/does/not/exist.txt");
+  } catch (e) {
+    err = e;
+  }
+  assert(err instanceof Deno.errors.NotFound);
+  assert(!(err instanceof Deno.errors.PermissionDenied));
+  assertEquals(err.kind, Deno.ErrorKind.NotFound);
+});
+
+testPerm({ read: true }, async function errorsNotFound() {
+  let err;
+  try {
+    await Deno.stat("this/file/does/not/exist.txt");
+  } catch (e) {
+    err = e;
+  }
+  assert(err instanceof Deno.errors.NotFound);
+});
+
+testPerm({ read: false }, function errorsPermissionDenied() {
+  let err;
+  try {
+    Deno.readFileSync("package.json");
+  } catch (e) {
+    err = e;
+  }
+  assert(err instanceof Deno.errors.PermissionDenied);
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+});
+
+testPerm({ net: true }, async function errorsConnectionRefused() {
+  let err;
+  try {
+    // Nothing listens on the port.
+    await Deno.dial("tcp", "127.0.0.1:4520");
+  } catch (e) {
+    err = e;
+  }
+  assert(err instanceof Deno.errors.ConnectionRefused);
+  assertEquals(err.kind, Deno.ErrorKind.ConnectionRefused);
+});
+
+testPerm({ net: true }, function errorsAddrInUse() {
+  const listener = Deno.listen("tcp", "127.0.0.1:4521");
+  let err;
+  try {
+    Deno.listen("tcp", "127.0.0.1:4521");
+  } catch (e) {
+    err = e;
+  }
+  listener.close();
+  assert(err instanceof Deno.errors.AddrInUse);
+});
+
+test(function errorsBadResource() {
+  let err;
+  try {
+    Deno.close(1000000);
+  } catch (e) {
+    err = e;
+  }
+  assert(err instanceof Deno.errors.BadResource);
+});
+
+testPerm({ read: true }, function errorsOtherKinds() {
+  let err;
+  try {
+    Deno.readDirSync("package.json");
+  } catch (e) {
+    err = e;
+  }
+  // Not a kind with a class of its own.
+  assert(err instanceof Deno.DenoError);
+  assertEquals(err.constructor, Deno.DenoError);
+});
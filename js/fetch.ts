@@ -1,7 +1,7 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
 import { assert, createResolvable, notImplemented, isTypedArray } from "./util";
 import * as flatbuffers from "./flatbuffers";
-import { sendAsync } from "./dispatch";
+import { sendAsyncCancellable, CancellablePromise } from "./dispatch";
 import * as msg from "gen/cli/msg_generated";
 import * as domTypes from "./dom_types";
 import { TextDecoder, TextEncoder } from "./text_encoding";
@@ -9,7 +9,6 @@ import { DenoBlob, bytesSymbol as blobBytesSymbol } from "./blob";
 import { Headers } from "./headers";
 import * as io from "./io";
 import { read, close } from "./files";
-import { Buffer } from "./buffer";
 import { FormData } from "./form_data";
 import { URLSearchParams } from "./url_search_params";
 
@@ -30,6 +29,11 @@ function hasHeaderValueOf(s: string, value: string): boolean {
   return new RegExp(`^${value}[\t\s]*;?`).test(s);
 }
 
+/** The size of the reads of a body without a Content-Length. */
+const BODY_CHUNK_SIZE = 16 * 1024;
+/** A larger Content-Length isn't allocated up front, the body may be cut. */
+const MAX_EXPECTED_BODY_SIZE = 64 * 1024 * 1024;
+
 class Body implements domTypes.Body, domTypes.ReadableStream, io.ReadCloser {
   bodyUsed = false;
   private _bodyPromise: null | Promise<ArrayBuffer> = null;
@@ -37,24 +41,45 @@ class Body implements domTypes.Body, domTypes.ReadableStream, io.ReadCloser {
   readonly locked: boolean = false; // TODO
   readonly body: null | Body = this;
 
-  constructor(private rid: number, readonly contentType: string) {}
-
+  /** `contentLength` is the Content-Length of the response, if it has one,
+   * which is taken only as the size to expect.
+   */
+  constructor(
+    readonly rid: number,
+    readonly contentType: string,
+    private readonly contentLength: number | null = null
+  ) {}
+
+  /** Reads the body straight into the ArrayBuffer it resolves to, which is
+   * copied again only if the body is longer or shorter than expected.
+   */
   private async _bodyBuffer(): Promise<ArrayBuffer> {
     assert(this._bodyPromise == null);
-    const buf = new Buffer();
+    const expected = Math.min(this.contentLength || 0, MAX_EXPECTED_BODY_SIZE);
+    let buf = new Uint8Array(expected || BODY_CHUNK_SIZE);
+    let nread = 0;
     try {
-      const nread = await buf.readFrom(this);
-      const ui8 = buf.bytes();
-      assert(ui8.byteLength === nread);
-      this._data = ui8.buffer.slice(
-        ui8.byteOffset,
-        ui8.byteOffset + nread
-      ) as ArrayBuffer;
-      assert(this._data.byteLength === nread);
+      // Tells the end of a body which fills `buf` without growing it.
+      const spare = new Uint8Array(BODY_CHUNK_SIZE);
+      while (true) {
+        const full = nread === buf.byteLength;
+        const result = await this.read(full ? spare : buf.subarray(nread));
+        if (result.eof) {
+          break;
+        }
+        if (full) {
+          const grown = new Uint8Array(buf.byteLength * 2 + result.nread);
+          grown.set(buf);
+          grown.set(spare.subarray(0, result.nread), nread);
+          buf = grown;
+        }
+        nread += result.nread;
+      }
     } finally {
       this.close();
     }
-
+    this._data =
+      nread === buf.byteLength ? buf.buffer : buf.buffer.slice(0, nread);
     return this._data;
   }
 
@@ -216,7 +241,11 @@ class Body implements domTypes.Body, domTypes.ReadableStream, io.ReadCloser {
     return decoder.decode(ab);
   }
 
-  read(p: Uint8Array): Promise<io.ReadResult> {
+  /** Reads the next chunk of the body into `p`. `cancel()` of the promise
+   * stops a pending read, which rejects with `Interrupted`, and leaves the
+   * body open, while `cancel()` of the body closes it.
+   */
+  read(p: Uint8Array): CancellablePromise<io.ReadResult> {
     return read(this.rid, p);
   }
 
@@ -224,8 +253,11 @@ class Body implements domTypes.Body, domTypes.ReadableStream, io.ReadCloser {
     close(this.rid);
   }
 
+  /** Closes the body, which aborts its connection. Pending reads reject with
+   * `BadResource`.
+   */
   async cancel(): Promise<void> {
-    return notImplemented();
+    this.close();
   }
 
   getReader(): domTypes.ReadableStreamReader {
@@ -234,10 +266,8 @@ class Body implements domTypes.Body, domTypes.ReadableStream, io.ReadCloser {
 }
 
 class Response implements domTypes.Response {
-  readonly url: string = "";
   statusText = "FIXME"; // TODO
   readonly type = "basic"; // TODO
-  redirected = false; // TODO
   headers: domTypes.Headers;
   readonly trailer: Promise<domTypes.Headers>;
   bodyUsed = false;
@@ -247,14 +277,23 @@ class Response implements domTypes.Response {
     readonly status: number,
     headersList: Array<[string, string]>,
     rid: number,
-    body_: null | Body = null
+    body_: null | Body = null,
+    readonly url: string = "",
+    readonly redirected = false
   ) {
     this.trailer = createResolvable();
     this.headers = new Headers(headersList);
     const contentType = this.headers.get("content-type") || "";
+    const contentLength = Number(this.headers.get("content-length"));
 
     if (body_ == null) {
-      this.body = new Body(rid, contentType);
+      this.body = new Body(
+        rid,
+        contentType,
+        Number.isSafeInteger(contentLength) && contentLength > 0
+          ? contentLength
+          : null
+      );
     } else {
       this.body = body_;
     }
@@ -298,7 +337,14 @@ class Response implements domTypes.Response {
       headersList.push(header);
     }
 
-    return new Response(this.status, headersList, -1, this.body);
+    return new Response(
+      this.status,
+      headersList,
+      -1,
+      this.body,
+      this.url,
+      this.redirected
+    );
   }
 }
 
@@ -342,20 +388,43 @@ function deserializeHeaderFields(m: msg.HttpHeader): Array<[string, string]> {
   return out;
 }
 
-/** Fetch a resource from the network. */
-export async function fetch(
+function isResourceBody(body: unknown): body is domTypes.ResourceBody {
+  return typeof (body as domTypes.ResourceBody).rid === "number";
+}
+
+function reqFetch(
   input: domTypes.Request | string,
   init?: domTypes.RequestInit
-): Promise<Response> {
+): [
+  flatbuffers.Builder,
+  msg.Any,
+  flatbuffers.Offset,
+  ArrayBufferView | undefined
+] {
   let url: string;
   let method: string | null = null;
   let headers: domTypes.Headers | null = null;
   let body: ArrayBufferView | undefined;
+  let bodyRid: number | undefined;
+  let redirect = msg.FetchRedirect.Follow;
+  let maxRedirects = -1;
+  let timeout = 0;
+  let cookies = false;
 
   if (typeof input === "string") {
     url = input;
     if (init != null) {
       method = init.method || null;
+      if (init.redirect === "manual") {
+        redirect = msg.FetchRedirect.Manual;
+      } else if (init.redirect === "error") {
+        redirect = msg.FetchRedirect.Error;
+      }
+      if (init.maxRedirects !== undefined) {
+        maxRedirects = init.maxRedirects;
+      }
+      timeout = init.timeout || 0;
+      cookies = init.credentials === "include";
       if (init.headers) {
         headers =
           init.headers instanceof Headers
@@ -384,6 +453,8 @@ export async function fetch(
         } else if (init.body instanceof DenoBlob) {
           body = init.body[blobBytesSymbol];
           contentType = init.body.type;
+        } else if (isResourceBody(init.body)) {
+          bodyRid = init.body.rid;
         } else {
           // TODO: FormData, ReadableStream
           notImplemented();
@@ -399,28 +470,73 @@ export async function fetch(
     headers = input.headers;
   }
 
-  // Send Fetch message
   const builder = flatbuffers.createBuilder();
   const headerOff = msgHttpRequest(builder, url, method, headers);
-  const resBase = await sendAsync(
+  const inner = msg.Fetch.createFetch(
     builder,
-    msg.Any.Fetch,
-    msg.Fetch.createFetch(builder, headerOff),
-    body
+    headerOff,
+    bodyRid !== undefined,
+    bodyRid || 0,
+    redirect,
+    maxRedirects,
+    timeout,
+    cookies
   );
+  return [builder, msg.Any.Fetch, inner, body];
+}
 
-  // Decode FetchRes
+function resFetch(resBase: msg.Base): Response {
   assert(msg.Any.FetchRes === resBase.innerType());
   const inner = new msg.FetchRes();
   assert(resBase.inner(inner) != null);
 
   const header = inner.header()!;
-  const bodyRid = inner.bodyRid();
   assert(!header.isRequest());
   const status = header.status();
 
   const headersList = deserializeHeaderFields(header);
 
-  const response = new Response(status, headersList, bodyRid);
+  const response = new Response(
+    status,
+    headersList,
+    inner.bodyRid(),
+    null,
+    inner.url()!,
+    inner.redirected()
+  );
   return response;
 }
+
+/** Fetch a resource from the network. It resolves as soon as the headers of
+ * the response are there, the body is read chunk by chunk with
+ * `response.body.read()`, and only buffered by methods like `text()`.
+ *
+ * Up to `maxRedirects` redirects are followed, each to a host which
+ * `--allow-net` allows. With `redirect: "manual"` the redirect is the
+ * response, with its `location` header, and with `redirect: "error"` it
+ * rejects with `RedirectNotAllowed`. A request with a `timeout` rejects with
+ * `TimedOut` unless the headers arrive in time.
+ *
+ * Requests with `credentials: "include"` send the cookies which responses to
+ * such requests set, they are kept until the program ends.
+ *
+ * A request body with a `rid`, like a `Deno.File`, is streamed from that
+ * resource without reading it into memory, with chunked transfer encoding
+ * unless there is a `content-length` header. The resource is not closed.
+ *
+ *       const file = await Deno.open("upload.bin");
+ *       await fetch("http://localhost:8000/upload", {
+ *         method: "POST",
+ *         body: file
+ *       });
+ *       file.close();
+ *
+ * `cancel()` of the promise aborts the request while it waits for the
+ * headers, and rejects it with `Interrupted`.
+ */
+export function fetch(
+  input: domTypes.Request | string,
+  init?: domTypes.RequestInit
+): CancellablePromise<Response> {
+  return sendAsyncCancellable(resFetch, ...reqFetch(input, init));
+}
@@ -33,6 +33,31 @@ testPerm({ net: true }, async function fetchBlob() {
   assertEquals(blob.size, Number(headers.get("Content-Length")));
 });
 
+testPerm({ net: true, read: true }, async function fetchArrayBuffer() {
+  const response = await fetch("http://localhost:4545/package.json");
+  const length = Number(response.headers.get("Content-Length"));
+  const ab = await response.arrayBuffer();
+  assertEquals(ab.byteLength, length);
+  assertEquals(new Uint8Array(ab), Deno.readFileSync("package.json"));
+});
+
+testPerm({ net: true }, async function fetchArrayBufferNoContentLength() {
+  // The echo has no Content-Length, so its body is read in chunks into a
+  // growing buffer.
+  const data = new Uint8Array(100000);
+  for (let i = 0; i < data.length; i++) {
+    data[i] = i % 251;
+  }
+  const response = await fetch("http://localhost:4545/echo_server", {
+    method: "POST",
+    body: data
+  });
+  assertEquals(response.headers.get("Content-Length"), null);
+  const ab = await response.arrayBuffer();
+  assertEquals(ab.byteLength, data.length);
+  assertEquals(new Uint8Array(ab), data);
+});
+
 testPerm({ net: true }, async function responseClone() {
   const response = await fetch("http://localhost:4545/package.json");
   const response1 = response.clone();
@@ -134,6 +159,182 @@ testPerm({ net: true }, async function fetchInitBlobBody() {
   assert(response.headers.get("content-type").startsWith("text/javascript"));
 });
 
+testPerm({ read: true, net: true }, async function fetchInitFileBody() {
+  const data = Deno.readFileSync("tests/hello.txt");
+  const file = await Deno.open("tests/hello.txt");
+  const response = await fetch("http://localhost:4545/echo_server", {
+    method: "POST",
+    headers: [["content-length", String(data.byteLength)]],
+    body: file
+  });
+  file.close();
+  const body = new Uint8Array(await response.arrayBuffer());
+  assertEquals(body, data);
+});
+
+testPerm({ net: true }, async function fetchBodyCancel() {
+  // Sends the headers of the response, but not its body.
+  const listener = Deno.listen("tcp", "127.0.0.1:4510");
+  const aborted = listener.accept().then(async conn => {
+    const buf = new Uint8Array(1024);
+    await conn.read(buf);
+    await conn.write(
+      new TextEncoder().encode("HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\n")
+    );
+    let eof = false;
+    while (!eof) {
+      eof = (await conn.read(buf)).eof;
+    }
+    conn.close();
+  });
+  const response = await fetch("http://127.0.0.1:4510/");
+  assertEquals(response.headers.get("content-length"), "5");
+  const pending = response.body.read(new Uint8Array(5));
+  await response.body.cancel();
+  let err;
+  try {
+    await pending;
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.BadResource);
+  // Closing the body closed the connection.
+  await aborted;
+  listener.close();
+});
+
+testPerm({ net: true }, async function fetchCancel() {
+  // Responds to the second request only.
+  const listener = Deno.listen("tcp", "127.0.0.1:4522");
+  const first = listener.accept();
+  const pending = fetch("http://127.0.0.1:4522/");
+  const conn = await first;
+  pending.cancel();
+  let err;
+  try {
+    await pending;
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.Interrupted);
+  conn.close();
+
+  const second = listener.accept();
+  const responded = fetch("http://127.0.0.1:4522/");
+  const server = await second;
+  const buf = new Uint8Array(1024);
+  await server.read(buf);
+  await server.write(
+    new TextEncoder().encode("HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\n")
+  );
+  const response = await responded;
+  // A read of the body is cancelled, the body stays open.
+  const body = new Uint8Array(5);
+  const read = response.body.read(body);
+  read.cancel();
+  err = undefined;
+  try {
+    await read;
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.Interrupted);
+  await server.write(new TextEncoder().encode("hello"));
+  assertEquals(await response.text(), "hello");
+  server.close();
+  listener.close();
+});
+
+testPerm({ net: true }, async function fetchRedirectFollow() {
+  const response = await fetch("http://localhost:4546/tests/hello.txt");
+  assertEquals(response.status, 200);
+  assert(response.redirected);
+  assertEquals(response.url, "http://localhost:4545/tests/hello.txt");
+  assertEquals(await response.text(), "Hello world!");
+});
+
+testPerm({ net: true }, async function fetchNotRedirected() {
+  const response = await fetch("http://localhost:4545/tests/hello.txt");
+  assert(!response.redirected);
+  assertEquals(response.url, "http://localhost:4545/tests/hello.txt");
+  await response.text();
+});
+
+testPerm({ net: true }, async function fetchRedirectManual() {
+  const response = await fetch("http://localhost:4546/tests/hello.txt", {
+    redirect: "manual"
+  });
+  assertEquals(response.status, 301);
+  assert(!response.redirected);
+  assertEquals(
+    response.headers.get("location"),
+    "http://localhost:4545/tests/hello.txt"
+  );
+  await response.text();
+});
+
+testPerm({ net: true }, async function fetchRedirectError() {
+  let err;
+  try {
+    await fetch("http://localhost:4546/tests/hello.txt", {
+      redirect: "error"
+    });
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.RedirectNotAllowed);
+  assertEquals(err.name, "RedirectNotAllowed");
+});
+
+testPerm({ net: true }, async function fetchTooManyRedirects() {
+  let err;
+  try {
+    await fetch("http://localhost:4545/redirect_loop/a.js");
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.TooManyRedirects);
+
+  err = undefined;
+  try {
+    await fetch("http://localhost:4546/tests/hello.txt", { maxRedirects: 0 });
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.TooManyRedirects);
+});
+
+testPerm({ net: true }, async function fetchTimeout() {
+  // Accepts the connection, but never responds.
+  const listener = Deno.listen("tcp", "127.0.0.1:4511");
+  const accepted = listener.accept();
+  let err;
+  try {
+    await fetch("http://127.0.0.1:4511/", { timeout: 100 });
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.TimedOut);
+  (await accepted).close();
+  listener.close();
+});
+
+testPerm({ net: true }, async function fetchCookies() {
+  // Only kept for requests with credentials.
+  await (await fetch("http://localhost:4545/cookies/set")).text();
+  let response = await fetch("http://localhost:4545/cookies/echo", {
+    credentials: "include"
+  });
+  assertEquals(await response.text(), "");
+
+  const init: RequestInit = { credentials: "include" };
+  await (await fetch("http://localhost:4545/cookies/set", init)).text();
+  response = await fetch("http://localhost:4545/cookies/echo", init);
+  assertEquals(await response.text(), "deno_test=1");
+  response = await fetch("http://localhost:4545/cookies/echo");
+  assertEquals(await response.text(), "");
+});
+
 // TODO(ry) The following tests work but are flaky. There's a race condition
 // somewhere. Here is what one of these flaky failures looks like:
 //
@@ -11,18 +11,35 @@ import {
   SyncSeeker
 } from "./io";
 import * as dispatch from "./dispatch";
+import { CancellablePromise } from "./dispatch";
 import * as msg from "gen/cli/msg_generated";
 import { assert } from "./util";
 import * as flatbuffers from "./flatbuffers";
 
 function reqOpen(
   filename: string,
-  mode: OpenMode
+  mode: OpenMode | OpenOptions
 ): [flatbuffers.Builder, msg.Any, flatbuffers.Offset] {
   const builder = flatbuffers.createBuilder();
   const filename_ = builder.createString(filename);
-  const mode_ = builder.createString(mode);
-  const inner = msg.Open.createOpen(builder, filename_, 0, mode_);
+  let inner: flatbuffers.Offset;
+  if (typeof mode === "string") {
+    const mode_ = builder.createString(mode);
+    msg.Open.startOpen(builder);
+    msg.Open.addFilename(builder, filename_);
+    msg.Open.addMode(builder, mode_);
+    inner = msg.Open.endOpen(builder);
+  } else {
+    msg.Open.startOpen(builder);
+    msg.Open.addFilename(builder, filename_);
+    msg.Open.addRead(builder, !!mode.read);
+    msg.Open.addWrite(builder, !!mode.write);
+    msg.Open.addAppend(builder, !!mode.append);
+    msg.Open.addTruncate(builder, !!mode.truncate);
+    msg.Open.addCreate(builder, !!mode.create);
+    msg.Open.addCreateNew(builder, !!mode.createNew);
+    inner = msg.Open.endOpen(builder);
+  }
   return [builder, msg.Any.Open, inner];
 }
 
@@ -40,8 +57,15 @@ function resOpen(baseRes: null | msg.Base): File {
  *  synchronously.
  *
  *       const file = Deno.openSync("/foo/bar.txt");
+ *       const log = Deno.openSync("log.txt", { append: true, create: true });
+ *
+ * Opening for reading requires the `--allow-read` flag, for writing or
+ * appending the `--allow-write` flag.
  */
-export function openSync(filename: string, mode: OpenMode = "r"): File {
+export function openSync(
+  filename: string,
+  mode: OpenMode | OpenOptions = "r"
+): File {
   return resOpen(dispatch.sendSync(...reqOpen(filename, mode)));
 }
 
@@ -50,10 +74,13 @@ export function openSync(filename: string, mode: OpenMode = "r"): File {
  *       (async () => {
  *         const file = await Deno.open("/foo/bar.txt");
  *       })();
+ *
+ * Opening for reading requires the `--allow-read` flag, for writing or
+ * appending the `--allow-write` flag.
  */
 export async function open(
   filename: string,
-  mode: OpenMode = "r"
+  mode: OpenMode | OpenOptions = "r"
 ): Promise<File> {
   return resOpen(await dispatch.sendAsync(...reqOpen(filename, mode)));
 }
@@ -91,7 +118,9 @@ export function readSync(rid: number, p: Uint8Array): ReadResult {
 
 /** Read from a file ID into an array buffer.
  *
- * Resolves with the `ReadResult` for the operation.
+ * Resolves with the `ReadResult` for the operation. The data is read straight
+ * into `p`, which should not be used otherwise until then. `cancel()` stops a
+ * pending read, which rejects with `Interrupted`, and leaves the file open.
  *
  *       (async () => {
  *         const file = await Deno.open("/foo/bar.txt");
@@ -100,8 +129,11 @@ export function readSync(rid: number, p: Uint8Array): ReadResult {
  *         const text = new TextDecoder().decode(buf);
  *       })();
  */
-export async function read(rid: number, p: Uint8Array): Promise<ReadResult> {
-  return resRead(await dispatch.sendAsync(...reqRead(rid, p)));
+export function read(
+  rid: number,
+  p: Uint8Array
+): CancellablePromise<ReadResult> {
+  return dispatch.sendAsyncCancellable(resRead, ...reqRead(rid, p));
 }
 
 function reqWrite(
@@ -136,7 +168,8 @@ export function writeSync(rid: number, p: Uint8Array): number {
 
 /** Write to the file ID the contents of the array buffer.
  *
- * Resolves with the number of bytes written.
+ * Resolves with the number of bytes written. The data is written straight
+ * from `p`, changes to it before then may be written too.
  *
  *      (async () => {
  *        const encoder = new TextEncoder();
@@ -150,13 +183,46 @@ export async function write(rid: number, p: Uint8Array): Promise<number> {
   return resWrite(await dispatch.sendAsync(...reqWrite(rid, p)));
 }
 
+// @internal
+/** Does the reads of `reads`, each of a file ID into its buffer, with one
+ * dispatch, see `sendSyncBatch()`. Each result is the `ReadResult` of the
+ * read or the error it failed with.
+ */
+export function readSyncBatch(
+  reads: Array<[number, Uint8Array]>
+): Array<ReadResult | Error> {
+  const ops = reads.map(([rid, p]) => dispatch.batchOp(...reqRead(rid, p)));
+  return dispatch
+    .sendSyncBatch(ops)
+    .map(result => (result instanceof Error ? result : resRead(result)));
+}
+
+// @internal
+/** Does the writes of `writes` with one dispatch, like `readSyncBatch()`.
+ * Each result is the number of bytes written or the error.
+ */
+export function writeSyncBatch(
+  writes: Array<[number, Uint8Array]>
+): Array<number | Error> {
+  const ops = writes.map(([rid, p]) =>
+    dispatch.batchOp(...reqWrite(rid, p))
+  );
+  return dispatch
+    .sendSyncBatch(ops)
+    .map(result => (result instanceof Error ? result : resWrite(result)));
+}
+
 function reqSeek(
   rid: number,
   offset: number,
   whence: SeekMode
 ): [flatbuffers.Builder, msg.Any, flatbuffers.Offset] {
   const builder = flatbuffers.createBuilder();
-  const inner = msg.Seek.createSeek(builder, rid, offset, whence);
+  // The offset is 64 bits, so that large files can be seeked.
+  const high = Math.floor(offset / 0x100000000);
+  const low = offset - high * 0x100000000;
+  const offset_ = builder.createLong(low | 0, high | 0);
+  const inner = msg.Seek.createSeek(builder, rid, offset_, whence);
   return [builder, msg.Any.Seek, inner];
 }
 
@@ -211,7 +277,7 @@ export class File
     return writeSync(this.rid, p);
   }
 
-  read(p: Uint8Array): Promise<ReadResult> {
+  read(p: Uint8Array): CancellablePromise<ReadResult> {
     return read(this.rid, p);
   }
 
@@ -265,6 +331,26 @@ export type OpenMode =
   /** Read-write. Behaves like `x` and allows to read from file. */
   | "x+";
 
+/** The options of `open()`, at least one of `read`, `write` and `append` is
+ * required.
+ */
+export interface OpenOptions {
+  /** Allows reading. */
+  read?: boolean;
+  /** Allows writing, starting at the beginning of the file. */
+  write?: boolean;
+  /** Allows writing, every write appends to the end of the file. */
+  append?: boolean;
+  /** Empties an existing file, requires `write`. */
+  truncate?: boolean;
+  /** Creates the file if it doesn't exist, requires `write` or `append`. */
+  create?: boolean;
+  /** Creates the file and fails if it exists already, requires `write` or
+   * `append`.
+   */
+  createNew?: boolean;
+}
+
 /** A factory function for creating instances of `File` associated with the
  * supplied file name.
  * @internal
@@ -1,6 +1,10 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
 import { test, testPerm, assert, assertEquals } from "./test_util.ts";
 
+// The batched ops aren't exposed in the types.
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+const { readSyncBatch, writeSyncBatch } = Deno as any;
+
 test(function filesStdioFileDescriptors() {
   assertEquals(Deno.stdin.rid, 0);
   assertEquals(Deno.stdout.rid, 1);
@@ -73,6 +77,74 @@ testPerm({ write: false, read: false }, async function readWritePermFailure() {
   }
 });
 
+testPerm({ write: true }, async function openOptionsAppendWriteOnly() {
+  const filename = Deno.makeTempDirSync() + "/log.txt";
+  const encoder = new TextEncoder();
+  let file = await Deno.open(filename, { append: true, create: true });
+  await file.write(encoder.encode("Hello "));
+  file.close();
+  file = Deno.openSync(filename, { append: true });
+  file.writeSync(encoder.encode("world!"));
+  file.close();
+
+  // Reading the file back needs --allow-read.
+  let err;
+  try {
+    Deno.openSync(filename, { read: true, append: true });
+  } catch (e) {
+    err = e;
+  }
+  assert(!!err);
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+});
+
+testPerm({ read: true, write: true }, async function openOptions() {
+  const tempDir = Deno.makeTempDirSync();
+  const filename = tempDir + "/test.txt";
+  const encoder = new TextEncoder();
+  const decoder = new TextDecoder();
+
+  let file = Deno.openSync(filename, { write: true, createNew: true });
+  file.writeSync(encoder.encode("Hello world!"));
+  file.close();
+  let err;
+  try {
+    Deno.openSync(filename, { write: true, createNew: true });
+  } catch (e) {
+    err = e;
+  }
+  assert(!!err);
+  assertEquals(err.kind, Deno.ErrorKind.AlreadyExists);
+
+  file = await Deno.open(filename, { read: true, append: true });
+  await file.write(encoder.encode("!"));
+  await file.seek(0, Deno.SeekMode.SEEK_START);
+  const buf = new Uint8Array(20);
+  const { nread } = await file.read(buf);
+  assertEquals(decoder.decode(buf.subarray(0, nread)), "Hello world!!");
+  file.close();
+
+  file = await Deno.open(filename, { write: true, truncate: true });
+  file.close();
+  assertEquals(Deno.statSync(filename).len, 0);
+  await Deno.remove(tempDir, { recursive: true });
+});
+
+testPerm({ read: true, write: true }, function openOptionsInvalid() {
+  const filename = "tests/hello.txt";
+  const invalid: Deno.OpenOptions[] = [{}, { read: true, create: true }];
+  for (const options of invalid) {
+    let err;
+    try {
+      Deno.openSync(filename, options);
+    } catch (e) {
+      err = e;
+    }
+    assert(!!err);
+    assertEquals(err.kind, Deno.ErrorKind.InvalidInput);
+  }
+});
+
 testPerm({ read: true, write: true }, async function createFile() {
   const tempDir = await Deno.makeTempDir();
   const filename = tempDir + "/test.txt";
@@ -241,3 +313,105 @@ testPerm({ read: true }, async function seekMode() {
   await file.read(buf); // "H"
   assertEquals(new TextDecoder().decode(buf), "H");
 });
+
+testPerm({ read: true }, async function readEmptyBuffer() {
+  const file = await Deno.open("tests/hello.txt");
+  // An empty buffer reads nothing, but the end is not reached.
+  let result = await file.read(new Uint8Array(0));
+  assertEquals(result, { nread: 0, eof: false });
+  await file.seek(0, Deno.SeekMode.SEEK_END);
+  result = await file.read(new Uint8Array(1));
+  assertEquals(result, { nread: 0, eof: true });
+  file.close();
+});
+
+testPerm({ read: true }, async function readConcurrentSharedBuffer() {
+  // Reads go straight into the buffers they are given, so reads into parts
+  // of the same ArrayBuffer must each stay within their own part.
+  const a = await Deno.open("tests/hello.txt");
+  const b = await Deno.open("tests/subdir/subdir2/mod2.ts");
+  const buf = new Uint8Array(8192);
+  buf.fill(7);
+  const [resultA, resultB] = await Promise.all([
+    a.read(buf.subarray(0, 4096)),
+    b.read(buf.subarray(4096))
+  ]);
+  const expectedA = Deno.readFileSync("tests/hello.txt");
+  const expectedB = Deno.readFileSync("tests/subdir/subdir2/mod2.ts");
+  assertEquals(resultA.nread, expectedA.byteLength);
+  assertEquals(resultB.nread, expectedB.byteLength);
+  assertEquals(buf.subarray(0, resultA.nread), expectedA);
+  assertEquals(buf.subarray(4096, 4096 + resultB.nread), expectedB);
+  assert(buf.subarray(resultA.nread, 4096).every(byte => byte === 7));
+  assert(buf.subarray(4096 + resultB.nread).every(byte => byte === 7));
+  a.close();
+  b.close();
+});
+
+testPerm({ read: true, write: true }, function readWriteSyncBatch() {
+  const tempDir = Deno.makeTempDirSync();
+  const a = Deno.openSync(tempDir + "/a.txt", "w+");
+  const b = Deno.openSync(tempDir + "/b.txt", "w+");
+  const encoder = new TextEncoder();
+  const written = writeSyncBatch([
+    [a.rid, encoder.encode("hello")],
+    [b.rid, encoder.encode("world!")],
+    [a.rid, encoder.encode(" again")],
+    [-1, encoder.encode("nowhere")]
+  ]);
+  assertEquals(written.slice(0, 3), [5, 6, 6]);
+  assertEquals(written[3].kind, Deno.ErrorKind.BadResource);
+  a.seekSync(0, Deno.SeekMode.SEEK_START);
+  b.seekSync(0, Deno.SeekMode.SEEK_START);
+
+  // The reads are given their data back, each into its own buffer.
+  const bufA = new Uint8Array(20);
+  const bufB = new Uint8Array(3);
+  const read = readSyncBatch([
+    [a.rid, bufA],
+    [b.rid, bufB],
+    [b.rid, new Uint8Array(0)]
+  ]);
+  assertEquals(read, [
+    { nread: 11, eof: false },
+    { nread: 3, eof: false },
+    { nread: 0, eof: false }
+  ]);
+  const decoder = new TextDecoder();
+  assertEquals(decoder.decode(bufA.subarray(0, 11)), "hello again");
+  assert(bufA.subarray(11).every(byte => byte === 0));
+  assertEquals(decoder.decode(bufB), "wor");
+  a.close();
+  b.close();
+  Deno.removeSync(tempDir, { recursive: true });
+});
+
+testPerm({ read: true, write: true }, async function seekLargeOffset() {
+  const tempDir = Deno.makeTempDirSync();
+  const filename = tempDir + "/sparse.bin";
+  const file = await Deno.open(filename, "w+");
+  // Past 4 GiB, the file is sparse on most file systems.
+  const offset = 2 ** 32 + 5;
+  await file.seek(offset, Deno.SeekMode.SEEK_START);
+  await file.write(new Uint8Array([42]));
+  assertEquals(Deno.statSync(filename).len, offset + 1);
+  await file.seek(-1, Deno.SeekMode.SEEK_END);
+  const buf = new Uint8Array(1);
+  assertEquals((await file.read(buf)).nread, 1);
+  assertEquals(buf[0], 42);
+  file.close();
+  await Deno.remove(tempDir, { recursive: true });
+});
+
+testPerm({ read: true }, async function seekNegativeStart() {
+  const file = await Deno.open("tests/hello.txt");
+  let err;
+  try {
+    await file.seek(-1, Deno.SeekMode.SEEK_START);
+  } catch (e) {
+    err = e;
+  }
+  assert(!!err);
+  assertEquals(err.kind, Deno.ErrorKind.InvalidInput);
+  file.close();
+});
@@ -0,0 +1,132 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import * as msg from "gen/cli/msg_generated";
+import * as flatbuffers from "./flatbuffers";
+import * as dispatch from "./dispatch";
+import { close } from "./files";
+import { assert, unreachable } from "./util";
+
+export type FsEventKind = "create" | "modify" | "remove";
+
+/** A change of the watched files. A rename removes the old path and creates
+ * the new one.
+ */
+export interface FsEvent {
+  kind: FsEventKind;
+  paths: string[];
+}
+
+export interface WatchFsOptions {
+  /** Also watch everything below the paths. Defaults to true. */
+  recursive?: boolean;
+}
+
+function kindMap(kind: msg.FsEventKind): FsEventKind {
+  switch (kind) {
+    case msg.FsEventKind.Create:
+      return "create";
+    case msg.FsEventKind.Modify:
+      return "modify";
+    case msg.FsEventKind.Remove:
+      return "remove";
+    default:
+      return unreachable();
+  }
+}
+
+function open(paths: string[], recursive: boolean): number {
+  const builder = flatbuffers.createBuilder();
+  const paths_ = msg.FsEventsOpen.createPathsVector(
+    builder,
+    paths.map(p => builder.createString(p))
+  );
+  const inner = msg.FsEventsOpen.createFsEventsOpen(
+    builder,
+    paths_,
+    recursive
+  );
+  const baseRes = dispatch.sendSync(builder, msg.Any.FsEventsOpen, inner);
+  assert(baseRes != null);
+  assert(msg.Any.FsEventsOpenRes === baseRes!.innerType());
+  const res = new msg.FsEventsOpenRes();
+  assert(baseRes!.inner(res) != null);
+  return res.rid();
+}
+
+/** Resolves to null when the watcher of `rid` is closed. */
+async function poll(rid: number): Promise<FsEvent[] | null> {
+  const builder = flatbuffers.createBuilder();
+  const inner = msg.FsEventsPoll.createFsEventsPoll(builder, rid);
+  const baseRes = await dispatch.sendAsync(
+    builder,
+    msg.Any.FsEventsPoll,
+    inner
+  );
+  assert(baseRes != null);
+  assert(msg.Any.FsEventsPollRes === baseRes!.innerType());
+  const res = new msg.FsEventsPollRes();
+  assert(baseRes!.inner(res) != null);
+  if (res.done()) {
+    return null;
+  }
+  const events: FsEvent[] = [];
+  for (let i = 0; i < res.eventsLength(); i++) {
+    const event = res.events(i)!;
+    const paths: string[] = [];
+    for (let j = 0; j < event.pathsLength(); j++) {
+      paths.push(event.paths(j));
+    }
+    events.push({ kind: kindMap(event.kind()), paths });
+  }
+  return events;
+}
+
+/** The changes of watched files, as an async iterator of batches. */
+export class FsWatcher implements AsyncIterableIterator<FsEvent[]> {
+  readonly rid: number;
+  private _closed = false;
+
+  // @internal
+  constructor(paths: string[], recursive: boolean) {
+    this.rid = open(paths, recursive);
+  }
+
+  async next(): Promise<IteratorResult<FsEvent[]>> {
+    const events = this._closed ? null : await poll(this.rid);
+    if (events === null) {
+      return { done: true, value: [] };
+    }
+    return { done: false, value: events };
+  }
+
+  [Symbol.asyncIterator](): AsyncIterableIterator<FsEvent[]> {
+    return this;
+  }
+
+  /** Stops watching, pending `next()`s are done. */
+  close(): void {
+    if (!this._closed) {
+      this._closed = true;
+      close(this.rid);
+    }
+  }
+}
+
+/** Watches `paths`, files or directories, for changes. Each iteration
+ * yields the events which happened since the last one. Requires the
+ * `--allow-read` flag for the paths.
+ *
+ *       const watcher = Deno.watchFs("src");
+ *       for await (const events of watcher) {
+ *         console.log(events); // [{ kind: "modify", paths: [...] }]
+ *       }
+ *
+ * An event has one kind and all the paths of that kind in the batch, a path
+ * changed several times in a row is reported once.
+ */
+export function watchFs(
+  paths: string | string[],
+  options: WatchFsOptions = {}
+): FsWatcher {
+  const { recursive = true } = options;
+  return new FsWatcher(Array.isArray(paths) ? paths : [paths], recursive);
+}
@@ -0,0 +1,73 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import { test, testPerm, assert, assertEquals } from "./test_util.ts";
+
+// Waits for events until one of `kind` has a path ending with `name`.
+async function waitFor(
+  watcher: Deno.FsWatcher,
+  kind: Deno.FsEventKind,
+  name: string
+): Promise<void> {
+  for await (const events of watcher) {
+    for (const event of events) {
+      if (event.kind === kind && event.paths.some(p => p.endsWith(name))) {
+        return;
+      }
+    }
+  }
+  throw new Error(`no ${kind} event for ${name}`);
+}
+
+test(function watchFsPermissions() {
+  let caughtError = false;
+  try {
+    Deno.watchFs(".");
+  } catch (err) {
+    caughtError = true;
+    assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+    assertEquals(err.name, "PermissionDenied");
+  }
+  assert(caughtError);
+});
+
+testPerm({ read: true }, function watchFsNotFound() {
+  let err;
+  try {
+    Deno.watchFs("this file hopefully doesn't exist");
+  } catch (e) {
+    err = e;
+  }
+  assert(err instanceof Deno.DenoError);
+  assertEquals(err.kind, Deno.ErrorKind.NotFound);
+});
+
+testPerm({ read: true, write: true }, async function watchFsEvents() {
+  const dir = Deno.makeTempDirSync();
+  const watcher = Deno.watchFs(dir);
+  assertEquals(Deno.resources()[watcher.rid], "fsEvents");
+  const file = dir + "/file.txt";
+  const data = new TextEncoder().encode("hello");
+  Deno.writeFileSync(file, data);
+  await waitFor(watcher, "create", "file.txt");
+  Deno.removeSync(file);
+  await waitFor(watcher, "remove", "file.txt");
+  watcher.close();
+  assertEquals(Deno.resources()[watcher.rid], undefined);
+});
+
+testPerm({ read: true, write: true }, async function watchFsRecursive() {
+  const dir = Deno.makeTempDirSync();
+  Deno.mkdirSync(dir + "/sub");
+  const watcher = Deno.watchFs([dir], { recursive: true });
+  Deno.writeFileSync(dir + "/sub/file.txt", new Uint8Array([1]));
+  await waitFor(watcher, "create", "file.txt");
+  watcher.close();
+});
+
+testPerm({ read: true, write: true }, async function watchFsCloseEnds() {
+  const dir = Deno.makeTempDirSync();
+  const watcher = Deno.watchFs(dir);
+  const next = watcher.next();
+  watcher.close();
+  assertEquals((await next).done, true);
+  assertEquals((await watcher.next()).done, true);
+});
@@ -107,6 +107,8 @@ window.TextDecoder = textEncoding.TextDecoder;
 export type TextDecoder = textEncoding.TextDecoder;
 
 window.performance = new performanceUtil.Performance();
+window.PerformanceEntry = performanceUtil.PerformanceEntry;
+export type PerformanceEntry = performanceUtil.PerformanceEntry;
 
 // This variable functioning correctly depends on `declareAsLet`
 // in //tools/ts_library_builder/main.ts
@@ -118,6 +120,8 @@ window.postMessage = workers.postMessage;
 
 window.Worker = workers.WorkerImpl;
 export type Worker = workers.Worker;
+export type WorkerOptions = workers.WorkerOptions;
+export type WorkerErrorEvent = workers.WorkerErrorEvent;
 
 // below are interfaces that are available in TypeScript but
 // have different signatures
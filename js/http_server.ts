@@ -0,0 +1,198 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import * as msg from "gen/cli/msg_generated";
+import * as flatbuffers from "./flatbuffers";
+import * as dispatch from "./dispatch";
+import * as domTypes from "./dom_types";
+import { close, read } from "./files";
+import { Headers } from "./headers";
+import { Closer, ReadResult, Reader } from "./io";
+import { Listener } from "./net";
+import { TextEncoder } from "./text_encoding";
+import { assert } from "./util";
+
+/** The response to a `ServerRequest`. A body with a `rid`, like a
+ * `Deno.File`, is streamed from that resource, with chunked transfer encoding
+ * unless there is a `content-length` header.
+ */
+export interface ServerResponse {
+  /** Defaults to 200. */
+  status?: number;
+  headers?: domTypes.HeadersInit;
+  body?: Uint8Array | string | domTypes.ResourceBody;
+}
+
+class RequestBody implements Reader, Closer {
+  constructor(readonly rid: number) {}
+
+  read(p: Uint8Array): Promise<ReadResult> {
+    return read(this.rid, p);
+  }
+
+  close(): void {
+    close(this.rid);
+  }
+}
+
+/** A request to an `HttpServer`, which waits for `respond()`. */
+export class ServerRequest {
+  readonly headers: Headers;
+  /** Closed once the response was sent, the part which was not read is
+   * discarded. It can be the body of the response, to echo it.
+   */
+  readonly body: Reader & Closer & { rid: number };
+  private _responded = false;
+
+  // @internal
+  constructor(
+    readonly method: string,
+    /** The path and the query, e.g. "/index.html?lang=en". */
+    readonly url: string,
+    headersList: Array<[string, string]>,
+    readonly remoteAddr: string,
+    bodyRid: number,
+    private readonly _responderRid: number
+  ) {
+    this.headers = new Headers(headersList);
+    this.body = new RequestBody(bodyRid);
+  }
+
+  /** Sends the response, it resolves once a streamed body was read. A
+   * request can only be responded to once, a response which can't be sent
+   * because the client went away rejects with `BrokenPipe`.
+   */
+  async respond(r: ServerResponse): Promise<void> {
+    assert(!this._responded, "The request was already responded to");
+    this._responded = true;
+    const headers = new Headers(r.headers);
+    let data: Uint8Array | undefined;
+    let bodyRid: number | undefined;
+    if (typeof r.body === "string") {
+      data = new TextEncoder().encode(r.body);
+    } else if (r.body instanceof Uint8Array) {
+      data = r.body;
+    } else if (r.body != null) {
+      bodyRid = r.body.rid;
+    }
+
+    const builder = flatbuffers.createBuilder();
+    const fields: flatbuffers.Offset[] = [];
+    for (const [key, value] of headers.entries()) {
+      const key_ = builder.createString(key);
+      const value_ = builder.createString(value);
+      fields.push(msg.KeyValue.createKeyValue(builder, key_, value_));
+    }
+    const header = msg.HttpHeader.createHttpHeader(
+      builder,
+      false,
+      0,
+      0,
+      r.status === undefined ? 200 : r.status,
+      msg.HttpHeader.createFieldsVector(builder, fields)
+    );
+    const inner = msg.HttpRespond.createHttpRespond(
+      builder,
+      this._responderRid,
+      header,
+      bodyRid !== undefined,
+      bodyRid || 0
+    );
+    try {
+      await dispatch.sendAsync(builder, msg.Any.HttpRespond, inner, data);
+    } finally {
+      try {
+        this.body.close();
+      } catch (e) {
+        // The body was closed already.
+      }
+    }
+  }
+}
+
+/** An HTTP/1.1 server, an async iterator of its requests. */
+export class HttpServer implements AsyncIterableIterator<ServerRequest> {
+  readonly rid: number;
+
+  // @internal
+  constructor(listener: Listener) {
+    const builder = flatbuffers.createBuilder();
+    const inner = msg.HttpServe.createHttpServe(builder, listener.rid);
+    const baseRes = dispatch.sendSync(builder, msg.Any.HttpServe, inner);
+    assert(baseRes != null);
+    assert(msg.Any.HttpServeRes === baseRes!.innerType());
+    const res = new msg.HttpServeRes();
+    assert(baseRes!.inner(res) != null);
+    this.rid = res.rid();
+  }
+
+  /** Waits for the next request, resolves to null once the server or its
+   * listener is closed.
+   */
+  async accept(): Promise<ServerRequest | null> {
+    const builder = flatbuffers.createBuilder();
+    const inner = msg.HttpAccept.createHttpAccept(builder, this.rid);
+    const baseRes = await dispatch.sendAsync(
+      builder,
+      msg.Any.HttpAccept,
+      inner
+    );
+    assert(baseRes != null);
+    assert(msg.Any.HttpAcceptRes === baseRes!.innerType());
+    const res = new msg.HttpAcceptRes();
+    assert(baseRes!.inner(res) != null);
+    if (res.done()) {
+      return null;
+    }
+    const header = res.header()!;
+    const headersList: Array<[string, string]> = [];
+    for (let i = 0; i < header.fieldsLength(); i++) {
+      const field = header.fields(i)!;
+      headersList.push([field.key()!, field.value()!]);
+    }
+    return new ServerRequest(
+      header.method()!,
+      header.url()!,
+      headersList,
+      res.remoteAddr()!,
+      res.bodyRid(),
+      res.responderRid()
+    );
+  }
+
+  async next(): Promise<IteratorResult<ServerRequest>> {
+    const request = await this.accept();
+    if (request === null) {
+      // The value of a done result is not used.
+      return { done: true, value: (undefined as unknown) as ServerRequest };
+    }
+    return { done: false, value: request };
+  }
+
+  [Symbol.asyncIterator](): AsyncIterableIterator<ServerRequest> {
+    return this;
+  }
+
+  /** Closes the server and its connections, the requests which were not
+   * responded to yet fail. The listener stays open.
+   */
+  close(): void {
+    close(this.rid);
+  }
+}
+
+/** Serves HTTP/1.1 on `listener`, from `Deno.listen()` or
+ * `Deno.listenTLS()`. Unlike a server on top of `accept()`, reading and
+ * writing, the parsing of requests, keep-alive and the responses to
+ * malformed requests are handled natively.
+ *
+ *       const server = Deno.serveHttp(Deno.listen("tcp", "127.0.0.1:8000"));
+ *       for await (const req of server) {
+ *         req.respond({ body: "Hello World\n" });
+ *       }
+ *
+ * Pipelined requests are responded to in order: a connection has one
+ * request at a time, the next one comes after the response was sent.
+ * Closing the listener closes the server.
+ */
+export function serveHttp(listener: Listener): HttpServer {
+  return new HttpServer(listener);
+}
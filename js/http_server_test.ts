@@ -0,0 +1,150 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import { testPerm, assert, assertEquals } from "./test_util.ts";
+
+async function readAll(conn: Deno.Conn): Promise<string> {
+  const decoder = new TextDecoder();
+  const buf = new Uint8Array(1024);
+  let text = "";
+  while (true) {
+    const { nread, eof } = await conn.read(buf);
+    text += decoder.decode(buf.subarray(0, nread));
+    if (eof) {
+      return text;
+    }
+  }
+}
+
+testPerm({ net: true }, async function serveHttpRequest() {
+  const listener = Deno.listen("tcp", "127.0.0.1:4512");
+  const server = Deno.serveHttp(listener);
+  const response = fetch("http://127.0.0.1:4512/path?query=1", {
+    method: "POST",
+    headers: [["x-test", "a"]],
+    body: "hello"
+  });
+  const req = await server.accept();
+  assertEquals(req.method, "POST");
+  assertEquals(req.url, "/path?query=1");
+  assertEquals(req.headers.get("x-test"), "a");
+  assertEquals(req.remoteAddr.split(":")[0], "127.0.0.1");
+  const body = new Deno.Buffer();
+  await body.readFrom(req.body);
+  assertEquals(body.toString(), "hello");
+  await req.respond({
+    status: 201,
+    headers: [["content-type", "text/plain"], ["set-cookie", "a=1"]],
+    body: "world"
+  });
+  const res = await response;
+  assertEquals(res.status, 201);
+  assertEquals(res.headers.get("content-type"), "text/plain");
+  assertEquals(await res.text(), "world");
+  // A pending accept is done when the server is closed.
+  const next = server.accept();
+  server.close();
+  assertEquals(await next, null);
+  listener.close();
+});
+
+testPerm({ read: true, net: true }, async function serveHttpStreamedBody() {
+  const listener = Deno.listen("tcp", "127.0.0.1:4513");
+  const server = Deno.serveHttp(listener);
+  const response = fetch("http://127.0.0.1:4513/");
+  const req = await server.accept();
+  const file = await Deno.open("tests/hello.txt");
+  await req.respond({ body: file });
+  // The whole file was read when respond() resolved.
+  file.close();
+  const res = await response;
+  assertEquals(res.headers.get("transfer-encoding"), "chunked");
+  assertEquals(await res.text(), "Hello world!");
+  server.close();
+  listener.close();
+});
+
+testPerm({ net: true }, async function serveHttpPipelining() {
+  const listener = Deno.listen("tcp", "127.0.0.1:4514");
+  const server = Deno.serveHttp(listener);
+  const conn = await Deno.dial("tcp", "127.0.0.1:4514");
+  await conn.write(
+    new TextEncoder().encode(
+      "GET /first HTTP/1.1\r\nhost: localhost\r\n\r\n" +
+        "GET /second HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n"
+    )
+  );
+  const responses = readAll(conn);
+  // Both requests came on one connection, which was kept alive.
+  for (const url of ["/first", "/second"]) {
+    const req = await server.accept();
+    assertEquals(req.url, url);
+    await req.respond({ body: url });
+  }
+  const text = await responses;
+  const first = text.indexOf("/first");
+  assert(first > 0);
+  assert(text.indexOf("/second") > first);
+  assertEquals(text.split("HTTP/1.1 200 OK").length, 3);
+  conn.close();
+  server.close();
+  listener.close();
+});
+
+testPerm({ net: true }, async function serveHttpMalformedRequest() {
+  const listener = Deno.listen("tcp", "127.0.0.1:4515");
+  const server = Deno.serveHttp(listener);
+  // Accepts the connection.
+  const next = server.accept();
+  const conn = await Deno.dial("tcp", "127.0.0.1:4515");
+  await conn.write(new TextEncoder().encode("NOT HTTP\r\n\r\n"));
+  const text = await readAll(conn);
+  assert(text.startsWith("HTTP/1.1 400 Bad Request"));
+  conn.close();
+  // Closing the listener ends the server.
+  listener.close();
+  assertEquals(await next, null);
+  server.close();
+});
+
+testPerm({ net: true }, async function serveHttpCloseServer() {
+  const listener = Deno.listen("tcp", "127.0.0.1:4516");
+  const server = Deno.serveHttp(listener);
+  const response = fetch("http://127.0.0.1:4516/");
+  const req = await server.accept();
+  // Its connection is closed with the server.
+  server.close();
+  let err;
+  try {
+    await response;
+  } catch (e) {
+    err = e;
+  }
+  assert(err !== undefined);
+  err = undefined;
+  try {
+    await req.respond({ body: "late" });
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.BrokenPipe);
+  listener.close();
+});
+
+testPerm({ net: true }, async function serveHttpIterator() {
+  const listener = Deno.listen("tcp", "127.0.0.1:4517");
+  const server = Deno.serveHttp(listener);
+  const responses = Promise.all([
+    fetch("http://127.0.0.1:4517/a"),
+    fetch("http://127.0.0.1:4517/b")
+  ]);
+  let count = 0;
+  for await (const req of server) {
+    await req.respond({ body: req.url });
+    if (++count === 2) {
+      break;
+    }
+  }
+  const texts = await Promise.all((await responses).map(r => r.text()));
+  assertEquals(texts.sort(), ["/a", "/b"]);
+  server.close();
+  listener.close();
+});
@@ -76,6 +76,17 @@ test(function linkSyncPerm() {
   assertEquals(err.name, "PermissionDenied");
 });
 
+// The link could be used to read the file.
+testPerm({ write: true }, function linkSyncReadPerm() {
+  let err;
+  try {
+    Deno.linkSync("oldbaddir", "newbaddir");
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+});
+
 testPerm({ read: true, write: true }, async function linkSuccess() {
   const testDir = Deno.makeTempDirSync();
   const oldData = "Hardlink";
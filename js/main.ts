@@ -11,33 +11,34 @@ import { args } from "./deno";
 import { replLoop } from "./repl";
 import { setVersions } from "./version";
 import { setLocation } from "./location";
+import { setPrepareStackTrace } from "./error_stack";
+import { window } from "./window";
 
 // builtin modules
 import * as deno from "./deno";
 
-// TODO(kitsonk) remove with `--types` below
-import libDts from "gen/cli/lib/lib.deno_runtime.d.ts!string";
+// Called by Rust once the event loop drained.
+window.denoUnload = os.unload;
 
 export default function denoMain(name?: string): void {
   const startResMsg = os.start(name);
 
+  setPrepareStackTrace();
+
   setVersions(startResMsg.denoVersion()!, startResMsg.v8Version()!);
 
-  // handle `--version`
+  // handle `--version` and `deno version`
   if (startResMsg.versionFlag()) {
+    if (startResMsg.versionJsonFlag()) {
+      console.log(JSON.stringify(deno.version, null, 2));
+      os.exit(0);
+    }
     console.log("deno:", deno.version.deno);
     console.log("v8:", deno.version.v8);
     console.log("typescript:", deno.version.typescript);
     os.exit(0);
   }
 
-  // handle `--types`
-  // TODO(kitsonk) move to Rust fetching from compiler
-  if (startResMsg.typesFlag()) {
-    console.log(libDts);
-    os.exit(0);
-  }
-
   const mainModule = startResMsg.mainModule();
   if (mainModule) {
     assert(mainModule.length > 0);
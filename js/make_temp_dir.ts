@@ -4,21 +4,31 @@ import * as flatbuffers from "./flatbuffers";
 import * as dispatch from "./dispatch";
 import { assert } from "./util";
 
-export interface MakeTempDirOptions {
+export interface MakeTempOptions {
   dir?: string;
   prefix?: string;
   suffix?: string;
 }
 
-function req({
-  dir,
-  prefix,
-  suffix
-}: MakeTempDirOptions): [flatbuffers.Builder, msg.Any, flatbuffers.Offset] {
+export type MakeTempDirOptions = MakeTempOptions;
+
+function req(
+  { dir, prefix, suffix }: MakeTempOptions,
+  isFile: boolean
+): [flatbuffers.Builder, msg.Any, flatbuffers.Offset] {
   const builder = flatbuffers.createBuilder();
   const fbDir = dir == null ? 0 : builder.createString(dir);
   const fbPrefix = prefix == null ? 0 : builder.createString(prefix);
   const fbSuffix = suffix == null ? 0 : builder.createString(suffix);
+  if (isFile) {
+    const inner = msg.MakeTempFile.createMakeTempFile(
+      builder,
+      fbDir,
+      fbPrefix,
+      fbSuffix
+    );
+    return [builder, msg.Any.MakeTempFile, inner];
+  }
   const inner = msg.MakeTempDir.createMakeTempDir(
     builder,
     fbDir,
@@ -30,10 +40,17 @@ function req({
 
 function res(baseRes: null | msg.Base): string {
   assert(baseRes != null);
-  assert(msg.Any.MakeTempDirRes === baseRes!.innerType());
-  const res = new msg.MakeTempDirRes();
-  assert(baseRes!.inner(res) != null);
-  const path = res.path();
+  let path: string | null;
+  if (baseRes!.innerType() === msg.Any.MakeTempFileRes) {
+    const res = new msg.MakeTempFileRes();
+    assert(baseRes!.inner(res) != null);
+    path = res.path();
+  } else {
+    assert(msg.Any.MakeTempDirRes === baseRes!.innerType());
+    const res = new msg.MakeTempDirRes();
+    assert(baseRes!.inner(res) != null);
+    path = res.path();
+  }
   assert(path != null);
   return path!;
 }
@@ -43,23 +60,47 @@ function res(baseRes: null | msg.Base): string {
  *       const tempDirName0 = Deno.makeTempDirSync();
  *       const tempDirName1 = Deno.makeTempDirSync({ prefix: 'my_temp' });
  */
-export function makeTempDirSync(options: MakeTempDirOptions = {}): string {
-  return res(dispatch.sendSync(...req(options)));
+export function makeTempDirSync(options: MakeTempOptions = {}): string {
+  return res(dispatch.sendSync(...req(options, false)));
 }
 
 /** makeTempDir creates a new temporary directory in the directory `dir`, its
  * name beginning with `prefix` and ending with `suffix`.
- * It returns the full path to the newly created directory.
+ * It returns the full path to the newly created directory, which only its
+ * owner can access (mode 0o700).
  * If `dir` is unspecified, tempDir uses the default directory for temporary
- * files. Multiple programs calling tempDir simultaneously will not choose the
- * same directory. It is the caller's responsibility to remove the directory
- * when no longer needed.
+ * files, from `TMPDIR` (`TMP` or `TEMP` on Windows). Multiple programs calling
+ * tempDir simultaneously will not choose the same directory. The prefix and
+ * suffix must not contain path separators. It is the caller's responsibility
+ * to remove the directory when no longer needed.
  *
  *       const tempDirName0 = await Deno.makeTempDir();
  *       const tempDirName1 = await Deno.makeTempDir({ prefix: 'my_temp' });
  */
 export async function makeTempDir(
-  options: MakeTempDirOptions = {}
+  options: MakeTempOptions = {}
+): Promise<string> {
+  return res(await dispatch.sendAsync(...req(options, false)));
+}
+
+/** makeTempFileSync is the synchronous version of `makeTempFile`.
+ *
+ *       const tempFileName0 = Deno.makeTempFileSync();
+ *       const tempFileName1 = Deno.makeTempFileSync({ suffix: '.json' });
+ */
+export function makeTempFileSync(options: MakeTempOptions = {}): string {
+  return res(dispatch.sendSync(...req(options, true)));
+}
+
+/** makeTempFile creates a new empty file like `makeTempDir` creates a
+ * directory, which only its owner can read and write (mode 0o600).
+ * It returns the full path to the newly created file.
+ *
+ *       const tempFileName0 = await Deno.makeTempFile();
+ *       const tempFileName1 = await Deno.makeTempFile({ suffix: '.json' });
+ */
+export async function makeTempFile(
+  options: MakeTempOptions = {}
 ): Promise<string> {
-  return res(await dispatch.sendAsync(...req(options)));
+  return res(await dispatch.sendAsync(...req(options, true)));
 }
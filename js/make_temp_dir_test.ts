@@ -64,3 +64,78 @@ testPerm({ write: true }, async function makeTempDirSuccess() {
   assertEquals(err.kind, Deno.ErrorKind.NotFound);
   assertEquals(err.name, "NotFound");
 });
+
+testPerm({ read: true, write: true }, function makeTempDirSyncMode() {
+  const dir = Deno.makeTempDirSync();
+  assert(Deno.statSync(dir).isDirectory());
+  if (Deno.build.os !== "win") {
+    assertEquals(Deno.statSync(dir).mode & 0o777, 0o700);
+  }
+});
+
+testPerm({ write: true }, function makeTempDirSyncInvalidPrefix() {
+  for (const options of [
+    { prefix: "../escape" },
+    { suffix: "a/b" },
+    { prefix: "a\\b" }
+  ]) {
+    let err;
+    try {
+      Deno.makeTempDirSync(options);
+    } catch (err_) {
+      err = err_;
+    }
+    assertEquals(err.kind, Deno.ErrorKind.InvalidInput);
+    assertEquals(err.name, "InvalidInput");
+  }
+});
+
+testPerm({ read: true, write: true }, function makeTempFileSyncSuccess() {
+  const file1 = Deno.makeTempFileSync({ prefix: "hello", suffix: "world" });
+  const file2 = Deno.makeTempFileSync({ prefix: "hello", suffix: "world" });
+  // Check that both files are different.
+  assert(file1 !== file2);
+  for (const file of [file1, file2]) {
+    // Check that the prefix and suffix are applied.
+    const lastPart = file.replace(/^.*[\\\/]/, "");
+    assert(lastPart.startsWith("hello"));
+    assert(lastPart.endsWith("world"));
+    const info = Deno.statSync(file);
+    assert(info.isFile());
+    assertEquals(info.len, 0);
+    if (Deno.build.os !== "win") {
+      assertEquals(info.mode & 0o777, 0o600);
+    }
+  }
+  // Check that the `dir` option works.
+  const dir = Deno.makeTempDirSync();
+  const file3 = Deno.makeTempFileSync({ dir });
+  assert(file3.startsWith(dir));
+  assert(/^[\\\/]/.test(file3.slice(dir.length)));
+  // Check that creating a temp file inside a nonexisting directory fails.
+  let err;
+  try {
+    Deno.makeTempFileSync({ dir: "/baddir" });
+  } catch (err_) {
+    err = err_;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.NotFound);
+  assertEquals(err.name, "NotFound");
+});
+
+test(function makeTempFileSyncPerm() {
+  let err;
+  try {
+    Deno.makeTempFileSync();
+  } catch (err_) {
+    err = err_;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+  assertEquals(err.name, "PermissionDenied");
+});
+
+testPerm({ read: true, write: true }, async function makeTempFileSuccess() {
+  const file = await Deno.makeTempFile({ prefix: "hello" });
+  assert(file.replace(/^.*[\\\/]/, "").startsWith("hello"));
+  assert(Deno.statSync(file).isFile());
+});
@@ -10,6 +10,8 @@ export interface Metrics {
   bytesSentControl: number;
   bytesSentData: number;
   bytesReceived: number;
+  /** The resources which are open, see `Deno.resources()`. */
+  resourcesOpen: number;
 }
 
 function req(): [flatbuffers.Builder, msg.Any, flatbuffers.Offset] {
@@ -29,7 +31,8 @@ function res(baseRes: null | msg.Base): Metrics {
     opsCompleted: res.opsCompleted().toFloat64(),
     bytesSentControl: res.bytesSentControl().toFloat64(),
     bytesSentData: res.bytesSentData().toFloat64(),
-    bytesReceived: res.bytesReceived().toFloat64()
+    bytesReceived: res.bytesReceived().toFloat64(),
+    resourcesOpen: res.resourcesOpen().toFloat64()
   };
 }
 
@@ -44,6 +47,7 @@ function res(baseRes: null | msg.Base): Metrics {
  *      │ bytesSentControl │  504   │
  *      │  bytesSentData   │   0    │
  *      │  bytesReceived   │  856   │
+ *      │  resourcesOpen   │   3    │
  *      └──────────────────┴────────┘
  */
 export function metrics(): Metrics {
@@ -1,5 +1,5 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
-import { test, testPerm, assert } from "./test_util.ts";
+import { test, testPerm, assert, assertEquals } from "./test_util.ts";
 
 test(async function metrics() {
   const m1 = Deno.metrics();
@@ -41,3 +41,16 @@ testPerm({ write: true }, async function metricsUpdatedIfNoResponseAsync() {
   const metrics = Deno.metrics();
   assert(metrics.opsDispatched === metrics.opsCompleted);
 });
+
+testPerm({ read: true }, async function metricsResourcesOpen() {
+  const m1 = Deno.metrics();
+  assert(m1.resourcesOpen >= 3); // stdin, stdout and stderr.
+
+  const file = await Deno.open("tests/hello.txt");
+  const m2 = Deno.metrics();
+  assertEquals(m2.resourcesOpen, m1.resourcesOpen + 1);
+
+  file.close();
+  const m3 = Deno.metrics();
+  assertEquals(m3.resourcesOpen, m1.resourcesOpen);
+});
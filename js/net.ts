@@ -3,8 +3,10 @@ import { ReadResult, Reader, Writer, Closer } from "./io";
 import * as msg from "gen/cli/msg_generated";
 import { assert, notImplemented } from "./util";
 import * as dispatch from "./dispatch";
+import { CancellablePromise } from "./dispatch";
 import * as flatbuffers from "./flatbuffers";
 import { read, write, close } from "./files";
+import { build } from "./build";
 
 export type Network = "tcp";
 // TODO support other types:
@@ -15,15 +17,22 @@ export type Addr = string;
 
 /** A Listener is a generic network listener for stream-oriented protocols. */
 export interface Listener {
-  /** Waits for and resolves to the next connection to the `Listener`. */
-  accept(): Promise<Conn>;
+  /** The resource ID of the listener. */
+  rid: number;
+
+  /** Waits for and resolves to the next connection to the `Listener`.
+   * `cancel()` stops the wait and leaves the listener open.
+   */
+  accept(): CancellablePromise<Conn>;
 
   /** Close closes the listener. Any pending accept promises will be rejected
-   * with errors.
+   * with `BadResource` errors.
    */
   close(): void;
 
-  /** Return the address of the `Listener`. */
+  /** Return the address the `Listener` is bound to, with the port which was
+   * chosen if it was `0`.
+   */
   addr(): Addr;
 }
 
@@ -53,7 +62,7 @@ class ConnImpl implements Conn {
     return write(this.rid, p);
   }
 
-  read(p: Uint8Array): Promise<ReadResult> {
+  read(p: Uint8Array): CancellablePromise<ReadResult> {
     return read(this.rid, p);
   }
 
@@ -76,18 +85,25 @@ class ConnImpl implements Conn {
   }
 }
 
+function resAccept(baseRes: msg.Base): Conn {
+  assert(msg.Any.NewConn === baseRes.innerType());
+  const res = new msg.NewConn();
+  assert(baseRes.inner(res) != null);
+  return new ConnImpl(res.rid(), res.remoteAddr()!, res.localAddr()!);
+}
+
 class ListenerImpl implements Listener {
-  constructor(readonly rid: number) {}
+  constructor(readonly rid: number, private readonly localAddr: string) {}
 
-  async accept(): Promise<Conn> {
+  accept(): CancellablePromise<Conn> {
     const builder = flatbuffers.createBuilder();
     const inner = msg.Accept.createAccept(builder, this.rid);
-    const baseRes = await dispatch.sendAsync(builder, msg.Any.Accept, inner);
-    assert(baseRes != null);
-    assert(msg.Any.NewConn === baseRes!.innerType());
-    const res = new msg.NewConn();
-    assert(baseRes!.inner(res) != null);
-    return new ConnImpl(res.rid(), res.remoteAddr()!, res.localAddr()!);
+    return dispatch.sendAsyncCancellable(
+      resAccept,
+      builder,
+      msg.Any.Accept,
+      inner
+    );
   }
 
   close(): void {
@@ -95,7 +111,7 @@ class ListenerImpl implements Listener {
   }
 
   addr(): Addr {
-    return notImplemented();
+    return this.localAddr;
   }
 }
 
@@ -106,6 +122,10 @@ export interface Conn extends Reader, Writer, Closer {
   remoteAddr: string;
   /** The resource ID of the connection. */
   rid: number;
+  /** Reads into `p`, like `Deno.read()`. `cancel()` stops the read and leaves
+   * the connection open, e.g. after a timeout.
+   */
+  read(p: Uint8Array): CancellablePromise<ReadResult>;
   /** Shuts down (`shutdown(2)`) the reading side of the TCP connection. Most
    * callers should just use `close()`.
    */
@@ -116,6 +136,60 @@ export interface Conn extends Reader, Writer, Closer {
   closeWrite(): void;
 }
 
+export interface ListenOptions {
+  /** Sets `SO_REUSEADDR`, to bind to an address which the connections of a
+   * closed listener still use. Defaults to true, except on Windows where it
+   * allows to take over an address which is listened on.
+   */
+  reuseAddress?: boolean;
+  /** Sets `SO_REUSEPORT`, so that several listeners can bind to the same
+   * address and share its connections. Not supported on Windows.
+   */
+  reusePort?: boolean;
+  /** The number of connections which may wait to be accepted. Defaults to
+   * 128.
+   */
+  backlog?: number;
+}
+
+export interface DialOptions {
+  /** Milliseconds after which connecting fails with `TimedOut`, by default
+   * only the OS gives up.
+   */
+  timeout?: number;
+}
+
+/** The address of a unix domain socket, see `listen()` and `dial()`. */
+export interface UnixAddr {
+  transport: "unix";
+  /** The path of the socket file. */
+  path: string;
+}
+
+function unixListen(path: string): Listener {
+  const builder = flatbuffers.createBuilder();
+  const path_ = builder.createString(path);
+  const inner = msg.UnixListen.createUnixListen(builder, path_);
+  const baseRes = dispatch.sendSync(builder, msg.Any.UnixListen, inner);
+  assert(baseRes != null);
+  assert(msg.Any.ListenRes === baseRes!.innerType());
+  const res = new msg.ListenRes();
+  assert(baseRes!.inner(res) != null);
+  return new ListenerImpl(res.rid(), res.localAddr()!);
+}
+
+async function unixDial(path: string): Promise<Conn> {
+  const builder = flatbuffers.createBuilder();
+  const path_ = builder.createString(path);
+  const inner = msg.UnixDial.createUnixDial(builder, path_);
+  const baseRes = await dispatch.sendAsync(builder, msg.Any.UnixDial, inner);
+  assert(baseRes != null);
+  assert(msg.Any.NewConn === baseRes!.innerType());
+  const res = new msg.NewConn();
+  assert(baseRes!.inner(res) != null);
+  return new ConnImpl(res.rid(), res.remoteAddr()!, res.localAddr()!);
+}
+
 /** Listen announces on the local network address.
  *
  * The network must be `tcp`, `tcp4`, `tcp6`, `unix` or `unixpacket`.
@@ -130,18 +204,55 @@ export interface Conn extends Reader, Writer, Closer {
  * `addr()` method of `Listener` can be used to discover the chosen port.
  *
  * See `dial()` for a description of the network and address parameters.
+ *
+ *       const listener = Deno.listen("tcp", "127.0.0.1:0");
+ *       console.log(listener.addr()); // "127.0.0.1:49152"
+ *
+ * Requires the `--allow-net` flag for the address.
+ *
+ * A unix domain socket listens on a file, which is created and then removed
+ * when the listener is closed. It requires both the `--allow-read` and the
+ * `--allow-write` flags for the path, not `--allow-net`, and is not supported
+ * on Windows.
+ *
+ *       const listener = Deno.listen({ transport: "unix", path: "app.sock" });
  */
-export function listen(network: Network, address: string): Listener {
+export function listen(addr: UnixAddr): Listener;
+export function listen(
+  network: Network,
+  address: string,
+  options?: ListenOptions
+): Listener;
+export function listen(
+  network: Network | UnixAddr,
+  address = "",
+  options: ListenOptions = {}
+): Listener {
+  if (typeof network !== "string") {
+    return unixListen(network.path);
+  }
+  const {
+    reuseAddress = build.os !== "win",
+    reusePort = false,
+    backlog = 0
+  } = options;
   const builder = flatbuffers.createBuilder();
   const network_ = builder.createString(network);
   const address_ = builder.createString(address);
-  const inner = msg.Listen.createListen(builder, network_, address_);
+  const inner = msg.Listen.createListen(
+    builder,
+    network_,
+    address_,
+    reuseAddress,
+    reusePort,
+    backlog
+  );
   const baseRes = dispatch.sendSync(builder, msg.Any.Listen, inner);
   assert(baseRes != null);
   assert(msg.Any.ListenRes === baseRes!.innerType());
   const res = new msg.ListenRes();
   assert(baseRes!.inner(res) != null);
-  return new ListenerImpl(res.rid());
+  return new ListenerImpl(res.rid(), res.localAddr()!);
 }
 
 /** Dial connects to the address on the named network.
@@ -170,12 +281,35 @@ export function listen(network: Network, address: string): Listener {
  *     dial("udp", "[2001:db8::1]:domain")
  *     dial("udp", "[fe80::1%lo0]:53")
  *     dial("tcp", ":80")
+ *     dial("tcp", "198.51.100.1:80", { timeout: 1000 })
+ *     dial({ transport: "unix", path: "app.sock" })
+ *
+ * Requires the `--allow-net` flag for the address, and for a unix domain
+ * socket both the `--allow-read` and the `--allow-write` flags for its path.
  */
-export async function dial(network: Network, address: string): Promise<Conn> {
+export function dial(addr: UnixAddr): Promise<Conn>;
+export function dial(
+  network: Network,
+  address: string,
+  options?: DialOptions
+): Promise<Conn>;
+export async function dial(
+  network: Network | UnixAddr,
+  address = "",
+  options: DialOptions = {}
+): Promise<Conn> {
+  if (typeof network !== "string") {
+    return unixDial(network.path);
+  }
   const builder = flatbuffers.createBuilder();
   const network_ = builder.createString(network);
   const address_ = builder.createString(address);
-  const inner = msg.Dial.createDial(builder, network_, address_);
+  const inner = msg.Dial.createDial(
+    builder,
+    network_,
+    address_,
+    options.timeout || 0
+  );
   const baseRes = await dispatch.sendAsync(builder, msg.Any.Dial, inner);
   assert(baseRes != null);
   assert(msg.Any.NewConn === baseRes!.innerType());
@@ -191,3 +325,252 @@ export async function connect(
 ): Promise<Conn> {
   return notImplemented();
 }
+
+export interface ListenTLSOptions {
+  /** A PEM file with the certificate chain of the server. */
+  certFile: string;
+  /** A PEM file with the PKCS#8 or RSA private key of the certificate. */
+  keyFile: string;
+}
+
+export interface DialTLSOptions {
+  /** The name the certificate of the server must be issued for, which is
+   * also sent to it as SNI. Defaults to the host of the address, IP
+   * addresses are not supported.
+   */
+  hostname?: string;
+  /** A PEM file with CA certificates to trust in addition to the default
+   * roots, e.g. the one which issued a self-signed certificate.
+   */
+  caFile?: string;
+}
+
+/** Listens like `listen("tcp", address)`, and performs a TLS handshake with
+ * each accepted connection. A failed handshake rejects the `accept()`.
+ *
+ *       const listener = Deno.listenTLS("0.0.0.0:8443", {
+ *         certFile: "server.crt",
+ *         keyFile: "server.key"
+ *       });
+ *
+ * Requires the `--allow-net` flag for the address and the `--allow-read`
+ * flag for the certificate and key files.
+ */
+export function listenTLS(
+  address: string,
+  options: ListenTLSOptions
+): Listener {
+  const builder = flatbuffers.createBuilder();
+  const address_ = builder.createString(address);
+  const certFile_ = builder.createString(options.certFile);
+  const keyFile_ = builder.createString(options.keyFile);
+  const inner = msg.ListenTls.createListenTls(
+    builder,
+    address_,
+    certFile_,
+    keyFile_
+  );
+  const baseRes = dispatch.sendSync(builder, msg.Any.ListenTls, inner);
+  assert(baseRes != null);
+  assert(msg.Any.ListenRes === baseRes!.innerType());
+  const res = new msg.ListenRes();
+  assert(baseRes!.inner(res) != null);
+  return new ListenerImpl(res.rid(), res.localAddr()!);
+}
+
+/** The host of a `host:port` address, without the brackets of IPv6. */
+function hostOf(address: string): string {
+  const host = address.slice(0, address.lastIndexOf(":"));
+  if (host.startsWith("[") && host.endsWith("]")) {
+    return host.slice(1, -1);
+  }
+  return host || "localhost";
+}
+
+/** Connects like `dial("tcp", address)`, and performs a TLS handshake. The
+ * connection reads and writes plain text. A failed handshake rejects with
+ * the reason, e.g. an expired certificate or one issued for another name.
+ *
+ *       const conn = await Deno.dialTLS("deno.land:443");
+ *       const local = await Deno.dialTLS("localhost:8443", {
+ *         caFile: "RootCA.pem"
+ *       });
+ *
+ * Requires the `--allow-net` flag for the address and the `--allow-read`
+ * flag for the CA file.
+ */
+export async function dialTLS(
+  address: string,
+  options: DialTLSOptions = {}
+): Promise<Conn> {
+  const { hostname = hostOf(address), caFile } = options;
+  const builder = flatbuffers.createBuilder();
+  const address_ = builder.createString(address);
+  const hostname_ = builder.createString(hostname);
+  const caFile_ = caFile == null ? 0 : builder.createString(caFile);
+  const inner = msg.DialTls.createDialTls(
+    builder,
+    address_,
+    hostname_,
+    caFile_
+  );
+  const baseRes = await dispatch.sendAsync(builder, msg.Any.DialTls, inner);
+  assert(baseRes != null);
+  assert(msg.Any.NewConn === baseRes!.innerType());
+  const res = new msg.NewConn();
+  assert(baseRes!.inner(res) != null);
+  return new ConnImpl(res.rid(), res.remoteAddr()!, res.localAddr()!);
+}
+
+/** A datagram of `DatagramConn.receive()`. */
+export interface Datagram {
+  /** The payload, or its start if it was truncated. */
+  data: Uint8Array;
+  /** The address of the sender. */
+  addr: Addr;
+  /** The payload was larger than the buffer it was received into. */
+  truncated: boolean;
+}
+
+/** A socket which sends and receives datagrams, see `listenDatagram()`. */
+export interface DatagramConn extends Closer {
+  /** The resource ID of the socket. */
+  rid: number;
+  /** Waits for and resolves to the next datagram, received into `p`. Without
+   * `p` a buffer large enough for any datagram is used.
+   */
+  receive(p?: Uint8Array): Promise<Datagram>;
+  /** Sends `p` as one datagram to `addr` and resolves to the number of bytes
+   * sent. Requires the `--allow-net` flag for `addr`.
+   */
+  send(p: Uint8Array, addr: Addr): Promise<number>;
+  /** Joins the multicast `group`, an IP address, on the default interface. */
+  joinMulticast(group: string): void;
+  /** Leaves the multicast `group`. */
+  leaveMulticast(group: string): void;
+  /** Return the address the socket is bound to. */
+  addr(): Addr;
+}
+
+export interface ListenDatagramOptions {
+  /** Sets `SO_BROADCAST`, to allow sending to broadcast addresses. Defaults to
+   * false.
+   */
+  broadcast?: boolean;
+  /** The multicast groups to join, see `DatagramConn.joinMulticast()`. */
+  multicast?: string[];
+}
+
+/** The largest payload of a datagram. */
+const MAX_DATAGRAM_SIZE = 65536;
+
+function setMulticast(rid: number, group: string, join: boolean): void {
+  const builder = flatbuffers.createBuilder();
+  const group_ = builder.createString(group);
+  const inner = msg.DatagramMulticast.createDatagramMulticast(
+    builder,
+    rid,
+    group_,
+    join
+  );
+  const baseRes = dispatch.sendSync(builder, msg.Any.DatagramMulticast, inner);
+  assert(baseRes == null);
+}
+
+class DatagramConnImpl implements DatagramConn {
+  constructor(readonly rid: number, private readonly localAddr: string) {}
+
+  async receive(p?: Uint8Array): Promise<Datagram> {
+    const buf = p || new Uint8Array(MAX_DATAGRAM_SIZE);
+    const builder = flatbuffers.createBuilder();
+    const inner = msg.DatagramReceive.createDatagramReceive(builder, this.rid);
+    const baseRes = await dispatch.sendAsync(
+      builder,
+      msg.Any.DatagramReceive,
+      inner,
+      buf
+    );
+    assert(baseRes != null);
+    assert(msg.Any.DatagramReceiveRes === baseRes!.innerType());
+    const res = new msg.DatagramReceiveRes();
+    assert(baseRes!.inner(res) != null);
+    return {
+      data: buf.subarray(0, res.nread()),
+      addr: res.remoteAddr()!,
+      truncated: res.truncated()
+    };
+  }
+
+  async send(p: Uint8Array, addr: Addr): Promise<number> {
+    const builder = flatbuffers.createBuilder();
+    const addr_ = builder.createString(addr);
+    const inner = msg.DatagramSend.createDatagramSend(builder, this.rid, addr_);
+    const baseRes = await dispatch.sendAsync(
+      builder,
+      msg.Any.DatagramSend,
+      inner,
+      p
+    );
+    assert(baseRes != null);
+    assert(msg.Any.DatagramSendRes === baseRes!.innerType());
+    const res = new msg.DatagramSendRes();
+    assert(baseRes!.inner(res) != null);
+    return res.nsent();
+  }
+
+  joinMulticast(group: string): void {
+    setMulticast(this.rid, group, true);
+  }
+
+  leaveMulticast(group: string): void {
+    setMulticast(this.rid, group, false);
+  }
+
+  close(): void {
+    close(this.rid);
+  }
+
+  addr(): Addr {
+    return this.localAddr;
+  }
+}
+
+/** Binds a socket for datagrams to the local address. The port `0` chooses
+ * one, which `addr()` tells. Closing the socket rejects the pending
+ * `receive()`s with `BadResource` errors.
+ *
+ *       const socket = Deno.listenDatagram("udp", "127.0.0.1:0");
+ *       await socket.send(new TextEncoder().encode("ping"), "127.0.0.1:4000");
+ *       const { data, addr } = await socket.receive();
+ *
+ * Requires the `--allow-net` flag for the address.
+ */
+export function listenDatagram(
+  network: "udp",
+  address: string,
+  options: ListenDatagramOptions = {}
+): DatagramConn {
+  assert(network === "udp");
+  const builder = flatbuffers.createBuilder();
+  const address_ = builder.createString(address);
+  const inner = msg.DatagramListen.createDatagramListen(
+    builder,
+    address_,
+    !!options.broadcast
+  );
+  const baseRes = dispatch.sendSync(builder, msg.Any.DatagramListen, inner);
+  assert(baseRes != null);
+  assert(msg.Any.DatagramListenRes === baseRes!.innerType());
+  const res = new msg.DatagramListenRes();
+  assert(baseRes!.inner(res) != null);
+  const conn = new DatagramConnImpl(res.rid(), res.localAddr()!);
+  try {
+    for (const group of options.multicast || []) {
+      conn.joinMulticast(group);
+    }
+  } catch (e) {
+    conn.close();
+    throw e;
+  }
+  return conn;
+}
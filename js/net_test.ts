@@ -17,29 +17,129 @@ testPerm({ net: true }, async function netCloseWhileAccept() {
     err = e;
   }
   assert(!!err);
-  assertEquals(err.kind, Deno.ErrorKind.Other);
-  assertEquals(err.message, "Listener has been closed");
+  assertEquals(err.kind, Deno.ErrorKind.BadResource);
+});
+
+testPerm({ net: true }, async function netCancelAccept() {
+  const listener = Deno.listen("tcp", "127.0.0.1:4518");
+  const p = listener.accept();
+  p.cancel();
+  let err;
+  try {
+    await p;
+  } catch (e) {
+    err = e;
+  }
+  assert(!!err);
+  assertEquals(err.kind, Deno.ErrorKind.Interrupted);
+  // The listener is left open.
+  const dialed = Deno.dial("tcp", "127.0.0.1:4518");
+  const conn = await listener.accept();
+  (await dialed).close();
+  conn.close();
+  listener.close();
+});
+
+testPerm({ net: true }, async function netCancelRead() {
+  const listener = Deno.listen("tcp", "127.0.0.1:4519");
+  const accepted = listener.accept();
+  const conn = await Deno.dial("tcp", "127.0.0.1:4519");
+  const server = await accepted;
+  const buf = new Uint8Array(1024);
+  const p = conn.read(buf);
+  p.cancel();
+  let err;
+  try {
+    await p;
+  } catch (e) {
+    err = e;
+  }
+  assert(!!err);
+  assertEquals(err.kind, Deno.ErrorKind.Interrupted);
+  // The connection is still usable.
+  await server.write(new Uint8Array([1, 2, 3]));
+  const readResult = await conn.read(buf);
+  assertEquals(3, readResult.nread);
+  assertEquals(3, buf[2]);
+  server.close();
+  conn.close();
+  listener.close();
 });
 
 testPerm({ net: true }, async function netConcurrentAccept() {
-  const listener = Deno.listen("tcp", ":4502");
-  let acceptErrCount = 0;
-  const checkErr = (e): void => {
-    assertEquals(e.kind, Deno.ErrorKind.Other);
-    if (e.message === "Listener has been closed") {
-      assertEquals(acceptErrCount, 1);
-    } else if (e.message === "Another accept task is ongoing") {
-      acceptErrCount++;
-    } else {
-      throw new Error("Unexpected error message");
-    }
-  };
-  const p = listener.accept().catch(checkErr);
-  const p1 = listener.accept().catch(checkErr);
-  await Promise.race([p, p1]);
-  listener.close();
-  await [p, p1];
-  assertEquals(acceptErrCount, 1);
+  const listener = Deno.listen("tcp", "127.0.0.1:4502");
+  const p = listener.accept();
+  const p1 = listener.accept();
+  const p2 = listener.accept();
+  const dialed = [
+    await Deno.dial("tcp", "127.0.0.1:4502"),
+    await Deno.dial("tcp", "127.0.0.1:4502")
+  ];
+  const accepted = await Promise.all([p, p1]);
+  for (const conn of accepted.concat(dialed)) {
+    conn.close();
+  }
+  listener.close();
+  let err;
+  try {
+    await p2;
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.BadResource);
+});
+
+testPerm({ net: true }, function netListenDoubleClose() {
+  const listener = Deno.listen("tcp", "127.0.0.1:4503");
+  listener.close();
+  let err;
+  try {
+    listener.close();
+  } catch (e) {
+    err = e;
+  }
+  assert(!!err);
+  assertEquals(err.kind, Deno.ErrorKind.BadResource);
+});
+
+testPerm({ net: true }, async function netListenEphemeralPort() {
+  const listener = Deno.listen("tcp", "127.0.0.1:0", { backlog: 16 });
+  const addr = listener.addr();
+  const port = Number(addr.split(":")[1]);
+  assert(addr.startsWith("127.0.0.1:"));
+  assert(port > 0);
+  const p = listener.accept();
+  const conn = await Deno.dial("tcp", addr, { timeout: 1000 });
+  const accepted = await p;
+  assertEquals(conn.remoteAddr, addr);
+  assertEquals(accepted.localAddr, addr);
+  assertEquals(accepted.remoteAddr, conn.localAddr);
+  conn.close();
+  accepted.close();
+  listener.close();
+});
+
+testPerm({ net: true }, function netListenReusePort() {
+  if (Deno.build.os === "win") {
+    return;
+  }
+  const listener = Deno.listen("tcp", "127.0.0.1:4504", { reusePort: true });
+  const listener2 = Deno.listen("tcp", "127.0.0.1:4504", { reusePort: true });
+  listener.close();
+  listener2.close();
+});
+
+testPerm({ net: true }, function netListenAddrInUse() {
+  const listener = Deno.listen("tcp", "127.0.0.1:4505");
+  let err;
+  try {
+    Deno.listen("tcp", "127.0.0.1:4505");
+  } catch (e) {
+    err = e;
+  }
+  assert(!!err);
+  assertEquals(err.kind, Deno.ErrorKind.AddrInUse);
+  listener.close();
 });
 
 testPerm({ net: true }, async function netDialListen() {
@@ -192,3 +292,249 @@ testPerm({ net: true }, async function netDoubleCloseWrite() {
   conn.close();
 });
 */
+
+testPerm({ net: true }, async function netDatagramSendReceive() {
+  const alice = Deno.listenDatagram("udp", "127.0.0.1:0");
+  const bob = Deno.listenDatagram("udp", "127.0.0.1:0");
+  assert(alice.addr().startsWith("127.0.0.1:"));
+  const nsent = await alice.send(new Uint8Array([1, 2, 3]), bob.addr());
+  assertEquals(nsent, 3);
+  const datagram = await bob.receive();
+  assertEquals(datagram.data, new Uint8Array([1, 2, 3]));
+  assertEquals(datagram.addr, alice.addr());
+  assertEquals(datagram.truncated, false);
+  alice.close();
+  bob.close();
+});
+
+testPerm({ net: true }, async function netDatagramTruncated() {
+  const alice = Deno.listenDatagram("udp", "127.0.0.1:0");
+  const bob = Deno.listenDatagram("udp", "127.0.0.1:0");
+  await alice.send(new Uint8Array([1, 2, 3, 4, 5]), bob.addr());
+  const datagram = await bob.receive(new Uint8Array(2));
+  assertEquals(datagram.data, new Uint8Array([1, 2]));
+  assertEquals(datagram.truncated, true);
+  alice.close();
+  bob.close();
+});
+
+testPerm({ net: true }, async function netDatagramReceiveSharedBuffer() {
+  // Buffers large enough for any datagram are received into directly, each
+  // pending receive into its own half of the same ArrayBuffer.
+  const alice = Deno.listenDatagram("udp", "127.0.0.1:0");
+  const bob = Deno.listenDatagram("udp", "127.0.0.1:0");
+  const size = 65536;
+  const buf = new Uint8Array(2 * size);
+  const receives = Promise.all([
+    bob.receive(buf.subarray(0, size)),
+    bob.receive(buf.subarray(size))
+  ]);
+  await alice.send(new Uint8Array([1, 1]), bob.addr());
+  await alice.send(new Uint8Array([2, 2, 2]), bob.addr());
+  const datagrams = await receives;
+  const offsets = datagrams.map(d => d.data.byteOffset).sort((a, b) => a - b);
+  assertEquals(offsets, [0, size]);
+  const payloads = datagrams
+    .map(d => Array.from(d.data))
+    .sort((a, b) => a.length - b.length);
+  assertEquals(payloads, [[1, 1], [2, 2, 2]]);
+  for (const { data, truncated } of datagrams) {
+    assert(!truncated);
+    const end = data.byteOffset + size;
+    const rest = buf.subarray(data.byteOffset + data.length, end);
+    assert(rest.every(byte => byte === 0));
+  }
+  alice.close();
+  bob.close();
+});
+
+testPerm({ net: true }, async function netDatagramCloseWhileReceive() {
+  const socket = Deno.listenDatagram("udp", "127.0.0.1:0");
+  const p = socket.receive();
+  socket.close();
+  let err;
+  try {
+    await p;
+  } catch (e) {
+    err = e;
+  }
+  assert(!!err);
+  assertEquals(err.kind, Deno.ErrorKind.BadResource);
+  assert(!(socket.rid in Deno.resources()));
+});
+
+testPerm({ net: true }, function netDatagramMulticastInvalid() {
+  const socket = Deno.listenDatagram("udp", "0.0.0.0:0", { broadcast: true });
+  let err;
+  try {
+    socket.joinMulticast("127.0.0.1");
+  } catch (e) {
+    err = e;
+  }
+  assert(!!err);
+  assertEquals(err.kind, Deno.ErrorKind.InvalidInput);
+  socket.close();
+});
+
+testPerm({ net: false }, function netDatagramPerm() {
+  let err;
+  try {
+    Deno.listenDatagram("udp", "127.0.0.1:0");
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+  assertEquals(err.name, "PermissionDenied");
+});
+
+testPerm({ read: true, write: true }, async function netUnixListenDial() {
+  if (Deno.build.os === "win") {
+    return;
+  }
+  const path = Deno.makeTempDirSync() + "/test.sock";
+  const listener = Deno.listen({ transport: "unix", path });
+  assertEquals(listener.addr(), path);
+  const p = listener.accept();
+  const conn = await Deno.dial({ transport: "unix", path });
+  const accepted = await p;
+  assertEquals(conn.remoteAddr, path);
+  assertEquals(accepted.localAddr, path);
+  await accepted.write(new Uint8Array([1, 2, 3]));
+  accepted.close();
+  const buf = new Uint8Array(8);
+  const { nread } = await conn.read(buf);
+  assertEquals(buf.subarray(0, nread), new Uint8Array([1, 2, 3]));
+  assert((await conn.read(buf)).eof);
+  conn.close();
+
+  // The socket file goes with the listener, pending accepts fail.
+  const pending = listener.accept();
+  listener.close();
+  let err;
+  try {
+    await pending;
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.BadResource);
+  err = undefined;
+  try {
+    Deno.lstatSync(path);
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.NotFound);
+});
+
+testPerm({ read: true, write: true }, async function netUnixNotSupported() {
+  if (Deno.build.os !== "win") {
+    return;
+  }
+  let err;
+  try {
+    Deno.listen({ transport: "unix", path: "test.sock" });
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.NotSupported);
+});
+
+testPerm({ net: true }, function netUnixListenPerm() {
+  let err;
+  try {
+    Deno.listen({ transport: "unix", path: "test.sock" });
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+  assertEquals(err.name, "PermissionDenied");
+});
+
+const tlsOptions = {
+  certFile: "tests/tls/localhost.crt",
+  keyFile: "tests/tls/localhost.key"
+};
+
+testPerm({ read: true, net: true }, async function netListenDialTLS() {
+  const listener = Deno.listenTLS("127.0.0.1:4506", tlsOptions);
+  const p = listener.accept();
+  const conn = await Deno.dialTLS("127.0.0.1:4506", {
+    hostname: "localhost",
+    caFile: "tests/tls/RootCA.pem"
+  });
+  const accepted = await p;
+  assertEquals(conn.remoteAddr, "127.0.0.1:4506");
+  assertEquals(accepted.localAddr, "127.0.0.1:4506");
+  await conn.write(new Uint8Array([1, 2, 3]));
+  const buf = new Uint8Array(8);
+  const { nread } = await accepted.read(buf);
+  assertEquals(buf.subarray(0, nread), new Uint8Array([1, 2, 3]));
+  await accepted.write(new Uint8Array([4, 5]));
+  const res = await conn.read(buf);
+  assertEquals(buf.subarray(0, res.nread), new Uint8Array([4, 5]));
+  accepted.close();
+  conn.close();
+  listener.close();
+});
+
+testPerm({ read: true, net: true }, async function netDialTLSNameMismatch() {
+  const listener = Deno.listenTLS("127.0.0.1:4507", tlsOptions);
+  const p = listener.accept();
+  let err;
+  try {
+    await Deno.dialTLS("127.0.0.1:4507", {
+      hostname: "deno.land",
+      caFile: "tests/tls/RootCA.pem"
+    });
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.InvalidData);
+  assert(err.message.startsWith("TLS handshake failed: "));
+  assert(err.message.includes("CertNotValidForName"));
+  // The server fails the accept, and can accept the next connection.
+  err = undefined;
+  try {
+    await p;
+  } catch (e) {
+    err = e;
+  }
+  assert(err.message.startsWith("TLS handshake failed: "));
+  listener.close();
+});
+
+testPerm({ read: true, net: true }, async function netDialTLSUnknownIssuer() {
+  const listener = Deno.listenTLS("127.0.0.1:4508", tlsOptions);
+  const p = listener.accept().catch(() => {});
+  let err;
+  try {
+    await Deno.dialTLS("127.0.0.1:4508", { hostname: "localhost" });
+  } catch (e) {
+    err = e;
+  }
+  assert(err.message.includes("UnknownIssuer"));
+  await p;
+  listener.close();
+});
+
+testPerm({ net: true }, function netListenTLSPerm() {
+  let err;
+  try {
+    Deno.listenTLS("127.0.0.1:4509", tlsOptions);
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+  assertEquals(err.name, "PermissionDenied");
+});
+
+testPerm({ read: true }, async function netDialTLSPerm() {
+  let err;
+  try {
+    await Deno.dialTLS("localhost:4509");
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+  assertEquals(err.name, "PermissionDenied");
+});
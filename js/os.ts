@@ -28,6 +28,7 @@ interface ResponseModuleMetaData {
   filename: string | undefined;
   mediaType: msg.MediaType;
   sourceCode: string | undefined;
+  typesUrl: string | undefined;
 }
 
 /** Check if running in terminal.
@@ -45,14 +46,100 @@ export function isTTY(): { stdin: boolean; stdout: boolean; stderr: boolean } {
   return { stdin: res.stdin(), stdout: res.stdout(), stderr: res.stderr() };
 }
 
-/** Exit the Deno process with optional exit code. */
-export function exit(exitCode = 0): never {
+/** A function which runs before the program exits, given the exit code. */
+export type UnloadHook = (exitCode: number) => void;
+
+const unloadHooks: UnloadHook[] = [];
+let unloading = false;
+
+/** How long the unload hooks may take together, in milliseconds. Once they
+ * took longer, those which did not run yet are skipped.
+ */
+export const UNLOAD_HOOKS_BUDGET = 1000;
+
+/** Thrown by `exit()` in an unload hook, which ends the hook. */
+class HookExit {
+  constructor(readonly exitCode: number) {}
+}
+
+/** Registers `hook` to run when `Deno.exit()` is called and when the program
+ * is done because nothing is left to do, not after an uncaught error. Hooks
+ * run synchronously, in the order they were added, so promises they create
+ * are not waited for. A hook which calls `Deno.exit()` ends, the exit code
+ * becomes the one it gave and the other hooks still run. Returns a function
+ * which removes the hook.
+ *
+ *       Deno.addUnloadHook(code => {
+ *         Deno.writeFileSync("status.txt", encoder.encode(`exit ${code}`));
+ *       });
+ */
+export function addUnloadHook(hook: UnloadHook): () => void {
+  unloadHooks.push(hook);
+  return (): void => {
+    const index = unloadHooks.indexOf(hook);
+    if (index >= 0) {
+      unloadHooks.splice(index, 1);
+    }
+  };
+}
+
+/** Runs the unload hooks, once. Returns the exit code, which a hook may have
+ * changed.
+ */
+function runUnloadHooks(exitCode: number): number {
+  unloading = true;
+  const start = Date.now();
+  const hooks = unloadHooks.splice(0);
+  for (let i = 0; i < hooks.length; i++) {
+    if (Date.now() - start > UNLOAD_HOOKS_BUDGET) {
+      console.error(
+        `Warning: ${hooks.length - i} unload hook(s) skipped, the hooks ` +
+          `took longer than ${UNLOAD_HOOKS_BUDGET} ms`
+      );
+      break;
+    }
+    try {
+      hooks[i](exitCode);
+    } catch (e) {
+      if (e instanceof HookExit) {
+        exitCode = e.exitCode;
+      } else {
+        console.error("Uncaught error in unload hook:", e);
+      }
+    }
+  }
+  return exitCode;
+}
+
+function sendExit(exitCode: number): never {
   const builder = flatbuffers.createBuilder();
   const inner = msg.Exit.createExit(builder, exitCode);
   sendSync(builder, msg.Any.Exit, inner);
   return util.unreachable();
 }
 
+/** Exit the Deno process with optional exit code. The unload hooks run
+ * first, see `Deno.addUnloadHook()`.
+ */
+export function exit(exitCode = 0): never {
+  if (unloading) {
+    throw new HookExit(exitCode);
+  }
+  return sendExit(runUnloadHooks(exitCode));
+}
+
+// @internal
+/** Called once the event loop drained, the program exits normally after. */
+export function unload(): void {
+  if (unloading) {
+    return;
+  }
+  const exitCode = runUnloadHooks(0);
+  if (exitCode !== 0) {
+    sendExit(exitCode);
+  }
+}
+
 const decoder = new TextDecoder();
 
 // @internal
@@ -86,7 +173,8 @@ export function fetchModuleMetaData(
     moduleName: fetchModuleMetaDataRes.moduleName() || undefined,
     filename: fetchModuleMetaDataRes.filename() || undefined,
     mediaType: fetchModuleMetaDataRes.mediaType(),
-    sourceCode
+    sourceCode,
+    typesUrl: fetchModuleMetaDataRes.typesUrl() || undefined
   };
 }
 
@@ -98,6 +186,13 @@ function setEnv(key: string, value: string): void {
   sendSync(builder, msg.Any.SetEnv, inner);
 }
 
+function deleteEnv(key: string): void {
+  const builder = flatbuffers.createBuilder();
+  const key_ = builder.createString(key);
+  const inner = msg.DeleteEnv.createDeleteEnv(builder, key_);
+  sendSync(builder, msg.Any.DeleteEnv, inner);
+}
+
 function createEnv(inner: msg.EnvironRes): { [index: string]: string } {
   const env: { [index: string]: string } = {};
 
@@ -110,22 +205,15 @@ function createEnv(inner: msg.EnvironRes): { [index: string]: string } {
     set(obj, prop: string, value: string) {
       setEnv(prop, value);
       return Reflect.set(obj, prop, value);
+    },
+    deleteProperty(obj, prop: string) {
+      deleteEnv(prop);
+      return Reflect.deleteProperty(obj, prop);
     }
   });
 }
 
-/** Returns a snapshot of the environment variables at invocation. Mutating a
- * property in the object will set that variable in the environment for
- * the process. The environment object will only accept `string`s
- * as values.
- *
- *       const myEnv = Deno.env();
- *       console.log(myEnv.SHELL);
- *       myEnv.TEST_VAR = "HELLO";
- *       const newEnv = Deno.env();
- *       console.log(myEnv.TEST_VAR == newEnv.TEST_VAR);
- */
-export function env(): { [index: string]: string } {
+function getEnv(): { [index: string]: string } {
   /* Ideally we could write
   const res = sendSync({
     command: msg.Command.ENV,
@@ -141,6 +229,45 @@ export function env(): { [index: string]: string } {
   return createEnv(res);
 }
 
+export interface Env {
+  /** Returns a snapshot of the environment variables at invocation. Mutating
+   * a property in the object will set that variable in the environment for
+   * the process, deleting it deletes the variable. The environment object
+   * will only accept `string`s as values.
+   *
+   *       const myEnv = Deno.env();
+   *       console.log(myEnv.SHELL);
+   *       myEnv.TEST_VAR = "HELLO";
+   *       const newEnv = Deno.env();
+   *       console.log(myEnv.TEST_VAR == newEnv.TEST_VAR);
+   */
+  (): { [index: string]: string };
+
+  /** Sets the environment variable `key` for the process and the
+   * subprocesses it runs afterwards. An empty `value` sets the variable to
+   * the empty string, on Windows too.
+   *
+   *       Deno.env.set("FOO", "bar");
+   */
+  set(key: string, value: string): void;
+
+  /** Deletes the environment variable `key`. On Windows variable names are
+   * case-insensitive, deleting "foo" deletes "FOO".
+   *
+   *       Deno.env.delete("FOO");
+   */
+  delete(key: string): void;
+}
+
+/** The environment variables of the process, see `Env`. Reading and writing
+ * them requires the `--allow-env` flag, or the variable in
+ * `--allow-env=<names>`.
+ */
+export const env: Env = Object.assign(getEnv, {
+  set: setEnv,
+  delete: deleteEnv
+});
+
 /** Send to the privileged side that we have setup and are ready. */
 function sendStart(): msg.StartRes {
   const builder = flatbuffers.createBuilder();
@@ -23,6 +23,82 @@ test(function envFailure() {
   assert(caughtError);
 });
 
+testPerm({ env: true }, function envSetDelete() {
+  Deno.env.set("DENO_ENV_TEST", "Hello");
+  assertEquals(Deno.env().DENO_ENV_TEST, "Hello");
+  Deno.env.set("DENO_ENV_TEST", "");
+  assertEquals(Deno.env().DENO_ENV_TEST, "");
+  Deno.env.delete("DENO_ENV_TEST");
+  assertEquals(Deno.env().DENO_ENV_TEST, undefined);
+  // Deleting a variable which is not set is not an error.
+  Deno.env.delete("DENO_ENV_TEST");
+});
+
+testPerm({ env: true }, function envDeleteProperty() {
+  const env = Deno.env();
+  env.DENO_ENV_TEST = "Hello";
+  delete env.DENO_ENV_TEST;
+  assertEquals(env.DENO_ENV_TEST, undefined);
+  assertEquals(Deno.env().DENO_ENV_TEST, undefined);
+});
+
+testPerm({ env: true }, function envCaseInsensitiveOnWindows() {
+  Deno.env.set("DENO_ENV_TEST", "Hello");
+  Deno.env.delete("deno_env_test");
+  const expected = Deno.build.os === "win" ? undefined : "Hello";
+  assertEquals(Deno.env().DENO_ENV_TEST, expected);
+  Deno.env.delete("DENO_ENV_TEST");
+});
+
+testPerm({ env: true }, function envInvalidName() {
+  let err;
+  try {
+    Deno.env.set("A=B", "C");
+  } catch (e) {
+    err = e;
+  }
+  assert(err instanceof Deno.DenoError);
+  assertEquals(err.kind, Deno.ErrorKind.InvalidInput);
+});
+
+testPerm({ env: true, run: true }, async function envSubprocess() {
+  Deno.env.set("DENO_ENV_TEST", "Hello");
+  const p = Deno.run({
+    args: [
+      "python",
+      "-c",
+      "import os, sys; sys.stdout.write(os.environ.get('DENO_ENV_TEST', 'unset'))"
+    ],
+    stdout: "piped"
+  });
+  const set = new TextDecoder().decode(await p.output());
+  p.close();
+  Deno.env.delete("DENO_ENV_TEST");
+  const q = Deno.run({
+    args: [
+      "python",
+      "-c",
+      "import os, sys; sys.stdout.write(os.environ.get('DENO_ENV_TEST', 'unset'))"
+    ],
+    stdout: "piped"
+  });
+  const deleted = new TextDecoder().decode(await q.output());
+  q.close();
+  assertEquals(set, "Hello");
+  assertEquals(deleted, "unset");
+});
+
+test(function envSetFailure() {
+  let caughtError = false;
+  try {
+    Deno.env.set("DENO_ENV_TEST", "Hello");
+  } catch (err) {
+    caughtError = true;
+    assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+  }
+  assert(caughtError);
+});
+
 test(function osPid() {
   console.log("pid", Deno.pid);
   assert(Deno.pid > 0);
@@ -4,10 +4,87 @@ import { sendSync } from "./dispatch";
 import * as flatbuffers from "./flatbuffers";
 import { assert } from "./util";
 
+export type PerformanceEntryType = "mark" | "measure";
+
+/** A mark or measure of `performance`. The times are in milliseconds since
+ * Deno's start, coarsened like `performance.now()`.
+ */
+export class PerformanceEntry {
+  constructor(
+    readonly name: string,
+    readonly entryType: PerformanceEntryType,
+    readonly startTime: number,
+    readonly duration: number
+  ) {}
+
+  toJSON(): object {
+    return {
+      name: this.name,
+      entryType: this.entryType,
+      startTime: this.startTime,
+      duration: this.duration
+    };
+  }
+}
+
+function toEntry(entry: msg.PerformanceEntry): PerformanceEntry {
+  return new PerformanceEntry(
+    entry.name()!,
+    entry.entryType()! as PerformanceEntryType,
+    entry.startTime(),
+    entry.duration()
+  );
+}
+
+function toEntryRes(baseRes: msg.Base | null): PerformanceEntry {
+  assert(baseRes != null);
+  assert(msg.Any.PerformanceEntryRes === baseRes!.innerType());
+  const res = new msg.PerformanceEntryRes();
+  assert(baseRes!.inner(res) != null);
+  return toEntry(res.entry()!);
+}
+
+function getEntries(name?: string, type?: string): PerformanceEntry[] {
+  if (type !== undefined && type !== "mark" && type !== "measure") {
+    return [];
+  }
+  const builder = flatbuffers.createBuilder();
+  const name_ = name === undefined ? 0 : builder.createString(name);
+  const type_ = type === undefined ? 0 : builder.createString(type);
+  const inner = msg.PerformanceGetEntries.createPerformanceGetEntries(
+    builder,
+    name_,
+    type_
+  );
+  const baseRes = sendSync(builder, msg.Any.PerformanceGetEntries, inner);
+  assert(baseRes != null);
+  assert(msg.Any.PerformanceGetEntriesRes === baseRes!.innerType());
+  const res = new msg.PerformanceGetEntriesRes();
+  assert(baseRes!.inner(res) != null);
+  const entries: PerformanceEntry[] = [];
+  for (let i = 0; i < res.entriesLength(); i++) {
+    entries.push(toEntry(res.entries(i)!));
+  }
+  return entries;
+}
+
+function clear(type: PerformanceEntryType, name?: string): void {
+  const builder = flatbuffers.createBuilder();
+  const type_ = builder.createString(type);
+  const name_ = name === undefined ? 0 : builder.createString(name);
+  const inner = msg.PerformanceClear.createPerformanceClear(
+    builder,
+    type_,
+    name_
+  );
+  sendSync(builder, msg.Any.PerformanceClear, inner);
+}
+
 export class Performance {
   /** Returns a current time from Deno's start.
    *  In milliseconds. Flag --allow-high-precision give
-   *  a precise measure.
+   *  a precise measure, without it the time is coarsened to
+   *  milliseconds, with a random jitter against timing attacks.
    *
    *       const t = performance.now();
    *       console.log(`${t} ms since start!`);
@@ -21,4 +98,65 @@ export class Performance {
     assert(baseRes.inner(res) != null);
     return res.seconds().toFloat64() * 1e3 + res.subsecNanos() / 1e6;
   }
+
+  /** Records the current time as the mark `name`.
+   *
+   *       performance.mark("start");
+   */
+  mark(name: string): PerformanceEntry {
+    const builder = flatbuffers.createBuilder();
+    const name_ = builder.createString(name);
+    const inner = msg.PerformanceMark.createPerformanceMark(builder, name_);
+    return toEntryRes(sendSync(builder, msg.Any.PerformanceMark, inner));
+  }
+
+  /** Records the time between the marks `startMark`, or Deno's start, and
+   * `endMark`, or now, as the measure `name`. For marks of the same name the
+   * last one counts, a mark which does not exist throws `NotFound`.
+   *
+   *       performance.mark("start");
+   *       doWork();
+   *       const { duration } = performance.measure("work", "start");
+   */
+  measure(
+    name: string,
+    startMark?: string,
+    endMark?: string
+  ): PerformanceEntry {
+    const builder = flatbuffers.createBuilder();
+    const name_ = builder.createString(name);
+    const start_ =
+      startMark === undefined ? 0 : builder.createString(startMark);
+    const end_ = endMark === undefined ? 0 : builder.createString(endMark);
+    const inner = msg.PerformanceMeasure.createPerformanceMeasure(
+      builder,
+      name_,
+      start_,
+      end_
+    );
+    return toEntryRes(sendSync(builder, msg.Any.PerformanceMeasure, inner));
+  }
+
+  /** All marks and measures, sorted by their start time. */
+  getEntries(): PerformanceEntry[] {
+    return getEntries();
+  }
+
+  getEntriesByName(name: string, type?: string): PerformanceEntry[] {
+    return getEntries(name, type);
+  }
+
+  getEntriesByType(type: string): PerformanceEntry[] {
+    return getEntries(undefined, type);
+  }
+
+  /** Removes the marks named `name`, all of them without a name. */
+  clearMarks(name?: string): void {
+    clear("mark", name);
+  }
+
+  /** Removes the measures named `name`, all of them without a name. */
+  clearMeasures(name?: string): void {
+    clear("measure", name);
+  }
 }
@@ -1,10 +1,67 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
-import { testPerm, assert } from "./test_util.ts";
+import { test, testPerm, assert, assertEquals } from "./test_util.ts";
 
 testPerm({ highPrecision: false }, function now() {
   const start = performance.now();
   setTimeout(() => {
     const end = performance.now();
-    assert(end - start >= 10);
+    // The coarsened clock is up to a millisecond off at both ends.
+    assert(end - start >= 9);
   }, 10);
 });
+
+testPerm({ highPrecision: false }, function nowCoarsened() {
+  let last = performance.now();
+  for (let i = 0; i < 1000; i++) {
+    const now = performance.now();
+    assert(Number.isInteger(now), `${now} is not a whole millisecond`);
+    assert(now >= last);
+    last = now;
+  }
+});
+
+testPerm({ highPrecision: true }, function nowHighPrecision() {
+  let fractional = false;
+  for (let i = 0; i < 100 && !fractional; i++) {
+    fractional = !Number.isInteger(performance.now());
+  }
+  assert(fractional);
+});
+
+test(function performanceMarkAndMeasure() {
+  const start = performance.mark("test-start");
+  assertEquals(start.name, "test-start");
+  assertEquals(start.entryType, "mark");
+  assertEquals(start.duration, 0);
+  const end = performance.mark("test-end");
+  const measure = performance.measure("test", "test-start", "test-end");
+  assertEquals(measure.entryType, "measure");
+  assertEquals(measure.startTime, start.startTime);
+  assertEquals(measure.duration, end.startTime - start.startTime);
+  const sinceStart = performance.measure("test-since-start");
+  assertEquals(sinceStart.startTime, 0);
+
+  assertEquals(performance.getEntriesByName("test"), [measure]);
+  assertEquals(performance.getEntriesByName("test-end", "mark"), [end]);
+  assertEquals(performance.getEntriesByName("test-end", "measure"), []);
+  assertEquals(performance.getEntriesByType("paint"), []);
+  const names = performance.getEntries().map(e => e.name);
+  assertEquals(names.slice(0, 1), ["test-since-start"]);
+
+  performance.clearMarks("test-start");
+  assertEquals(performance.getEntriesByName("test-start"), []);
+  performance.clearMarks();
+  performance.clearMeasures();
+  assertEquals(performance.getEntries(), []);
+});
+
+test(function performanceMeasureMissingMark() {
+  let err;
+  try {
+    performance.measure("missing", "no-such-mark");
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.NotFound);
+  assertEquals(err.name, "NotFound");
+});
@@ -34,14 +34,7 @@ function createPermissions(inner: msg.PermissionsRes): Permissions {
   };
 }
 
-/** Inspect granted permissions for the current program.
- *
- *       if (Deno.permissions().read) {
- *         const file = await Deno.readFile("example.test");
- *         // ...
- *       }
- */
-export function permissions(): Permissions {
+function getPermissions(): Permissions {
   const baseRes = dispatch.sendSync(...getReq())!;
   assert(msg.Any.PermissionsRes === baseRes.innerType());
   const res = new msg.PermissionsRes();
@@ -73,3 +66,114 @@ function revokeReq(
 export function revokePermission(permission: Permission): void {
   dispatch.sendSync(...revokeReq(permission));
 }
+
+/** A permission, for one resource of it if one is given. Without one `read`
+ * is for all paths, `net` for all hosts, and so on.
+ */
+export interface PermissionDescriptor {
+  name: Permission;
+  /** A path to read or write. */
+  path?: string;
+  /** A "host" or a "host:port" pair. */
+  host?: string;
+  /** A command to run. */
+  command?: string;
+  /** An environment variable. */
+  variable?: string;
+}
+
+/** "prompt" when access would be prompted for, which it can only be with a
+ * terminal to answer on and without `--no-prompt`.
+ */
+export type PermissionState = "granted" | "denied" | "prompt";
+
+export interface PermissionStatus {
+  readonly state: PermissionState;
+}
+
+const permissionStates: { [state: number]: PermissionState } = {
+  [msg.PermissionState.Granted]: "granted",
+  [msg.PermissionState.Denied]: "denied",
+  [msg.PermissionState.Prompt]: "prompt"
+};
+
+function queryReq(
+  desc: PermissionDescriptor,
+  request: boolean
+): [flatbuffers.Builder, msg.Any, flatbuffers.Offset] {
+  const builder = flatbuffers.createBuilder();
+  const name = builder.createString(desc.name);
+  const resource = desc.path || desc.host || desc.command || desc.variable;
+  const resource_ = resource === undefined ? 0 : builder.createString(resource);
+  msg.PermissionQuery.startPermissionQuery(builder);
+  msg.PermissionQuery.addName(builder, name);
+  if (resource_) {
+    msg.PermissionQuery.addResource(builder, resource_);
+  }
+  msg.PermissionQuery.addRequest(builder, request);
+  const inner = msg.PermissionQuery.endPermissionQuery(builder);
+  return [builder, msg.Any.PermissionQuery, inner];
+}
+
+function sendQuery(
+  desc: PermissionDescriptor,
+  request: boolean
+): PermissionStatus {
+  const baseRes = dispatch.sendSync(...queryReq(desc, request))!;
+  assert(msg.Any.PermissionQueryRes === baseRes.innerType());
+  const res = new msg.PermissionQueryRes();
+  assert(baseRes.inner(res) != null);
+  return { state: permissionStates[res.state()] };
+}
+
+/** The state of a permission. Taking no permission itself, it tells granted
+ * for paths within those of `--allow-read=<paths>`, and so on.
+ *
+ *       const status = await Deno.permissions.query({ name: "net" });
+ *       if (status.state === "granted") {
+ *         // ...
+ *       }
+ */
+async function query(desc: PermissionDescriptor): Promise<PermissionStatus> {
+  return sendQuery(desc, false);
+}
+
+/** Like `query()`, but prompts for a permission which would be prompted for,
+ * and tells the answer. Without a prompt it resolves to denied.
+ *
+ *       const status = await Deno.permissions.request({
+ *         name: "read",
+ *         path: "/etc/hosts"
+ *       });
+ */
+async function request(desc: PermissionDescriptor): Promise<PermissionStatus> {
+  return sendQuery(desc, true);
+}
+
+/** Revokes the permission `desc.name`, all of it as `revokePermission()`
+ * does, and tells its state after.
+ */
+async function revoke(desc: PermissionDescriptor): Promise<PermissionStatus> {
+  revokePermission(desc.name);
+  return sendQuery(desc, false);
+}
+
+export interface PermissionsApi {
+  /** Inspect granted permissions for the current program.
+   *
+   *       if (Deno.permissions().read) {
+   *         const file = await Deno.readFile("example.test");
+   *         // ...
+   *       }
+   */
+  (): Permissions;
+  query: typeof query;
+  request: typeof request;
+  revoke: typeof revoke;
+}
+
+export const permissions: PermissionsApi = Object.assign(getPermissions, {
+  query,
+  request,
+  revoke
+});
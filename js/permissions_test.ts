@@ -1,5 +1,5 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
-import { testPerm, assert, assertEquals } from "./test_util.ts";
+import { test, testPerm, assert, assertEquals } from "./test_util.ts";
 
 const knownPermissions: Deno.Permission[] = [
   "run",
@@ -10,6 +10,33 @@ const knownPermissions: Deno.Permission[] = [
   "highPrecision"
 ];
 
+testPerm({ read: true }, async function permissionsQueryGranted() {
+  const status = await Deno.permissions.query({ name: "read" });
+  assertEquals(status.state, "granted");
+  const path = await Deno.permissions.query({ name: "read", path: "/tmp/x" });
+  assertEquals(path.state, "granted");
+  // Nothing is left to prompt for.
+  const requested = await Deno.permissions.request({ name: "read" });
+  assertEquals(requested.state, "granted");
+});
+
+testPerm({ read: true }, async function permissionsQueryNotGranted() {
+  const status = await Deno.permissions.query({ name: "net", host: "x.y" });
+  assert(status.state !== "granted");
+  const write = await Deno.permissions.query({ name: "write", path: "/tmp" });
+  assert(write.state !== "granted");
+});
+
+test(async function permissionsQueryUnknown() {
+  let err;
+  try {
+    await Deno.permissions.query({ name: "disk" as Deno.Permission });
+  } catch (e) {
+    err = e;
+  }
+  assert(err instanceof Deno.errors.InvalidInput);
+});
+
 for (let grant of knownPermissions) {
   testPerm({ [grant]: true }, function envGranted() {
     const perms = Deno.permissions();
@@ -26,3 +53,9 @@ for (let grant of knownPermissions) {
     }
   });
 }
+
+testPerm({ env: true }, async function permissionsRevoke() {
+  const status = await Deno.permissions.revoke({ name: "env" });
+  assert(status.state !== "granted");
+  assertEquals(Deno.permissions().env, false);
+});
@@ -110,6 +110,11 @@ export class Process {
     }
   }
 
+  /** Sends the signal `signo` to the process, see `Deno.kill()`. */
+  kill(signo: number): void {
+    kill(this.pid, signo);
+  }
+
   close(): void {
     close(this.rid);
   }
@@ -134,6 +139,20 @@ function stdioMap(s: ProcessStdio): msg.ProcessStdio {
   }
 }
 
+/** Sends the signal `signo`, e.g. 15 for SIGTERM, to the process `pid`.
+ * Requires the `--allow-run` flag, the commands of `--allow-run=<commands>`
+ * are not enough. On Windows the process is terminated whatever the signal.
+ *
+ *       const p = Deno.run({ args: ["sleep", "10"] });
+ *       Deno.kill(p.pid, 15);
+ *       console.log(await p.status()); // { signal: 15, success: false }
+ */
+export function kill(pid: number, signo: number): void {
+  const builder = flatbuffers.createBuilder();
+  const inner = msg.Kill.createKill(builder, pid, signo);
+  dispatch.sendSync(builder, msg.Any.Kill, inner);
+}
+
 /**
  * Spawns new subprocess.
  *
@@ -216,3 +216,86 @@ testPerm({ run: true }, async function runEnv() {
   assertEquals(s, "01234567");
   p.close();
 });
+
+test(function killPermissions() {
+  let caughtError = false;
+  try {
+    // The permission check comes before the signal is sent.
+    Deno.kill(Deno.pid, 15);
+  } catch (e) {
+    caughtError = true;
+    assertEquals(e.kind, Deno.ErrorKind.PermissionDenied);
+    assertEquals(e.name, "PermissionDenied");
+  }
+  assert(caughtError);
+});
+
+testPerm({ run: true }, async function killSuccess() {
+  const p = run({
+    args: ["python", "-c", "import time; time.sleep(10)"]
+  });
+  p.kill(15);
+  const status = await p.status();
+  assertEquals(status.success, false);
+  if (Deno.build.os === "win") {
+    // Terminated, there are no signals on windows.
+    assertEquals(status.code, 1);
+    assertEquals(status.signal, undefined);
+  } else {
+    assertEquals(status.code, undefined);
+    assertEquals(status.signal, 15);
+  }
+  p.close();
+});
+
+testPerm({ run: true }, function killNotFound() {
+  let error;
+  try {
+    Deno.kill(2147483647, 15);
+  } catch (e) {
+    error = e;
+  }
+  assert(error instanceof DenoError);
+});
+
+testPerm({ run: true }, async function runClosesResources() {
+  const before = Object.keys(Deno.resources()).map(Number);
+  const p = run({
+    args: ["python", "-c", "print('hello')"],
+    stdin: "piped",
+    stdout: "piped"
+  });
+  assertEquals(Deno.resources()[p.rid], "child");
+  const opened = Object.keys(Deno.resources())
+    .map(Number)
+    .filter(rid => !before.includes(rid));
+  assertEquals(opened.length, 3);
+  p.stdin!.close();
+  await p.output();
+  await p.status();
+  p.close();
+  const after = Deno.resources();
+  for (const rid of opened) {
+    assertEquals(after[rid], undefined);
+  }
+});
+
+testPerm({ read: true, run: true }, async function runReapsChild() {
+  if (Deno.build.os !== "linux") {
+    return; // Only linux has /proc.
+  }
+  const p = run({
+    args: ["python", "-c", "pass"]
+  });
+  await p.status();
+  // A zombie keeps its /proc entry until it is reaped.
+  let error;
+  try {
+    Deno.statSync(`/proc/${p.pid}`);
+  } catch (e) {
+    error = e;
+  }
+  assert(error instanceof DenoError);
+  assertEquals(error.kind, ErrorKind.NotFound);
+  p.close();
+});
@@ -5,10 +5,24 @@ import * as dispatch from "./dispatch";
 import { FileInfo, FileInfoImpl } from "./file_info";
 import { assert } from "./util";
 
-function req(path: string): [flatbuffers.Builder, msg.Any, flatbuffers.Offset] {
+export interface ReadDirOptions {
+  /** Also list the entries of the directories below, but not of those which
+   * symlinks point to. Defaults to false.
+   */
+  recursive?: boolean;
+}
+
+function req(
+  path: string,
+  options: ReadDirOptions
+): [flatbuffers.Builder, msg.Any, flatbuffers.Offset] {
   const builder = flatbuffers.createBuilder();
   const path_ = builder.createString(path);
-  const inner = msg.ReadDir.createReadDir(builder, path_);
+  const inner = msg.ReadDir.createReadDir(
+    builder,
+    path_,
+    !!options.recursive
+  );
   return [builder, msg.Any.ReadDir, inner];
 }
 
@@ -25,18 +39,31 @@ function res(baseRes: null | msg.Base): FileInfo[] {
 }
 
 /** Reads the directory given by path and returns a list of file info
- * synchronously.
+ * synchronously. The file info of an entry, its type and size too, comes
+ * with it, that of a symlink is of the link itself. Requires the
+ * `--allow-read` flag for `path`, which covers everything below it.
  *
  *       const files = Deno.readDirSync("/");
+ *       const tree = Deno.readDirSync("src", { recursive: true });
  */
-export function readDirSync(path: string): FileInfo[] {
-  return res(dispatch.sendSync(...req(path)));
+export function readDirSync(
+  path: string,
+  options: ReadDirOptions = {}
+): FileInfo[] {
+  return res(dispatch.sendSync(...req(path, options)));
 }
 
-/** Reads the directory given by path and returns a list of file info.
+/** Reads the directory given by path and returns a list of file info. The
+ * file info of an entry, its type and size too, comes with it, that of a
+ * symlink is of the link itself. Requires the `--allow-read` flag for `path`,
+ * which covers everything below it.
  *
  *       const files = await Deno.readDir("/");
+ *       const tree = await Deno.readDir("src", { recursive: true });
  */
-export async function readDir(path: string): Promise<FileInfo[]> {
-  return res(await dispatch.sendAsync(...req(path)));
+export async function readDir(
+  path: string,
+  options: ReadDirOptions = {}
+): Promise<FileInfo[]> {
+  return res(await dispatch.sendAsync(...req(path, options)));
 }
@@ -83,3 +83,45 @@ testPerm({ read: false }, async function readDirPerm() {
   }
   assert(caughtError);
 });
+
+testPerm({ read: true, write: true }, function readDirSyncRecursive() {
+  const root = Deno.makeTempDirSync();
+  // A deep tree, a directory is read to the end before the next is opened
+  let dir = root;
+  for (let i = 0; i < 300; i++) {
+    dir += "/d";
+  }
+  Deno.mkdirSync(dir, true);
+  Deno.writeFileSync(dir + "/leaf.txt", new TextEncoder().encode("leaf"));
+
+  const files = Deno.readDirSync(root, { recursive: true });
+  assertEquals(files.length, 301);
+  assertEquals(files.filter(f => f.isDirectory()).length, 300);
+  const leaf = files.find(f => f.name === "leaf.txt")!;
+  assertEquals(leaf.path, dir + "/leaf.txt");
+  assert(leaf.isFile());
+  assertEquals(leaf.len, 4);
+  // Only the entries of the root without the option
+  assertEquals(Deno.readDirSync(root).length, 1);
+  Deno.removeSync(root, { recursive: true });
+});
+
+testPerm({ read: true, write: true }, async function readDirSymlinkCycle() {
+  if (Deno.build.os === "win") {
+    return;
+  }
+  const root = Deno.makeTempDirSync();
+  Deno.mkdirSync(root + "/a/b", true);
+  Deno.symlinkSync(root, root + "/a/b/up");
+  Deno.symlinkSync(root + "/a", root + "/a/b/back");
+
+  const files = await Deno.readDir(root, { recursive: true });
+  const names = files.map(f => f.name).sort();
+  assertEquals(names, ["a", "b", "back", "up"]);
+  const links = files.filter(f => f.name === "up" || f.name === "back");
+  for (const link of links) {
+    assert(link.isSymlink());
+    assert(!link.isDirectory());
+  }
+  Deno.removeSync(root, { recursive: true });
+});
@@ -21,7 +21,8 @@ function res(baseRes: null | msg.Base): string {
   return path!;
 }
 
-/** Returns the destination of the named symbolic link synchronously.
+/** Returns the destination of the named symbolic link synchronously. Fails
+ * with `InvalidInput` if the file is not a symbolic link.
  *
  *       const targetPath = Deno.readlinkSync("symlink/path");
  */
@@ -29,7 +30,8 @@ export function readlinkSync(name: string): string {
   return res(dispatch.sendSync(...req(name)));
 }
 
-/** Returns the destination of the named symbolic link.
+/** Returns the destination of the named symbolic link. Fails with
+ * `InvalidInput` if the file is not a symbolic link.
  *
  *       const targetPath = await Deno.readlink("symlink/path");
  */
@@ -6,13 +6,9 @@ testPerm({ write: true, read: true }, function readlinkSyncSuccess() {
   const target = testDir + "/target";
   const symlink = testDir + "/symln";
   Deno.mkdirSync(target);
-  // TODO Add test for Windows once symlink is implemented for Windows.
-  // See https://github.com/denoland/deno/issues/815.
-  if (Deno.build.os !== "win") {
-    Deno.symlinkSync(target, symlink);
-    const targetPath = Deno.readlinkSync(symlink);
-    assertEquals(targetPath, target);
-  }
+  Deno.symlinkSync(target, symlink);
+  const targetPath = Deno.readlinkSync(symlink);
+  assertEquals(targetPath, target);
 });
 
 testPerm({ read: false }, async function readlinkSyncPerm() {
@@ -40,18 +36,36 @@ testPerm({ read: true }, function readlinkSyncNotFound() {
   assertEquals(data, undefined);
 });
 
+testPerm({ write: true, read: true }, function readlinkSyncNotSymlink() {
+  const testDir = Deno.makeTempDirSync();
+  const file = testDir + "/file";
+  Deno.writeFileSync(file, new Uint8Array());
+  let err;
+  try {
+    Deno.readlinkSync(file);
+  } catch (e) {
+    err = e;
+  }
+  assert(!!err);
+  assertEquals(err.kind, Deno.ErrorKind.InvalidInput);
+  assertEquals(err.name, "InvalidInput");
+});
+
+testPerm({ write: true, read: true }, function readlinkSyncDangling() {
+  const testDir = Deno.makeTempDirSync();
+  const symlink = testDir + "/symln";
+  Deno.symlinkSync("missing", symlink, "file");
+  assertEquals(Deno.readlinkSync(symlink), "missing");
+});
+
 testPerm({ write: true, read: true }, async function readlinkSuccess() {
   const testDir = Deno.makeTempDirSync();
   const target = testDir + "/target";
   const symlink = testDir + "/symln";
   Deno.mkdirSync(target);
-  // TODO Add test for Windows once symlink is implemented for Windows.
-  // See https://github.com/denoland/deno/issues/815.
-  if (Deno.build.os !== "win") {
-    Deno.symlinkSync(target, symlink);
-    const targetPath = await Deno.readlink(symlink);
-    assertEquals(targetPath, target);
-  }
+  Deno.symlinkSync(target, symlink);
+  const targetPath = await Deno.readlink(symlink);
+  assertEquals(targetPath, target);
 });
 
 testPerm({ read: false }, async function readlinkPerm() {
@@ -333,3 +333,50 @@ testPerm({ write: false }, async function removeAllPerm() {
   assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
   assertEquals(err.name, "PermissionDenied");
 });
+
+testPerm({ read: true, write: true }, function removeSyncAllSymlinks() {
+  if (Deno.build.os === "win") {
+    return;
+  }
+  const outside = Deno.makeTempDirSync();
+  Deno.writeFileSync(outside + "/keep.txt", new TextEncoder().encode("keep"));
+  const root = Deno.makeTempDirSync();
+  Deno.mkdirSync(root + "/a/b", true);
+  Deno.symlinkSync(outside, root + "/a/b/outside");
+  Deno.symlinkSync(outside + "/keep.txt", root + "/a/keep.txt");
+  // A cycle back to the root
+  Deno.symlinkSync(root, root + "/a/b/up");
+
+  Deno.removeSync(root, { recursive: true });
+  let err;
+  try {
+    Deno.statSync(root);
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.NotFound);
+  // The links are gone, what they pointed to is not
+  assert(Deno.statSync(outside + "/keep.txt").isFile());
+  Deno.removeSync(outside, { recursive: true });
+});
+
+testPerm({ read: true, write: true }, function removeSyncDirSymlink() {
+  if (Deno.build.os === "win") {
+    return;
+  }
+  const target = Deno.makeTempDirSync();
+  Deno.writeFileSync(target + "/keep.txt", new TextEncoder().encode("keep"));
+  const link = Deno.makeTempDirSync() + "/link";
+  Deno.symlinkSync(target, link);
+  // Without `recursive`, the link to a directory is still removed as a file
+  Deno.removeSync(link);
+  assert(Deno.statSync(target + "/keep.txt").isFile());
+  let err;
+  try {
+    Deno.lstatSync(link);
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.NotFound);
+  Deno.removeSync(target, { recursive: true });
+});
@@ -3,34 +3,56 @@ import * as msg from "gen/cli/msg_generated";
 import * as flatbuffers from "./flatbuffers";
 import * as dispatch from "./dispatch";
 
+export interface RenameOptions {
+  /** Replace `newpath` if it exists. Defaults to false. */
+  overwrite?: boolean;
+}
+
 function req(
   oldpath: string,
-  newpath: string
+  newpath: string,
+  options: RenameOptions
 ): [flatbuffers.Builder, msg.Any, flatbuffers.Offset] {
   const builder = flatbuffers.createBuilder();
   const oldpath_ = builder.createString(oldpath);
   const newpath_ = builder.createString(newpath);
-  const inner = msg.Rename.createRename(builder, oldpath_, newpath_);
+  const inner = msg.Rename.createRename(
+    builder,
+    oldpath_,
+    newpath_,
+    !!options.overwrite
+  );
   return [builder, msg.Any.Rename, inner];
 }
 
 /** Synchronously renames (moves) `oldpath` to `newpath`. If `newpath` already
- * exists and is not a directory, `renameSync()` replaces it. OS-specific
- * restrictions may apply when `oldpath` and `newpath` are in different
- * directories.
+ * exists it is an `AlreadyExists` error, unless `overwrite` is set, which
+ * replaces it if it is not a directory or an empty one. Between file systems
+ * `oldpath` is copied and then removed. Requires the `--allow-read` flag for
+ * `oldpath` and `--allow-write` for `newpath`.
  *
  *       Deno.renameSync("old/path", "new/path");
  */
-export function renameSync(oldpath: string, newpath: string): void {
-  dispatch.sendSync(...req(oldpath, newpath));
+export function renameSync(
+  oldpath: string,
+  newpath: string,
+  options: RenameOptions = {}
+): void {
+  dispatch.sendSync(...req(oldpath, newpath, options));
 }
 
-/** Renames (moves) `oldpath` to `newpath`. If `newpath` already exists and is
- * not a directory, `rename()` replaces it. OS-specific restrictions may apply
- * when `oldpath` and `newpath` are in different directories.
+/** Renames (moves) `oldpath` to `newpath`. If `newpath` already exists it is
+ * an `AlreadyExists` error, unless `overwrite` is set, which replaces it if
+ * it is not a directory or an empty one. Between file systems `oldpath` is
+ * copied and then removed. Requires the `--allow-read` flag for `oldpath` and
+ * `--allow-write` for `newpath`.
  *
- *       await Deno.rename("old/path", "new/path");
+ *       await Deno.rename("old/path", "new/path", { overwrite: true });
  */
-export async function rename(oldpath: string, newpath: string): Promise<void> {
-  await dispatch.sendAsync(...req(oldpath, newpath));
+export async function rename(
+  oldpath: string,
+  newpath: string,
+  options: RenameOptions = {}
+): Promise<void> {
+  await dispatch.sendAsync(...req(oldpath, newpath, options));
 }
@@ -36,6 +36,45 @@ testPerm({ read: true, write: false }, function renameSyncPerm() {
   assertEquals(err.name, "PermissionDenied");
 });
 
+testPerm({ read: false, write: true }, function renameSyncReadPerm() {
+  let err;
+  try {
+    Deno.renameSync("/oldbaddir", "/newbaddir");
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+  assertEquals(err.name, "PermissionDenied");
+});
+
+testPerm({ read: true, write: true }, function renameSyncNoOverwrite() {
+  const testDir = Deno.makeTempDirSync();
+  const oldpath = testDir + "/oldpath";
+  const newpath = testDir + "/newpath";
+  const encoder = new TextEncoder();
+  Deno.writeFileSync(oldpath, encoder.encode("old"));
+  Deno.writeFileSync(newpath, encoder.encode("new"));
+  let err;
+  try {
+    Deno.renameSync(oldpath, newpath);
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.AlreadyExists);
+  assertEquals(Deno.statSync(oldpath).len, 3);
+
+  Deno.renameSync(oldpath, newpath, { overwrite: true });
+  assertEquals(new TextDecoder().decode(Deno.readFileSync(newpath)), "old");
+  let caughtErr = false;
+  try {
+    Deno.statSync(oldpath);
+  } catch (e) {
+    caughtErr = true;
+    assertEquals(e.kind, Deno.ErrorKind.NotFound);
+  }
+  assert(caughtErr);
+});
+
 testPerm({ read: true, write: true }, async function renameSuccess() {
   const testDir = Deno.makeTempDirSync();
   const oldpath = testDir + "/oldpath";
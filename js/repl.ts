@@ -80,14 +80,52 @@ function isRecoverableError(e: Error): boolean {
   return recoverableErrorMessages.includes(e.message);
 }
 
+// Returns true if the code opens more brackets than it closes, e.g. a function
+// body which continues on the next line. Brackets inside of string and
+// template literals are ignored.
+function hasUnclosedBrackets(code: string): boolean {
+  let depth = 0;
+  let quote: string | null = null;
+  for (let i = 0; i < code.length; i++) {
+    const c = code[i];
+    if (quote) {
+      if (c === "\\") {
+        i++;
+      } else if (c === quote) {
+        quote = null;
+      }
+    } else if (c === '"' || c === "'" || c === "`") {
+      quote = c;
+    } else if ("([{".includes(c)) {
+      depth++;
+    } else if (")]}".includes(c)) {
+      depth--;
+    }
+  }
+  return depth > 0;
+}
+
+// Binds `_` to the result of the last evaluated expression.
+function setLastEvalResult(result: unknown): void {
+  Object.defineProperty(window, "_", {
+    value: result,
+    writable: true,
+    configurable: true
+  });
+}
+
 // Evaluate code.
 // Returns true if code is consumed (no error/irrecoverable error).
 // Returns false if error is recoverable
 function evaluate(code: string): boolean {
   const [result, errInfo] = core.evalContext(code);
   if (!errInfo) {
+    setLastEvalResult(result);
     console.log(result);
-  } else if (errInfo.isCompileError && isRecoverableError(errInfo.thrown)) {
+  } else if (
+    errInfo.isCompileError &&
+    (isRecoverableError(errInfo.thrown) || hasUnclosedBrackets(code))
+  ) {
     // Recoverable compiler error
     return false; // don't consume code.
   } else {
@@ -107,7 +145,8 @@ function evaluate(code: string): boolean {
 export async function replLoop(): Promise<void> {
   Object.defineProperties(window, replCommands);
 
-  const historyFile = "deno_history.txt";
+  // Stored in DENO_DIR.
+  const historyFile = "history";
   const rid = startRepl(historyFile);
 
   const quitRepl = (exitCode: number): void => {
@@ -129,15 +168,14 @@ export async function replLoop(): Promise<void> {
     } catch (err) {
       if (err.message === "EOF") {
         quitRepl(0);
+      } else if (err.message === "Interrupted") {
+        // Ctrl-C discards the current line instead of quitting.
+        continue;
       } else {
-        // If interrupted, don't print error.
-        if (err.message !== "Interrupted") {
-          // e.g. this happens when we have deno.close(3).
-          // We want to display the problem.
-          const formattedError = formatError(core.errorToJSON(err));
-          console.error(formattedError);
-        }
-        // Quit REPL anyways.
+        // e.g. this happens when we have deno.close(3).
+        // We want to display the problem.
+        const formattedError = formatError(core.errorToJSON(err));
+        console.error(formattedError);
         quitRepl(1);
       }
     }
@@ -40,3 +40,10 @@ testPerm({ read: true }, async function resourcesFile() {
   });
   assertEquals(resourcesAfter[newRid], "fsFile");
 });
+
+testPerm({ read: true }, async function resourcesFileClose() {
+  const file = await Deno.open("tests/hello.txt");
+  assertEquals(Deno.resources()[file.rid], "fsFile");
+  file.close();
+  assertEquals(Deno.resources()[file.rid], undefined);
+});
@@ -0,0 +1,99 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import * as msg from "gen/cli/msg_generated";
+import * as flatbuffers from "./flatbuffers";
+import * as dispatch from "./dispatch";
+import { close } from "./files";
+import { assert } from "./util";
+
+/** The signals `signal()` can listen to. Windows only has SIGINT, sent by
+ * Ctrl-C, and SIGBREAK, sent by Ctrl-Break, which only exists there.
+ */
+export enum Signal {
+  SIGBREAK = "SIGBREAK",
+  SIGHUP = "SIGHUP",
+  SIGINT = "SIGINT",
+  SIGTERM = "SIGTERM",
+  SIGUSR1 = "SIGUSR1",
+  SIGUSR2 = "SIGUSR2"
+}
+
+function bind(signal: Signal): number {
+  const builder = flatbuffers.createBuilder();
+  const signal_ = builder.createString(signal);
+  const inner = msg.SignalBind.createSignalBind(builder, signal_);
+  const baseRes = dispatch.sendSync(builder, msg.Any.SignalBind, inner);
+  assert(baseRes != null);
+  assert(msg.Any.SignalBindRes === baseRes!.innerType());
+  const res = new msg.SignalBindRes();
+  assert(baseRes!.inner(res) != null);
+  return res.rid();
+}
+
+/** Resolves to true when the stream of `rid` is done. */
+async function poll(rid: number): Promise<boolean> {
+  const builder = flatbuffers.createBuilder();
+  const inner = msg.SignalPoll.createSignalPoll(builder, rid);
+  const baseRes = await dispatch.sendAsync(builder, msg.Any.SignalPoll, inner);
+  assert(baseRes != null);
+  assert(msg.Any.SignalPollRes === baseRes!.innerType());
+  const res = new msg.SignalPollRes();
+  assert(baseRes!.inner(res) != null);
+  return res.done();
+}
+
+/** The arrivals of a signal, as an async iterator, or as a promise of the
+ * next one. The signal does not take its default action, like ending the
+ * program, until the stream is disposed.
+ */
+export class SignalStream
+  implements AsyncIterableIterator<void>, PromiseLike<void> {
+  readonly rid: number;
+  private _disposed = false;
+
+  // @internal
+  constructor(signal: Signal) {
+    this.rid = bind(signal);
+  }
+
+  async next(): Promise<IteratorResult<void>> {
+    const done = this._disposed || (await poll(this.rid));
+    return { done, value: undefined };
+  }
+
+  [Symbol.asyncIterator](): AsyncIterableIterator<void> {
+    return this;
+  }
+
+  then<T = void, S = never>(
+    onFulfilled?: ((value: void) => T | PromiseLike<T>) | null,
+    onRejected?: ((reason: Error) => S | PromiseLike<S>) | null
+  ): Promise<T | S> {
+    return this.next()
+      .then(() => {})
+      .then(onFulfilled, onRejected);
+  }
+
+  /** Stops listening to the signal. Once a signal has no more streams it
+   * takes its default action again, and pending `next()`s are done.
+   */
+  dispose(): void {
+    if (!this._disposed) {
+      this._disposed = true;
+      close(this.rid);
+    }
+  }
+}
+
+/** Listens to a signal, which then no longer ends the program.
+ *
+ *       const sigint = Deno.signal(Deno.Signal.SIGINT);
+ *       for await (const _ of sigint) {
+ *         console.log("Shutting down");
+ *         sigint.dispose();
+ *       }
+ *
+ * Throws `InvalidInput` if the signal does not exist on this platform.
+ */
+export function signal(signal: Signal): SignalStream {
+  return new SignalStream(signal);
+}
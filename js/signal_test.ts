@@ -0,0 +1,45 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import { test, testPerm, assert, assertEquals } from "./test_util.ts";
+const { signal, Signal } = Deno;
+
+const isWindows = Deno.build.os === "win";
+
+testPerm({ run: true }, async function signalStreamReceivesSignal() {
+  if (isWindows) {
+    return;
+  }
+  const stream = signal(Signal.SIGUSR1);
+  const p = Deno.run({
+    args: [
+      "python",
+      "-c",
+      `import os, signal; os.kill(${Deno.pid}, signal.SIGUSR1)`
+    ]
+  });
+  await p.status();
+  p.close();
+  // Still alive, the signal went to the stream instead of ending the program.
+  await stream;
+  stream.dispose();
+});
+
+test(async function signalStreamDisposeEndsIteration() {
+  const stream = signal(Signal.SIGINT);
+  assertEquals(Deno.resources()[stream.rid], "signal");
+  const next = stream.next();
+  stream.dispose();
+  assertEquals((await next).done, true);
+  assertEquals((await stream.next()).done, true);
+  assertEquals(Deno.resources()[stream.rid], undefined);
+});
+
+test(function signalNotOnThisPlatform() {
+  let err;
+  try {
+    signal(isWindows ? Signal.SIGTERM : Signal.SIGBREAK);
+  } catch (e) {
+    err = e;
+  }
+  assert(err instanceof Deno.DenoError);
+  assertEquals(err.kind, Deno.ErrorKind.InvalidInput);
+});
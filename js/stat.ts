@@ -63,3 +63,17 @@ export async function stat(filename: string): Promise<FileInfo> {
 export function statSync(filename: string): FileInfo {
   return res(dispatch.sendSync(...req(filename, false)));
 }
+
+// @internal
+/** Stats `filenames` with one dispatch, see `sendSyncBatch()`. Each result
+ * is the `FileInfo` of the file or the error it failed with.
+ */
+export function statSyncBatch(
+  filenames: string[],
+  lstat = false
+): Array<FileInfo | Error> {
+  const ops = filenames.map(f => dispatch.batchOp(...req(f, lstat)));
+  return dispatch
+    .sendSyncBatch(ops)
+    .map(result => (result instanceof Error ? result : res(result)));
+}
@@ -1,6 +1,10 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
 import { testPerm, assert, assertEquals } from "./test_util.ts";
 
+// The batched ops aren't exposed in the types.
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+const { statSyncBatch } = Deno as any;
+
 // TODO Add tests for modified, accessed, and created fields once there is a way
 // to create temp files.
 testPerm({ read: true }, async function statSyncSuccess() {
@@ -170,3 +174,21 @@ testPerm({ read: true }, async function lstatNotFound() {
   assert(caughtError);
   assertEquals(badInfo, undefined);
 });
+
+testPerm({ read: true }, function statSyncBatchSuccess() {
+  const [packageInfo, missing, testingInfo] = statSyncBatch([
+    "package.json",
+    "bad_file_name",
+    "testing"
+  ]);
+  assert(packageInfo.isFile());
+  assertEquals(packageInfo.len, Deno.statSync("package.json").len);
+  assertEquals(missing.kind, Deno.ErrorKind.NotFound);
+  assert(testingInfo.isDirectory());
+  assertEquals(statSyncBatch([]), []);
+});
+
+testPerm({ read: false }, function statSyncBatchPerm() {
+  const [err] = statSyncBatch(["package.json"]);
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+});
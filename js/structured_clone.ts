@@ -0,0 +1,310 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+// The messages of workers are structured clones of the posted values, sent
+// as JSON. Values which JSON keeps as they are, as the messages of the
+// compiler, are sent as plain JSON. The others are wrapped: every object is a
+// record in a list, referenced by its index, which keeps cycles and shared
+// references.
+
+/** The key of the object which wraps a serialized value. */
+const SERIALIZED = "$deno$structuredClone";
+
+const TYPED_ARRAYS: { [name: string]: ArrayBufferViewConstructor } = {
+  Int8Array,
+  Uint8Array,
+  Uint8ClampedArray,
+  Int16Array,
+  Uint16Array,
+  Int32Array,
+  Uint32Array,
+  Float32Array,
+  Float64Array,
+  BigInt64Array,
+  BigUint64Array,
+  DataView
+};
+
+interface ArrayBufferViewConstructor {
+  new (buffer: ArrayBuffer, byteOffset: number, length: number): object;
+}
+
+const ERRORS: { [name: string]: ErrorConstructor } = {
+  Error,
+  EvalError,
+  RangeError,
+  ReferenceError,
+  SyntaxError,
+  TypeError,
+  URIError
+};
+
+function typeName(value: object): string {
+  return Object.prototype.toString.call(value).slice(8, -1);
+}
+
+function cloneError(what: string): TypeError {
+  return new TypeError(`${what} could not be cloned.`);
+}
+
+/** Whether JSON keeps `value` as it is: no undefined, non-finite numbers,
+ * special objects or references to the same object.
+ */
+function isPlainJson(value: unknown, seen: Set<object>): boolean {
+  switch (typeof value) {
+    case "boolean":
+    case "string":
+      return true;
+    case "number":
+      return isFinite(value) && !Object.is(value, -0);
+    case "object":
+      break;
+    default:
+      return false;
+  }
+  if (value === null) {
+    return true;
+  }
+  const object = value as { [key: string]: unknown };
+  if (seen.has(object)) {
+    return false;
+  }
+  seen.add(object);
+  if (Array.isArray(object)) {
+    return (
+      Object.keys(object).length === object.length &&
+      object.every(item => isPlainJson(item, seen))
+    );
+  }
+  const proto = Object.getPrototypeOf(object);
+  if (proto !== Object.prototype && proto !== null) {
+    return false;
+  }
+  return Object.keys(object).every(key => isPlainJson(object[key], seen));
+}
+
+class Serializer {
+  readonly records: unknown[] = [];
+  private readonly _ids = new Map<object, number>();
+
+  value(value: unknown): unknown {
+    switch (typeof value) {
+      case "undefined":
+        return ["u"];
+      case "boolean":
+      case "string":
+        return value;
+      case "number":
+        if (Object.is(value, -0)) {
+          return ["n", "-0"];
+        }
+        return isFinite(value) ? value : ["n", String(value)];
+      case "bigint":
+        return ["i", value.toString()];
+      case "object":
+        return value === null ? null : ["r", this._object(value as object)];
+      default:
+        throw cloneError(`A ${typeof value}`);
+    }
+  }
+
+  private _object(object: object): number {
+    let id = this._ids.get(object);
+    if (id === undefined) {
+      id = this.records.length;
+      this._ids.set(object, id);
+      this.records.push(null);
+      this.records[id] = this._record(object);
+    }
+    return id;
+  }
+
+  private _entries(object: object): unknown[] {
+    const properties = object as { [key: string]: unknown };
+    return Object.keys(object).map(key => [key, this.value(properties[key])]);
+  }
+
+  private _record(object: object): unknown {
+    const name = typeName(object);
+    if (Array.isArray(object)) {
+      return ["A", object.length, this._entries(object)];
+    }
+    if (TYPED_ARRAYS.hasOwnProperty(name)) {
+      const view = object as ArrayBufferView & { length?: number };
+      return [
+        "V",
+        name,
+        this._object(view.buffer),
+        view.byteOffset,
+        view.length === undefined ? view.byteLength : view.length
+      ];
+    }
+    switch (name) {
+      case "Date":
+        return ["D", (object as Date).getTime()];
+      case "RegExp":
+        return ["R", (object as RegExp).source, (object as RegExp).flags];
+      case "Map": {
+        const entries: unknown[] = [];
+        for (const [key, value] of object as Map<unknown, unknown>) {
+          entries.push([this.value(key), this.value(value)]);
+        }
+        return ["M", entries];
+      }
+      case "Set": {
+        const items: unknown[] = [];
+        for (const item of object as Set<unknown>) {
+          items.push(this.value(item));
+        }
+        return ["S", items];
+      }
+      case "ArrayBuffer":
+        return ["B", Array.from(new Uint8Array(object as ArrayBuffer))];
+      case "Boolean":
+      case "Number":
+      case "String":
+        return ["W", this.value(object.valueOf())];
+      case "Error": {
+        const error = object as Error;
+        const errorName = ERRORS.hasOwnProperty(error.name)
+          ? error.name
+          : "Error";
+        return ["E", errorName, error.message, error.stack];
+      }
+      case "Object":
+        return ["O", this._entries(object)];
+      default:
+        // Promises, weak collections, symbols and the like.
+        throw cloneError(`A ${name}`);
+    }
+  }
+}
+
+class Deserializer {
+  private readonly _objects: unknown[];
+
+  constructor(records: unknown[][]) {
+    // The objects are created first, so that references can point forward,
+    // the views once the buffers exist.
+    this._objects = records.map(record => this._create(record));
+    records.forEach((record, id) => {
+      if (record[0] === "V") {
+        const [, name, bufferId, byteOffset, length] = record as [
+          string,
+          string,
+          number,
+          number,
+          number
+        ];
+        const buffer = this._objects[bufferId] as ArrayBuffer;
+        this._objects[id] = new TYPED_ARRAYS[name](buffer, byteOffset, length);
+      }
+    });
+    records.forEach((record, id) => this._fill(record, this._objects[id]));
+  }
+
+  value(value: unknown): unknown {
+    if (!Array.isArray(value)) {
+      return value;
+    }
+    switch (value[0]) {
+      case "u":
+        return undefined;
+      case "n":
+        return Number(value[1]);
+      case "i":
+        return BigInt(value[1]);
+      case "r":
+        return this._objects[value[1]];
+      default:
+        throw new TypeError(`Invalid message: ${value[0]}`);
+    }
+  }
+
+  private _create(record: unknown[]): unknown {
+    switch (record[0]) {
+      case "A":
+        return new Array(record[1] as number);
+      case "O":
+        return {};
+      case "D":
+        return new Date(record[1] as number);
+      case "R":
+        return new RegExp(record[1] as string, record[2] as string);
+      case "M":
+        return new Map();
+      case "S":
+        return new Set();
+      case "B":
+        return new Uint8Array(record[1] as number[]).buffer;
+      case "W":
+        return Object(this.value(record[1]));
+      case "E": {
+        const name = record[1] as string;
+        const constructor = ERRORS.hasOwnProperty(name) ? ERRORS[name] : Error;
+        const error = new constructor(record[2] as string);
+        error.stack = record[3] as string;
+        return error;
+      }
+      case "V":
+        return null;
+      default:
+        throw new TypeError(`Invalid message: ${record[0]}`);
+    }
+  }
+
+  private _fill(record: unknown[], object: unknown): void {
+    switch (record[0]) {
+      case "A":
+        for (const [key, value] of record[2] as Array<[string, unknown]>) {
+          (object as { [key: string]: unknown })[key] = this.value(value);
+        }
+        break;
+      case "O":
+        for (const [key, value] of record[1] as Array<[string, unknown]>) {
+          (object as { [key: string]: unknown })[key] = this.value(value);
+        }
+        break;
+      case "M":
+        for (const [key, value] of record[1] as Array<[unknown, unknown]>) {
+          (object as Map<unknown, unknown>).set(
+            this.value(key),
+            this.value(value)
+          );
+        }
+        break;
+      case "S":
+        for (const item of record[1] as unknown[]) {
+          (object as Set<unknown>).add(this.value(item));
+        }
+        break;
+    }
+  }
+}
+
+/** The JSON of the structured clone of `value`. Functions, symbols and
+ * objects like promises can't be cloned, they throw a `TypeError`.
+ */
+export function serialize(value: unknown): string {
+  const isWrapper =
+    typeof value === "object" &&
+    value !== null &&
+    Object.keys(value).includes(SERIALIZED);
+  if (!isWrapper && isPlainJson(value, new Set())) {
+    return JSON.stringify(value);
+  }
+  const serializer = new Serializer();
+  const root = serializer.value(value);
+  return JSON.stringify({ [SERIALIZED]: [root, serializer.records] });
+}
+
+export function deserialize(json: string): unknown {
+  const value = JSON.parse(json);
+  if (
+    typeof value !== "object" ||
+    value === null ||
+    !value.hasOwnProperty(SERIALIZED)
+  ) {
+    return value;
+  }
+  const [root, records] = value[SERIALIZED];
+  return new Deserializer(records).value(root);
+}
@@ -2,48 +2,67 @@
 import * as msg from "gen/cli/msg_generated";
 import * as flatbuffers from "./flatbuffers";
 import * as dispatch from "./dispatch";
-import * as util from "./util";
+import { unreachable } from "./util";
+
+/** What a symbolic link points to, see `symlink()`. */
+export type SymlinkType = "file" | "dir";
+
+function typeMap(type?: SymlinkType): msg.SymlinkType {
+  switch (type) {
+    case undefined:
+      return msg.SymlinkType.Auto;
+    case "file":
+      return msg.SymlinkType.File;
+    case "dir":
+      return msg.SymlinkType.Dir;
+    default:
+      return unreachable();
+  }
+}
 
 function req(
   oldname: string,
   newname: string,
-  type?: string
+  type?: SymlinkType
 ): [flatbuffers.Builder, msg.Any, flatbuffers.Offset] {
-  // TODO Use type for Windows.
-  if (type) {
-    return util.notImplemented();
-  }
   const builder = flatbuffers.createBuilder();
   const oldname_ = builder.createString(oldname);
   const newname_ = builder.createString(newname);
-  const inner = msg.Symlink.createSymlink(builder, oldname_, newname_);
+  const inner = msg.Symlink.createSymlink(
+    builder,
+    oldname_,
+    newname_,
+    typeMap(type)
+  );
   return [builder, msg.Any.Symlink, inner];
 }
 
-/** Synchronously creates `newname` as a symbolic link to `oldname`. The type
- * argument can be set to `dir` or `file` and is only available on Windows
- * (ignored on other platforms).
+/** Synchronously creates `newname` as a symbolic link to `oldname`, which is
+ * relative to the directory of `newname` unless absolute. The type argument
+ * can be set to `dir` or `file` and is only used on Windows (without it the
+ * type of `oldname` is used).
  *
  *       Deno.symlinkSync("old/name", "new/name");
  */
 export function symlinkSync(
   oldname: string,
   newname: string,
-  type?: string
+  type?: SymlinkType
 ): void {
   dispatch.sendSync(...req(oldname, newname, type));
 }
 
-/** Creates `newname` as a symbolic link to `oldname`. The type argument can be
- * set to `dir` or `file` and is only available on Windows (ignored on other
- * platforms).
+/** Creates `newname` as a symbolic link to `oldname`, which is relative to
+ * the directory of `newname` unless absolute. The type argument can be set to
+ * `dir` or `file` and is only used on Windows (without it the type of
+ * `oldname` is used).
  *
  *       await Deno.symlink("old/name", "new/name");
  */
 export async function symlink(
   oldname: string,
   newname: string,
-  type?: string
+  type?: SymlinkType
 ): Promise<void> {
   await dispatch.sendAsync(...req(oldname, newname, type));
 }
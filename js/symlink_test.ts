@@ -6,22 +6,11 @@ testPerm({ read: true, write: true }, function symlinkSyncSuccess() {
   const oldname = testDir + "/oldname";
   const newname = testDir + "/newname";
   Deno.mkdirSync(oldname);
-  let errOnWindows;
-  // Just for now, until we implement symlink for Windows.
-  try {
-    Deno.symlinkSync(oldname, newname);
-  } catch (e) {
-    errOnWindows = e;
-  }
-  if (errOnWindows) {
-    assertEquals(errOnWindows.kind, Deno.ErrorKind.Other);
-    assertEquals(errOnWindows.message, "Not implemented");
-  } else {
-    const newNameInfoLStat = Deno.lstatSync(newname);
-    const newNameInfoStat = Deno.statSync(newname);
-    assert(newNameInfoLStat.isSymlink());
-    assert(newNameInfoStat.isDirectory());
-  }
+  Deno.symlinkSync(oldname, newname);
+  const newNameInfoLStat = Deno.lstatSync(newname);
+  const newNameInfoStat = Deno.statSync(newname);
+  assert(newNameInfoLStat.isSymlink());
+  assert(newNameInfoStat.isDirectory());
 });
 
 test(function symlinkSyncPerm() {
@@ -35,36 +24,73 @@ test(function symlinkSyncPerm() {
   assertEquals(err.name, "PermissionDenied");
 });
 
-// Just for now, until we implement symlink for Windows.
-testPerm({ write: true }, function symlinkSyncNotImplemented() {
+testPerm({ write: true }, function symlinkSyncReadPerm() {
   let err;
   try {
-    Deno.symlinkSync("oldname", "newname", "dir");
+    Deno.symlinkSync("oldname", "newname");
   } catch (e) {
     err = e;
   }
-  assertEquals(err.message, "Not implemented");
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
 });
 
-testPerm({ read: true, write: true }, async function symlinkSuccess() {
+testPerm({ read: true, write: true }, function symlinkSyncFile() {
   const testDir = Deno.makeTempDirSync();
   const oldname = testDir + "/oldname";
   const newname = testDir + "/newname";
-  Deno.mkdirSync(oldname);
-  let errOnWindows;
-  // Just for now, until we implement symlink for Windows.
+  Deno.writeFileSync(oldname, new TextEncoder().encode("hello"));
+  Deno.symlinkSync(oldname, newname, "file");
+  assert(Deno.lstatSync(newname).isSymlink());
+  assert(Deno.statSync(newname).isFile());
+  const data = new TextDecoder().decode(Deno.readFileSync(newname));
+  assertEquals(data, "hello");
+});
+
+testPerm({ read: true, write: true }, function symlinkSyncRelative() {
+  const testDir = Deno.makeTempDirSync();
+  Deno.mkdirSync(testDir + "/dir");
+  Deno.writeFileSync(testDir + "/dir/oldname", new Uint8Array([1]));
+  // Relative to the directory of the link, not to the current directory.
+  Deno.symlinkSync("oldname", testDir + "/dir/newname");
+  assert(Deno.statSync(testDir + "/dir/newname").isFile());
+});
+
+testPerm({ read: true, write: true }, function symlinkSyncDangling() {
+  const testDir = Deno.makeTempDirSync();
+  const newname = testDir + "/newname";
+  Deno.symlinkSync(testDir + "/missing", newname);
+  assert(Deno.lstatSync(newname).isSymlink());
+  let err;
   try {
-    await Deno.symlink(oldname, newname);
+    Deno.statSync(newname);
   } catch (e) {
-    errOnWindows = e;
+    err = e;
   }
-  if (errOnWindows) {
-    assertEquals(errOnWindows.kind, Deno.ErrorKind.Other);
-    assertEquals(errOnWindows.message, "Not implemented");
-  } else {
-    const newNameInfoLStat = Deno.lstatSync(newname);
-    const newNameInfoStat = Deno.statSync(newname);
-    assert(newNameInfoLStat.isSymlink());
-    assert(newNameInfoStat.isDirectory());
+  assertEquals(err.kind, Deno.ErrorKind.NotFound);
+});
+
+testPerm({ read: true, write: true }, function symlinkSyncExists() {
+  const testDir = Deno.makeTempDirSync();
+  const newname = testDir + "/newname";
+  Deno.writeFileSync(newname, new Uint8Array());
+  let err;
+  try {
+    Deno.symlinkSync(testDir, newname, "dir");
+  } catch (e) {
+    err = e;
   }
+  assertEquals(err.kind, Deno.ErrorKind.AlreadyExists);
+  assertEquals(err.name, "AlreadyExists");
+});
+
+testPerm({ read: true, write: true }, async function symlinkSuccess() {
+  const testDir = Deno.makeTempDirSync();
+  const oldname = testDir + "/oldname";
+  const newname = testDir + "/newname";
+  Deno.mkdirSync(oldname);
+  await Deno.symlink(oldname, newname);
+  const newNameInfoLStat = Deno.lstatSync(newname);
+  const newNameInfoStat = Deno.statSync(newname);
+  assert(newNameInfoLStat.isSymlink());
+  assert(newNameInfoStat.isDirectory());
 });
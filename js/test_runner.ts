@@ -0,0 +1,241 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+// The tests of `deno test`, which imports the test files, so that they
+// register their tests, and then runs them all in this process, with the
+// permissions of the whole run.
+import * as msg from "gen/cli/msg_generated";
+import { pendingOps } from "./dispatch";
+import { addUnloadHook, exit } from "./os";
+import { resources } from "./resources";
+import { pendingTimers, setTimerErrorHandler } from "./timers";
+import { createResolvable } from "./util";
+
+export type TestFunction = () => void | Promise<void>;
+
+export interface TestDefinition {
+  name: string;
+  fn: TestFunction;
+  /** Fail the test if it leaves resources open, true by default. */
+  sanitizeResources?: boolean;
+  /** Fail the test if it leaves async ops or timers pending, true by
+   * default.
+   */
+  sanitizeOps?: boolean;
+}
+
+export interface RunTestsOptions {
+  /** Only the tests whose name contains it run. */
+  filter?: string | null;
+  /** Stop at the first test which fails. */
+  failFast?: boolean;
+}
+
+export interface TestReport {
+  passed: string[];
+  /** The names of the tests which failed, with their errors. */
+  failed: Array<{ name: string; error: unknown }>;
+  /** How many tests the filter skipped. */
+  filtered: number;
+}
+
+const tests: TestDefinition[] = [];
+
+/** Registers a test for `deno test`, named after the function unless a name
+ * is given. A test fails when it throws, when the promise it returns rejects,
+ * and when the callback of a timer throws while it runs. A test which passes
+ * fails nonetheless if it leaves more resources open, or async ops or timers
+ * pending, than there were before it ran, unless the definition turns that
+ * check off:
+ *
+ *       Deno.test(function addition(): void {
+ *         if (1 + 2 !== 3) throw new Error("1 + 2 is not 3");
+ *       });
+ *       Deno.test("fetch", async (): Promise<void> => {
+ *         const res = await fetch("http://localhost:4545/");
+ *         await res.text();
+ *       });
+ *       Deno.test({
+ *         name: "listen",
+ *         sanitizeResources: false,
+ *         fn(): void {
+ *           Deno.listen("tcp", "127.0.0.1:4500");
+ *         }
+ *       });
+ */
+export function test(fn: TestFunction): void;
+export function test(name: string, fn: TestFunction): void;
+export function test(definition: TestDefinition): void;
+export function test(
+  nameOrFn: string | TestFunction | TestDefinition,
+  fn?: TestFunction
+): void {
+  let definition: TestDefinition;
+  if (typeof nameOrFn === "string") {
+    definition = { name: nameOrFn, fn: fn! };
+  } else if (typeof nameOrFn === "function") {
+    definition = { name: nameOrFn.name, fn: nameOrFn };
+  } else {
+    definition = nameOrFn;
+  }
+  if (typeof definition.fn !== "function") {
+    throw new TypeError("A test needs a function");
+  }
+  if (!definition.name) {
+    throw new TypeError("A test needs a name");
+  }
+  tests.push(definition);
+}
+
+/** Resolves once `fn` is done, rejects when it throws or an uncaught error of
+ * a timer comes first.
+ */
+function runTest(fn: TestFunction): Promise<void> {
+  const done = createResolvable<void>();
+  setTimerErrorHandler(done.reject);
+  try {
+    Promise.resolve(fn()).then(done.resolve, done.reject);
+  } catch (e) {
+    done.reject(e);
+  }
+  return done;
+}
+
+/** The number of each kind of what a test may leak. */
+type Counts = Map<string, number>;
+
+interface Snapshot {
+  resources: Counts;
+  ops: Counts;
+}
+
+function count(counts: Counts, kind: string, n = 1): void {
+  counts.set(kind, (counts.get(kind) || 0) + n);
+}
+
+/** The open resources by kind, and the pending ops by type, with the timers.
+ * Timers wait for a single TimersPoll op, it is not counted.
+ */
+function snapshot(): Snapshot {
+  const result: Snapshot = { resources: new Map(), ops: new Map() };
+  for (const kind of Object.values(resources())) {
+    count(result.resources, kind);
+  }
+  for (const type of pendingOps()) {
+    if (type !== msg.Any.TimersPoll) {
+      count(result.ops, `op ${msg.Any[type]}`);
+    }
+  }
+  const timers = pendingTimers();
+  if (timers > 0) {
+    count(result.ops, "timer", timers);
+  }
+  return result;
+}
+
+/** The kinds of which there are more `after`, one line each. */
+function leaks(before: Counts, after: Counts): string[] {
+  const lines: string[] = [];
+  for (const [kind, n] of after) {
+    const m = before.get(kind) || 0;
+    if (n > m) {
+      lines.push(`  ${kind}: ${m} before, ${n} after`);
+    }
+  }
+  return lines;
+}
+
+/** Throws if the test which ran since `before` leaked what `definition`
+ * checks.
+ */
+function sanitize(definition: TestDefinition, before: Snapshot): void {
+  const { sanitizeResources = true, sanitizeOps = true } = definition;
+  const after = snapshot();
+  const lines: string[] = [];
+  if (sanitizeResources) {
+    lines.push(...leaks(before.resources, after.resources));
+  }
+  if (sanitizeOps) {
+    lines.push(...leaks(before.ops, after.ops));
+  }
+  if (lines.length > 0) {
+    throw new Error(
+      "The test leaked resources or async ops, those left open or pending " +
+        "are:\n" +
+        lines.join("\n")
+    );
+  }
+}
+
+function elapsed(start: number): string {
+  return `(${Date.now() - start}ms)`;
+}
+
+/** Runs the registered tests one after the other, in the order they were
+ * registered, and prints the result of each, then the errors of those which
+ * failed. `deno test` calls it after the test files were imported.
+ */
+export async function runTests(
+  options: RunTestsOptions = {}
+): Promise<TestReport> {
+  const { filter, failFast = false } = options;
+  const selected = tests.filter(t => filter == null || t.name.includes(filter));
+  const report: TestReport = {
+    passed: [],
+    failed: [],
+    filtered: tests.length - selected.length
+  };
+  const start = Date.now();
+  let running: string | null = null;
+  const summary = (): void => {
+    if (report.failed.length > 0) {
+      console.log("\nfailures:\n");
+      for (const { name, error } of report.failed) {
+        console.log(name);
+        console.log(error);
+        console.log();
+      }
+    }
+    const result = report.failed.length > 0 ? "FAILED" : "ok";
+    console.log(
+      `\ntest result: ${result}. ${report.passed.length} passed; ` +
+        `${report.failed.length} failed; ${report.filtered} filtered out ` +
+        elapsed(start)
+    );
+  };
+  // A test which waits for something which never happens would otherwise
+  // end the program silently once nothing is left to do.
+  const removeHook = addUnloadHook(() => {
+    console.log(`test ${running} ... FAILED`);
+    report.failed.push({
+      name: running!,
+      error: new Error("The test never finished, nothing was left to do")
+    });
+    summary();
+    exit(1);
+  });
+  console.log(`running ${selected.length} tests`);
+  try {
+    for (const definition of selected) {
+      const { name, fn } = definition;
+      running = name;
+      const testStart = Date.now();
+      try {
+        const before = snapshot();
+        await runTest(fn);
+        sanitize(definition, before);
+        report.passed.push(name);
+        console.log(`test ${name} ... ok ${elapsed(testStart)}`);
+      } catch (error) {
+        report.failed.push({ name, error });
+        console.log(`test ${name} ... FAILED ${elapsed(testStart)}`);
+        if (failFast) {
+          break;
+        }
+      }
+    }
+  } finally {
+    setTimerErrorHandler(null);
+    removeHook();
+  }
+  summary();
+  return report;
+}
@@ -2,120 +2,68 @@
 import { assert } from "./util";
 import * as msg from "gen/cli/msg_generated";
 import * as flatbuffers from "./flatbuffers";
-import { sendAsync, sendSync } from "./dispatch";
+import { cancel, sendAsync, sendSyncWithId } from "./dispatch";
 
 interface Timer {
+  // The id of the TimerStart op, which is that of the timer in Rust too.
   id: number;
   callback: () => void;
-  delay: number;
-  due: number;
   repeat: boolean;
-  scheduled: boolean;
 }
 
-// We'll subtract EPOCH every time we retrieve the time with Date.now(). This
-// ensures that absolute time values stay below UINT32_MAX - 2, which is the
-// maximum object key that EcmaScript considers "numerical". After running for
-// about a month, this is no longer true, and Deno explodes.
-// TODO(piscisaureus): fix that ^.
-const EPOCH = Date.now();
-const APOCALYPSE = 2 ** 32 - 2;
-
-// Timeout values > TIMEOUT_MAX are set to 1.
+// Delays above TIMEOUT_MAX are set to TIMEOUT_MAX.
 const TIMEOUT_MAX = 2 ** 31 - 1;
 
-let globalTimeoutDue: number | null = null;
-
-let nextTimerId = 1;
+// The timers which were started and did not fire or were cleared, their
+// deadlines are kept in Rust. See //cli/timers.rs.
 const idMap = new Map<number, Timer>();
-const dueMap: { [due: number]: Timer[] } = Object.create(null);
+// Whether there is a TimersPoll op.
+let polling = false;
+// Gets the errors the callbacks throw, see setTimerErrorHandler().
+let errorHandler: ((error: unknown) => void) | null = null;
+
+// @internal
+/** How many timers were started and did not fire or were cleared. */
+export function pendingTimers(): number {
+  return idMap.size;
+}
 
-function getTime(): number {
-  // TODO: use a monotonic clock.
-  const now = Date.now() - EPOCH;
-  assert(now >= 0 && now < APOCALYPSE);
-  return now;
+// @internal
+/** Makes `handler` get the errors which the callbacks of timers throw, which
+ * are uncaught otherwise. `deno test` fails the test which runs with them.
+ * `null` removes the handler.
+ */
+export function setTimerErrorHandler(
+  handler: ((error: unknown) => void) | null
+): void {
+  errorHandler = handler;
 }
 
-function clearGlobalTimeout(): void {
+/** Returns the id of the timer. */
+function startTimer(delay: number, repeat: boolean): number {
   const builder = flatbuffers.createBuilder();
-  const inner = msg.GlobalTimerStop.createGlobalTimerStop(builder);
-  globalTimeoutDue = null;
-  let res = sendSync(builder, msg.Any.GlobalTimerStop, inner);
+  const inner = msg.TimerStart.createTimerStart(builder, delay, repeat);
+  const [id, res] = sendSyncWithId(builder, msg.Any.TimerStart, inner);
   assert(res == null);
+  return id;
 }
 
-async function setGlobalTimeout(due: number, now: number): Promise<void> {
-  // Since JS and Rust don't use the same clock, pass the time to rust as a
-  // relative time value. On the Rust side we'll turn that into an absolute
-  // value again.
-  let timeout = due - now;
-  assert(timeout >= 0);
-
-  // Send message to the backend.
+/** Resolves to the ids of the timers which are due, in the order they fire,
+ * or to none if no timer is left in Rust.
+ */
+async function dueTimers(): Promise<number[]> {
   const builder = flatbuffers.createBuilder();
-  msg.GlobalTimer.startGlobalTimer(builder);
-  msg.GlobalTimer.addTimeout(builder, timeout);
-  const inner = msg.GlobalTimer.endGlobalTimer(builder);
-  globalTimeoutDue = due;
-  await sendAsync(builder, msg.Any.GlobalTimer, inner);
-  // eslint-disable-next-line @typescript-eslint/no-use-before-define
-  fireTimers();
-}
-
-function setOrClearGlobalTimeout(due: number | null, now: number): void {
-  if (due == null) {
-    clearGlobalTimeout();
-  } else {
-    setGlobalTimeout(due, now);
-  }
-}
-
-function schedule(timer: Timer, now: number): void {
-  assert(!timer.scheduled);
-  assert(now <= timer.due);
-  // Find or create the list of timers that will fire at point-in-time `due`.
-  let list = dueMap[timer.due];
-  if (list === undefined) {
-    list = dueMap[timer.due] = [];
-  }
-  // Append the newly scheduled timer to the list and mark it as scheduled.
-  list.push(timer);
-  timer.scheduled = true;
-  // If the new timer is scheduled to fire before any timer that existed before,
-  // update the global timeout to reflect this.
-  if (globalTimeoutDue === null || globalTimeoutDue > timer.due) {
-    setOrClearGlobalTimeout(timer.due, now);
-  }
-}
-
-function unschedule(timer: Timer): void {
-  if (!timer.scheduled) {
-    return;
-  }
-  // Find the list of timers that will fire at point-in-time `due`.
-  const list = dueMap[timer.due];
-  if (list.length === 1) {
-    // Time timer is the only one in the list. Remove the entire list.
-    assert(list[0] === timer);
-    delete dueMap[timer.due];
-    // If the unscheduled timer was 'next up', find when the next timer that
-    // still exists is due, and update the global alarm accordingly.
-    if (timer.due === globalTimeoutDue) {
-      let nextTimerDue: number | null = null;
-      for (const key in dueMap) {
-        nextTimerDue = Number(key);
-        break;
-      }
-      setOrClearGlobalTimeout(nextTimerDue, getTime());
-    }
-  } else {
-    // Multiple timers that are due at the same point in time.
-    // Remove this timer from the list.
-    const index = list.indexOf(timer);
-    assert(index > -1);
-    list.splice(index, 1);
+  const inner = msg.TimersPoll.createTimersPoll(builder);
+  const baseRes = await sendAsync(builder, msg.Any.TimersPoll, inner);
+  assert(baseRes != null);
+  assert(msg.Any.TimersPollRes === baseRes!.innerType());
+  const res = new msg.TimersPollRes();
+  assert(baseRes!.inner(res) != null);
+  const ids: number[] = [];
+  for (let i = 0; i < res.idsLength(); i++) {
+    ids.push(res.ids(i)!);
   }
+  return ids;
 }
 
 function fire(timer: Timer): void {
@@ -124,58 +72,38 @@ function fire(timer: Timer): void {
   if (!idMap.has(timer.id)) {
     return;
   }
-  // Reschedule the timer if it is a repeating one, otherwise drop it.
+  // One-shot timer: remove the timer from this id-to-timer map. Rust has
+  // rescheduled an interval already.
   if (!timer.repeat) {
-    // One-shot timer: remove the timer from this id-to-timer map.
     idMap.delete(timer.id);
-  } else {
-    // Interval timer: compute when timer was supposed to fire next.
-    // However make sure to never schedule the next interval in the past.
-    const now = getTime();
-    timer.due = Math.max(now, timer.due + timer.delay);
-    schedule(timer, now);
   }
   // Call the user callback. Intermediate assignment is to avoid leaking `this`
   // to it, while also keeping the stack trace neat when it shows up in there.
   const callback = timer.callback;
-  callback();
-}
-
-function fireTimers(): void {
-  const now = getTime();
-  // Bail out if we're not expecting the global timer to fire (yet).
-  if (globalTimeoutDue === null || now < globalTimeoutDue) {
+  if (errorHandler === null) {
+    callback();
     return;
   }
-  // After firing the timers that are due now, this will hold the due time of
-  // the first timer that hasn't fired yet.
-  let nextTimerDue: number | null = null;
-  // Walk over the keys of the 'due' map. Since dueMap is actually a regular
-  // object and its keys are numerical and smaller than UINT32_MAX - 2,
-  // keys are iterated in ascending order.
-  for (const key in dueMap) {
-    // Convert the object key (a string) to a number.
-    const due = Number(key);
-    // Break out of the loop if the next timer isn't due to fire yet.
-    if (Number(due) > now) {
-      nextTimerDue = due;
-      break;
-    }
-    // Get the list of timers that have this due time, then drop it.
-    const list = dueMap[key];
-    delete dueMap[key];
-    // Fire all the timers in the list.
-    for (const timer of list) {
-      // With the list dropped, the timer is no longer scheduled.
-      timer.scheduled = false;
-      // Place the callback on the microtask queue.
-      Promise.resolve(timer).then(fire);
-    }
+  try {
+    callback();
+  } catch (e) {
+    errorHandler(e);
   }
+}
 
-  // Update the global alarm to go off when the first-up timer that hasn't fired
-  // yet is due.
-  setOrClearGlobalTimeout(nextTimerDue, now);
+async function fireTimers(): Promise<void> {
+  polling = true;
+  while (idMap.size > 0) {
+    for (const id of await dueTimers()) {
+      const timer = idMap.get(id);
+      if (timer !== undefined) {
+        // Place the callback on the microtask queue. The callbacks run in the
+        // order of the ids, after the promises which were resolved already.
+        Promise.resolve(timer).then(fire);
+      }
+    }
+  }
+  polling = false;
 }
 
 export type Args = unknown[];
@@ -189,33 +117,28 @@ function setTimer(
   // If any `args` were provided (which is uncommon), bind them to the callback.
   const callback: () => void = args.length === 0 ? cb : cb.bind(null, ...args);
   // In the browser, the delay value must be coercible to an integer between 0
-  // and INT32_MAX. Any other value will cause the timer to fire immediately.
-  // We emulate this behavior.
-  const now = getTime();
+  // and INT32_MAX. Larger values fire after INT32_MAX milliseconds rather than
+  // at once, other values are 0.
   if (delay > TIMEOUT_MAX) {
     console.warn(
       `${delay} does not fit into` +
         " a 32-bit signed integer." +
-        "\nTimeout duration was set to 1."
+        `\nTimeout duration was set to ${TIMEOUT_MAX}.`
     );
-    delay = 1;
+    delay = TIMEOUT_MAX;
   }
   delay = Math.max(0, delay | 0);
 
-  // Create a new, unscheduled timer object.
   const timer = {
-    id: nextTimerId++,
+    id: startTimer(delay, repeat),
     callback,
-    args,
-    delay,
-    due: now + delay,
-    repeat,
-    scheduled: false
+    repeat
   };
   // Register the timer's existence in the id-to-timer map.
   idMap.set(timer.id, timer);
-  // Schedule the timer in the due table.
-  schedule(timer, now);
+  if (!polling) {
+    fireTimers();
+  }
   return timer.id;
 }
 
@@ -244,7 +167,7 @@ export function clearTimer(id: number): void {
     // Timer doesn't exist any more or never existed. This is not an error.
     return;
   }
-  // Unschedule the timer if it is currently scheduled, and forget about it.
-  unschedule(timer);
+  // Forget about the timer, and stop it in Rust.
   idMap.delete(timer.id);
+  cancel(timer.id);
 }
@@ -161,11 +161,42 @@ test(async function intervalCancelInvalidSilentFail() {
   clearInterval(2147483647);
 });
 
-test(async function fireCallbackImmediatelyWhenDelayOverMaxValue() {
+test(async function timeoutOverMaxValueIsClamped() {
+  // Like in browsers, the timer fires after 2 ** 31 - 1 ms rather than at once.
   let count = 0;
-  setTimeout(() => {
+  const id = setTimeout(() => {
     count++;
   }, 2 ** 31);
-  await waitForMs(1);
-  assertEquals(count, 1);
+  await waitForMs(10);
+  assertEquals(count, 0);
+  clearTimeout(id);
+});
+
+test(async function timeoutZeroDelayOrdering() {
+  const { promise, resolve } = deferred();
+  const order = [];
+  setTimeout(() => order.push("timeout 1"), 0);
+  setTimeout(() => order.push("timeout 2"), 0);
+  setTimeout(() => {
+    order.push("timeout 3");
+    resolve();
+  }, -1);
+  Promise.resolve().then(() => order.push("promise"));
+  await promise;
+  assertEquals(order, ["promise", "timeout 1", "timeout 2", "timeout 3"]);
+});
+
+test(async function timeoutSameDeadlineOrdering() {
+  const { promise, resolve } = deferred();
+  const order = [];
+  for (let i = 0; i < 10; i++) {
+    setTimeout(() => {
+      order.push(i);
+      if (order.length === 10) {
+        resolve();
+      }
+    }, 10);
+  }
+  await promise;
+  assertEquals(order, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
 });
@@ -0,0 +1,22 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import * as msg from "gen/cli/msg_generated";
+import * as flatbuffers from "./flatbuffers";
+import * as dispatch from "./dispatch";
+import { assert } from "./util";
+
+/** Sets the umask of the process to `mask`, if given, and returns the
+ * previous one. Requires the `--allow-write` flag. Fails with `NotSupported`
+ * on Windows.
+ *
+ *       const previous = Deno.umask(0o022);
+ */
+export function umask(mask?: number): number {
+  const builder = flatbuffers.createBuilder();
+  const inner = msg.Umask.createUmask(builder, mask || 0, mask != null);
+  const baseRes = dispatch.sendSync(builder, msg.Any.Umask, inner);
+  assert(baseRes != null);
+  assert(msg.Any.UmaskRes === baseRes!.innerType());
+  const res = new msg.UmaskRes();
+  assert(baseRes!.inner(res) != null);
+  return res.previous();
+}
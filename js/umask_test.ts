@@ -0,0 +1,47 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import { testPerm, assertEquals } from "./test_util.ts";
+
+const isNotWindows = Deno.build.os !== "win";
+
+testPerm({ write: false }, function umaskPerm() {
+  let err;
+  try {
+    Deno.umask();
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+  assertEquals(err.name, "PermissionDenied");
+});
+
+testPerm({ read: true, write: true }, function umaskSuccess() {
+  if (!isNotWindows) {
+    let err;
+    try {
+      Deno.umask();
+    } catch (e) {
+      err = e;
+    }
+    assertEquals(err.kind, Deno.ErrorKind.NotSupported);
+    return;
+  }
+  const previous = Deno.umask(0o027);
+  try {
+    assertEquals(Deno.umask(), 0o027);
+    const dirname = Deno.makeTempDirSync() + "/dir";
+    Deno.mkdirSync(dirname, false, 0o777);
+    assertEquals(Deno.statSync(dirname).mode & 0o777, 0o750);
+  } finally {
+    assertEquals(Deno.umask(previous), 0o027);
+  }
+});
+
+testPerm({ write: true }, function umaskInvalid() {
+  let err;
+  try {
+    Deno.umask(0o1000);
+  } catch (e) {
+    err = e;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.InvalidInput);
+});
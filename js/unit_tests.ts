@@ -7,20 +7,24 @@ import "./blob_test.ts";
 import "./buffer_test.ts";
 import "./build_test.ts";
 import "./chmod_test.ts";
+import "./chown_test.ts";
 // TODO find a way to test the compiler with split snapshots
 // import "./compiler_test.ts";
 import "./console_test.ts";
 import "./copy_file_test.ts";
 import "./custom_event_test.ts";
 import "./dir_test.ts";
+import "./errors_test.ts";
 import "./event_test.ts";
 import "./event_target_test.ts";
 import "./fetch_test.ts";
 import "./file_test.ts";
 import "./files_test.ts";
 import "./form_data_test.ts";
+import "./fs_events_test.ts";
 import "./globals_test.ts";
 import "./headers_test.ts";
+import "./http_server_test.ts";
 import "./link_test.ts";
 import "./location_test.ts";
 import "./make_temp_dir_test.ts";
@@ -35,13 +39,16 @@ import "./read_file_test.ts";
 import "./read_link_test.ts";
 import "./rename_test.ts";
 import "./resources_test.ts";
+import "./signal_test.ts";
 import "./stat_test.ts";
 import "./symlink_test.ts";
 import "./text_encoding_test.ts";
 import "./timers_test.ts";
 import "./truncate_test.ts";
+import "./umask_test.ts";
 import "./url_test.ts";
 import "./url_search_params_test.ts";
+import "./websocket_test.ts";
 import "./write_file_test.ts";
 import "./performance_test.ts";
 import "./permissions_test.ts";
@@ -0,0 +1,205 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import * as msg from "gen/cli/msg_generated";
+import * as flatbuffers from "./flatbuffers";
+import * as dispatch from "./dispatch";
+import { close } from "./files";
+import { TextDecoder, TextEncoder } from "./text_encoding";
+import { assert, unreachable } from "./util";
+
+/** A message of a WebSocket. Pings are answered with a pong automatically,
+ * they are received nonetheless. A close without a code has the code 1005,
+ * a connection which ended without a close the code 1006.
+ */
+export type WebSocketMessage =
+  | { type: "text"; data: string }
+  | { type: "binary"; data: Uint8Array }
+  | { type: "ping"; data: Uint8Array }
+  | { type: "pong"; data: Uint8Array }
+  | { type: "close"; code: number; reason: string };
+
+export interface DialWebSocketOptions {
+  /** The subprotocols to offer, the server chooses one of them or none. */
+  protocols?: string[];
+}
+
+function send(
+  rid: number,
+  kind: msg.WebSocketMessageKind,
+  data: Uint8Array
+): Promise<msg.Base | null> {
+  const builder = flatbuffers.createBuilder();
+  const inner = msg.WebSocketSend.createWebSocketSend(builder, rid, kind, 0);
+  return dispatch.sendAsync(builder, msg.Any.WebSocketSend, inner, data);
+}
+
+/** Resolves to null after the close message. */
+async function receive(rid: number): Promise<WebSocketMessage | null> {
+  const builder = flatbuffers.createBuilder();
+  const inner = msg.WebSocketReceive.createWebSocketReceive(builder, rid);
+  const baseRes = await dispatch.sendAsync(
+    builder,
+    msg.Any.WebSocketReceive,
+    inner
+  );
+  assert(baseRes != null);
+  assert(msg.Any.WebSocketReceiveRes === baseRes!.innerType());
+  const res = new msg.WebSocketReceiveRes();
+  assert(baseRes!.inner(res) != null);
+  if (res.done()) {
+    return null;
+  }
+  // The data is a view of the response, it is copied to be kept.
+  const data = (res.dataArray() || new Uint8Array()).slice();
+  switch (res.kind()) {
+    case msg.WebSocketMessageKind.Text:
+      return { type: "text", data: new TextDecoder().decode(data) };
+    case msg.WebSocketMessageKind.Binary:
+      return { type: "binary", data };
+    case msg.WebSocketMessageKind.Ping:
+      return { type: "ping", data };
+    case msg.WebSocketMessageKind.Pong:
+      return { type: "pong", data };
+    case msg.WebSocketMessageKind.Close:
+      return {
+        type: "close",
+        code: res.code(),
+        reason: new TextDecoder().decode(data)
+      };
+    default:
+      return unreachable();
+  }
+}
+
+/** An open WebSocket, an async iterator of the messages it receives. */
+export class WebSocketConn
+  implements AsyncIterableIterator<WebSocketMessage> {
+  private _closed = false;
+
+  // @internal
+  constructor(
+    readonly rid: number,
+    /** The subprotocol the server chose, empty if none. */
+    readonly protocol: string
+  ) {}
+
+  /** Sends a text message for a string, a binary one otherwise. */
+  async send(data: string | Uint8Array): Promise<void> {
+    if (typeof data === "string") {
+      const encoded = new TextEncoder().encode(data);
+      await send(this.rid, msg.WebSocketMessageKind.Text, encoded);
+    } else {
+      await send(this.rid, msg.WebSocketMessageKind.Binary, data);
+    }
+  }
+
+  /** Sends a ping, the pong of the server is received as a message. */
+  async ping(data: Uint8Array = new Uint8Array()): Promise<void> {
+    await send(this.rid, msg.WebSocketMessageKind.Ping, data);
+  }
+
+  /** Resolves to the next message. After the close message, which closes
+   * the WebSocket, it resolves to null.
+   */
+  async receive(): Promise<WebSocketMessage | null> {
+    if (this._closed) {
+      return null;
+    }
+    let message: WebSocketMessage | null;
+    try {
+      message = await receive(this.rid);
+    } catch (e) {
+      if (this._closed) {
+        return null;
+      }
+      throw e;
+    }
+    if (message === null || message.type === "close") {
+      this._closeResource();
+    }
+    return message;
+  }
+
+  async next(): Promise<IteratorResult<WebSocketMessage>> {
+    const message = await this.receive();
+    if (message === null) {
+      // The value of a done result is not used.
+      return { done: true, value: (undefined as unknown) as WebSocketMessage };
+    }
+    return { done: false, value: message };
+  }
+
+  [Symbol.asyncIterator](): AsyncIterableIterator<WebSocketMessage> {
+    return this;
+  }
+
+  /** Sends a close message and resolves once the server replied, then the
+   * WebSocket is closed. The messages which were not received are
+   * discarded.
+   */
+  async close(code = 1000, reason = ""): Promise<void> {
+    if (this._closed) {
+      return;
+    }
+    const builder = flatbuffers.createBuilder();
+    const reason_ = builder.createString(reason);
+    const inner = msg.WebSocketClose.createWebSocketClose(
+      builder,
+      this.rid,
+      code,
+      reason_
+    );
+    try {
+      await dispatch.sendAsync(builder, msg.Any.WebSocketClose, inner);
+    } finally {
+      this._closeResource();
+    }
+  }
+
+  private _closeResource(): void {
+    if (!this._closed) {
+      this._closed = true;
+      close(this.rid);
+    }
+  }
+}
+
+/** Opens a WebSocket to the "ws:" or "wss:" `url`, after the opening
+ * handshake. Requires the `--allow-net` flag for the host.
+ *
+ *       const ws = await Deno.dialWebSocket("ws://localhost:8080/chat");
+ *       await ws.send("hello");
+ *       for await (const message of ws) {
+ *         console.log(message); // { type: "text", data: "hello" }
+ *         await ws.close();
+ *       }
+ *
+ * The frames are masked, fragmented messages are reassembled and the
+ * messages of more than 64 MiB rejected.
+ */
+export async function dialWebSocket(
+  url: string,
+  options: DialWebSocketOptions = {}
+): Promise<WebSocketConn> {
+  const { protocols = [] } = options;
+  const builder = flatbuffers.createBuilder();
+  const url_ = builder.createString(url);
+  const protocols_ = msg.WebSocketDial.createProtocolsVector(
+    builder,
+    protocols.map(p => builder.createString(p))
+  );
+  const inner = msg.WebSocketDial.createWebSocketDial(
+    builder,
+    url_,
+    protocols_
+  );
+  const baseRes = await dispatch.sendAsync(
+    builder,
+    msg.Any.WebSocketDial,
+    inner
+  );
+  assert(baseRes != null);
+  assert(msg.Any.WebSocketDialRes === baseRes!.innerType());
+  const res = new msg.WebSocketDialRes();
+  assert(baseRes!.inner(res) != null);
+  return new WebSocketConn(res.rid(), res.protocol() || "");
+}
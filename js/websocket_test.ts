@@ -0,0 +1,57 @@
+// Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import { test, testPerm, assert, assertEquals } from "./test_util.ts";
+
+test(async function dialWebSocketPerm() {
+  let err;
+  try {
+    await Deno.dialWebSocket("ws://localhost:4549/");
+  } catch (err_) {
+    err = err_;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.PermissionDenied);
+  assertEquals(err.name, "PermissionDenied");
+});
+
+testPerm({ net: true }, async function dialWebSocketInvalidScheme() {
+  let err;
+  try {
+    await Deno.dialWebSocket("http://localhost:4549/");
+  } catch (err_) {
+    err = err_;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.InvalidInput);
+});
+
+testPerm({ net: true }, async function dialWebSocketEcho() {
+  const ws = await Deno.dialWebSocket("ws://localhost:4549/", {
+    protocols: ["echo", "chat"]
+  });
+  assertEquals(ws.protocol, "echo");
+  await ws.send("hello");
+  assertEquals(await ws.receive(), { type: "text", data: "hello" });
+  await ws.ping(new Uint8Array([1, 2]));
+  const pong = await ws.receive();
+  assertEquals(pong!.type, "pong");
+  assertEquals(Array.from((pong as { data: Uint8Array }).data), [1, 2]);
+  await ws.close(1000, "done");
+  assertEquals(await ws.receive(), null);
+  let err;
+  try {
+    await ws.send("closed");
+  } catch (err_) {
+    err = err_;
+  }
+  assertEquals(err.kind, Deno.ErrorKind.BadResource);
+});
+
+testPerm({ net: true }, async function dialWebSocketIterator() {
+  const ws = await Deno.dialWebSocket("ws://localhost:4549/");
+  assertEquals(ws.protocol, "");
+  await ws.send("close:4000");
+  const messages = [];
+  for await (const message of ws) {
+    messages.push(message);
+  }
+  assertEquals(messages, [{ type: "close", code: 4000, reason: "bye" }]);
+  assert(!Deno.resources()[ws.rid]);
+});
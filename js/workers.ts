@@ -3,6 +3,8 @@
 import { sendAsync, sendSync } from "./dispatch";
 import * as msg from "gen/cli/msg_generated";
 import * as flatbuffers from "./flatbuffers";
+import { close } from "./files";
+import { deserialize, serialize } from "./structured_clone";
 import { assert, log } from "./util";
 import { TextDecoder, TextEncoder } from "./text_encoding";
 import { window } from "./window";
@@ -10,20 +12,60 @@ import { window } from "./window";
 const encoder = new TextEncoder();
 const decoder = new TextDecoder();
 
+/** Messages are structured clones, see `structured_clone.ts`. */
 export function encodeMessage(data: any): Uint8Array {
-  const dataJson = JSON.stringify(data);
-  return encoder.encode(dataJson);
+  return encoder.encode(serialize(data));
 }
 
 export function decodeMessage(dataIntArray: Uint8Array): any {
-  const dataJson = decoder.decode(dataIntArray);
-  return JSON.parse(dataJson);
+  return deserialize(decoder.decode(dataIntArray));
 }
 
-function createWorker(specifier: string): number {
+/** The permissions of a worker. Those which are false are denied, the others
+ * are the ones of its creator: a worker never has more permissions.
+ */
+export interface WorkerPermissions {
+  read?: boolean;
+  write?: boolean;
+  net?: boolean;
+  env?: boolean;
+  run?: boolean;
+  highPrecision?: boolean;
+}
+
+export interface WorkerOptions {
+  permissions?: WorkerPermissions;
+}
+
+/** The uncaught error which ended a worker. */
+export interface WorkerErrorEvent {
+  message: string;
+  filename: string;
+  lineno: number;
+  colno: number;
+}
+
+function createWorker(
+  specifier: string,
+  permissions: WorkerPermissions
+): number {
   const builder = flatbuffers.createBuilder();
   const specifier_ = builder.createString(specifier);
-  const inner = msg.CreateWorker.createCreateWorker(builder, specifier_);
+  const keep = (allowed: boolean | undefined): boolean => allowed !== false;
+  const permissions_ = msg.WorkerPermissions.createWorkerPermissions(
+    builder,
+    keep(permissions.read),
+    keep(permissions.write),
+    keep(permissions.net),
+    keep(permissions.env),
+    keep(permissions.run),
+    keep(permissions.highPrecision)
+  );
+  const inner = msg.CreateWorker.createCreateWorker(
+    builder,
+    specifier_,
+    permissions_
+  );
   const baseRes = sendSync(builder, msg.Any.CreateWorker, inner);
   assert(baseRes != null);
   assert(
@@ -35,10 +77,39 @@ function createWorker(specifier: string): number {
   return res.rid();
 }
 
-async function hostGetWorkerClosed(rid: number): Promise<void> {
+interface WorkerError {
+  event: WorkerErrorEvent;
+  formatted: string;
+}
+
+/** Resolves when the worker is done, to its uncaught error if there is one. */
+async function hostGetWorkerClosed(rid: number): Promise<WorkerError | null> {
   const builder = flatbuffers.createBuilder();
   const inner = msg.HostGetWorkerClosed.createHostGetWorkerClosed(builder, rid);
-  await sendAsync(builder, msg.Any.HostGetWorkerClosed, inner);
+  const baseRes = await sendAsync(builder, msg.Any.HostGetWorkerClosed, inner);
+  assert(baseRes != null);
+  assert(msg.Any.HostGetWorkerClosedRes === baseRes!.innerType());
+  const res = new msg.HostGetWorkerClosedRes();
+  assert(baseRes!.inner(res) != null);
+  if (!res.error()) {
+    return null;
+  }
+  return {
+    event: {
+      message: res.message()!,
+      filename: res.filename() || "",
+      lineno: res.lineNumber(),
+      colno: res.columnNumber()
+    },
+    formatted: res.formatted()!
+  };
+}
+
+function hostTerminateWorker(rid: number): void {
+  const builder = flatbuffers.createBuilder();
+  const inner = msg.HostTerminateWorker.createHostTerminateWorker(builder, rid);
+  const baseRes = sendSync(builder, msg.Any.HostTerminateWorker, inner);
+  assert(baseRes == null);
 }
 
 function hostPostMessage(rid: number, data: any): void {
@@ -142,27 +213,49 @@ export async function workerMain(): Promise<void> {
 }
 
 export interface Worker {
-  onerror?: () => void;
+  onerror?: (e: WorkerErrorEvent) => void;
   onmessage?: (e: { data: any }) => void;
   onmessageerror?: () => void;
   postMessage(data: any): void;
+  terminate(): void;
   closed: Promise<void>;
 }
 
+/** A worker runs the module `specifier` in its own isolate, at the same time
+ * as its creator. The messages are structured clones of the posted values.
+ * The program keeps running while a worker runs.
+ *
+ *       const worker = new Worker("./worker.ts", {
+ *         permissions: { net: false, run: false }
+ *       });
+ *       worker.onmessage = e => console.log(e.data);
+ *       worker.onerror = e => console.error(e.message);
+ *       worker.postMessage({ numbers: new Float64Array([1, 2]) });
+ *
+ * An uncaught error ends the worker, not its creator: it is given to
+ * `onerror`, or printed without a handler.
+ */
 export class WorkerImpl implements Worker {
   private readonly rid: number;
   private isClosing: boolean = false;
   private readonly isClosedPromise: Promise<void>;
-  public onerror?: () => void;
+  public onerror?: (e: WorkerErrorEvent) => void;
   public onmessage?: (data: any) => void;
   public onmessageerror?: () => void;
 
-  constructor(specifier: string) {
-    this.rid = createWorker(specifier);
+  constructor(specifier: string, options: WorkerOptions = {}) {
+    this.rid = createWorker(specifier, options.permissions || {});
     this.run();
-    this.isClosedPromise = hostGetWorkerClosed(this.rid);
-    this.isClosedPromise.then(() => {
+    this.isClosedPromise = hostGetWorkerClosed(this.rid).then(error => {
       this.isClosing = true;
+      if (error === null) {
+        return;
+      }
+      if (this.onerror) {
+        this.onerror(error.event);
+      } else {
+        console.error(error.formatted);
+      }
     });
   }
 
@@ -170,8 +263,21 @@ export class WorkerImpl implements Worker {
     return this.isClosedPromise;
   }
 
+  /** Messages to a worker which is done are dropped. */
   postMessage(data: any): void {
-    hostPostMessage(this.rid, data);
+    if (!this.isClosing) {
+      hostPostMessage(this.rid, data);
+    }
+  }
+
+  /** Stops the worker at once, even in the middle of a computation. Its
+   * messages which were not received yet are dropped.
+   */
+  terminate(): void {
+    if (!this.isClosing) {
+      this.isClosing = true;
+      hostTerminateWorker(this.rid);
+    }
   }
 
   private async run(): Promise<void> {
@@ -182,10 +288,12 @@ export class WorkerImpl implements Worker {
         break;
       }
       // TODO(afinch7) stop this from eating messages before onmessage has been assigned
-      if (this.onmessage) {
+      if (this.onmessage && !this.isClosing) {
         const event = { data };
         this.onmessage(event);
       }
     }
+    // All messages were received, or the worker was terminated.
+    close(this.rid);
   }
 }
@@ -0,0 +1,5 @@
+import { printHello } from "hello";
+import { printLibHello } from "lib/mod.ts";
+
+printHello();
+printLibHello();
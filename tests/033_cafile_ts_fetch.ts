@@ -0,0 +1,3 @@
+import { printHello } from "https://localhost:5545/tests/subdir/print_hello.ts";
+
+printHello();
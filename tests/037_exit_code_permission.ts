@@ -0,0 +1 @@
+Deno.readFileSync("package.json");
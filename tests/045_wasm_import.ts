@@ -0,0 +1,3 @@
+import { quadruple } from "./subdir/quadruple.wasm";
+
+console.log(quadruple(5));
@@ -0,0 +1,6 @@
+// Piped to deno by 047_stdin.test, relative imports resolve against the cwd.
+import { printHello } from "./tests/subdir/print_hello.ts";
+
+const args: string[] = Deno.args;
+printHello();
+console.log(args.slice(1).join(" "));
@@ -0,0 +1,3 @@
+// A type error, which is not reported without type checking.
+const n: number = "not a number";
+console.log(n);
@@ -0,0 +1,23 @@
+interface Point {
+  x: number;
+  y: number;
+}
+
+type Pair = [number, number];
+
+function length(p: Point): number {
+  return Math.sqrt(p.x * p.x + p.y * p.y);
+}
+
+try {
+  throw new Error("caught");
+} catch (e) {
+  console.log(e.stack);
+}
+
+function fail(pair: Pair): never {
+  throw new Error(`bad pair ${pair}`);
+}
+
+console.log(length({ x: 3, y: 4 }));
+fail([1, 2]);
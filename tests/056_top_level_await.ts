@@ -0,0 +1,16 @@
+const delay = (ms: number): Promise<void> =>
+  new Promise(resolve => setTimeout(resolve, ms));
+
+async function* count(n: number): AsyncIterableIterator<number> {
+  for (let i = 1; i <= n; i++) {
+    await delay(1);
+    yield i;
+  }
+}
+
+console.log("before");
+await delay(10);
+console.log("after");
+for await (const i of count(3)) {
+  console.log(i);
+}
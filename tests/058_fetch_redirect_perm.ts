@@ -0,0 +1,13 @@
+async function main(): Promise<void> {
+  try {
+    await fetch("http://localhost:4546/tests/hello.txt");
+  } catch (e) {
+    console.log(e.name);
+  }
+  const res = await fetch("http://localhost:4546/tests/hello.txt", {
+    redirect: "manual"
+  });
+  console.log(res.status, res.headers.get("location"));
+}
+
+main();
@@ -0,0 +1,39 @@
+function show(message: Deno.WebSocketMessage | null): string {
+  if (message === null) {
+    return "null";
+  }
+  switch (message.type) {
+    case "text":
+      return `text ${message.data}`;
+    case "close":
+      return `close ${message.code} ${message.reason}`;
+    default:
+      return `${message.type} ${message.data.length} bytes`;
+  }
+}
+
+async function main(): Promise<void> {
+  const ws = await Deno.dialWebSocket("ws://localhost:4549/echo");
+  await ws.send("hello");
+  console.log(show(await ws.receive()));
+  await ws.send(new Uint8Array(100000).fill(7));
+  const binary = await ws.receive();
+  console.log(show(binary));
+  if (binary !== null && binary.type === "binary") {
+    console.log(binary.data.every(byte => byte === 7));
+  }
+  await ws.send("fragment:héllo");
+  console.log(show(await ws.receive()));
+  await ws.ping();
+  console.log(show(await ws.receive()));
+  await ws.close(1000, "done");
+  console.log(show(await ws.receive()));
+
+  const closing = await Deno.dialWebSocket("ws://localhost:4549/echo");
+  await closing.send("close:4000");
+  for await (const message of closing) {
+    console.log(show(message));
+  }
+}
+
+main();
@@ -0,0 +1,28 @@
+const worker = new Worker("tests/subdir/echo_worker.ts");
+
+try {
+  worker.postMessage({ callback: (): void => {} });
+} catch (e) {
+  console.log(e.name, e.message);
+}
+
+const buffer = new Uint8Array([1, 2, 3]).buffer;
+const message: { [key: string]: unknown } = {
+  map: new Map([["a", 1]]),
+  set: new Set([undefined, NaN]),
+  bytes: new Uint8Array(buffer, 1),
+  view: new DataView(buffer),
+  when: new Date(0),
+  pattern: /a+/g
+};
+message.self = message;
+
+worker.onmessage = e => {
+  const { data } = e;
+  console.log(data !== message, data.self === data);
+  console.log(data.map.get("a"), Array.from(data.set).map(String).join(","));
+  console.log(data.bytes.buffer === data.view.buffer, data.bytes[0]);
+  console.log(data.when.getTime(), data.pattern.source, data.pattern.flags);
+};
+
+worker.postMessage(message);
@@ -0,0 +1,18 @@
+const specifier = "tests/subdir/permissions_worker.ts";
+
+async function check(name: string, options?: WorkerOptions): Promise<void> {
+  const worker = new Worker(specifier, options);
+  worker.onmessage = e => {
+    console.log(`${name}: ${e.data.join(", ")}`);
+  };
+  worker.postMessage(null);
+  await worker.closed;
+}
+
+async function main(): Promise<void> {
+  await check("inherited");
+  await check("without read", { permissions: { read: false } });
+  await check("with env", { permissions: { env: true } });
+}
+
+main();
@@ -0,0 +1,11 @@
+const worker = new Worker("tests/subdir/throwing_worker.ts");
+
+worker.onerror = e => {
+  console.log("error:", e.message);
+  console.log(e.filename.endsWith("throwing_worker.ts"), e.lineno > 0);
+};
+
+worker.postMessage(null);
+worker.closed.then(() => {
+  console.log("the program goes on");
+});
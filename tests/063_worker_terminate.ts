@@ -0,0 +1,12 @@
+const worker = new Worker("tests/subdir/busy_worker.ts");
+
+worker.onmessage = async e => {
+  console.log(e.data);
+  worker.terminate();
+  await worker.closed;
+  console.log("terminated");
+  // Dropped since the worker is done.
+  worker.postMessage(null);
+};
+
+worker.postMessage(null);
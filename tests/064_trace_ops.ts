@@ -0,0 +1,10 @@
+async function main(): Promise<void> {
+  Deno.readFileSync("tests/064_trace_ops.ts");
+  try {
+    await Deno.open("tests/064_trace_ops.ts", "w");
+  } catch (e) {
+    console.log(e.name);
+  }
+}
+
+main();
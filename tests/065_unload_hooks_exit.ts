@@ -0,0 +1,19 @@
+Deno.addUnloadHook(code => {
+  console.log("first hook, exit code", code);
+});
+const remove = Deno.addUnloadHook(() => {
+  console.log("removed hook");
+});
+remove();
+Deno.addUnloadHook(() => {
+  console.log("second hook");
+  Deno.exit(3);
+});
+Deno.addUnloadHook(code => {
+  console.log("third hook, exit code", code);
+});
+
+setTimeout(() => {
+  console.log("exit");
+  Deno.exit(2);
+}, 0);
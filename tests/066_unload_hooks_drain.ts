@@ -0,0 +1,10 @@
+Deno.addUnloadHook(() => {
+  throw new Error("failing hook");
+});
+Deno.addUnloadHook(code => {
+  console.log("unload, exit code", code);
+});
+
+setTimeout(() => {
+  console.log("timeout");
+}, 10);
@@ -0,0 +1,14 @@
+const attempts = [
+  (): unknown => Deno.readFileSync("/etc/passwd"),
+  (): unknown => Deno.writeFileSync("/etc/passwd", new Uint8Array()),
+  (): unknown => Deno.listen("tcp", "127.0.0.1:4555"),
+  (): unknown => Deno.run({ args: ["ls"] }),
+  (): unknown => Deno.env()
+];
+for (const attempt of attempts) {
+  try {
+    attempt();
+  } catch (e) {
+    console.log(`${e.name}: ${e.message}`);
+  }
+}
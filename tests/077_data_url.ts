@@ -0,0 +1,4 @@
+import { greeting } from "data:application/typescript;base64,ZXhwb3J0IGNvbnN0IGdyZWV0aW5nOiBzdHJpbmcgPSAiSGVsbG8gZnJvbSBhIGRhdGEgVVJMIjsK";
+import answer from "data:text/javascript,export%20default%2042;";
+
+console.log(greeting, answer);
@@ -0,0 +1,5 @@
+import { printHello } from "./subdir/print_hello";
+import { double } from "./subdir/math";
+
+printHello();
+console.log(double(21));
@@ -0,0 +1,5 @@
+// @deno-types="./subdir/type_reference.d.ts"
+import { add } from "./subdir/type_reference.js";
+
+const sum: number = add(1, 2);
+console.log(sum);
@@ -0,0 +1,4 @@
+import { greet } from "http://localhost:4545/tests/subdir/type_headers.js";
+
+const greeting: string = greet("types");
+console.log(greeting);
@@ -0,0 +1,5 @@
+// Named like the errors of denied ops, but thrown by the script.
+class PermissionDenied extends Error {
+  name = "PermissionDenied";
+}
+throw new PermissionDenied("not from an op");
@@ -0,0 +1,9 @@
+import { numbers } from "./numbers.ts";
+
+Deno.bench(function sum(): void {
+  numbers.reduce((a, b) => a + b, 0);
+});
+
+Deno.bench("throws", (): void => {
+  throw new Error("bad benchmark");
+});
@@ -0,0 +1,2 @@
+// Not a bench file, deno bench only runs it as an import.
+export const numbers = Array.from({ length: 1000 }, (_, i) => i);
@@ -0,0 +1,5 @@
+Deno.bench({
+  name: "sleep",
+  iterations: 2,
+  fn: () => new Promise(resolve => setTimeout(resolve, 1))
+});
@@ -0,0 +1,10 @@
+/** Adds `a` and `b`. */
+export function sum(a: number, b: number): number {
+  return a + b;
+}
+
+function square(n: number): number {
+  return n * n;
+}
+
+export default square;
@@ -0,0 +1,13 @@
+// Re-exports in a cycle, deno doc walks each module once.
+export * from "./stack.ts";
+export { sum as add } from "./math.ts";
+
+/** Some overloads, the implementation is not documented. */
+export function parse(s: string): number;
+export function parse(s: string, radix: number): number;
+export function parse(s: string, radix = 10): number {
+  return parseInt(s, radix);
+}
+
+/** A value, or the promise of one. */
+export type Awaitable<T> = T | Promise<T>;
@@ -0,0 +1,25 @@
+export * from "./mod.ts";
+
+/**
+ * A last in, first out list.
+ *
+ *       const stack = new Stack<number>();
+ *       stack.push(1);
+ */
+export class Stack<T> {
+  private items: T[] = [];
+
+  /** Adds `item` on top. */
+  push(item: T): void {
+    this.items.push(item);
+  }
+
+  get size(): number {
+    return this.items.length;
+  }
+}
+
+export interface Sized {
+  /** The number of items. */
+  readonly size: number;
+}
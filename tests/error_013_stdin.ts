@@ -0,0 +1 @@
+const n: number = "not a number";
@@ -0,0 +1,2 @@
+import "./subdir/type_error_a.ts";
+import "./subdir/type_error_b.ts";
@@ -0,0 +1,5 @@
+function identity(x) {
+  return x;
+}
+
+console.log(identity(1));
@@ -0,0 +1,11 @@
+async function fetchData(): Promise<string> {
+  throw new Error("no data");
+}
+
+// Handled in a later tick, so it is not reported.
+const later = Promise.reject(new Error("handled later"));
+setTimeout(() => {
+  later.catch(() => console.log("caught later"));
+}, 0);
+
+fetchData();
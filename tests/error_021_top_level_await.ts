@@ -0,0 +1,2 @@
+await Promise.resolve();
+throw new Error("failed after await");
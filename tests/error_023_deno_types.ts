@@ -0,0 +1,4 @@
+// @deno-types="./subdir/type_reference.d.ts"
+import { add } from "./subdir/type_reference.js";
+
+console.log(add("1", 2));
@@ -0,0 +1,3 @@
+import { greet } from "http://localhost:4545/tests/subdir/type_headers.js";
+
+console.log(greet(true));
@@ -0,0 +1,21 @@
+// Downloads a large body chunk by chunk, then uploads a large file, without
+// keeping either in memory. See tools/fetch_stream_test.py.
+const [, url, path] = Deno.args;
+
+const response = await fetch(url);
+const buf = new Uint8Array(64 * 1024);
+let downloaded = 0;
+while (true) {
+  const { nread, eof } = await response.body.read(buf);
+  if (eof) {
+    break;
+  }
+  downloaded += nread;
+}
+response.body.close();
+console.log(downloaded);
+
+const file = await Deno.open(path);
+const upload = await fetch(url, { method: "POST", body: file });
+file.close();
+console.log(await upload.text());
@@ -0,0 +1,13 @@
+// Used for benchmarking Deno.serveHttp(). See tools/http_benchmark.py
+const addr = Deno.args[1] || "127.0.0.1:4500";
+const server = Deno.serveHttp(Deno.listen("tcp", addr));
+const body = new TextEncoder().encode("Hello World\n");
+
+async function main(): Promise<void> {
+  console.log("Listening on", addr);
+  for await (const req of server) {
+    req.respond({ body });
+  }
+}
+
+main();
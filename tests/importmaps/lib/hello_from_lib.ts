@@ -0,0 +1,3 @@
+export function printHello(): void {
+  console.log("Hello from lib");
+}
@@ -0,0 +1,3 @@
+// Inside of the "./lib/" scope "hello" maps to a different module.
+import { printHello } from "hello";
+export { printHello as printLibHello };
@@ -0,0 +1,3 @@
+for (let i = 0; i < 4; ++i) {
+  console.log(Math.random());
+}
@@ -0,0 +1,37 @@
+// Stats the files of the tree in $STAT_TREE_DIR, which tools/benchmark.py
+// makes, with an op for each file and with batches of ops.
+const { env, readDirSync, statSync, statSyncBatch } = Deno;
+
+const BATCH_SIZE = 100;
+
+function walk(dir, paths) {
+  for (const info of readDirSync(dir)) {
+    paths.push(info.path);
+    if (info.isDirectory()) {
+      walk(info.path, paths);
+    }
+  }
+  return paths;
+}
+
+const paths = walk(env().STAT_TREE_DIR, []);
+
+Deno.bench({
+  name: "stat_tree",
+  iterations: 10,
+  fn() {
+    for (const path of paths) {
+      statSync(path);
+    }
+  }
+});
+
+Deno.bench({
+  name: "stat_tree_batched",
+  iterations: 10,
+  fn() {
+    for (let i = 0; i < paths.length; i += BATCH_SIZE) {
+      statSyncBatch(paths.slice(i, i + BATCH_SIZE));
+    }
+  }
+});
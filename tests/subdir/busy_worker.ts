@@ -0,0 +1,4 @@
+onmessage = function(): void {
+  postMessage("busy");
+  while (true) {}
+};
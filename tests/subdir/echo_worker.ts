@@ -0,0 +1,4 @@
+onmessage = function(e): void {
+  postMessage(e.data);
+  workerClose();
+};
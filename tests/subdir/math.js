@@ -0,0 +1,6 @@
+// Imported by quadruple.wasm.
+export function double(x) {
+  return x * 2;
+}
+
+export const memory = new WebAssembly.Memory({ initial: 1 });
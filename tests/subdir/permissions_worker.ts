@@ -0,0 +1,16 @@
+function attempt(name: string, fn: () => void): string {
+  try {
+    fn();
+    return `${name}: allowed`;
+  } catch (e) {
+    return `${name}: ${e.name}`;
+  }
+}
+
+onmessage = function(): void {
+  postMessage([
+    attempt("read", () => Deno.readFileSync("tests/subdir/echo_worker.ts")),
+    attempt("env", () => Deno.env())
+  ]);
+  workerClose();
+};
@@ -0,0 +1,3 @@
+onmessage = function(): void {
+  throw new Error("boom");
+};
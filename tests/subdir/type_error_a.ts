@@ -0,0 +1 @@
+export const a: number = "a";
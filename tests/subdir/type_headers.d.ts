@@ -0,0 +1 @@
+export function greet(name: string): string;
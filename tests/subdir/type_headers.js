@@ -0,0 +1,4 @@
+// Served with an X-TypeScript-Types header which points to type_headers.d.ts.
+export function greet(name) {
+  return "Hello " + name;
+}
@@ -0,0 +1,24 @@
+import { sum } from "./sum.ts";
+
+Deno.test(function addition(): void {
+  if (sum(1, 2) !== 3) {
+    throw new Error("1 + 2 is not 3");
+  }
+});
+
+Deno.test("failing assertion", (): void => {
+  throw new Error("expected 2, got 3");
+});
+
+Deno.test(async function asyncSleep(): Promise<void> {
+  await new Promise(resolve => setTimeout(resolve, 10));
+});
+
+Deno.test("uncaught timer error", async (): Promise<void> => {
+  await new Promise(resolve => {
+    setTimeout(() => {
+      throw new Error("thrown in a timer");
+    }, 0);
+    setTimeout(resolve, 50);
+  });
+});
@@ -0,0 +1,9 @@
+Deno.test("async rejection", async () => {
+  await Promise.resolve();
+  throw new Error("rejected");
+});
+
+Deno.test({
+  name: "in a test.js file",
+  fn() {}
+});
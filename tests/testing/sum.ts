@@ -0,0 +1,4 @@
+// Not a test file, deno test only runs it as an import.
+export function sum(a: number, b: number): number {
+  return a + b;
+}
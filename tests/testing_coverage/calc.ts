@@ -0,0 +1,11 @@
+// Only abs() is tested, by calc_test.ts.
+export function abs(n: number): number {
+  if (n < 0) {
+    return -n;
+  }
+  return n;
+}
+
+export function sign(n: number): number {
+  return n < 0 ? -1 : 1;
+}
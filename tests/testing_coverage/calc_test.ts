@@ -0,0 +1,13 @@
+import { abs } from "./calc.ts";
+
+Deno.test(function absOfPositive(): void {
+  if (abs(2) !== 2) {
+    throw new Error("abs(2) is not 2");
+  }
+});
+
+Deno.test(function absOfNegative(): void {
+  if (abs(-2) !== 3) {
+    throw new Error("abs(-2) is not 3");
+  }
+});
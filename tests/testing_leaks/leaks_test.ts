@@ -0,0 +1,23 @@
+const file = "tests/testing_leaks/leaks_test.ts";
+
+Deno.test(async function leaksFile(): Promise<void> {
+  await Deno.open(file);
+});
+
+Deno.test(function leaksOpAndTimer(): void {
+  Deno.stat(file);
+  setTimeout(() => {}, 0);
+});
+
+Deno.test({
+  name: "leaksOnPurpose",
+  sanitizeResources: false,
+  async fn(): Promise<void> {
+    await Deno.open(file);
+  }
+});
+
+Deno.test(async function closesFile(): Promise<void> {
+  const f = await Deno.open(file);
+  f.close();
+});
@@ -0,0 +1,5 @@
+Deno.test(async function waitsForever(): Promise<void> {
+  await new Promise(() => {});
+});
+
+Deno.test(function neverRuns(): void {});
@@ -27,6 +27,9 @@ exec_time_benchmarks = [
     ("cold_relative_import", ["--reload", "tests/003_relative_import.ts"]),
     ("workers_startup", ["tests/workers_startup_bench.ts"]),
     ("workers_round_robin", ["tests/workers_round_robin_bench.ts"]),
+    ("hello_no_snapshot", ["--no-snapshot", "tests/002_hello.ts"]),
+    ("cold_hello_no_snapshot",
+     ["--reload", "--no-snapshot", "tests/002_hello.ts"]),
 ]
 
 gh_pages_data_file = "gh-pages/data.json"
@@ -81,6 +84,17 @@ def get_binary_sizes(build_dir):
     return sizes
 
 
+def snapshot_startup_delta(benchmark):
+    # How much longer the benchmarks run with --no-snapshot, in seconds, which
+    # is what the snapshots save at startup.
+    delta = {}
+    for name, data in benchmark.items():
+        no_snapshot = benchmark.get(name + "_no_snapshot")
+        if no_snapshot:
+            delta[name] = no_snapshot["mean"] - data["mean"]
+    return delta
+
+
 def get_strace_summary_text(test_args):
     f = tempfile.NamedTemporaryFile()
     run(["strace", "-c", "-f", "-o", f.name] + test_args)
@@ -135,13 +149,25 @@ def run_thread_count_benchmark(deno_path):
     return thread_count_map
 
 
+throughput_benchmarks = [
+    ("100M_tcp", throughput_benchmark.tcp, 100),
+    ("100M_cat", throughput_benchmark.cat, 100),
+    ("10M_tcp", throughput_benchmark.tcp, 10),
+    ("10M_cat", throughput_benchmark.cat, 10),
+    ("1G_cat", throughput_benchmark.cat, 1024),
+]
+
+
+# Returns the seconds each of throughput_benchmarks takes, and the CPU time it
+# takes, in which copying the data between buffers shows.
 def run_throughput(deno_exe):
     m = {}
-    m["100M_tcp"] = throughput_benchmark.tcp(deno_exe, 100)
-    m["100M_cat"] = throughput_benchmark.cat(deno_exe, 100)
-    m["10M_tcp"] = throughput_benchmark.tcp(deno_exe, 10)
-    m["10M_cat"] = throughput_benchmark.cat(deno_exe, 10)
-    return m
+    cpu = {}
+    for name, benchmark, megs in throughput_benchmarks:
+        start = throughput_benchmark.child_cpu_time()
+        m[name] = benchmark(deno_exe, megs)
+        cpu[name] = throughput_benchmark.child_cpu_time() - start
+    return m, cpu
 
 
 def run_syscall_count_benchmark(deno_path):
@@ -154,6 +180,27 @@ def run_syscall_count_benchmark(deno_path):
     return syscall_count_map
 
 
+def run_stat_tree_benchmark(deno_path):
+    # A tree of 10k files, 100 directories of 100 files each, which the
+    # benchmarks of tests/stat_tree_bench.js stat one op at a time or batched.
+    tree = tempfile.mkdtemp()
+    try:
+        for i in range(100):
+            directory = os.path.join(tree, str(i))
+            os.mkdir(directory)
+            for j in range(100):
+                open(os.path.join(directory, str(j)), "w").close()
+        args = [
+            deno_path, "bench", "--allow-read", "--allow-env", "--json",
+            "tests/stat_tree_bench.js"
+        ]
+        output = run_output(args, merge_env={"STAT_TREE_DIR": tree})
+    finally:
+        shutil.rmtree(tree)
+    results = json.loads(output)["results"]
+    return {result["name"]: result["mean"] for result in results}
+
+
 def main(argv):
     if len(argv) == 2:
         build_dir = sys.argv[1]
@@ -188,6 +235,7 @@ def main(argv):
         "binary_size": {},
         "thread_count": {},
         "syscall_count": {},
+        "stat_tree": {},
         "benchmark": {}
     }
     for [[name, _], data] in zip(exec_time_benchmarks,
@@ -200,14 +248,18 @@ def main(argv):
             "min": data["min"],
             "max": data["max"]
         }
+    new_data["snapshot_startup_delta"] = snapshot_startup_delta(
+        new_data["benchmark"])
 
     new_data["binary_size"] = get_binary_sizes(build_dir)
+    new_data["stat_tree"] = run_stat_tree_benchmark(deno_path)
     # Cannot run throughput benchmark on windows because they don't have nc or
     # pipe.
     if os.name != 'nt':
         hyper_hello_path = os.path.join(build_dir, "hyper_hello")
         core_http_bench_exe = os.path.join(build_dir, "deno_core_http_bench")
-        new_data["throughput"] = run_throughput(deno_path)
+        new_data["throughput"], new_data["throughput_cpu"] = run_throughput(
+            deno_path)
         stats = http_benchmark(deno_path, hyper_hello_path,
                                core_http_bench_exe)
         new_data["req_per_sec"] = {
@@ -20,6 +20,24 @@ def strace_parse_test():
         assert summary["total"]["calls"] == 704
 
 
+def snapshot_startup_delta_test():
+    delta = benchmark.snapshot_startup_delta({
+        "hello": {
+            "mean": 0.25
+        },
+        "hello_no_snapshot": {
+            "mean": 0.75
+        },
+        "cold_hello": {
+            "mean": 1.0
+        },
+        "error_001": {
+            "mean": 0.5
+        }
+    })
+    assert delta == {"hello": 0.5}
+
+
 def binary_size_test(build_dir):
     binary_size_dict = benchmark.get_binary_sizes(build_dir)
     assert binary_size_dict["deno"] > 0
@@ -42,6 +60,7 @@ def syscall_count_test(deno_path):
 
 def benchmark_test(build_dir, deno_path):
     strace_parse_test()
+    snapshot_startup_delta_test()
     binary_size_test(build_dir)
     if "linux" in sys.platform:
         thread_count_test(deno_path)
@@ -0,0 +1,40 @@
+#!/usr/bin/env python
+# Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import os
+import shutil
+import sys
+from util import mkdtemp, run_output, tests_path, green_ok
+
+# Modules with local, remote and JSON imports.
+MODULES = ["005_more_imports.ts", "006_url_imports.ts", "020_json_modules.ts"]
+
+
+def bundle_test(deno_exe):
+    sys.stdout.write("bundle_test...")
+    sys.stdout.flush()
+
+    d = mkdtemp()
+    try:
+        for name in MODULES:
+            t = os.path.join(tests_path, name)
+            out_file = os.path.join(d, name + ".js")
+            run_output([deno_exe, "bundle", t, out_file], quiet=True)
+            # The bundle stands on its own.
+            actual = run_output([deno_exe, out_file], quiet=True)
+            with open(t + ".out") as f:
+                expected = f.read()
+            assert actual.strip() == expected.strip(), name
+
+        t = os.path.join(tests_path, MODULES[0])
+        bundle = run_output([deno_exe, "bundle", "--sourcemap", t],
+                            quiet=True)
+        assert bundle.startswith("const __denoBundle")
+        assert "sourceMappingURL=data:application/json;base64," in bundle
+    finally:
+        shutil.rmtree(d)
+
+    print green_ok()
+
+
+if __name__ == "__main__":
+    bundle_test(sys.argv[1])
@@ -2,10 +2,11 @@
 # Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
 # Check deno dir is created properly
 # Usage: deno_dir_test.py [path to deno dir]
+import json
 import os
 import subprocess
 import sys
-from util import rmtree, run
+from util import rmtree, run, run_output
 
 
 def deno_dir_test(deno_exe, deno_dir):
@@ -28,12 +29,37 @@ def deno_dir_test(deno_exe, deno_dir):
     assert os.path.isdir(deno_dir)
     assert os.path.isdir(os.path.join(deno_dir, "deps"))
     assert os.path.isdir(os.path.join(deno_dir, "gen"))
+    info = deno_dir_info(deno_exe, merge_env={"DENO_DIR": deno_dir})
+    assert info["source"] == "env"
+    assert info["env"]["DENO_DIR"] == deno_dir
+    assert info["cacheSize"] > 0
     rmtree(deno_dir)
 
+    # --deno-dir takes precedence over DENO_DIR
+    flag_deno_dir = deno_dir + "_flag"
+    run([deno_exe, "--deno-dir", flag_deno_dir, "tests/002_hello.ts"],
+        quiet=True,
+        merge_env={"DENO_DIR": deno_dir})
+    assert not os.path.isdir(deno_dir)
+    assert os.path.isdir(os.path.join(flag_deno_dir, "deps"))
+    assert os.path.isdir(os.path.join(flag_deno_dir, "gen"))
+    info = deno_dir_info(
+        deno_exe, ["--deno-dir", flag_deno_dir],
+        merge_env={"DENO_DIR": deno_dir})
+    assert info["source"] == "flag"
+    assert info["denoDir"] == os.path.realpath(flag_deno_dir)
+    assert info["gen"] == os.path.join(info["denoDir"], "gen")
+    rmtree(flag_deno_dir)
+
     if old_deno_dir is not None:
         os.environ["DENO_DIR"] = old_deno_dir
 
 
+def deno_dir_info(deno_exe, args=None, merge_env=None):
+    cmd = [deno_exe, "info", "--json"] + (args or [])
+    return json.loads(run_output(cmd, quiet=True, merge_env=merge_env))
+
+
 def run_deno(deno_exe, deno_dir=None):
     cmd = [deno_exe, "tests/002_hello.ts"]
     deno_dir_env = {"DENO_DIR": deno_dir} if deno_dir is not None else None
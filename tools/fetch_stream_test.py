@@ -0,0 +1,95 @@
+#!/usr/bin/env python
+# Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+# Streams bodies larger than the memory deno may use through fetch, both ways,
+# see tests/fetch_stream.ts.
+import os
+import shutil
+import subprocess
+import sys
+import threading
+from BaseHTTPServer import BaseHTTPRequestHandler, HTTPServer
+from util import build_path, executable_suffix, mkdtemp, green_ok
+
+FETCH_STREAM_TS = "tests/fetch_stream.ts"
+PORT = 4555
+BODY_SIZE = 256 * 1024 * 1024
+CHUNK_SIZE = 64 * 1024
+# Deno itself stays well below this, a buffered body would not.
+MAX_RSS = 128 * 1024 * 1024
+
+
+class Handler(BaseHTTPRequestHandler):
+    protocol_version = "HTTP/1.1"
+
+    def do_GET(self):
+        self.send_response(200)
+        self.send_header("content-length", str(BODY_SIZE))
+        self.end_headers()
+        chunk = b"x" * CHUNK_SIZE
+        for _ in range(BODY_SIZE // CHUNK_SIZE):
+            self.wfile.write(chunk)
+
+    def do_POST(self):
+        # The upload is chunked, it is counted without keeping it.
+        received = 0
+        while True:
+            size = int(self.rfile.readline().split(b";")[0], 16)
+            if size == 0:
+                self.rfile.readline()
+                break
+            received += len(self.rfile.read(size))
+            self.rfile.readline()
+        body = str(received)
+        self.send_response(200)
+        self.send_header("content-length", str(len(body)))
+        self.end_headers()
+        self.wfile.write(body)
+
+    def log_message(self, *args):
+        pass
+
+
+def fetch_stream_test(deno_exe):
+    sys.stdout.write("fetch_stream_test...")
+    sys.stdout.flush()
+
+    server = HTTPServer(("127.0.0.1", PORT), Handler)
+    thread = threading.Thread(target=server.serve_forever)
+    thread.daemon = True
+    thread.start()
+    d = mkdtemp()
+    try:
+        # A sparse file, which takes neither memory nor disk space.
+        upload = os.path.join(d, "upload.bin")
+        with open(upload, "wb") as f:
+            f.truncate(BODY_SIZE)
+
+        url = "http://127.0.0.1:%d/" % PORT
+        cmd = [
+            deno_exe, "run", "--allow-net", "--allow-read", FETCH_STREAM_TS,
+            url, upload
+        ]
+        p = subprocess.Popen(cmd, stdout=subprocess.PIPE)
+        out = p.stdout.read()
+        _, status, usage = os.wait4(p.pid, 0)
+        assert os.WIFEXITED(status) and os.WEXITSTATUS(status) == 0
+        assert out.split() == [str(BODY_SIZE), str(BODY_SIZE)]
+        # Kilobytes on Linux, bytes on macOS.
+        max_rss = usage.ru_maxrss
+        if sys.platform != "darwin":
+            max_rss *= 1024
+        assert max_rss < MAX_RSS, "max RSS %d" % max_rss
+    finally:
+        server.shutdown()
+        shutil.rmtree(d)
+
+    print green_ok()
+
+
+def main():
+    deno_exe = os.path.join(build_path(), "deno" + executable_suffix)
+    fetch_stream_test(deno_exe)
+
+
+if __name__ == "__main__":
+    main()
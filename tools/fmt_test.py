@@ -5,6 +5,7 @@ import sys
 from util import mkdtemp, root_path, tests_path, run, green_ok
 import shutil
 import json
+import subprocess
 
 
 def fmt_test(deno_exe):
@@ -19,6 +20,21 @@ def fmt_test(deno_exe):
         # Set DENO_DIR to //js/ so we don't have to rely on an intenet
         # connection to download https://deno.land/std/prettier/main.ts
         deno_dir = os.path.join(root_path, "js")
+        # --check must report the file and fail without modifying it.
+        rc = subprocess.call(
+            [os.path.join(root_path, deno_exe), "fmt", "--check",
+             "badly_formatted.js"],
+            cwd=d,
+            env=dict(os.environ, DENO_DIR=deno_dir))
+        if rc == 0:
+            print "Expected fmt --check to fail on an unformatted file."
+            sys.exit(1)
+        with open(src) as f:
+            original = f.read()
+        with open(dst) as f:
+            if f.read() != original:
+                print "fmt --check must not modify files."
+                sys.exit(1)
         # TODO(kt3k) The below line should be run([deno_exe, "fmt", dst], ...)
         # It should be updated when the below issue is addressed
         # https://github.com/denoland/deno_std/issues/330
@@ -35,6 +51,21 @@ def fmt_test(deno_exe):
             print "actual: ", json.dumps(actual)
             sys.exit(1)
 
+        # "deno fmt -" formats stdin to stdout without touching any file.
+        with open(src) as f:
+            p = subprocess.Popen(
+                [os.path.join(root_path, deno_exe), "fmt", "-"],
+                cwd=d,
+                stdin=f,
+                stdout=subprocess.PIPE,
+                env=dict(os.environ, DENO_DIR=deno_dir))
+            actual, _ = p.communicate()
+        if p.returncode != 0 or expected != actual:
+            print "Expected stdin to be formatted to stdout."
+            print "expected: ", json.dumps(expected)
+            print "actual: ", json.dumps(actual)
+            sys.exit(1)
+
     finally:
         shutil.rmtree(d)
     print green_ok()
@@ -30,6 +30,12 @@ def deno_net_http_benchmark(deno_exe):
         })
 
 
+def deno_http_serve_benchmark(deno_exe):
+    deno_cmd = [deno_exe, "--allow-net", "tests/http_serve_bench.ts", ADDR]
+    print "http_benchmark testing DENO using Deno.serveHttp()."
+    return run(deno_cmd)
+
+
 def deno_core_single(exe):
     print "http_benchmark testing deno_core_single"
     return run([exe, "--single-thread"])
@@ -65,6 +71,7 @@ def http_benchmark(deno_exe, hyper_hello_exe, core_http_bench_exe):
     return {
         "deno": deno_http_benchmark(deno_exe),
         "deno_net_http": deno_net_http_benchmark(deno_exe),
+        "deno_http_serve": deno_http_serve_benchmark(deno_exe),
         "deno_core_single": deno_core_single(core_http_bench_exe),
         "deno_core_multi": deno_core_multi(core_http_bench_exe),
         "node": node_http_benchmark(),
@@ -2,7 +2,11 @@
 # Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
 # Many tests expect there to be an http server on port 4545 servering the deno
 # root directory.
+import base64
+import hashlib
 import os
+import ssl
+import struct
 import sys
 from threading import Thread
 import SimpleHTTPServer
@@ -14,6 +18,10 @@ PORT = 4545
 REDIRECT_PORT = 4546
 ANOTHER_REDIRECT_PORT = 4547
 DOUBLE_REDIRECTS_PORT = 4548
+HTTPS_PORT = 5545
+WEBSOCKET_PORT = 4549
+WEBSOCKET_GUID = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11"
+ETAG = '"33a64df5"'
 
 
 class ContentTypeHandler(SimpleHTTPServer.SimpleHTTPRequestHandler):
@@ -39,6 +47,46 @@ class ContentTypeHandler(SimpleHTTPServer.SimpleHTTPRequestHandler):
                       '\r\n--boundary--\r\n'
                       'Epilogue'))
             return
+        if "etag_script.ts" in self.path:
+            # Only sent again if the ETag of the cached copy is stale.
+            if self.headers.getheader('if-none-match') == ETAG:
+                self.send_response(304)
+                self.end_headers()
+                return
+            self.send_response(200)
+            self.send_header('Content-type', 'application/typescript')
+            self.send_header('ETag', ETAG)
+            self.end_headers()
+            self.wfile.write(bytes('console.log("etag");\n'))
+            return
+        if self.path.endswith("/type_headers.js"):
+            self.send_response(200)
+            self.send_header('Content-type', 'application/javascript')
+            self.send_header('X-TypeScript-Types', './type_headers.d.ts')
+            self.end_headers()
+            with open(os.path.join(root_path, self.path[1:])) as f:
+                self.wfile.write(f.read())
+            return
+        if self.path.startswith("/redirect_loop/"):
+            # a.js and b.js redirect to each other.
+            other = "b.js" if self.path.endswith("/a.js") else "a.js"
+            self.send_response(302)
+            self.send_header('Location', "/redirect_loop/" + other)
+            self.end_headers()
+            return
+        if self.path == "/cookies/set":
+            self.send_response(200)
+            self.send_header('Set-Cookie', 'deno_test=1; Path=/cookies')
+            self.end_headers()
+            return
+        if self.path == "/cookies/echo":
+            # The cookies sent with the request are the body of the response.
+            cookie = self.headers.getheader('cookie') or ''
+            self.send_response(200)
+            self.send_header('Content-type', 'text/plain')
+            self.end_headers()
+            self.wfile.write(bytes(cookie))
+            return
         return SimpleHTTPServer.SimpleHTTPRequestHandler.do_GET(self)
 
     def do_POST(self):
@@ -94,6 +142,7 @@ def server():
         ".ts": "application/typescript",
         ".js": "application/javascript",
         ".json": "application/json",
+        ".wasm": "application/wasm",
     })
     SocketServer.TCPServer.allow_reuse_address = True
     s = SocketServer.TCPServer(("", PORT), Handler)
@@ -101,6 +150,20 @@ def server():
     return s
 
 
+# https server with a certificate only trusted via --cert=tests/tls/RootCA.pem
+def https_server():
+    os.chdir(root_path)
+    SocketServer.TCPServer.allow_reuse_address = True
+    s = SocketServer.TCPServer(("", HTTPS_PORT), ContentTypeHandler)
+    s.socket = ssl.wrap_socket(
+        s.socket,
+        certfile=os.path.join(root_path, "tests/tls/localhost.crt"),
+        keyfile=os.path.join(root_path, "tests/tls/localhost.key"),
+        server_side=True)
+    print "Deno https test server https://localhost:%d/" % HTTPS_PORT
+    return s
+
+
 def base_redirect_server(host_port, target_port, extra_path_segment=""):
     os.chdir(root_path)
     target_host = "http://localhost:%d" % target_port
@@ -137,6 +200,90 @@ def double_redirects_server():
     return base_redirect_server(DOUBLE_REDIRECTS_PORT, REDIRECT_PORT)
 
 
+# WebSocket server which echoes the messages. "fragment:<text>" is echoed
+# with a frame per byte of the text, "close:<code>" makes it close the
+# connection with that code.
+class WebSocketEchoHandler(SocketServer.StreamRequestHandler):
+    def handle(self):
+        self.rfile.readline()  # The request line.
+        headers = {}
+        while True:
+            line = self.rfile.readline().strip()
+            if not line:
+                break
+            name, _, value = line.partition(":")
+            headers[name.strip().lower()] = value.strip()
+        accept = base64.b64encode(
+            hashlib.sha1(headers["sec-websocket-key"] +
+                         WEBSOCKET_GUID).digest())
+        response = [
+            "HTTP/1.1 101 Switching Protocols", "Upgrade: websocket",
+            "Connection: Upgrade", "Sec-WebSocket-Accept: " + accept
+        ]
+        protocols = headers.get("sec-websocket-protocol")
+        if protocols:
+            protocol = protocols.split(",")[0].strip()
+            response.append("Sec-WebSocket-Protocol: " + protocol)
+        self.wfile.write("\r\n".join(response) + "\r\n\r\n")
+        while True:
+            frame = self.read_frame()
+            if frame is None:
+                return
+            opcode, payload = frame
+            if opcode == 0x1 and payload.startswith("fragment:"):
+                text = payload[len("fragment:"):]
+                for i, c in enumerate(text):
+                    self.write_frame(
+                        0x1 if i == 0 else 0x0, c, fin=i == len(text) - 1)
+            elif opcode == 0x1 and payload.startswith("close:"):
+                code = int(payload[len("close:"):])
+                self.write_frame(0x8, struct.pack("!H", code) + "bye")
+                self.read_frame()  # The reply of the client.
+                return
+            elif opcode == 0x8:
+                self.write_frame(0x8, payload)
+                return
+            elif opcode == 0x9:
+                self.write_frame(0xa, payload)
+            else:
+                self.write_frame(opcode, payload)
+
+    def read_frame(self):
+        header = self.rfile.read(2)
+        if len(header) < 2:
+            return None
+        opcode = ord(header[0]) & 0x0f
+        length = ord(header[1]) & 0x7f
+        if length == 126:
+            length, = struct.unpack("!H", self.rfile.read(2))
+        elif length == 127:
+            length, = struct.unpack("!Q", self.rfile.read(8))
+        # The frames of a client are masked.
+        mask = [ord(c) for c in self.rfile.read(4)]
+        payload = self.rfile.read(length)
+        return opcode, "".join(
+            chr(ord(c) ^ mask[i % 4]) for i, c in enumerate(payload))
+
+    def write_frame(self, opcode, payload, fin=True):
+        header = chr((0x80 if fin else 0) | opcode)
+        if len(payload) < 126:
+            header += chr(len(payload))
+        elif len(payload) < 65536:
+            header += chr(126) + struct.pack("!H", len(payload))
+        else:
+            header += chr(127) + struct.pack("!Q", len(payload))
+        self.wfile.write(header + payload)
+
+
+def websocket_server():
+    SocketServer.ThreadingTCPServer.allow_reuse_address = True
+    s = SocketServer.ThreadingTCPServer(("", WEBSOCKET_PORT),
+                                        WebSocketEchoHandler)
+    s.daemon_threads = True
+    print "websocket echo server ws://localhost:%d/" % WEBSOCKET_PORT
+    return s
+
+
 def spawn():
     # Main http server
     s = server()
@@ -158,6 +305,16 @@ def spawn():
     dr_thread = Thread(target=drs.serve_forever)
     dr_thread.daemon = True
     dr_thread.start()
+    # Https server
+    hs = https_server()
+    h_thread = Thread(target=hs.serve_forever)
+    h_thread.daemon = True
+    h_thread.start()
+    # WebSocket echo server
+    ws = websocket_server()
+    ws_thread = Thread(target=ws.serve_forever)
+    ws_thread.daemon = True
+    ws_thread.start()
     sleep(1)  # TODO I'm too lazy to figure out how to do this properly.
     return thread
 
@@ -0,0 +1,136 @@
+#!/usr/bin/env python
+# Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+# Debugs a script like Chrome DevTools does: finds the target at /json, opens
+# its WebSocket and resumes the script from the break on start.
+import base64
+import hashlib
+import json
+import os
+import socket
+import struct
+import subprocess
+import sys
+import threading
+import urllib2
+from util import green_ok, tests_path
+
+TIMEOUT = 30
+WEBSOCKET_GUID = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11"
+
+
+def websocket_connect(host, port, path):
+    s = socket.create_connection((host, port), TIMEOUT)
+    key = base64.b64encode(os.urandom(16))
+    s.sendall("GET %s HTTP/1.1\r\n"
+              "Host: %s:%d\r\n"
+              "Upgrade: websocket\r\n"
+              "Connection: Upgrade\r\n"
+              "Sec-WebSocket-Key: %s\r\n"
+              "Sec-WebSocket-Version: 13\r\n\r\n" % (path, host, port, key))
+    head = ""
+    while not head.endswith("\r\n\r\n"):
+        head += s.recv(1)
+    assert head.startswith("HTTP/1.1 101 ")
+    accept = base64.b64encode(hashlib.sha1(key + WEBSOCKET_GUID).digest())
+    assert "Sec-WebSocket-Accept: %s\r\n" % accept in head
+    return s
+
+
+def recv_exact(s, n):
+    data = ""
+    while len(data) < n:
+        chunk = s.recv(n - len(data))
+        assert chunk, "connection closed"
+        data += chunk
+    return data
+
+
+def send_message(s, message):
+    # Clients mask their frames.
+    payload = json.dumps(message)
+    mask = os.urandom(4)
+    if len(payload) < 126:
+        header = struct.pack("!BB", 0x81, 0x80 | len(payload))
+    else:
+        header = struct.pack("!BBH", 0x81, 0x80 | 126, len(payload))
+    masked = "".join(
+        chr(ord(c) ^ ord(mask[i % 4])) for i, c in enumerate(payload))
+    s.sendall(header + mask + masked)
+
+
+def recv_message(s):
+    first, second = struct.unpack("!BB", recv_exact(s, 2))
+    # The server sends unfragmented, unmasked text frames.
+    assert first == 0x81
+    length = second & 0x7f
+    if length == 126:
+        length, = struct.unpack("!H", recv_exact(s, 2))
+    elif length == 127:
+        length, = struct.unpack("!Q", recv_exact(s, 8))
+    return json.loads(recv_exact(s, length))
+
+
+def recv_until(s, predicate):
+    while True:
+        message = recv_message(s)
+        if predicate(message):
+            return message
+
+
+def inspector_test(deno_exe):
+    sys.stdout.write("inspector_test...")
+    sys.stdout.flush()
+
+    script = os.path.join(tests_path, "002_hello.ts")
+    p = subprocess.Popen(
+        [deno_exe, "run", "--inspect-brk=127.0.0.1:0", script],
+        stdout=subprocess.PIPE,
+        stderr=subprocess.PIPE)
+    # Don't hang the test suite if the script is never resumed.
+    timer = threading.Timer(TIMEOUT, p.kill)
+    timer.start()
+    try:
+        line = p.stderr.readline()
+        prefix = "Debugger listening on ws://"
+        assert line.startswith(prefix)
+        addr, target_id = line[len(prefix):].strip().split("/")
+        host, port = addr.rsplit(":", 1)
+        port = int(port)
+        assert p.stderr.readline().startswith("Open devtools://")
+
+        targets = json.load(urllib2.urlopen("http://%s/json" % addr))
+        assert len(targets) == 1
+        target = targets[0]
+        assert target["id"] == target_id
+        assert target["webSocketDebuggerUrl"] == "ws://%s/%s" % (addr,
+                                                                 target_id)
+        assert target["devtoolsFrontendUrl"].startswith("devtools://")
+
+        s = websocket_connect(host, port, "/" + target_id)
+        try:
+            send_message(s, {"id": 1, "method": "Debugger.enable"})
+            recv_until(s, lambda m: m.get("id") == 1)
+            send_message(s, {
+                "id": 2,
+                "method": "Runtime.runIfWaitingForDebugger"
+            })
+            paused = recv_until(s,
+                                lambda m: m.get("method") == "Debugger.paused")
+            assert paused["params"]["reason"] == "Break on start"
+            send_message(s, {"id": 3, "method": "Debugger.resume"})
+            recv_until(s, lambda m: m.get("id") == 3)
+            assert p.stdout.readline().strip() == "Hello World"
+        finally:
+            s.close()
+        assert p.wait() == 0
+    finally:
+        timer.cancel()
+        if p.poll() is None:
+            p.kill()
+        p.communicate()
+
+    print green_ok()
+
+
+if __name__ == "__main__":
+    inspector_test(sys.argv[1])
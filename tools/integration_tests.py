@@ -65,6 +65,10 @@ def integration_tests(deno_exe, test_filter=None):
 
         stderr = subprocess.STDOUT if check_stderr else open(os.devnull, 'w')
 
+        # A file to pipe to stdin.
+        stdin_file = test.get("input")
+        stdin = open(os.path.join(root_path, stdin_file)) if stdin_file else None
+
         output_abs = os.path.join(root_path, test.get("output", ""))
         with open(output_abs, 'r') as f:
             expected_out = f.read()
@@ -74,7 +78,7 @@ def integration_tests(deno_exe, test_filter=None):
         actual_code = 0
         try:
             actual_out = subprocess.check_output(
-                cmd, universal_newlines=True, stderr=stderr)
+                cmd, universal_newlines=True, stderr=stderr, stdin=stdin)
         except subprocess.CalledProcessError as e:
             actual_code = e.returncode
             actual_out = e.output
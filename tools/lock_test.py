@@ -0,0 +1,61 @@
+#!/usr/bin/env python
+# Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import json
+import os
+import shutil
+import subprocess
+import sys
+from util import mkdtemp, tests_path, green_ok
+
+MOD2_URL = "http://localhost:4545/tests/subdir/mod2.ts"
+
+
+def run(deno_exe, args, deno_dir):
+    p = subprocess.Popen([deno_exe] + args,
+                         stdout=subprocess.PIPE,
+                         stderr=subprocess.PIPE,
+                         env=dict(os.environ, DENO_DIR=deno_dir))
+    out, err = p.communicate()
+    return p.returncode, out, err
+
+
+def lock_test(deno_exe):
+    sys.stdout.write("lock_test...")
+    sys.stdout.flush()
+
+    deno_dir = mkdtemp()
+    try:
+        t = os.path.join(tests_path, "006_url_imports.ts")
+        lock_file = os.path.join(deno_dir, "lock.json")
+
+        code, _, _ = run(deno_exe,
+                         ["--lock=" + lock_file, "--lock-write", t], deno_dir)
+        assert code == 0
+        with open(lock_file) as f:
+            lock = json.load(f)
+        assert MOD2_URL in lock
+        # Local modules are not locked.
+        assert all(url.startswith("http") for url in lock)
+
+        code, out, _ = run(deno_exe, ["--lock=" + lock_file, t], deno_dir)
+        assert code == 0
+        assert "success" in out
+
+        # Tamper with the cached copy of a dependency.
+        cached = os.path.join(
+            deno_dir, "deps/http/localhost_PORT4545/tests/subdir/mod2.ts")
+        with open(cached, "a") as f:
+            f.write("\nconsole.log('tampered');\n")
+        code, out, err = run(deno_exe, ["--lock=" + lock_file, t], deno_dir)
+        assert code != 0
+        assert "tampered" not in out
+        assert MOD2_URL in err
+        assert lock[MOD2_URL] in err
+    finally:
+        shutil.rmtree(deno_dir)
+
+    print green_ok()
+
+
+if __name__ == "__main__":
+    lock_test(sys.argv[1])
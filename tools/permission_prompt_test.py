@@ -14,16 +14,27 @@ PERMISSIONS_PROMPT_TEST_TS = "tools/permission_prompt_test.ts"
 
 PROMPT_PATTERN = b'⚠️'
 FIRST_CHECK_FAILED_PATTERN = b'First check failed'
-PERMISSION_DENIED_PATTERN = b'PermissionDenied: permission denied'
+PERMISSION_DENIED_PATTERN = b'PermissionDenied: '
+# The prompt names the call site in the script.
+CALL_SITE_PATTERN = b' from file://'
+# What the second check of each test asks for, a denial names it.
+ACCESS_PATTERNS = {
+    "read": b'read access to "',
+    "write": b'write access to "',
+    "env": b'access to environment variables',
+    "net": b'network access to "127.0.0.1:4541"',
+    "run": b'access to run "python"',
+}
 
 
 # This function is copied from:
 # https://gist.github.com/hayd/4f46a68fc697ba8888a7b517a414583e
 # https://stackoverflow.com/q/52954248/1240268
-def tty_capture(cmd, bytes_input, timeout=5):
+def tty_capture(cmd, bytes_input, timeout=5, pipe_stdout=False):
     """Capture the output of cmd with bytes_input to stdin,
-    with stdin, stdout and stderr as TTYs."""
-    mo, so = pty.openpty()  # provide tty to enable line-buffering
+    with stdin, stdout and stderr as TTYs, or stdout as a pipe."""
+    # provide tty to enable line-buffering
+    mo, so = os.pipe() if pipe_stdout else pty.openpty()
     me, se = pty.openpty()
     mi, si = pty.openpty()
     fdmap = {mo: 'stdout', me: 'stderr', mi: 'stdin'}
@@ -65,14 +76,26 @@ def wrap_test(test_name, test_method, *argv):
 
 
 class Prompt(object):
-    def __init__(self, deno_exe, test_types):
+    def __init__(self, deno_exe, test_types, scoped_test_types):
         self.deno_exe = deno_exe
         self.test_types = test_types
+        self.scoped_test_types = scoped_test_types
 
-    def run(self, flags, args, bytes_input):
+    def run(self, flags, args, bytes_input, pipe_stdout=False):
         "Returns (return_code, stdout, stderr)."
         cmd = [self.deno_exe] + flags + [PERMISSIONS_PROMPT_TEST_TS] + args
-        return tty_capture(cmd, bytes_input)
+        return tty_capture(cmd, bytes_input, pipe_stdout=pipe_stdout)
+
+    def run_piped(self, flags, args, bytes_input):
+        "Like run(), but without any TTY."
+        cmd = [self.deno_exe] + flags + [PERMISSIONS_PROMPT_TEST_TS] + args
+        p = subprocess.Popen(
+            cmd,
+            stdin=subprocess.PIPE,
+            stdout=subprocess.PIPE,
+            stderr=subprocess.PIPE)
+        stdout, stderr = p.communicate(bytes_input)
+        return p.returncode, stdout, stderr
 
     def warm_up(self):
         # ignore the ts compiling message
@@ -90,12 +113,21 @@ class Prompt(object):
             wrap_test(test_name_base + "_no_yes", self.test_no_yes, test_type)
             wrap_test(test_name_base + "_allow", self.test_allow, test_type)
             wrap_test(test_name_base + "_deny", self.test_deny, test_type)
+            if test_type in self.scoped_test_types:
+                wrap_test(test_name_base + "_allow_scope",
+                          self.test_allow_scope, test_type)
             wrap_test(test_name_base + "_unrecognized_option",
                       self.test_unrecognized_option, test_type)
             wrap_test(test_name_base + "_no_prompt", self.test_no_prompt,
                       test_type)
             wrap_test(test_name_base + "_no_prompt_allow",
                       self.test_no_prompt_allow, test_type)
+            wrap_test(test_name_base + "_piped_stdout",
+                      self.test_piped_stdout, test_type)
+            wrap_test(test_name_base + "_piped_stdin", self.test_piped_stdin,
+                      test_type)
+            wrap_test(test_name_base + "_prompt_stack",
+                      self.test_prompt_stack, test_type)
 
     def test_allow_flag(self, test_type):
         code, stdout, stderr = self.run(
@@ -110,13 +142,16 @@ class Prompt(object):
                                         b'y\ny\n')
         assert code == 0
         assert PROMPT_PATTERN in stderr
+        assert CALL_SITE_PATTERN in stderr
+        assert PERMISSIONS_PROMPT_TEST_TS.encode() + b':' in stderr
+        assert not b'    at ' in stderr
         assert not FIRST_CHECK_FAILED_PATTERN in stdout
         assert not PERMISSION_DENIED_PATTERN in stderr
 
     def test_yes_no(self, test_type):
         code, stdout, stderr = self.run([], ["needs" + test_type.capitalize()],
                                         b'y\nn\n')
-        assert code == 1
+        assert code == 3
         assert PROMPT_PATTERN in stderr
         assert not FIRST_CHECK_FAILED_PATTERN in stdout
         assert PERMISSION_DENIED_PATTERN in stderr
@@ -124,7 +159,7 @@ class Prompt(object):
     def test_no_no(self, test_type):
         code, stdout, stderr = self.run([], ["needs" + test_type.capitalize()],
                                         b'n\nn\n')
-        assert code == 1
+        assert code == 3
         assert PROMPT_PATTERN in stderr
         assert FIRST_CHECK_FAILED_PATTERN in stdout
         assert PERMISSION_DENIED_PATTERN in stderr
@@ -146,13 +181,25 @@ class Prompt(object):
         assert not FIRST_CHECK_FAILED_PATTERN in stdout
         assert not PERMISSION_DENIED_PATTERN in stderr
 
+    # Allowing the requested path/host/command must not prompt again when the
+    # same resource is requested a second time.
+    def test_allow_scope(self, test_type):
+        code, stdout, stderr = self.run([], ["needs" + test_type.capitalize()],
+                                        b's\n')
+        assert code == 0
+        assert stderr.count(PROMPT_PATTERN) == 1
+        assert not FIRST_CHECK_FAILED_PATTERN in stdout
+        assert not PERMISSION_DENIED_PATTERN in stderr
+
     def test_deny(self, test_type):
         code, stdout, stderr = self.run([], ["needs" + test_type.capitalize()],
                                         b'd\n')
-        assert code == 1
+        assert code == 3
         assert PROMPT_PATTERN in stderr
         assert FIRST_CHECK_FAILED_PATTERN in stdout
         assert PERMISSION_DENIED_PATTERN in stderr
+        assert ACCESS_PATTERNS[test_type] in stderr
+        assert b' was denied' in stderr
 
     def test_unrecognized_option(self, test_type):
         code, stdout, stderr = self.run([], ["needs" + test_type.capitalize()],
@@ -166,10 +213,12 @@ class Prompt(object):
     def test_no_prompt(self, test_type):
         code, stdout, stderr = self.run(
             ["--no-prompt"], ["needs" + test_type.capitalize()], b'')
-        assert code == 1
+        assert code == 3
         assert not PROMPT_PATTERN in stderr
         assert FIRST_CHECK_FAILED_PATTERN in stdout
         assert PERMISSION_DENIED_PATTERN in stderr
+        assert ACCESS_PATTERNS[test_type] in stderr
+        assert b", run again with --allow-" + test_type in stderr
 
     def test_no_prompt_allow(self, test_type):
         code, stdout, stderr = self.run(
@@ -180,9 +229,42 @@ class Prompt(object):
         assert not FIRST_CHECK_FAILED_PATTERN in stdout
         assert not PERMISSION_DENIED_PATTERN in stderr
 
+    def test_piped_stdout(self, test_type):
+        code, stdout, stderr = self.run(
+            [], ["needs" + test_type.capitalize()],
+            b'y\ny\n',
+            pipe_stdout=True)
+        assert code == 0
+        assert PROMPT_PATTERN in stderr
+        assert not PROMPT_PATTERN in stdout
+        assert not b'Grant?' in stdout
+
+    # Without a terminal to answer on there is no prompt, the same as with
+    # --no-prompt.
+    def test_piped_stdin(self, test_type):
+        code, stdout, stderr = self.run_piped(
+            [], ["needs" + test_type.capitalize()], b'y\ny\n')
+        assert code == 3
+        assert not PROMPT_PATTERN in stdout
+        assert not PROMPT_PATTERN in stderr
+        assert FIRST_CHECK_FAILED_PATTERN in stdout
+        assert b", run again with --allow-" + test_type in stderr
+
+    # With --prompt-stack the frames of the script are printed above the
+    # prompt, the runtime's own are left out.
+    def test_prompt_stack(self, test_type):
+        code, stdout, stderr = self.run(["--prompt-stack"],
+                                        ["needs" + test_type.capitalize()],
+                                        b'y\ny\n')
+        assert code == 0
+        assert PROMPT_PATTERN in stderr
+        assert b'    at ' in stderr
+        assert not b'gen/cli/bundle/main.js' in stderr
+
 
 def permission_prompt_test(deno_exe):
-    p = Prompt(deno_exe, ["read", "write", "env", "net", "run"])
+    p = Prompt(deno_exe, ["read", "write", "env", "net", "run"],
+               ["read", "write", "net", "run"])
     p.test()
 
 
@@ -1,6 +1,7 @@
 #!/usr/bin/env python
 # Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
 import os
+import subprocess
 import sys
 from util import mkdtemp, tests_path, run_output, green_ok
 import shutil
@@ -10,16 +11,33 @@ def prefetch_test(deno_exe):
     sys.stdout.write("prefetch_test...")
     sys.stdout.flush()
 
+    t = os.path.join(tests_path, "006_url_imports.ts")
+    for args in [["prefetch", t], ["--prefetch", t]]:
+        deno_dir = mkdtemp()
+        try:
+            output = run_output([deno_exe] + args,
+                                merge_env={"DENO_DIR": deno_dir})
+            # Every module of the graph is listed, nothing is executed.
+            assert "http://localhost:4545/tests/subdir/mod2.ts" in output
+            assert "success" not in output
+            # Check that we actually did the prefetch.
+            os.path.exists(
+                os.path.join(
+                    deno_dir,
+                    "deps/http/localhost_PORT4545/tests/subdir/mod2.ts"))
+        finally:
+            shutil.rmtree(deno_dir)
+
+    # A dependency that fails to download makes the whole prefetch fail.
+    t = os.path.join(tests_path, "error_004_missing_module.ts")
     deno_dir = mkdtemp()
     try:
-        t = os.path.join(tests_path, "006_url_imports.ts")
-        output = run_output([deno_exe, "--prefetch", t],
-                            merge_env={"DENO_DIR": deno_dir})
-        assert output == ""
-        # Check that we actually did the prefetch.
-        os.path.exists(
-            os.path.join(deno_dir,
-                         "deps/http/localhost_PORT4545/tests/subdir/mod2.ts"))
+        with open(os.devnull, "w") as devnull:
+            code = subprocess.call([deno_exe, "prefetch", t],
+                                   stdout=devnull,
+                                   stderr=devnull,
+                                   env=dict(os.environ, DENO_DIR=deno_dir))
+        assert code != 0
     finally:
         shutil.rmtree(deno_dir)
 
@@ -84,6 +84,19 @@ class Repl(object):
         assertEqual(err, '')
         assertEqual(code, 0)
 
+    def test_multiline_unbalanced_braces(self):
+        out, err, code = self.input("function f() {", "return [1,", "2];",
+                                    "}", "f()")
+        assertEqual(out, 'undefined\n[ 1, 2 ]\n')
+        assertEqual(err, '')
+        assertEqual(code, 0)
+
+    def test_last_result(self):
+        out, err, code = self.input("1 + 2", "_ * 2", "_")
+        assertEqual(out, '3\n6\n6\n')
+        assertEqual(err, '')
+        assertEqual(code, 0)
+
     # This should print error instead of wait for input
     def test_eval_unterminated(self):
         out, err, code = self.input("eval('{')")
@@ -0,0 +1,25 @@
+#!/usr/bin/env python
+# Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import os
+import sys
+from util import run_output, tests_path, green_ok
+
+
+def seed_test(deno_exe):
+    sys.stdout.write("seed_test...")
+    sys.stdout.flush()
+
+    t = os.path.join(tests_path, "seed_random.js")
+    def random_numbers(args):
+        return run_output([deno_exe] + args + [t], quiet=True)
+
+    first = random_numbers(["--seed=100"])
+    assert first == random_numbers(["--seed=100"])
+    assert first != random_numbers(["--seed=101"])
+    assert first != random_numbers([])
+
+    print green_ok()
+
+
+if __name__ == "__main__":
+    seed_test(sys.argv[1])
@@ -14,6 +14,11 @@ from util_test import util_test
 from benchmark_test import benchmark_test
 from repl_test import repl_tests
 from prefetch_test import prefetch_test
+from lock_test import lock_test
+from seed_test import seed_test
+from watch_test import watch_test
+from inspector_test import inspector_test
+from bundle_test import bundle_test
 from fmt_test import fmt_test
 import subprocess
 import http_server
@@ -95,18 +100,26 @@ def main(argv):
     unit_tests(deno_exe)
 
     prefetch_test(deno_exe)
+    lock_test(deno_exe)
+    seed_test(deno_exe)
+    watch_test(deno_exe)
+    inspector_test(deno_exe)
+    bundle_test(deno_exe)
     fmt_test(deno_exe)
 
     integration_tests(deno_exe)
 
     # TODO We currently skip testing the prompt and IsTTY in Windows completely.
     # Windows does not support the pty module used for testing the permission
-    # prompt.
+    # prompt. The memory use of streamed fetches is measured with os.wait4(),
+    # which is missing there as well.
     if os.name != 'nt':
         from is_tty_test import is_tty_test
         from permission_prompt_test import permission_prompt_test
+        from fetch_stream_test import fetch_stream_test
         permission_prompt_test(deno_exe)
         is_tty_test(deno_exe)
+        fetch_stream_test(deno_exe)
 
     repl_tests(deno_exe)
 
@@ -16,6 +16,12 @@ MB = 1024 * 1024
 ADDR = "127.0.0.1:4544"
 
 
+def child_cpu_time():
+    # The user and system time of the child processes which were waited for.
+    times = os.times()
+    return times[2] + times[3]
+
+
 def cat(deno_exe, megs):
     size = megs * MB
     start = time.time()
@@ -43,6 +49,7 @@ def tcp(deno_exe, megs):
         return end - start
     finally:
         echo_server.kill()
+        echo_server.wait()
 
 
 def main():
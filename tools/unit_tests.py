@@ -50,11 +50,18 @@ def unit_tests(deno_exe):
     run_unit_test(deno_exe, "permR0W1N0E0U0H0", ["--allow-write"])
     run_unit_test(deno_exe, "permR1W1N0E0U0H0",
                   ["--allow-read", "--allow-write"])
+    run_unit_test(deno_exe, "permR0W0N1E0U0H0", ["--allow-net"])
+    run_unit_test(deno_exe, "permR1W0N1E0U0H0",
+                  ["--allow-read", "--allow-net"])
     run_unit_test(deno_exe, "permR0W0N0E1U0H0", ["--allow-env"])
     run_unit_test(deno_exe, "permR0W0N0E0U0H1", ["--allow-high-precision"])
     run_unit_test(deno_exe, "permR0W0N0E0U1H0", ["--allow-run"])
     run_unit_test(deno_exe, "permR0W1N0E0U1H0",
                   ["--allow-run", "--allow-write"])
+    run_unit_test(deno_exe, "permR1W0N0E0U1H0",
+                  ["--allow-read", "--allow-run"])
+    run_unit_test(deno_exe, "permR0W0N0E1U1H0",
+                  ["--allow-env", "--allow-run"])
     # TODO We might accidentally miss some. We should be smarter about which we
     # run. Maybe we can use the "filtered out" number to check this.
 
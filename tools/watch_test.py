@@ -0,0 +1,88 @@
+#!/usr/bin/env python
+# Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
+import os
+import shutil
+import subprocess
+import sys
+import threading
+import time
+from util import mkdtemp, green_ok
+
+TIMEOUT = 30
+
+
+def write(filename, source):
+    # Some file systems only keep the mtime in seconds.
+    time.sleep(1)
+    with open(filename, "w") as f:
+        f.write(source)
+
+
+def watch_test(deno_exe):
+    sys.stdout.write("watch_test...")
+    sys.stdout.flush()
+
+    d = mkdtemp()
+    try:
+        main = os.path.join(d, "main.ts")
+        dep = os.path.join(d, "dep.ts")
+        new_dep = os.path.join(d, "new_dep.ts")
+        write(dep, "export const msg = 'v1';\n")
+        write(main, "import { msg } from './dep.ts';\nconsole.log(msg);\n")
+
+        p = subprocess.Popen([deno_exe, "run", "--watch", main],
+                             stdout=subprocess.PIPE,
+                             stderr=subprocess.PIPE)
+        # Don't hang the test suite if no restart happens.
+        timer = threading.Timer(TIMEOUT, p.kill)
+        timer.start()
+        try:
+            assert p.stdout.readline().strip() == "v1"
+
+            write(dep, "export const msg = 'v2';\n")
+            assert p.stdout.readline().strip() == "v2"
+
+            # Imports added by a change are watched after the restart.
+            write(new_dep, "export const msg = 'v3';\n")
+            write(main,
+                  "import { msg } from './new_dep.ts';\nconsole.log(msg);\n")
+            assert p.stdout.readline().strip() == "v3"
+            write(new_dep, "export const msg = 'v4';\n")
+            assert p.stdout.readline().strip() == "v4"
+        finally:
+            timer.cancel()
+            p.kill()
+            _, err = p.communicate()
+        # The paths may be canonicalized.
+        restarts = [
+            line for line in err.splitlines()
+            if line.startswith("[deno] restarting on change: ")
+        ]
+        assert len(restarts) == 3
+        assert restarts[0].endswith("dep.ts")
+        assert restarts[1].endswith("main.ts")
+        assert restarts[2].endswith("new_dep.ts")
+
+        # --watch given by DENO_FLAGS is not passed on to the child.
+        p = subprocess.Popen([deno_exe, main],
+                             stdout=subprocess.PIPE,
+                             stderr=subprocess.PIPE,
+                             env=dict(os.environ, DENO_FLAGS="--watch"))
+        timer = threading.Timer(TIMEOUT, p.kill)
+        timer.start()
+        try:
+            assert p.stdout.readline().strip() == "v4"
+            write(new_dep, "export const msg = 'v5';\n")
+            assert p.stdout.readline().strip() == "v5"
+        finally:
+            timer.cancel()
+            p.kill()
+            p.communicate()
+    finally:
+        shutil.rmtree(d)
+
+    print green_ok()
+
+
+if __name__ == "__main__":
+    watch_test(sys.argv[1])
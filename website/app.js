@@ -42,6 +42,14 @@ export function createThroughputColumns(data) {
   return createColumns(data, "throughput");
 }
 
+export function createThroughputCpuColumns(data) {
+  return createColumns(data, "throughput_cpu");
+}
+
+export function createStatTreeColumns(data) {
+  return createColumns(data, "stat_tree");
+}
+
 export function createReqPerSecColumns(data) {
   return createColumns(data, "req_per_sec");
 }
@@ -50,6 +58,10 @@ export function createMaxLatencyColumns(data) {
   return createColumns(data, "max_latency");
 }
 
+export function createSnapshotStartupDeltaColumns(data) {
+  return createColumns(data, "snapshot_startup_delta");
+}
+
 export function createBinarySizeColumns(data) {
   const propName = "binary_size";
   const binarySizeNames = Object.keys(data[data.length - 1][propName]);
@@ -201,8 +213,11 @@ export async function drawChartsFromBenchmarkData(dataUrl) {
 
   const execTimeColumns = createExecTimeColumns(data);
   const throughputColumns = createThroughputColumns(data);
+  const throughputCpuColumns = createThroughputCpuColumns(data);
+  const statTreeColumns = createStatTreeColumns(data);
   const reqPerSecColumns = createReqPerSecColumns(data);
   const maxLatencyColumns = createMaxLatencyColumns(data);
+  const snapshotStartupDeltaColumns = createSnapshotStartupDeltaColumns(data);
   const binarySizeColumns = createBinarySizeColumns(data);
   const threadCountColumns = createThreadCountColumns(data);
   const syscallCountColumns = createSyscallCountColumns(data);
@@ -229,8 +244,11 @@ export async function drawChartsFromBenchmarkData(dataUrl) {
 
   gen("#exec-time-chart", execTimeColumns, "seconds", logScale);
   gen("#throughput-chart", throughputColumns, "seconds", logScale);
+  gen("#throughput-cpu-chart", throughputCpuColumns, "seconds", logScale);
+  gen("#stat-tree-chart", statTreeColumns, "milliseconds");
   gen("#req-per-sec-chart", reqPerSecColumns, "1000 req/sec", formatReqSec);
   gen("#max-latency-chart", maxLatencyColumns, "milliseconds", logScale);
+  gen("#snapshot-startup-chart", snapshotStartupDeltaColumns, "seconds");
   gen("#binary-size-chart", binarySizeColumns, "megabytes", formatMB);
   gen("#thread-count-chart", threadCountColumns, "threads");
   gen("#syscall-count-chart", syscallCountColumns, "syscalls");